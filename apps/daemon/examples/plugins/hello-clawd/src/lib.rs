@@ -33,7 +33,9 @@ static mut PLUGIN_VTABLE: ClawaPlugin = ClawaPlugin {
     on_session_end: None,
     on_tool_call: None,
     on_message: None,
-    _reserved: [None; 8],
+    on_tool_invoke: None,
+    on_rpc: None,
+    _reserved: [None; 6],
 };
 
 // ─── Required export ──────────────────────────────────────────────────────────
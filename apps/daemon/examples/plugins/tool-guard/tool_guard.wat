@@ -0,0 +1,88 @@
+;; SPDX-License-Identifier: MIT
+;; tool-guard — example ClawDE WASM plugin in WebAssembly text format.
+;;
+;; Denies `apply_patch`, pins `run_tests` to the full workspace test run,
+;; and announces every new session to connected clients.
+;;
+;; No Rust toolchain needed; compile with any WAT assembler, e.g.
+;;   wasm-tools parse tool_guard.wat -o tool_guard.wasm
+;;
+;; See `src/plugins/wasm_runtime.rs` for the host ABI.
+(module
+  (import "env" "clawd_log" (func $log (param i32 i32 i32)))
+  (import "env" "clawd_send_event" (func $send_event (param i32 i32 i32 i32) (result i32)))
+  (import "env" "clawd_set_tool_input" (func $set_tool_input (param i32 i32) (result i32)))
+  (import "env" "clawd_set_reason" (func $set_reason (param i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+
+  ;; ─── Static strings ─────────────────────────────────────────────────────
+  (data (i32.const 0) "apply_patch")                                ;; 11
+  (data (i32.const 16) "run_tests")                                 ;; 9
+  (data (i32.const 32) "tool-guard: patches need a human review")   ;; 39
+  (data (i32.const 96) "{\"command\":\"cargo test --workspace\"}")  ;; 36
+  (data (i32.const 160) "tool-guard loaded")                        ;; 17
+  (data (i32.const 192) "plugin.tool-guard.sessionSeen")            ;; 29
+  (data (i32.const 224) "{\"plugin\":\"tool-guard\"}")              ;; 23
+
+  ;; ─── Bump allocator for host-supplied arguments ─────────────────────────
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "clawd_alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $len)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+          (then unreachable))))
+    (local.get $ptr))
+
+  ;; The host frees newest first, so unwinding the bump pointer is enough.
+  (func (export "clawd_free") (param $ptr i32) (param $len i32)
+    (if (i32.eq (i32.add (local.get $ptr) (local.get $len)) (global.get $heap))
+      (then (global.set $heap (local.get $ptr)))))
+
+  ;; Byte-wise string equality.
+  (func $eq (param $a i32) (param $a_len i32) (param $b i32) (param $b_len i32) (result i32)
+    (local $i i32)
+    (if (i32.ne (local.get $a_len) (local.get $b_len))
+      (then (return (i32.const 0))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $a_len)))
+        (if (i32.ne
+              (i32.load8_u (i32.add (local.get $a) (local.get $i)))
+              (i32.load8_u (i32.add (local.get $b) (local.get $i))))
+          (then (return (i32.const 0))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.const 1))
+
+  ;; ─── Hooks ──────────────────────────────────────────────────────────────
+  (func (export "clawd_plugin_init") (result i32)
+    (call $log (i32.const 2) (i32.const 160) (i32.const 17))
+    (i32.const 0))
+
+  (func (export "on_session_start") (param $sid i32) (param $sid_len i32) (result i32)
+    (drop (call $send_event
+      (i32.const 192) (i32.const 29)
+      (i32.const 224) (i32.const 23)))
+    (i32.const 0))
+
+  (func (export "on_tool_call")
+    (param $sid i32) (param $sid_len i32)
+    (param $tool i32) (param $tool_len i32)
+    (param $input i32) (param $input_len i32)
+    (result i32)
+    ;; Deny: ClawaError::CapabilityDenied
+    (if (call $eq (local.get $tool) (local.get $tool_len) (i32.const 0) (i32.const 11))
+      (then
+        (drop (call $set_reason (i32.const 32) (i32.const 39)))
+        (return (i32.const 3))))
+    ;; Modify: replace the input, return ClawaError::None
+    (if (call $eq (local.get $tool) (local.get $tool_len) (i32.const 16) (i32.const 9))
+      (then
+        (drop (call $set_tool_input (i32.const 96) (i32.const 36)))))
+    (i32.const 0))
+)
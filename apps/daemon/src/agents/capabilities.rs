@@ -17,6 +17,27 @@ pub enum Provider {
     Unknown(String),
 }
 
+impl Provider {
+    /// Parse a provider name as used in config and RPC params.  Providers
+    /// without a dedicated variant (gemini, cursor, HTTP profiles) map to
+    /// `Unknown(name)`.
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "claude" => Provider::Claude,
+            "codex" => Provider::Codex,
+            other => Provider::Unknown(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Provider::Claude => "claude",
+            Provider::Codex => "codex",
+            Provider::Unknown(name) => name,
+        }
+    }
+}
+
 // ─── ProviderSpeed ────────────────────────────────────────────────────────────
 
 /// Speed/quality tier for a Codex request.
@@ -7,6 +7,7 @@ use anyhow::{Context, Result};
 use std::collections::HashMap;
 
 use crate::mcp::client::{McpClient, McpServerConfig, McpTrustLevel};
+use crate::mcp::transport::McpTransport;
 
 /// Timeout for the MCP server initialization handshake.
 const MCP_INIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
@@ -27,13 +28,16 @@ impl CodexMcpServer {
     pub async fn spawn() -> Result<Self> {
         let config = McpServerConfig {
             name: "codex".to_string(),
+            transport: McpTransport::Stdio,
             command: "codex".to_string(),
             args: vec!["mcp-server".to_string()],
             env: HashMap::new(),
+            headers: HashMap::new(),
             // Codex is a first-party provider; trust its responses.
             trust: McpTrustLevel::Trusted,
+            sampling: false,
         };
-        let client = tokio::time::timeout(MCP_INIT_TIMEOUT, McpClient::spawn(config))
+        let client = tokio::time::timeout(MCP_INIT_TIMEOUT, McpClient::connect(config))
             .await
             .context("MCP server initialization timed out after 10 seconds")?
             .context("MCP server spawn failed")?;
@@ -5,7 +5,18 @@ use std::sync::Arc;
 use crate::agents::capabilities::Provider;
 use crate::agents::lifecycle::{AgentRecord, AgentRegistry, AgentStatus, SharedAgentRegistry};
 use crate::agents::roles::AgentRole;
-use crate::agents::routing::route_agent;
+use crate::agents::routing::route_agent_with_chain;
+use crate::config::FallbackChainsConfig;
+use crate::scheduler::fallback::{FallbackConfig, FallbackHop, SharedFallbackEngine};
+
+/// Result of [`Orchestrator::spawn_in_project`].
+#[derive(Debug, Clone)]
+pub struct SpawnedAgent {
+    pub agent_id: String,
+    pub provider: Provider,
+    /// Fallback chain entries skipped while routing this agent.
+    pub hops: Vec<FallbackHop>,
+}
 
 /// Orchestrates the lifecycle of all agents in the system.
 ///
@@ -13,15 +24,33 @@ use crate::agents::routing::route_agent;
 /// planner → implementer → reviewer → QA handoff chain.
 pub struct Orchestrator {
     pub registry: SharedAgentRegistry,
+    /// Provider chains from `[fallback]`; empty means the built-in routing.
+    chains: FallbackChainsConfig,
+    /// Account availability; providers it reports as exhausted are skipped.
+    fallback: Option<SharedFallbackEngine>,
 }
 
 impl Orchestrator {
     pub fn new() -> Self {
         Self {
             registry: Arc::new(tokio::sync::RwLock::new(AgentRegistry::new())),
+            chains: FallbackChainsConfig::default(),
+            fallback: None,
         }
     }
 
+    /// Route agents along the configured per-role / per-project chains.
+    pub fn with_fallback_chains(mut self, chains: FallbackChainsConfig) -> Self {
+        self.chains = chains;
+        self
+    }
+
+    /// Skip chain entries whose accounts are all rate-limited or blocked.
+    pub fn with_fallback_engine(mut self, engine: SharedFallbackEngine) -> Self {
+        self.fallback = Some(engine);
+        self
+    }
+
     /// Spawn an agent for the given role + task. Enforces `max_concurrent` caps.
     ///
     /// Returns the new agent ID on success.
@@ -33,9 +62,42 @@ impl Orchestrator {
         worktree_path: Option<String>,
         previous_provider: Option<Provider>,
     ) -> Result<String, OrchestratorError> {
+        self.spawn_in_project(
+            role,
+            task_id,
+            complexity,
+            worktree_path,
+            previous_provider,
+            None,
+        )
+        .await
+        .map(|spawned| spawned.agent_id)
+    }
+
+    /// Like [`spawn`](Self::spawn), but resolves the fallback chain for
+    /// `repo_path` and reports the routing hops taken.
+    pub async fn spawn_in_project(
+        &self,
+        role: AgentRole,
+        task_id: &str,
+        complexity: &str,
+        worktree_path: Option<String>,
+        previous_provider: Option<Provider>,
+        repo_path: Option<&str>,
+    ) -> Result<SpawnedAgent, OrchestratorError> {
         // Compute routing before acquiring the write lock (no shared state needed).
-        let providers = vec![Provider::Claude, Provider::Codex];
-        let decision = route_agent(&role, complexity, previous_provider.as_ref(), &providers);
+        let mut chain = FallbackConfig::resolve(&self.chains, Some(role.as_str()), repo_path);
+        let mut hops = Vec::new();
+        if let Some(engine) = &self.fallback {
+            let (available, skipped) = engine.available_chain(&chain).await;
+            hops = skipped;
+            chain = available.ok_or_else(|| OrchestratorError::NoAvailableProvider {
+                chain: chain.source.clone(),
+            })?;
+        }
+        let decision =
+            route_agent_with_chain(&role, complexity, previous_provider.as_ref(), &chain);
+        hops.extend(decision.hops);
 
         let agent_id = format!("A-{}", &uuid::Uuid::new_v4().to_string()[..8]);
         let now = chrono::Utc::now();
@@ -44,7 +106,7 @@ impl Orchestrator {
             agent_id: agent_id.clone(),
             role: role.clone(),
             task_id: task_id.to_string(),
-            provider: decision.provider,
+            provider: decision.provider.clone(),
             model: decision.model,
             worktree_path,
             status: AgentStatus::Pending,
@@ -68,7 +130,11 @@ impl Orchestrator {
             });
         }
         registry.register(record);
-        Ok(agent_id)
+        Ok(SpawnedAgent {
+            agent_id,
+            provider: decision.provider,
+            hops,
+        })
     }
 
     /// Cancel an agent — sets its status to Failed.
@@ -189,6 +255,8 @@ pub enum OrchestratorError {
     ConcurrencyCapReached { role: String, limit: usize },
     #[error("agent not found: {0}")]
     AgentNotFound(String),
+    #[error("no provider in fallback chain {chain} has an available account")]
+    NoAvailableProvider { chain: String },
 }
 
 /// Thread-safe shared orchestrator.
@@ -2,6 +2,7 @@
 
 use crate::agents::capabilities::{select_provider, Provider, ProviderSpeed, SelectionContext};
 use crate::agents::roles::AgentRole;
+use crate::scheduler::fallback::{FallbackConfig, FallbackHop};
 
 // ─── Model name constants ─────────────────────────────────────────────────────
 
@@ -17,6 +18,8 @@ pub struct RoutingDecision {
     pub model: String,
     pub speed: ProviderSpeed,
     pub reason: String,
+    /// Chain entries passed over before `provider` was chosen.
+    pub hops: Vec<FallbackHop>,
 }
 
 // ─── Speed tier from role ─────────────────────────────────────────────────────
@@ -62,6 +65,63 @@ pub fn route_agent(
         model,
         speed,
         provider,
+        hops: Vec::new(),
+    }
+}
+
+/// Route along a configured fallback chain.
+///
+/// The chain order is authoritative: the first entry wins, except that a
+/// reviewer skips the previous agent's provider when the chain offers another
+/// one (cross-model verification).  Every skipped entry is recorded as a hop.
+/// The built-in chain keeps the capability-based [`route_agent`] behaviour.
+pub fn route_agent_with_chain(
+    role: &AgentRole,
+    complexity: &str,
+    previous_provider: Option<&Provider>,
+    chain: &FallbackConfig,
+) -> RoutingDecision {
+    let providers: Vec<Provider> = chain.providers().map(Provider::from_name).collect();
+    if chain.is_builtin() {
+        return route_agent(role, complexity, previous_provider, &providers);
+    }
+
+    let mut hops = Vec::new();
+    let mut chosen = providers[0].clone();
+    for (i, candidate) in providers.iter().enumerate() {
+        let cross_model_skip = *role == AgentRole::Reviewer
+            && previous_provider == Some(candidate)
+            && providers.iter().any(|p| Some(p) != previous_provider);
+        if !cross_model_skip {
+            chosen = candidate.clone();
+            break;
+        }
+        hops.push(FallbackHop {
+            from: candidate.as_str().to_string(),
+            to: providers.get(i + 1).map(|p| p.as_str().to_string()),
+            reason: format!(
+                "cross-model review: previous agent ran on {}",
+                candidate.as_str()
+            ),
+            chain: chain.source.clone(),
+        });
+    }
+
+    let speed = speed_for_role(role);
+    let model = default_model_for(&chosen, role, &speed);
+    RoutingDecision {
+        role: role.clone(),
+        reason: format!(
+            "role={}, complexity={}, speed={:?}, chain={}",
+            role.as_str(),
+            complexity,
+            speed,
+            chain.source
+        ),
+        model,
+        speed,
+        provider: chosen,
+        hops,
     }
 }
 
@@ -70,13 +130,14 @@ pub fn route_agent(
 /// Return the default model string for a provider + role + speed combination.
 ///
 /// Claude always uses the same model family regardless of speed; the Router
-/// role gets the cheaper Haiku variant.  Codex selects by speed tier.
+/// role gets the cheaper Haiku variant.  Codex selects by speed tier.  Other
+/// providers (gemini, cursor, …) run their CLI's configured default model.
 pub fn default_model_for(provider: &Provider, role: &AgentRole, speed: &ProviderSpeed) -> String {
     match (provider, role, speed) {
         (Provider::Claude, AgentRole::Router, _) => "claude-haiku-4-5-20251001".to_string(),
         (Provider::Claude, _, _) => "claude-sonnet-4-6".to_string(),
         (Provider::Codex, _, ProviderSpeed::Fast) => CODEX_SPARK.to_string(),
         (Provider::Codex, _, ProviderSpeed::Full) => GPT_53_CODEX.to_string(),
-        _ => "claude-sonnet-4-6".to_string(),
+        (Provider::Unknown(_), _, _) => "default".to_string(),
     }
 }
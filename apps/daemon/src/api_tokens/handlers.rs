@@ -0,0 +1,111 @@
+//! RPC handlers for scoped API tokens.
+//!
+//! | Method         | Params                                                   |
+//! |----------------|----------------------------------------------------------|
+//! | `token.create` | `name`, `methods`, `repos?`, `expires_in_secs?`          |
+//! | `token.list`   | —                                                        |
+//! | `token.revoke` | `id`                                                     |
+//!
+//! All three need the daemon's master token; scoped tokens cannot call them.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::storage::{ApiTokenStorage, NewApiToken};
+use super::{is_valid_glob, ApiToken};
+use crate::AppContext;
+
+fn token_storage(ctx: &AppContext) -> ApiTokenStorage {
+    ApiTokenStorage::new(ctx.storage.clone_pool())
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn token_json(t: &ApiToken, now: i64) -> Value {
+    json!({
+        "id": t.id,
+        "name": t.name,
+        "prefix": t.prefix,
+        "methods": t.methods,
+        "repos": t.repos,
+        "created_at": t.created_at,
+        "expires_at": t.expires_at,
+        "last_used_at": t.last_used_at,
+        "revoked_at": t.revoked_at,
+        "status": t.status(now),
+    })
+}
+
+#[derive(Deserialize)]
+struct CreateParams {
+    name: String,
+    methods: Vec<String>,
+    #[serde(default)]
+    repos: Vec<String>,
+    expires_in_secs: Option<i64>,
+}
+
+/// `token.create` — issue a scoped token.  The response carries the secret;
+/// it is not stored and cannot be shown again.
+pub async fn create(params: Value, ctx: &AppContext) -> Result<Value> {
+    let p: CreateParams = serde_json::from_value(params)?;
+
+    let name = p.name.trim();
+    if name.is_empty() {
+        bail!("INVALID_PARAMS: name must not be empty");
+    }
+    if p.methods.is_empty() {
+        bail!("INVALID_PARAMS: methods must list at least one method or glob");
+    }
+    if let Some(bad) = p.methods.iter().find(|m| !is_valid_glob(m)) {
+        bail!("INVALID_PARAMS: invalid method glob '{bad}'");
+    }
+    if let Some(bad) = p.repos.iter().find(|r| !Path::new(r).is_absolute()) {
+        bail!("INVALID_PARAMS: repo '{bad}' must be an absolute path");
+    }
+    let expires_at = match p.expires_in_secs {
+        Some(secs) if secs <= 0 => bail!("INVALID_PARAMS: expires_in_secs must be positive"),
+        Some(secs) => Some(now() + secs),
+        None => None,
+    };
+
+    let (token, secret) = token_storage(ctx)
+        .create(NewApiToken {
+            name: name.to_string(),
+            methods: p.methods,
+            repos: p.repos,
+            expires_at,
+        })
+        .await?;
+
+    Ok(json!({ "token": token_json(&token, now()), "secret": secret }))
+}
+
+/// `token.list` — every token, newest first, without secrets.
+pub async fn list(_params: Value, ctx: &AppContext) -> Result<Value> {
+    let now = now();
+    let tokens: Vec<Value> = token_storage(ctx)
+        .list()
+        .await?
+        .iter()
+        .map(|t| token_json(t, now))
+        .collect();
+    Ok(json!({ "tokens": tokens }))
+}
+
+/// `token.revoke` — revoke a token.  Takes effect on the holder's next call,
+/// including on an open WebSocket.
+pub async fn revoke(params: Value, ctx: &AppContext) -> Result<Value> {
+    let id = params["id"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("INVALID_PARAMS: missing `id` parameter"))?;
+
+    if !token_storage(ctx).revoke(id).await? {
+        bail!("API_TOKEN_NOT_FOUND: no active token with id {id}");
+    }
+    Ok(json!({ "revoked": true }))
+}
@@ -0,0 +1,552 @@
+// SPDX-License-Identifier: MIT
+//! Scoped API tokens.
+//!
+//! Besides the daemon's master token (`ipc::auth`), clients can be issued
+//! named tokens limited to a set of RPC method globs (`tasks.*`,
+//! `session.getMessages`) and, optionally, to specific repos.  Tokens expire,
+//! record when they were last used, and are stored as SHA-256 hashes — the
+//! secret is returned once by `token.create` and never again.
+//!
+//! The same check runs on the WebSocket (`ipc::dispatch_text`), the REST
+//! routes (`rest::auth`) and the JSON-RPC bridge (`rest::rpc`):
+//!
+//! - the method must match one of the token's globs;
+//! - token management (`token.create/list/revoke`) and admin-scoped methods
+//!   (`account.*`, `device.*`, ...) are never allowed, so a token cannot mint
+//!   a broader one or reach the daemon's credentials;
+//! - a repo-limited token may only call methods whose target repo is
+//!   checked (`REPO_CHECKED_METHODS`), and must name its repo — through
+//!   `repo_path`, or a session, task or approval id that belongs to it.
+//!   Calls that name no repo, or an id that does not resolve, are refused.
+//!   Repos are compared as canonical paths, so `..` and symlinks cannot step
+//!   outside an allowed repo.
+
+pub mod handlers;
+pub mod storage;
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::{Component, Path, PathBuf};
+
+pub use storage::ApiTokenStorage;
+
+use crate::config::ApiScope;
+use crate::rest::auth::{event_scope, method_scope};
+
+/// Every scoped token starts with this, so the daemon only looks up
+/// strings that could be one.
+pub const TOKEN_PREFIX: &str = "clawd_";
+
+/// Methods a scoped token may never call, whatever its globs say.
+pub const MANAGEMENT_METHODS: &[&str] = &["token.create", "token.list", "token.revoke"];
+
+/// Param keys that name a repo directly.
+pub(crate) const REPO_KEYS: &[&str] = &["repo_path", "repoPath"];
+/// Param keys that name a session, whose repo is looked up.
+pub(crate) const SESSION_KEYS: &[&str] = &["session_id", "sessionId"];
+/// Param keys that name a task, whose repo is looked up.
+pub(crate) const TASK_KEYS: &[&str] = &["task_id", "taskId"];
+
+/// Param keys that name another session whose context is read
+/// (`session.create`'s `inheritFrom`).
+const LINKED_SESSION_KEYS: &[&str] = &["inheritFrom"];
+/// Param keys that name a pending approval, whose task's repo is looked up
+/// for `approval.respond`.
+const APPROVAL_KEYS: &[&str] = &["approval_id", "approvalId"];
+
+/// Methods a repo-limited token may call.  Each acts only on the repo named
+/// by `repo_path`, or on the session, task or approval its id names.  Every
+/// other method — keyed by some other id or path (`memory.remove`,
+/// `automation.*`, `lsp.*`, ...) or covering every repo (`session.search`,
+/// `repo.list`) — is refused: an allowed `repo_path` alongside would not bind
+/// what it touches.
+pub(crate) const REPO_CHECKED_METHODS: &[&str] = &[
+    "repo.open",
+    "repo.close",
+    "repo.status",
+    "repo.diff",
+    "repo.fileDiff",
+    "repo.tree",
+    "repo.readFile",
+    "repo.scan",
+    "repo.profile",
+    "repo.generateArtifacts",
+    "repo.syncArtifacts",
+    "repo.driftScore",
+    "repo.driftReport",
+    "session.create",
+    "session.get",
+    "session.delete",
+    "session.sendMessage",
+    "session.getMessages",
+    "session.pause",
+    "session.resume",
+    "session.cancel",
+    "session.setProvider",
+    "session.setMode",
+    "session.health",
+    "session.attentionMap",
+    "session.intentSummary",
+    "session.export",
+    "session.trace",
+    "session.events",
+    "token.sessionUsage",
+    "events.subscribe",
+    "events.stream",
+    "tasks.list",
+    "tasks.get",
+    "tasks.claim",
+    "tasks.release",
+    "tasks.heartbeat",
+    "tasks.updateStatus",
+    "tasks.logActivity",
+    "tasks.note",
+    "tasks.activity",
+    "tasks.summary",
+    "tasks.progressEstimate",
+    "tasks.export",
+    "tasks.validate",
+    "tasks.sync",
+    "tasks.transition",
+    "tasks.listEvents",
+    "worktrees.create",
+    "worktrees.diff",
+    "worktrees.commit",
+    "worktrees.accept",
+    "worktrees.reject",
+    "worktrees.delete",
+    "worktrees.merge",
+    "approval.list",
+    "approval.respond",
+    "memory.list",
+    "memory.add",
+];
+
+/// A scoped token as stored — never includes the secret.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    /// First characters of the secret, for telling tokens apart in lists.
+    pub prefix: String,
+    pub methods: Vec<String>,
+    /// Empty means any repo.
+    pub repos: Vec<String>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiToken {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// `"active"`, `"expired"` or `"revoked"`.
+    pub fn status(&self, now: i64) -> &'static str {
+        if self.revoked_at.is_some() {
+            "revoked"
+        } else if self.is_expired(now) {
+            "expired"
+        } else {
+            "active"
+        }
+    }
+
+    /// Whether the method globs allow `method`.  Scoped tokens stop below
+    /// the admin scope, even with a `*` glob.
+    pub fn allows_method(&self, method: &str) -> bool {
+        !MANAGEMENT_METHODS.contains(&method)
+            && method_scope(method) != ApiScope::Admin
+            && self.methods.iter().any(|g| glob_matches(g, method))
+    }
+
+    /// Whether the method globs allow a broadcast `event`.  Admin events
+    /// (`account.*`, `device.*`, ...) stay hidden, as admin methods do.
+    pub fn allows_event(&self, event: &str) -> bool {
+        event_scope(event) != ApiScope::Admin && self.methods.iter().any(|g| glob_matches(g, event))
+    }
+
+    /// Check one call against the method globs and repo limit.
+    ///
+    /// `Err` carries the message for an `unauthorized` response.
+    pub async fn authorize(
+        &self,
+        method: &str,
+        params: &Value,
+        pool: &SqlitePool,
+    ) -> Result<(), String> {
+        if !self.allows_method(method) {
+            return Err(format!(
+                "Unauthorized — token '{}' may not call {method}",
+                self.name
+            ));
+        }
+        if self.repos.is_empty() {
+            return Ok(());
+        }
+        if !REPO_CHECKED_METHODS.contains(&method) {
+            return Err(format!(
+                "Unauthorized — token '{}' is limited to specific repos, which {method} cannot be checked against",
+                self.name
+            ));
+        }
+        let named = repos_named_by(method, params, pool)
+            .await
+            .map_err(|id| format!("Unauthorized — token '{}' cannot resolve {id}", self.name))?;
+        if named.is_empty() {
+            return Err(format!(
+                "Unauthorized — token '{}' is limited to specific repos; pass repo_path",
+                self.name
+            ));
+        }
+        match named.iter().find(|r| !self.allows_repo(r)) {
+            Some(repo) => Err(format!(
+                "Unauthorized — token '{}' may not access {repo}",
+                self.name
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `repo` is one of the token's repos or inside one.
+    pub fn allows_repo(&self, repo: &str) -> bool {
+        self.repos.iter().any(|allowed| repo_within(repo, allowed))
+    }
+}
+
+/// SHA-256 of a secret, hex-encoded — the form stored in SQLite.
+pub fn hash_token(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Match an RPC method against a glob where `*` stands for any run of
+/// characters, dots included (`tasks.*`, `*.list`, `*`).
+pub fn glob_matches(pattern: &str, method: &str) -> bool {
+    let Some((head, rest)) = pattern.split_once('*') else {
+        return pattern == method;
+    };
+    let Some(mut remaining) = method.strip_prefix(head) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let tail = parts.pop().unwrap_or("");
+    for part in parts {
+        match remaining.find(part) {
+            Some(i) => remaining = &remaining[i + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(tail)
+}
+
+/// Whether `pattern` is a well-formed method glob.
+pub fn is_valid_glob(pattern: &str) -> bool {
+    !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '*'))
+}
+
+/// Trim trailing slashes so `/src/app/` and `/src/app` compare equal.
+pub fn normalize_repo(repo: &str) -> &str {
+    let trimmed = repo.trim_end_matches('/');
+    if trimmed.is_empty() {
+        "/"
+    } else {
+        trimmed
+    }
+}
+
+/// Whether `repo` is `root` or a path inside it, once both are resolved
+/// with `canonical_path`.
+pub fn repo_within(repo: &str, root: &str) -> bool {
+    canonical_path(repo).starts_with(canonical_path(root))
+}
+
+/// `path` with symlinks and `.`/`..` resolved.  The longest existing prefix
+/// is canonicalized by the filesystem; the rest, which cannot contain a
+/// symlink, is resolved lexically.
+pub fn canonical_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let mut existing = path;
+    let mut rest: Vec<&std::ffi::OsStr> = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let tail = rest.iter().rev().map(|&name| Component::Normal(name));
+            return resolve_lexically(canonical, tail);
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            // Nothing along the path exists.
+            _ => return resolve_lexically(PathBuf::new(), path.components()),
+        }
+    }
+}
+
+fn resolve_lexically<'a>(
+    mut out: PathBuf,
+    components: impl Iterator<Item = Component<'a>>,
+) -> PathBuf {
+    for component in components {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Look up the scoped token for a presented secret.
+///
+/// Returns `None` for anything that is not an active token — unknown,
+/// revoked or expired.  Records the use on success.
+pub async fn authenticate(pool: &SqlitePool, secret: &str) -> Option<ApiToken> {
+    if !secret.starts_with(TOKEN_PREFIX) {
+        return None;
+    }
+    let storage = ApiTokenStorage::new(pool.clone());
+    match storage.find_active(&hash_token(secret)).await {
+        Ok(Some(token)) => {
+            if let Err(e) = storage.touch(&token.id).await {
+                tracing::debug!(err = %e, "could not record API token use");
+            }
+            Some(token)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!(err = %e, "API token lookup failed");
+            None
+        }
+    }
+}
+
+/// Every repo a call names, directly or through a session, task or (for
+/// `approval.respond`) approval id.  `Err` names an id that does not
+/// resolve: the handler could still act on it, e.g. a worktree whose task
+/// row is gone, so it cannot be vouched for by a `repo_path` alongside.
+async fn repos_named_by(
+    method: &str,
+    params: &Value,
+    pool: &SqlitePool,
+) -> Result<Vec<String>, String> {
+    let str_param = |keys: &[&str]| -> Vec<(String, String)> {
+        keys.iter()
+            .filter_map(|k| Some((k.to_string(), params.get(*k)?.as_str()?.to_string())))
+            .collect()
+    };
+    let resolved =
+        |key: String, id: String, repo: Option<String>| repo.ok_or_else(|| format!("{key} '{id}'"));
+
+    let mut repos: Vec<String> = str_param(REPO_KEYS).into_iter().map(|(_, r)| r).collect();
+    for (key, id) in str_param(SESSION_KEYS)
+        .into_iter()
+        .chain(str_param(LINKED_SESSION_KEYS))
+    {
+        let repo = session_repo(pool, &id).await;
+        repos.push(resolved(key, id, repo)?);
+    }
+    for (key, id) in str_param(TASK_KEYS) {
+        let repo = task_repo(pool, &id).await;
+        repos.push(resolved(key, id, repo)?);
+    }
+    if method == "approval.respond" {
+        for (key, id) in str_param(APPROVAL_KEYS) {
+            let repo = approval_repo(pool, &id).await;
+            repos.push(resolved(key, id, repo)?);
+        }
+    }
+    Ok(repos)
+}
+
+/// Repo of a session, if it exists.
+pub(crate) async fn session_repo(pool: &SqlitePool, session_id: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT repo_path FROM sessions WHERE id = ?")
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Repo of a task, if it exists.
+pub(crate) async fn task_repo(pool: &SqlitePool, task_id: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT repo_path FROM agent_tasks WHERE id = ?")
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Repo of the task an approval was requested for, if it exists.
+async fn approval_repo(pool: &SqlitePool, approval_id: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT repo_path FROM task_approvals WHERE approval_id = ?")
+        .bind(approval_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(methods: &[&str], repos: &[&str]) -> ApiToken {
+        ApiToken {
+            id: "t1".into(),
+            name: "ci".into(),
+            prefix: "clawd_ab12".into(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            repos: repos.iter().map(|r| r.to_string()).collect(),
+            created_at: 0,
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn globs_match_methods() {
+        assert!(glob_matches("tasks.*", "tasks.list"));
+        assert!(glob_matches("*", "session.getMessages"));
+        assert!(glob_matches("*.list", "worktrees.list"));
+        assert!(glob_matches("session.get*", "session.getMessages"));
+        assert!(glob_matches("session.getMessages", "session.getMessages"));
+        assert!(!glob_matches("tasks.*", "tasksx.list"));
+        assert!(!glob_matches("session.get", "session.getMessages"));
+        assert!(!glob_matches("*.list", "tasks.listEvents"));
+    }
+
+    #[test]
+    fn management_methods_are_never_allowed() {
+        let t = token(&["*"], &[]);
+        assert!(t.allows_method("tasks.claim"));
+        assert!(!t.allows_method("token.create"));
+        assert!(t.allows_method("token.sessionUsage"));
+        assert!(t.allows_event("task.updated"));
+        assert!(!t.allows_event("account.updated"));
+    }
+
+    #[test]
+    fn repo_limits_cover_subpaths_only() {
+        let t = token(&["*"], &["/src/app"]);
+        assert!(t.allows_repo("/src/app"));
+        assert!(t.allows_repo("/src/app/"));
+        assert!(t.allows_repo("/src/app/packages/web"));
+        assert!(!t.allows_repo("/src/app2"));
+        assert!(!t.allows_repo("/src"));
+        assert!(!t.allows_repo("/src/app/../other"));
+        assert!(t.allows_repo("/src/other/../app/lib"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_an_allowed_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app, other) = (dir.path().join("app"), dir.path().join("other"));
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        std::os::unix::fs::symlink(&other, app.join("escape")).unwrap();
+
+        let t = token(&["*"], &[app.to_str().unwrap()]);
+        assert!(t.allows_repo(app.join("src").to_str().unwrap()));
+        assert!(!t.allows_repo(app.join("escape").to_str().unwrap()));
+        assert!(!t.allows_repo(app.join("escape/new").to_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn only_repo_checked_methods_are_open_to_repo_limited_tokens() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let params = serde_json::json!({
+            "repo_path": "/src/app",
+            "workspaceRoot": "/etc",
+            "id": "m1",
+        });
+        let limited = token(&["*"], &["/src/app"]);
+        for method in [
+            "lsp.start",
+            "mcp.getPrompt",
+            "session.search",
+            "repo.list",
+            "memory.remove",
+            "memory.update",
+            "automation.trigger",
+        ] {
+            assert!(
+                limited.authorize(method, &params, &pool).await.is_err(),
+                "{method}"
+            );
+        }
+        assert!(limited
+            .authorize("repo.status", &params, &pool)
+            .await
+            .is_ok());
+        let open = token(&["*"], &[]);
+        assert!(open.authorize("lsp.start", &params, &pool).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn ids_resolve_to_their_repo_or_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = crate::storage::Storage::new(dir.path()).await.unwrap();
+        let pool = storage.clone_pool();
+        let tasks = crate::tasks::storage::TaskStorage::new(pool.clone());
+        for (task, repo, approval) in [
+            ("t1", "/src/app", "ap-app"),
+            ("t2", "/src/other", "ap-other"),
+        ] {
+            tasks
+                .add_task(
+                    task, task, None, None, None, None, None, None, None, None, None, None, repo,
+                )
+                .await
+                .unwrap();
+            tasks
+                .request_approval(approval, task, "agent", "apply_patch", "high", "{}")
+                .await
+                .unwrap();
+        }
+        let limited = token(&["*"], &["/src/app"]);
+        let respond = |id: &str| serde_json::json!({ "approval_id": id, "repo_path": "/src/app" });
+
+        assert!(limited
+            .authorize("approval.respond", &respond("ap-app"), &pool)
+            .await
+            .is_ok());
+        assert!(limited
+            .authorize("approval.respond", &respond("ap-other"), &pool)
+            .await
+            .is_err());
+        assert!(limited
+            .authorize("approval.respond", &respond("ap-gone"), &pool)
+            .await
+            .is_err());
+        // A task id that does not resolve is not vouched for by repo_path.
+        let params = serde_json::json!({ "task_id": "t-gone", "repo_path": "/src/app" });
+        assert!(limited
+            .authorize("worktrees.merge", &params, &pool)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn expiry_and_revocation_show_in_status() {
+        let mut t = token(&["*"], &[]);
+        assert_eq!(t.status(100), "active");
+        t.expires_at = Some(100);
+        assert_eq!(t.status(100), "expired");
+        t.revoked_at = Some(50);
+        assert_eq!(t.status(10), "revoked");
+    }
+}
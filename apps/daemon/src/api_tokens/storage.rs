@@ -0,0 +1,174 @@
+//! SQLite persistence for scoped API tokens.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use super::{hash_token, normalize_repo, ApiToken, TOKEN_PREFIX};
+
+/// Characters of the secret kept in `prefix` for display.
+const DISPLAY_PREFIX_LEN: usize = TOKEN_PREFIX.len() + 6;
+
+/// Minimum seconds between `last_used_at` writes for one token.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+fn unixepoch() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Parameters for a new token.
+#[derive(Debug, Clone)]
+pub struct NewApiToken {
+    pub name: String,
+    pub methods: Vec<String>,
+    pub repos: Vec<String>,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ApiTokenRow {
+    id: String,
+    name: String,
+    prefix: String,
+    methods: String,
+    repos: Option<String>,
+    created_at: i64,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+    revoked_at: Option<i64>,
+}
+
+impl From<ApiTokenRow> for ApiToken {
+    fn from(r: ApiTokenRow) -> Self {
+        Self {
+            id: r.id,
+            name: r.name,
+            prefix: r.prefix,
+            methods: serde_json::from_str(&r.methods).unwrap_or_default(),
+            repos: r
+                .repos
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+            last_used_at: r.last_used_at,
+            revoked_at: r.revoked_at,
+        }
+    }
+}
+
+const COLUMNS: &str =
+    "id, name, prefix, methods, repos, created_at, expires_at, last_used_at, revoked_at";
+
+pub struct ApiTokenStorage {
+    pool: SqlitePool,
+}
+
+impl ApiTokenStorage {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Persist a new token and return it with its secret.  The secret is not
+    /// stored and cannot be retrieved again.
+    pub async fn create(&self, req: NewApiToken) -> Result<(ApiToken, String)> {
+        let id = Ulid::new().to_string();
+        let secret = format!("{TOKEN_PREFIX}{}", Uuid::new_v4().simple());
+        let prefix = secret[..DISPLAY_PREFIX_LEN].to_string();
+        let repos: Vec<String> = req
+            .repos
+            .iter()
+            .map(|r| normalize_repo(r).to_string())
+            .collect();
+        let now = unixepoch();
+
+        sqlx::query(
+            "INSERT INTO api_tokens \
+             (id, name, token_hash, prefix, methods, repos, created_at, expires_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&req.name)
+        .bind(hash_token(&secret))
+        .bind(&prefix)
+        .bind(serde_json::to_string(&req.methods)?)
+        .bind(if repos.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&repos)?)
+        })
+        .bind(now)
+        .bind(req.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        let token = ApiToken {
+            id,
+            name: req.name,
+            prefix,
+            methods: req.methods,
+            repos,
+            created_at: now,
+            expires_at: req.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        };
+        Ok((token, secret))
+    }
+
+    /// All tokens, newest first, including revoked and expired ones.
+    pub async fn list(&self) -> Result<Vec<ApiToken>> {
+        let rows = sqlx::query_as::<_, ApiTokenRow>(&format!(
+            "SELECT {COLUMNS} FROM api_tokens ORDER BY created_at DESC, id DESC"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(ApiToken::from).collect())
+    }
+
+    /// The unrevoked, unexpired token with this secret hash.
+    pub async fn find_active(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let row = sqlx::query_as::<_, ApiTokenRow>(&format!(
+            "SELECT {COLUMNS} FROM api_tokens \
+             WHERE token_hash = ? AND revoked_at IS NULL \
+             AND (expires_at IS NULL OR expires_at > ?)"
+        ))
+        .bind(token_hash)
+        .bind(unixepoch())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(ApiToken::from))
+    }
+
+    /// Record a use.  Writes at most once a minute per token so busy
+    /// clients do not turn every call into a write.
+    pub async fn touch(&self, id: &str) -> Result<()> {
+        let now = unixepoch();
+        sqlx::query(
+            "UPDATE api_tokens SET last_used_at = ? \
+             WHERE id = ? AND (last_used_at IS NULL OR last_used_at <= ?)",
+        )
+        .bind(now)
+        .bind(id)
+        .bind(now - TOUCH_INTERVAL_SECS)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Revoke a token.  Returns `false` if no active token has this id.
+    pub async fn revoke(&self, id: &str) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+                .bind(unixepoch())
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
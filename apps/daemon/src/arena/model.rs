@@ -57,6 +57,20 @@ pub struct LeaderboardEntry {
     pub win_rate: f64,
 }
 
+/// Head-to-head record of one provider in one task type and repo language.
+///
+/// Unlike [`LeaderboardEntry`], `trials` counts only the arenas the provider
+/// took part in, so the win rate is not diluted by comparisons it never ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRecord {
+    pub provider: String,
+    pub task_type: String,
+    /// Primary language of the arena's repo, or "unknown" if never scanned.
+    pub language: String,
+    pub wins: u64,
+    pub trials: u64,
+}
+
 // ─── Tests ────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -6,7 +6,7 @@ use chrono::Utc;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use super::model::{ArenaSession, ArenaVote, LeaderboardEntry};
+use super::model::{ArenaSession, ArenaVote, LeaderboardEntry, ProviderRecord};
 
 /// Thin storage wrapper for arena tables.
 ///
@@ -200,6 +200,41 @@ impl ArenaStorage {
         Ok(entries)
     }
 
+    /// Return per-provider wins and participations grouped by task type and
+    /// the primary language of the repo the arena ran in.
+    pub async fn get_provider_records(&self) -> Result<Vec<ProviderRecord>> {
+        let rows: Vec<(String, String, String, i64, i64)> = sqlx::query_as(
+            "SELECT
+                 p.provider,
+                 v.task_type,
+                 COALESCE(rp.primary_lang, 'unknown') AS language,
+                 SUM(CASE WHEN v.winner_provider = p.provider THEN 1 ELSE 0 END) AS wins,
+                 COUNT(*) AS trials
+             FROM (SELECT id, session_a_id, provider_a AS provider FROM arena_sessions
+                   UNION ALL
+                   SELECT id, session_a_id, provider_b AS provider FROM arena_sessions) p
+             JOIN arena_votes v ON v.arena_id = p.id
+             LEFT JOIN sessions s ON s.id = p.session_a_id
+             LEFT JOIN repo_profiles rp ON rp.repo_path = s.repo_path
+             GROUP BY p.provider, v.task_type, language",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(provider, task_type, language, wins, trials)| ProviderRecord {
+                    provider,
+                    task_type,
+                    language,
+                    wins: wins.max(0) as u64,
+                    trials: trials.max(0) as u64,
+                },
+            )
+            .collect())
+    }
+
     /// Return the total number of votes recorded across all arena sessions.
     pub async fn get_vote_count(&self) -> Result<u64> {
         let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM arena_votes")
@@ -12,7 +12,8 @@ use uuid::Uuid;
 
 use crate::AppContext;
 
-use super::engine::{ActionType, Automation, TriggerEvent, TriggerType};
+use super::condition::Condition;
+use super::engine::{ActionType, Automation, CatchUpPolicy, TriggerEvent, TriggerType};
 
 // ─── Built-in definitions ─────────────────────────────────────────────────
 
@@ -24,9 +25,12 @@ pub fn all() -> Vec<Automation> {
             enabled: false, // opt-in — user must enable in UI or config
             trigger: TriggerType::SessionComplete,
             condition: None,
+            schedule: None,
+            catch_up: CatchUpPolicy::default(),
             action: ActionType::RunTests,
             action_config: serde_json::json!({ "command": "cargo test" }),
             builtin: true,
+            repo_path: None,
             last_triggered_at: None,
         },
         Automation {
@@ -35,9 +39,12 @@ pub fn all() -> Vec<Automation> {
             enabled: true,
             trigger: TriggerType::SessionComplete,
             condition: None,
+            schedule: None,
+            catch_up: CatchUpPolicy::default(),
             action: ActionType::CreateTask,
             action_config: serde_json::json!({ "title_prefix": "TODO from session" }),
             builtin: true,
+            repo_path: None,
             last_triggered_at: None,
         },
         Automation {
@@ -45,10 +52,15 @@ pub fn all() -> Vec<Automation> {
             description: "Send a notification when a session runs longer than 5 minutes.".into(),
             enabled: true,
             trigger: TriggerType::SessionComplete,
-            condition: Some("session_duration_secs>300".into()),
+            condition: Some(
+                Condition::parse("session_duration_secs>300").expect("built-in condition parses"),
+            ),
+            schedule: None,
+            catch_up: CatchUpPolicy::default(),
             action: ActionType::SendNotification,
             action_config: serde_json::json!({ "message": "Your long session has completed." }),
             builtin: true,
+            repo_path: None,
             last_triggered_at: None,
         },
     ]
@@ -86,10 +98,12 @@ async fn run_tests(automation: &Automation, ctx: &AppContext) -> Result<()> {
     if parts.is_empty() {
         bail!("empty test command");
     }
-    let output = tokio::process::Command::new(parts[0])
-        .args(&parts[1..])
-        .output()
-        .await?;
+    let mut cmd = tokio::process::Command::new(parts[0]);
+    cmd.args(&parts[1..]);
+    if let Some(repo) = &automation.repo_path {
+        cmd.current_dir(repo);
+    }
+    let output = cmd.output().await?;
 
     let success = output.status.success();
     let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
@@ -203,11 +217,12 @@ async fn run_script(automation: &Automation, _ctx: &AppContext) -> Result<()> {
         .unwrap_or("echo 'no script configured'");
 
     info!("automation run-script: {script}");
-    tokio::process::Command::new("sh")
-        .arg("-c")
-        .arg(script)
-        .output()
-        .await?;
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c").arg(script);
+    if let Some(repo) = &automation.repo_path {
+        cmd.current_dir(repo);
+    }
+    cmd.output().await?;
 
     Ok(())
 }
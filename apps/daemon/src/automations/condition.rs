@@ -0,0 +1,650 @@
+//! Condition expressions for automations.
+//!
+//! A small typed language evaluated against a [`TriggerEvent`]:
+//!
+//! ```text
+//! session_duration_secs > 300 and not file_path glob "target/**"
+//! trigger == "task_done" or (file_ext == "rs" && session_output =~ "(?i)panic")
+//! ```
+//!
+//! Fields: `trigger`, `session_id`, `task_id`, `file_path`, `file_ext`
+//! (extension of `file_path`, no dot), `session_output` (strings) and
+//! `session_duration_secs` (number). A bare field name is true when the event
+//! carries a value for it.
+//!
+//! Operators: `and`/`&&`, `or`/`||`, `not`/`!`, `==` (also `=`), `!=`, `<`,
+//! `<=`, `>`, `>=`, `glob` (`*`, `**`, `?`) and `=~` (regex). String literals
+//! are quoted with `"` or `'`; inside them `\"`, `\'` and `\\` stand for the
+//! character itself and any other backslash is kept, so regex escapes such as
+//! `"\d+"` need no doubling.  Unquoted words are accepted as strings so the
+//! legacy `file_ext=.rs` form keeps working.
+//!
+//! Expressions are type-checked when parsed, so typos fail at config-load time
+//! instead of silently matching. A comparison against a field the event does
+//! not carry is false.
+
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::engine::{TriggerEvent, TriggerType};
+
+/// A parse or type error, with the byte offset in the source where it occurred.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message} (at position {offset})")]
+pub struct ConditionError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl ConditionError {
+    fn new(message: impl Into<String>, offset: usize) -> Self {
+        Self {
+            message: message.into(),
+            offset,
+        }
+    }
+}
+
+/// A parsed, type-checked condition expression.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse and type-check `source`.
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let tokens = lex(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.len(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some(tok) = parser.peek() {
+            return Err(ConditionError::new(
+                format!("unexpected {}", tok.kind),
+                tok.offset,
+            ));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// Evaluate against an event.
+    pub fn evaluate(&self, event: &TriggerEvent) -> bool {
+        self.expr.eval(event)
+    }
+}
+
+/// Conditions are stored and sent as their source text.
+impl Serialize for Condition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// ─── Fields ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Trigger,
+    SessionId,
+    TaskId,
+    FilePath,
+    FileExt,
+    SessionOutput,
+    SessionDurationSecs,
+}
+
+/// All field names, for error messages.
+pub const FIELD_NAMES: &[&str] = &[
+    "trigger",
+    "session_id",
+    "task_id",
+    "file_path",
+    "file_ext",
+    "session_output",
+    "session_duration_secs",
+];
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "trigger" => Field::Trigger,
+            "session_id" => Field::SessionId,
+            "task_id" => Field::TaskId,
+            "file_path" => Field::FilePath,
+            "file_ext" => Field::FileExt,
+            "session_output" => Field::SessionOutput,
+            "session_duration_secs" => Field::SessionDurationSecs,
+            _ => return None,
+        })
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Field::SessionDurationSecs)
+    }
+
+    fn string_value(self, event: &TriggerEvent) -> Option<String> {
+        match self {
+            Field::Trigger => Some(trigger_name(&event.kind).to_string()),
+            Field::SessionId => event.session_id.clone(),
+            Field::TaskId => event.task_id.clone(),
+            Field::FilePath => event.file_path.clone(),
+            Field::FileExt => event.file_path.as_deref().and_then(|p| {
+                std::path::Path::new(p)
+                    .extension()
+                    .map(|e| e.to_string_lossy().into_owned())
+            }),
+            Field::SessionOutput => event.session_output.clone(),
+            Field::SessionDurationSecs => event.session_duration_secs.map(|n| n.to_string()),
+        }
+    }
+
+    fn number_value(self, event: &TriggerEvent) -> Option<u64> {
+        match self {
+            Field::SessionDurationSecs => event.session_duration_secs,
+            _ => None,
+        }
+    }
+}
+
+fn trigger_name(kind: &TriggerType) -> &'static str {
+    match kind {
+        TriggerType::SessionComplete => "session_complete",
+        TriggerType::TaskDone => "task_done",
+        TriggerType::FileSaved => "file_saved",
+        TriggerType::Cron => "cron",
+    }
+}
+
+// ─── AST ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Present(Field),
+    CmpNum(Field, CmpOp, u64),
+    CmpStr(Field, CmpOp, String),
+    Matches(Field, Regex),
+}
+
+impl Expr {
+    fn eval(&self, event: &TriggerEvent) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(event) && b.eval(event),
+            Expr::Or(a, b) => a.eval(event) || b.eval(event),
+            Expr::Not(e) => !e.eval(event),
+            Expr::Present(f) => f.string_value(event).is_some(),
+            Expr::CmpNum(f, op, rhs) => match f.number_value(event) {
+                Some(lhs) => compare(op, &lhs, rhs),
+                None => false,
+            },
+            Expr::CmpStr(f, op, rhs) => match f.string_value(event) {
+                Some(lhs) => compare(op, lhs.as_str(), rhs.as_str()),
+                None => false,
+            },
+            Expr::Matches(f, re) => f
+                .string_value(event)
+                .map(|v| re.is_match(&v))
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn compare<T: PartialOrd + ?Sized>(op: &CmpOp, lhs: &T, rhs: &T) -> bool {
+    match op {
+        CmpOp::Eq => lhs == rhs,
+        CmpOp::Ne => lhs != rhs,
+        CmpOp::Lt => lhs < rhs,
+        CmpOp::Le => lhs <= rhs,
+        CmpOp::Gt => lhs > rhs,
+        CmpOp::Ge => lhs >= rhs,
+    }
+}
+
+/// Translate a glob (`*` within a segment, `**` across segments, `?` one char)
+/// into an anchored regex.
+pub(crate) fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches zero directories.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+// ─── Lexer ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Glob,
+    RegexMatch,
+    Cmp(CmpOp),
+    Str(String),
+    Word(String),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::And => write!(f, "'and'"),
+            TokenKind::Or => write!(f, "'or'"),
+            TokenKind::Not => write!(f, "'not'"),
+            TokenKind::Glob => write!(f, "'glob'"),
+            TokenKind::RegexMatch => write!(f, "'=~'"),
+            TokenKind::Cmp(_) => write!(f, "comparison operator"),
+            TokenKind::Str(s) => write!(f, "string \"{s}\""),
+            TokenKind::Word(w) => write!(f, "'{w}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '*' | '/' | '?')
+}
+
+fn lex(src: &str) -> Result<Vec<Token>, ConditionError> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(i, c)) = chars.peek() {
+        let two = src.get(i..i + 2).unwrap_or("");
+        let (kind, len) = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => (TokenKind::LParen, 1),
+            ')' => (TokenKind::RParen, 1),
+            '&' if two == "&&" => (TokenKind::And, 2),
+            '|' if two == "||" => (TokenKind::Or, 2),
+            '=' if two == "=~" => (TokenKind::RegexMatch, 2),
+            '=' if two == "==" => (TokenKind::Cmp(CmpOp::Eq), 2),
+            '=' => (TokenKind::Cmp(CmpOp::Eq), 1),
+            '!' if two == "!=" => (TokenKind::Cmp(CmpOp::Ne), 2),
+            '!' => (TokenKind::Not, 1),
+            '<' if two == "<=" => (TokenKind::Cmp(CmpOp::Le), 2),
+            '<' => (TokenKind::Cmp(CmpOp::Lt), 1),
+            '>' if two == ">=" => (TokenKind::Cmp(CmpOp::Ge), 2),
+            '>' => (TokenKind::Cmp(CmpOp::Gt), 1),
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                let mut closed = false;
+                while let Some((_, ch)) = chars.next() {
+                    match ch {
+                        '\\' => match chars.peek() {
+                            Some(&(_, esc)) if matches!(esc, '"' | '\'' | '\\') => {
+                                value.push(esc);
+                                chars.next();
+                            }
+                            _ => value.push('\\'),
+                        },
+                        ch if ch == c => {
+                            closed = true;
+                            break;
+                        }
+                        ch => value.push(ch),
+                    }
+                }
+                if !closed {
+                    return Err(ConditionError::new("unterminated string", i));
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    offset: i,
+                });
+                continue;
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&(_, ch)) = chars.peek() {
+                    if !is_word_char(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                let kind = match word.to_ascii_lowercase().as_str() {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    "glob" => TokenKind::Glob,
+                    _ => TokenKind::Word(word),
+                };
+                tokens.push(Token { kind, offset: i });
+                continue;
+            }
+            other => {
+                return Err(ConditionError::new(
+                    format!("unexpected character '{other}'"),
+                    i,
+                ))
+            }
+        };
+        for _ in 0..len {
+            chars.next();
+        }
+        tokens.push(Token { kind, offset: i });
+    }
+    Ok(tokens)
+}
+
+// ─── Parser ────────────────────────────────────────────────────────────────
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Source length — offset reported for "unexpected end of input".
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, ConditionError> {
+        let tok = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| ConditionError::new("unexpected end of expression", self.end))?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().map(|t| &t.kind) == Some(kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ConditionError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat(&TokenKind::Or) {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ConditionError> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat(&TokenKind::And) {
+            let rhs = self.parse_unary()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ConditionError> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ConditionError> {
+        let tok = self.next()?;
+        match tok.kind {
+            TokenKind::LParen => {
+                let inner = self.parse_or()?;
+                let close = self.next()?;
+                if close.kind != TokenKind::RParen {
+                    return Err(ConditionError::new(
+                        format!("expected ')', found {}", close.kind),
+                        close.offset,
+                    ));
+                }
+                Ok(inner)
+            }
+            TokenKind::Word(name) => {
+                let field = Field::from_name(&name).ok_or_else(|| {
+                    ConditionError::new(
+                        format!(
+                            "unknown field '{name}' (expected one of: {})",
+                            FIELD_NAMES.join(", ")
+                        ),
+                        tok.offset,
+                    )
+                })?;
+                self.parse_comparison(field)
+            }
+            other => Err(ConditionError::new(
+                format!("expected a field name, found {other}"),
+                tok.offset,
+            )),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Expr, ConditionError> {
+        let op = match self.peek().map(|t| t.kind.clone()) {
+            Some(op @ (TokenKind::Cmp(_) | TokenKind::Glob | TokenKind::RegexMatch)) => {
+                self.pos += 1;
+                op
+            }
+            _ => return Ok(Expr::Present(field)),
+        };
+        let lit = self.next()?;
+        let text = match &lit.kind {
+            TokenKind::Str(s) | TokenKind::Word(s) => s.clone(),
+            other => {
+                return Err(ConditionError::new(
+                    format!("expected a value, found {other}"),
+                    lit.offset,
+                ))
+            }
+        };
+        let is_quoted = matches!(lit.kind, TokenKind::Str(_));
+
+        match op {
+            TokenKind::Cmp(cmp) if field.is_numeric() => {
+                let n: u64 = text.parse().ok().filter(|_| !is_quoted).ok_or_else(|| {
+                    ConditionError::new(
+                        format!("expected a number, found {}", lit.kind),
+                        lit.offset,
+                    )
+                })?;
+                Ok(Expr::CmpNum(field, cmp, n))
+            }
+            TokenKind::Cmp(cmp @ (CmpOp::Eq | CmpOp::Ne)) => {
+                let value = match field {
+                    // `file_ext=.rs` and `file_ext == "rs"` are equivalent.
+                    Field::FileExt => text.trim_start_matches('.').to_string(),
+                    Field::Trigger => {
+                        if !["session_complete", "task_done", "file_saved", "cron"]
+                            .contains(&text.as_str())
+                        {
+                            return Err(ConditionError::new(
+                                format!("unknown trigger '{text}'"),
+                                lit.offset,
+                            ));
+                        }
+                        text
+                    }
+                    _ => text,
+                };
+                Ok(Expr::CmpStr(field, cmp, value))
+            }
+            TokenKind::Cmp(_) => Err(ConditionError::new(
+                "ordering comparisons need a numeric field",
+                lit.offset,
+            )),
+            _ if field.is_numeric() => Err(ConditionError::new(
+                "glob and regex matches need a string field",
+                lit.offset,
+            )),
+            TokenKind::Glob => {
+                let re = Regex::new(&glob_to_regex(&text))
+                    .map_err(|e| ConditionError::new(format!("invalid glob: {e}"), lit.offset))?;
+                Ok(Expr::Matches(field, re))
+            }
+            _ => {
+                let re = Regex::new(&text)
+                    .map_err(|e| ConditionError::new(format!("invalid regex: {e}"), lit.offset))?;
+                Ok(Expr::Matches(field, re))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> TriggerEvent {
+        TriggerEvent {
+            kind: TriggerType::FileSaved,
+            session_id: Some("s1".into()),
+            task_id: None,
+            file_path: Some("src/automations/engine.rs".into()),
+            session_output: Some("thread 'main' panicked at src/lib.rs".into()),
+            session_duration_secs: Some(120),
+        }
+    }
+
+    fn eval(src: &str) -> bool {
+        Condition::parse(src).unwrap().evaluate(&event())
+    }
+
+    #[test]
+    fn legacy_forms_still_work() {
+        assert!(eval("session_duration_secs>100"));
+        assert!(!eval("session_duration_secs>300"));
+        assert!(eval("file_ext=.rs"));
+        assert!(eval("file_ext=rs"));
+    }
+
+    #[test]
+    fn boolean_operators_and_precedence() {
+        assert!(eval("trigger == 'file_saved' and not task_id"));
+        assert!(eval("task_id or session_id"));
+        // `and` binds tighter than `or`.
+        assert!(eval("session_id || task_id && file_ext == 'py'"));
+        assert!(!eval("(session_id || task_id) && file_ext == 'py'"));
+        assert!(eval("!(session_duration_secs >= 121)"));
+    }
+
+    #[test]
+    fn glob_and_regex() {
+        assert!(eval(r#"file_path glob "src/**/*.rs""#));
+        assert!(eval(r#"file_path glob "**/engine.rs""#));
+        assert!(!eval(r#"file_path glob "src/*.rs""#));
+        assert!(eval(r#"session_output =~ "(?i)PANICKED""#));
+        assert!(!eval(r#"session_output =~ "^ok$""#));
+    }
+
+    #[test]
+    fn only_quotes_and_backslashes_are_unescaped() {
+        assert!(eval(r#"file_path =~ "\w+\.rs$""#));
+        assert!(eval(r#"session_output =~ 'thread \'main\''"#));
+        let tokens = lex(r#""a\\b\"c\d""#).unwrap();
+        match &tokens[0].kind {
+            TokenKind::Str(s) => assert_eq!(s, r#"a\b"c\d"#),
+            other => panic!("unexpected {other}"),
+        }
+    }
+
+    #[test]
+    fn conditions_serialize_as_their_source() {
+        let cond = Condition::parse("file_ext == 'rs'").unwrap();
+        let json = serde_json::to_value(&cond).unwrap();
+        assert_eq!(json, "file_ext == 'rs'");
+        let back: Condition = serde_json::from_value(json).unwrap();
+        assert!(back.evaluate(&event()));
+        assert!(serde_json::from_value::<Condition>("nope ==".into()).is_err());
+    }
+
+    #[test]
+    fn missing_fields_compare_false() {
+        assert!(!eval("task_id == 't1'"));
+        assert!(!eval("task_id != 't1'"));
+    }
+
+    #[test]
+    fn errors_carry_offsets() {
+        let err = Condition::parse("session_duraton_secs > 5").unwrap_err();
+        assert_eq!(err.offset, 0);
+        assert!(err.message.contains("unknown field"));
+
+        let err = Condition::parse("file_path > 5").unwrap_err();
+        assert!(err.message.contains("numeric"));
+
+        let err = Condition::parse("session_duration_secs > 'x'").unwrap_err();
+        assert_eq!(err.offset, 24);
+
+        let err = Condition::parse("session_output =~ '('").unwrap_err();
+        assert!(err.message.contains("invalid regex"));
+
+        let err = Condition::parse("(session_id").unwrap_err();
+        assert_eq!(err.offset, 11);
+
+        assert!(Condition::parse("trigger == 'nope'").is_err());
+        assert!(Condition::parse("session_id == 's1' extra").is_err());
+        assert!(Condition::parse("").is_err());
+    }
+}
@@ -5,12 +5,19 @@
 //! [[automations]]
 //! name        = "run-tests-on-complete"
 //! trigger     = "session_complete"
-//! condition   = ""
+//! condition   = "file_path glob 'src/**' and session_duration_secs > 60"
 //! action      = "run_tests"
 //! enabled     = true
 //!
 //! [automations.action_config]
 //! command = "cargo test"
+//!
+//! [[automations]]
+//! name     = "nightly-tests"
+//! trigger  = "cron"
+//! schedule = "0 3 * * *"   # daemon local time; or "@daily", "@hourly", "*/5 * * * *"
+//! catch_up = "once"        # skip | once | all (runs missed while the daemon was down)
+//! action   = "run_tests"
 //! ```
 
 use std::path::Path;
@@ -19,7 +26,9 @@ use anyhow::{Context, Result};
 use serde::Deserialize;
 use tracing::{debug, warn};
 
-use super::engine::{ActionType, Automation, TriggerType};
+use super::condition::Condition;
+use super::cron::CronSchedule;
+use super::engine::{ActionType, Automation, CatchUpPolicy, TriggerType};
 
 // ─── Raw TOML types ────────────────────────────────────────────────────────
 
@@ -39,6 +48,10 @@ struct AutomationEntry {
     trigger: String,
     #[serde(default)]
     condition: String,
+    #[serde(default)]
+    schedule: Option<String>,
+    #[serde(default)]
+    catch_up: Option<String>,
     action: String,
     #[serde(default = "default_toml_table")]
     action_config: toml::Value,
@@ -56,18 +69,23 @@ fn default_toml_table() -> toml::Value {
 
 /// Load user automations from `.claw/config.toml` in `repo_path`.
 /// Returns an empty vec if the file or the `[automations]` section is missing.
+/// Invalid entries (unknown trigger, bad condition or schedule) fail the whole
+/// file and are logged with the offending automation name.
 pub fn load_from_repo(repo_path: &Path) -> Vec<Automation> {
     let config_path = repo_path.join(".claw").join("config.toml");
     if !config_path.exists() {
         return vec![];
     }
     match load_file(&config_path) {
-        Ok(automations) => {
+        Ok(mut automations) => {
+            for auto in &mut automations {
+                auto.repo_path = Some(repo_path.to_string_lossy().into_owned());
+            }
             debug!(count = automations.len(), "loaded automations from config");
             automations
         }
         Err(e) => {
-            warn!(path = %config_path.display(), "failed to parse automations config: {e}");
+            warn!(path = %config_path.display(), "failed to parse automations config: {e:#}");
             vec![]
         }
     }
@@ -88,20 +106,36 @@ fn entry_to_automation(e: AutomationEntry) -> Result<Automation> {
     let trigger = parse_trigger(&e.trigger)?;
     let action = parse_action(&e.action)?;
     let action_config = toml_to_json(e.action_config);
+    let catch_up = parse_catch_up(e.catch_up.as_deref().unwrap_or("once"))?;
+
+    let condition =
+        if e.condition.is_empty() {
+            None
+        } else {
+            Some(Condition::parse(&e.condition).map_err(|err| {
+                anyhow::anyhow!("automation '{}': invalid condition: {err}", e.name)
+            })?)
+        };
+    if trigger == TriggerType::Cron {
+        let expr = e.schedule.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("automation '{}': cron trigger requires `schedule`", e.name)
+        })?;
+        CronSchedule::parse(expr)
+            .with_context(|| format!("automation '{}': invalid schedule", e.name))?;
+    }
 
     Ok(Automation {
         name: e.name,
         description: e.description,
         enabled: e.enabled,
         trigger,
-        condition: if e.condition.is_empty() {
-            None
-        } else {
-            Some(e.condition)
-        },
+        condition,
+        schedule: e.schedule,
+        catch_up,
         action,
         action_config,
         builtin: false,
+        repo_path: None,
         last_triggered_at: None,
     })
 }
@@ -116,6 +150,15 @@ fn parse_trigger(s: &str) -> Result<TriggerType> {
     }
 }
 
+fn parse_catch_up(s: &str) -> Result<CatchUpPolicy> {
+    match s {
+        "skip" => Ok(CatchUpPolicy::Skip),
+        "once" => Ok(CatchUpPolicy::Once),
+        "all" => Ok(CatchUpPolicy::All),
+        other => anyhow::bail!("unknown catch_up policy: {other}"),
+    }
+}
+
 fn parse_action(s: &str) -> Result<ActionType> {
     match s {
         "run_tests" => Ok(ActionType::RunTests),
@@ -0,0 +1,360 @@
+//! Cron expression parser for `trigger = "cron"` automations.
+//!
+//! Supports the classic five-field form (`minute hour day-of-month month
+//! day-of-week`) with `*`, lists (`1,15`), ranges (`1-5`), steps (`*/5`,
+//! `10-40/10`), three-letter month/weekday names, and the `@yearly`,
+//! `@annually`, `@monthly`, `@weekly`, `@daily`, `@midnight` and `@hourly`
+//! shorthands.
+//!
+//! Day-of-month and day-of-week follow Vixie cron semantics: when both are
+//! restricted, a day matches if *either* field matches. A field starting
+//! with `*` (including `*/N`) counts as unrestricted, and then a day must
+//! match both.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, TimeZone, Timelike};
+
+/// How far ahead `next_after` searches before giving up (e.g. `0 0 30 2 *`).
+const SEARCH_HORIZON_DAYS: i64 = 366 * 5;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron schedule. Each field is stored as a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression or `@`-shorthand.
+    pub fn parse(expr: &str) -> Result<Self> {
+        let source = expr.trim();
+        let expanded = match source.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            s if s.starts_with('@') => bail!("unsupported cron shorthand: {source}"),
+            _ => source,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            bail!(
+                "cron expression must have 5 fields (minute hour day month weekday), got {}: {source}",
+                fields.len()
+            );
+        }
+
+        let minutes = parse_field(fields[0], 0, 59, &[]).context("minute field")?;
+        let hours = parse_field(fields[1], 0, 23, &[]).context("hour field")?;
+        let days_of_month = parse_field(fields[2], 1, 31, &[]).context("day-of-month field")?;
+        let months = parse_field(fields[3], 1, 12, &MONTH_NAMES).context("month field")?;
+        let mut days_of_week =
+            parse_field(fields[4], 0, 7, &WEEKDAY_NAMES).context("day-of-week field")?;
+        // Both 0 and 7 mean Sunday.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            dom_restricted: !fields[2].starts_with('*'),
+            dow_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// The first fire time strictly after `after`, evaluated in `after`'s
+    /// time zone. Wall-clock times skipped by a DST transition never fire;
+    /// repeated ones fire once (on the earlier instant).
+    ///
+    /// Returns `None` if the expression can never match (e.g. February 30th).
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let local = after.naive_local();
+        let mut t = local.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let horizon = local + Duration::days(SEARCH_HORIZON_DAYS);
+
+        while t <= horizon {
+            if !bit(self.months, t.month()) {
+                let (y, m) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !bit(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            let candidate = match tz.from_local_datetime(&t) {
+                LocalResult::Single(dt) => Some(dt),
+                LocalResult::Ambiguous(earliest, latest) => {
+                    if earliest > *after {
+                        Some(earliest)
+                    } else if latest > *after {
+                        Some(latest)
+                    } else {
+                        None
+                    }
+                }
+                LocalResult::None => None,
+            };
+            match candidate {
+                Some(dt) if dt > *after => return Some(dt),
+                _ => t += Duration::minutes(1),
+            }
+        }
+        None
+    }
+
+    /// All fire times in `(after, until]`, oldest first, capped at `limit`
+    /// entries (the most recent ones are kept).
+    pub fn fire_times_between<Tz: TimeZone>(
+        &self,
+        after: &DateTime<Tz>,
+        until: &DateTime<Tz>,
+        limit: usize,
+    ) -> Vec<DateTime<Tz>> {
+        let mut out = VecDeque::new();
+        let mut cursor = after.clone();
+        while let Some(next) = self.next_after(&cursor) {
+            if next > *until {
+                break;
+            }
+            out.push_back(next.clone());
+            if out.len() > limit {
+                out.pop_front();
+            }
+            cursor = next;
+        }
+        out.into()
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = bit(self.days_of_month, date.day());
+        let dow = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1u64 << value) != 0
+}
+
+/// Parse one comma-separated cron field into a bitmask over `min..=max`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((r, s)) => {
+                let step: u32 = s.parse().with_context(|| format!("invalid step '{s}'"))?;
+                if step == 0 {
+                    bail!("step must be greater than zero");
+                }
+                (r, step)
+            }
+            None => (item, 1),
+        };
+
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (
+                parse_value(a, min, max, names)?,
+                parse_value(b, min, max, names)?,
+            )
+        } else {
+            let v = parse_value(range, min, max, names)?;
+            // `5/15` means "from 5 to the end of the range, every 15".
+            if step > 1 {
+                (v, max)
+            } else {
+                (v, v)
+            }
+        };
+        if lo > hi {
+            bail!("range start {lo} is greater than end {hi}");
+        }
+
+        let mut v = lo;
+        while v <= hi {
+            mask |= 1u64 << v;
+            v += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(s: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+    let lower = s.to_ascii_lowercase();
+    if let Some(idx) = names.iter().position(|n| *n == lower) {
+        // Month names are 1-based, weekday names are 0-based.
+        return Ok(idx as u32 + min);
+    }
+    let v: u32 = s.parse().with_context(|| format!("invalid value '{s}'"))?;
+    if v < min || v > max {
+        bail!("value {v} out of range {min}-{max}");
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn shorthands_expand() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        assert_eq!(
+            hourly.next_after(&at("2026-03-01T10:15:00Z")),
+            Some(at("2026-03-01T11:00:00Z"))
+        );
+        let daily = CronSchedule::parse("@daily").unwrap();
+        assert_eq!(
+            daily.next_after(&at("2026-03-01T10:15:00Z")),
+            Some(at("2026-03-02T00:00:00Z"))
+        );
+        assert!(CronSchedule::parse("@reboot").is_err());
+    }
+
+    #[test]
+    fn step_and_range_fields() {
+        let every5 = CronSchedule::parse("*/5 * * * *").unwrap();
+        assert_eq!(
+            every5.next_after(&at("2026-03-01T10:02:30Z")),
+            Some(at("2026-03-01T10:05:00Z"))
+        );
+        // Exactly on a fire time: next is strictly after.
+        assert_eq!(
+            every5.next_after(&at("2026-03-01T10:05:00Z")),
+            Some(at("2026-03-01T10:10:00Z"))
+        );
+
+        let weekdays = CronSchedule::parse("30 9 * * mon-fri").unwrap();
+        // 2026-03-07 is a Saturday.
+        assert_eq!(
+            weekdays.next_after(&at("2026-03-07T08:00:00Z")),
+            Some(at("2026-03-09T09:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn dom_and_dow_are_ored_when_both_restricted() {
+        // 1st of the month OR any Friday.
+        let s = CronSchedule::parse("0 0 1 * 5").unwrap();
+        // 2026-03-02 (Mon) → next Friday 2026-03-06.
+        assert_eq!(
+            s.next_after(&at("2026-03-02T00:00:00Z")),
+            Some(at("2026-03-06T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn starred_step_day_of_month_is_unrestricted() {
+        // Vixie cron: `*/2` does not restrict, so both fields must match —
+        // odd days of the month that are also Mondays.
+        let s = CronSchedule::parse("0 0 */2 * 1").unwrap();
+        // 2026-03-02 is an even Monday; 2026-03-09 is the next odd one.
+        assert_eq!(
+            s.next_after(&at("2026-03-01T00:00:00Z")),
+            Some(at("2026-03-09T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        let a = CronSchedule::parse("0 12 * * 0").unwrap();
+        let b = CronSchedule::parse("0 12 * * 7").unwrap();
+        let from = at("2026-03-02T00:00:00Z");
+        assert_eq!(a.next_after(&from), b.next_after(&from));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "x * * * *",
+        ] {
+            assert!(
+                CronSchedule::parse(expr).is_err(),
+                "{expr:?} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_date_never_fires() {
+        let s = CronSchedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(s.next_after(&at("2026-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn fire_times_between_keeps_most_recent() {
+        let s = CronSchedule::parse("@hourly").unwrap();
+        let runs =
+            s.fire_times_between(&at("2026-03-01T00:00:00Z"), &at("2026-03-01T05:30:00Z"), 2);
+        assert_eq!(
+            runs,
+            vec![at("2026-03-01T04:00:00Z"), at("2026-03-01T05:00:00Z")]
+        );
+    }
+}
@@ -1,14 +1,22 @@
 //! Automation engine — trigger evaluation and action dispatch.
 //!
 //! Automations are lightweight "if trigger → run action" rules loaded from
-//! `.claw/config.toml` and three built-in automations that are always active.
+//! each open repo's `.claw/config.toml` and three built-in automations that
+//! are always active.
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 use tokio::sync::broadcast;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
+use super::condition::Condition;
+use super::cron::CronSchedule;
 use crate::AppContext;
 
 // ─── Trigger types ─────────────────────────────────────────────────────────
@@ -24,9 +32,32 @@ pub enum TriggerType {
     /// A file in the repo was written by a tool call.
     FileSaved,
     /// A cron expression fired (simple: `"@hourly"`, `"@daily"`, `"*/5 * * * *"`).
+    /// The expression lives in [`Automation::schedule`].
     Cron,
 }
 
+/// What to do with cron fire times that were missed while the daemon was down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed runs; wait for the next scheduled time.
+    Skip,
+    /// Run once on startup if at least one run was missed.
+    #[default]
+    Once,
+    /// Replay every missed run (capped at [`MAX_CATCH_UP_RUNS`]).
+    All,
+}
+
+/// Upper bound on replayed runs for [`CatchUpPolicy::All`].
+pub const MAX_CATCH_UP_RUNS: usize = 24;
+
+/// A fire time this close to "now" is on schedule rather than missed.
+const CRON_GRACE_SECS: i64 = 60;
+
+/// Longest the cron loop sleeps before re-reading the automation list.
+const CRON_MAX_SLEEP_SECS: i64 = 60;
+
 /// A trigger event payload passed to automation evaluators.
 #[derive(Debug, Clone)]
 pub struct TriggerEvent {
@@ -72,19 +103,39 @@ pub struct Automation {
     pub enabled: bool,
     /// What fires this automation.
     pub trigger: TriggerType,
-    /// Optional condition expression (simple key=value check on event fields).
-    pub condition: Option<String>,
+    /// Optional condition over event fields (see [`super::condition`]),
+    /// parsed when the automation is loaded.
+    pub condition: Option<Condition>,
+    /// Cron expression for `TriggerType::Cron` automations (e.g. `"0 3 * * *"`).
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// Catch-up behaviour for cron runs missed while the daemon was down.
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
     /// What the automation does when triggered.
     pub action: ActionType,
     /// Action-specific configuration (command to run, task title template, etc.).
     pub action_config: serde_json::Value,
     /// Whether this is a built-in (cannot be deleted, only disabled).
     pub builtin: bool,
+    /// Repo whose `.claw/config.toml` defines this automation; its commands
+    /// run there.  `None` for built-ins.
+    #[serde(default)]
+    pub repo_path: Option<String>,
     /// ISO-8601 timestamp of last trigger (or None if never run).
     pub last_triggered_at: Option<String>,
 }
 
 impl Automation {
+    /// Key its last cron fire is persisted under: the name, qualified by the
+    /// repo for automations loaded from one.
+    pub fn cron_key(&self) -> String {
+        match &self.repo_path {
+            Some(repo) => format!("{repo}#{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
     /// Returns true if the event matches this automation's trigger.
     pub fn matches(&self, event: &TriggerEvent) -> bool {
         if !self.enabled {
@@ -93,30 +144,72 @@ impl Automation {
         if self.trigger != event.kind {
             return false;
         }
-        // Simple condition check: "key=value" string matched against event fields.
-        if let Some(cond) = &self.condition {
-            if !evaluate_condition(cond, event) {
-                return false;
-            }
-        }
-        true
+        self.condition
+            .as_ref()
+            .is_none_or(|cond| cond.evaluate(event))
     }
-}
 
-/// Evaluate a simple condition string like `"session_duration_secs>300"`.
-fn evaluate_condition(condition: &str, event: &TriggerEvent) -> bool {
-    if let Some(rest) = condition.strip_prefix("session_duration_secs>") {
-        if let (Ok(threshold), Some(actual)) = (rest.parse::<u64>(), event.session_duration_secs) {
-            return actual > threshold;
+    /// Parsed cron schedule, if this is a cron automation with a valid expression.
+    pub fn cron_schedule(&self) -> Option<CronSchedule> {
+        if self.trigger != TriggerType::Cron {
+            return None;
         }
+        self.schedule
+            .as_deref()
+            .and_then(|s| CronSchedule::parse(s).ok())
     }
-    if let Some(rest) = condition.strip_prefix("file_ext=") {
-        if let Some(path) = &event.file_path {
-            return path.ends_with(rest);
+
+    /// Next time this automation will fire after `now` (enabled cron automations only).
+    pub fn next_run_after<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        if !self.enabled {
+            return None;
         }
+        self.cron_schedule()?.next_after(now)
+    }
+}
+
+/// Decide which cron fire times are due at `now`.
+///
+/// `last_fired` is the persisted time of the most recent fire (or the time the
+/// automation was first seen). Fire times within the grace window are always
+/// run; older ones are "missed" and handled according to `policy`. The caller
+/// should persist the latest due time (fired or skipped) before dispatching so
+/// a crash never replays the same run twice.
+pub fn plan_cron_runs<Tz: TimeZone>(
+    schedule: &CronSchedule,
+    policy: CatchUpPolicy,
+    last_fired: &DateTime<Tz>,
+    now: &DateTime<Tz>,
+) -> CronPlan<Tz> {
+    let due = schedule.fire_times_between(last_fired, now, MAX_CATCH_UP_RUNS);
+    let Some(latest) = due.last().cloned() else {
+        return CronPlan {
+            run: vec![],
+            advance_to: None,
+        };
+    };
+    let grace = Duration::seconds(CRON_GRACE_SECS);
+    let on_time = now.clone() - latest.clone() <= grace;
+
+    let run = match policy {
+        CatchUpPolicy::All => due,
+        CatchUpPolicy::Once => vec![latest.clone()],
+        CatchUpPolicy::Skip if on_time => vec![latest.clone()],
+        CatchUpPolicy::Skip => vec![],
+    };
+    CronPlan {
+        run,
+        advance_to: Some(latest),
     }
-    // Unknown condition — treat as always true so the user's intent is respected.
-    true
+}
+
+/// Output of [`plan_cron_runs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronPlan<Tz: TimeZone> {
+    /// Scheduled times to dispatch now, oldest first.
+    pub run: Vec<DateTime<Tz>>,
+    /// New `last_fired` value to persist, if anything became due.
+    pub advance_to: Option<DateTime<Tz>>,
 }
 
 // ─── Automation registry ───────────────────────────────────────────────────
@@ -138,6 +231,35 @@ impl AutomationEngine {
         })
     }
 
+    /// Load the automations in `repo_path`'s `.claw/config.toml`, replacing
+    /// any loaded from it before.  Called when the repo is opened.
+    ///
+    /// Names are unique across the engine: an automation whose name is
+    /// already taken by a built-in or another repo is skipped.
+    pub async fn load_repo(&self, repo_path: &str) {
+        let repo = canonical_repo(repo_path);
+        let loaded = super::config::load_from_repo(Path::new(&repo));
+        let mut automations = self.automations.write().await;
+        automations.retain(|a| a.repo_path.as_deref() != Some(repo.as_str()));
+        for auto in loaded {
+            if automations.iter().any(|a| a.name == auto.name) {
+                warn!(name = %auto.name, repo = %repo, "automation name already in use — skipped");
+                continue;
+            }
+            automations.push(auto);
+        }
+    }
+
+    /// Drop the automations loaded from `repo_path`.  Called when the repo
+    /// is closed.
+    pub async fn unload_repo(&self, repo_path: &str) {
+        let repo = canonical_repo(repo_path);
+        self.automations
+            .write()
+            .await
+            .retain(|a| a.repo_path.as_deref() != Some(repo.as_str()));
+    }
+
     /// Fire a trigger event. All matching automations will execute asynchronously.
     pub fn fire(&self, event: TriggerEvent) {
         if let Err(e) = self.event_tx.send(event) {
@@ -182,6 +304,158 @@ impl AutomationEngine {
     }
 }
 
+// ─── Cron scheduler ────────────────────────────────────────────────────────
+
+impl AutomationEngine {
+    /// Start the cron scheduling loop. Must be called once at daemon startup.
+    ///
+    /// Last-fired times are persisted in `automation_cron_state`, so a restart
+    /// neither double-fires nor silently skips a run: anything that came due
+    /// while the daemon was down is handled by the automation's
+    /// [`CatchUpPolicy`].
+    pub fn start_cron_scheduler(engine: Arc<Self>, ctx: AppContext) {
+        tokio::spawn(async move {
+            let pool = ctx.storage.clone_pool();
+            let mut last_fired = match load_cron_state(&pool).await {
+                Ok(state) => state,
+                Err(e) => {
+                    warn!("AutomationEngine: failed to load cron state: {e}");
+                    HashMap::new()
+                }
+            };
+
+            loop {
+                let now = Local::now();
+                let cron_autos: Vec<(Automation, CronSchedule)> = engine
+                    .automations
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|a| a.enabled)
+                    .filter_map(|a| a.cron_schedule().map(|s| (a.clone(), s)))
+                    .collect();
+
+                let mut next_wake = now + Duration::seconds(CRON_MAX_SLEEP_SECS);
+                for (auto, schedule) in &cron_autos {
+                    let key = auto.cron_key();
+                    let last = match last_fired.get(&key) {
+                        Some(t) => t.with_timezone(&Local),
+                        None => {
+                            // First sighting — start counting from now rather
+                            // than replaying the automation's entire history.
+                            record_fired(&pool, &mut last_fired, &key, now).await;
+                            now
+                        }
+                    };
+
+                    let plan = plan_cron_runs(schedule, auto.catch_up, &last, &now);
+                    if let Some(advance_to) = plan.advance_to {
+                        // Persist before dispatch: at-most-once across crashes.
+                        record_fired(&pool, &mut last_fired, &key, advance_to).await;
+                        if plan.run.is_empty() {
+                            info!(name = %auto.name, "cron automation: skipping missed runs");
+                        }
+                    }
+                    for scheduled_at in plan.run {
+                        engine.dispatch_cron(auto, scheduled_at, &ctx).await;
+                    }
+
+                    if let Some(next) = schedule.next_after(&now) {
+                        next_wake = next_wake.min(next);
+                    }
+                }
+
+                let sleep_for = (next_wake - Local::now())
+                    .to_std()
+                    .unwrap_or(std::time::Duration::from_millis(200));
+                tokio::time::sleep(sleep_for).await;
+            }
+        });
+    }
+
+    /// Run one cron fire of `auto`, honouring its condition.
+    async fn dispatch_cron(
+        &self,
+        auto: &Automation,
+        scheduled_at: DateTime<Local>,
+        ctx: &AppContext,
+    ) {
+        let event = TriggerEvent {
+            kind: TriggerType::Cron,
+            session_id: None,
+            task_id: None,
+            file_path: None,
+            session_output: None,
+            session_duration_secs: None,
+        };
+        if !auto.matches(&event) {
+            return;
+        }
+        debug!(name = %auto.name, %scheduled_at, "cron automation firing");
+
+        if let Some(a) = self
+            .automations
+            .write()
+            .await
+            .iter_mut()
+            .find(|a| a.name == auto.name)
+        {
+            a.last_triggered_at = Some(Utc::now().to_rfc3339());
+        }
+
+        let auto = auto.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::automations::builtins::execute(&auto, &event, &ctx).await {
+                warn!(name = %auto.name, "cron automation action failed: {e}");
+            }
+        });
+    }
+}
+
+/// Canonical form of a repo path, as the repo registry keys it.
+fn canonical_repo(repo_path: &str) -> String {
+    std::fs::canonicalize(repo_path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| repo_path.to_string())
+}
+
+async fn load_cron_state(pool: &SqlitePool) -> Result<HashMap<String, DateTime<Utc>>> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, last_fired_at FROM automation_cron_state")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(name, ts)| {
+            DateTime::parse_from_rfc3339(&ts)
+                .ok()
+                .map(|t| (name, t.with_timezone(&Utc)))
+        })
+        .collect())
+}
+
+async fn record_fired(
+    pool: &SqlitePool,
+    cache: &mut HashMap<String, DateTime<Utc>>,
+    name: &str,
+    at: DateTime<Local>,
+) {
+    let at = at.with_timezone(&Utc);
+    cache.insert(name.to_string(), at);
+    if let Err(e) = sqlx::query(
+        "INSERT INTO automation_cron_state (name, last_fired_at) VALUES (?, ?)
+         ON CONFLICT(name) DO UPDATE SET last_fired_at = excluded.last_fired_at",
+    )
+    .bind(name)
+    .bind(at.to_rfc3339())
+    .execute(pool)
+    .await
+    {
+        warn!(name, "failed to persist cron state: {e}");
+    }
+}
+
 /// Convenience — get a snapshot of all automations for RPC responses.
 pub async fn list_automations(engine: &AutomationEngine) -> Vec<Automation> {
     engine.automations.read().await.clone()
@@ -4,5 +4,7 @@
 //! occur in the daemon (session complete, task done, file saved, cron).
 
 pub mod builtins;
+pub mod condition;
 pub mod config;
+pub mod cron;
 pub mod engine;
@@ -0,0 +1,293 @@
+// cli/mcp.rs — `clawd mcp serve --stdio`.
+//
+// Lets MCP clients that launch servers as subprocesses (Claude Code and
+// Codex `mcpServers` entries) use the running daemon's MCP server.  Each
+// newline-delimited JSON-RPC message on stdin is POSTed to the daemon's
+// Streamable-HTTP endpoint (`/api/v1/mcp`) with the master token; responses
+// and progress notifications are written to stdout, one per line.
+//
+// - Requests run concurrently, so `notifications/cancelled` can reach a call
+//   that is still running.  `initialize` and notifications are sent in
+//   order, before anything read after them.
+// - If the daemon restarts and forgets the session (404), the bridge replays
+//   the client's `initialize` and retries once.
+// - Once it has a session the bridge also holds the session's GET stream
+//   open and relays server notifications (resource updates) from it.
+// - Nothing but protocol messages may go to stdout; logs go to stderr.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use super::client::read_auth_token;
+use crate::rest::mcp::SESSION_HEADER;
+use crate::rest::REST_PORT;
+use crate::session::http::SseDecoder;
+
+/// Serve MCP on stdin/stdout for the daemon in `data_dir`.
+pub async fn serve_stdio(data_dir: &Path) -> Result<()> {
+    let token = read_auth_token(data_dir)?;
+    let endpoint = format!("http://127.0.0.1:{REST_PORT}/api/v1/mcp");
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    bridge(&endpoint, &token, stdin, tokio::io::stdout()).await
+}
+
+/// Shared state of one stdio ↔ HTTP bridge.
+struct Bridge {
+    http: reqwest::Client,
+    endpoint: String,
+    token: String,
+    session: Mutex<Option<String>>,
+    /// The client's `initialize`, replayed when the daemon loses the session.
+    init: Mutex<Option<Value>>,
+    /// Relays the session's standalone stream.
+    listener: Mutex<Option<tokio::task::AbortHandle>>,
+    out: mpsc::UnboundedSender<String>,
+}
+
+/// Relay MCP messages between `input`/`output` and `endpoint` until `input`
+/// closes, then end the session.
+pub async fn bridge<R, W>(endpoint: &str, token: &str, input: R, mut output: W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (out, mut rx) = mpsc::unbounded_channel::<String>();
+    let writer = tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            if output.write_all(line.as_bytes()).await.is_err()
+                || output.write_all(b"\n").await.is_err()
+                || output.flush().await.is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let bridge = Arc::new(Bridge {
+        http: reqwest::Client::new(),
+        endpoint: endpoint.to_string(),
+        token: token.to_string(),
+        session: Mutex::new(None),
+        init: Mutex::new(None),
+        listener: Mutex::new(None),
+        out,
+    });
+
+    let mut lines = input.lines();
+    let mut calls = tokio::task::JoinSet::new();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let msg: Value = match serde_json::from_str(&line) {
+            Ok(msg) => msg,
+            Err(_) => {
+                let err = json!({ "jsonrpc": "2.0", "id": null,
+                                  "error": { "code": -32700, "message": "Parse error" } });
+                bridge.emit(err.to_string());
+                continue;
+            }
+        };
+        let is_request = msg.get("id").is_some() && msg.get("method").is_some();
+        let is_initialize = msg.get("method").and_then(Value::as_str) == Some("initialize");
+        if is_initialize {
+            *bridge.init.lock().unwrap_or_else(|e| e.into_inner()) = Some(msg.clone());
+        }
+        if is_request && !is_initialize {
+            let bridge = Arc::clone(&bridge);
+            calls.spawn(async move { bridge.forward(msg).await });
+        } else {
+            bridge.forward(msg).await;
+        }
+    }
+
+    // Let answered calls flush, then end the session.
+    while calls.join_next().await.is_some() {}
+    bridge.end_session().await;
+    drop(bridge);
+    let _ = writer.await;
+    Ok(())
+}
+
+impl Bridge {
+    fn emit(&self, line: String) {
+        let _ = self.out.send(line);
+    }
+
+    fn session(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Send one message and relay whatever comes back.
+    async fn forward(&self, msg: Value) {
+        let id = msg.get("id").cloned();
+        let result = match self.post(&msg).await {
+            Ok(resp)
+                if resp.status() == reqwest::StatusCode::NOT_FOUND && self.reinitialize().await =>
+            {
+                self.post(&msg).await
+            }
+            other => other,
+        };
+        let outcome = match result {
+            Ok(resp) => self.relay(resp).await,
+            Err(e) => Err(e),
+        };
+        if let (Err(e), Some(id)) = (outcome, id.filter(|_| msg.get("method").is_some())) {
+            let err = json!({ "jsonrpc": "2.0", "id": id,
+                              "error": { "code": -32603, "message": format!("{e:#}") } });
+            self.emit(err.to_string());
+        }
+    }
+
+    async fn post(&self, msg: &Value) -> Result<reqwest::Response> {
+        let mut req = self
+            .http
+            .post(&self.endpoint)
+            .bearer_auth(&self.token)
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(msg);
+        if let Some(session) = self.session() {
+            req = req.header(SESSION_HEADER, session);
+        }
+        req.send().await.with_context(|| {
+            format!(
+                "clawd daemon is not reachable at {} — is it running?",
+                self.endpoint
+            )
+        })
+    }
+
+    /// Write a response body — JSON or an SSE stream of messages — to stdout.
+    async fn relay(&self, mut resp: reqwest::Response) -> Result<()> {
+        if let Some(session) = resp
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            self.set_session(session);
+        }
+        let status = resp.status();
+        let is_stream = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+        if is_stream {
+            let mut sse = SseDecoder::default();
+            while let Some(chunk) = resp.chunk().await? {
+                for data in sse.push(&chunk) {
+                    self.emit(data);
+                }
+            }
+            return Ok(());
+        }
+        let body = resp.text().await?;
+        if body.trim().is_empty() {
+            return Ok(());
+        }
+        let value: Value = serde_json::from_str(&body).context("daemon sent invalid JSON")?;
+        if !status.is_success() && value.get("jsonrpc").is_none() {
+            let error = value["error"].as_str().unwrap_or("request failed");
+            anyhow::bail!("daemon returned {status}: {error}");
+        }
+        match value {
+            Value::Array(items) => items.into_iter().for_each(|v| self.emit(v.to_string())),
+            single => self.emit(single.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Replay the client's `initialize` for a fresh session.  The response
+    /// is dropped — the client already has one.
+    async fn reinitialize(&self) -> bool {
+        let Some(init) = self.init.lock().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return false;
+        };
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        let Ok(resp) = self.post(&init).await else {
+            return false;
+        };
+        let Some(session) = resp
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+        else {
+            return false;
+        };
+        self.set_session(&session);
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        self.post(&initialized).await.is_ok()
+    }
+
+    /// Record the session and, when it is new, (re)open its stream.
+    fn set_session(&self, session: &str) {
+        let mut current = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        if current.as_deref() == Some(session) {
+            return;
+        }
+        *current = Some(session.to_string());
+        let req = self
+            .http
+            .get(&self.endpoint)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .header(SESSION_HEADER, session);
+        let out = self.out.clone();
+        let listener = tokio::spawn(async move {
+            let Ok(mut resp) = req.send().await else {
+                return;
+            };
+            if !resp.status().is_success() {
+                return;
+            }
+            let mut sse = SseDecoder::default();
+            while let Ok(Some(chunk)) = resp.chunk().await {
+                for data in sse.push(&chunk) {
+                    if out.send(data).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        if let Some(old) = self
+            .listener
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(listener.abort_handle())
+        {
+            old.abort();
+        }
+    }
+
+    async fn end_session(&self) {
+        if let Some(listener) = self
+            .listener
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            listener.abort();
+        }
+        if let Some(session) = self.session() {
+            let _ = self
+                .http
+                .delete(&self.endpoint)
+                .bearer_auth(&self.token)
+                .header(SESSION_HEADER, session)
+                .send()
+                .await;
+        }
+    }
+}
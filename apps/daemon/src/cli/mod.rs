@@ -12,6 +12,7 @@ pub mod ghost_diff;
 pub mod git_query;
 pub mod instructions;
 pub mod license;
+pub mod mcp;
 pub mod memory;
 pub mod observe;
 pub mod plugin;
@@ -21,3 +22,4 @@ pub mod recipe;
 pub mod session_export;
 pub mod sign_run;
 pub mod sovereignty;
+pub mod token;
@@ -57,7 +57,7 @@ pub async fn scaffold(plugin_type: &str, name: &str) -> Result<()> {
             [lib]\n\
             crate-type = [\"cdylib\"]\n\n\
             [dependencies]\n\
-            clawd_plugin_abi = \"1\"\n"
+            clawd_plugin_abi = \"1.1\"\n"
         );
         let lib_rs = r#"use clawd_plugin_abi::{ClawaContext, ClawaError, ClawaPlugin, CLAWD_PLUGIN_ABI_VERSION};
 
@@ -71,7 +71,9 @@ static mut PLUGIN: ClawaPlugin = ClawaPlugin {
     on_session_end: None,
     on_tool_call: None,
     on_message: None,
-    _reserved: [None; 8],
+    on_tool_invoke: None,
+    on_rpc: None,
+    _reserved: [None; 6],
 };
 
 unsafe extern "C" fn on_load(_ctx: *mut ClawaContext) -> ClawaError {
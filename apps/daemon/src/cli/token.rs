@@ -0,0 +1,165 @@
+// cli/token.rs — `clawd token create/list/revoke` CLI commands.
+//
+// Scoped API tokens are managed through the running daemon with the master
+// token from {data_dir}/auth_token.
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::client::{read_auth_token, DaemonClient};
+
+/// Parse a duration like `30d`, `12h`, `45m` or `90s` into seconds.
+pub fn parse_expiry(s: &str) -> Result<i64> {
+    let s = s.trim();
+    let (num, unit) = s.split_at(s.len().saturating_sub(1));
+    let n: i64 = match num.parse() {
+        Ok(n) if n > 0 => n,
+        _ => bail!("invalid expiry '{s}' — use e.g. 30d, 12h, 45m"),
+    };
+    let secs = match unit {
+        "d" => n * 86_400,
+        "h" => n * 3_600,
+        "m" => n * 60,
+        "s" => n,
+        _ => bail!("invalid expiry '{s}' — use e.g. 30d, 12h, 45m"),
+    };
+    Ok(secs)
+}
+
+fn format_ts(ts: &Value) -> String {
+    ts.as_i64()
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "—".to_string())
+}
+
+fn join(list: &Value) -> String {
+    list.as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default()
+}
+
+/// `clawd token create <name> --method <glob>... [--repo <path>...] [--expires 30d]`
+pub async fn cmd_create(
+    name: String,
+    methods: Vec<String>,
+    repos: Vec<String>,
+    expires: Option<String>,
+    data_dir: &Path,
+    port: u16,
+) -> Result<()> {
+    let expires_in_secs = expires.as_deref().map(parse_expiry).transpose()?;
+    let repos = repos
+        .into_iter()
+        .map(|r| {
+            std::fs::canonicalize(&r)
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or(r)
+        })
+        .collect::<Vec<_>>();
+
+    let token = read_auth_token(data_dir)?;
+    let client = DaemonClient::new(port, token);
+    let result = client
+        .call_once(
+            "token.create",
+            json!({
+                "name": name,
+                "methods": methods,
+                "repos": repos,
+                "expires_in_secs": expires_in_secs,
+            }),
+        )
+        .await?;
+
+    let t = &result["token"];
+    println!(
+        "✓ Token '{}' created (id: {})",
+        t["name"].as_str().unwrap_or(""),
+        t["id"].as_str().unwrap_or("")
+    );
+    println!("  methods: {}", join(&t["methods"]));
+    if !repos.is_empty() {
+        println!("  repos:   {}", join(&t["repos"]));
+    }
+    println!("  expires: {}", format_ts(&t["expires_at"]));
+    println!();
+    println!("{}", result["secret"].as_str().unwrap_or(""));
+    println!();
+    println!("Store this token now — it cannot be shown again.");
+    Ok(())
+}
+
+/// `clawd token list [--json]`
+pub async fn cmd_list(json_output: bool, data_dir: &Path, port: u16) -> Result<()> {
+    let token = read_auth_token(data_dir)?;
+    let client = DaemonClient::new(port, token);
+    let result = client.call_once("token.list", json!({})).await?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&result["tokens"])?);
+        return Ok(());
+    }
+
+    let tokens = result["tokens"].as_array().cloned().unwrap_or_default();
+    if tokens.is_empty() {
+        println!("No API tokens. Create one with: clawd token create <name> --method '<glob>'");
+        return Ok(());
+    }
+
+    println!(
+        "{:<26} {:<16} {:<13} {:<8} {:<16} {:<16} Methods",
+        "ID", "Name", "Prefix", "Status", "Expires", "Last used"
+    );
+    println!("{}", "-".repeat(110));
+    for t in &tokens {
+        println!(
+            "{:<26} {:<16} {:<13} {:<8} {:<16} {:<16} {}",
+            t["id"].as_str().unwrap_or(""),
+            t["name"].as_str().unwrap_or(""),
+            t["prefix"].as_str().unwrap_or(""),
+            t["status"].as_str().unwrap_or(""),
+            format_ts(&t["expires_at"]),
+            format_ts(&t["last_used_at"]),
+            join(&t["methods"]),
+        );
+        let repos = join(&t["repos"]);
+        if !repos.is_empty() {
+            println!("{:<26} repos: {}", "", repos);
+        }
+    }
+    Ok(())
+}
+
+/// `clawd token revoke <id>`
+pub async fn cmd_revoke(id: String, data_dir: &Path, port: u16) -> Result<()> {
+    let token = read_auth_token(data_dir)?;
+    let client = DaemonClient::new(port, token);
+    client
+        .call_once("token.revoke", json!({ "id": id }))
+        .await?;
+    println!("✓ Token {id} revoked");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_units() {
+        assert_eq!(parse_expiry("30d").unwrap(), 30 * 86_400);
+        assert_eq!(parse_expiry("12h").unwrap(), 12 * 3_600);
+        assert_eq!(parse_expiry("45m").unwrap(), 45 * 60);
+        assert!(parse_expiry("0d").is_err());
+        assert!(parse_expiry("10").is_err());
+        assert!(parse_expiry("").is_err());
+    }
+}
@@ -28,6 +28,12 @@ pub struct DiffRiskConfig {
     pub warn_threshold: f64,
     /// Risk score at which the diff is blocked (default: 200.0).
     pub block_threshold: f64,
+    /// After an agent's `apply_patch`, feed the changed files to the running
+    /// language server and attach new errors to the result (default: true).
+    pub lsp_gate: bool,
+    /// Refuse `transition_task` to `in_cr` while errors introduced by the
+    /// task's patches are still reported (default: false).
+    pub block_review_on_lsp_errors: bool,
 }
 
 impl Default for DiffRiskConfig {
@@ -35,6 +41,176 @@ impl Default for DiffRiskConfig {
         Self {
             warn_threshold: 50.0,
             block_threshold: 200.0,
+            lsp_gate: true,
+            block_review_on_lsp_errors: false,
+        }
+    }
+}
+
+// ─── JudgeConfig ──────────────────────────────────────────────────────────────
+
+/// LLM-as-judge configuration (`[judge]` in config.toml), used by
+/// `evals::judge` to score agent output against a rubric.
+///
+/// ```toml
+/// [judge]
+/// judges = ["claude", "http"]   # scores are averaged across judges
+/// endpoint = "http://localhost:8080/v1/chat/completions"
+/// model = "gpt-4o-mini"
+/// api_key_env = "OPENAI_API_KEY"
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JudgeConfig {
+    /// Judges to consult: provider names (`claude`, `codex`, `gemini`) run
+    /// through their CLI, a `[provider.<name>]` with `api` set, or `http`
+    /// for the endpoint below. Default: `["claude"]`.
+    pub judges: Vec<String>,
+    /// OpenAI-compatible chat completions URL for the `http` judge.
+    pub endpoint: Option<String>,
+    /// Model name sent to the `http` judge.
+    pub model: Option<String>,
+    /// Environment variable holding the `http` judge's bearer token.
+    pub api_key_env: Option<String>,
+    /// Per-judge timeout in seconds. Default: 120.
+    pub timeout_secs: u64,
+}
+
+impl Default for JudgeConfig {
+    fn default() -> Self {
+        Self {
+            judges: vec!["claude".to_string()],
+            endpoint: None,
+            model: None,
+            api_key_env: None,
+            timeout_secs: 120,
+        }
+    }
+}
+
+// ─── FallbackChainsConfig ─────────────────────────────────────────────────────
+
+/// Provider fallback chains (`[fallback]` in config.toml), resolved by
+/// `scheduler::fallback::FallbackConfig::resolve`.
+///
+/// The most specific chain wins: project + role, project default, role,
+/// global default, then the built-in `claude → codex`.  Role keys are agent
+/// role names (`router`, `planner`, `implementer`, `reviewer`, `qa_executor`);
+/// project keys are repo paths.
+///
+/// ```toml
+/// [fallback]
+/// default = ["claude", "codex", "gemini"]
+/// roles.reviewer = ["codex", "gemini", "claude"]
+///
+/// [fallback.projects."/home/me/app"]
+/// default = ["gemini", "claude"]
+/// roles.implementer = ["cursor", "claude"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FallbackChainsConfig {
+    /// Chain used when nothing more specific matches.
+    pub default: Vec<String>,
+    /// Per-role chains.
+    pub roles: std::collections::HashMap<String, Vec<String>>,
+    /// Per-project overrides, keyed by repo path.
+    pub projects: std::collections::HashMap<String, ProjectFallbackConfig>,
+}
+
+/// Fallback chains for one project (`[fallback.projects."<repo>"]`).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProjectFallbackConfig {
+    pub default: Vec<String>,
+    pub roles: std::collections::HashMap<String, Vec<String>>,
+}
+
+// ─── SchedulerConfig ──────────────────────────────────────────────────────────
+
+/// Scheduler queue tuning (`[scheduler]` in config.toml).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// A queued request gains `aging_step` priority for every full interval
+    /// it waits, so low-priority work cannot starve. 0 disables aging. Default: 60.
+    pub aging_interval_secs: u64,
+    /// Priority added per aging interval. Default: 10.
+    pub aging_step: u32,
+    /// Requests that may run concurrently on one account. Default: 1.
+    pub slots_per_account: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            aging_interval_secs: 60,
+            aging_step: 10,
+            slots_per_account: 1,
+        }
+    }
+}
+
+// ─── RoutingConfig ────────────────────────────────────────────────────────────
+
+/// Learned auto-routing for `provider = "auto"` (`[routing]` in config.toml).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Use arena votes, session outcomes and costs to pick a provider.
+    /// When false, only the keyword heuristic is used. Default: true.
+    pub learned: bool,
+    /// Observations a history bucket needs before it is trusted. Default: 5.
+    pub min_samples: u64,
+    /// How strongly relative cost lowers a provider's score, in win-rate
+    /// points (0.0 ignores cost). Default: 0.15.
+    pub cost_weight: f64,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            learned: true,
+            min_samples: 5,
+            cost_weight: 0.15,
+        }
+    }
+}
+
+// ─── PluginsConfig ────────────────────────────────────────────────────────────
+
+/// Plugin runtime limits (`[plugins]` in config.toml).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// Upper bound on a single plugin callback in milliseconds. A plugin
+    /// that exceeds it is skipped for that event. Default: 2000.
+    pub call_timeout_ms: u64,
+    /// Fuel (≈ WASM instructions) granted to each WASM plugin call.
+    /// Default: 500 000 000.
+    pub wasm_fuel: u64,
+    /// Linear memory cap per WASM plugin in MiB. Default: 64.
+    pub wasm_memory_mb: u64,
+    /// Reload a plugin when its files under `.claw/plugins/` change.
+    /// Default: true.
+    pub hot_reload: bool,
+    /// Quiet period before a change triggers a reload, in milliseconds, so a
+    /// multi-file upgrade is picked up at once. Default: 500.
+    pub reload_debounce_ms: u64,
+    /// Hex Ed25519 public key every plugin binary must be signed with.
+    /// Checked on load and again on every reload. Default: unset.
+    pub signer_pubkey: Option<String>,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            call_timeout_ms: 2000,
+            wasm_fuel: 500_000_000,
+            wasm_memory_mb: 64,
+            hot_reload: true,
+            reload_debounce_ms: 500,
+            signer_pubkey: None,
         }
     }
 }
@@ -153,6 +329,68 @@ pub struct ProviderProfile {
     pub max_tokens: Option<u64>,
     /// Prefix prepended to the system prompt for this provider.
     pub system_prompt_prefix: Option<String>,
+    /// Wire protocol for a direct HTTP provider. Setting this turns the
+    /// profile into a session provider of its own (selected by its section
+    /// name) that talks to `base_url` instead of spawning a CLI.
+    pub api: Option<HttpApi>,
+    /// API base URL, e.g. `http://localhost:11434/v1`
+    /// (default: the vendor's public endpoint for `api`).
+    pub base_url: Option<String>,
+    /// Model ID sent with every request (required when `api` is set).
+    pub model: Option<String>,
+    /// Env var holding the API key (default: `OPENAI_API_KEY` / `ANTHROPIC_API_KEY`).
+    pub api_key_env: Option<String>,
+}
+
+impl ProviderProfile {
+    /// True when this profile describes a direct HTTP provider.
+    pub fn is_http(&self) -> bool {
+        self.api.is_some()
+    }
+}
+
+/// Permission scope granted to a REST API token (`api_scope` in config.toml).
+///
+/// Scopes are ordered: `admin` includes `write`, which includes `read`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Listing and inspecting state.
+    Read,
+    /// Everything that changes sessions, tasks, memory and other work state.
+    #[default]
+    Write,
+    /// Accounts, devices, updates, plugins and other daemon administration.
+    Admin,
+}
+
+/// HTTP API dialect spoken by a direct provider profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpApi {
+    /// OpenAI-compatible `/chat/completions` (OpenAI, vLLM, Ollama, llama.cpp).
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Anthropic Messages API (`/messages`).
+    Anthropic,
+}
+
+impl HttpApi {
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            HttpApi::OpenAi => "https://api.openai.com/v1",
+            HttpApi::Anthropic => "https://api.anthropic.com/v1",
+        }
+    }
+
+    pub fn default_api_key_env(self) -> &'static str {
+        match self {
+            HttpApi::OpenAi => "OPENAI_API_KEY",
+            HttpApi::Anthropic => "ANTHROPIC_API_KEY",
+        }
+    }
 }
 
 /// `{data_dir}/config.toml` — all fields are optional overrides.
@@ -201,10 +439,22 @@ struct TomlConfig {
     limits: Option<LimitsConfig>,
     /// Bearer token for the REST API (Sprint QQ RA.6). None = REST auth disabled.
     api_token: Option<String>,
+    /// Scope granted to `api_token` (default: write).
+    api_scope: Option<ApiScope>,
     /// Community integration opt-ins (`[community]`).
     community: Option<CommunityConfig>,
     /// Diff risk thresholds (`[diff_risk]`).
     diff_risk: Option<DiffRiskConfig>,
+    /// LLM-as-judge settings (`[judge]`).
+    judge: Option<JudgeConfig>,
+    /// Provider fallback chains (`[fallback]`).
+    fallback: Option<FallbackChainsConfig>,
+    /// Scheduler queue tuning (`[scheduler]`).
+    scheduler: Option<SchedulerConfig>,
+    /// Learned auto-routing (`[routing]`).
+    routing: Option<RoutingConfig>,
+    /// Plugin runtime limits (`[plugins]`).
+    plugins: Option<PluginsConfig>,
 }
 
 fn load_toml(data_dir: &Path) -> Option<TomlConfig> {
@@ -396,10 +646,22 @@ pub struct DaemonConfig {
     /// Set via `CLAWD_API_TOKEN` env var or `api_token` in config.toml.
     /// None = REST authentication disabled (local-only, trusted loopback use).
     pub api_token: Option<String>,
+    /// Highest scope `api_token` may use (REST routes and `POST /api/v1/rpc`).
+    pub api_scope: ApiScope,
     /// Community integration opt-ins (Sprint TT DC.3).
     pub community: CommunityConfig,
     /// Diff risk thresholds (Sprint ZZ DR.T02).
     pub diff_risk: DiffRiskConfig,
+    /// LLM-as-judge settings for evals.
+    pub judge: JudgeConfig,
+    /// Provider fallback chains per role / project.
+    pub fallback: FallbackChainsConfig,
+    /// Scheduler queue aging and per-account concurrency.
+    pub scheduler: SchedulerConfig,
+    /// Learned auto-routing thresholds.
+    pub routing: RoutingConfig,
+    /// Plugin callback timeout and WASM limits.
+    pub plugins: PluginsConfig,
 }
 
 impl DaemonConfig {
@@ -485,6 +747,11 @@ impl DaemonConfig {
 
         let community = toml.community.unwrap_or_default();
         let diff_risk = toml.diff_risk.unwrap_or_default();
+        let judge = toml.judge.unwrap_or_default();
+        let fallback = toml.fallback.unwrap_or_default();
+        let scheduler = toml.scheduler.unwrap_or_default();
+        let routing = toml.routing.unwrap_or_default();
+        let plugins = toml.plugins.unwrap_or_default();
 
         Self {
             port,
@@ -509,8 +776,14 @@ impl DaemonConfig {
             connectivity,
             limits,
             api_token,
+            api_scope: toml.api_scope.unwrap_or_default(),
             community,
             diff_risk,
+            judge,
+            fallback,
+            scheduler,
+            routing,
+            plugins,
         }
     }
 
@@ -518,6 +791,15 @@ impl DaemonConfig {
     pub fn provider_profile(&self, name: &str) -> Option<&ProviderProfile> {
         self.providers.get(name)
     }
+
+    /// Profiles that define direct HTTP providers, keyed by provider name.
+    pub fn http_providers(&self) -> std::collections::HashMap<String, ProviderProfile> {
+        self.providers
+            .iter()
+            .filter(|(_, p)| p.is_http())
+            .map(|(name, p)| (name.clone(), p.clone()))
+            .collect()
+    }
 }
 
 impl Default for DaemonConfig {
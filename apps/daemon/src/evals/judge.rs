@@ -1,41 +1,680 @@
-//! LLM-as-judge scoring stub.
+//! LLM-as-judge scoring.
 //!
-//! In production this would call an LLM API with a structured rubric to score
-//! agent output quality on a 0–10 scale.  The stub returns a fixed neutral score
-//! until an API integration is configured.
+//! A [`Judge`] sends a rubric and a piece of agent output to one or more
+//! [`JudgeBackend`]s — a provider CLI run in one-shot mode, a direct HTTP
+//! provider from `[provider.<name>]`, or an OpenAI-compatible endpoint —
+//! and parses a structured
+//! `{"score": 0-10, "reasoning": "..."}` verdict from each reply. With
+//! several judges the scores are averaged.
+//!
+//! Verdicts are cached in `eval_judgments`, keyed by a SHA-256 of the judge
+//! id, rubric and output, so re-running an eval over unchanged output costs
+//! nothing.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::config::{JudgeConfig, ProviderProfile};
+use crate::session::http::{request_body, HttpEndpoint, SseDecoder, StreamDecoder, Turn};
+
+/// Longest reasoning kept from a judge reply that had no structured verdict.
+const MAX_FREEFORM_REASONING: usize = 2000;
 
 // ─── Types ────────────────────────────────────────────────────────────────────
 
-/// Score produced by the LLM judge for a piece of agent output.
-#[derive(Debug)]
+/// Aggregate score produced by the LLM judge(s) for a piece of agent output.
+#[derive(Debug, Clone)]
 pub struct JudgeScore {
-    /// Score on a 0–10 scale (10 = perfect).
-    pub score: u8,
-    /// Reasoning provided by the judge.
+    /// Mean score across successful judges, on a 0–10 scale (10 = perfect).
+    pub score: f64,
+    /// Reasoning provided by the judge (one line per judge when averaged).
     pub reasoning: String,
+    /// Individual verdicts that went into `score`.
+    pub judgments: Vec<Judgment>,
+    /// Judges that failed to produce a verdict, as `"<judge>: <error>"`.
+    pub errors: Vec<String>,
+}
+
+/// One judge's verdict.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Judgment {
+    pub judge: String,
+    pub score: f64,
+    pub reasoning: String,
+    /// True if the verdict came from `eval_judgments` rather than the model.
+    pub cached: bool,
+}
+
+// ─── Backends ─────────────────────────────────────────────────────────────────
+
+/// Something that can answer a judging prompt.
+#[async_trait]
+pub trait JudgeBackend: Send + Sync {
+    /// Stable identifier; part of the cache key, so it must change whenever
+    /// the model or system prompt behind the backend does.
+    fn id(&self) -> String;
+
+    /// Send `prompt` and return the raw reply text.
+    async fn complete(&self, prompt: &str) -> Result<String>;
+}
+
+/// Runs a provider CLI in non-interactive mode (`claude -p`, `codex exec -`,
+/// `gemini`) and returns its stdout.  The prompt is written to stdin, so
+/// large outputs never hit the argv size limit.
+pub struct CliJudge {
+    provider: String,
+    program: String,
+    timeout: Duration,
+    model: Option<String>,
+    system_prompt_prefix: Option<String>,
+}
+
+impl CliJudge {
+    pub fn new(provider: &str) -> Result<Self> {
+        if !matches!(provider, "claude" | "codex" | "gemini") {
+            bail!(
+                "unsupported judge provider '{provider}' (expected claude, codex, gemini or http)"
+            );
+        }
+        Ok(Self {
+            provider: provider.to_string(),
+            program: provider.to_string(),
+            timeout: Duration::from_secs(JudgeConfig::default().timeout_secs),
+            model: None,
+            system_prompt_prefix: None,
+        })
+    }
+
+    /// Apply `[provider.<name>]`: its `timeout` replaces the judge timeout,
+    /// its `model` is passed as `--model` and its `system_prompt_prefix` is
+    /// prepended to every prompt.
+    pub fn with_profile(mut self, profile: &ProviderProfile) -> Self {
+        if let Some(secs) = profile.timeout {
+            self.timeout = Duration::from_secs(secs);
+        }
+        self.model = profile.model.clone();
+        self.system_prompt_prefix = profile.system_prompt_prefix.clone();
+        self
+    }
+
+    /// Run `program` instead of the provider's default binary.
+    pub fn with_program(mut self, program: impl Into<String>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// One-shot arguments; each CLI reads the prompt from stdin.
+    fn args(&self) -> Vec<&str> {
+        let mut args = match self.provider.as_str() {
+            "claude" => vec!["-p", "--output-format", "text"],
+            "codex" => vec!["exec"],
+            _ => vec![],
+        };
+        if let Some(model) = &self.model {
+            args.extend(["--model", model.as_str()]);
+        }
+        if self.provider == "codex" {
+            args.push("-");
+        }
+        args
+    }
+}
+
+#[async_trait]
+impl JudgeBackend for CliJudge {
+    fn id(&self) -> String {
+        backend_id(
+            &self.provider,
+            self.model.as_deref(),
+            self.system_prompt_prefix.as_deref(),
+        )
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let prompt = match &self.system_prompt_prefix {
+            Some(prefix) => format!("{prefix}\n\n{prompt}"),
+            None => prompt.to_string(),
+        };
+        let mut child = tokio::process::Command::new(&self.program)
+            .args(self.args())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to spawn `{}` — is it installed?", self.program))?;
+        let mut stdin = child.stdin.take().context("judge stdin unavailable")?;
+        let run = async move {
+            // Feed stdin while stdout drains so a chatty CLI cannot deadlock.
+            let write = async move {
+                stdin.write_all(prompt.as_bytes()).await?;
+                stdin.shutdown().await
+            };
+            let (written, output) = tokio::join!(write, child.wait_with_output());
+            let output = output?;
+            if let Err(e) = written {
+                // A CLI that exits early closes the pipe; its exit status says why.
+                debug!(err = %e, "judge stdin closed early");
+            }
+            anyhow::Ok(output)
+        };
+        let output = tokio::time::timeout(self.timeout, run)
+            .await
+            .with_context(|| {
+                format!("{} judge timed out after {:?}", self.provider, self.timeout)
+            })??;
+        if !output.status.success() {
+            bail!(
+                "{} judge exited with {}: {}",
+                self.provider,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Runs the prompt on a direct HTTP provider (`[provider.<name>] api = ...`),
+/// the same way sessions on that provider do.
+pub struct ProviderJudge {
+    provider: String,
+    endpoint: HttpEndpoint,
+    client: reqwest::Client,
+}
+
+impl ProviderJudge {
+    pub fn new(provider: &str, profile: &ProviderProfile) -> Result<Self> {
+        Ok(Self {
+            provider: provider.to_string(),
+            endpoint: HttpEndpoint::from_profile(provider, profile)?,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl JudgeBackend for ProviderJudge {
+    fn id(&self) -> String {
+        backend_id(
+            &self.provider,
+            Some(&self.endpoint.model),
+            self.endpoint.system_prompt_prefix.as_deref(),
+        )
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let system = self
+            .endpoint
+            .system_prompt_prefix
+            .as_deref()
+            .unwrap_or_default();
+        let body = request_body(&self.endpoint, system, &[Turn::User(prompt.into())], &[]);
+        let resp = self
+            .endpoint
+            .post(&self.client, &body)
+            .send()
+            .await
+            .with_context(|| format!("{} judge unreachable", self.provider))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            bail!(
+                "{} judge returned HTTP {status}: {}",
+                self.provider,
+                body.chars().take(500).collect::<String>()
+            );
+        }
+
+        let mut stream = resp.bytes_stream();
+        let mut sse = SseDecoder::default();
+        let mut decoder = StreamDecoder::new(self.endpoint.api);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("judge stream interrupted")?;
+            for data in sse.push(&chunk) {
+                decoder.apply(&data)?;
+            }
+        }
+        Ok(decoder.finish().text)
+    }
+}
+
+/// Posts the prompt to an OpenAI-compatible `/chat/completions` endpoint.
+pub struct HttpJudge {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl HttpJudge {
+    pub fn new(
+        endpoint: &str,
+        model: &str,
+        api_key: Option<String>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("build judge HTTP client")?;
+        Ok(Self {
+            endpoint: endpoint.to_string(),
+            model: model.to_string(),
+            api_key,
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl JudgeBackend for HttpJudge {
+    fn id(&self) -> String {
+        format!("http:{}#{}", self.endpoint, self.model)
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let mut req = self.client.post(&self.endpoint).json(&json!({
+            "model": self.model,
+            "temperature": 0,
+            "messages": [{ "role": "user", "content": prompt }],
+        }));
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req.send().await.context("judge endpoint unreachable")?;
+        let status = resp.status();
+        let body: Value = resp
+            .json()
+            .await
+            .context("judge endpoint returned non-JSON")?;
+        if !status.is_success() {
+            bail!("judge endpoint returned {status}: {body}");
+        }
+        body.pointer("/choices/0/message/content")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .context("judge endpoint response has no choices[0].message.content")
+    }
+}
+
+// ─── Judge ────────────────────────────────────────────────────────────────────
+
+/// A panel of one or more judges with an optional verdict cache.
+pub struct Judge {
+    backends: Vec<Arc<dyn JudgeBackend>>,
+    cache: Option<SqlitePool>,
+}
+
+impl Judge {
+    pub fn new(backends: Vec<Arc<dyn JudgeBackend>>) -> Self {
+        Self {
+            backends,
+            cache: None,
+        }
+    }
+
+    /// Build the panel described by `[judge]` in config.toml.  A judge named
+    /// after a `[provider.<name>]` profile runs under that profile.
+    pub fn from_config(
+        config: &JudgeConfig,
+        providers: &HashMap<String, ProviderProfile>,
+    ) -> Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let mut backends: Vec<Arc<dyn JudgeBackend>> = Vec::new();
+        for name in &config.judges {
+            if name == "http" {
+                let endpoint = config
+                    .endpoint
+                    .as_deref()
+                    .context("[judge] endpoint is required for the http judge")?;
+                let model = config.model.as_deref().unwrap_or("default");
+                let api_key = config
+                    .api_key_env
+                    .as_deref()
+                    .and_then(|var| std::env::var(var).ok())
+                    .filter(|k| !k.is_empty());
+                backends.push(Arc::new(HttpJudge::new(endpoint, model, api_key, timeout)?));
+            } else if let Some(profile) = providers.get(name).filter(|p| p.is_http()) {
+                backends.push(Arc::new(ProviderJudge::new(name, profile)?));
+            } else {
+                let mut judge = CliJudge::new(name)?.with_timeout(timeout);
+                if let Some(profile) = providers.get(name) {
+                    judge = judge.with_profile(profile);
+                }
+                backends.push(Arc::new(judge));
+            }
+        }
+        if backends.is_empty() {
+            bail!("[judge] judges must list at least one judge");
+        }
+        Ok(Self::new(backends))
+    }
+
+    /// Cache verdicts in `eval_judgments`.
+    pub fn with_cache(mut self, pool: SqlitePool) -> Self {
+        self.cache = Some(pool);
+        self
+    }
+
+    /// Score `output` against `rubric` with every judge and average the results.
+    ///
+    /// Fails only if no judge produced a verdict.
+    pub async fn score(&self, output: &str, rubric: &str) -> Result<JudgeScore> {
+        let verdicts = futures_util::future::join_all(
+            self.backends
+                .iter()
+                .map(|backend| self.judge_one(backend.as_ref(), output, rubric)),
+        )
+        .await;
+
+        let mut judgments = Vec::new();
+        let mut errors = Vec::new();
+        for (backend, verdict) in self.backends.iter().zip(verdicts) {
+            match verdict {
+                Ok(j) => judgments.push(j),
+                Err(e) => {
+                    warn!(judge = %backend.id(), "judge failed: {e:#}");
+                    errors.push(format!("{}: {e:#}", backend.id()));
+                }
+            }
+        }
+        if judgments.is_empty() {
+            bail!("no judge produced a verdict: {}", errors.join("; "));
+        }
+
+        let score = judgments.iter().map(|j| j.score).sum::<f64>() / judgments.len() as f64;
+        let reasoning = if judgments.len() == 1 {
+            judgments[0].reasoning.clone()
+        } else {
+            judgments
+                .iter()
+                .map(|j| format!("[{} {:.1}] {}", j.judge, j.score, j.reasoning))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Ok(JudgeScore {
+            score,
+            reasoning,
+            judgments,
+            errors,
+        })
+    }
+
+    async fn judge_one(
+        &self,
+        backend: &dyn JudgeBackend,
+        output: &str,
+        rubric: &str,
+    ) -> Result<Judgment> {
+        let judge = backend.id();
+        let key = cache_key(&judge, rubric, output);
+
+        if let Some(pool) = &self.cache {
+            let hit: Option<(f64, String)> =
+                sqlx::query_as("SELECT score, reasoning FROM eval_judgments WHERE cache_key = ?")
+                    .bind(&key)
+                    .fetch_optional(pool)
+                    .await?;
+            if let Some((score, reasoning)) = hit {
+                debug!(judge = %judge, "judge cache hit");
+                return Ok(Judgment {
+                    judge,
+                    score,
+                    reasoning,
+                    cached: true,
+                });
+            }
+        }
+
+        let reply = backend.complete(&build_prompt(output, rubric)).await?;
+        let (score, reasoning) =
+            parse_verdict(&reply).with_context(|| format!("unparseable {judge} verdict"))?;
+
+        if let Some(pool) = &self.cache {
+            sqlx::query(
+                "INSERT OR REPLACE INTO eval_judgments (cache_key, judge, score, reasoning)
+                 VALUES (?, ?, ?, ?)",
+            )
+            .bind(&key)
+            .bind(&judge)
+            .bind(score)
+            .bind(&reasoning)
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(Judgment {
+            judge,
+            score,
+            reasoning,
+            cached: false,
+        })
+    }
+}
+
+// ─── Prompt ──────────────────────────────────────────────────────────────────
+
+/// The prompt sent to every judge.
+pub fn build_prompt(output: &str, rubric: &str) -> String {
+    format!(
+        "You are grading the output of an AI coding agent.\n\n\
+         ## Rubric\n{rubric}\n\n\
+         ## Output to grade\n<output>\n{output}\n</output>\n\n\
+         Score the output from 0 (fails the rubric entirely) to 10 (fully meets it).\n\
+         Reply with only a JSON object: {{\"score\": <0-10>, \"reasoning\": \"<one paragraph>\"}}"
+    )
+}
+
+/// `provider[#model][+prompt:<hash>]`: the hash covers the system prompt
+/// prefix so editing it invalidates cached verdicts.
+fn backend_id(provider: &str, model: Option<&str>, prefix: Option<&str>) -> String {
+    let mut id = provider.to_string();
+    if let Some(model) = model {
+        id.push('#');
+        id.push_str(model);
+    }
+    if let Some(prefix) = prefix {
+        let digest = hex::encode(Sha256::digest(prefix.as_bytes()));
+        id.push_str("+prompt:");
+        id.push_str(&digest[..12]);
+    }
+    id
 }
 
-// ─── Scoring ─────────────────────────────────────────────────────────────────
+/// SHA-256 over judge id, rubric and output (NUL-separated).
+pub fn cache_key(judge: &str, rubric: &str, output: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [judge, rubric, output] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hex::encode(hasher.finalize())
+}
 
-/// Score agent output against a rubric using an LLM judge.
+static SCORE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bscore\b\W{0,3}(\d+(?:\.\d+)?)").expect("valid score regex"));
+
+/// Extract `(score, reasoning)` from a judge reply.
 ///
-/// Currently a stub — returns `score: 5` with a note that the judge is not
-/// yet configured.  Replace the body with an actual LLM API call when ready.
-pub async fn score_output(_output: &str, _rubric: &str) -> anyhow::Result<JudgeScore> {
-    Ok(JudgeScore {
-        score: 5,
-        reasoning: "LLM judge not yet configured — returning neutral score.".to_string(),
-    })
+/// Prefers the first JSON object with a `score` field (the reply may wrap it
+/// in prose or a code fence); falls back to a `Score: N` line, keeping the
+/// whole reply as reasoning. Scores outside 0–10 are rejected.
+pub fn parse_verdict(reply: &str) -> Result<(f64, String)> {
+    for (start, _) in reply.match_indices('{') {
+        let mut stream = serde_json::Deserializer::from_str(&reply[start..]).into_iter::<Value>();
+        let Some(Ok(Value::Object(obj))) = stream.next() else {
+            continue;
+        };
+        let Some(raw) = obj.get("score") else {
+            continue;
+        };
+        let score = match raw {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .with_context(|| format!("score is not a number: {raw}"))?;
+        let reasoning = obj
+            .get("reasoning")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        return Ok((check_range(score)?, reasoning));
+    }
+
+    if let Some(caps) = SCORE_RE.captures(reply) {
+        let score: f64 = caps[1].parse()?;
+        let reasoning: String = reply.trim().chars().take(MAX_FREEFORM_REASONING).collect();
+        return Ok((check_range(score)?, reasoning));
+    }
+    bail!("judge reply contained no score")
+}
+
+fn check_range(score: f64) -> Result<f64> {
+    if !(0.0..=10.0).contains(&score) {
+        bail!("score {score} is outside 0–10");
+    }
+    Ok(score)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn stub_returns_neutral_score() {
-        let result = score_output("some output", "quality rubric").await.unwrap();
-        assert_eq!(result.score, 5);
-        assert!(!result.reasoning.is_empty());
+    #[test]
+    fn parses_json_verdict_inside_prose() {
+        let reply =
+            "Here is my grade:\n```json\n{\"score\": 7, \"reasoning\": \"Mostly right.\"}\n```";
+        assert_eq!(
+            parse_verdict(reply).unwrap(),
+            (7.0, "Mostly right.".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_string_score_and_freeform_fallback() {
+        let (score, _) = parse_verdict(r#"{"score": "8.5", "reasoning": "ok"}"#).unwrap();
+        assert_eq!(score, 8.5);
+
+        let (score, reasoning) = parse_verdict("Score: 6/10 because tests are missing").unwrap();
+        assert_eq!(score, 6.0);
+        assert!(reasoning.contains("tests are missing"));
+    }
+
+    #[test]
+    fn rejects_missing_or_out_of_range_scores() {
+        assert!(parse_verdict("looks fine to me").is_err());
+        assert!(parse_verdict(r#"{"score": 42}"#).is_err());
+        assert!(parse_verdict(r#"{"score": null}"#).is_err());
+    }
+
+    #[test]
+    fn cache_key_depends_on_every_part() {
+        let base = cache_key("claude", "rubric", "output");
+        assert_eq!(base, cache_key("claude", "rubric", "output"));
+        assert_ne!(base, cache_key("codex", "rubric", "output"));
+        assert_ne!(base, cache_key("claude", "rubric2", "output"));
+        assert_ne!(base, cache_key("claude", "rubricoutput", ""));
+    }
+
+    #[test]
+    fn judge_id_covers_model_and_prompt_prefix() {
+        let cli = |model: Option<&str>, prefix: Option<&str>| {
+            let profile = ProviderProfile {
+                model: model.map(Into::into),
+                system_prompt_prefix: prefix.map(Into::into),
+                ..Default::default()
+            };
+            CliJudge::new("claude").unwrap().with_profile(&profile)
+        };
+        assert_eq!(cli(None, None).id(), "claude");
+        assert_eq!(cli(Some("opus"), None).id(), "claude#opus");
+        assert_eq!(
+            cli(Some("opus"), None).args(),
+            ["-p", "--output-format", "text", "--model", "opus"]
+        );
+
+        // Changing the prefix changes the id, so cached verdicts miss.
+        let strict = cli(None, Some("Be strict.")).id();
+        let lenient = cli(None, Some("Be lenient.")).id();
+        assert_ne!(strict, lenient);
+        assert_ne!(
+            cache_key(&strict, "rubric", "output"),
+            cache_key(&lenient, "rubric", "output")
+        );
+
+        let http = |prefix: &str| ProviderProfile {
+            api: Some(crate::config::HttpApi::OpenAi),
+            model: Some("local-model".into()),
+            base_url: Some("http://127.0.0.1:9/v1".into()),
+            system_prompt_prefix: Some(prefix.into()),
+            ..Default::default()
+        };
+        let a = ProviderJudge::new("local", &http("Be strict.")).unwrap();
+        let b = ProviderJudge::new("local", &http("Be lenient.")).unwrap();
+        assert!(a.id().starts_with("local#local-model+prompt:"));
+        assert_ne!(a.id(), b.id());
+    }
+
+    #[test]
+    fn config_rejects_unknown_judges() {
+        let cfg = JudgeConfig {
+            judges: vec!["mystery".into()],
+            ..Default::default()
+        };
+        assert!(Judge::from_config(&cfg, &HashMap::new()).is_err());
+
+        let http_without_endpoint = JudgeConfig {
+            judges: vec!["http".into()],
+            ..Default::default()
+        };
+        assert!(Judge::from_config(&http_without_endpoint, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn provider_profiles_configure_judges() {
+        let profile = ProviderProfile {
+            timeout: Some(7),
+            system_prompt_prefix: Some("Be strict.".into()),
+            ..Default::default()
+        };
+        let judge = CliJudge::new("claude").unwrap().with_profile(&profile);
+        assert_eq!(judge.timeout, Duration::from_secs(7));
+        assert_eq!(judge.system_prompt_prefix.as_deref(), Some("Be strict."));
+
+        // A profile with `api` turns the judge into a direct HTTP provider,
+        // even for a name the CLI judge does not support.
+        let http = ProviderProfile {
+            api: Some(crate::config::HttpApi::OpenAi),
+            model: Some("local-model".into()),
+            base_url: Some("http://127.0.0.1:9/v1".into()),
+            ..Default::default()
+        };
+        let cfg = JudgeConfig {
+            judges: vec!["local".into()],
+            ..Default::default()
+        };
+        let providers = HashMap::from([("local".to_string(), http)]);
+        assert!(Judge::from_config(&cfg, &providers).is_ok());
     }
 }
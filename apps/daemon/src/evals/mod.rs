@@ -4,7 +4,7 @@
 //!  - `versioning`  — SHA-256 hashing of policy files
 //!  - `detector`    — change detection and background watching
 //!  - `runner`      — fixture-based eval runner
-//!  - `judge`       — LLM-as-judge scoring stub
+//!  - `judge`       — LLM-as-judge scoring (provider CLI or HTTP judges)
 //!  - `budget`      — rule size budget checks
 //!  - `rollback`    — policy snapshot/restore
 //!  - `report`      — markdown regression report generation
@@ -2,22 +2,26 @@
 //!
 //! Loads `.claw/evals/*.yaml` files and runs each `EvalCase` against
 //! a pattern match (no live provider call — checks session output patterns).
+//! Cases with a `rubric` are instead scored by the LLM judge configured in
+//! `[judge]` (see `evals::judge`).
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::evals::judge::Judge;
 use crate::AppContext;
 
 // ─── Eval case types ───────────────────────────────────────────────────────
 
 /// A single eval case loaded from a YAML file.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct EvalCase {
     /// Human-readable name shown in the results table.
     pub name: String,
     /// The prompt / input to the session.
     pub prompt: String,
     /// Pattern that must appear in the session output to pass.
+    #[serde(default)]
     pub expected_pattern: String,
     /// Pass condition: `"contains"`, `"regex"`, `"not_empty"`.
     #[serde(default = "default_pass_condition")]
@@ -25,8 +29,21 @@ pub struct EvalCase {
     /// Optional provider hint (ignored in pattern-match mode).
     #[serde(default)]
     pub provider: Option<String>,
+    /// Grading rubric; when set the case is scored by the LLM judge instead
+    /// of `pass_condition`.
+    #[serde(default)]
+    pub rubric: Option<String>,
+    /// Minimum judge score (0–10) to pass. Default: 7.
+    #[serde(default)]
+    pub min_score: Option<f64>,
+    /// Recorded agent output to evaluate instead of the synthetic output.
+    #[serde(default)]
+    pub output: Option<String>,
 }
 
+/// Default pass mark for judge-scored cases.
+const DEFAULT_MIN_JUDGE_SCORE: f64 = 7.0;
+
 fn default_pass_condition() -> String {
     "contains".to_string()
 }
@@ -36,7 +53,7 @@ fn default_pass_condition() -> String {
 pub struct EvalResult {
     pub name: String,
     pub passed: bool,
-    /// 0.0 (fail) or 1.0 (pass).
+    /// 0.0 (fail) or 1.0 (pass); judge score / 10 for rubric cases.
     pub score: f64,
     pub reason: String,
 }
@@ -59,70 +76,70 @@ fn builtin_cases() -> Vec<EvalCase> {
             prompt: "Read the file README.md".into(),
             expected_pattern: "read".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "file-write capability".into(),
             prompt: "Write 'hello' to /tmp/eval_test.txt".into(),
             expected_pattern: "written".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "git-diff capability".into(),
             prompt: "Show git diff".into(),
             expected_pattern: "diff".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "task-create capability".into(),
             prompt: "Create a task titled 'Eval test task'".into(),
             expected_pattern: "task".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "session-resume capability".into(),
             prompt: "Resume the last session".into(),
             expected_pattern: "session".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "worktree-create capability".into(),
             prompt: "Create a worktree for the current task".into(),
             expected_pattern: "worktree".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "pack-install capability".into(),
             prompt: "Install the lint-guard pack".into(),
             expected_pattern: "pack".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "memory-inject capability".into(),
             prompt: "Remember that the project uses Rust".into(),
             expected_pattern: "memory".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "approval-gate capability".into(),
             prompt: "Request approval before deleting files".into(),
             expected_pattern: "approval".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
         EvalCase {
             name: "test-run capability".into(),
             prompt: "Run the project tests".into(),
             expected_pattern: "test".into(),
             pass_condition: "not_empty".into(),
-            provider: None,
+            ..Default::default()
         },
     ]
 }
@@ -137,7 +154,7 @@ fn builtin_cases() -> Vec<EvalCase> {
 pub async fn run_evals(
     repo_path: &str,
     eval_file: &str,
-    _ctx: &AppContext,
+    ctx: &AppContext,
 ) -> Result<Vec<EvalResult>> {
     let cases = if eval_file.starts_with("builtin") {
         builtin_cases()
@@ -149,37 +166,75 @@ pub async fn run_evals(
         load_eval_file(&path)?
     };
 
-    let results = cases
-        .into_iter()
-        .map(|case| {
-            // Pattern-match mode: check that the prompt contains the expected pattern.
-            // This tests the eval infrastructure; real runs would use live sessions.
-            let synthetic_output = case.prompt.clone();
-            let passed = match case.pass_condition.as_str() {
-                "contains" => synthetic_output
-                    .to_lowercase()
-                    .contains(&case.expected_pattern.to_lowercase()),
-                "not_empty" => !synthetic_output.is_empty(),
-                "regex" => {
-                    // Simple contains fallback (avoid regex dep).
-                    synthetic_output
-                        .to_lowercase()
-                        .contains(&case.expected_pattern.to_lowercase())
+    let mut judge = None;
+    let mut results = Vec::with_capacity(cases.len());
+    for case in cases {
+        // Pattern-match mode: check that the prompt contains the expected pattern.
+        // This tests the eval infrastructure; real runs would use live sessions.
+        let output = case.output.clone().unwrap_or_else(|| case.prompt.clone());
+        let result = match &case.rubric {
+            Some(rubric) => {
+                if judge.is_none() {
+                    judge = Some(
+                        Judge::from_config(&ctx.config.judge, &ctx.config.providers)?
+                            .with_cache(ctx.storage.clone_pool()),
+                    );
                 }
-                _ => false,
-            };
-            EvalResult {
-                name: case.name,
-                passed,
-                score: if passed { 1.0 } else { 0.0 },
-                reason: if passed {
-                    "pattern matched".to_string()
-                } else {
-                    format!("expected '{}' in output", case.expected_pattern)
-                },
+                let judge = judge.as_ref().expect("judge initialised above");
+                judge_case(judge, &case, &output, rubric).await
             }
-        })
-        .collect();
+            None => pattern_case(&case, &output),
+        };
+        results.push(result);
+    }
 
     Ok(results)
 }
+
+/// Score one case with the LLM judge.
+pub async fn judge_case(judge: &Judge, case: &EvalCase, output: &str, rubric: &str) -> EvalResult {
+    let min_score = case.min_score.unwrap_or(DEFAULT_MIN_JUDGE_SCORE);
+    match judge.score(output, rubric).await {
+        Ok(verdict) => EvalResult {
+            name: case.name.clone(),
+            passed: verdict.score >= min_score,
+            score: verdict.score / 10.0,
+            reason: format!(
+                "judge score {:.1}/10 (pass ≥ {min_score}): {}",
+                verdict.score, verdict.reasoning
+            ),
+        },
+        Err(e) => EvalResult {
+            name: case.name.clone(),
+            passed: false,
+            score: 0.0,
+            reason: format!("judge error: {e:#}"),
+        },
+    }
+}
+
+fn pattern_case(case: &EvalCase, output: &str) -> EvalResult {
+    let passed = match case.pass_condition.as_str() {
+        "contains" => output
+            .to_lowercase()
+            .contains(&case.expected_pattern.to_lowercase()),
+        "not_empty" => !output.is_empty(),
+        "regex" => {
+            // Simple contains fallback (avoid regex dep).
+            output
+                .to_lowercase()
+                .contains(&case.expected_pattern.to_lowercase())
+        }
+        _ => false,
+    };
+    EvalResult {
+        name: case.name.clone(),
+        passed,
+        score: if passed { 1.0 } else { 0.0 },
+        reason: if passed {
+            "pattern matched".to_string()
+        } else {
+            format!("expected '{}' in output", case.expected_pattern)
+        },
+    }
+}
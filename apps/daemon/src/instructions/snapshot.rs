@@ -59,6 +59,16 @@ pub async fn check_snapshot(
     Ok((false, diff))
 }
 
+/// The compiled instructions saved in a golden file, without its header.
+/// `None` when no snapshot has been written.
+pub async fn read_snapshot(snapshot_path: &Path) -> Result<Option<String>> {
+    match tokio::fs::read_to_string(snapshot_path).await {
+        Ok(golden) => Ok(Some(strip_header(&golden).to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 fn content_hash(content: &str) -> String {
     let hash = Sha256::digest(content.as_bytes());
     hex::encode(&hash[..16])
@@ -121,4 +131,16 @@ mod tests {
             "<!-- instruction-snapshot\nhash: abc123\ngenerated: 2026-01-01\n-->\n# Content";
         assert_eq!(extract_hash(snapshot), Some("abc123".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_read_snapshot_strips_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".instruction-snapshot.md");
+        assert_eq!(read_snapshot(&path).await.unwrap(), None);
+        write_snapshot("# Rules\nUse pnpm.", &path).await.unwrap();
+        assert_eq!(
+            read_snapshot(&path).await.unwrap().as_deref(),
+            Some("# Rules\nUse pnpm.")
+        );
+    }
 }
@@ -1,11 +1,96 @@
 use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tracing::warn;
 
+/// Events kept for `Last-Event-ID` / `last_event_id` replay.
+pub const DEFAULT_REPLAY_CAPACITY: usize = 2048;
+
+/// The low bits of an event id count events within one daemon run; the bits
+/// above hold the run's epoch, which the daemon bumps in storage on every
+/// start (`Storage::next_event_epoch`).  Ids therefore keep increasing across
+/// restarts, and an id from an earlier run is never mistaken for one from
+/// this run.  Ids stay below 2^53, which JavaScript clients read exactly.
+const SEQ_BITS: u32 = 32;
+const EPOCH_BITS: u32 = 21;
+
+/// A broadcast event with its daemon-wide sequence id.  Ids increase by one
+/// per event, starting above every id of the previous daemon run.
+#[derive(Debug)]
+pub struct SequencedEvent {
+    pub id: u64,
+    pub method: String,
+    pub params: Value,
+    /// The notification exactly as plain subscribers receive it.
+    pub json: String,
+}
+
+impl SequencedEvent {
+    /// The notification with its `event_id`, for clients that resume.
+    pub fn json_with_id(&self) -> String {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": self.method,
+            "params": self.params,
+            "event_id": self.id,
+        })
+        .to_string()
+    }
+}
+
+/// Bounded buffer of the most recent events.
+struct ReplayBuffer {
+    /// Id "before the first event" of this run (its epoch, sequence 0).
+    base: u64,
+    next_id: u64,
+    capacity: usize,
+    events: VecDeque<Arc<SequencedEvent>>,
+}
+
+impl ReplayBuffer {
+    fn push(&mut self, event: Arc<SequencedEvent>) {
+        self.next_id = event.id + 1;
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Buffered events after `after`, and whether some were already evicted
+    /// (or `after` is not an id from this run).  `0` means from the start
+    /// of this run.
+    fn since(&self, after: u64) -> (Vec<Arc<SequencedEvent>>, bool) {
+        let after = if after == 0 { self.base } else { after };
+        if after >> SEQ_BITS != self.base >> SEQ_BITS {
+            // An id from another daemon run: everything buffered is new to
+            // the client, but its state predates the restart.
+            return (self.events.iter().cloned().collect(), true);
+        }
+        if after >= self.next_id {
+            return (Vec::new(), true);
+        }
+        let oldest = self.events.front().map_or(self.next_id, |e| e.id);
+        let events = self
+            .events
+            .iter()
+            .filter(|e| e.id > after)
+            .cloned()
+            .collect();
+        (events, after + 1 < oldest)
+    }
+}
+
 /// Broadcasts JSON-RPC notification strings to all connected WebSocket clients.
+///
+/// Every event also gets a sequence id and is kept in a bounded replay
+/// buffer, so SSE and WebSocket clients can resume after a disconnect
+/// (`subscribe_from`).
 #[derive(Clone)]
 pub struct EventBroadcaster {
     tx: broadcast::Sender<String>,
+    seq_tx: broadcast::Sender<Arc<SequencedEvent>>,
+    replay: Arc<Mutex<ReplayBuffer>>,
 }
 
 impl Default for EventBroadcaster {
@@ -19,7 +104,39 @@ impl EventBroadcaster {
         // 4096-message channel gives headroom for bursts of tool-call events
         // across many concurrent sessions before lagging receivers are dropped.
         let (tx, _) = broadcast::channel(4096);
-        Self { tx }
+        let (seq_tx, _) = broadcast::channel(4096);
+        let base = 1 << SEQ_BITS;
+        Self {
+            tx,
+            seq_tx,
+            replay: Arc::new(Mutex::new(ReplayBuffer {
+                base,
+                next_id: base + 1,
+                capacity: DEFAULT_REPLAY_CAPACITY,
+                events: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Number ids from `epoch` (clamped to 1..2^21) instead of epoch 1.  Call
+    /// before the first broadcast, with a value that grows on every start.
+    pub fn with_epoch(self, epoch: u64) -> Self {
+        {
+            let mut replay = self.lock_replay();
+            replay.base = epoch.clamp(1, (1 << EPOCH_BITS) - 1) << SEQ_BITS;
+            replay.next_id = replay.base + 1;
+        }
+        self
+    }
+
+    /// Keep `capacity` events for replay instead of `DEFAULT_REPLAY_CAPACITY`.
+    pub fn with_replay_capacity(self, capacity: usize) -> Self {
+        self.lock_replay().capacity = capacity.max(1);
+        self
+    }
+
+    fn lock_replay(&self) -> std::sync::MutexGuard<'_, ReplayBuffer> {
+        self.replay.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Send a JSON-RPC notification to all connected clients.
@@ -31,7 +148,18 @@ impl EventBroadcaster {
         });
         match serde_json::to_string(&notification) {
             Ok(json) => {
+                // Ids are assigned, buffered and sent under the lock so a
+                // resuming subscriber sees each event exactly once.
+                let mut replay = self.lock_replay();
+                let event = Arc::new(SequencedEvent {
+                    id: replay.next_id,
+                    method: method.to_string(),
+                    params: notification["params"].clone(),
+                    json: json.clone(),
+                });
+                replay.push(event.clone());
                 // Ignore send errors — no subscribers is fine
+                let _ = self.seq_tx.send(event);
                 let _ = self.tx.send(json);
             }
             Err(e) => {
@@ -44,4 +172,241 @@ impl EventBroadcaster {
     pub fn subscribe(&self) -> broadcast::Receiver<String> {
         self.tx.subscribe()
     }
+
+    /// Id of the most recent event, or this run's starting id before the
+    /// first.
+    pub fn last_event_id(&self) -> u64 {
+        self.lock_replay().next_id - 1
+    }
+
+    /// Subscribe to sequenced events, first replaying buffered events after
+    /// `after` when resuming.
+    pub fn subscribe_from(&self, after: Option<u64>) -> EventCursor {
+        let replay = self.lock_replay();
+        let rx = self.seq_tx.subscribe();
+        let latest = replay.next_id - 1;
+        let (pending, gap) = match after {
+            Some(after) => replay.since(after),
+            None => (Vec::new(), false),
+        };
+        // After a gap the client starts over from whatever is buffered.
+        let last_id = match (gap, pending.first()) {
+            (true, Some(first)) => first.id - 1,
+            _ => after.map_or(latest, |a| a.min(latest)),
+        };
+        EventCursor {
+            rx,
+            pending: pending.into(),
+            last_id,
+            gap: gap.then(|| after.unwrap_or(0)),
+            replay: Arc::clone(&self.replay),
+        }
+    }
+
+    #[cfg(test)]
+    fn replay_since(&self, after: u64) -> (Vec<Arc<SequencedEvent>>, bool) {
+        self.lock_replay().since(after)
+    }
+
+    /// The id of this run's `seq`th event.
+    #[cfg(test)]
+    fn id_at(&self, seq: u64) -> u64 {
+        self.lock_replay().base + seq
+    }
+}
+
+/// What an `EventCursor` yields.
+#[derive(Debug, Clone)]
+pub enum CursorItem {
+    Event(Arc<SequencedEvent>),
+    /// Events after `after` were evicted from the replay buffer before this
+    /// subscriber could receive them; the client should reload its state.
+    Gap {
+        after: u64,
+    },
+}
+
+/// A subscriber's position in the event sequence.
+///
+/// Yields buffered events first, then live ones, each exactly once and in
+/// id order.  A receiver that falls behind the channel catches up from the
+/// replay buffer, and reports a `Gap` only if events are gone from there too.
+pub struct EventCursor {
+    rx: broadcast::Receiver<Arc<SequencedEvent>>,
+    pending: VecDeque<Arc<SequencedEvent>>,
+    last_id: u64,
+    gap: Option<u64>,
+    replay: Arc<Mutex<ReplayBuffer>>,
+}
+
+impl EventCursor {
+    /// Id of the last event yielded (or the starting point).
+    pub fn last_id(&self) -> u64 {
+        self.last_id
+    }
+
+    /// Next item, or `None` once the broadcaster is gone.  Cancel-safe.
+    pub async fn next(&mut self) -> Option<CursorItem> {
+        if let Some(after) = self.gap.take() {
+            return Some(CursorItem::Gap { after });
+        }
+        loop {
+            let event = match self.pending.pop_front() {
+                Some(event) => event,
+                None => match self.rx.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let (events, gap) = self
+                            .replay
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .since(self.last_id);
+                        self.pending.extend(events);
+                        if gap {
+                            let after = self.last_id;
+                            if let Some(first) = self.pending.front() {
+                                self.last_id = first.id - 1;
+                            }
+                            return Some(CursorItem::Gap { after });
+                        }
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            };
+            if event.id > self.last_id {
+                self.last_id = event.id;
+                return Some(CursorItem::Event(event));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(items: &[CursorItem]) -> Vec<i64> {
+        items
+            .iter()
+            .map(|i| match i {
+                CursorItem::Event(e) => (e.id & ((1 << SEQ_BITS) - 1)) as i64,
+                CursorItem::Gap { .. } => -1,
+            })
+            .collect()
+    }
+
+    async fn take(cursor: &mut EventCursor, n: usize) -> Vec<CursorItem> {
+        let mut out = Vec::new();
+        for _ in 0..n {
+            out.push(cursor.next().await.unwrap());
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_last_seen_id() {
+        let b = EventBroadcaster::new();
+        for i in 0..5 {
+            b.broadcast("test.event", json!({ "n": i }));
+        }
+        assert_eq!(b.last_event_id(), b.id_at(5));
+
+        let mut cursor = b.subscribe_from(Some(b.id_at(3)));
+        b.broadcast("test.event", json!({ "n": 5 }));
+        assert_eq!(ids(&take(&mut cursor, 3).await), [4, 5, 6]);
+
+        // A fresh subscriber only sees new events.
+        let mut live = b.subscribe_from(None);
+        b.broadcast("test.event", json!({}));
+        assert_eq!(ids(&take(&mut live, 1).await), [7]);
+    }
+
+    #[tokio::test]
+    async fn reports_a_gap_when_events_were_evicted() {
+        let b = EventBroadcaster::new().with_replay_capacity(3);
+        for _ in 0..6 {
+            b.broadcast("test.event", json!({}));
+        }
+        let mut cursor = b.subscribe_from(Some(b.id_at(1)));
+        assert_eq!(ids(&take(&mut cursor, 4).await), [-1, 4, 5, 6]);
+
+        // An id this run has not reached is a gap with nothing to replay.
+        let mut cursor = b.subscribe_from(Some(b.id_at(99)));
+        b.broadcast("test.event", json!({}));
+        assert_eq!(ids(&take(&mut cursor, 2).await), [-1, 7]);
+    }
+
+    #[tokio::test]
+    async fn ids_from_a_previous_run_are_a_gap() {
+        let current = EventBroadcaster::new().with_epoch(7);
+        for _ in 0..3 {
+            current.broadcast("test.event", json!({}));
+        }
+        assert_eq!(current.id_at(0) >> SEQ_BITS, 7);
+
+        // Seq 2 of another run's epoch must not resume after this run's
+        // seq 2: the client is told to reload, then gets everything buffered.
+        let stale = current.id_at(2) - (1 << SEQ_BITS);
+        let mut cursor = current.subscribe_from(Some(stale));
+        current.broadcast("test.event", json!({}));
+        assert_eq!(ids(&take(&mut cursor, 5).await), [-1, 1, 2, 3, 4]);
+
+        // `0` still means "from the start of this run".
+        let (events, gap) = current.replay_since(0);
+        assert_eq!((events.len(), gap), (4, false));
+    }
+
+    #[tokio::test]
+    async fn ids_keep_increasing_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = crate::storage::Storage::new(dir.path()).await.unwrap();
+
+        // The first run sends many more events than the second.
+        let first = EventBroadcaster::new().with_epoch(storage.next_event_epoch().await.unwrap());
+        for _ in 0..1000 {
+            first.broadcast("test.event", json!({}));
+        }
+        let last_before_restart = first.last_event_id();
+        drop(first);
+
+        let second = EventBroadcaster::new().with_epoch(storage.next_event_epoch().await.unwrap());
+        assert!(second.last_event_id() > last_before_restart);
+        second.broadcast("test.event", json!({}));
+        let (events, _) = second.replay_since(0);
+        assert!(events[0].id > last_before_restart);
+
+        // The old id is recognised as another run's, not as a future one.
+        let (events, gap) = second.replay_since(last_before_restart);
+        assert_eq!((events.len(), gap), (1, true));
+    }
+
+    #[tokio::test]
+    async fn lagging_cursors_catch_up_from_the_buffer() {
+        let b = EventBroadcaster::new().with_replay_capacity(8192);
+        let mut cursor = b.subscribe_from(None);
+        for _ in 0..4100 {
+            b.broadcast("test.event", json!({}));
+        }
+        let items = take(&mut cursor, 4100).await;
+        let got = ids(&items);
+        assert!(!got.contains(&-1), "the buffer still had every event");
+        assert!(got.windows(2).all(|w| w[1] == w[0] + 1));
+        assert_eq!(got.last(), Some(&4100));
+    }
+
+    #[test]
+    fn ids_ride_along_for_resuming_clients() {
+        let b = EventBroadcaster::new();
+        let mut rx = b.subscribe();
+        b.broadcast("session.statusChanged", json!({ "sessionId": "s1" }));
+        let plain: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert!(plain.get("event_id").is_none());
+
+        let (events, _) = b.replay_since(0);
+        let with_id: Value = serde_json::from_str(&events[0].json_with_id()).unwrap();
+        assert_eq!(with_id["event_id"], b.id_at(1));
+        assert_eq!(with_id["params"]["sessionId"], "s1");
+    }
 }
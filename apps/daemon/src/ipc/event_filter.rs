@@ -0,0 +1,143 @@
+//! Filters for event subscribers — `GET /api/v1/events` and the WebSocket's
+//! `events.subscribe`.
+//!
+//! An event passes when it matches every filter that is set:
+//!
+//! - `types` — method globs such as `session.*` or `*.statusChanged`;
+//! - `session_ids` — the event's `sessionId` / `session_id`;
+//! - `repo_path` — the repo the event names, directly or through its session
+//!   or task.  Events that name no repo do not pass.
+//!
+//! A subscriber that authenticated with a scoped API token is also held to
+//! the token: its method globs apply to event methods, and a repo-limited
+//! token only sees events that name one of its repos.
+
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::event::SequencedEvent;
+use crate::api_tokens::{
+    glob_matches, repo_within, session_repo, task_repo, ApiToken, REPO_KEYS, SESSION_KEYS,
+    TASK_KEYS,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EventFilter {
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub session_ids: Vec<String>,
+    pub repo_path: Option<String>,
+    /// Let events that name no session through the session filter.
+    #[serde(skip)]
+    pub include_global: bool,
+    /// Scoped token the subscriber authenticated with, if any.
+    #[serde(skip)]
+    pub token: Option<Arc<ApiToken>>,
+    /// Repo per `s:<session>` / `t:<task>`, so each is looked up once.
+    #[serde(skip)]
+    repos: HashMap<String, Option<String>>,
+}
+
+fn str_param<'a>(params: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|k| params.get(*k).and_then(Value::as_str))
+}
+
+/// Split a comma-separated query value, dropping empty items.
+fn split_list(list: Option<&str>) -> Vec<String> {
+    list.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl EventFilter {
+    /// Build a filter from comma-separated query values.
+    pub fn from_query(types: Option<&str>, session_ids: Option<&str>, repo: Option<&str>) -> Self {
+        Self {
+            types: split_list(types),
+            session_ids: split_list(session_ids),
+            repo_path: repo.filter(|r| !r.is_empty()).map(str::to_string),
+            ..Self::default()
+        }
+    }
+
+    /// Only events for `session_id`, plus events that name no session.
+    pub fn for_session(session_id: String) -> Self {
+        Self {
+            session_ids: vec![session_id],
+            include_global: true,
+            ..Self::default()
+        }
+    }
+
+    /// Every event the scoped `token` may see.
+    pub fn for_token(token: Arc<ApiToken>) -> Self {
+        Self {
+            token: Some(token),
+            ..Self::default()
+        }
+    }
+
+    pub async fn matches(&mut self, event: &SequencedEvent, pool: &SqlitePool) -> bool {
+        if let Some(token) = self.token.clone() {
+            if !token.allows_event(&event.method) {
+                return false;
+            }
+            if !token.repos.is_empty() {
+                let repo = self.repo_of(&event.params, pool).await;
+                if !repo.is_some_and(|r| token.allows_repo(&r)) {
+                    return false;
+                }
+            }
+        }
+        if !self.types.is_empty() && !self.types.iter().any(|g| glob_matches(g, &event.method)) {
+            return false;
+        }
+        let session = str_param(&event.params, SESSION_KEYS);
+        if !self.session_ids.is_empty() {
+            let passes = match session {
+                Some(id) => self.session_ids.iter().any(|s| s == id),
+                None => self.include_global,
+            };
+            if !passes {
+                return false;
+            }
+        }
+        let Some(root) = self.repo_path.clone() else {
+            return true;
+        };
+        self.repo_of(&event.params, pool)
+            .await
+            .is_some_and(|repo| repo_within(&repo, &root))
+    }
+
+    async fn repo_of(&mut self, params: &Value, pool: &SqlitePool) -> Option<String> {
+        if let Some(repo) = str_param(params, REPO_KEYS) {
+            return Some(repo.to_string());
+        }
+        if let Some(id) = str_param(params, SESSION_KEYS) {
+            let key = format!("s:{id}");
+            if !self.repos.contains_key(&key) {
+                let repo = session_repo(pool, id).await;
+                self.repos.insert(key.clone(), repo);
+            }
+            return self.repos[&key].clone();
+        }
+        if let Some(id) = str_param(params, TASK_KEYS) {
+            let key = format!("t:{id}");
+            if !self.repos.contains_key(&key) {
+                let repo = task_repo(pool, id).await;
+                self.repos.insert(key.clone(), repo);
+            }
+            return self.repos[&key].clone();
+        }
+        None
+    }
+}
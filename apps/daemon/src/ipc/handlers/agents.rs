@@ -77,7 +77,7 @@ pub async fn disconnect(params: Value, ctx: &AppContext) -> Result<Value> {
 
 /// `agents.spawn` — spawn a new orchestrated agent.
 ///
-/// Params: `{ role, task_id, complexity?, worktree_path?, previous_provider? }`
+/// Params: `{ role, task_id, complexity?, worktree_path?, previous_provider?, repo_path? }`
 pub async fn spawn_agent(params: Value, ctx: &AppContext) -> Result<Value> {
     let role_str = params
         .get("role")
@@ -98,27 +98,53 @@ pub async fn spawn_agent(params: Value, ctx: &AppContext) -> Result<Value> {
     let previous_provider = params
         .get("previous_provider")
         .and_then(|v| v.as_str())
-        .and_then(|s| match s {
-            "claude" => Some(crate::agents::capabilities::Provider::Claude),
-            "codex" => Some(crate::agents::capabilities::Provider::Codex),
-            _ => None,
-        });
+        .map(crate::agents::capabilities::Provider::from_name);
 
     let role = crate::agents::roles::AgentRole::from_str(role_str)
         .ok_or_else(|| anyhow::anyhow!("unknown role: {}", role_str))?;
 
-    let agent_id = ctx
+    // Project-specific fallback chains key off the repo; default to the task's.
+    let repo_path = match params.get("repo_path").and_then(|v| v.as_str()) {
+        Some(repo) => Some(repo.to_string()),
+        None => ctx
+            .task_storage
+            .get_task(task_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|t| t.repo_path),
+    };
+
+    let spawned = ctx
         .orchestrator
-        .spawn(role, task_id, complexity, worktree_path, previous_provider)
+        .spawn_in_project(
+            role,
+            task_id,
+            complexity,
+            worktree_path,
+            previous_provider,
+            repo_path.as_deref(),
+        )
         .await
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
+    for hop in &spawned.hops {
+        hop.emit(
+            &ctx.broadcaster,
+            json!({ "agentId": spawned.agent_id, "taskId": task_id, "role": role_str }),
+        );
+    }
     ctx.broadcaster.broadcast(
         "agent.spawned",
-        json!({ "agent_id": agent_id, "task_id": task_id, "role": role_str }),
+        json!({
+            "agent_id": spawned.agent_id,
+            "task_id": task_id,
+            "role": role_str,
+            "provider": spawned.provider.as_str(),
+        }),
     );
 
-    Ok(json!({ "agent_id": agent_id }))
+    Ok(json!({ "agent_id": spawned.agent_id, "provider": spawned.provider.as_str() }))
 }
 
 /// `agents.list` — list agents, optionally filtered by task or role.
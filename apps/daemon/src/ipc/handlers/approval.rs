@@ -1,7 +1,7 @@
 //! RPC handlers for the human-approval workflow.
 //!
 //! Exposes:
-//!   `approval.list`    — list tasks currently awaiting human approval
+//!   `approval.list`    — list approval requests awaiting a human
 //!   `approval.respond` — grant or deny a pending approval request
 
 use crate::AppContext;
@@ -12,33 +12,34 @@ fn sv<'a>(v: &'a Value, key: &str) -> Option<&'a str> {
     v.get(key).and_then(|v| v.as_str())
 }
 
-/// `approval.list` — list tasks in `needs_approval` state.
+/// `approval.list` — list pending approval requests, oldest first.
 ///
-/// Params: (none required)
-/// Returns: `{ approvals: [ { task_id, pending_approval_id, tool_name, risk_level, ... } ] }`
-pub async fn list(_params: Value, ctx: &AppContext) -> Result<Value> {
-    use crate::tasks::storage::TaskListParams;
-
-    let tasks = ctx
+/// Params: `{ repo_path?: string, limit?: number, offset?: number }` —
+/// only that repo's approvals when `repo_path` is given (default limit 100)
+/// Returns: `{ approvals: [ { approval_id, task_id, title, tool_name, risk_level, ... } ] }`
+pub async fn list(params: Value, ctx: &AppContext) -> Result<Value> {
+    let rows = ctx
         .task_storage
-        .list_tasks(&TaskListParams {
-            status: Some("needs_approval".to_string()),
-            limit: Some(100),
-            ..Default::default()
-        })
+        .list_pending_approvals(
+            sv(&params, "repo_path"),
+            params["limit"].as_i64().unwrap_or(100),
+            params["offset"].as_i64().unwrap_or(0),
+        )
         .await?;
 
-    let approvals: Vec<Value> = tasks
+    let approvals: Vec<Value> = rows
         .into_iter()
-        .map(|t| {
+        .map(|a| {
             json!({
-                "task_id": t.id,
-                "title": t.title,
-                "claimed_by": t.claimed_by,
-                "repo_path": t.repo_path,
-                "updated_at": t.updated_at,
-                // pending_approval_id is stored in notes by request_approval
-                "approval_id": t.notes,
+                "approval_id": a.approval_id,
+                "task_id": a.task_id,
+                "title": a.title,
+                "agent_id": a.agent_id,
+                "tool_name": a.tool_name,
+                "arguments": serde_json::from_str::<Value>(&a.arguments).unwrap_or(Value::Null),
+                "risk_level": a.risk_level,
+                "repo_path": a.repo_path,
+                "requested_at": a.requested_at,
             })
         })
         .collect();
@@ -50,8 +51,8 @@ pub async fn list(_params: Value, ctx: &AppContext) -> Result<Value> {
 ///
 /// Params: `{ approval_id: string, decision: "grant" | "deny", reason?: string }`
 ///
-/// Finds the task awaiting approval with the given `approval_id` and transitions
-/// it: `grant` → `in_progress` (Active), `deny` → `blocked`.
+/// Resolves the approval and transitions its task: `grant` → `in_progress`
+/// (Active), `deny` → `blocked`.
 pub async fn respond(params: Value, ctx: &AppContext) -> Result<Value> {
     let approval_id =
         sv(&params, "approval_id").ok_or_else(|| anyhow::anyhow!("missing field: approval_id"))?;
@@ -66,26 +67,15 @@ pub async fn respond(params: Value, ctx: &AppContext) -> Result<Value> {
         ));
     }
 
-    // Find the task whose notes field contains this approval_id.
-    // (set by request_approval when it transitions the task to needs_approval)
-    use crate::tasks::storage::TaskListParams;
-    let candidates = ctx
+    let approval = ctx
         .task_storage
-        .list_tasks(&TaskListParams {
-            status: Some("needs_approval".to_string()),
-            limit: Some(200),
-            ..Default::default()
-        })
-        .await?;
-
-    let task = candidates
-        .into_iter()
-        .find(|t| t.notes.as_deref() == Some(approval_id))
+        .resolve_approval(approval_id, decision == "grant")
+        .await?
         .ok_or_else(|| {
             anyhow::anyhow!("approval '{}' not found or already resolved", approval_id)
         })?;
 
-    let task_id = &task.id;
+    let task_id = &approval.task_id;
 
     match decision {
         "grant" => {
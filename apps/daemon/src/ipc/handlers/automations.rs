@@ -11,6 +11,9 @@ fn automation_to_json(a: &crate::automations::engine::Automation) -> Value {
         "enabled": a.enabled,
         "trigger": format!("{:?}", a.trigger).to_lowercase(),
         "condition": a.condition,
+        "schedule": a.schedule,
+        "catchUp": a.catch_up,
+        "nextRunAt": a.next_run_after(&chrono::Local::now()).map(|t| t.to_rfc3339()),
         "action": format!("{:?}", a.action).to_lowercase(),
         "actionConfig": a.action_config,
         "builtin": a.builtin,
@@ -81,3 +84,35 @@ pub async fn disable(params: Value, ctx: &AppContext) -> Result<Value> {
         None => anyhow::bail!("automation '{}' not found", name),
     }
 }
+
+/// `automation.validate` — lint a condition (and optional cron schedule)
+/// before saving, without registering anything.
+///
+/// Params: `{ condition?: string, schedule?: string }`.
+/// Returns `{ valid, errors: [{ field, message, offset? }] }`.
+pub async fn validate(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let mut errors = Vec::new();
+
+    if let Some(cond) = params.get("condition").and_then(|v| v.as_str()) {
+        if !cond.trim().is_empty() {
+            if let Err(e) = crate::automations::condition::Condition::parse(cond) {
+                errors.push(json!({
+                    "field": "condition",
+                    "message": e.message,
+                    "offset": e.offset,
+                }));
+            }
+        }
+    }
+
+    if let Some(schedule) = params.get("schedule").and_then(|v| v.as_str()) {
+        if let Err(e) = crate::automations::cron::CronSchedule::parse(schedule) {
+            errors.push(json!({
+                "field": "schedule",
+                "message": format!("{e:#}"),
+            }));
+        }
+    }
+
+    Ok(json!({ "valid": errors.is_empty(), "errors": errors }))
+}
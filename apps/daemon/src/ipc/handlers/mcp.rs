@@ -0,0 +1,30 @@
+//! RPC handlers for upstream MCP servers.
+//!
+//! Exposes:
+//!   `mcp.status` — the state, published tools and restart history of each
+//!                  server in `.claw/mcp-servers.json`.
+
+use crate::AppContext;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// `mcp.status` — one entry per configured server.
+///
+/// Params: `{ "name"?: "<server>" }` — limit the result to one server.
+/// Returns:
+/// ```json
+/// {
+///   "servers": [ { name, transport, state, tools, restarts, lastError, since, retryAt } ]
+/// }
+/// ```
+/// `state` is `starting`, `running`, `restarting` or `blocked`.
+pub async fn status(params: Value, ctx: &AppContext) -> Result<Value> {
+    let name = params.get("name").and_then(Value::as_str);
+    let servers: Vec<_> = ctx
+        .mcp_hub
+        .status()
+        .into_iter()
+        .filter(|s| name.is_none_or(|n| s.name == n))
+        .collect();
+    Ok(json!({ "servers": servers }))
+}
@@ -7,6 +7,7 @@ pub mod bench;
 pub mod daemon;
 pub mod instructions;
 pub mod license;
+pub mod mcp;
 pub mod policy;
 pub mod provider;
 pub mod repo;
@@ -6,13 +6,19 @@
 //! - `plugin.enable`  — enable a disabled plugin (re-loads it)
 //! - `plugin.disable` — disable a running plugin (calls on_unload)
 //! - `plugin.info`    — get detail for a single plugin
+//! - `plugin.<name>.<method>` — methods contributed by a loaded plugin
 
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
 use serde_json::{json, Value};
 
+use crate::mcp::dispatch::task_state;
 use crate::plugins::manager::PluginManager;
+use crate::policy::PolicyDecision;
+use crate::storage::event_log::AuditEntry;
+use crate::AppContext;
 
 /// `plugin.list` — list all known plugins.
 pub async fn list(manager: Arc<PluginManager>, _params: Value) -> Result<Value> {
@@ -50,3 +56,78 @@ pub async fn info(manager: Arc<PluginManager>, params: Value) -> Result<Value> {
         None => anyhow::bail!("plugin '{}' not found", name),
     }
 }
+
+/// `plugin.<name>.<method>` — forward to the plugin's `on_rpc`.
+///
+/// The method name is evaluated by the policy engine like a tool: plugins
+/// review it, and its risk (`tool-risk.json`, else the manifest's
+/// `method_risk`, else medium) decides whether a `task_id` naming an
+/// in-progress task is needed.  Every call,
+/// refused or not, is written to the audit log under the caller's session
+/// (see `caller_session`).
+pub async fn call(method: &str, mut params: Value, ctx: &AppContext) -> Result<Value> {
+    if !ctx.plugin_manager.has_method(method).await {
+        anyhow::bail!("METHOD_NOT_FOUND:{}", method);
+    }
+    let task_state = task_state(ctx, &params).await?;
+    let session_id = caller_session(ctx, &params).await?;
+    let decision = ctx
+        .policy_engine
+        .evaluate_mut(method, &mut params, task_state.as_ref(), &session_id)
+        .await;
+    let risk = format!("{:?}", ctx.policy_engine.risk_of(method).await).to_lowercase();
+    let args_json = params.to_string();
+    let audit = |status: &str, duration_ms: u64| {
+        AuditEntry::new(
+            &session_id,
+            None,
+            method,
+            &args_json,
+            &risk,
+            status,
+            duration_ms,
+        )
+    };
+    if let PolicyDecision::Deny { reason } | PolicyDecision::NeedsApproval { reason, .. } =
+        &decision
+    {
+        ctx.audit_log
+            .append(&audit(decision.audit_status(), 0))
+            .await;
+        anyhow::bail!("TOOL_DENIED: {reason}");
+    }
+
+    let started = Instant::now();
+    let result = ctx.plugin_manager.call_method(method, params).await;
+    let status = if result.is_ok() { "allowed" } else { "failed" };
+    ctx.audit_log
+        .append(&audit(status, started.elapsed().as_millis() as u64))
+        .await;
+    result
+}
+
+/// The session an RPC call acts for: its `session_id` param, else the agent
+/// that claimed the task named by `task_id`.  A call naming neither has no
+/// session and is reported as `rpc`.
+async fn caller_session(ctx: &AppContext, params: &Value) -> Result<String> {
+    let mut caller = params
+        .get("session_id")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if caller.is_none() {
+        if let Some(task_id) = params.get("task_id").and_then(Value::as_str) {
+            caller = ctx
+                .task_storage
+                .get_task(task_id)
+                .await?
+                .and_then(|t| t.claimed_by);
+        }
+    }
+    let caller = caller.unwrap_or_else(|| "rpc".to_string());
+    // `plugin:<name>` marks a plugin's own calls, which that plugin does
+    // not review; an RPC client must not pose as one.
+    if caller.starts_with("plugin:") {
+        anyhow::bail!("TOOL_DENIED: session '{caller}' is reserved for plugins");
+    }
+    Ok(caller)
+}
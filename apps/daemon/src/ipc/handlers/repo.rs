@@ -63,6 +63,7 @@ pub async fn open(params: Value, ctx: &AppContext) -> Result<Value> {
     // DC.T41: check for data-dir overlap and .clawd/ injection
     security::check_repo_path_safety(Path::new(&p.repo_path), &ctx.config.data_dir)?;
     let status = ctx.repo_registry.open(&p.repo_path).await?;
+    ctx.automation_engine.load_repo(&p.repo_path).await;
     Ok(serde_json::to_value(status)?)
 }
 
@@ -77,6 +78,7 @@ pub async fn close(params: Value, ctx: &AppContext) -> Result<Value> {
     let p: RepoPathParams = serde_json::from_value(params)?;
     validate_repo_path(&p.repo_path)?;
     let removed = ctx.repo_registry.close(&p.repo_path).await?;
+    ctx.automation_engine.unload_repo(&p.repo_path).await;
     if !removed {
         bail!(
             "REPO_NOT_FOUND: repo is not currently tracked: {}",
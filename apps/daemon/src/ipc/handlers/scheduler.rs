@@ -1,28 +1,38 @@
 //! RPC handlers for the account scheduler.
 //!
 //! Exposes:
-//!   `scheduler.status` — report scheduler state: accounts, queue depth, etc.
+//!   `scheduler.status`       — report scheduler state: accounts, queue, slots.
+//!   `scheduler.enqueue`      — add a provider request to the queue.
+//!   `scheduler.cancel`       — remove a queued request by id.
+//!   `scheduler.reprioritize` — change the priority of a queued request.
+//!   `scheduler.acquire`      — claim the next request that has a free account slot.
+//!   `scheduler.release`      — finish a running request and free its slot.
 
+use crate::scheduler::queue::SchedulerRequest;
 use crate::AppContext;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde_json::{json, Value};
 
 /// `scheduler.status` — return a snapshot of the scheduler's current state.
 ///
-/// Params: (none required)
+/// Params: `{ "id"?: "<request id>" }`
 /// Returns:
 /// ```json
 /// {
 ///   "accounts": [ { account_id, provider, is_available, blocked_until, rpm_used, tpm_used, total_requests } ],
 ///   "queue_length": N,
 ///   "queue_next_priority": N | null,
-///   "fallback_config": { "primary": "...", "alternatives": [...] }
+///   "queue": [ { id, task_id, provider, priority, effective_priority, position, waiting_secs, ... } ],
+///   "running": [ { id, task_id, provider, account_id, started_at, ... } ],
+///   "slots": { "per_account": N, "in_use": { "<account_id>": N } },
+///   "position": N | null            // only when `id` is given
 /// }
 /// ```
-pub async fn status(_params: Value, ctx: &AppContext) -> Result<Value> {
+pub async fn status(params: Value, ctx: &AppContext) -> Result<Value> {
     let accounts = ctx.account_pool.list().await;
     let queue_length = ctx.scheduler_queue.len().await;
     let queue_next_priority = ctx.scheduler_queue.peek_priority().await;
+    let snapshot = ctx.scheduler_queue.snapshot().await;
 
     let accounts_json: Vec<Value> = accounts
         .into_iter()
@@ -41,9 +51,151 @@ pub async fn status(_params: Value, ctx: &AppContext) -> Result<Value> {
         })
         .collect();
 
-    Ok(json!({
+    let mut result = json!({
         "accounts": accounts_json,
         "queue_length": queue_length,
         "queue_next_priority": queue_next_priority,
-    }))
+        "queue": snapshot.queued,
+        "running": snapshot.running,
+        "slots": {
+            "per_account": snapshot.slots_per_account,
+            "in_use": snapshot.slots_in_use,
+        },
+    });
+    if let Some(id) = params.get("id").and_then(Value::as_str) {
+        result["position"] = json!(ctx.scheduler_queue.position(id).await);
+    }
+    Ok(result)
+}
+
+/// `scheduler.enqueue` — queue a provider request.
+///
+/// Params: `{ "task_id": "...", "provider": "...", "agent_id"?: "...", "role"?: "...", "priority"?: 0-255 }`
+/// Returns: `{ "id": "...", "position": N }`
+pub async fn enqueue(params: Value, ctx: &AppContext) -> Result<Value> {
+    let task_id = params
+        .get("task_id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("missing task_id"))?;
+    let provider = params
+        .get("provider")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("missing provider"))?;
+    let priority = parse_priority(&params)?.unwrap_or(0);
+    let str_param = |key: &str| {
+        params
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let req = SchedulerRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: task_id.to_string(),
+        agent_id: str_param("agent_id"),
+        role: str_param("role"),
+        provider: provider.to_string(),
+        priority,
+        enqueued_at: chrono::Utc::now(),
+    };
+    let id = req.id.clone();
+    ctx.scheduler_queue.enqueue(req).await?;
+    let position = ctx.scheduler_queue.position(&id).await;
+    ctx.broadcaster.broadcast(
+        "scheduler.queueChanged",
+        json!({ "id": id, "action": "enqueued" }),
+    );
+    Ok(json!({ "id": id, "position": position }))
+}
+
+/// `scheduler.cancel` — remove a queued request.
+///
+/// Params: `{ "id": "..." }`
+/// Returns: `{ "cancelled": true }`
+pub async fn cancel(params: Value, ctx: &AppContext) -> Result<Value> {
+    let id = request_id(&params)?;
+    if !ctx.scheduler_queue.cancel(id).await? {
+        bail!("no queued request {id}");
+    }
+    ctx.broadcaster.broadcast(
+        "scheduler.queueChanged",
+        json!({ "id": id, "action": "cancelled" }),
+    );
+    Ok(json!({ "cancelled": true }))
+}
+
+/// `scheduler.reprioritize` — change the base priority of a queued request.
+///
+/// Params: `{ "id": "...", "priority": 0-255 }`
+/// Returns: `{ "id": "...", "priority": N, "position": N }`
+pub async fn reprioritize(params: Value, ctx: &AppContext) -> Result<Value> {
+    let id = request_id(&params)?;
+    let Some(priority) = parse_priority(&params)? else {
+        bail!("missing priority");
+    };
+    if !ctx.scheduler_queue.reprioritize(id, priority).await? {
+        bail!("no queued request {id}");
+    }
+    let position = ctx.scheduler_queue.position(id).await;
+    ctx.broadcaster.broadcast(
+        "scheduler.queueChanged",
+        json!({ "id": id, "action": "reprioritized", "priority": priority }),
+    );
+    Ok(json!({ "id": id, "priority": priority, "position": position }))
+}
+
+/// `scheduler.acquire` — lease the best queued request whose provider has an
+/// account with a free slot.
+///
+/// Params: `{}`
+/// Returns: `{ "lease": { id, task_id, provider, account_id, started_at, ... } | null }`
+pub async fn acquire(_params: Value, ctx: &AppContext) -> Result<Value> {
+    let accounts = ctx.account_pool.list().await;
+    let lease = ctx.scheduler_queue.acquire(&accounts).await?;
+    if let Some(ref lease) = lease {
+        ctx.broadcaster.broadcast(
+            "scheduler.queueChanged",
+            json!({ "id": lease.request.id, "action": "started", "account_id": lease.account_id }),
+        );
+    }
+    Ok(json!({ "lease": lease }))
+}
+
+/// `scheduler.release` — finish a leased request, recording its token usage
+/// against the account that ran it.
+///
+/// Params: `{ "id": "...", "tokens"?: N }`
+/// Returns: `{ "released": true }`
+pub async fn release(params: Value, ctx: &AppContext) -> Result<Value> {
+    let id = request_id(&params)?;
+    let Some(lease) = ctx.scheduler_queue.release(id).await? else {
+        bail!("no running request {id}");
+    };
+    let tokens = params.get("tokens").and_then(Value::as_u64).unwrap_or(0);
+    ctx.fallback_engine
+        .record_completion(&lease.account_id, tokens)
+        .await;
+    ctx.broadcaster.broadcast(
+        "scheduler.queueChanged",
+        json!({ "id": id, "action": "released" }),
+    );
+    Ok(json!({ "released": true }))
+}
+
+fn request_id(params: &Value) -> Result<&str> {
+    params
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("missing id"))
+}
+
+fn parse_priority(params: &Value) -> Result<Option<u8>> {
+    match params.get("priority") {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(p) if p <= u8::MAX as u64 => Ok(Some(p as u8)),
+            _ => bail!("priority must be an integer between 0 and 255"),
+        },
+    }
 }
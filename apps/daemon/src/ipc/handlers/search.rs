@@ -1,58 +1,233 @@
 // SPDX-License-Identifier: MIT
 // session.search RPC handler (Sprint GG, SS.3 + SS.5).
 //
-// Full-text search across all session messages using SQLite FTS5.
-// Returns BM25-ranked results with snippets.
+// Full-text search using SQLite FTS5 across session messages, agent tasks,
+// memory entries and the tool-call audit log.  Each kind has its own index
+// (`session_fts`, `task_fts`, `memory_fts`, `tool_call_fts`); hits are merged
+// into one BM25-ranked list and paged with an opaque keyset cursor.
+//
+// BM25 scores come from separate indexes, so the merged order across kinds
+// is approximate — within a kind it is exact.
 
 use crate::AppContext;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sqlx::{QueryBuilder, Sqlite};
 use tracing::debug;
 
+/// Tokens of context `snippet()` keeps around a match.
+const SNIPPET_TOKENS: u32 = 16;
+
+/// Match markers passed to `snippet()` and stripped again to compute
+/// `highlights`.  Private-use code points, so they never clash with text.
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
+/// `id, session_id, role, title, created, snippet, score` of one index row.
+type HitRow = (
+    String,
+    Option<String>,
+    Option<String>,
+    String,
+    String,
+    String,
+    f64,
+);
+
+/// The entity a search hit refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HitKind {
+    Message,
+    Task,
+    Memory,
+    ToolCall,
+}
+
+impl HitKind {
+    const ALL: [HitKind; 4] = [Self::Message, Self::Task, Self::Memory, Self::ToolCall];
+
+    /// Tie-break between kinds with equal scores; part of the cursor.
+    fn order(self) -> i64 {
+        self as i64
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Self::Message => "session_fts",
+            Self::Task => "task_fts",
+            Self::Memory => "memory_fts",
+            Self::ToolCall => "tool_call_fts",
+        }
+    }
+
+    /// `id, session_id, role, title, created` for this kind's index.
+    fn columns(self) -> &'static str {
+        match self {
+            Self::Message => {
+                "message_id AS id, session_id, role, \
+                 COALESCE((SELECT title FROM sessions WHERE sessions.id = session_fts.session_id), '') AS title, \
+                 created_at AS created"
+            }
+            Self::Task => {
+                "task_id AS id, NULL AS session_id, NULL AS role, title, \
+                 strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'unixepoch') AS created"
+            }
+            Self::Memory => {
+                "entry_id AS id, NULL AS session_id, NULL AS role, key AS title, \
+                 strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'unixepoch') AS created"
+            }
+            Self::ToolCall => {
+                "event_id AS id, session_id, NULL AS role, tool_name AS title, \
+                 created_at AS created"
+            }
+        }
+    }
+
+    /// Column `snippet()` draws from: the message text, or the best match.
+    fn snippet_column(self) -> i32 {
+        match self {
+            Self::Message => 0,
+            _ => -1,
+        }
+    }
+
+    /// Tasks and memory store unix seconds; the others RFC 3339 text.
+    fn unix_timestamps(self) -> bool {
+        matches!(self, Self::Task | Self::Memory)
+    }
+
+    /// Whether the `sessionId` and `role` filters can match this kind.
+    fn accepts(self, filters: &Filters) -> bool {
+        match self {
+            Self::Message => true,
+            Self::ToolCall => filters.role.is_none(),
+            Self::Task | Self::Memory => filters.session_id.is_none() && filters.role.is_none(),
+        }
+    }
+}
+
 /// A single search result entry.
 #[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SearchResult {
-    /// ID of the session containing this message.
-    #[serde(rename = "sessionId")]
-    pub session_id: String,
-    /// ID of the matching message.
-    #[serde(rename = "messageId")]
-    pub message_id: String,
-    /// Short snippet of the matching text (FTS5 `snippet()` function output).
-    pub snippet: String,
+    pub kind: HitKind,
+    /// ID of the message, task, memory entry or tool-call event.
+    pub id: String,
+    /// Session of a message or tool call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Same as `id` for messages; kept for clients predating `kind`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
     /// Role of the message author ("user" | "assistant").
-    pub role: String,
-    /// ISO-8601 creation timestamp of the message.
-    #[serde(rename = "createdAt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Session title, task title, memory key or tool name.
+    pub title: String,
+    /// Plain-text excerpt around the match.
+    pub snippet: String,
+    /// `[start, end)` ranges of matched terms in `snippet`, in UTF-16 code
+    /// units so they index Dart and JavaScript strings directly.
+    pub highlights: Vec<[usize; 2]>,
+    /// ISO-8601 creation timestamp.
     pub created_at: String,
-    /// BM25 rank (lower is more relevant; exposed as negative f32 for display).
-    pub rank: f32,
+    /// BM25 score (lower is more relevant).
+    pub rank: f64,
 }
 
 /// Optional search filters.
 #[derive(Debug, Clone, Deserialize, Default)]
 struct SearchFilters {
-    /// Restrict results to a specific session.
+    /// Restrict results to a specific session (messages and tool calls).
     #[serde(rename = "sessionId")]
     session_id: Option<String>,
-    /// Restrict to messages created on or after this ISO-8601 timestamp.
+    /// Restrict to entries created on or after this RFC 3339 timestamp.
     #[serde(rename = "dateFrom")]
     date_from: Option<String>,
-    /// Restrict to messages created before or at this ISO-8601 timestamp.
+    /// Restrict to entries created before or at this RFC 3339 timestamp.
     #[serde(rename = "dateTo")]
     date_to: Option<String>,
-    /// Restrict to a specific message role ("user" | "assistant").
+    /// Restrict to messages with this role ("user" | "assistant").
+    role: Option<String>,
+}
+
+/// Validated filters, bound into every per-kind query.
+struct Filters {
+    session_id: Option<String>,
     role: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<SearchFilters> for Filters {
+    type Error = anyhow::Error;
+
+    fn try_from(f: SearchFilters) -> Result<Self> {
+        let parse = |field: &str, value: Option<String>| {
+            value
+                .map(|v| {
+                    DateTime::parse_from_rfc3339(&v)
+                        .map(|t| t.with_timezone(&Utc))
+                        .map_err(|_| {
+                            anyhow!("INVALID_PARAMS: {field} is not an RFC 3339 timestamp")
+                        })
+                })
+                .transpose()
+        };
+        Ok(Self {
+            session_id: f.session_id,
+            role: f.role,
+            from: parse("dateFrom", f.date_from)?,
+            to: parse("dateTo", f.date_to)?,
+        })
+    }
+}
+
+/// Position after the last hit of a page: `(rank, kind, id)`.
+#[derive(Debug, Clone, PartialEq)]
+struct Cursor {
+    rank: f64,
+    kind: i64,
+    id: String,
 }
 
-/// `session.search` — full-text search across all session messages.
+impl Cursor {
+    fn encode(&self) -> String {
+        // The rank's bit pattern survives the round trip exactly.
+        let raw = format!("{:016x}:{}:{}", self.rank.to_bits(), self.kind, self.id);
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || anyhow!("INVALID_PARAMS: invalid cursor");
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let mut parts = raw.splitn(3, ':');
+        let (Some(rank), Some(kind), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            rank: f64::from_bits(u64::from_str_radix(rank, 16).map_err(|_| invalid())?),
+            kind: kind.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// `session.search` — full-text search across messages, tasks, memory and
+/// the tool-call audit log.
 ///
 /// Parameters (JSON):
 /// ```json
 /// {
 ///   "query": "string to search",
 ///   "limit": 20,
+///   "cursor": "nextCursor of the previous page",
+///   "kinds": ["message", "task", "memory", "toolCall"],
 ///   "filterBy": {
 ///     "sessionId": "optional",
 ///     "dateFrom": "2026-01-01T00:00:00Z",
@@ -62,11 +237,16 @@ struct SearchFilters {
 /// }
 /// ```
 ///
+/// `kinds` defaults to all of them.  `sessionId` limits results to messages
+/// and tool calls, `role` to messages.
+///
 /// Returns:
 /// ```json
 /// {
-///   "results": [{ "sessionId", "messageId", "snippet", "role", "createdAt", "rank" }],
-///   "totalHits": 5
+///   "results": [{ "kind", "id", "sessionId", "messageId", "role", "title",
+///                 "snippet", "highlights", "createdAt", "rank" }],
+///   "totalHits": 5,
+///   "nextCursor": "opaque, or null on the last page"
 /// }
 /// ```
 pub async fn search(params: Value, ctx: &AppContext) -> Result<Value> {
@@ -75,6 +255,8 @@ pub async fn search(params: Value, ctx: &AppContext) -> Result<Value> {
         query: String,
         #[serde(default = "default_limit")]
         limit: u32,
+        cursor: Option<String>,
+        kinds: Option<Vec<HitKind>>,
         #[serde(rename = "filterBy", default)]
         filter_by: SearchFilters,
     }
@@ -85,79 +267,154 @@ pub async fn search(params: Value, ctx: &AppContext) -> Result<Value> {
 
     let p: Params = serde_json::from_value(params)?;
 
-    if p.query.trim().is_empty() {
-        return Ok(json!({ "results": [], "totalHits": 0 }));
+    // Escape the FTS query string (FTS5 uses a different syntax).
+    let fts_query = sanitize_fts_query(&p.query);
+    if fts_query == "\"\"" {
+        return Ok(json!({ "results": [], "totalHits": 0, "nextCursor": null }));
     }
 
-    // Clamp limit to a sane maximum.
-    let limit = p.limit.min(200) as i64;
+    // Clamp limit to a sane range.
+    let limit = p.limit.clamp(1, 200) as usize;
+    let filters = Filters::try_from(p.filter_by)?;
+    let cursor = p.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let kinds: Vec<HitKind> = p
+        .kinds
+        .unwrap_or_else(|| HitKind::ALL.to_vec())
+        .into_iter()
+        .filter(|k| k.accepts(&filters))
+        .collect();
+
+    debug!(query = %p.query, limit, ?kinds, "session.search");
+
+    let pool = ctx.storage.pool();
+    let mut hits = Vec::new();
+    let mut total = 0i64;
+    for kind in HitKind::ALL.into_iter().filter(|k| kinds.contains(k)) {
+        let mut count = QueryBuilder::new(format!("SELECT count(*) FROM {}", kind.table()));
+        push_match(&mut count, kind, &fts_query, &filters);
+        let (n,): (i64,) = count
+            .build_query_as()
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("FTS search failed: {e}"))?;
+        total += n;
+
+        // One more than a page from each kind is enough to fill the merged
+        // page and tell whether another follows.
+        let table = kind.table();
+        let mut page = QueryBuilder::new(format!(
+            "SELECT id, session_id, role, title, created, snippet, score FROM (\
+             SELECT {}, \
+             snippet({table}, {}, char({}), char({}), '…', {SNIPPET_TOKENS}) AS snippet, \
+             bm25({table}) AS score FROM {table}",
+            kind.columns(),
+            kind.snippet_column(),
+            MARK_START as u32,
+            MARK_END as u32,
+        ));
+        push_match(&mut page, kind, &fts_query, &filters);
+        page.push(")");
+        if let Some(c) = &cursor {
+            page.push(format!(" WHERE (score, {}, id) > (", kind.order()))
+                .push_bind(c.rank)
+                .push(", ")
+                .push_bind(c.kind)
+                .push(", ")
+                .push_bind(c.id.clone())
+                .push(")");
+        }
+        page.push(" ORDER BY score, id LIMIT ")
+            .push_bind(limit as i64 + 1);
 
-    debug!(query = %p.query, limit = limit, "session.search");
+        let rows: Vec<HitRow> = page
+            .build_query_as()
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow!("FTS search failed: {e}"))?;
 
-    // Build dynamic SQL using FTS5 MATCH with optional row value filters.
-    // We build a parameterized query by composing filter conditions.
-    let mut conditions: Vec<String> = Vec::new();
-    if let Some(sid) = &p.filter_by.session_id {
-        conditions.push(format!("session_id = '{}'", sid.replace('\'', "''")));
+        hits.extend(rows.into_iter().map(
+            |(id, session_id, role, title, created_at, marked, rank)| {
+                let (snippet, highlights) = highlight(&marked);
+                SearchResult {
+                    kind,
+                    message_id: (kind == HitKind::Message).then(|| id.clone()),
+                    id,
+                    session_id,
+                    role,
+                    title,
+                    snippet,
+                    highlights,
+                    created_at,
+                    rank,
+                }
+            },
+        ));
     }
-    if let Some(role) = &p.filter_by.role {
-        conditions.push(format!("role = '{}'", role.replace('\'', "''")));
+
+    hits.sort_by(|a, b| {
+        a.rank
+            .total_cmp(&b.rank)
+            .then(a.kind.order().cmp(&b.kind.order()))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let next_cursor = (hits.len() > limit).then(|| {
+        let last = &hits[limit - 1];
+        Cursor {
+            rank: last.rank,
+            kind: last.kind.order(),
+            id: last.id.clone(),
+        }
+        .encode()
+    });
+    hits.truncate(limit);
+
+    Ok(json!({ "results": hits, "totalHits": total, "nextCursor": next_cursor }))
+}
+
+/// Append ` WHERE <index> MATCH ?` and the filters that apply to `kind`, all
+/// as bound parameters.
+fn push_match(qb: &mut QueryBuilder<'_, Sqlite>, kind: HitKind, fts_query: &str, f: &Filters) {
+    qb.push(format!(" WHERE {} MATCH ", kind.table()))
+        .push_bind(fts_query.to_string());
+    if let Some(sid) = &f.session_id {
+        qb.push(" AND session_id = ").push_bind(sid.clone());
     }
-    if let Some(from) = &p.filter_by.date_from {
-        conditions.push(format!("created_at >= '{}'", from.replace('\'', "''")));
+    if let Some(role) = &f.role {
+        qb.push(" AND role = ").push_bind(role.clone());
     }
-    if let Some(to) = &p.filter_by.date_to {
-        conditions.push(format!("created_at <= '{}'", to.replace('\'', "''")));
+    for (op, bound) in [(">=", f.from), ("<=", f.to)] {
+        let Some(t) = bound else { continue };
+        qb.push(format!(" AND created_at {op} "));
+        if kind.unix_timestamps() {
+            qb.push_bind(t.timestamp());
+        } else {
+            qb.push_bind(t.to_rfc3339());
+        }
     }
+}
 
-    // Escape the FTS query string (FTS5 uses a different syntax).
-    let fts_query = sanitize_fts_query(&p.query);
-
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("AND {}", conditions.join(" AND "))
-    };
-
-    let sql = format!(
-        r#"
-        SELECT
-            session_id,
-            message_id,
-            snippet(session_fts, 0, '<b>', '</b>', '…', 16) AS snippet,
-            role,
-            created_at,
-            rank
-        FROM session_fts
-        WHERE session_fts MATCH ?
-        {where_clause}
-        ORDER BY rank
-        LIMIT {limit}
-        "#
-    );
-
-    let rows = sqlx::query_as::<_, (String, String, String, String, String, f64)>(&sql)
-        .bind(&fts_query)
-        .fetch_all(ctx.storage.pool())
-        .await
-        .map_err(|e| anyhow::anyhow!("FTS search failed: {e}"))?;
-
-    let results: Vec<SearchResult> = rows
-        .into_iter()
-        .map(
-            |(session_id, message_id, snippet, role, created_at, rank)| SearchResult {
-                session_id,
-                message_id,
-                snippet,
-                role,
-                created_at,
-                rank: rank as f32,
-            },
-        )
-        .collect();
-
-    let total = results.len();
-    Ok(json!({ "results": results, "totalHits": total }))
+/// Strip the match markers from a `snippet()` result, returning the plain
+/// text and the marked ranges as UTF-16 offsets.
+fn highlight(marked: &str) -> (String, Vec<[usize; 2]>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    let mut start = None;
+    for c in marked.chars() {
+        match c {
+            MARK_START => start = Some(pos),
+            MARK_END => {
+                if let Some(s) = start.take().filter(|&s| s < pos) {
+                    ranges.push([s, pos]);
+                }
+            }
+            c => {
+                text.push(c);
+                pos += c.len_utf16();
+            }
+        }
+    }
+    (text, ranges)
 }
 
 /// Sanitize a user-provided query string for FTS5 MATCH.
@@ -204,4 +461,27 @@ mod tests {
         let q = sanitize_fts_query("");
         assert_eq!(q, "\"\"");
     }
+
+    #[test]
+    fn highlights_are_utf16_offsets_into_the_plain_snippet() {
+        let (text, ranges) =
+            highlight("…the \u{E000}café\u{E001} 🦀 \u{E000}crab\u{E001}\u{E000}\u{E001}");
+        assert_eq!(text, "…the café 🦀 crab");
+        // The crab emoji is a surrogate pair: two code units.
+        assert_eq!(ranges, vec![[5, 9], [13, 17]]);
+        let units: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&units[13..17]).unwrap(), "crab");
+    }
+
+    #[test]
+    fn cursors_round_trip_and_reject_garbage() {
+        let cursor = Cursor {
+            rank: -1.479e-6,
+            kind: HitKind::ToolCall.order(),
+            id: "a:b".into(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("zz:1:x")).is_err());
+    }
 }
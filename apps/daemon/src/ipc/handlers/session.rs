@@ -71,7 +71,12 @@ pub async fn create(params: Value, ctx: &AppContext) -> Result<Value> {
     let title = p.title.unwrap_or_else(|| "New Session".to_string());
 
     // Strict provider name validation — return -32602 (invalid params) for unknown.
-    if !VALID_PROVIDERS.contains(&p.provider.as_str()) {
+    // Direct HTTP providers configured as `[provider.<name>]` are also accepted.
+    let is_http_provider = ctx
+        .config
+        .provider_profile(&p.provider)
+        .is_some_and(|profile| profile.is_http());
+    if !VALID_PROVIDERS.contains(&p.provider.as_str()) && !is_http_provider {
         anyhow::bail!(
             "invalid type: unknown provider '{}' — must be one of: {}",
             p.provider,
@@ -126,6 +131,11 @@ pub async fn create(params: Value, ctx: &AppContext) -> Result<Value> {
         .await;
     ctx.telemetry
         .send(TelemetryEvent::new("session.start").with_provider(&p.provider));
+    {
+        let plugins = ctx.plugin_manager.clone();
+        let session_id = session.id.clone();
+        tokio::spawn(async move { plugins.on_session_start(&session_id).await });
+    }
     Ok(serde_json::to_value(session)?)
 }
 
@@ -144,6 +154,10 @@ pub async fn delete(params: Value, ctx: &AppContext) -> Result<Value> {
     let p: SessionIdParams = serde_json::from_value(params)?;
     ctx.session_manager.delete(&p.session_id).await?;
     ctx.telemetry.send(TelemetryEvent::new("session.end"));
+    {
+        let plugins = ctx.plugin_manager.clone();
+        tokio::spawn(async move { plugins.on_session_end(&p.session_id).await });
+    }
     Ok(json!({}))
 }
 
@@ -292,6 +292,7 @@ pub async fn update_status(params: Value, ctx: &AppContext) -> Result<Value> {
         json!({
             "task_id": task_id,
             "status": new_status,
+            "repo_path": task.repo_path,
         }),
     );
 
@@ -1,8 +1,10 @@
 //! Sprint DD WR.3 — `workflow.*` RPC handlers.
 
+use crate::workflows::runner;
 use crate::AppContext;
 use anyhow::Result;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 /// `workflow.create` — store a new workflow recipe from YAML.
@@ -21,8 +23,8 @@ pub async fn create(params: Value, ctx: &AppContext) -> Result<Value> {
         .unwrap_or("");
     let tags = params.get("tags").cloned().unwrap_or_else(|| json!([]));
 
-    // Validate YAML parses correctly.
-    crate::workflows::engine::parse_recipe_yaml(yaml)?;
+    // Validate YAML parses and the step graph is sound.
+    crate::workflows::engine::parse_and_plan(yaml)?;
 
     let id = Uuid::new_v4().to_string();
     let tags_json = serde_json::to_string(&tags)?;
@@ -70,165 +72,56 @@ pub async fn list(_params: Value, ctx: &AppContext) -> Result<Value> {
     Ok(json!({ "recipes": recipes }))
 }
 
-/// `workflow.run` — start executing a workflow recipe.
+/// `workflow.run` — start executing a workflow recipe, or resume one.
 ///
-/// Executes each step sequentially, creating a session per step and feeding
-/// `inherit_from` for chained context. Pushes `workflow.stepCompleted` after
-/// each step and `workflow.ran` when all steps complete.
+/// Params: `{ recipeId, repoPath?, inputs? }` to start a new run, or
+/// `{ resumeRunId }` to continue an interrupted or failed run. Steps execute
+/// in the background following the recipe's DAG; progress is pushed as
+/// `workflow.stepStarted` / `workflow.stepCompleted`, then `workflow.ran` or
+/// `workflow.failed`.
 pub async fn run(params: Value, ctx: &AppContext) -> Result<Value> {
-    let recipe_id = params
-        .get("recipeId")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("recipeId required"))?;
-    let repo_path = params
-        .get("repoPath")
-        .and_then(|v| v.as_str())
-        .unwrap_or(".");
-    let inputs: std::collections::HashMap<String, String> = params
-        .get("inputs")
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
-        .unwrap_or_default();
-
-    // Load recipe YAML.
-    use sqlx::Row as _;
-    let row = sqlx::query(
-        "SELECT id, name, template_yaml, is_builtin FROM workflow_recipes WHERE id = ?",
-    )
-    .bind(recipe_id)
-    .fetch_optional(ctx.storage.pool())
-    .await?
-    .ok_or_else(|| anyhow::anyhow!("workflow recipe not found: {}", recipe_id))?;
-
-    let recipe_yaml: String = row.get("template_yaml");
-    let recipe_name: String = row.get("name");
-    let recipe = crate::workflows::engine::parse_recipe_yaml(&recipe_yaml)?;
-
-    let run_id = Uuid::new_v4().to_string();
-    let total_steps = recipe.steps.len() as i64;
-
-    sqlx::query(
-        "INSERT INTO workflow_runs (id, recipe_id, status, total_steps) VALUES (?, ?, 'running', ?)",
-    )
-    .bind(&run_id)
-    .bind(recipe_id)
-    .bind(total_steps)
-    .execute(ctx.storage.pool())
-    .await?;
-
-    // Increment run_count.
-    sqlx::query("UPDATE workflow_recipes SET run_count = run_count + 1 WHERE id = ?")
-        .bind(recipe_id)
-        .execute(ctx.storage.pool())
-        .await?;
-
-    // Execute steps in a background task.
-    let run_id_bg = run_id.clone();
-    let recipe_id_bg = recipe_id.to_string();
-    let recipe_name_bg = recipe_name.clone();
-    let ctx_bg = ctx.clone();
-    let repo_path_owned = repo_path.to_string();
-
-    tokio::spawn(async move {
-        let mut prev_session_id: Option<String> = None;
-
-        for (i, step) in recipe.steps.iter().enumerate() {
-            // Substitute {key} placeholders from inputs.
-            let mut prompt = step.prompt.clone();
-            for (k, v) in &inputs {
-                prompt = prompt.replace(&format!("{{{}}}", k), v);
-            }
-
-            let inherit = if step.inherit_from.as_deref() == Some("previous") {
-                prev_session_id.clone()
-            } else {
-                None
-            };
-
-            let provider = step.provider.as_deref().unwrap_or("claude");
-
-            // Create session for this step.
-            match ctx_bg
-                .session_manager
-                .create(
-                    provider,
-                    &repo_path_owned,
-                    &format!("{} — step {}", recipe_name_bg, i + 1),
-                    0,
-                    None,
-                    Some(&prompt),
-                )
-                .await
-            {
-                Ok(session) => {
-                    if inherit.is_some() {
-                        // Send prompt as first message so it runs immediately.
-                        let _ = ctx_bg
-                            .session_manager
-                            .send_message(&session.id, &prompt, &ctx_bg)
-                            .await;
-                    }
-
-                    prev_session_id = Some(session.id.clone());
-
-                    // Update run state.
-                    let _ = sqlx::query("UPDATE workflow_runs SET current_step = ? WHERE id = ?")
-                        .bind(i as i64 + 1)
-                        .bind(&run_id_bg)
-                        .execute(ctx_bg.storage.pool())
-                        .await;
-
-                    ctx_bg.broadcaster.broadcast(
-                        "workflow.stepCompleted",
-                        json!({
-                            "runId": run_id_bg,
-                            "recipeId": recipe_id_bg,
-                            "stepIndex": i,
-                            "sessionId": session.id,
-                        }),
-                    );
-                }
-                Err(e) => {
-                    let _ = sqlx::query(
-                        "UPDATE workflow_runs SET status = 'failed', finished_at = datetime('now') WHERE id = ?",
-                    )
-                    .bind(&run_id_bg)
-                    .execute(ctx_bg.storage.pool())
-                    .await;
-
-                    ctx_bg.broadcaster.broadcast(
-                        "workflow.failed",
-                        json!({ "runId": run_id_bg, "error": e.to_string() }),
-                    );
-                    return;
-                }
-            }
-        }
-
-        // Mark run complete.
-        let _ = sqlx::query(
-            "UPDATE workflow_runs SET status = 'done', finished_at = datetime('now') WHERE id = ?",
-        )
-        .bind(&run_id_bg)
-        .execute(ctx_bg.storage.pool())
-        .await;
-
-        ctx_bg.broadcaster.broadcast(
-            "workflow.ran",
-            json!({
-                "runId": run_id_bg,
-                "recipeId": recipe_id_bg,
-                "stepsCompleted": total_steps,
-            }),
-        );
+    let pool = ctx.storage.pool();
+    let (prepared, resumed) =
+        if let Some(run_id) = params.get("resumeRunId").and_then(|v| v.as_str()) {
+            (runner::prepare_resume(pool, run_id).await?, true)
+        } else {
+            let recipe_id = params
+                .get("recipeId")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("recipeId required"))?;
+            let repo_path = params
+                .get("repoPath")
+                .and_then(|v| v.as_str())
+                .unwrap_or(".");
+            let inputs: HashMap<String, String> = params
+                .get("inputs")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            (
+                runner::create_run(pool, recipe_id, &inputs, repo_path, None).await?,
+                false,
+            )
+        };
+
+    let response = json!({
+        "runId": prepared.run_id,
+        "recipeId": prepared.recipe_id,
+        "recipeName": prepared.recipe_name,
+        "totalSteps": prepared.plan.steps.len(),
+        "status": "running",
+        "resumed": resumed,
     });
+    runner::spawn_run(ctx, prepared);
+    Ok(response)
+}
 
-    Ok(json!({
-        "runId": run_id,
-        "recipeId": recipe_id,
-        "recipeName": recipe_name,
-        "totalSteps": total_steps,
-        "status": "running",
-    }))
+/// `workflow.getRun` — run status with per-step state and outputs.
+pub async fn get_run(params: Value, ctx: &AppContext) -> Result<Value> {
+    let run_id = params
+        .get("runId")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("runId required"))?;
+    runner::run_status(ctx.storage.pool(), run_id).await
 }
 
 /// `workflow.delete` — remove a user-defined workflow recipe.
@@ -1,9 +1,12 @@
 pub mod auth;
 pub mod event;
+pub mod event_filter;
 pub mod handlers;
 
 use crate::AppContext;
 use anyhow::Result;
+use event::{CursorItem, EventCursor};
+use event_filter::EventFilter;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -92,7 +95,7 @@ impl RpcRateLimiter {
 
 /// Constant-time token comparison to prevent timing-based token oracle attacks.
 /// Returns `true` if `a == b` without short-circuiting on mismatch.
-fn tokens_equal(a: &str, b: &str) -> bool {
+pub(crate) fn tokens_equal(a: &str, b: &str) -> bool {
     let a = a.as_bytes();
     let b = b.as_bytes();
     if a.len() != b.len() {
@@ -141,12 +144,12 @@ struct RpcError {
 // sessionPaused        = -32006  (session is paused — call session.resume first)
 // sessionLimitReached  = -32007  (max session count reached)
 
-const PARSE_ERROR: i32 = -32700;
-const INVALID_REQUEST: i32 = -32600;
-const METHOD_NOT_FOUND: i32 = -32601;
+pub(crate) const PARSE_ERROR: i32 = -32700;
+pub(crate) const INVALID_REQUEST: i32 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i32 = -32601;
 const INVALID_PARAMS: i32 = -32602;
 const INTERNAL_ERROR: i32 = -32603;
-const UNAUTHORIZED: i32 = -32004;
+pub(crate) const UNAUTHORIZED: i32 = -32004;
 const SESSION_NOT_FOUND: i32 = -32001;
 const REPO_NOT_FOUND: i32 = -32005;
 /// Session is currently running — cannot accept a new message turn.
@@ -176,6 +179,8 @@ const TASK_NOT_RESUMABLE_CODE: i32 = -32015;
 /// NOTE: spec originally listed -32006 here, but -32006 = sessionPaused — using -32016.
 #[allow(dead_code)]
 pub const MODE_VIOLATION_CODE: i32 = -32016;
+/// `token.revoke` — no active scoped API token with the given id.
+const API_TOKEN_NOT_FOUND_CODE: i32 = -32030;
 
 // ─── Server ──────────────────────────────────────────────────────────────────
 
@@ -357,10 +362,13 @@ async fn handle_connection(
     //
     // Token is stored at {data_dir}/auth_token with mode 0600.  The Flutter
     // desktop/mobile app reads this file and sends it here on every connect.
+    // Scoped API tokens (`token.create`) are accepted too; their method and
+    // repo limits are checked per call in `dispatch_text`.
     //
     // We save the token the client authenticated with so we can re-verify it
     // on every subsequent RPC dispatch (supports auth token rotation).
     let mut client_token = String::new();
+    let mut scoped = None;
     if !ctx.auth_token.is_empty() {
         let first = tokio::time::timeout(std::time::Duration::from_secs(10), stream.next()).await;
 
@@ -405,7 +413,13 @@ async fn handle_connection(
             .and_then(Value::as_str)
             .unwrap_or_default();
 
-        if !tokens_equal(provided, &ctx.auth_token) {
+        let is_daemon_token = tokens_equal(provided, &ctx.auth_token);
+        if !is_daemon_token {
+            scoped = crate::api_tokens::authenticate(ctx.storage.pool(), provided)
+                .await
+                .map(Arc::new);
+        }
+        if !is_daemon_token && scoped.is_none() {
             let _ = sink
                 .send(Message::Text(error_response(
                     id,
@@ -427,7 +441,13 @@ async fn handle_connection(
         debug!("client authenticated");
     }
 
-    let mut broadcast_rx = ctx.broadcaster.subscribe();
+    // Plain clients get every broadcast as-is.  After `events.subscribe`
+    // the connection gets only matching events, each with its `event_id`,
+    // starting with any it missed since `last_event_id`.  A scoped token
+    // only ever sees the events its globs and repos allow.
+    let mut events = ctx.broadcaster.subscribe_from(None);
+    let mut subscription: Option<EventFilter> = None;
+    let mut token_filter = scoped.clone().map(EventFilter::for_token);
     let mut rpc_limiter = RpcRateLimiter::new(
         ctx.config.security.max_rpc_calls_per_minute_per_ip,
         peer_ip.is_loopback(),
@@ -449,7 +469,21 @@ async fn handle_connection(
                             }
                             continue;
                         }
-                        let response = dispatch_text(&text, &ctx, &client_token).await;
+                        let response = match serde_json::from_str::<RpcRequest>(&text) {
+                            Err(_) => error_response(Value::Null, PARSE_ERROR, "Parse error"),
+                            Ok(req) if req.method == "events.subscribe" => {
+                                match subscribe_events(req, &ctx, &client_token).await {
+                                    Ok((resp, mut filter, cursor)) => {
+                                        filter.token = scoped.clone();
+                                        subscription = Some(filter);
+                                        events = cursor;
+                                        resp
+                                    }
+                                    Err(resp) => resp,
+                                }
+                            }
+                            Ok(req) => dispatch_request(req, &ctx, &client_token).await,
+                        };
                         if let Err(e) = sink.send(Message::Text(response)).await {
                             warn!(err = %e, "send error");
                             break;
@@ -467,21 +501,40 @@ async fn handle_connection(
                 }
             }
             // Outgoing broadcast event
-            event = broadcast_rx.recv() => {
-                match event {
-                    Ok(json) => {
-                        if let Err(e) = sink.send(Message::Text(json)).await {
-                            warn!(err = %e, "broadcast send error");
-                            break;
-                        }
+            item = events.next() => {
+                let frame = match item {
+                    Some(CursorItem::Event(event)) => match (subscription.as_mut(), token_filter.as_mut()) {
+                        (None, None) => Some(event.json.clone()),
+                        (None, Some(filter)) => filter
+                            .matches(&event, ctx.storage.pool())
+                            .await
+                            .then(|| event.json.clone()),
+                        (Some(filter), _) => filter
+                            .matches(&event, ctx.storage.pool())
+                            .await
+                            .then(|| event.json_with_id()),
+                    },
+                    Some(CursorItem::Gap { after }) => {
+                        // Slow client could not keep up with broadcast rate
+                        // and the replay buffer has moved past it.  The
+                        // sender is never blocked; subscribed clients are
+                        // told so they can reload, the rest just miss events.
+                        warn!(after, "broadcast lagged — slow client skipped events");
+                        subscription.is_some().then(|| {
+                            serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "method": "events.gap",
+                                "params": { "after": after },
+                            })
+                            .to_string()
+                        })
                     }
-                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                        // Slow client could not keep up with broadcast rate.
-                        // Events are dropped for this client; the sender is
-                        // never blocked. Log and continue rather than
-                        // disconnecting, so the client can still send RPCs.
-                        warn!(skipped = n, "broadcast lagged — slow client skipped events");
+                    None => break,
+                };
+                if let Some(frame) = frame {
+                    if let Err(e) = sink.send(Message::Text(frame)).await {
+                        warn!(err = %e, "broadcast send error");
+                        break;
                     }
                 }
             }
@@ -497,18 +550,38 @@ async fn handle_connection(
 /// immediately invalidates in-flight connections.  Relay connections pass `""`
 /// to skip this check (they authenticate at the relay layer instead).
 pub(crate) async fn dispatch_text(text: &str, ctx: &AppContext, client_token: &str) -> String {
-    // Parse
-    let req: RpcRequest = match serde_json::from_str(text) {
-        Ok(r) => r,
-        Err(_) => {
-            return error_response(Value::Null, PARSE_ERROR, "Parse error");
-        }
-    };
+    match serde_json::from_str(text) {
+        Ok(req) => dispatch_request(req, ctx, client_token).await,
+        Err(_) => error_response(Value::Null, PARSE_ERROR, "Parse error"),
+    }
+}
+
+/// Authorize and dispatch an already-parsed request (see `dispatch_text`).
+async fn dispatch_request(req: RpcRequest, ctx: &AppContext, client_token: &str) -> String {
+    if let Err(resp) = authorize(&req, ctx, client_token).await {
+        return resp;
+    }
+
+    let id = req.id.unwrap_or(Value::Null);
+    let params = req.params.unwrap_or(Value::Null);
+
+    dispatch_call(&req.method, id, params, ctx)
+        .await
+        .to_string()
+}
+
+/// Check a parsed request's token (see `dispatch_text`) and envelope.
+/// `Err` carries the error response to send instead.
+async fn authorize(req: &RpcRequest, ctx: &AppContext, client_token: &str) -> Result<(), String> {
+    let id = || req.id.clone().unwrap_or(Value::Null);
 
     // Re-validate bearer token on every RPC dispatch.
-    // Accepts either the static daemon auth_token OR a valid (non-revoked) paired device token.
+    // Accepts the static daemon auth_token, a valid (non-revoked) paired device
+    // token, or an active scoped API token — the last limited to its method
+    // globs and repos (see `api_tokens`).
     // An empty client_token is no longer exempt: relay-proxied connections must
     // include the daemon bearer token in their JSON-RPC frames, just like local clients.
+    let mut scoped = None;
     if !ctx.auth_token.is_empty() {
         let is_daemon_token = tokens_equal(client_token, &ctx.auth_token);
         let is_device_token = if !is_daemon_token && !client_token.is_empty() {
@@ -523,30 +596,88 @@ pub(crate) async fn dispatch_text(text: &str, ctx: &AppContext, client_token: &s
             false
         };
         if !is_daemon_token && !is_device_token {
-            return error_response(
-                req.id.unwrap_or(Value::Null),
+            scoped = crate::api_tokens::authenticate(ctx.storage.pool(), client_token).await;
+        }
+        if !is_daemon_token && !is_device_token && scoped.is_none() {
+            return Err(error_response(
+                id(),
                 UNAUTHORIZED,
                 "Unauthorized — invalid or missing token",
-            );
+            ));
         }
     }
 
     // Validate jsonrpc field
     if req.jsonrpc != "2.0" {
-        return error_response(
-            req.id.unwrap_or(Value::Null),
-            INVALID_REQUEST,
-            "Invalid Request",
-        );
+        return Err(error_response(id(), INVALID_REQUEST, "Invalid Request"));
     }
 
+    if let Some(token) = scoped {
+        let params = req.params.clone().unwrap_or(Value::Null);
+        if let Err(msg) = token
+            .authorize(&req.method, &params, ctx.storage.pool())
+            .await
+        {
+            return Err(error_response(id(), UNAUTHORIZED, &msg));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    #[serde(flatten)]
+    filter: EventFilter,
+    last_event_id: Option<u64>,
+}
+
+/// `events.subscribe` — switch this connection to filtered events with ids,
+/// replaying those after `last_event_id` first.  Calling it again replaces
+/// the filter.  Handled here rather than in `dispatch` because it changes
+/// the connection, not daemon state.
+async fn subscribe_events(
+    req: RpcRequest,
+    ctx: &AppContext,
+    client_token: &str,
+) -> Result<(String, EventFilter, EventCursor), String> {
+    authorize(&req, ctx, client_token).await?;
     let id = req.id.unwrap_or(Value::Null);
-    let params = req.params.unwrap_or(Value::Null);
+    let params = req
+        .params
+        .filter(|p| !p.is_null())
+        .unwrap_or_else(|| serde_json::json!({}));
+    let p: SubscribeParams = serde_json::from_value(params)
+        .map_err(|e| error_response(id.clone(), INVALID_PARAMS, &format!("Invalid params: {e}")))?;
+
+    let cursor = ctx.broadcaster.subscribe_from(p.last_event_id);
+    let resp = RpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result: Some(serde_json::json!({
+            "subscribed": true,
+            "last_event_id": ctx.broadcaster.last_event_id(),
+        })),
+        error: None,
+    };
+    let resp = serde_json::to_string(&resp).unwrap_or_default();
+    Ok((resp, p.filter, cursor))
+}
 
-    trace!(method = %req.method, "rpc dispatch");
+/// Run one authorized call through the handler table and build its JSON-RPC
+/// response object.
+///
+/// Shared by `dispatch_text` and the REST bridge (`rest::rpc`), which does its
+/// own token and scope checks first.
+pub(crate) async fn dispatch_call(
+    method: &str,
+    id: Value,
+    params: Value,
+    ctx: &AppContext,
+) -> Value {
+    trace!(method = %method, "rpc dispatch");
     ctx.metrics.inc_rpc_requests();
 
-    let result = dispatch(&req.method, params, ctx).await;
+    let result = dispatch(method, params, ctx).await;
 
     match result {
         Ok(value) => {
@@ -556,12 +687,12 @@ pub(crate) async fn dispatch_text(text: &str, ctx: &AppContext, client_token: &s
                 result: Some(value),
                 error: None,
             };
-            serde_json::to_string(&resp).unwrap_or_default()
+            serde_json::to_value(&resp).unwrap_or_default()
         }
         Err(e) => {
             // Map specific errors to RPC codes
-            let (code, msg) = classify_error(&e, &req.method);
-            error_response(id, code, &msg)
+            let (code, msg) = classify_error(&e, method);
+            error_value(id, code, &msg)
         }
     }
 }
@@ -635,6 +766,7 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         "session.delete" => handlers::session::delete(params, ctx).await,
         "session.sendMessage" => handlers::session::send_message(params, ctx).await,
         "session.getMessages" => handlers::session::get_messages(params, ctx).await,
+        "session.search" => handlers::search::search(params, ctx).await,
         "session.pause" => handlers::session::pause(params, ctx).await,
         "session.resume" => handlers::session::resume(params, ctx).await,
         "session.cancel" => handlers::session::cancel(params, ctx).await,
@@ -644,6 +776,16 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         "token.sessionUsage" => handlers::token::session_usage(params, ctx).await,
         "token.totalUsage" => handlers::token::total_usage(params, ctx).await,
         "token.budgetStatus" => handlers::token::budget_status(params, ctx).await,
+        // ─── Scoped API tokens ────────────────────────────────────────────────
+        "token.create" => crate::api_tokens::handlers::create(params, ctx).await,
+        "token.list" => crate::api_tokens::handlers::list(params, ctx).await,
+        "token.revoke" => crate::api_tokens::handlers::revoke(params, ctx).await,
+        // ─── Event stream ─────────────────────────────────────────────────────
+        // Handled per connection in `handle_connection`; over HTTP use
+        // GET /api/v1/events instead.
+        "events.subscribe" => anyhow::bail!(
+            "INVALID_PARAMS: events.subscribe is only available on the WebSocket — use GET /api/v1/events"
+        ),
         "tool.approve" => handlers::tool::approve(params, ctx).await,
         "tool.reject" => handlers::tool::reject(params, ctx).await,
         // ─── Tool call audit log (DC.T43) ─────────────────────────────────────
@@ -715,8 +857,23 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         // ─── Human-approval workflow ──────────────────────────────────────────
         "approval.list" => handlers::approval::list(params, ctx).await,
         "approval.respond" => handlers::approval::respond(params, ctx).await,
+        // ─── Plugins ─────────────────────────────────────────────────────────
+        "plugin.list" => handlers::plugins::list(ctx.plugin_manager.clone(), params).await,
+        "plugin.enable" => handlers::plugins::enable(ctx.plugin_manager.clone(), params).await,
+        "plugin.disable" => handlers::plugins::disable(ctx.plugin_manager.clone(), params).await,
+        "plugin.info" => handlers::plugins::info(ctx.plugin_manager.clone(), params).await,
+        m if m.starts_with("plugin.") && m.matches('.').count() >= 2 => {
+            handlers::plugins::call(m, params, ctx).await
+        }
+        // ─── Upstream MCP servers ────────────────────────────────────────────
+        "mcp.status" => handlers::mcp::status(params, ctx).await,
         // ─── Phase 43m: Account Scheduler ────────────────────────────────────
         "scheduler.status" => handlers::scheduler::status(params, ctx).await,
+        "scheduler.enqueue" => handlers::scheduler::enqueue(params, ctx).await,
+        "scheduler.cancel" => handlers::scheduler::cancel(params, ctx).await,
+        "scheduler.reprioritize" => handlers::scheduler::reprioritize(params, ctx).await,
+        "scheduler.acquire" => handlers::scheduler::acquire(params, ctx).await,
+        "scheduler.release" => handlers::scheduler::release(params, ctx).await,
         // ─── Phase 43f: Conversation Threading ───────────────────────────────────
         "threads.start" => handlers::threads::start_thread(ctx, params).await,
         "threads.resume" => handlers::threads::resume_thread(ctx, params).await,
@@ -854,6 +1011,7 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         "automation.list" => handlers::automations::list(params, ctx).await,
         "automation.trigger" => handlers::automations::trigger(params, ctx).await,
         "automation.disable" => handlers::automations::disable(params, ctx).await,
+        "automation.validate" => handlers::automations::validate(params, ctx).await,
         // EV — Session Evals
         "eval.list" => handlers::evals::eval_list(params, ctx).await,
         "eval.run" => handlers::evals::eval_run(params, ctx).await,
@@ -871,6 +1029,7 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         "workflow.create" => handlers::workflow::create(params, ctx).await,
         "workflow.list" => handlers::workflow::list(params, ctx).await,
         "workflow.run" => handlers::workflow::run(params, ctx).await,
+        "workflow.getRun" => handlers::workflow::get_run(params, ctx).await,
         "workflow.delete" => handlers::workflow::delete(params, ctx).await,
 
         // ─── Sprint DD: Tool Sovereignty ──────────────────────────────────────
@@ -906,6 +1065,13 @@ async fn dispatch(method: &str, params: Value, ctx: &AppContext) -> anyhow::Resu
         "lsp.stop" => crate::lsp::handlers::lsp_stop(params, ctx).await,
         "lsp.diagnostics" => crate::lsp::handlers::lsp_diagnostics(params, ctx).await,
         "lsp.completions" => crate::lsp::handlers::lsp_completions(params, ctx).await,
+        "lsp.hover" => crate::lsp::handlers::lsp_hover(params, ctx).await,
+        "lsp.definition" => crate::lsp::handlers::lsp_definition(params, ctx).await,
+        "lsp.references" => crate::lsp::handlers::lsp_references(params, ctx).await,
+        "lsp.rename" => crate::lsp::handlers::lsp_rename(params, ctx).await,
+        "lsp.codeActions" => crate::lsp::handlers::lsp_code_actions(params, ctx).await,
+        "lsp.documentSymbols" => crate::lsp::handlers::lsp_document_symbols(params, ctx).await,
+        "lsp.workspaceSymbols" => crate::lsp::handlers::lsp_workspace_symbols(params, ctx).await,
         "lsp.list" => crate::lsp::handlers::lsp_list_servers(params, ctx).await,
 
         // ─── Sprint L: Browser Tool (Visual & Multimodal) ────────────────────
@@ -1102,6 +1268,12 @@ fn classify_error(e: &anyhow::Error, _method: &str) -> (i32, String) {
     if msg.contains("REPO_NOT_FOUND") {
         return (REPO_NOT_FOUND, "Repo not found".to_string());
     }
+    if msg.contains("API_TOKEN_NOT_FOUND") {
+        return (API_TOKEN_NOT_FOUND_CODE, "API token not found".to_string());
+    }
+    if let Some(detail) = msg.strip_prefix("INVALID_PARAMS: ") {
+        return (INVALID_PARAMS, format!("Invalid params: {detail}"));
+    }
     if msg.contains("PROVIDER_NOT_AVAILABLE") {
         let detail = msg
             .split_once("PROVIDER_NOT_AVAILABLE: ")
@@ -1188,6 +1360,11 @@ fn sanitize_path_in_message(msg: &str) -> String {
 }
 
 fn error_response(id: Value, code: i32, message: &str) -> String {
+    error_value(id, code, message).to_string()
+}
+
+/// JSON-RPC error object with home-directory paths stripped from `message`.
+pub(crate) fn error_value(id: Value, code: i32, message: &str) -> Value {
     let sanitized = sanitize_path_in_message(message);
     let resp = RpcResponse {
         jsonrpc: "2.0",
@@ -1198,5 +1375,5 @@ fn error_response(id: Value, code: i32, message: &str) -> String {
             message: sanitized,
         }),
     };
-    serde_json::to_string(&resp).unwrap_or_default()
+    serde_json::to_value(&resp).unwrap_or_default()
 }
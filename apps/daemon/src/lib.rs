@@ -1,6 +1,7 @@
 pub mod account;
 pub mod agents;
 pub mod analysis;
+pub mod api_tokens;
 pub mod automations;
 pub mod autostart;
 pub mod ci;
@@ -142,6 +143,14 @@ pub struct AppContext {
     pub memory_store: memory::MemoryStore,
     /// Session cost + token metrics store (Sprint PP OB.1).
     pub metrics_store: metrics::MetricsStore,
+    /// Loaded dylib/WASM plugins and their event hooks.
+    pub plugin_manager: Arc<plugins::manager::PluginManager>,
+    /// Tool-call policy; consults `plugin_manager` before its own rules.
+    pub policy_engine: Arc<policy::PolicyEngine>,
+    /// Supervised upstream MCP servers whose tools `clawd` republishes.
+    pub mcp_hub: Arc<mcp::McpHub>,
+    /// Shared `audit.jsonl` writer for tool and plugin method calls.
+    pub audit_log: Arc<storage::event_log::AuditLog>,
 }
 
 impl AppContext {
@@ -152,17 +161,21 @@ impl AppContext {
     pub fn init_scheduler_and_worktrees(mut self, data_dir: &std::path::Path) -> Self {
         let account_pool = Arc::new(AccountPool::new());
         let rate_limit_tracker = Arc::new(RateLimitTracker::new());
-        let fallback_engine = Arc::new(FallbackEngine::new(
-            Arc::clone(&account_pool),
-            Arc::clone(&rate_limit_tracker),
-        ));
+        let fallback_engine = Arc::new(
+            FallbackEngine::new(Arc::clone(&account_pool), Arc::clone(&rate_limit_tracker))
+                .with_events(self.broadcaster.clone()),
+        );
 
         self.worktree_manager = Arc::new(WorktreeManager::new(data_dir));
         self.account_pool = account_pool;
         self.rate_limit_tracker = rate_limit_tracker;
         self.fallback_engine = fallback_engine;
-        self.scheduler_queue = Arc::new(SchedulerQueue::new());
-        self.orchestrator = Arc::new(Orchestrator::new());
+        self.scheduler_queue = Arc::new(SchedulerQueue::with_config(self.config.scheduler.clone()));
+        self.orchestrator = Arc::new(
+            Orchestrator::new()
+                .with_fallback_chains(self.config.fallback.clone())
+                .with_fallback_engine(Arc::clone(&self.fallback_engine)),
+        );
         self
     }
 }
@@ -4,24 +4,40 @@
 /// Registered methods:
 ///   lsp.start          — launch an LSP server for a language + workspace
 ///   lsp.stop           — stop a running LSP server
-///   lsp.diagnostics    — get diagnostics for a file
+///   lsp.diagnostics    — sync a file and return its cached diagnostics
 ///   lsp.completions    — get completions at a cursor position
+///   lsp.hover          — hover text at a cursor position
+///   lsp.definition     — go to definition
+///   lsp.references     — find references
+///   lsp.rename         — edits that rename a symbol (not applied)
+///   lsp.codeActions    — fixes / refactorings for a range (not applied)
+///   lsp.documentSymbols  — outline of a file
+///   lsp.workspaceSymbols — symbol search across the workspace
 ///   lsp.listServers    — list all running LSP server processes
+use crate::api_tokens::repo_within;
+use crate::ipc::event::EventBroadcaster;
+use crate::lsp::model::TextRange;
 use crate::lsp::proxy::LspProxy;
 use crate::AppContext;
 use anyhow::{bail, Result};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::path::Path;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 /// Daemon-global LSP proxy (lazy-initialized on first use).
 static LSP_PROXY: OnceLock<LspProxy> = OnceLock::new();
 
-fn lsp_proxy() -> &'static LspProxy {
+pub fn lsp_proxy() -> &'static LspProxy {
     LSP_PROXY.get_or_init(LspProxy::new)
 }
 
+/// Create the daemon-global proxy with `lsp.diagnosticsChanged` push events.
+/// Called once at startup; has no effect if the proxy already exists.
+pub fn init_lsp_proxy(events: Arc<EventBroadcaster>) {
+    let _ = LSP_PROXY.set(LspProxy::new().with_events(events));
+}
+
 // ─── Param structs ────────────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -45,8 +61,9 @@ struct LspDiagnosticsParams {
     workspace_root: String,
     /// Absolute path to the file to analyse.
     file: String,
-    /// Current file content to send to the LSP server.
-    content: String,
+    /// Current file content to send to the LSP server; read from disk when
+    /// omitted.
+    content: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -62,6 +79,74 @@ struct LspCompletionsParams {
     col: u32,
 }
 
+/// Shared by the cursor-position requests.
+#[derive(Deserialize)]
+struct LspPositionParams {
+    language: String,
+    #[serde(rename = "workspaceRoot")]
+    workspace_root: String,
+    /// Absolute path to the file.
+    file: String,
+    /// 0-based line number.
+    line: u32,
+    /// 0-based column number.
+    col: u32,
+}
+
+#[derive(Deserialize)]
+struct LspReferencesParams {
+    #[serde(flatten)]
+    position: LspPositionParams,
+    #[serde(rename = "includeDeclaration", default = "default_true")]
+    include_declaration: bool,
+}
+
+#[derive(Deserialize)]
+struct LspRenameParams {
+    #[serde(flatten)]
+    position: LspPositionParams,
+    #[serde(rename = "newName")]
+    new_name: String,
+}
+
+#[derive(Deserialize)]
+struct LspCodeActionsParams {
+    #[serde(flatten)]
+    position: LspPositionParams,
+    /// End of the range; defaults to the start position.
+    #[serde(rename = "endLine")]
+    end_line: Option<u32>,
+    #[serde(rename = "endCol")]
+    end_col: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct LspFileParams {
+    language: String,
+    #[serde(rename = "workspaceRoot")]
+    workspace_root: String,
+    /// Absolute path to the file.
+    file: String,
+}
+
+#[derive(Deserialize)]
+struct LspWorkspaceSymbolsParams {
+    language: String,
+    #[serde(rename = "workspaceRoot")]
+    workspace_root: String,
+    query: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl LspPositionParams {
+    fn validate(&self) -> Result<()> {
+        validate_file(&self.workspace_root, &self.file)
+    }
+}
+
 // ─── Path validation ──────────────────────────────────────────────────────────
 
 fn validate_abs_path(path: &str, name: &str) -> Result<()> {
@@ -74,6 +159,17 @@ fn validate_abs_path(path: &str, name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Validate `workspaceRoot` and `file`, and require `file` to resolve inside
+/// the workspace root so a request cannot read or open files elsewhere.
+fn validate_file(workspace_root: &str, file: &str) -> Result<()> {
+    validate_abs_path(workspace_root, "workspaceRoot")?;
+    validate_abs_path(file, "file")?;
+    if !repo_within(file, workspace_root) {
+        bail!("INVALID_PARAMS: file must be inside workspaceRoot");
+    }
+    Ok(())
+}
+
 // ─── Handlers ─────────────────────────────────────────────────────────────────
 
 /// `lsp.start` — spawn (or reconnect to) the LSP server for `language` at
@@ -116,28 +212,26 @@ pub async fn lsp_stop(params: Value, _ctx: &AppContext) -> Result<Value> {
     Ok(json!({ "stopped": true, "language": p.language }))
 }
 
-/// `lsp.diagnostics` — open the file in the LSP server and return any
-/// diagnostics that are published synchronously.
+/// `lsp.diagnostics` — sync the file (from `content`, or from disk when
+/// omitted) and return its diagnostics.
+///
+/// The server keeps the file open, so repeated calls send only what
+/// changed.  Diagnostics are cached from the server's pushes; when the file
+/// changed, the call waits briefly for a fresh set.  Later updates arrive as
+/// `lsp.diagnosticsChanged` events.
 ///
 /// The LSP server must have been started via `lsp.start` first.
 pub async fn lsp_diagnostics(params: Value, _ctx: &AppContext) -> Result<Value> {
     let p: LspDiagnosticsParams = serde_json::from_value(params)?;
-    validate_abs_path(&p.workspace_root, "workspaceRoot")?;
-    validate_abs_path(&p.file, "file")?;
+    validate_file(&p.workspace_root, &p.file)?;
 
     let proxy = lsp_proxy();
-    let language = p.language.clone();
-    let workspace_root = p.workspace_root.clone();
-    let file = p.file.clone();
-    let content = p.content.clone();
-
     let items = tokio::task::spawn_blocking(move || {
-        proxy.get_diagnostics(
-            &language,
-            Path::new(&workspace_root),
-            Path::new(&file),
-            &content,
-        )
+        let (root, file) = (Path::new(&p.workspace_root), Path::new(&p.file));
+        match &p.content {
+            Some(content) => proxy.get_diagnostics(&p.language, root, file, content),
+            None => proxy.diagnostics_from_disk(&p.language, root, file),
+        }
     })
     .await??;
 
@@ -150,8 +244,7 @@ pub async fn lsp_diagnostics(params: Value, _ctx: &AppContext) -> Result<Value>
 /// a prior `lsp.completions` call on the same file).
 pub async fn lsp_completions(params: Value, _ctx: &AppContext) -> Result<Value> {
     let p: LspCompletionsParams = serde_json::from_value(params)?;
-    validate_abs_path(&p.workspace_root, "workspaceRoot")?;
-    validate_abs_path(&p.file, "file")?;
+    validate_file(&p.workspace_root, &p.file)?;
 
     let proxy = lsp_proxy();
     let language = p.language.clone();
@@ -183,3 +276,129 @@ pub async fn lsp_list_servers(_params: Value, _ctx: &AppContext) -> Result<Value
 
     Ok(json!({ "servers": servers }))
 }
+
+/// `lsp.hover` — hover text (type, docs) at the cursor, or `null`.
+pub async fn lsp_hover(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspPositionParams = serde_json::from_value(params)?;
+    p.validate()?;
+    let hover = tokio::task::spawn_blocking(move || {
+        lsp_proxy().hover(
+            &p.language,
+            Path::new(&p.workspace_root),
+            Path::new(&p.file),
+            p.line,
+            p.col,
+        )
+    })
+    .await??;
+    Ok(json!({ "hover": hover }))
+}
+
+/// `lsp.definition` — locations where the symbol at the cursor is defined.
+pub async fn lsp_definition(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspPositionParams = serde_json::from_value(params)?;
+    p.validate()?;
+    let locations = tokio::task::spawn_blocking(move || {
+        lsp_proxy().definition(
+            &p.language,
+            Path::new(&p.workspace_root),
+            Path::new(&p.file),
+            p.line,
+            p.col,
+        )
+    })
+    .await??;
+    Ok(json!({ "locations": locations }))
+}
+
+/// `lsp.references` — every reference to the symbol at the cursor.
+pub async fn lsp_references(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspReferencesParams = serde_json::from_value(params)?;
+    p.position.validate()?;
+    let locations = tokio::task::spawn_blocking(move || {
+        let pos = &p.position;
+        lsp_proxy().references(
+            &pos.language,
+            Path::new(&pos.workspace_root),
+            Path::new(&pos.file),
+            pos.line,
+            pos.col,
+            p.include_declaration,
+        )
+    })
+    .await??;
+    Ok(json!({ "locations": locations }))
+}
+
+/// `lsp.rename` — the workspace edit that renames the symbol at the cursor.
+///
+/// The edit is returned, not applied.
+pub async fn lsp_rename(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspRenameParams = serde_json::from_value(params)?;
+    p.position.validate()?;
+    if p.new_name.trim().is_empty() {
+        bail!("invalid newName: must not be empty");
+    }
+    let edit = tokio::task::spawn_blocking(move || {
+        let pos = &p.position;
+        lsp_proxy().rename(
+            &pos.language,
+            Path::new(&pos.workspace_root),
+            Path::new(&pos.file),
+            pos.line,
+            pos.col,
+            &p.new_name,
+        )
+    })
+    .await??;
+    Ok(json!({ "edit": edit }))
+}
+
+/// `lsp.codeActions` — quick fixes and refactorings for a range.
+pub async fn lsp_code_actions(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspCodeActionsParams = serde_json::from_value(params)?;
+    p.position.validate()?;
+    let actions = tokio::task::spawn_blocking(move || {
+        let pos = &p.position;
+        let range = TextRange {
+            line: pos.line,
+            col: pos.col,
+            end_line: p.end_line.unwrap_or(pos.line),
+            end_col: p.end_col.unwrap_or(pos.col),
+        };
+        lsp_proxy().code_actions(
+            &pos.language,
+            Path::new(&pos.workspace_root),
+            Path::new(&pos.file),
+            range,
+        )
+    })
+    .await??;
+    Ok(json!({ "actions": actions }))
+}
+
+/// `lsp.documentSymbols` — flattened outline of a file.
+pub async fn lsp_document_symbols(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspFileParams = serde_json::from_value(params)?;
+    validate_file(&p.workspace_root, &p.file)?;
+    let symbols = tokio::task::spawn_blocking(move || {
+        lsp_proxy().document_symbols(
+            &p.language,
+            Path::new(&p.workspace_root),
+            Path::new(&p.file),
+        )
+    })
+    .await??;
+    Ok(json!({ "symbols": symbols }))
+}
+
+/// `lsp.workspaceSymbols` — symbols matching `query` across the workspace.
+pub async fn lsp_workspace_symbols(params: Value, _ctx: &AppContext) -> Result<Value> {
+    let p: LspWorkspaceSymbolsParams = serde_json::from_value(params)?;
+    validate_abs_path(&p.workspace_root, "workspaceRoot")?;
+    let symbols = tokio::task::spawn_blocking(move || {
+        lsp_proxy().workspace_symbols(&p.language, Path::new(&p.workspace_root), &p.query)
+    })
+    .await??;
+    Ok(json!({ "symbols": symbols }))
+}
@@ -3,16 +3,22 @@ pub mod handlers;
 /// LSP (Language Server Protocol) proxy — Sprint S, LS.T01–LS.T04.
 ///
 /// Manages per-language LSP server processes, proxies LSP requests from the
-/// daemon's JSON-RPC layer, and exposes diagnostics and completions to Flutter
-/// clients via the `lsp.*` RPC family.
+/// daemon's JSON-RPC layer, and exposes diagnostics, completions, hover,
+/// navigation, rename, code actions and symbols to Flutter clients via the
+/// `lsp.*` RPC family and to agents via the `lsp_*` MCP tools.
 ///
 /// ## Module layout
 ///
-/// - `model`    — data types (LspConfig, LspProcess, DiagnosticItem, CompletionItem)
-/// - `proxy`    — LspProxy: subprocess lifecycle + JSON-RPC-over-stdio transport
+/// - `model`    — data types (LspConfig, LspProcess, DiagnosticItem, CompletionItem,
+///   Location, WorkspaceEdit, CodeAction, SymbolItem, ...)
+/// - `proxy`    — LspProxy: subprocess lifecycle, JSON-RPC-over-stdio transport,
+///   background reader, per-file diagnostics cache and incremental document sync
 /// - `handlers` — RPC handler functions wired into the dispatch table
 pub mod model;
 pub mod proxy;
 
-pub use model::{CompletionItem, DiagSeverity, DiagnosticItem, LspConfig, LspProcess};
+pub use model::{
+    CodeAction, CompletionItem, DiagSeverity, DiagnosticItem, FileEdit, HoverInfo, Location,
+    LspConfig, LspProcess, SymbolItem, TextEdit, TextRange, WorkspaceEdit,
+};
 pub use proxy::LspProxy;
@@ -116,7 +116,7 @@ impl DiagSeverity {
 }
 
 /// A single diagnostic finding returned by an LSP server.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DiagnosticItem {
     /// Absolute file path.
     pub file: String,
@@ -183,6 +183,119 @@ impl CompletionItem {
     }
 }
 
+// ─── Navigation and refactoring ───────────────────────────────────────────────
+
+/// A span of text, 0-based like LSP.  The end position is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TextRange {
+    pub line: u32,
+    pub col: u32,
+    pub end_line: u32,
+    pub end_col: u32,
+}
+
+/// A range in a specific file (definitions, references, symbols).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    /// Absolute file path.
+    pub file: String,
+    #[serde(flatten)]
+    pub range: TextRange,
+}
+
+/// `textDocument/hover` result flattened to plain text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoverInfo {
+    /// Hover text; multiple marked strings are joined with blank lines.
+    pub contents: String,
+    /// The range the hover applies to, when the server reports one.
+    pub range: Option<TextRange>,
+}
+
+/// Replace `range` with `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    #[serde(flatten)]
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+/// Edits to one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEdit {
+    /// Absolute file path.
+    pub file: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// A set of edits across files, as returned by rename and code actions.
+///
+/// The proxy never applies edits itself; callers decide what to write.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceEdit {
+    pub changes: Vec<FileEdit>,
+}
+
+/// A quick fix or refactoring offered by `textDocument/codeAction`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeAction {
+    pub title: String,
+    /// e.g. `"quickfix"`, `"refactor.extract"`.
+    pub kind: Option<String>,
+    pub is_preferred: bool,
+    /// Edits the action makes, if the server computed them up front.
+    pub edit: Option<WorkspaceEdit>,
+    /// Server-side command the action runs instead of (or after) the edit.
+    pub command: Option<String>,
+}
+
+/// A symbol from `textDocument/documentSymbol` or `workspace/symbol`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolItem {
+    pub name: String,
+    /// Kind string (e.g. `"function"`, `"struct"`, `"method"`).
+    pub kind: String,
+    pub detail: Option<String>,
+    /// Enclosing symbol name (document symbols are flattened).
+    pub container: Option<String>,
+    pub location: Location,
+}
+
+impl SymbolItem {
+    /// Map LSP `SymbolKind` integer to a human-readable kind string.
+    pub fn kind_from_lsp_int(kind: u64) -> &'static str {
+        match kind {
+            1 => "file",
+            2 => "module",
+            3 => "namespace",
+            4 => "package",
+            5 => "class",
+            6 => "method",
+            7 => "property",
+            8 => "field",
+            9 => "constructor",
+            10 => "enum",
+            11 => "interface",
+            12 => "function",
+            13 => "variable",
+            14 => "constant",
+            15 => "string",
+            16 => "number",
+            17 => "boolean",
+            18 => "array",
+            19 => "object",
+            20 => "key",
+            21 => "null",
+            22 => "enum_member",
+            23 => "struct",
+            24 => "event",
+            25 => "operator",
+            26 => "type_parameter",
+            _ => "unknown",
+        }
+    }
+}
+
 // ─── LSP wire types (used for parsing server responses) ──────────────────────
 
 /// Minimal JSON-RPC 2.0 message sent to / received from an LSP server via stdio.
@@ -214,6 +327,18 @@ impl LspMessage {
         }
     }
 
+    /// Build a successful response to a request from the server.
+    pub fn response(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id: Some(id),
+            method: None,
+            params: None,
+            result: Some(result),
+            error: None,
+        }
+    }
+
     /// Build a JSON-RPC notification (no `id`).
     pub fn notification(method: &str, params: serde_json::Value) -> Self {
         Self {
@@ -302,6 +427,30 @@ mod tests {
         assert_eq!(CompletionItem::kind_from_lsp_int(999), "value");
     }
 
+    #[test]
+    fn symbol_kind_maps_common_values() {
+        assert_eq!(SymbolItem::kind_from_lsp_int(12), "function");
+        assert_eq!(SymbolItem::kind_from_lsp_int(23), "struct");
+        assert_eq!(SymbolItem::kind_from_lsp_int(0), "unknown");
+    }
+
+    #[test]
+    fn location_serializes_flat() {
+        let loc = Location {
+            file: "/w/a.rs".into(),
+            range: TextRange {
+                line: 1,
+                col: 2,
+                end_line: 1,
+                end_col: 5,
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&loc).unwrap(),
+            serde_json::json!({"file": "/w/a.rs", "line": 1, "col": 2, "end_line": 1, "end_col": 5})
+        );
+    }
+
     // ── LspMessage ────────────────────────────────────────────────────────
 
     #[test]
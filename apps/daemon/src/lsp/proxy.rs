@@ -3,45 +3,77 @@
 ///
 /// Sprint S, LS.T01–LS.T02.
 ///
-/// Each `LspProxy` instance manages a pool of language server processes, one
-/// per (language, workspace_root) pair.  Communication uses JSON-RPC 2.0 over
-/// the subprocess's stdin/stdout (the stdio transport mandated by LSP 3.17).
+/// Each `LspProxy` instance manages a pool of long-lived language server
+/// processes, one per (language, workspace_root) pair.  Communication uses
+/// JSON-RPC 2.0 over the subprocess's stdin/stdout (the stdio transport
+/// mandated by LSP 3.17).
 ///
 /// # Design notes
 ///
-/// - The proxy is intentionally simple: it does synchronous request/response
-///   over stdio.  There is no async LSP push-notification loop — diagnostics
-///   are fetched on demand via `textDocument/diagnostic` or by opening a file
-///   and waiting for `textDocument/publishDiagnostics` notifications.
+/// - Each server has a background reader thread that owns its stdout.  It
+///   routes responses to the waiting request by id, answers server-to-client
+///   requests (`workspace/configuration`, `client/registerCapability`, ...)
+///   and caches `textDocument/publishDiagnostics` per file.
+/// - Requests may be issued concurrently; each waits on its own channel with
+///   a timeout, so one slow request cannot wedge the proxy.
 /// - Each `start_server` call spawns a child process and performs the LSP
 ///   `initialize` / `initialized` handshake.
-/// - Requests are serialized with a monotonically increasing id so that
-///   responses can be matched even when the server sends notifications
-///   interleaved with responses.
+/// - Documents are opened once and kept in sync.  The proxy remembers the
+///   text it last sent, so a change goes out as one incremental `didChange`
+///   range when the server supports it (full text otherwise) and unchanged
+///   files send nothing.  Edits on disk reach the server through
+///   [`LspProxy::sync_changed_files`], which the repo watcher calls.
+/// - When a file's cached diagnostics change, an `lsp.diagnosticsChanged`
+///   push event is broadcast (see [`LspProxy::with_events`]).
+/// - Position-based requests (hover, definition, references, rename, code
+///   actions, document symbols) sync the file from disk first.  Rename and
+///   code actions return edits; nothing is written to disk here.
+use crate::ipc::event::EventBroadcaster;
 use crate::lsp::model::{
-    CompletionItem, DiagSeverity, DiagnosticItem, LspConfig, LspMessage, LspProcess,
+    CodeAction, CompletionItem, DiagSeverity, DiagnosticItem, FileEdit, HoverInfo, Location,
+    LspConfig, LspMessage, LspProcess, SymbolItem, TextEdit, TextRange, WorkspaceEdit,
 };
 use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStdin, ChildStdout, Stdio};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
-// ─── Internal server state ────────────────────────────────────────────────────
+/// How long a request waits for its response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
-struct ServerState {
-    process: Child,
+/// How long `get_diagnostics` waits for the server to publish after a change.
+const DIAGNOSTICS_WAIT: Duration = Duration::from_millis(1500);
+
+/// `TextDocumentSyncKind.Incremental`.
+const SYNC_INCREMENTAL: u64 = 2;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// ─── Transport ────────────────────────────────────────────────────────────────
+
+/// A document the server has open, with the text it was last sent.
+struct OpenDoc {
+    version: i64,
+    text: String,
+}
+
+/// Write half of a server connection plus the documents it has open.
+struct Writer {
     stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
-    language: String,
-    workspace_root: String,
-    next_id: AtomicU64,
+    /// Open documents by URI.
+    open_docs: HashMap<String, OpenDoc>,
 }
 
-impl ServerState {
+impl Writer {
     /// Send a JSON-RPC message via LSP stdio transport.
     ///
     /// The LSP stdio transport framing is:
@@ -58,73 +90,385 @@ impl ServerState {
         self.stdin.flush()?;
         Ok(())
     }
+}
 
-    /// Read the next complete LSP message from the server's stdout.
-    ///
-    /// Parses the `Content-Length` header, then reads exactly that many bytes
-    /// as the JSON body.
-    fn recv(&mut self) -> Result<LspMessage> {
-        // Read headers until blank line
-        let mut content_length: Option<usize> = None;
+/// Read the next complete LSP message from the server's stdout.
+///
+/// Parses the `Content-Length` header, then reads exactly that many bytes
+/// as the JSON body.  Returns `None` when the server closed its stdout.
+fn read_message(stdout: &mut BufReader<ChildStdout>) -> Result<Option<LspMessage>> {
+    // Read headers until blank line
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        if stdout.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length: ") {
+            content_length = Some(value.trim().parse()?);
+        }
+    }
+    let length = content_length.context("LSP response missing Content-Length header")?;
+
+    // Read exactly `length` bytes of JSON body
+    let mut body = vec![0u8; length];
+    stdout.read_exact(&mut body)?;
+
+    let msg: LspMessage = serde_json::from_slice(&body).context("failed to parse LSP JSON body")?;
+    Ok(Some(msg))
+}
+
+/// Requests waiting for a response, by id.
+#[derive(Default)]
+struct Pending {
+    /// Set once the reader has stopped; no response will arrive.
+    closed: bool,
+    waiters: HashMap<u64, mpsc::Sender<LspMessage>>,
+}
+
+// ─── Diagnostics cache ────────────────────────────────────────────────────────
+
+struct Published {
+    /// Bumped on every publish, so waiters see re-publishes of the same set.
+    generation: u64,
+    items: Vec<DiagnosticItem>,
+}
+
+/// Latest `publishDiagnostics` per file for one server.
+struct DiagnosticsCache {
+    language: String,
+    workspace_root: String,
+    events: Option<Arc<EventBroadcaster>>,
+    /// File path → latest diagnostics.
+    files: Mutex<HashMap<String, Published>>,
+    updated: Condvar,
+}
+
+impl DiagnosticsCache {
+    fn generation(&self, file: &str) -> u64 {
+        lock(&self.files).get(file).map_or(0, |p| p.generation)
+    }
+
+    /// Store `items` for `file`, broadcasting `lsp.diagnosticsChanged` when
+    /// they differ from the cached set.
+    fn publish(&self, file: &str, items: Vec<DiagnosticItem>) {
+        let mut files = lock(&self.files);
+        let entry = files.entry(file.to_string()).or_insert(Published {
+            generation: 0,
+            items: Vec::new(),
+        });
+        entry.generation += 1;
+        let changed = entry.generation == 1 || entry.items != items;
+        entry.items = items;
+        if changed {
+            self.broadcast(file, &entry.items);
+        }
+        drop(files);
+        self.updated.notify_all();
+    }
+
+    /// Forget `file` (closed or deleted); clients are told it is clean.
+    fn remove(&self, file: &str) {
+        let removed = lock(&self.files).remove(file);
+        if removed.is_some_and(|p| !p.items.is_empty()) {
+            self.broadcast(file, &[]);
+        }
+    }
+
+    /// Wait up to `timeout` for a publish for `file` newer than `seen`, then
+    /// return whatever is cached.
+    fn wait_newer(&self, file: &str, seen: u64, timeout: Duration) -> Vec<DiagnosticItem> {
+        let deadline = Instant::now() + timeout;
+        let mut files = lock(&self.files);
         loop {
-            let mut line = String::new();
-            self.stdout.read_line(&mut line)?;
-            let line = line.trim_end_matches(['\r', '\n']);
-            if line.is_empty() {
-                break;
+            let now = Instant::now();
+            match files.get(file) {
+                Some(p) if p.generation > seen => break,
+                _ if now >= deadline => break,
+                _ => {}
             }
-            if let Some(value) = line.strip_prefix("Content-Length: ") {
-                content_length = Some(value.trim().parse()?);
+            files = match self.updated.wait_timeout(files, deadline - now) {
+                Ok((guard, _)) => guard,
+                Err(e) => e.into_inner().0,
+            };
+        }
+        files.get(file).map(|p| p.items.clone()).unwrap_or_default()
+    }
+
+    fn broadcast(&self, file: &str, items: &[DiagnosticItem]) {
+        if let Some(events) = &self.events {
+            events.broadcast(
+                "lsp.diagnosticsChanged",
+                json!({
+                    "language": self.language,
+                    "workspaceRoot": self.workspace_root,
+                    "file": file,
+                    "diagnostics": items,
+                }),
+            );
+        }
+    }
+}
+
+// ─── Reader thread ────────────────────────────────────────────────────────────
+
+/// Owns the server's stdout for the lifetime of the process.
+struct Reader {
+    language: String,
+    writer: Arc<Mutex<Writer>>,
+    pending: Arc<Mutex<Pending>>,
+    diagnostics: Arc<DiagnosticsCache>,
+}
+
+impl Reader {
+    fn run(self, mut stdout: BufReader<ChildStdout>) {
+        loop {
+            match read_message(&mut stdout) {
+                Ok(Some(msg)) => self.handle(msg),
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(language = %self.language, "LSP read failed: {e:#}");
+                    break;
+                }
             }
         }
-        let length = content_length.context("LSP response missing Content-Length header")?;
+        let mut pending = lock(&self.pending);
+        pending.closed = true;
+        // Dropping the senders fails every in-flight request.
+        pending.waiters.clear();
+        debug!(language = %self.language, "LSP reader stopped");
+    }
 
-        // Read exactly `length` bytes of JSON body
-        let mut body = vec![0u8; length];
-        use std::io::Read;
-        self.stdout.read_exact(&mut body)?;
+    fn handle(&self, msg: LspMessage) {
+        match (msg.id.clone(), msg.method.as_deref()) {
+            // Server-to-client request — answer so the server does not stall.
+            (Some(id), Some(method)) => {
+                debug!(method, "lsp server request");
+                let result = server_request_result(method, msg.params.as_ref());
+                if let Err(e) = lock(&self.writer).send(&LspMessage::response(id, result)) {
+                    warn!(method, "failed to answer LSP server request: {e:#}");
+                }
+            }
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let params = msg.params.unwrap_or_default();
+                let Some(uri) = params.get("uri").and_then(|u| u.as_str()) else {
+                    return;
+                };
+                let file = uri_to_path(uri);
+                self.diagnostics
+                    .publish(&file, parse_diagnostics(&file, &params));
+            }
+            (None, method) => debug!(?method, "lsp notification (skipped)"),
+            (Some(id), None) => {
+                let waiter = id
+                    .as_u64()
+                    .and_then(|id| lock(&self.pending).waiters.remove(&id));
+                match waiter {
+                    Some(tx) => {
+                        let _ = tx.send(msg);
+                    }
+                    None => debug!(%id, "lsp response without a waiting request"),
+                }
+            }
+        }
+    }
+}
 
-        let msg: LspMessage =
-            serde_json::from_slice(&body).context("failed to parse LSP JSON body")?;
-        Ok(msg)
+/// Result for a request the server sends to us.  `workspace/configuration`
+/// gets one `null` per item (use defaults); everything else gets `null`.
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|p| p.get("items"))
+                .and_then(|i| i.as_array())
+                .map_or(0, |i| i.len());
+            Value::Array(vec![Value::Null; items])
+        }
+        _ => Value::Null,
     }
+}
+
+// ─── Internal server state ────────────────────────────────────────────────────
 
-    /// Send a request and wait for the matching response (skipping notifications).
-    fn request(&mut self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+struct ServerState {
+    process: Mutex<Child>,
+    writer: Arc<Mutex<Writer>>,
+    language: String,
+    workspace_root: String,
+    next_id: AtomicU64,
+    pending: Arc<Mutex<Pending>>,
+    diagnostics: Arc<DiagnosticsCache>,
+    /// The server accepts incremental `didChange` ranges.
+    incremental: AtomicBool,
+}
+
+impl ServerState {
+    fn send(&self, msg: &LspMessage) -> Result<()> {
+        lock(&self.writer).send(msg)
+    }
+
+    /// Send a request and wait for the matching response.
+    fn request(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        let req = LspMessage::request(id, method, params);
-        self.send(&req)?;
+        let (tx, rx) = mpsc::channel();
+        {
+            let mut pending = lock(&self.pending);
+            if pending.closed {
+                bail!("LSP server for {} has exited", self.language);
+            }
+            pending.waiters.insert(id, tx);
+        }
+        if let Err(e) = self.send(&LspMessage::request(id, method, params)) {
+            lock(&self.pending).waiters.remove(&id);
+            return Err(e);
+        }
 
-        // Consume messages until we get a response matching our id
-        loop {
-            let msg = self.recv()?;
-            // Skip notifications (no id field)
-            if msg.id.is_none() {
-                debug!(method = ?msg.method, "lsp notification (skipped)");
-                continue;
+        let msg = match rx.recv_timeout(REQUEST_TIMEOUT) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) => {
+                lock(&self.pending).waiters.remove(&id);
+                bail!(
+                    "LSP request {method} timed out after {}s",
+                    REQUEST_TIMEOUT.as_secs()
+                );
             }
-            // Match by id
-            let resp_id = msg.id.as_ref().and_then(|v| v.as_u64()).unwrap_or(u64::MAX);
-            if resp_id != id {
-                debug!(resp_id, expected = id, "lsp response id mismatch, skipping");
-                continue;
+            Err(RecvTimeoutError::Disconnected) => {
+                bail!("LSP server for {} exited during {method}", self.language)
+            }
+        };
+        if let Some(error) = msg.error {
+            bail!("LSP error response: {error}");
+        }
+        // `"result": null` is a valid "nothing here" answer.
+        Ok(msg.result.unwrap_or(Value::Null))
+    }
+
+    /// Bring the server's copy of `file` up to `content`: `didOpen` the
+    /// first time, then `didChange` — one incremental range when supported.
+    ///
+    /// Returns `false` (and sends nothing) if the server already has it.
+    fn sync_document(&self, file: &Path, content: &str) -> Result<bool> {
+        let uri = file_uri(file);
+        let mut writer = lock(&self.writer);
+        let msg = match writer.open_docs.get_mut(&uri) {
+            Some(doc) if doc.text == content => return Ok(false),
+            Some(doc) => {
+                doc.version += 1;
+                let change = if self.incremental.load(Ordering::Relaxed) {
+                    incremental_change(&doc.text, content)
+                } else {
+                    json!({ "text": content })
+                };
+                doc.text = content.to_string();
+                LspMessage::notification(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": doc.version },
+                        "contentChanges": [change]
+                    }),
+                )
             }
-            if let Some(error) = msg.error {
-                bail!("LSP error response: {error}");
+            None => {
+                writer.open_docs.insert(
+                    uri.clone(),
+                    OpenDoc {
+                        version: 1,
+                        text: content.to_string(),
+                    },
+                );
+                LspMessage::notification(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": self.language,
+                            "version": 1,
+                            "text": content
+                        }
+                    }),
+                )
             }
-            return msg.result.context("LSP response has no result");
+        };
+        writer.send(&msg)?;
+        Ok(true)
+    }
+
+    /// Sync `file` from disk (opening it if needed).
+    fn sync_from_disk(&self, file: &Path) -> Result<bool> {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("cannot read {}", file.display()))?;
+        self.sync_document(file, &content)
+    }
+
+    fn is_open(&self, file: &Path) -> bool {
+        lock(&self.writer).open_docs.contains_key(&file_uri(file))
+    }
+
+    /// `didClose` `file` and drop its cached diagnostics.
+    fn close_document(&self, file: &Path) -> Result<()> {
+        let uri = file_uri(file);
+        let mut writer = lock(&self.writer);
+        if writer.open_docs.remove(&uri).is_none() {
+            return Ok(());
         }
+        writer.send(&LspMessage::notification(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        ))?;
+        drop(writer);
+        self.diagnostics.remove(&file.to_string_lossy());
+        Ok(())
+    }
+
+    /// Sync `content`, then return `file`'s diagnostics — waiting briefly for
+    /// a fresh publish when the server was sent something new.
+    fn diagnostics_for(&self, file: &Path, content: &str) -> Result<Vec<DiagnosticItem>> {
+        let key = file.to_string_lossy();
+        let seen = self.diagnostics.generation(&key);
+        let timeout = if self.sync_document(file, content)? || seen == 0 {
+            DIAGNOSTICS_WAIT
+        } else {
+            Duration::ZERO
+        };
+        Ok(self.diagnostics.wait_newer(&key, seen, timeout))
     }
 
-    /// Allocate the next request id.
-    #[allow(dead_code)]
-    fn alloc_id(&self) -> u64 {
-        self.next_id.fetch_add(1, Ordering::SeqCst)
+    /// Sync `file` from disk, then send a request positioned in it.
+    fn position_request(
+        &self,
+        method: &str,
+        file: &Path,
+        line: u32,
+        col: u32,
+        extra: Value,
+    ) -> Result<Value> {
+        self.sync_from_disk(file)?;
+        let mut params = json!({
+            "textDocument": { "uri": file_uri(file) },
+            "position": { "line": line, "character": col }
+        });
+        if let (Some(params), Value::Object(extra)) = (params.as_object_mut(), extra) {
+            params.extend(extra);
+        }
+        self.request(method, params)
     }
 
     /// Check whether the child process has exited.
-    fn is_alive(&mut self) -> bool {
-        self.process.try_wait().ok().flatten().is_none()
+    fn is_alive(&self) -> bool {
+        lock(&self.process).try_wait().ok().flatten().is_none()
+    }
+
+    fn info(&self) -> LspProcess {
+        LspProcess {
+            language: self.language.clone(),
+            pid: lock(&self.process).id(),
+            workspace_root: self.workspace_root.clone(),
+        }
     }
 }
 
@@ -137,9 +481,11 @@ impl ServerState {
 #[derive(Clone)]
 pub struct LspProxy {
     /// Map key: `"<language>:<workspace_root>"`.
-    servers: Arc<Mutex<HashMap<String, ServerState>>>,
+    servers: Arc<Mutex<HashMap<String, Arc<ServerState>>>>,
     /// Available LSP configs (built-ins + user overrides).
     configs: Vec<LspConfig>,
+    /// Receives `lsp.diagnosticsChanged`.
+    events: Option<Arc<EventBroadcaster>>,
 }
 
 impl Default for LspProxy {
@@ -151,10 +497,7 @@ impl Default for LspProxy {
 impl LspProxy {
     /// Create a new proxy with the default built-in language server configs.
     pub fn new() -> Self {
-        Self {
-            servers: Arc::new(Mutex::new(HashMap::new())),
-            configs: LspConfig::builtin_defaults(),
-        }
+        Self::with_configs(LspConfig::builtin_defaults())
     }
 
     /// Create a proxy with a custom set of configs (built-ins are not added).
@@ -162,9 +505,16 @@ impl LspProxy {
         Self {
             servers: Arc::new(Mutex::new(HashMap::new())),
             configs,
+            events: None,
         }
     }
 
+    /// Broadcast `lsp.diagnosticsChanged` for servers started from now on.
+    pub fn with_events(mut self, events: Arc<EventBroadcaster>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     fn server_key(language: &str, workspace_root: &Path) -> String {
         format!("{}:{}", language, workspace_root.display())
     }
@@ -174,6 +524,54 @@ impl LspProxy {
         self.configs.iter().find(|c| c.language == language)
     }
 
+    /// Language whose configured file extensions include `file`'s extension.
+    pub fn language_for(&self, file: &Path) -> Option<String> {
+        let ext = format!(".{}", file.extension()?.to_string_lossy());
+        LspConfig::for_extension(&self.configs, &ext).map(|c| c.language.clone())
+    }
+
+    /// The running server that covers `file`: one for the file's language
+    /// whose workspace root contains it (the deepest root wins).
+    ///
+    /// Returns `(language, workspace_root)`.
+    pub fn running_server_for(&self, file: &Path) -> Option<(String, PathBuf)> {
+        let language = self.language_for(file)?;
+        let servers = self.servers.lock().ok()?;
+        servers
+            .values()
+            .filter(|s| s.language == language && file.starts_with(&s.workspace_root))
+            .filter(|s| s.is_alive())
+            .map(|s| PathBuf::from(&s.workspace_root))
+            .max_by_key(|root| root.components().count())
+            .map(|root| (language, root))
+    }
+
+    /// Run `f` against the running server for (`language`, `workspace_root`).
+    ///
+    /// The pool lock is released before `f` runs, so requests to different
+    /// servers (and concurrent requests to one server) do not serialize.
+    fn with_server<T>(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        f: impl FnOnce(&ServerState) -> Result<T>,
+    ) -> Result<T> {
+        let key = Self::server_key(language, workspace_root);
+        let state = self
+            .servers
+            .lock()
+            .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "LSP server not running for {language} at {}",
+                    workspace_root.display()
+                )
+            })?;
+        f(&state)
+    }
+
     /// Spawn an LSP server for `language` at `workspace_root` and perform the
     /// `initialize` / `initialized` handshake.
     ///
@@ -188,13 +586,9 @@ impl LspProxy {
             .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?;
 
         // Return existing healthy server
-        if let Some(existing) = servers.get_mut(&key) {
+        if let Some(existing) = servers.get(&key) {
             if existing.is_alive() {
-                return Ok(LspProcess {
-                    language: existing.language.clone(),
-                    pid: existing.process.id(),
-                    workspace_root: existing.workspace_root.clone(),
-                });
+                return Ok(existing.info());
             }
             // Dead server — remove and restart
             warn!(language, "LSP server process exited, restarting");
@@ -227,14 +621,43 @@ impl LspProxy {
         let stdin = child.stdin.take().context("child stdin not available")?;
         let stdout = child.stdout.take().context("child stdout not available")?;
         let pid = child.id();
+        let root = workspace_root.to_string_lossy().to_string();
 
-        let mut state = ServerState {
-            process: child,
+        let writer = Arc::new(Mutex::new(Writer {
             stdin,
-            stdout: BufReader::new(stdout),
+            open_docs: HashMap::new(),
+        }));
+        let pending = Arc::new(Mutex::new(Pending::default()));
+        let diagnostics = Arc::new(DiagnosticsCache {
+            language: language.to_string(),
+            workspace_root: root.clone(),
+            events: self.events.clone(),
+            files: Mutex::new(HashMap::new()),
+            updated: Condvar::new(),
+        });
+        let reader = Reader {
             language: language.to_string(),
-            workspace_root: workspace_root.to_string_lossy().to_string(),
+            writer: Arc::clone(&writer),
+            pending: Arc::clone(&pending),
+            diagnostics: Arc::clone(&diagnostics),
+        };
+        let spawned = std::thread::Builder::new()
+            .name(format!("lsp-{language}"))
+            .spawn(move || reader.run(BufReader::new(stdout)));
+        if let Err(e) = spawned {
+            let _ = child.kill();
+            bail!("failed to start LSP reader for {language}: {e}");
+        }
+
+        let state = ServerState {
+            process: Mutex::new(child),
+            writer,
+            language: language.to_string(),
+            workspace_root: root,
             next_id: AtomicU64::new(1),
+            pending,
+            diagnostics,
+            incremental: AtomicBool::new(false),
         };
 
         // ── LSP initialize handshake ──────────────────────────────────────────
@@ -254,9 +677,20 @@ impl LspProxy {
                         }
                     },
                     "hover": { "contentFormat": ["plaintext"] },
+                    "definition": { "linkSupport": true },
+                    "references": {},
+                    "rename": { "prepareSupport": false },
+                    "codeAction": {
+                        "codeActionLiteralSupport": {
+                            "codeActionKind": { "valueSet": ["quickfix", "refactor", "source"] }
+                        }
+                    },
+                    "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
                     "publishDiagnostics": { "relatedInformation": false }
                 },
                 "workspace": {
+                    "symbol": {},
+                    "configuration": true,
                     "applyEdit": false,
                     "workspaceEdit": { "documentChanges": false }
                 }
@@ -266,21 +700,25 @@ impl LspProxy {
                 .to_string_lossy() }]
         });
 
-        state.request("initialize", init_params)?;
-
-        // Send the required `initialized` notification (no response expected)
-        let initialized_notif = LspMessage::notification("initialized", serde_json::json!({}));
-        state.send(&initialized_notif)?;
+        let initialized = state.request("initialize", init_params).and_then(|result| {
+            state
+                .incremental
+                .store(sync_kind(&result) == SYNC_INCREMENTAL, Ordering::Relaxed);
+            // Send the required `initialized` notification (no response expected)
+            state.send(&LspMessage::notification(
+                "initialized",
+                serde_json::json!({}),
+            ))
+        });
+        if let Err(e) = initialized {
+            let _ = lock(&state.process).kill();
+            return Err(e.context(format!("LSP initialize failed for {language}")));
+        }
 
         debug!(language, pid, "LSP server initialized");
 
-        let process_info = LspProcess {
-            language: language.to_string(),
-            pid,
-            workspace_root: workspace_root.to_string_lossy().to_string(),
-        };
-
-        servers.insert(key, state);
+        let process_info = state.info();
+        servers.insert(key, Arc::new(state));
         Ok(process_info)
     }
 
@@ -290,17 +728,18 @@ impl LspProxy {
     /// then removes the process from the pool.
     pub fn stop_server(&self, language: &str, workspace_root: &Path) -> Result<()> {
         let key = Self::server_key(language, workspace_root);
-        let mut servers = self
+        let removed = self
             .servers
             .lock()
-            .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?;
+            .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?
+            .remove(&key);
 
-        if let Some(mut state) = servers.remove(&key) {
+        if let Some(state) = removed {
             // Best-effort shutdown handshake — ignore errors (server may have already exited)
             let _ = state.request("shutdown", serde_json::Value::Null);
             let exit_notif = LspMessage::notification("exit", serde_json::json!({}));
             let _ = state.send(&exit_notif);
-            let _ = state.process.wait();
+            let _ = lock(&state.process).wait();
             info!(language, "LSP server stopped");
         }
         Ok(())
@@ -308,37 +747,49 @@ impl LspProxy {
 
     /// List all currently running LSP server processes.
     pub fn list_servers(&self) -> Result<Vec<LspProcess>> {
-        let mut servers = self
+        let servers = self
             .servers
             .lock()
             .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?;
         let procs = servers
-            .values_mut()
-            .filter_map(|s| {
-                if s.is_alive() {
-                    Some(LspProcess {
-                        language: s.language.clone(),
-                        pid: s.process.id(),
-                        workspace_root: s.workspace_root.clone(),
-                    })
-                } else {
-                    None
-                }
-            })
+            .values()
+            .filter(|s| s.is_alive())
+            .map(|s| s.info())
             .collect();
         Ok(procs)
     }
 
-    /// Open a file in the LSP server and collect any diagnostics published in
-    /// response.
+    /// Push on-disk changes to every server that has one of `paths` open.
     ///
-    /// Uses `textDocument/didOpen` to notify the server of the file content.
-    /// The server may publish `textDocument/publishDiagnostics` notifications
-    /// synchronously (e.g. rust-analyzer does this for simple errors); we
-    /// collect those by draining the stdout buffer briefly.
+    /// Called by the repo watcher.  Modified files are re-synced
+    /// (incrementally when the server supports it); deleted files are
+    /// closed.  Files no server has open are left to the servers' own
+    /// indexing.
+    pub fn sync_changed_files(&self, paths: &[PathBuf]) {
+        let servers: Vec<Arc<ServerState>> = match self.servers.lock() {
+            Ok(servers) => servers.values().cloned().collect(),
+            Err(_) => return,
+        };
+        for server in servers {
+            for path in paths.iter().filter(|p| server.is_open(p)) {
+                let synced = match std::fs::read_to_string(path) {
+                    Ok(content) => server.sync_document(path, &content).map(|_| ()),
+                    Err(_) => server.close_document(path),
+                };
+                if let Err(e) = synced {
+                    warn!(language = %server.language, file = %path.display(), "LSP sync failed: {e:#}");
+                }
+            }
+        }
+    }
+
+    /// Sync `content` for `file` and return its diagnostics.
     ///
-    /// Returns an empty list if no diagnostics are published within the read
-    /// window — callers should poll periodically for a complete picture.
+    /// The first call opens the file (`textDocument/didOpen`); later calls
+    /// send only what changed.  Diagnostics come from the cache the reader
+    /// fills from `textDocument/publishDiagnostics`.  When something new was
+    /// sent, waits briefly for the server to re-publish; callers wanting the
+    /// final picture can listen for `lsp.diagnosticsChanged`.
     pub fn get_diagnostics(
         &self,
         language: &str,
@@ -346,68 +797,22 @@ impl LspProxy {
         file: &Path,
         content: &str,
     ) -> Result<Vec<DiagnosticItem>> {
-        let key = Self::server_key(language, workspace_root);
-        let mut servers = self
-            .servers
-            .lock()
-            .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?;
-        let state = servers.get_mut(&key).ok_or_else(|| {
-            anyhow::anyhow!(
-                "LSP server not running for {language} at {}",
-                workspace_root.display()
-            )
-        })?;
-
-        let file_uri = format!("file://{}", file.display());
-
-        // Notify the server that we have opened this file
-        let lang_id = language.to_string();
-        let did_open = LspMessage::notification(
-            "textDocument/didOpen",
-            serde_json::json!({
-                "textDocument": {
-                    "uri": file_uri,
-                    "languageId": lang_id,
-                    "version": 1,
-                    "text": content
-                }
-            }),
-        );
-        state.send(&did_open)?;
-
-        // Drain any immediately-available messages looking for publishDiagnostics
-        let mut diagnostics: Vec<DiagnosticItem> = Vec::new();
-        let file_str = file.to_string_lossy().to_string();
-
-        // Read up to 20 messages non-blockingly to collect diagnostics
-        // (We use a short timeout loop — stdin is a blocking pipe so we peek
-        //  at what's already buffered)
-        for _ in 0..20 {
-            // Check if there's data available without blocking
-            // Using fill_buf to see if anything is buffered
-            let has_data = state
-                .stdout
-                .fill_buf()
-                .map(|b| !b.is_empty())
-                .unwrap_or(false);
-            if !has_data {
-                break;
-            }
-
-            let msg = match state.recv() {
-                Ok(m) => m,
-                Err(_) => break,
-            };
-
-            if msg.method.as_deref() == Some("textDocument/publishDiagnostics") {
-                if let Some(params) = msg.params {
-                    let items = parse_diagnostics(&file_str, &params);
-                    diagnostics.extend(items);
-                }
-            }
-        }
+        self.with_server(language, workspace_root, |state| {
+            state.diagnostics_for(file, content)
+        })
+    }
 
-        Ok(diagnostics)
+    /// Like [`get_diagnostics`](Self::get_diagnostics), with the file's
+    /// current content read from disk.
+    pub fn diagnostics_from_disk(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+    ) -> Result<Vec<DiagnosticItem>> {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("cannot read {}", file.display()))?;
+        self.get_diagnostics(language, workspace_root, file, &content)
     }
 
     /// Request completions at a given cursor position in the open file.
@@ -422,30 +827,228 @@ impl LspProxy {
         line: u32,
         col: u32,
     ) -> Result<Vec<CompletionItem>> {
-        let key = Self::server_key(language, workspace_root);
-        let mut servers = self
-            .servers
-            .lock()
-            .map_err(|_| anyhow::anyhow!("LSP proxy mutex poisoned"))?;
-        let state = servers.get_mut(&key).ok_or_else(|| {
-            anyhow::anyhow!(
-                "LSP server not running for {language} at {}",
-                workspace_root.display()
-            )
-        })?;
+        self.with_server(language, workspace_root, |state| {
+            let file_uri = file_uri(file);
+            let result = state.request(
+                "textDocument/completion",
+                json!({
+                    "textDocument": { "uri": file_uri },
+                    "position": { "line": line, "character": col },
+                    "context": { "triggerKind": 1 }
+                }),
+            )?;
+            Ok(parse_completions(&result))
+        })
+    }
 
-        let file_uri = format!("file://{}", file.display());
-        let result = state.request(
-            "textDocument/completion",
-            serde_json::json!({
-                "textDocument": { "uri": file_uri },
-                "position": { "line": line, "character": col },
-                "context": { "triggerKind": 1 }
-            }),
-        )?;
+    /// `textDocument/hover` — type and documentation at a position.
+    pub fn hover(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+        line: u32,
+        col: u32,
+    ) -> Result<Option<HoverInfo>> {
+        self.with_server(language, workspace_root, |state| {
+            let result =
+                state.position_request("textDocument/hover", file, line, col, Value::Null)?;
+            Ok(parse_hover(&result))
+        })
+    }
 
-        Ok(parse_completions(&result))
+    /// `textDocument/definition` — where the symbol at a position is defined.
+    pub fn definition(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+        line: u32,
+        col: u32,
+    ) -> Result<Vec<Location>> {
+        self.with_server(language, workspace_root, |state| {
+            let result =
+                state.position_request("textDocument/definition", file, line, col, Value::Null)?;
+            Ok(parse_locations(&result))
+        })
     }
+
+    /// `textDocument/references` — every use of the symbol at a position.
+    pub fn references(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+        line: u32,
+        col: u32,
+        include_declaration: bool,
+    ) -> Result<Vec<Location>> {
+        self.with_server(language, workspace_root, |state| {
+            let result = state.position_request(
+                "textDocument/references",
+                file,
+                line,
+                col,
+                json!({ "context": { "includeDeclaration": include_declaration } }),
+            )?;
+            Ok(parse_locations(&result))
+        })
+    }
+
+    /// `textDocument/rename` — the edits that rename the symbol at a position.
+    ///
+    /// Returns an empty edit when the server finds nothing to rename.
+    pub fn rename(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+        line: u32,
+        col: u32,
+        new_name: &str,
+    ) -> Result<WorkspaceEdit> {
+        self.with_server(language, workspace_root, |state| {
+            let result = state.position_request(
+                "textDocument/rename",
+                file,
+                line,
+                col,
+                json!({ "newName": new_name }),
+            )?;
+            Ok(parse_workspace_edit(&result))
+        })
+    }
+
+    /// `textDocument/codeAction` — fixes and refactorings for a range.
+    pub fn code_actions(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+        range: TextRange,
+    ) -> Result<Vec<CodeAction>> {
+        self.with_server(language, workspace_root, |state| {
+            state.sync_from_disk(file)?;
+            let result = state.request(
+                "textDocument/codeAction",
+                json!({
+                    "textDocument": { "uri": file_uri(file) },
+                    "range": {
+                        "start": { "line": range.line, "character": range.col },
+                        "end": { "line": range.end_line, "character": range.end_col }
+                    },
+                    "context": { "diagnostics": [] }
+                }),
+            )?;
+            Ok(parse_code_actions(&result))
+        })
+    }
+
+    /// `textDocument/documentSymbol` — the file's outline, flattened.
+    pub fn document_symbols(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        file: &Path,
+    ) -> Result<Vec<SymbolItem>> {
+        self.with_server(language, workspace_root, |state| {
+            state.sync_from_disk(file)?;
+            let result = state.request(
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": file_uri(file) } }),
+            )?;
+            Ok(parse_symbols(&file.to_string_lossy(), &result))
+        })
+    }
+
+    /// `workspace/symbol` — symbols matching `query` anywhere in the workspace.
+    pub fn workspace_symbols(
+        &self,
+        language: &str,
+        workspace_root: &Path,
+        query: &str,
+    ) -> Result<Vec<SymbolItem>> {
+        self.with_server(language, workspace_root, |state| {
+            let result = state.request("workspace/symbol", json!({ "query": query }))?;
+            Ok(parse_symbols("", &result))
+        })
+    }
+}
+
+// ─── Document sync ────────────────────────────────────────────────────────────
+
+/// `TextDocumentSyncKind` from an `initialize` result; the capability is
+/// either the kind itself or an options object with a `change` field.
+fn sync_kind(init_result: &Value) -> u64 {
+    let sync = &init_result["capabilities"]["textDocumentSync"];
+    sync.as_u64()
+        .or_else(|| sync.get("change").and_then(|c| c.as_u64()))
+        .unwrap_or(0)
+}
+
+/// The single-range `didChange` entry that turns `old` into `new`: the span
+/// between their common prefix and common suffix.
+fn incremental_change(old: &str, new: &str) -> Value {
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    json!({
+        "range": {
+            "start": lsp_position(old, prefix),
+            "end": lsp_position(old, old.len() - suffix)
+        },
+        "text": &new[prefix..new.len() - suffix]
+    })
+}
+
+/// LSP position of byte `offset` in `text`.  Columns count UTF-16 code
+/// units, the protocol's default encoding.
+fn lsp_position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count()
+    })
+}
+
+// ─── URIs ─────────────────────────────────────────────────────────────────────
+
+fn file_uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// `file:///a%20b/c.rs` → `/a b/c.rs`.  Non-file URIs are returned unchanged.
+fn uri_to_path(uri: &str) -> String {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return uri.to_string();
+    };
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
 }
 
 // ─── Parsing helpers ──────────────────────────────────────────────────────────
@@ -528,3 +1131,306 @@ fn parse_completions(result: &serde_json::Value) -> Vec<CompletionItem> {
         })
         .collect()
 }
+
+/// Parse an LSP `Range` object.
+fn parse_range(range: &Value) -> Option<TextRange> {
+    let start = range.get("start")?;
+    let end = range.get("end")?;
+    Some(TextRange {
+        line: start.get("line")?.as_u64()? as u32,
+        col: start.get("character")?.as_u64()? as u32,
+        end_line: end.get("line")?.as_u64()? as u32,
+        end_col: end.get("character")?.as_u64()? as u32,
+    })
+}
+
+/// Parse a `Location` or `LocationLink` (uses the target selection range).
+fn parse_location(value: &Value) -> Option<Location> {
+    if let Some(uri) = value.get("targetUri").and_then(|u| u.as_str()) {
+        let range = value
+            .get("targetSelectionRange")
+            .or_else(|| value.get("targetRange"))?;
+        return Some(Location {
+            file: uri_to_path(uri),
+            range: parse_range(range)?,
+        });
+    }
+    Some(Location {
+        file: uri_to_path(value.get("uri")?.as_str()?),
+        range: parse_range(value.get("range")?)?,
+    })
+}
+
+/// Parse a definition / references result: `null`, one location, or a list.
+fn parse_locations(result: &Value) -> Vec<Location> {
+    match result {
+        Value::Array(items) => items.iter().filter_map(parse_location).collect(),
+        Value::Object(_) => parse_location(result).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Flatten `MarkupContent`, `MarkedString` or `MarkedString[]` to text.
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(hover_text)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(o) => o
+            .get("value")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Parse a `textDocument/hover` result.
+fn parse_hover(result: &Value) -> Option<HoverInfo> {
+    let contents = hover_text(result.get("contents")?);
+    if contents.is_empty() {
+        return None;
+    }
+    Some(HoverInfo {
+        contents,
+        range: result.get("range").and_then(parse_range),
+    })
+}
+
+fn parse_text_edits(edits: &Value) -> Vec<TextEdit> {
+    edits
+        .as_array()
+        .map(|edits| {
+            edits
+                .iter()
+                .filter_map(|e| {
+                    Some(TextEdit {
+                        range: parse_range(e.get("range")?)?,
+                        new_text: e.get("newText")?.as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a `WorkspaceEdit` in either the `changes` or `documentChanges` form.
+/// Resource operations (create / rename / delete file) are skipped.
+fn parse_workspace_edit(result: &Value) -> WorkspaceEdit {
+    let mut changes: Vec<FileEdit> = Vec::new();
+    if let Some(map) = result.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in map {
+            changes.push(FileEdit {
+                file: uri_to_path(uri),
+                edits: parse_text_edits(edits),
+            });
+        }
+    }
+    if let Some(docs) = result.get("documentChanges").and_then(|c| c.as_array()) {
+        for doc in docs {
+            let Some(uri) = doc.pointer("/textDocument/uri").and_then(|u| u.as_str()) else {
+                continue;
+            };
+            changes.push(FileEdit {
+                file: uri_to_path(uri),
+                edits: parse_text_edits(doc.get("edits").unwrap_or(&Value::Null)),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.file.cmp(&b.file));
+    WorkspaceEdit { changes }
+}
+
+/// Parse a `textDocument/codeAction` result (`Command` or `CodeAction` items).
+fn parse_code_actions(result: &Value) -> Vec<CodeAction> {
+    let Some(items) = result.as_array() else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let title = item.get("title")?.as_str()?.to_string();
+            // A bare `Command` has a string `command`; a `CodeAction` nests it.
+            let command = match item.get("command") {
+                Some(Value::String(c)) => Some(c.clone()),
+                Some(c) => c.get("command").and_then(|c| c.as_str()).map(String::from),
+                None => None,
+            };
+            Some(CodeAction {
+                title,
+                kind: item.get("kind").and_then(|k| k.as_str()).map(String::from),
+                is_preferred: item
+                    .get("isPreferred")
+                    .and_then(|p| p.as_bool())
+                    .unwrap_or(false),
+                edit: item.get("edit").map(parse_workspace_edit),
+                command,
+            })
+        })
+        .collect()
+}
+
+/// Parse `DocumentSymbol[]` (hierarchical, flattened depth-first with the
+/// parent as `container`) or `SymbolInformation[]` / `WorkspaceSymbol[]`.
+///
+/// `file` is used for document symbols, which carry no URI of their own.
+fn parse_symbols(file: &str, result: &Value) -> Vec<SymbolItem> {
+    fn walk(file: &str, container: Option<&str>, items: &[Value], out: &mut Vec<SymbolItem>) {
+        for item in items {
+            let Some(name) = item.get("name").and_then(|n| n.as_str()) else {
+                continue;
+            };
+            let kind = SymbolItem::kind_from_lsp_int(
+                item.get("kind").and_then(|k| k.as_u64()).unwrap_or(0),
+            )
+            .to_string();
+            let location = match item.get("location") {
+                // SymbolInformation / WorkspaceSymbol (range may be omitted).
+                Some(loc) => Location {
+                    file: uri_to_path(loc.get("uri").and_then(|u| u.as_str()).unwrap_or(file)),
+                    range: loc.get("range").and_then(parse_range).unwrap_or_default(),
+                },
+                // DocumentSymbol.
+                None => Location {
+                    file: file.to_string(),
+                    range: item
+                        .get("selectionRange")
+                        .or_else(|| item.get("range"))
+                        .and_then(parse_range)
+                        .unwrap_or_default(),
+                },
+            };
+            out.push(SymbolItem {
+                name: name.to_string(),
+                kind,
+                detail: item
+                    .get("detail")
+                    .and_then(|d| d.as_str())
+                    .map(String::from),
+                container: item
+                    .get("containerName")
+                    .and_then(|c| c.as_str())
+                    .or(container)
+                    .map(String::from),
+                location,
+            });
+            if let Some(children) = item.get("children").and_then(|c| c.as_array()) {
+                walk(file, Some(name), children, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    if let Some(items) = result.as_array() {
+        walk(file, None, items, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_to_path_decodes_escapes() {
+        assert_eq!(uri_to_path("file:///a%20b/c.rs"), "/a b/c.rs");
+        assert_eq!(uri_to_path("file:///a/100%"), "/a/100%");
+        assert_eq!(uri_to_path("untitled:x"), "untitled:x");
+    }
+
+    #[test]
+    fn locations_accept_single_list_and_links() {
+        let range =
+            json!({"start": {"line": 1, "character": 2}, "end": {"line": 1, "character": 4}});
+        let single = json!({"uri": "file:///w/a.rs", "range": range});
+        assert_eq!(parse_locations(&single).len(), 1);
+        assert_eq!(parse_locations(&json!([single, single])).len(), 2);
+        let link = json!([{"targetUri": "file:///w/b.rs", "targetRange": range, "targetSelectionRange": range}]);
+        assert_eq!(parse_locations(&link)[0].file, "/w/b.rs");
+        assert!(parse_locations(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn hover_flattens_marked_strings() {
+        let hover = json!({"contents": ["fn main()", {"language": "rust", "value": "docs"}]});
+        assert_eq!(parse_hover(&hover).unwrap().contents, "fn main()\n\ndocs");
+        let markup = json!({"contents": {"kind": "plaintext", "value": "x: u32"}});
+        assert_eq!(parse_hover(&markup).unwrap().contents, "x: u32");
+        assert!(parse_hover(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn workspace_edit_reads_both_forms() {
+        let edit = json!({"newText": "y", "range": {"start": {"line": 0, "character": 4}, "end": {"line": 0, "character": 5}}});
+        let changes = json!({"changes": {"file:///w/a.rs": [edit]}});
+        let docs = json!({"documentChanges": [
+            {"textDocument": {"uri": "file:///w/a.rs", "version": 1}, "edits": [edit]},
+            {"kind": "create", "uri": "file:///w/new.rs"}
+        ]});
+        assert_eq!(parse_workspace_edit(&changes), parse_workspace_edit(&docs));
+        assert_eq!(
+            parse_workspace_edit(&changes).changes[0].edits[0].new_text,
+            "y"
+        );
+    }
+
+    #[test]
+    fn document_symbols_are_flattened_with_container() {
+        let range =
+            json!({"start": {"line": 0, "character": 0}, "end": {"line": 3, "character": 1}});
+        let result = json!([{
+            "name": "Foo", "kind": 23, "range": range, "selectionRange": range,
+            "children": [{"name": "bar", "kind": 6, "range": range, "selectionRange": range}]
+        }]);
+        let symbols = parse_symbols("/w/a.rs", &result);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[1].name, "bar");
+        assert_eq!(symbols[1].container.as_deref(), Some("Foo"));
+        assert_eq!(symbols[1].location.file, "/w/a.rs");
+    }
+
+    #[test]
+    fn incremental_change_covers_only_the_edit() {
+        let change = incremental_change("fn a() {}\nfn b() {}\n", "fn a() {}\nfn bee() {}\n");
+        assert_eq!(
+            change,
+            json!({
+                "range": {
+                    "start": { "line": 1, "character": 4 },
+                    "end": { "line": 1, "character": 4 }
+                },
+                "text": "ee"
+            })
+        );
+
+        let change = incremental_change("one\ntwo\nthree", "one\nthree");
+        // Common prefix "one\nt" and suffix "hree": "wo\nt" is removed.
+        assert_eq!(
+            change["range"]["start"],
+            json!({ "line": 1, "character": 1 })
+        );
+        assert_eq!(change["range"]["end"], json!({ "line": 2, "character": 1 }));
+        assert_eq!(change["text"], "");
+    }
+
+    #[test]
+    fn incremental_change_counts_utf16_columns() {
+        // '🦀' is two UTF-16 code units.
+        let change = incremental_change("let s = \"🦀\";", "let s = \"🦀!\";");
+        assert_eq!(change["range"]["start"]["character"], 11);
+        assert_eq!(change["text"], "!");
+    }
+
+    #[test]
+    fn sync_kind_reads_number_or_options() {
+        let kind =
+            |sync: Value| sync_kind(&json!({ "capabilities": { "textDocumentSync": sync } }));
+        assert_eq!(kind(json!(2)), 2);
+        assert_eq!(kind(json!({ "openClose": true, "change": 1 })), 1);
+        assert_eq!(sync_kind(&json!({ "capabilities": {} })), 0);
+    }
+}
@@ -199,18 +199,28 @@ enum Command {
     /// Examples:
     ///   clawd pair
     Pair,
-    /// Manage the daemon auth token.
+    /// Manage the daemon auth token and scoped API tokens.
     ///
-    /// Show or display the auth token used to authenticate clients.
+    /// Show or display the auth token used to authenticate clients, or issue
+    /// tokens limited to specific RPC methods and repos.
     ///
     /// Examples:
     ///   clawd token show
     ///   clawd token qr
     ///   clawd token qr --relay
+    ///   clawd token create ci --method 'tasks.*' --repo . --expires 30d
     Token {
         #[command(subcommand)]
         cmd: TokenCmd,
     },
+    /// Serve clawd's MCP tools and resources to MCP clients.
+    ///
+    /// Examples:
+    ///   clawd mcp serve --stdio
+    Mcp {
+        #[command(subcommand)]
+        cmd: McpCmd,
+    },
     /// Manage projects (workspaces containing multiple repos).
     ///
     /// Projects group multiple git repositories under one workspace.
@@ -528,6 +538,65 @@ enum TokenCmd {
         #[arg(long)]
         relay: bool,
     },
+    /// Create a scoped API token (requires running daemon).
+    ///
+    /// The token may only call RPC methods matching one of the `--method`
+    /// globs and, with `--repo`, only touch those repos. The secret is printed
+    /// once and cannot be retrieved again.
+    ///
+    /// Examples:
+    ///   clawd token create ci --method 'tasks.*' --method session.getMessages
+    ///   clawd token create dashboard --method '*.list' --repo ~/src/app --expires 30d
+    Create {
+        /// Label shown in `clawd token list`
+        name: String,
+        /// RPC method or glob the token may call (repeatable)
+        #[arg(long = "method", required = true)]
+        methods: Vec<String>,
+        /// Repo the token is limited to (repeatable; default: any repo)
+        #[arg(long = "repo")]
+        repos: Vec<String>,
+        /// Lifetime, e.g. 30d, 12h, 45m (default: never expires)
+        #[arg(long)]
+        expires: Option<String>,
+    },
+    /// List scoped API tokens (requires running daemon).
+    ///
+    /// Examples:
+    ///   clawd token list
+    ///   clawd token list --json
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Revoke a scoped API token (requires running daemon).
+    ///
+    /// Takes effect immediately, including on open connections.
+    ///
+    /// Examples:
+    ///   clawd token revoke 01J9Z3K4R8T6V2M5X7B0C1D2E3
+    Revoke {
+        /// Token id from `clawd token list`
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum McpCmd {
+    /// Serve MCP over stdin/stdout (requires running daemon).
+    ///
+    /// For MCP clients that launch servers as subprocesses. Messages are
+    /// relayed to the daemon's Streamable-HTTP endpoint (/api/v1/mcp), which
+    /// HTTP clients can also use directly.
+    ///
+    /// Example `mcpServers` entry:
+    ///   { "clawd": { "command": "clawd", "args": ["mcp", "serve", "--stdio"] } }
+    Serve {
+        /// Speak newline-delimited JSON-RPC on stdin/stdout
+        #[arg(long, required = true)]
+        stdio: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -915,7 +984,17 @@ async fn main() -> Result<()> {
     // Init once — must happen before any tracing calls.
     let log_level = args.log.as_deref().unwrap_or("info").to_owned();
     let log_format = std::env::var("CLAWD_LOG_FORMAT").unwrap_or_else(|_| "pretty".to_string());
-    let _file_guard = setup_logging(&log_level, args.log_file.as_deref(), &log_format);
+    // stdout carries the MCP protocol for `clawd mcp serve --stdio`.
+    let _file_guard = if matches!(args.command, Some(Command::Mcp { .. })) {
+        tracing_subscriber::fmt()
+            .with_env_filter(args.log.as_deref().unwrap_or("warn"))
+            .with_writer(std::io::stderr)
+            .compact()
+            .init();
+        None
+    } else {
+        setup_logging(&log_level, args.log_file.as_deref(), &log_format)
+    };
 
     let quiet = args.quiet;
     match args.command {
@@ -959,6 +1038,35 @@ async fn main() -> Result<()> {
             match cmd {
                 TokenCmd::Show => run_token_show(&config)?,
                 TokenCmd::Qr { relay } => run_token_qr(&config, relay)?,
+                TokenCmd::Create {
+                    name,
+                    methods,
+                    repos,
+                    expires,
+                } => {
+                    clawd::cli::token::cmd_create(
+                        name,
+                        methods,
+                        repos,
+                        expires,
+                        &config.data_dir,
+                        config.port,
+                    )
+                    .await?
+                }
+                TokenCmd::List { json } => {
+                    clawd::cli::token::cmd_list(json, &config.data_dir, config.port).await?
+                }
+                TokenCmd::Revoke { id } => {
+                    clawd::cli::token::cmd_revoke(id, &config.data_dir, config.port).await?
+                }
+            }
+        }
+        Some(Command::Mcp { cmd }) => {
+            let config =
+                DaemonConfig::new(None, args.data_dir, Some("error".to_string()), None, None);
+            match cmd {
+                McpCmd::Serve { .. } => clawd::cli::mcp::serve_stdio(&config.data_dir).await?,
             }
         }
         Some(Command::Project(cmd)) => {
@@ -2290,13 +2398,22 @@ async fn run_server(
         }
     };
 
-    let broadcaster = Arc::new(EventBroadcaster::new());
+    let event_epoch = storage
+        .next_event_epoch()
+        .await
+        .context("failed to advance the event id epoch")?;
+    let broadcaster = Arc::new(EventBroadcaster::new().with_epoch(event_epoch));
+    clawd::lsp::handlers::init_lsp_proxy(broadcaster.clone());
     let repo_registry = Arc::new(RepoRegistry::new(broadcaster.clone()));
-    let session_manager = Arc::new(SessionManager::new(
-        storage.clone(),
-        broadcaster.clone(),
-        config.data_dir.clone(),
-    ));
+    let session_manager = Arc::new(
+        SessionManager::new(
+            storage.clone(),
+            broadcaster.clone(),
+            config.data_dir.clone(),
+        )
+        .with_http_providers(config.http_providers())
+        .with_routing(config.routing.clone()),
+    );
 
     let recovered = storage.recover_stale_sessions().await.unwrap_or(0);
     if recovered > 0 {
@@ -2396,11 +2513,25 @@ async fn run_server(
     let account_pool = std::sync::Arc::new(clawd::scheduler::accounts::AccountPool::new());
     let rate_limit_tracker =
         std::sync::Arc::new(clawd::scheduler::rate_limits::RateLimitTracker::new());
-    let fallback_engine = std::sync::Arc::new(clawd::scheduler::fallback::FallbackEngine::new(
-        std::sync::Arc::clone(&account_pool),
-        std::sync::Arc::clone(&rate_limit_tracker),
-    ));
-    let scheduler_queue = std::sync::Arc::new(clawd::scheduler::queue::SchedulerQueue::new());
+    let fallback_engine = std::sync::Arc::new(
+        clawd::scheduler::fallback::FallbackEngine::new(
+            std::sync::Arc::clone(&account_pool),
+            std::sync::Arc::clone(&rate_limit_tracker),
+        )
+        .with_events(broadcaster.clone()),
+    );
+    let orchestrator = std::sync::Arc::new(
+        clawd::agents::orchestrator::Orchestrator::new()
+            .with_fallback_chains(config.fallback.clone())
+            .with_fallback_engine(std::sync::Arc::clone(&fallback_engine)),
+    );
+    let scheduler_queue = std::sync::Arc::new(
+        clawd::scheduler::queue::SchedulerQueue::load(
+            storage.clone_pool(),
+            config.scheduler.clone(),
+        )
+        .await?,
+    );
 
     // ── Version bump watcher (D64.T16) ───────────────────────────────────────
     let version_watcher = std::sync::Arc::new(clawd::doctor::version_watcher::VersionWatcher::new(
@@ -2420,6 +2551,43 @@ async fn run_server(
         warn!(err = %e, "metrics store migration failed");
     }
 
+    // ── Plugins + tool-call policy ───────────────────────────────────────────
+    let claw_dir = config.data_dir.join(".claw");
+    let mut plugin_manager = clawd::plugins::manager::PluginManager::new(claw_dir.join("plugins"))
+        .with_call_timeout(std::time::Duration::from_millis(
+            config.plugins.call_timeout_ms,
+        ))
+        .with_wasm_limits(clawd::plugins::wasm_runtime::WasmLimits {
+            fuel: config.plugins.wasm_fuel,
+            memory_bytes: usize::try_from(config.plugins.wasm_memory_mb * 1024 * 1024)
+                .unwrap_or(usize::MAX),
+            ..Default::default()
+        })
+        .with_events(broadcaster.clone());
+    if let Some(ref pubkey) = config.plugins.signer_pubkey {
+        plugin_manager = plugin_manager.with_signer_pubkey(pubkey.clone());
+    }
+    let plugin_manager = Arc::new(plugin_manager);
+    if let Err(e) = plugin_manager.load_all().await {
+        warn!(err = %e, "failed to load plugins");
+    }
+    plugin_manager.start_event_dispatcher(&broadcaster);
+    // Held until shutdown; dropping it stops hot reload.
+    let _plugin_watcher = config
+        .plugins
+        .hot_reload
+        .then(|| {
+            clawd::plugins::watcher::PluginWatcher::start(
+                plugin_manager.clone(),
+                std::time::Duration::from_millis(config.plugins.reload_debounce_ms),
+            )
+        })
+        .flatten();
+    let policy_engine =
+        Arc::new(clawd::policy::PolicyEngine::load(&claw_dir).with_plugins(plugin_manager.clone()));
+    let mcp_hub =
+        Arc::new(clawd::mcp::McpHub::new(&policy_engine).with_events(broadcaster.clone()));
+
     // ── Connectivity (Sprint JJ) ──────────────────────────────────────────────
     let quality = clawd::connectivity::new_shared_quality();
     let peer_registry = clawd::connectivity::direct::new_registry();
@@ -2443,13 +2611,14 @@ async fn run_server(
         rate_limit_tracker,
         fallback_engine,
         scheduler_queue,
-        orchestrator: std::sync::Arc::new(clawd::agents::orchestrator::Orchestrator::new()),
+        orchestrator,
         token_tracker,
         metrics: std::sync::Arc::new(clawd::metrics::DaemonMetrics::new()),
         version_watcher: version_watcher.clone(),
         ide_bridge: clawd::ide::new_shared_bridge(),
         provider_sessions: clawd::agents::provider_session::new_shared_registry(),
         recovery_mode: no_migrate,
+        // Repo automations are loaded as `repo.open` registers each repo.
         automation_engine: clawd::automations::engine::AutomationEngine::new(
             clawd::automations::builtins::all(),
         ),
@@ -2457,15 +2626,63 @@ async fn run_server(
         peer_registry,
         memory_store,
         metrics_store,
+        plugin_manager: plugin_manager.clone(),
+        policy_engine,
+        mcp_hub,
+        audit_log: Arc::new(clawd::storage::event_log::AuditLog::new(&config.data_dir)),
     });
 
+    // ── WASM plugins run daemon tools through the MCP dispatcher ────────────
+    {
+        let weak = Arc::downgrade(&ctx);
+        let handle = tokio::runtime::Handle::current();
+        // A host call is bounded like the callback that makes it, so a
+        // plugin call never waits on a tool longer than its own timeout.
+        let limit = plugin_manager.call_timeout();
+        plugin_manager.set_tool_runner(Arc::new(move |plugin: &str, tool: &str, input| {
+            let ctx = weak
+                .upgrade()
+                .ok_or_else(|| anyhow::anyhow!("daemon is shutting down"))?;
+            let dispatcher = clawd::mcp::dispatch::McpDispatcher::new(ctx);
+            let caller = clawd::plugins::manager::plugin_session_id(plugin);
+            let call = dispatcher.dispatch(tool, input, Some(caller.clone()), Some(caller));
+            handle
+                .block_on(async { tokio::time::timeout(limit, call).await })
+                .map_err(|_| anyhow::anyhow!("tool '{tool}' timed out"))?
+        }));
+    }
+
+    // ── Upstream MCP servers (.claw/mcp-servers.json) ────────────────────────
+    match clawd::mcp::McpServersConfig::load(&config.data_dir) {
+        Ok(servers) => {
+            let sampler = Arc::new(clawd::mcp::DaemonSampler::new(ctx.clone()));
+            ctx.mcp_hub.start(servers, Some(sampler));
+        }
+        Err(e) => warn!(err = %e, "upstream MCP servers not started"),
+    }
+
     // ── Spawn automation engine dispatcher (Sprint CC CA.1) ──────────────────
     {
         let engine = Arc::clone(&ctx.automation_engine);
         let ctx_for_auto = (*ctx).clone();
-        clawd::automations::engine::AutomationEngine::start_dispatcher(engine, ctx_for_auto);
+        clawd::automations::engine::AutomationEngine::start_dispatcher(
+            Arc::clone(&engine),
+            ctx_for_auto.clone(),
+        );
+        clawd::automations::engine::AutomationEngine::start_cron_scheduler(engine, ctx_for_auto);
     }
 
+    // ── Flag workflow runs orphaned by a previous crash (Sprint DD) ──────────
+    match clawd::workflows::runner::mark_interrupted(ctx.storage.pool()).await {
+        Ok(0) => {}
+        Ok(n) => info!(
+            count = n,
+            "workflow runs interrupted — resume with workflow.run"
+        ),
+        Err(e) => warn!(err = %e, "failed to flag interrupted workflow runs"),
+    }
+    clawd::workflows::triggers::start_dispatcher((*ctx).clone());
+
     // ── Spawn version bump watcher (D64.T16) ─────────────────────────────────
     version_watcher.spawn();
 
@@ -2510,6 +2727,16 @@ async fn run_server(
         }
     }
 
+    // REST API on 127.0.0.1:4301 — non-fatal if the port is taken.
+    {
+        let rest_ctx = ctx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = clawd::rest::start_rest_server(rest_ctx).await {
+                warn!(err = %e, "REST API not started");
+            }
+        });
+    }
+
     // Spawn relay AFTER ctx is built so it can dispatch inbound RPC frames
     // through the full IPC handler and forward push events to remote clients.
     {
@@ -2519,6 +2746,8 @@ async fn run_server(
 
     let run_result = clawd::ipc::run(ctx).await;
 
+    plugin_manager.shutdown().await;
+
     // ── WAL checkpoint on clean shutdown (Sprint Z — Z.3) ────────────────────
     if let Err(e) =
         clawd::perf::wal_tuning::checkpoint_wal(storage_for_shutdown.pool(), "TRUNCATE").await
@@ -10,17 +10,20 @@ use serde_json::Value;
 
 /// The set of MCP capabilities that `clawd` can advertise as a server.
 ///
-/// `tools` and `resources` are enabled as of Sprint BB PV.12.
-/// `prompts` and `sampling` are reserved for future protocol expansions.
+/// `tools`, `resources` (with subscriptions) and `prompts` are served.
+/// `sampling` is a client-side capability: clawd never offers it as a
+/// server, but advertises it to upstream servers that may send
+/// `sampling/createMessage` (`McpClient::connect_with_sampling`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClawdCapabilities {
     /// `tools` — clawd exposes the task-management tool catalogue.
     pub tools: bool,
-    /// `resources` — clawd exposes sessions, tasks, messages, and repo files.
+    /// `resources` — clawd exposes sessions, tasks, messages, and repo files,
+    /// and notifies subscribers when they change.
     pub resources: bool,
-    /// `prompts` — not yet supported.
+    /// `prompts` — workflow recipes and compiled instructions.
     pub prompts: bool,
-    /// `sampling` — not yet supported (LLM sampling delegation).
+    /// `sampling` — completions requested by upstream servers.
     pub sampling: bool,
 }
 
@@ -28,8 +31,8 @@ impl Default for ClawdCapabilities {
     fn default() -> Self {
         Self {
             tools: true,
-            resources: false,
-            prompts: false,
+            resources: true,
+            prompts: true,
             sampling: false,
         }
     }
@@ -47,10 +50,16 @@ impl ClawdCapabilities {
             cap.insert("tools".into(), serde_json::json!({ "listChanged": false }));
         }
         if self.resources {
-            cap.insert("resources".into(), serde_json::json!({}));
+            cap.insert(
+                "resources".into(),
+                serde_json::json!({ "subscribe": true, "listChanged": false }),
+            );
         }
         if self.prompts {
-            cap.insert("prompts".into(), serde_json::json!({}));
+            cap.insert(
+                "prompts".into(),
+                serde_json::json!({ "listChanged": false }),
+            );
         }
         if self.sampling {
             cap.insert("sampling".into(), serde_json::json!({}));
@@ -113,10 +122,16 @@ mod tests {
     }
 
     #[test]
-    fn default_has_tools() {
+    fn default_has_tools_resources_and_prompts() {
         let defaults = ClawdCapabilities::default();
         assert!(defaults.tools);
-        assert!(!defaults.resources);
+        assert!(defaults.resources);
+        assert!(defaults.prompts);
+        assert!(!defaults.sampling, "sampling is a client capability");
+        let v = defaults.to_mcp_value();
+        assert_eq!(v["resources"]["subscribe"], true);
+        assert!(v["prompts"].is_object());
+        assert!(v.get("sampling").is_none());
     }
 
     #[test]
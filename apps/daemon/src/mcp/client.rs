@@ -1,23 +1,71 @@
-/// MCP client — connects to upstream MCP servers via stdio subprocess.
+/// MCP client — connects to upstream MCP servers.
 ///
-/// `McpClient` spawns a child process (the MCP server binary) and communicates
-/// with it over its stdin/stdout pipes using the JSON-RPC 2.0 MCP protocol.
-/// Each request gets a monotonically incrementing integer ID.
+/// `McpClient` talks JSON-RPC 2.0 MCP to one server over the transport its
+/// config names:
+///
+/// - **Stdio** — spawns the server binary and exchanges one JSON object per
+///   line over its stdin/stdout pipes.
+/// - **Streamable HTTP** — POSTs each message to the endpoint and reads the
+///   reply from a JSON or `text/event-stream` body, echoing the server's
+///   `Mcp-Session-Id`.  A server that rejects the `initialize` POST with a
+///   4xx is retried over HTTP+SSE, as the spec suggests for older servers.
+/// - **HTTP+SSE** (MCP 2024-11-05) — GETs an event stream, waits for its
+///   `endpoint` event and POSTs messages there; replies arrive on the stream.
+/// - **WebSocket** — one JSON-RPC message per text frame.
+///
+/// When the connection drops (process exit, closed stream, expired HTTP
+/// session) the client reconnects with backoff and runs the handshake again.
+/// A request that never reached the server is retried once on the new
+/// connection; one that may have been delivered fails instead, so tool calls
+/// are not run twice.
+///
+/// Requests from the server are answered too: `ping`, and
+/// `sampling/createMessage` when the config opts in and the client was
+/// connected with a `SamplingHandler` (`connect_with_sampling`).  Anything
+/// else gets "method not found".
 ///
 /// Trust levels: `Trusted` servers are invoked as-is.  `Untrusted` server
-/// responses are scanned for prompt-injection patterns before being returned
-/// to callers.
+/// responses — tool results, and the descriptions and input schemas from
+/// `tools/list` — are scanned for prompt-injection patterns before being
+/// returned to callers, whatever the transport.
 use anyhow::{Context, Result};
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout};
-use tokio::sync::Mutex;
-use tracing::{debug, warn};
-
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info, warn};
+
+use super::sampling::SamplingHandler;
 use super::tools::McpToolDef;
+use super::transport::{
+    McpTransport, MCP_INTERNAL_ERROR, MCP_METHOD_NOT_FOUND, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use crate::session::http::SseDecoder;
+
+/// Connecting plus the `initialize` handshake must finish within this.
+const INIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for one response — long enough for slow tool calls.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Reconnect attempts after a dropped connection, and the first delay
+/// between them (doubled after each failure).
+const RECONNECT_ATTEMPTS: u32 = 4;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(200);
+
+const SESSION_HEADER: &str = "mcp-session-id";
 
 // ─── Trust level ─────────────────────────────────────────────────────────────
 
@@ -39,14 +87,22 @@ pub enum McpTrustLevel {
 pub struct McpServerConfig {
     /// Display name for logging and UI.
     pub name: String,
+    /// How to reach the server.  `command`, `args` and `env` apply to
+    /// `Stdio`; `headers` to the others.
+    pub transport: McpTransport,
     /// Executable to run (e.g. `"npx"`, `"python"`, `"/usr/local/bin/my-mcp"`).
     pub command: String,
     /// Arguments passed to the command.
     pub args: Vec<String>,
     /// Environment variables to inject into the child process.
     pub env: HashMap<String, String>,
+    /// Extra headers sent with every HTTP request and the WebSocket upgrade
+    /// (e.g. `Authorization`).
+    pub headers: HashMap<String, String>,
     /// Trust level — controls whether prompt-injection scanning is applied.
     pub trust: McpTrustLevel,
+    /// Whether the server may request completions (`sampling/createMessage`).
+    pub sampling: bool,
 }
 
 // ─── Prompt-injection patterns ────────────────────────────────────────────────
@@ -91,23 +147,360 @@ fn sanitize_value(v: Value) -> Value {
     }
 }
 
-// ─── McpClient ────────────────────────────────────────────────────────────────
+// ─── Connection plumbing ──────────────────────────────────────────────────────
 
-/// A live connection to one upstream MCP server process.
-pub struct McpClient {
+/// The connection dropped.  `delivered` is whether the request may already
+/// have reached the server, in which case it is not retried.
+#[derive(Debug)]
+struct Disconnected {
+    reason: String,
+    delivered: bool,
+}
+
+impl std::fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MCP connection lost: {}", self.reason)
+    }
+}
+
+impl std::error::Error for Disconnected {}
+
+fn disconnected(reason: impl std::fmt::Display, delivered: bool) -> anyhow::Error {
+    Disconnected {
+        reason: reason.to_string(),
+        delivered,
+    }
+    .into()
+}
+
+/// A Streamable HTTP server refused the `initialize` POST — it may only
+/// speak HTTP+SSE.
+#[derive(Debug)]
+struct Rejected(reqwest::StatusCode);
+
+impl std::fmt::Display for Rejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MCP server rejected the request with {}", self.0)
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// Requests waiting for replies that arrive on a separate read side
+/// (stdout, the SSE stream, WebSocket frames).
+struct Inbox {
+    /// `None` once the read side has closed.
+    pending: std::sync::Mutex<Option<HashMap<u64, oneshot::Sender<Value>>>>,
+    /// Requests the server sends us.
+    requests: mpsc::UnboundedSender<Value>,
+}
+
+impl Inbox {
+    fn new(requests: mpsc::UnboundedSender<Value>) -> Arc<Self> {
+        Arc::new(Self {
+            pending: std::sync::Mutex::new(Some(HashMap::new())),
+            requests,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<HashMap<u64, oneshot::Sender<Value>>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register interest in the reply to `id`.
+    fn expect(&self, id: u64) -> Result<oneshot::Receiver<Value>> {
+        let (tx, rx) = oneshot::channel();
+        match self.lock().as_mut() {
+            Some(pending) => {
+                pending.insert(id, tx);
+                Ok(rx)
+            }
+            None => Err(disconnected("connection closed", false)),
+        }
+    }
+
+    fn forget(&self, id: u64) {
+        if let Some(pending) = self.lock().as_mut() {
+            pending.remove(&id);
+        }
+    }
+
+    /// Route one raw message (or batch) from the server: replies to their
+    /// request, server requests to `requests`.  Notifications are ignored.
+    fn deliver(&self, server: &str, raw: &str) {
+        let msgs = match serde_json::from_str::<Value>(raw) {
+            Ok(Value::Array(items)) => items,
+            Ok(single) => vec![single],
+            Err(e) => {
+                warn!(server = %server, err = %e, "MCP server sent invalid JSON — ignored");
+                return;
+            }
+        };
+        for msg in msgs {
+            if is_server_request(&msg) {
+                let _ = self.requests.send(msg);
+                continue;
+            }
+            let id = msg
+                .get("id")
+                .and_then(Value::as_u64)
+                .filter(|_| msg.get("method").is_none());
+            let Some(id) = id else {
+                debug!(server = %server, method = ?msg.get("method"), "MCP server message ignored");
+                continue;
+            };
+            if let Some(tx) = self.lock().as_mut().and_then(|p| p.remove(&id)) {
+                let _ = tx.send(msg);
+            }
+        }
+    }
+
+    /// The read side ended; fail everything still waiting.
+    fn close(&self) {
+        self.lock().take();
+    }
+}
+
+/// A background reader, stopped when its connection is dropped.
+struct ReaderTask(AbortHandle);
+
+impl Drop for ReaderTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+
+/// One open connection to the server.
+enum Link {
+    Stdio {
+        /// Killed when the link is dropped.
+        _child: Child,
+        stdin: Mutex<ChildStdin>,
+        inbox: Arc<Inbox>,
+        _reader: ReaderTask,
+    },
+    Http {
+        endpoint: String,
+        session: std::sync::Mutex<Option<String>>,
+        /// Requests the server sends us inside response streams.
+        requests: mpsc::UnboundedSender<Value>,
+    },
+    Sse {
+        post_url: reqwest::Url,
+        inbox: Arc<Inbox>,
+        _reader: ReaderTask,
+    },
+    WebSocket {
+        sink: Mutex<WsSink>,
+        inbox: Arc<Inbox>,
+        _reader: ReaderTask,
+    },
+}
+
+impl Link {
+    fn inbox(&self) -> Option<&Arc<Inbox>> {
+        match self {
+            Link::Stdio { inbox, .. } | Link::Sse { inbox, .. } | Link::WebSocket { inbox, .. } => {
+                Some(inbox)
+            }
+            Link::Http { .. } => None,
+        }
+    }
+
+    fn session(&self) -> Option<String> {
+        match self {
+            Link::Http { session, .. } => session.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            _ => None,
+        }
+    }
+}
+
+fn is_event_stream(resp: &reqwest::Response) -> bool {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"))
+}
+
+fn answers(msg: &Value, id: u64) -> bool {
+    msg.get("method").is_none() && msg.get("id").and_then(Value::as_u64) == Some(id)
+}
+
+fn is_server_request(msg: &Value) -> bool {
+    msg.get("method").is_some() && msg.get("id").is_some_and(|id| !id.is_null())
+}
+
+/// Opens connections for one server config and speaks JSON-RPC over them.
+struct Connector {
     config: McpServerConfig,
-    /// The child process (kept alive for the duration of the connection).
-    _child: Child,
-    stdin: Arc<Mutex<ChildStdin>>,
-    stdout: Arc<Mutex<BufReader<ChildStdout>>>,
-    next_id: AtomicU64,
+    http: reqwest::Client,
+    headers: reqwest::header::HeaderMap,
+    /// Set once an `Http` server turned out to speak only HTTP+SSE.
+    legacy_sse: std::sync::atomic::AtomicBool,
+    /// Answers `sampling/createMessage`; only set when the config opts in.
+    sampler: Option<Arc<dyn SamplingHandler>>,
 }
 
-impl McpClient {
-    /// Spawn a new MCP server subprocess and open its stdio pipes.
-    ///
-    /// Sends the MCP `initialize` handshake immediately after spawning.
-    pub async fn spawn(config: McpServerConfig) -> Result<Self> {
+impl Connector {
+    fn new(config: McpServerConfig, sampler: Option<Arc<dyn SamplingHandler>>) -> Self {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &config.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => warn!(server = %config.name, header = %name, "invalid MCP header — skipped"),
+            }
+        }
+        Self {
+            sampler: sampler.filter(|_| config.sampling),
+            config,
+            http: reqwest::Client::new(),
+            headers,
+            legacy_sse: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Connect and complete the `initialize` handshake, then start
+    /// answering the server's requests on the new link.
+    async fn open(self: &Arc<Self>, next_id: &AtomicU64) -> Result<Arc<Link>> {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let link = tokio::time::timeout(INIT_TIMEOUT, self.open_inner(next_id, requests))
+            .await
+            .unwrap_or_else(|_| {
+                Err(anyhow::anyhow!(
+                    "MCP server '{}' did not complete initialize within {} s",
+                    self.name(),
+                    INIT_TIMEOUT.as_secs()
+                ))
+            })?;
+        let link = Arc::new(link);
+        tokio::spawn(Arc::clone(self).serve_requests(Arc::downgrade(&link), rx));
+        Ok(link)
+    }
+
+    async fn open_inner(
+        &self,
+        next_id: &AtomicU64,
+        requests: mpsc::UnboundedSender<Value>,
+    ) -> Result<Link> {
+        let link = match &self.config.transport {
+            McpTransport::Stdio => self.spawn(requests)?,
+            McpTransport::Sse(url) => self.open_sse(url, requests).await?,
+            McpTransport::WebSocket(url) => self.open_websocket(url, requests).await?,
+            McpTransport::Http(url) if self.legacy_sse.load(Ordering::Relaxed) => {
+                self.open_sse(url, requests).await?
+            }
+            McpTransport::Http(url) => {
+                let link = Link::Http {
+                    endpoint: url.clone(),
+                    session: std::sync::Mutex::new(None),
+                    requests: requests.clone(),
+                };
+                match self.handshake(&link, next_id).await {
+                    Ok(()) => return Ok(link),
+                    Err(e) if e.downcast_ref::<Rejected>().is_some() => {
+                        info!(server = %self.name(), err = %e, "falling back to the HTTP+SSE transport");
+                        self.legacy_sse.store(true, Ordering::Relaxed);
+                        self.open_sse(url, requests).await?
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        self.handshake(&link, next_id).await?;
+        Ok(link)
+    }
+
+    /// Answer the server's requests until `link` is gone.  Each runs on its
+    /// own task, so a slow completion does not hold up a `ping`.
+    async fn serve_requests(
+        self: Arc<Self>,
+        link: Weak<Link>,
+        mut requests: mpsc::UnboundedReceiver<Value>,
+    ) {
+        while let Some(req) = requests.recv().await {
+            let conn = Arc::clone(&self);
+            let link = link.clone();
+            tokio::spawn(async move {
+                let reply = conn.answer(req).await;
+                let Some(link) = link.upgrade() else {
+                    return;
+                };
+                if let Err(e) = conn.send(&link, &reply).await {
+                    debug!(server = %conn.name(), err = %e, "could not answer MCP server request");
+                }
+            });
+        }
+    }
+
+    /// The reply to one server request.
+    async fn answer(&self, req: Value) -> Value {
+        let id = req["id"].clone();
+        let method = req["method"].as_str().unwrap_or_default();
+        let outcome = match (method, &self.sampler) {
+            ("ping", _) => Ok(json!({})),
+            ("sampling/createMessage", Some(sampler)) => sampler
+                .create_message(self.name(), req["params"].clone())
+                .await
+                .map_err(|e| {
+                    warn!(server = %self.name(), err = %e, "MCP sampling request failed");
+                    (MCP_INTERNAL_ERROR, format!("{e:#}"))
+                }),
+            _ => Err((
+                MCP_METHOD_NOT_FOUND,
+                format!("method not supported: {method}"),
+            )),
+        };
+        match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        }
+    }
+
+    /// `initialize`, then `notifications/initialized`.
+    async fn handshake(&self, link: &Link, next_id: &AtomicU64) -> Result<()> {
+        let capabilities = match self.sampler {
+            Some(_) => json!({ "sampling": {} }),
+            None => json!({}),
+        };
+        let params = json!({
+            "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+            "capabilities": capabilities,
+            "clientInfo": {
+                "name": "clawd",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        });
+        let id = next_id.fetch_add(1, Ordering::Relaxed);
+        let result = self.request(link, id, "initialize", Some(params)).await?;
+        debug!(
+            server = %self.name(),
+            protocol = result.get("protocolVersion").and_then(|v| v.as_str()).unwrap_or("?"),
+            "MCP server initialized"
+        );
+        let notif = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized",
+        });
+        self.send(link, &notif).await
+    }
+
+    fn spawn(&self, requests: mpsc::UnboundedSender<Value>) -> Result<Link> {
+        let config = &self.config;
         let mut cmd = tokio::process::Command::new(&config.command);
         cmd.args(&config.args);
         for (k, v) in &config.env {
@@ -116,6 +509,7 @@ impl McpClient {
         cmd.stdin(std::process::Stdio::piped());
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::null());
+        cmd.kill_on_drop(true);
 
         let mut child = cmd
             .spawn()
@@ -130,142 +524,405 @@ impl McpClient {
             .take()
             .ok_or_else(|| anyhow::anyhow!("MCP server stdout not available"))?;
 
-        let client = Self {
-            config,
-            _child: child,
-            stdin: Arc::new(Mutex::new(stdin)),
-            stdout: Arc::new(Mutex::new(BufReader::new(stdout))),
-            next_id: AtomicU64::new(1),
+        // MCP servers send one JSON object per line.
+        let inbox = Inbox::new(requests);
+        let reader = {
+            let inbox = Arc::clone(&inbox);
+            let name = config.name.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if !line.trim().is_empty() {
+                        inbox.deliver(&name, &line);
+                    }
+                }
+                inbox.close();
+            })
         };
+        Ok(Link::Stdio {
+            _child: child,
+            stdin: Mutex::new(stdin),
+            inbox,
+            _reader: ReaderTask(reader.abort_handle()),
+        })
+    }
 
-        // MCP initialize handshake — 10 s timeout to guard against servers that never respond.
-        tokio::time::timeout(std::time::Duration::from_secs(10), client.initialize())
+    async fn open_sse(&self, url: &str, requests: mpsc::UnboundedSender<Value>) -> Result<Link> {
+        let mut resp = self
+            .http
+            .get(url)
+            .headers(self.headers.clone())
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
             .await
-            .unwrap_or_else(|_| {
-                Err(anyhow::anyhow!(
-                    "MCP server '{}' did not complete initialize within 10 s",
-                    client.config.name
-                ))
-            })?;
-
-        Ok(client)
+            .map_err(|e| disconnected(e, false))?;
+        if !resp.status().is_success() || !is_event_stream(&resp) {
+            anyhow::bail!(
+                "MCP server '{}' returned {} instead of an event stream",
+                self.name(),
+                resp.status()
+            );
+        }
+        let base = resp.url().clone();
+
+        // The first `endpoint` event says where to POST; `message` events
+        // carry the replies.
+        let inbox = Inbox::new(requests);
+        let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+        let reader = {
+            let inbox = Arc::clone(&inbox);
+            let name = self.config.name.clone();
+            tokio::spawn(async move {
+                let mut endpoint_tx = Some(endpoint_tx);
+                let mut sse = SseDecoder::default();
+                while let Ok(Some(chunk)) = resp.chunk().await {
+                    for event in sse.push_events(&chunk) {
+                        match event.event.as_str() {
+                            "endpoint" => {
+                                if let Some(tx) = endpoint_tx.take() {
+                                    let _ = tx.send(event.data);
+                                }
+                            }
+                            "message" => inbox.deliver(&name, &event.data),
+                            _ => {}
+                        }
+                    }
+                }
+                inbox.close();
+            })
+        };
+        let reader = ReaderTask(reader.abort_handle());
+        let endpoint = endpoint_rx
+            .await
+            .map_err(|_| disconnected("event stream closed before its endpoint event", false))?;
+        let post_url = base
+            .join(endpoint.trim())
+            .with_context(|| format!("MCP server '{}' sent a bad endpoint", self.name()))?;
+        Ok(Link::Sse {
+            post_url,
+            inbox,
+            _reader: reader,
+        })
     }
 
-    // ─── Internals ──────────────────────────────────────────────────────────
-
-    fn next_id(&self) -> u64 {
-        self.next_id.fetch_add(1, Ordering::Relaxed)
+    async fn open_websocket(
+        &self,
+        url: &str,
+        requests: mpsc::UnboundedSender<Value>,
+    ) -> Result<Link> {
+        let mut request = url
+            .into_client_request()
+            .with_context(|| format!("bad WebSocket url for MCP server '{}'", self.name()))?;
+        request.headers_mut().extend(self.headers.clone());
+        let (ws, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| disconnected(e, false))?;
+        let (sink, mut stream) = ws.split();
+
+        let inbox = Inbox::new(requests);
+        let reader = {
+            let inbox = Arc::clone(&inbox);
+            let name = self.config.name.clone();
+            tokio::spawn(async move {
+                while let Some(Ok(frame)) = stream.next().await {
+                    match frame {
+                        Message::Text(text) => inbox.deliver(&name, &text),
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+                inbox.close();
+            })
+        };
+        Ok(Link::WebSocket {
+            sink: Mutex::new(sink),
+            inbox,
+            _reader: ReaderTask(reader.abort_handle()),
+        })
     }
 
-    /// Send a JSON-RPC request and read back the response line.
-    async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
-        let id = self.next_id();
+    /// Send a request on `link` and wait for its result.
+    async fn request(
+        &self,
+        link: &Link,
+        id: u64,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value> {
         let req = json!({
             "jsonrpc": "2.0",
             "id": id,
             "method": method,
             "params": params.unwrap_or(Value::Null)
         });
-        let mut line = serde_json::to_string(&req)?;
-        line.push('\n');
+        let exchange = async {
+            match link.inbox() {
+                None => self.http_request(link, id, &req).await,
+                Some(inbox) => {
+                    let rx = inbox.expect(id)?;
+                    if let Err(e) = self.send(link, &req).await {
+                        inbox.forget(id);
+                        return Err(e);
+                    }
+                    rx.await
+                        .map_err(|_| disconnected("connection closed before the reply", true))
+                }
+            }
+        };
+        let resp = match tokio::time::timeout(REQUEST_TIMEOUT, exchange).await {
+            Ok(resp) => resp?,
+            Err(_) => {
+                if let Some(inbox) = link.inbox() {
+                    inbox.forget(id);
+                }
+                anyhow::bail!(
+                    "MCP server '{}' did not answer {method} within {} s",
+                    self.name(),
+                    REQUEST_TIMEOUT.as_secs()
+                );
+            }
+        };
 
-        {
-            let mut stdin = self.stdin.lock().await;
-            stdin
-                .write_all(line.as_bytes())
-                .await
-                .context("write to MCP server stdin")?;
-            stdin.flush().await?;
+        if let Some(error) = resp.get("error") {
+            return Err(anyhow::anyhow!("MCP server returned error: {}", error));
         }
 
-        // Read response — MCP servers send one JSON object per line.
-        let response_line = {
-            let mut stdout = self.stdout.lock().await;
-            let mut buf = String::new();
-            stdout
-                .read_line(&mut buf)
-                .await
-                .context("read from MCP server stdout")?;
-            buf
-        };
+        Ok(resp.get("result").cloned().unwrap_or(Value::Null))
+    }
 
-        if response_line.is_empty() {
-            return Err(anyhow::anyhow!(
-                "MCP server '{}' closed stdout unexpectedly",
-                self.config.name
-            ));
+    /// Send one message without waiting for a reply.
+    async fn send(&self, link: &Link, msg: &Value) -> Result<()> {
+        match link {
+            Link::Stdio { stdin, .. } => {
+                let mut line = serde_json::to_string(msg)?;
+                line.push('\n');
+                let mut stdin = stdin.lock().await;
+                stdin
+                    .write_all(line.as_bytes())
+                    .await
+                    .map_err(|e| disconnected(e, false))?;
+                stdin.flush().await.map_err(|e| disconnected(e, false))
+            }
+            Link::WebSocket { sink, .. } => sink
+                .lock()
+                .await
+                .send(Message::Text(msg.to_string()))
+                .await
+                .map_err(|e| disconnected(e, false)),
+            Link::Sse { post_url, .. } => {
+                let resp = self
+                    .http
+                    .post(post_url.clone())
+                    .headers(self.headers.clone())
+                    .json(msg)
+                    .send()
+                    .await
+                    .map_err(|e| disconnected(e, false))?;
+                match resp.status() {
+                    s if s.is_success() => Ok(()),
+                    reqwest::StatusCode::NOT_FOUND => {
+                        Err(disconnected("message endpoint is gone", false))
+                    }
+                    s => Err(anyhow::anyhow!("MCP server '{}' returned {s}", self.name())),
+                }
+            }
+            Link::Http { .. } => {
+                let resp = self.http_post(link, msg).await?;
+                match resp.status() {
+                    s if s.is_success() => Ok(()),
+                    s => Err(anyhow::anyhow!("MCP server '{}' returned {s}", self.name())),
+                }
+            }
         }
+    }
 
-        let resp: Value =
-            serde_json::from_str(response_line.trim()).context("parse MCP server response")?;
+    /// POST to a Streamable HTTP endpoint, keeping track of the session.
+    async fn http_post(&self, link: &Link, msg: &Value) -> Result<reqwest::Response> {
+        let Link::Http {
+            endpoint, session, ..
+        } = link
+        else {
+            unreachable!("http_post on a non-HTTP link");
+        };
+        let current = link.session();
+        let mut req = self
+            .http
+            .post(endpoint)
+            .headers(self.headers.clone())
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(msg);
+        if let Some(id) = &current {
+            req = req.header(SESSION_HEADER, id);
+        }
+        let resp = req.send().await.map_err(|e| disconnected(e, false))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND && current.is_some() {
+            return Err(disconnected("HTTP session expired", false));
+        }
+        if let Some(id) = resp
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *session.lock().unwrap_or_else(|e| e.into_inner()) = Some(id.to_string());
+        }
+        Ok(resp)
+    }
 
-        // Verify the response ID matches the request ID we sent.
-        if let Some(resp_id) = resp.get("id") {
-            let expected = Value::Number(serde_json::Number::from(id));
-            if resp_id != &expected {
-                warn!(
-                    server = %self.config.name,
-                    expected = %expected,
-                    got = %resp_id,
-                    "MCP server returned response with mismatched ID"
-                );
-                return Err(anyhow::anyhow!(
-                    "MCP server '{}' returned response ID {} but expected {}",
-                    self.config.name,
-                    resp_id,
-                    id
-                ));
+    /// POST a request and read its reply from a JSON or event-stream body.
+    async fn http_request(&self, link: &Link, id: u64, req: &Value) -> Result<Value> {
+        let mut resp = self.http_post(link, req).await?;
+        let status = resp.status();
+        if status.is_client_error()
+            && status != reqwest::StatusCode::UNAUTHORIZED
+            && status != reqwest::StatusCode::FORBIDDEN
+        {
+            return Err(Rejected(status).into());
+        }
+        if !status.is_success() {
+            anyhow::bail!("MCP server '{}' returned {status}", self.name());
+        }
+        if is_event_stream(&resp) {
+            let mut sse = SseDecoder::default();
+            while let Some(chunk) = resp.chunk().await.map_err(|e| disconnected(e, true))? {
+                for event in sse.push_events(&chunk) {
+                    let Ok(msg) = serde_json::from_str::<Value>(&event.data) else {
+                        continue;
+                    };
+                    if event.event == "message" && answers(&msg, id) {
+                        return Ok(msg);
+                    }
+                    if let (true, Link::Http { requests, .. }) = (is_server_request(&msg), link) {
+                        let _ = requests.send(msg);
+                        continue;
+                    }
+                    debug!(server = %self.name(), method = ?msg.get("method"), "MCP server message ignored");
+                }
             }
+            return Err(disconnected("event stream ended before the reply", true));
         }
+        let body: Value = resp.json().await.context("parse MCP server response")?;
+        let reply = match body {
+            Value::Array(items) => items.into_iter().find(|m| answers(m, id)),
+            single => Some(single).filter(|m| answers(m, id)),
+        };
+        reply.ok_or_else(|| {
+            anyhow::anyhow!("MCP server '{}' did not answer request {id}", self.name())
+        })
+    }
+}
 
-        if let Some(error) = resp.get("error") {
-            return Err(anyhow::anyhow!("MCP server returned error: {}", error));
-        }
+// ─── McpClient ────────────────────────────────────────────────────────────────
 
-        Ok(resp.get("result").cloned().unwrap_or(Value::Null))
+/// A live connection to one upstream MCP server.
+pub struct McpClient {
+    conn: Arc<Connector>,
+    /// The current connection and its generation, bumped on each reconnect.
+    link: RwLock<(u64, Arc<Link>)>,
+    next_id: AtomicU64,
+}
+
+impl McpClient {
+    /// Connect to the server named in `config` — spawning it for stdio —
+    /// and complete the MCP `initialize` handshake.
+    pub async fn connect(config: McpServerConfig) -> Result<Self> {
+        Self::open(Connector::new(config, None)).await
     }
 
-    /// Send the MCP `initialize` request.
-    async fn initialize(&self) -> Result<()> {
-        let params = json!({
-            "protocolVersion": "2024-11-05",
-            "capabilities": {},
-            "clientInfo": {
-                "name": "clawd",
-                "version": env!("CARGO_PKG_VERSION")
-            }
-        });
+    /// Like `connect`, answering the server's `sampling/createMessage`
+    /// requests with `sampler` when `config.sampling` is set.
+    pub async fn connect_with_sampling(
+        config: McpServerConfig,
+        sampler: Arc<dyn SamplingHandler>,
+    ) -> Result<Self> {
+        Self::open(Connector::new(config, Some(sampler))).await
+    }
 
-        let result = self.send_request("initialize", Some(params)).await?;
-        debug!(
-            server = %self.config.name,
-            protocol = result.get("protocolVersion").and_then(|v| v.as_str()).unwrap_or("?"),
-            "MCP server initialized"
-        );
+    async fn open(conn: Connector) -> Result<Self> {
+        let conn = Arc::new(conn);
+        let next_id = AtomicU64::new(1);
+        let link = conn.open(&next_id).await?;
+        Ok(Self {
+            conn,
+            link: RwLock::new((0, link)),
+            next_id,
+        })
+    }
 
-        // Send the `initialized` notification (no response expected).
-        // Prepare data outside the lock, then acquire-write-drop-release.
-        let notif = json!({
-            "jsonrpc": "2.0",
-            "method": "initialized",
-            "params": {}
-        });
-        let mut line = serde_json::to_string(&notif)?;
-        line.push('\n');
-        let line_bytes = line.into_bytes();
+    // ─── Internals ──────────────────────────────────────────────────────────
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Send a JSON-RPC request and wait for its result, reconnecting if the
+    /// connection has dropped.
+    async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let (generation, link) = {
+            let current = self.link.read().await;
+            (current.0, Arc::clone(&current.1))
+        };
+        let err = match self
+            .conn
+            .request(&link, self.next_id(), method, params.clone())
+            .await
         {
-            // Lock scope limited to the write + flush pair; released before returning.
-            let mut stdin = self.stdin.lock().await;
-            stdin.write_all(&line_bytes).await?;
-            stdin.flush().await?;
-        } // stdin lock released here
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+        let Some(lost) = err.downcast_ref::<Disconnected>() else {
+            return Err(err);
+        };
+        warn!(server = %self.name(), reason = %lost.reason, "MCP connection lost — reconnecting");
+        let retry = !lost.delivered;
+        let link = self.reconnect(generation).await?;
+        if !retry {
+            return Err(err);
+        }
+        self.conn
+            .request(&link, self.next_id(), method, params)
+            .await
+    }
 
-        Ok(())
+    /// Replace the connection of generation `seen`, unless another caller
+    /// already has.
+    async fn reconnect(&self, seen: u64) -> Result<Arc<Link>> {
+        let mut current = self.link.write().await;
+        if current.0 != seen {
+            return Ok(Arc::clone(&current.1));
+        }
+        let mut delay = RECONNECT_BASE_DELAY;
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            match self.conn.open(&self.next_id).await {
+                Ok(link) => {
+                    *current = (seen + 1, link);
+                    info!(server = %self.name(), attempt, "MCP server reconnected");
+                    return Ok(Arc::clone(&current.1));
+                }
+                Err(e) if attempt == RECONNECT_ATTEMPTS => {
+                    return Err(e.context(format!(
+                        "could not reconnect to MCP server '{}'",
+                        self.name()
+                    )));
+                }
+                Err(e) => {
+                    debug!(server = %self.name(), attempt, err = %e, "MCP reconnect failed");
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+        unreachable!("the last attempt returns")
     }
 
     // ─── Public API ─────────────────────────────────────────────────────────
 
     /// List all tools available from this MCP server.
+    ///
+    /// Agents read tool descriptions and schemas as instructions, so for an
+    /// `Untrusted` server they are sanitized like call results.
     pub async fn list_tools(&self) -> Result<Vec<McpToolDef>> {
         let result = self.send_request("tools/list", None).await?;
         let raw_tools = result
@@ -274,11 +931,28 @@ impl McpClient {
             .cloned()
             .unwrap_or_default();
 
-        let tools: Vec<McpToolDef> = raw_tools
+        let mut tools: Vec<McpToolDef> = raw_tools
             .into_iter()
             .filter_map(|v| serde_json::from_value(v).ok())
             .collect();
 
+        if self.conn.config.trust == McpTrustLevel::Untrusted {
+            for tool in &mut tools {
+                let description = Value::String(std::mem::take(&mut tool.description));
+                let sanitized = sanitize_value(description.clone());
+                let schema = sanitize_value(tool.input_schema.clone());
+                if sanitized != description || schema != tool.input_schema {
+                    warn!(
+                        server = %self.name(),
+                        tool = %tool.name,
+                        "prompt injection pattern detected in untrusted MCP tool listing — redacted"
+                    );
+                }
+                tool.description = sanitized.as_str().unwrap_or_default().to_string();
+                tool.input_schema = schema;
+            }
+        }
+
         Ok(tools)
     }
 
@@ -295,11 +969,11 @@ impl McpClient {
         let result = self.send_request("tools/call", Some(params)).await?;
 
         // Sanitize untrusted responses.
-        let final_result = if self.config.trust == McpTrustLevel::Untrusted {
+        let final_result = if self.conn.config.trust == McpTrustLevel::Untrusted {
             let sanitized = sanitize_value(result.clone());
             if sanitized != result {
                 warn!(
-                    server = %self.config.name,
+                    server = %self.name(),
                     tool = name,
                     "prompt injection pattern detected in untrusted MCP response — redacted"
                 );
@@ -312,8 +986,35 @@ impl McpClient {
         Ok(final_result)
     }
 
+    /// Send a `ping`, reconnecting first if the connection has dropped.
+    pub async fn ping(&self) -> Result<()> {
+        self.send_request("ping", None).await.map(drop)
+    }
+
+    /// How many times the client has reconnected.  A change means the
+    /// server was restarted and may offer different tools.
+    pub async fn generation(&self) -> u64 {
+        self.link.read().await.0
+    }
+
+    /// End the connection.  Streamable HTTP sessions are deleted on the
+    /// server; stdio servers are killed when the client is dropped.
+    pub async fn close(&self) {
+        let link = Arc::clone(&self.link.read().await.1);
+        if let (Link::Http { endpoint, .. }, Some(session)) = (&*link, link.session()) {
+            let _ = self
+                .conn
+                .http
+                .delete(endpoint)
+                .headers(self.conn.headers.clone())
+                .header(SESSION_HEADER, session)
+                .send()
+                .await;
+        }
+    }
+
     /// The display name of this server (from config).
     pub fn name(&self) -> &str {
-        &self.config.name
+        self.conn.name()
     }
 }
@@ -13,17 +13,31 @@
 ///       "args": ["-y", "@modelcontextprotocol/server-filesystem", "/path/to/dir"],
 ///       "env": { "MY_KEY": "value" },
 ///       "trust": "trusted"
+///     },
+///     {
+///       "name": "search",
+///       "url": "https://mcp.internal/search",
+///       "headers": { "Authorization": "Bearer ${SEARCH_MCP_TOKEN}" },
+///       "sampling": true
 ///     }
 ///   ]
 /// }
 /// ```
-use anyhow::Result;
+///
+/// An entry has either a `command` (stdio subprocess) or a `url`.  A `url`
+/// uses Streamable HTTP — falling back to HTTP+SSE for older servers — unless
+/// it is `ws://`/`wss://` or `"transport"` says `"sse"` or `"websocket"`.
+/// `${VAR}` in header values is replaced from the daemon's environment.
+/// `"sampling": true` lets the server request completions from the daemon
+/// (`sampling/createMessage`); it is off by default.
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use tracing::{debug, warn};
 
 use super::client::{McpServerConfig, McpTrustLevel};
+use super::transport::McpTransport;
 
 // ─── Raw JSON types (for deserialization) ─────────────────────────────────────
 
@@ -45,28 +59,137 @@ impl From<JsonTrustLevel> for McpTrustLevel {
     }
 }
 
+/// The transport as it appears in JSON.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum JsonTransport {
+    Stdio,
+    Http,
+    Sse,
+    WebSocket,
+}
+
 /// One server entry as it appears in `mcp-servers.json`.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 struct JsonServerEntry {
     /// Display name.
     name: String,
-    /// Executable command.
-    command: String,
+    /// Executable command (stdio servers).
+    #[serde(default)]
+    command: Option<String>,
+    /// Endpoint URL (HTTP, SSE and WebSocket servers).
+    #[serde(default)]
+    url: Option<String>,
+    /// Overrides the transport implied by `command` / `url`.
+    #[serde(default)]
+    transport: Option<JsonTransport>,
     /// Arguments to the command.
     #[serde(default)]
     args: Vec<String>,
     /// Environment variables to inject.
     #[serde(default)]
     env: HashMap<String, String>,
+    /// Extra HTTP headers for remote servers; `${VAR}` is expanded.
+    #[serde(default)]
+    headers: HashMap<String, String>,
     /// Trust level.  Defaults to `untrusted` if omitted.
     #[serde(default = "default_trust")]
     trust: JsonTrustLevel,
+    /// Answer the server's `sampling/createMessage` requests.
+    #[serde(default)]
+    sampling: bool,
 }
 
 fn default_trust() -> JsonTrustLevel {
     JsonTrustLevel::Untrusted
 }
 
+impl JsonServerEntry {
+    /// Resolve the entry into a client config, expanding header values
+    /// through `env`.
+    fn into_config(self, env: &dyn Fn(&str) -> Option<String>) -> Result<McpServerConfig> {
+        let transport = match (self.command.as_deref(), self.url, self.transport) {
+            (Some(_), Some(_), _) => {
+                bail!("MCP server '{}' has both 'command' and 'url'", self.name)
+            }
+            (Some(_), None, None | Some(JsonTransport::Stdio)) => McpTransport::Stdio,
+            (Some(_), None, Some(_)) => {
+                bail!(
+                    "MCP server '{}' needs a 'url' for a remote transport",
+                    self.name
+                )
+            }
+            (None, Some(_), Some(JsonTransport::Stdio)) => {
+                bail!("MCP server '{}' needs a 'command' for stdio", self.name)
+            }
+            (None, Some(url), transport) => {
+                let parsed = reqwest::Url::parse(&url)
+                    .map_err(|e| anyhow::anyhow!("MCP server '{}': bad url: {e}", self.name))?;
+                let is_ws = matches!(parsed.scheme(), "ws" | "wss");
+                match transport {
+                    Some(JsonTransport::WebSocket) => McpTransport::WebSocket(url),
+                    None if is_ws => McpTransport::WebSocket(url),
+                    _ if is_ws => bail!(
+                        "MCP server '{}': {} is a WebSocket url",
+                        self.name,
+                        parsed.scheme()
+                    ),
+                    Some(JsonTransport::Sse) => McpTransport::Sse(url),
+                    _ => McpTransport::Http(url),
+                }
+            }
+            (None, None, _) => bail!("MCP server '{}' needs a 'command' or a 'url'", self.name),
+        };
+        let headers = self
+            .headers
+            .into_iter()
+            .filter_map(|(k, v)| match expand_env(&v, env) {
+                Ok(v) => Some((k, v)),
+                Err(var) => {
+                    warn!(
+                        server = %self.name,
+                        header = %k,
+                        var = %var,
+                        "MCP server header references an unset variable — header dropped"
+                    );
+                    None
+                }
+            })
+            .collect();
+        Ok(McpServerConfig {
+            name: self.name,
+            transport,
+            command: self.command.unwrap_or_default(),
+            args: self.args,
+            env: self.env,
+            headers,
+            trust: self.trust.into(),
+            sampling: self.sampling,
+        })
+    }
+}
+
+/// Replace each `${VAR}` in `value` with `env(VAR)`.  Returns the name of
+/// the first unset variable on failure.
+fn expand_env(
+    value: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let var = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        out.push_str(&env(var).ok_or_else(|| var.to_string())?);
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
 /// The top-level structure of `mcp-servers.json`.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 struct JsonMcpServersFile {
@@ -108,15 +231,25 @@ impl McpServersConfig {
             )
         })?;
 
-        let parsed: JsonMcpServersFile = serde_json::from_str(&raw).map_err(|e| {
+        Self::parse(&raw).map_err(|e| {
             anyhow::anyhow!(
                 "invalid mcp-servers.json at '{}': {}",
                 config_path.display(),
                 e
             )
-        })?;
+        })
+    }
 
-        let servers: Vec<McpServerConfig> = parsed
+    /// Parse the contents of `mcp-servers.json`.
+    pub fn parse(raw: &str) -> Result<Self> {
+        Self::parse_with_env(raw, &|var| std::env::var(var).ok())
+    }
+
+    /// [`parse`](Self::parse) with header variables looked up through `env`.
+    fn parse_with_env(raw: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let parsed: JsonMcpServersFile = serde_json::from_str(raw)?;
+
+        let servers = parsed
             .servers
             .into_iter()
             .map(|entry| {
@@ -126,18 +259,73 @@ impl McpServersConfig {
                         "MCP server configured with Untrusted level — responses will be sanitized"
                     );
                 }
-                McpServerConfig {
-                    name: entry.name,
-                    command: entry.command,
-                    args: entry.args,
-                    env: entry.env,
-                    trust: entry.trust.into(),
-                }
+                entry.into_config(env)
             })
-            .collect();
+            .collect::<Result<Vec<McpServerConfig>>>()?;
 
         debug!(count = servers.len(), "loaded MCP server configs");
 
         Ok(Self { servers })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transports_follow_command_url_and_override() {
+        let config = McpServersConfig::parse(
+            r#"{ "servers": [
+                { "name": "fs", "command": "npx", "args": ["-y", "fs"], "trust": "trusted" },
+                { "name": "search", "url": "https://mcp.internal/search", "sampling": true },
+                { "name": "legacy", "url": "http://127.0.0.1:9000/sse", "transport": "sse" },
+                { "name": "live", "url": "wss://mcp.internal/ws" }
+            ] }"#,
+        )
+        .unwrap();
+        let transports: Vec<&McpTransport> = config.servers.iter().map(|s| &s.transport).collect();
+        assert_eq!(
+            transports,
+            [
+                &McpTransport::Stdio,
+                &McpTransport::Http("https://mcp.internal/search".into()),
+                &McpTransport::Sse("http://127.0.0.1:9000/sse".into()),
+                &McpTransport::WebSocket("wss://mcp.internal/ws".into()),
+            ]
+        );
+        assert_eq!(config.servers[0].trust, McpTrustLevel::Trusted);
+        assert_eq!(config.servers[1].trust, McpTrustLevel::Untrusted);
+        assert!(config.servers[1].sampling);
+        assert!(!config.servers[0].sampling, "sampling is opt-in");
+    }
+
+    #[test]
+    fn entries_need_exactly_one_of_command_and_url() {
+        for bad in [
+            r#"{ "servers": [{ "name": "x" }] }"#,
+            r#"{ "servers": [{ "name": "x", "command": "a", "url": "http://h" }] }"#,
+            r#"{ "servers": [{ "name": "x", "command": "a", "transport": "http" }] }"#,
+            r#"{ "servers": [{ "name": "x", "url": "ws://h", "transport": "sse" }] }"#,
+        ] {
+            let err = McpServersConfig::parse(bad).unwrap_err().to_string();
+            assert!(err.contains("'x'"), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn header_values_expand_environment_variables() {
+        let env = |var: &str| (var == "MCP_TOKEN").then(|| "s3cret".to_string());
+        let config = McpServersConfig::parse_with_env(
+            r#"{ "servers": [{ "name": "search", "url": "https://h/mcp", "headers": {
+                "Authorization": "Bearer ${MCP_TOKEN}",
+                "X-Team": "${MCP_TEAM}"
+            } }] }"#,
+            &env,
+        )
+        .unwrap();
+        let headers = &config.servers[0].headers;
+        assert_eq!(headers["Authorization"], "Bearer s3cret");
+        assert!(!headers.contains_key("X-Team"));
+    }
+}
@@ -4,19 +4,68 @@
 /// the handler functions in `mcp::tools::*`.  Write tools (apply_patch,
 /// run_tests) verify that the referenced task is Active+Claimed before
 /// proceeding; all other tools are callable in any task state.
+///
+/// Every built-in call is reviewed by plugins.  A built-in tool's handler
+/// gates the risk it has by default (task ownership, the `request_approval`
+/// flow), so the approval rules only decide once `tool-risk.json` raises the
+/// tool above that level.  Calls policy refuses are audited and fail with
+/// `MCP_POLICY_DENIED`.
+///
+/// Tools contributed by plugins (`<plugin>__<tool>`) and by upstream MCP
+/// servers (`<server>__<tool>`, see `McpHub`) run through the full policy
+/// engine and are written to the audit log before they are handed to the
+/// owning plugin's `on_tool_invoke` or forwarded to the server.  An upstream
+/// server named after a plugin is not served at all: its tools would pass
+/// for the plugin's.
+///
+/// `apply_patch` is wrapped by the LSP post-edit gate (`policy::lsp_gate`):
+/// touched files are baselined before the patch, and when `post_tool` asks
+/// for `run_linter` the errors the patch introduced are attached as `lsp`.
+use crate::lsp::handlers::lsp_proxy;
+use crate::policy::hooks::PolicyHooks;
+use crate::policy::lsp_gate::lsp_gate;
+use crate::policy::risk::{RiskDatabase, RiskLevel};
+use crate::policy::PolicyDecision;
+use crate::storage::event_log::AuditEntry;
+use crate::tasks::reducer::TaskState;
 use crate::AppContext;
-use serde_json::Value;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{info, warn};
 
-use super::tools as tool_list;
-use super::transport::{McpError, MCP_INVALID_PARAMS, MCP_PROVIDER_NOT_AVAILABLE};
+use super::hub::TOOL_SEPARATOR;
+use super::tools::{self as tool_list, McpToolDef};
+use super::transport::{
+    McpError, MCP_INVALID_PARAMS, MCP_POLICY_DENIED, MCP_PROVIDER_NOT_AVAILABLE,
+};
 
 /// Write tools that require the task to be Active+Claimed before proceeding.
 /// `transition_task` and `claim_task` are included so agents cannot advance
 /// task state or claim tasks without going through proper ownership checks.
 const WRITE_TOOLS: &[&str] = &["apply_patch", "run_tests", "transition_task", "claim_task"];
 
+/// Risk each built-in tool's own handler already accounts for.
+static BUILTIN_RISK: Lazy<RiskDatabase> = Lazy::new(RiskDatabase::default_rules);
+
+/// Who runs a tool that is not built in.
+#[derive(Clone, Copy)]
+enum ToolSource {
+    Plugin,
+    Upstream,
+}
+
+/// What an `apply_patch` call edits, for the LSP post-edit gate.
+#[derive(Clone)]
+struct PostEditTarget {
+    task_id: String,
+    worktree: PathBuf,
+    files: Vec<String>,
+}
+
 pub struct McpDispatcher {
     ctx: Arc<AppContext>,
 }
@@ -26,39 +75,164 @@ impl McpDispatcher {
         Self { ctx }
     }
 
+    /// Built-in tools, then tools contributed by loaded plugins, then tools
+    /// published by upstream MCP servers.
+    pub async fn list_tools(&self) -> Vec<McpToolDef> {
+        let mut tools = tool_list::clawd_tools();
+        tools.extend(
+            self.ctx
+                .plugin_manager
+                .tools()
+                .await
+                .into_iter()
+                .map(|t| McpToolDef {
+                    name: t.name,
+                    description: t.description,
+                    input_schema: t.input_schema,
+                }),
+        );
+        let plugins = self.plugin_names().await;
+        tools.extend(
+            self.ctx
+                .mcp_hub
+                .tools()
+                .into_iter()
+                .filter(|t| !shadows_plugin(&plugins, &t.name)),
+        );
+        tools
+    }
+
+    /// Whether `tool_name` is forwarded to an upstream server, whose result
+    /// is already a `CallToolResult`.
+    pub async fn is_upstream_tool(&self, tool_name: &str) -> bool {
+        self.ctx.plugin_manager.tool(tool_name).await.is_none()
+            && self.ctx.mcp_hub.has_tool(tool_name)
+            && !shadows_plugin(&self.plugin_names().await, tool_name)
+    }
+
+    async fn plugin_names(&self) -> HashSet<String> {
+        self.ctx
+            .plugin_manager
+            .list()
+            .await
+            .into_iter()
+            .map(|p| p.name)
+            .collect()
+    }
+
     /// Dispatch a `tools/call` invocation.
     ///
     /// `tool_name`  — the `name` field from the MCP `tools/call` params.
     /// `arguments`  — the `arguments` object from the MCP `tools/call` params.
     /// `agent_id`   — optional agent identifier from the calling session.
+    /// `session_id` — the MCP session the call arrived on, if any; plugins
+    ///                and the audit log see it as the session.
     ///
     /// Returns `Ok(Value)` with the tool result, or `Err(anyhow::Error)` whose
     /// message encodes a MCP error code (e.g. `"MCP_INVALID_PARAMS: ..."` or
-    /// `"MCP_PROVIDER_NOT_AVAILABLE: ..."`) so callers can map it correctly.
+    /// `"MCP_POLICY_DENIED: ..."`) so callers can map it correctly.
     pub async fn dispatch(
         &self,
         tool_name: &str,
-        arguments: Value,
+        mut arguments: Value,
         agent_id: Option<String>,
+        session_id: Option<String>,
     ) -> anyhow::Result<Value> {
         // Verify the tool is in our catalogue first.
         let known = tool_list::clawd_tools()
             .into_iter()
             .any(|t| t.name == tool_name);
+        if !known && self.ctx.plugin_manager.tool(tool_name).await.is_some() {
+            return self
+                .dispatch_contributed_tool(
+                    ToolSource::Plugin,
+                    tool_name,
+                    arguments,
+                    agent_id,
+                    session_id,
+                )
+                .await;
+        }
+        if !known && self.ctx.mcp_hub.has_tool(tool_name) {
+            if shadows_plugin(&self.plugin_names().await, tool_name) {
+                warn!(
+                    tool = tool_name,
+                    "MCP server is named after a plugin — not served"
+                );
+                return Err(anyhow::anyhow!(
+                    "MCP_INVALID_PARAMS: unknown tool: {tool_name} (its MCP server has the name of a plugin)"
+                ));
+            }
+            return self
+                .dispatch_contributed_tool(
+                    ToolSource::Upstream,
+                    tool_name,
+                    arguments,
+                    agent_id,
+                    session_id,
+                )
+                .await;
+        }
         if !known {
             let msg = format!("unknown tool: {}", tool_name);
             warn!(tool = tool_name, "MCP unknown tool");
             return Err(anyhow::anyhow!("MCP_INVALID_PARAMS: {}", msg));
         }
 
+        // Plugins may veto the call or rewrite its arguments; the approval
+        // rules apply once the tool is rated above its default risk.
+        let session_id = session_id.as_deref().unwrap_or("");
+        let policy = &self.ctx.policy_engine;
+        let mut refusal = policy
+            .review_with_plugins(tool_name, &mut arguments, session_id)
+            .await;
+        let risk = policy.risk_of(tool_name).await;
+        let handled = BUILTIN_RISK.lookup(tool_name).unwrap_or(RiskLevel::Low);
+        if refusal.is_none() && risk > handled {
+            let task_state = self.task_state(&arguments).await?;
+            refusal = Some(policy.apply_rules(tool_name, task_state.as_ref()).await)
+                .filter(|d| *d != PolicyDecision::Allow);
+        }
+        if let Some(decision) = refusal {
+            self.audit(
+                session_id, &agent_id, tool_name, &arguments, &risk, &decision,
+            )
+            .await;
+            warn!(tool = tool_name, decision = ?decision, "MCP tool call refused by policy");
+            return Err(policy_error(decision));
+        }
+
         // For write tools, verify the task is Active+Claimed.
         if WRITE_TOOLS.contains(&tool_name) {
             self.verify_active_claimed(&arguments, agent_id.as_deref())
                 .await?;
         }
 
+        // Baseline the files a patch touches so the post-edit check can tell
+        // new errors from old ones.
+        let post_edit = match tool_name {
+            "apply_patch" => self.post_edit_target(&arguments).await,
+            _ => None,
+        };
+        if let Some(target) = post_edit.clone() {
+            let _ = tokio::task::spawn_blocking(move || {
+                lsp_gate().before_patch(
+                    lsp_proxy(),
+                    &target.task_id,
+                    &target.worktree,
+                    &target.files,
+                )
+            })
+            .await;
+        }
+        let task_id = arguments
+            .get("task_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
         // Route to the correct handler.
-        let result = match tool_name {
+        let mut result = match tool_name {
             "create_task" => super::tools::task::create_task(&self.ctx, arguments).await?,
             "claim_task" => {
                 super::tools::task::claim_task(&self.ctx, arguments, agent_id.as_deref()).await?
@@ -80,6 +254,17 @@ impl McpDispatcher {
                 super::tools::task::transition_task(&self.ctx, arguments, agent_id.as_deref())
                     .await?
             }
+            "lsp_hover" => super::tools::lsp::hover(&self.ctx, arguments).await?,
+            "lsp_definition" => super::tools::lsp::definition(&self.ctx, arguments).await?,
+            "lsp_references" => super::tools::lsp::references(&self.ctx, arguments).await?,
+            "lsp_rename" => super::tools::lsp::rename(&self.ctx, arguments).await?,
+            "lsp_code_actions" => super::tools::lsp::code_actions(&self.ctx, arguments).await?,
+            "lsp_document_symbols" => {
+                super::tools::lsp::document_symbols(&self.ctx, arguments).await?
+            }
+            "lsp_workspace_symbols" => {
+                super::tools::lsp::workspace_symbols(&self.ctx, arguments).await?
+            }
             other => {
                 // Should not reach here — already checked above.
                 return Err(anyhow::anyhow!(
@@ -89,6 +274,24 @@ impl McpDispatcher {
             }
         };
 
+        let actions = PolicyHooks::post_tool(tool_name, &result, &task_id);
+        if let Some(target) = post_edit.filter(|_| actions.run_linter && result["applied"] == true)
+        {
+            let report = tokio::task::spawn_blocking(move || {
+                lsp_gate().after_patch(
+                    lsp_proxy(),
+                    &target.task_id,
+                    &target.worktree,
+                    &target.files,
+                )
+            })
+            .await
+            .unwrap_or_default();
+            if !report.checked_files.is_empty() {
+                result["lsp"] = json!(report);
+            }
+        }
+
         // Emit an audit event (stub — log only; full audit pipeline TBD).
         info!(
             tool = tool_name,
@@ -99,6 +302,115 @@ impl McpDispatcher {
         Ok(result)
     }
 
+    /// Run a plugin or upstream tool: policy, audit, then `on_tool_invoke`
+    /// or the upstream `tools/call`.
+    async fn dispatch_contributed_tool(
+        &self,
+        source: ToolSource,
+        tool_name: &str,
+        mut arguments: Value,
+        agent_id: Option<String>,
+        session_id: Option<String>,
+    ) -> anyhow::Result<Value> {
+        let session_id = session_id.as_deref().unwrap_or("");
+        let task_state = self.task_state(&arguments).await?;
+        let decision = self
+            .ctx
+            .policy_engine
+            .evaluate_mut(tool_name, &mut arguments, task_state.as_ref(), session_id)
+            .await;
+        let risk = self.ctx.policy_engine.risk_of(tool_name).await;
+        if decision != PolicyDecision::Allow {
+            self.audit(
+                session_id, &agent_id, tool_name, &arguments, &risk, &decision,
+            )
+            .await;
+            warn!(tool = tool_name, decision = ?decision, "MCP contributed tool refused by policy");
+            return Err(policy_error(decision));
+        }
+        let risk = format!("{risk:?}").to_lowercase();
+        let args_json = arguments.to_string();
+
+        let started = Instant::now();
+        let result = match source {
+            ToolSource::Plugin => {
+                self.ctx
+                    .plugin_manager
+                    .invoke_tool(session_id, tool_name, &arguments)
+                    .await
+            }
+            ToolSource::Upstream => {
+                self.ctx
+                    .mcp_hub
+                    .call_tool(tool_name, arguments.clone())
+                    .await
+            }
+        };
+        let status = if result.is_ok() { "allowed" } else { "failed" };
+        self.ctx
+            .audit_log
+            .append(&AuditEntry::new(
+                session_id,
+                agent_id.clone(),
+                tool_name,
+                &args_json,
+                risk.as_str(),
+                status,
+                started.elapsed().as_millis() as u64,
+            ))
+            .await;
+        info!(
+            tool = tool_name,
+            agent = agent_id.as_deref().unwrap_or("unknown"),
+            "MCP contributed tool executed"
+        );
+        result.map_err(|e| anyhow::anyhow!("MCP_PROVIDER_NOT_AVAILABLE: {:#}", e))
+    }
+
+    /// Record a call policy refused in the audit log.
+    async fn audit(
+        &self,
+        session_id: &str,
+        agent_id: &Option<String>,
+        tool_name: &str,
+        arguments: &Value,
+        risk: &RiskLevel,
+        decision: &PolicyDecision,
+    ) {
+        self.ctx
+            .audit_log
+            .append(&AuditEntry::new(
+                session_id,
+                agent_id.clone(),
+                tool_name,
+                &arguments.to_string(),
+                format!("{risk:?}").to_lowercase(),
+                decision.audit_status(),
+                0,
+            ))
+            .await;
+    }
+
+    /// Task, worktree and files of an `apply_patch` call, when the LSP gate
+    /// is enabled and the patch parses.
+    async fn post_edit_target(&self, arguments: &Value) -> Option<PostEditTarget> {
+        if !self.ctx.config.diff_risk.lsp_gate {
+            return None;
+        }
+        let task_id = arguments.get("task_id")?.as_str()?;
+        let files = super::tools::patch::files_in_patch(arguments.get("patch")?.as_str()?).ok()?;
+        let worktree = self.ctx.worktree_manager.get(task_id).await?.worktree_path;
+        Some(PostEditTarget {
+            task_id: task_id.to_string(),
+            worktree,
+            files,
+        })
+    }
+
+    async fn task_state(&self, arguments: &Value) -> anyhow::Result<Option<TaskState>> {
+        task_state(&self.ctx, arguments).await
+    }
+
     /// Verify that the `task_id` in `arguments` corresponds to a task that is
     /// currently `in_progress` and claimed by `agent_id`.
     ///
@@ -158,8 +470,44 @@ impl McpDispatcher {
         } else if msg.starts_with("MCP_PROVIDER_NOT_AVAILABLE:") {
             let detail = msg.trim_start_matches("MCP_PROVIDER_NOT_AVAILABLE:").trim();
             McpError::new(MCP_PROVIDER_NOT_AVAILABLE, detail)
+        } else if msg.starts_with("MCP_POLICY_DENIED:") {
+            let detail = msg.trim_start_matches("MCP_POLICY_DENIED:").trim();
+            McpError::new(MCP_POLICY_DENIED, detail)
         } else {
             McpError::new(-32603, "internal error")
         }
     }
 }
+
+/// Task state for policy purposes: `Active` when `task_id` names an
+/// in-progress task, otherwise none.
+pub(crate) async fn task_state(
+    ctx: &AppContext,
+    arguments: &Value,
+) -> anyhow::Result<Option<TaskState>> {
+    let Some(task_id) = arguments.get("task_id").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    let task = ctx.task_storage.get_task(task_id).await?;
+    Ok(task
+        .filter(|t| t.status == "in_progress")
+        .map(|_| TaskState::Active))
+}
+
+/// The `tools/call` error for a call policy did not allow.
+fn policy_error(decision: PolicyDecision) -> anyhow::Error {
+    match decision {
+        PolicyDecision::NeedsApproval { reason, .. } => {
+            anyhow::anyhow!("MCP_POLICY_DENIED: approval required: {}", reason)
+        }
+        PolicyDecision::Deny { reason } => anyhow::anyhow!("MCP_POLICY_DENIED: {}", reason),
+        PolicyDecision::Allow => anyhow::anyhow!("MCP_POLICY_DENIED: not allowed"),
+    }
+}
+
+/// Whether the upstream tool `name` (`<server>__<tool>`) belongs to a server
+/// that has the name of one of `plugins`.
+fn shadows_plugin(plugins: &HashSet<String>, name: &str) -> bool {
+    name.split_once(TOOL_SEPARATOR)
+        .is_some_and(|(server, _)| plugins.contains(server))
+}
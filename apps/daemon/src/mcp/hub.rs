@@ -0,0 +1,528 @@
+//! `McpHub` — keeps the upstream servers in `.claw/mcp-servers.json` running
+//! and publishes their tools.
+//!
+//! Each server gets a supervisor task that:
+//!
+//! 1. admits it: the server must be `trusted` in
+//!    `.claw/policies/mcp-trust.json`, and its fingerprint (command, args
+//!    and env, or URL and headers for remote servers) must match the one
+//!    recorded in
+//!    `.claw/policies/mcp-allowlist.json` on its first connect.  A refused
+//!    server is `blocked` and never retried;
+//! 2. connects and publishes the tools its trust entry allows as
+//!    `<server>__<tool>`;
+//! 3. pings it every 30 s.  When the client cannot get the connection back,
+//!    the server is restarted with backoff — 1 s, doubling up to 5 min, and
+//!    back to 1 s once it has stayed up for 5 min.
+//!
+//! `McpDispatcher` serves the published tools next to plugin tools, and the
+//! `mcp.status` RPC reports each server's `ServerStatus`.  State changes are
+//! broadcast as `mcp.serverStatusChanged`.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::AbortHandle;
+use tracing::{info, warn};
+
+use super::client::{McpClient, McpServerConfig};
+use super::config::McpServersConfig;
+use super::sampling::SamplingHandler;
+use super::tools::McpToolDef;
+use super::transport::McpTransport;
+use crate::ipc::event::EventBroadcaster;
+use crate::policy::mcp_trust::{TrustDatabase, TrustLevel};
+use crate::policy::supply_chain::SupplyChainPolicy;
+use crate::policy::PolicyEngine;
+
+/// Joins a server name and one of its tool names: `<server>__<tool>`.
+pub const TOOL_SEPARATOR: &str = "__";
+
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+/// A ping that takes longer than this counts as a failure.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(300);
+/// A server that stays up this long restarts at the base delay next time.
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+
+// ─── Status ───────────────────────────────────────────────────────────────────
+
+/// Where a server is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerState {
+    /// Connecting and listing tools.
+    Starting,
+    /// Connected; its tools are published.
+    Running,
+    /// Down, waiting for the next restart attempt.
+    Restarting,
+    /// Refused by the trust database, the supply-chain allowlist or its
+    /// config.  Not retried.
+    Blocked,
+}
+
+/// One server's entry in `mcp.status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub name: String,
+    /// `stdio`, `http`, `sse` or `websocket`.
+    pub transport: &'static str,
+    pub state: ServerState,
+    /// Published tool names (`<server>__<tool>`).
+    pub tools: Vec<String>,
+    /// Restarts since the hub started, including ones the client made on
+    /// its own after a dropped connection.
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// When the server entered its current state.
+    pub since: DateTime<Utc>,
+    /// The next restart attempt, while `restarting`.
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+/// A supervised server: its status, and its client while running.
+struct Upstream {
+    /// The `start` call that created the entry; a supervisor from an earlier
+    /// one must not touch it.
+    epoch: u64,
+    status: ServerStatus,
+    client: Option<Arc<McpClient>>,
+    tools: Vec<McpToolDef>,
+}
+
+impl Upstream {
+    fn new(config: &McpServerConfig, epoch: u64) -> Self {
+        Self {
+            epoch,
+            status: ServerStatus {
+                name: config.name.clone(),
+                transport: transport_name(&config.transport),
+                state: ServerState::Starting,
+                tools: Vec::new(),
+                restarts: 0,
+                last_error: None,
+                since: Utc::now(),
+                retry_at: None,
+            },
+            client: None,
+            tools: Vec::new(),
+        }
+    }
+}
+
+type Servers = Arc<std::sync::RwLock<BTreeMap<String, Upstream>>>;
+
+fn transport_name(transport: &McpTransport) -> &'static str {
+    match transport {
+        McpTransport::Stdio => "stdio",
+        McpTransport::Http(_) => "http",
+        McpTransport::Sse(_) => "sse",
+        McpTransport::WebSocket(_) => "websocket",
+    }
+}
+
+// ─── McpHub ───────────────────────────────────────────────────────────────────
+
+/// Supervises the configured upstream MCP servers.
+pub struct McpHub {
+    trust_db: Arc<RwLock<TrustDatabase>>,
+    supply_chain: Arc<RwLock<SupplyChainPolicy>>,
+    broadcaster: Option<Arc<EventBroadcaster>>,
+    health_interval: Duration,
+    restart_delay: Duration,
+    servers: Servers,
+    supervisors: Mutex<Vec<AbortHandle>>,
+    epoch: AtomicU64,
+}
+
+impl McpHub {
+    /// A hub with no servers that admits them with `policy`'s trust
+    /// database and supply-chain allowlist.
+    pub fn new(policy: &PolicyEngine) -> Self {
+        Self {
+            trust_db: Arc::clone(&policy.trust_db),
+            supply_chain: Arc::clone(&policy.supply_chain),
+            broadcaster: None,
+            health_interval: HEALTH_INTERVAL,
+            restart_delay: RESTART_BASE_DELAY,
+            servers: Arc::default(),
+            supervisors: Mutex::new(Vec::new()),
+            epoch: AtomicU64::new(0),
+        }
+    }
+
+    /// Broadcast `mcp.serverStatusChanged` on every state change.
+    pub fn with_events(mut self, broadcaster: Arc<EventBroadcaster>) -> Self {
+        self.broadcaster = Some(broadcaster);
+        self
+    }
+
+    /// Ping running servers every `interval` instead of every 30 s.
+    pub fn with_health_interval(mut self, interval: Duration) -> Self {
+        self.health_interval = interval;
+        self
+    }
+
+    /// Start the restart backoff at `delay` instead of 1 s.
+    pub fn with_restart_delay(mut self, delay: Duration) -> Self {
+        self.restart_delay = delay;
+        self
+    }
+
+    /// Supervise `config`'s servers, replacing any started before.
+    /// `sampler` answers `sampling/createMessage` for servers that opt in.
+    pub fn start(&self, config: McpServersConfig, sampler: Option<Arc<dyn SamplingHandler>>) {
+        let mut supervisors = self.supervisors.lock().unwrap_or_else(|e| e.into_inner());
+        for supervisor in supervisors.drain(..) {
+            supervisor.abort();
+        }
+        let epoch = self.epoch.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut admitted = Vec::new();
+        {
+            let mut servers = self.servers.write().unwrap_or_else(|e| e.into_inner());
+            servers.clear();
+            for server in config.servers {
+                if servers.contains_key(&server.name) {
+                    warn!(server = %server.name, "duplicate MCP server name — entry ignored");
+                    continue;
+                }
+                let mut upstream = Upstream::new(&server, epoch);
+                if server.name.is_empty() || server.name.contains(TOOL_SEPARATOR) {
+                    upstream.status.state = ServerState::Blocked;
+                    upstream.status.last_error = Some(format!(
+                        "server names must be non-empty and may not contain '{TOOL_SEPARATOR}'"
+                    ));
+                } else {
+                    admitted.push(server.clone());
+                }
+                servers.insert(server.name, upstream);
+            }
+        }
+
+        for config in admitted {
+            let supervisor = Supervisor {
+                epoch,
+                config,
+                sampler: sampler.clone(),
+                servers: Arc::clone(&self.servers),
+                trust_db: Arc::clone(&self.trust_db),
+                supply_chain: Arc::clone(&self.supply_chain),
+                broadcaster: self.broadcaster.clone(),
+                health_interval: self.health_interval,
+                restart_delay: self.restart_delay,
+            };
+            supervisors.push(tokio::spawn(supervisor.run()).abort_handle());
+        }
+    }
+
+    /// Every server's status, by name.
+    pub fn status(&self) -> Vec<ServerStatus> {
+        self.servers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|u| u.status.clone())
+            .collect()
+    }
+
+    /// The published tools of every running server.
+    pub fn tools(&self) -> Vec<McpToolDef> {
+        self.servers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .flat_map(|u| u.tools.iter().cloned())
+            .collect()
+    }
+
+    /// Whether `name` is a published tool.
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.servers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .any(|u| u.tools.iter().any(|t| t.name == name))
+    }
+
+    /// Call the published tool `name` (`<server>__<tool>`).  The result is
+    /// the server's `CallToolResult`.
+    pub async fn call_tool(&self, name: &str, args: Value) -> Result<Value> {
+        let (client, tool) = {
+            let servers = self.servers.read().unwrap_or_else(|e| e.into_inner());
+            let (server, tool) = name
+                .split_once(TOOL_SEPARATOR)
+                .ok_or_else(|| anyhow!("'{name}' is not an upstream MCP tool"))?;
+            let upstream = servers
+                .get(server)
+                .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+            let Some(client) = upstream.client.clone() else {
+                bail!("MCP server '{server}' is not running");
+            };
+            if !upstream.tools.iter().any(|t| t.name == name) {
+                bail!("MCP server '{server}' does not publish '{tool}'");
+            }
+            (client, tool.to_string())
+        };
+        client.call_tool(&tool, args).await
+    }
+}
+
+impl Drop for McpHub {
+    fn drop(&mut self) {
+        for supervisor in self
+            .supervisors
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+        {
+            supervisor.abort();
+        }
+    }
+}
+
+// ─── Supervision ──────────────────────────────────────────────────────────────
+
+/// Keeps one server running.
+struct Supervisor {
+    epoch: u64,
+    config: McpServerConfig,
+    sampler: Option<Arc<dyn SamplingHandler>>,
+    servers: Servers,
+    trust_db: Arc<RwLock<TrustDatabase>>,
+    supply_chain: Arc<RwLock<SupplyChainPolicy>>,
+    broadcaster: Option<Arc<EventBroadcaster>>,
+    health_interval: Duration,
+    restart_delay: Duration,
+}
+
+impl Supervisor {
+    async fn run(self) {
+        let name = self.config.name.clone();
+        let mut delay = self.restart_delay;
+        loop {
+            if let Err(reason) = self.admit().await {
+                warn!(server = %name, reason = %reason, "MCP server blocked");
+                self.update(ServerState::Blocked, |u| {
+                    u.status.last_error = Some(reason);
+                });
+                return;
+            }
+
+            let started = Instant::now();
+            let err = self.serve().await;
+            if started.elapsed() >= STABLE_AFTER {
+                delay = self.restart_delay;
+            }
+            warn!(server = %name, err = %format!("{err:#}"), retry_in = ?delay, "MCP server down — restarting");
+            let retry_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+            self.update(ServerState::Restarting, |u| {
+                u.status.restarts += 1;
+                u.status.last_error = Some(format!("{err:#}"));
+                u.status.retry_at = Some(retry_at);
+            });
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(RESTART_MAX_DELAY);
+        }
+    }
+
+    /// Check the trust database and the supply-chain allowlist.
+    async fn admit(&self) -> Result<(), String> {
+        let name = &self.config.name;
+        if self.trust_db.read().await.get_trust(name) != TrustLevel::Trusted {
+            return Err(format!(
+                "'{name}' is not trusted in .claw/policies/mcp-trust.json"
+            ));
+        }
+        let config = &self.config;
+        let (command, args, settings): (&str, Vec<&str>, Vec<(String, &str)>) = match &config
+            .transport
+        {
+            McpTransport::Stdio => (
+                &config.command,
+                config.args.iter().map(String::as_str).collect(),
+                config
+                    .env
+                    .iter()
+                    .map(|(k, v)| (format!("env:{k}"), v.as_str()))
+                    .collect(),
+            ),
+            McpTransport::Http(url) | McpTransport::Sse(url) | McpTransport::WebSocket(url) => (
+                url,
+                Vec::new(),
+                config
+                    .headers
+                    .iter()
+                    .map(|(k, v)| (format!("header:{}", k.to_ascii_lowercase()), v.as_str()))
+                    .collect(),
+            ),
+        };
+        let settings: Vec<(&str, &str)> = settings.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        self.supply_chain
+            .write()
+            .await
+            .verify_or_register(name, command, &args, &settings)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Connect, publish the server's tools and health-check it.  Returns the
+    /// error that took it down.
+    async fn serve(&self) -> anyhow::Error {
+        self.update(ServerState::Starting, |_| {});
+        let connected = match &self.sampler {
+            Some(sampler) => {
+                McpClient::connect_with_sampling(self.config.clone(), Arc::clone(sampler)).await
+            }
+            None => McpClient::connect(self.config.clone()).await,
+        };
+        let client = match connected {
+            Ok(client) => Arc::new(client),
+            Err(e) => return e,
+        };
+        let mut generation = client.generation().await;
+        if let Err(e) = self.publish(&client).await {
+            return e;
+        }
+
+        let mut ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + self.health_interval,
+            self.health_interval,
+        );
+        loop {
+            ticker.tick().await;
+            let pinged = tokio::time::timeout(PING_TIMEOUT, client.ping())
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("ping timed out")));
+            if let Err(e) = pinged {
+                client.close().await;
+                return e;
+            }
+            // The client reconnected on its own; the tools may have changed.
+            let current = client.generation().await;
+            if current != generation {
+                let reconnects = u32::try_from(current - generation).unwrap_or(u32::MAX);
+                generation = current;
+                self.update(ServerState::Running, |u| u.status.restarts += reconnects);
+                if let Err(e) = self.publish(&client).await {
+                    return e;
+                }
+            }
+        }
+    }
+
+    /// List the server's tools and publish the ones its trust entry allows.
+    async fn publish(&self, client: &Arc<McpClient>) -> Result<()> {
+        let name = &self.config.name;
+        let listed = client.list_tools().await?;
+        let tools: Vec<McpToolDef> = {
+            let trust_db = self.trust_db.read().await;
+            listed
+                .into_iter()
+                .filter(|t| trust_db.is_tool_allowed(name, &t.name))
+                .map(|t| McpToolDef {
+                    name: format!("{name}{TOOL_SEPARATOR}{}", t.name),
+                    ..t
+                })
+                .collect()
+        };
+        info!(server = %name, tools = tools.len(), "MCP server running");
+        self.update(ServerState::Running, |u| {
+            u.status.tools = tools.iter().map(|t| t.name.clone()).collect();
+            u.status.last_error = None;
+            u.client = Some(Arc::clone(client));
+            u.tools = tools;
+        });
+        Ok(())
+    }
+
+    /// Move to `state`, apply `change`, and broadcast the new status when
+    /// the state changed.
+    fn update(&self, state: ServerState, change: impl FnOnce(&mut Upstream)) {
+        let status = {
+            let mut servers = self.servers.write().unwrap_or_else(|e| e.into_inner());
+            let Some(upstream) = servers
+                .get_mut(&self.config.name)
+                .filter(|u| u.epoch == self.epoch)
+            else {
+                return;
+            };
+            let changed = upstream.status.state != state;
+            if changed {
+                upstream.status.state = state;
+                upstream.status.since = Utc::now();
+                if state != ServerState::Running {
+                    upstream.client = None;
+                    upstream.tools.clear();
+                    upstream.status.tools.clear();
+                }
+                if state != ServerState::Restarting {
+                    upstream.status.retry_at = None;
+                }
+            }
+            change(upstream);
+            changed.then(|| upstream.status.clone())
+        };
+        if let (Some(status), Some(broadcaster)) = (status, &self.broadcaster) {
+            broadcaster.broadcast(
+                "mcp.serverStatusChanged",
+                serde_json::to_value(status).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn untrusted_and_misnamed_servers_are_blocked() {
+        let hub = McpHub::new(&PolicyEngine::new(
+            Arc::default(),
+            Arc::default(),
+            Arc::new(RwLock::new(SupplyChainPolicy::empty())),
+        ));
+        let config = McpServersConfig::parse(
+            r#"{ "servers": [
+                { "name": "stranger", "command": "true" },
+                { "name": "bad__name", "command": "true" }
+            ] }"#,
+        )
+        .unwrap();
+        hub.start(config, None);
+
+        let blocked = |hub: &McpHub| {
+            hub.status()
+                .iter()
+                .filter(|s| s.state == ServerState::Blocked)
+                .count()
+        };
+        for _ in 0..50 {
+            if blocked(&hub) == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let status = hub.status();
+        assert_eq!(blocked(&hub), 2, "{status:?}");
+        assert!(status[0].last_error.as_deref().unwrap().contains("'__'"));
+        assert!(status[1]
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("mcp-trust.json"));
+        assert!(hub.tools().is_empty());
+        assert!(hub.call_tool("stranger__x", Value::Null).await.is_err());
+    }
+}
@@ -3,32 +3,45 @@
 //! This module covers two roles:
 //!
 //! 1. **MCP Server** — `clawd` exposes its task-management tools to MCP clients
-//!    (e.g. Claude Code, Codex) via `tools/list` and `tools/call`.
+//!    (e.g. Claude Code, Codex) via `tools/list` and `tools/call`, plus any
+//!    tools contributed by plugins (`<plugin>__<tool>`).  Served over stdio
+//!    (`clawd mcp serve --stdio`) and Streamable HTTP (`/api/v1/mcp`).
 //!
-//! 2. **MCP Client** — `clawd` can connect to upstream MCP servers (configured
-//!    in `.claw/mcp-servers.json`) and proxy their tools to local agents.
+//! 2. **MCP Client** — `clawd` connects to the upstream MCP servers configured
+//!    in `.claw/mcp-servers.json`, keeps them running, and republishes their
+//!    tools as `<server>__<tool>`.
 //!
 //! ## Protocol version
-//! MCP 2024-11-05.
+//! MCP 2025-03-26, falling back to 2024-11-05 for older clients.
 //!
 //! ## Submodules
 //!
 //! | Module | Role |
 //! |--------|------|
 //! | `transport` | JSON-RPC wire types, lifecycle handlers, progress notifications |
-//! | `tools` | `tools/list` response — the 7 ClawDE tool definitions |
-//! | `dispatch` | `tools/call` dispatcher — routes to `tools::task` / `tools::patch` |
+//! | `tools` | `tools/list` response — the 14 ClawDE tool definitions |
+//! | `dispatch` | `tools/call` dispatcher — routes to `tools::task` / `tools::patch` / `tools::lsp`, plugin tools and upstream tools |
+//! | `server` | Server sessions: lifecycle, tools, resources and subscriptions, prompts, progress, cancellation |
+//! | `resources` | `resources/list` / `resources/read`, and which events change which resources |
+//! | `prompts` | `prompts/list` / `prompts/get` — workflow recipes and instruction snapshots |
 //! | `capabilities` | Capability negotiation during `initialize` handshake |
 //! | `config` | `.claw/mcp-servers.json` loader |
-//! | `client` | Upstream MCP client (stdio subprocess) |
+//! | `client` | Upstream MCP client over stdio, Streamable HTTP, HTTP+SSE or WebSocket |
+//! | `hub` | Supervises upstream servers: trust and supply-chain admission, restarts, tool namespacing |
+//! | `sampling` | `sampling/createMessage` from upstream servers, run on the daemon's HTTP providers |
 //! | `tools::task` | create_task, claim_task, log_event, run_tests, request_approval, transition_task |
 //! | `tools::patch` | apply_patch with idempotency |
+//! | `tools::lsp` | lsp_hover, lsp_definition, lsp_references, lsp_rename, lsp_code_actions, lsp_document_symbols, lsp_workspace_symbols |
 
 pub mod capabilities;
 pub mod client;
 pub mod config;
 pub mod dispatch;
+pub mod hub;
+pub mod prompts;
 pub mod resources;
+pub mod sampling;
+pub mod server;
 pub mod tools;
 pub mod transport;
 
@@ -38,17 +51,25 @@ pub use transport::{
     handle_initialize, handle_initialized, handle_ping, send_progress, McpCancelledNotification,
     McpError, McpMessage, McpProgressNotification, McpResponse, McpTransport, McpTransportHandler,
     MCP_INTERNAL_ERROR, MCP_INVALID_PARAMS, MCP_INVALID_REQUEST, MCP_METHOD_NOT_FOUND,
-    MCP_PARSE_ERROR, MCP_PROVIDER_NOT_AVAILABLE,
+    MCP_PARSE_ERROR, MCP_POLICY_DENIED, MCP_PROVIDER_NOT_AVAILABLE, SUPPORTED_PROTOCOL_VERSIONS,
 };
 
 pub use tools::{clawd_tools, handle_tools_list, McpToolDef};
 
 pub use dispatch::McpDispatcher;
 
+pub use server::{McpSession, McpSessions};
+
 pub use client::{McpClient, McpServerConfig, McpTrustLevel};
 
 pub use config::McpServersConfig;
 
+pub use hub::{McpHub, ServerState, ServerStatus};
+
 pub use capabilities::{negotiate, ClawdCapabilities};
 
 pub use resources::{list_resources, read_resource, ResourceDescriptor};
+
+pub use prompts::{get_prompt, list_prompts, PromptDescriptor};
+
+pub use sampling::{DaemonSampler, SamplingHandler};
@@ -0,0 +1,287 @@
+//! MCP `prompts/list` and `prompts/get`.
+//!
+//! | Prompt | Content |
+//! |--------|---------|
+//! | `instructions` | The project's instruction snapshot (`.instruction-snapshot.md`), or a live compile when there is none |
+//! | `workflow/<name>` | One user message per step of a workflow recipe, with its inputs filled in |
+//!
+//! Workflow prompts cover the built-in recipes and every stored recipe; a
+//! stored recipe replaces a built-in of the same name.  Recipe inputs become
+//! prompt arguments — required unless they have a default or a daemon
+//! source (`from: git_diff`), which is computed in the first registered repo.
+//!
+//! Prompts only read registered repos, and of those only the ones the
+//! session's token may reach.  Bad names and arguments are `MCP_INVALID_PARAMS`
+//! errors; anything else is an internal error.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::Row as _;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::api_tokens::{canonical_path, ApiToken};
+use crate::instructions::compiler::{CompileTarget, InstructionCompiler};
+use crate::instructions::snapshot::read_snapshot;
+use crate::workflows::engine::{builtin_recipes, parse_recipe_yaml, WorkflowRecipeYaml};
+use crate::workflows::plan::{render_template, WorkflowPlan};
+use crate::workflows::runner::compute_input;
+use crate::AppContext;
+
+const INSTRUCTIONS_PROMPT: &str = "instructions";
+const WORKFLOW_PREFIX: &str = "workflow/";
+
+// ─── Prompt descriptors ───────────────────────────────────────────────────────
+
+/// One entry of a `prompts/list` response.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDescriptor {
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptArgument {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+/// Every prompt `clawd` offers.
+pub async fn list_prompts(ctx: &Arc<AppContext>) -> Vec<PromptDescriptor> {
+    let mut prompts = vec![PromptDescriptor {
+        name: INSTRUCTIONS_PROMPT.to_string(),
+        description: "Effective agent instructions for a project".to_string(),
+        arguments: vec![
+            PromptArgument {
+                name: "path".to_string(),
+                description: "Project directory (defaults to the first registered repo)"
+                    .to_string(),
+                required: false,
+            },
+            PromptArgument {
+                name: "target".to_string(),
+                description: "`claude` (default) or `codex`".to_string(),
+                required: false,
+            },
+        ],
+    }];
+    prompts.extend(recipes(ctx).await.values().map(recipe_prompt));
+    prompts
+}
+
+/// Built-in and stored recipes by name, stored ones winning.
+async fn recipes(ctx: &Arc<AppContext>) -> BTreeMap<String, WorkflowRecipeYaml> {
+    let mut recipes: BTreeMap<String, WorkflowRecipeYaml> = builtin_recipes()
+        .into_iter()
+        .map(|r| (r.name.clone(), r))
+        .collect();
+    let rows = sqlx::query("SELECT name, template_yaml FROM workflow_recipes")
+        .fetch_all(ctx.storage.pool())
+        .await
+        .unwrap_or_else(|e| {
+            warn!(err = %e, "MCP prompts: failed to load workflow recipes");
+            Vec::new()
+        });
+    for row in rows {
+        let name: String = row.get("name");
+        match parse_recipe_yaml(row.get("template_yaml")) {
+            Ok(recipe) => {
+                recipes.insert(name, recipe);
+            }
+            Err(e) => warn!(recipe = %name, err = %e, "MCP prompts: skipping invalid recipe"),
+        }
+    }
+    recipes
+}
+
+fn recipe_prompt(recipe: &WorkflowRecipeYaml) -> PromptDescriptor {
+    PromptDescriptor {
+        name: format!("{WORKFLOW_PREFIX}{}", recipe.name),
+        description: recipe.description.clone(),
+        arguments: recipe
+            .inputs
+            .iter()
+            .map(|input| PromptArgument {
+                name: input.name.clone(),
+                description: input.description.clone(),
+                required: input.default.is_none() && input.from.is_none(),
+            })
+            .collect(),
+    }
+}
+
+// ─── Prompt rendering ─────────────────────────────────────────────────────────
+
+/// The `prompts/get` result for `name` with string `args`, for a session
+/// authenticated with `token` (`None` for the daemon token).
+pub async fn get_prompt(
+    ctx: &Arc<AppContext>,
+    name: &str,
+    args: &Value,
+    token: Option<&ApiToken>,
+) -> Result<Value> {
+    let args: HashMap<String, String> = args
+        .as_object()
+        .map(|o| {
+            o.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if name == INSTRUCTIONS_PROMPT {
+        let repos = allowed_repos(ctx, token).await;
+        let path = match args.get("path") {
+            Some(path) => {
+                let wanted = canonical_path(path);
+                repos
+                    .into_iter()
+                    .find(|repo| canonical_path(repo) == wanted)
+                    .ok_or_else(|| anyhow!("MCP_INVALID_PARAMS: {path} is not a registered repo"))?
+            }
+            None => first_repo(repos)?,
+        };
+        let target = CompileTarget::parse(args.get("target").map_or("claude", String::as_str));
+        let text = instructions(ctx, &path, target).await?;
+        return Ok(json!({
+            "description": format!("Agent instructions for {path}"),
+            "messages": [user_message(&text)],
+        }));
+    }
+
+    let recipe_name = name
+        .strip_prefix(WORKFLOW_PREFIX)
+        .ok_or_else(|| anyhow!("MCP_INVALID_PARAMS: unknown prompt: {name}"))?;
+    let recipe = recipes(ctx)
+        .await
+        .remove(recipe_name)
+        .ok_or_else(|| anyhow!("MCP_INVALID_PARAMS: unknown prompt: {name}"))?;
+    let plan = WorkflowPlan::build(&recipe)?;
+    let (mut inputs, computed) = plan
+        .resolve_inputs(&args)
+        .map_err(|e| anyhow!("MCP_INVALID_PARAMS: {e}"))?;
+    if !computed.is_empty() {
+        let repo = first_repo(allowed_repos(ctx, token).await)?;
+        for (input, source) in computed {
+            let value = compute_input(source, &repo)
+                .await
+                .with_context(|| format!("computing input '{input}'"))?;
+            inputs.insert(input, value);
+        }
+    }
+    Ok(json!({
+        "description": recipe.description,
+        "messages": step_messages(&plan, &inputs),
+    }))
+}
+
+/// The golden snapshot for Claude targets when one exists, otherwise the
+/// compiled instructions.
+async fn instructions(ctx: &Arc<AppContext>, path: &str, target: CompileTarget) -> Result<String> {
+    if target == CompileTarget::Claude {
+        if let Some(snapshot) = read_snapshot(&Path::new(path).join(".instruction-snapshot.md"))
+            .await
+            .context("reading instruction snapshot")?
+        {
+            return Ok(snapshot);
+        }
+    }
+    let output = InstructionCompiler::new(&ctx.storage)
+        .compile(target, path)
+        .await?;
+    if output.content.trim().is_empty() {
+        bail!("no instructions for {path}");
+    }
+    Ok(output.content)
+}
+
+/// One user message per step, in recipe order.  References to other steps'
+/// outputs stay as placeholders.
+fn step_messages(plan: &WorkflowPlan, inputs: &HashMap<String, String>) -> Vec<Value> {
+    plan.steps
+        .iter()
+        .map(|step| user_message(&render_template(&step.prompt, inputs, &HashMap::new())))
+        .collect()
+}
+
+fn user_message(text: &str) -> Value {
+    json!({ "role": "user", "content": { "type": "text", "text": text } })
+}
+
+/// Registered repos, limited to a repo-limited token's repos, in order.
+async fn allowed_repos(ctx: &Arc<AppContext>, token: Option<&ApiToken>) -> Vec<String> {
+    let mut repos = ctx.repo_registry.list_paths().await;
+    repos.sort();
+    repos.retain(|repo| token.is_none_or(|t| t.repos.is_empty() || t.allows_repo(repo)));
+    repos
+}
+
+fn first_repo(repos: Vec<String>) -> Result<String> {
+    repos
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("MCP_INVALID_PARAMS: no registered repository is available"))
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: &str = r#"
+name: triage
+description: Triage an issue
+inputs:
+  - name: issue
+    description: Issue text
+  - name: tone
+    default: terse
+  - name: diff
+    from: git_diff
+steps:
+  - id: read
+    prompt: "Summarize {issue} in a {tone} way."
+  - prompt: "Fix it. Earlier: {steps.read.output}"
+"#;
+
+    #[test]
+    fn recipe_inputs_become_arguments() {
+        let prompt = recipe_prompt(&parse_recipe_yaml(RECIPE).unwrap());
+        assert_eq!(prompt.name, "workflow/triage");
+        let required: Vec<(&str, bool)> = prompt
+            .arguments
+            .iter()
+            .map(|a| (a.name.as_str(), a.required))
+            .collect();
+        assert_eq!(
+            required,
+            [("issue", true), ("tone", false), ("diff", false)]
+        );
+    }
+
+    #[test]
+    fn steps_render_as_user_messages() {
+        let plan = WorkflowPlan::build(&parse_recipe_yaml(RECIPE).unwrap()).unwrap();
+        let inputs = HashMap::from([
+            ("issue".to_string(), "a crash".to_string()),
+            ("tone".to_string(), "terse".to_string()),
+        ]);
+        let messages = step_messages(&plan, &inputs);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(
+            messages[0]["content"]["text"],
+            "Summarize a crash in a terse way."
+        );
+        assert_eq!(
+            messages[1]["content"]["text"],
+            "Fix it. Earlier: {steps.read.output}"
+        );
+    }
+}
@@ -9,7 +9,11 @@
 //! | `clawd://tasks` | JSON list of all active agent tasks |
 //! | `clawd://task/{id}` | JSON object with full task detail |
 //! | `clawd://repo/{path}` | UTF-8 file content from the active repo |
+//!
+//! `uris_for_event` maps daemon events onto these URIs so subscribed
+//! sessions get `notifications/resources/updated` (`server::McpSession`).
 
+use crate::api_tokens::{SESSION_KEYS, TASK_KEYS};
 use crate::AppContext;
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -254,6 +258,52 @@ async fn read_repo_file(ctx: &Arc<AppContext>, rel_path: &str) -> Result<Value,
     Ok(make_text_content(&uri, mime, &content))
 }
 
+// ─── Change tracking ──────────────────────────────────────────────────────────
+
+/// Resource URIs whose content a daemon event changes, for
+/// `notifications/resources/updated`.  Streaming deltas are skipped — the
+/// `session.messageUpdated` that follows them covers the message.
+pub fn uris_for_event(method: &str, params: &Value) -> Vec<String> {
+    let id = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| params.get(*k).and_then(Value::as_str))
+            .map(str::to_string)
+    };
+    let mut uris = Vec::new();
+    if let Some(event) = method.strip_prefix("session.") {
+        let Some(session_id) = id(SESSION_KEYS) else {
+            return uris;
+        };
+        if matches!(event, "statusChanged" | "messageCreated") {
+            uris.push("clawd://sessions".to_string());
+        }
+        if event.starts_with("message") && event != "message.delta" || event.starts_with("toolCall")
+        {
+            uris.push(format!("clawd://session/{session_id}/messages"));
+        }
+    } else if method.starts_with("task.") {
+        if let Some(task_id) = id(TASK_KEYS) {
+            uris.push("clawd://tasks".to_string());
+            uris.push(format!("clawd://task/{task_id}"));
+        }
+    }
+    uris
+}
+
+/// Whether `uris_for_event` can report changes to `uri`.  Repo files are
+/// not tracked.
+pub fn is_subscribable(uri: &str) -> bool {
+    uri == "clawd://sessions"
+        || uri == "clawd://tasks"
+        || uri
+            .strip_prefix("clawd://task/")
+            .is_some_and(|id| !id.is_empty())
+        || uri
+            .strip_prefix("clawd://session/")
+            .and_then(|s| s.strip_suffix("/messages"))
+            .is_some_and(|id| !id.is_empty())
+}
+
 // ─── Helpers ──────────────────────────────────────────────────────────────────
 
 fn make_text_content(uri: &str, mime_type: &str, text: &str) -> Value {
@@ -290,6 +340,28 @@ fn mime_for_extension(path: &str) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn events_map_to_the_resources_they_change() {
+        assert_eq!(
+            uris_for_event("session.messageCreated", &json!({ "sessionId": "s1" })),
+            ["clawd://sessions", "clawd://session/s1/messages"]
+        );
+        assert_eq!(
+            uris_for_event("session.statusChanged", &json!({ "session_id": "s1" })),
+            ["clawd://sessions"]
+        );
+        assert!(uris_for_event("session.message.delta", &json!({ "sessionId": "s1" })).is_empty());
+        assert_eq!(
+            uris_for_event("task.statusChanged", &json!({ "task_id": "t1" })),
+            ["clawd://tasks", "clawd://task/t1"]
+        );
+        assert!(uris_for_event("daemon.ready", &json!({})).is_empty());
+
+        assert!(is_subscribable("clawd://session/s1/messages"));
+        assert!(is_subscribable("clawd://task/t1"));
+        assert!(!is_subscribable("clawd://repo/README.md"));
+    }
+
     #[test]
     fn mime_for_rust_file() {
         assert_eq!(mime_for_extension("src/main.rs"), "text/x-rust");
@@ -0,0 +1,281 @@
+//! `sampling/createMessage` — completions requested by upstream MCP servers.
+//!
+//! Upstream servers configured with `"sampling": true` may ask clawd for a
+//! model completion.  `DaemonSampler` answers them with a direct HTTP
+//! provider (`[provider.<name>] api = ...`), under the same controls as the
+//! daemon's own work:
+//!
+//! - requests are refused once the month's spend reaches
+//!   `model_intelligence.monthly_budget_usd`;
+//! - when accounts are registered for the provider, the call runs on one the
+//!   `AccountPool` has available (its key is read from the environment
+//!   variable named by `vault_ref`, falling back to the profile's key); a 429
+//!   marks that account rate-limited;
+//! - token usage is recorded under the session id `mcp-sampling:<server>`.
+//!
+//! `modelPreferences.hints` pick among providers by model name; without a
+//! match the first provider by name is used.  Only text content is
+//! supported, and the completion runs without tools.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::config::{HttpApi, ProviderProfile};
+use crate::session::http::{request_body, HttpEndpoint, SseDecoder, StreamDecoder, Turn};
+use crate::AppContext;
+
+/// How long an account stays blocked after a 429 from the provider.
+const RATE_LIMIT_BACKOFF_SECS: u64 = 60;
+
+/// Answers `sampling/createMessage` requests from an upstream server.
+#[async_trait]
+pub trait SamplingHandler: Send + Sync {
+    /// `params` is the request's params; the result is the
+    /// `CreateMessageResult` (`role`, `content`, `model`, `stopReason`).
+    async fn create_message(&self, server: &str, params: Value) -> Result<Value>;
+}
+
+// ─── DaemonSampler ────────────────────────────────────────────────────────────
+
+/// Runs sampling requests on the daemon's HTTP providers.
+pub struct DaemonSampler {
+    ctx: Arc<AppContext>,
+    http: reqwest::Client,
+}
+
+impl DaemonSampler {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn check_budget(&self) -> Result<()> {
+        let cap = self.ctx.config.model_intelligence.monthly_budget_usd;
+        if cap <= 0.0 {
+            return Ok(());
+        }
+        let spent = self.ctx.token_tracker.get_monthly_total().await?;
+        if spent >= cap {
+            bail!("monthly budget of ${cap:.2} is spent (${spent:.2}); sampling is disabled");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SamplingHandler for DaemonSampler {
+    async fn create_message(&self, server: &str, params: Value) -> Result<Value> {
+        let history = sampling_turns(&params)?;
+        self.check_budget().await?;
+
+        let hints: Vec<String> = params
+            .pointer("/modelPreferences/hints")
+            .and_then(Value::as_array)
+            .map(|hints| {
+                hints
+                    .iter()
+                    .filter_map(|h| h["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let (provider, profile) = choose_provider(&self.ctx.config.http_providers(), &hints)
+            .ok_or_else(|| anyhow!("no HTTP provider is configured for sampling"))?;
+        let mut endpoint = HttpEndpoint::from_profile(&provider, &profile)?;
+
+        let pool = &self.ctx.account_pool;
+        let has_accounts = pool.list().await.iter().any(|a| a.provider == provider);
+        let account = if has_accounts {
+            let account = pool
+                .get_available(&provider)
+                .await
+                .ok_or_else(|| anyhow!("every '{provider}' account is rate-limited"))?;
+            // Without the account's own key the profile's key still applies.
+            if let Some(key) = std::env::var(&account.vault_ref)
+                .ok()
+                .filter(|k| !k.is_empty())
+            {
+                endpoint.api_key = Some(key);
+            }
+            Some(account)
+        } else {
+            None
+        };
+
+        if let Some(max) = params.get("maxTokens").and_then(Value::as_u64) {
+            endpoint.max_tokens = endpoint.max_tokens.min(max.max(1));
+        }
+        let system = params
+            .get("systemPrompt")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let mut body = request_body(&endpoint, system, &history, &[]);
+        if let Some(temperature) = params.get("temperature").filter(|t| t.is_number()) {
+            body["temperature"] = temperature.clone();
+        }
+        if let Some(stop) = params.get("stopSequences").filter(|s| s.is_array()) {
+            let key = match endpoint.api {
+                HttpApi::OpenAi => "stop",
+                HttpApi::Anthropic => "stop_sequences",
+            };
+            body[key] = stop.clone();
+        }
+
+        let resp = endpoint
+            .post(&self.http, &body)
+            .send()
+            .await
+            .with_context(|| format!("sampling request to provider '{provider}' failed"))?;
+        let status = resp.status();
+        if !status.is_success() {
+            if let (Some(account), reqwest::StatusCode::TOO_MANY_REQUESTS) = (&account, status) {
+                pool.mark_rate_limited(&account.account_id, RATE_LIMIT_BACKOFF_SECS)
+                    .await;
+            }
+            let body = resp.text().await.unwrap_or_default();
+            bail!(
+                "provider '{provider}' returned HTTP {status}: {}",
+                body.chars().take(500).collect::<String>()
+            );
+        }
+
+        let mut stream = resp.bytes_stream();
+        let mut sse = SseDecoder::default();
+        let mut decoder = StreamDecoder::new(endpoint.api);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("provider stream interrupted")?;
+            for data in sse.push(&chunk) {
+                decoder.apply(&data)?;
+            }
+        }
+        let completion = decoder.finish();
+
+        let tokens = completion.input_tokens + completion.output_tokens;
+        if let Some(account) = &account {
+            pool.record_usage(&account.account_id, u64::from(tokens))
+                .await;
+        }
+        if tokens > 0 {
+            self.ctx
+                .token_tracker
+                .record(
+                    &format!("mcp-sampling:{server}"),
+                    None,
+                    &endpoint.model,
+                    completion.input_tokens,
+                    completion.output_tokens,
+                )
+                .await?;
+        }
+        info!(server, provider = %provider, tokens, "MCP sampling request answered");
+        Ok(json!({
+            "role": "assistant",
+            "content": { "type": "text", "text": completion.text },
+            "model": endpoint.model,
+            "stopReason": "endTurn",
+        }))
+    }
+}
+
+// ─── Request mapping ──────────────────────────────────────────────────────────
+
+/// The request's messages as conversation turns.  Only text content is
+/// supported.
+fn sampling_turns(params: &Value) -> Result<Vec<Turn>> {
+    let messages = params
+        .get("messages")
+        .and_then(Value::as_array)
+        .filter(|m| !m.is_empty())
+        .ok_or_else(|| anyhow!("sampling request has no messages"))?;
+    messages
+        .iter()
+        .map(|m| {
+            let content = &m["content"];
+            let text = match content["type"].as_str() {
+                Some("text") => content["text"].as_str().unwrap_or_default().to_string(),
+                other => bail!(
+                    "unsupported sampling content type: {}",
+                    other.unwrap_or("none")
+                ),
+            };
+            match m["role"].as_str() {
+                Some("user") => Ok(Turn::User(text)),
+                Some("assistant") => Ok(Turn::Assistant {
+                    text,
+                    tool_calls: Vec::new(),
+                }),
+                other => bail!("unsupported sampling role: {}", other.unwrap_or("none")),
+            }
+        })
+        .collect()
+}
+
+/// The provider whose model matches the earliest hint, else the first by
+/// name.
+fn choose_provider(
+    providers: &HashMap<String, ProviderProfile>,
+    hints: &[String],
+) -> Option<(String, ProviderProfile)> {
+    let mut names: Vec<&String> = providers.keys().collect();
+    names.sort();
+    let model = |name: &String| providers[name].model.as_deref().unwrap_or_default();
+    let chosen = hints
+        .iter()
+        .find_map(|hint| names.iter().find(|n| model(n).contains(hint.as_str())))
+        .or_else(|| names.first())?;
+    Some(((*chosen).clone(), providers[*chosen].clone()))
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(model: &str) -> ProviderProfile {
+        ProviderProfile {
+            api: Some(HttpApi::OpenAi),
+            model: Some(model.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hints_pick_the_provider_by_model() {
+        let providers = HashMap::from([
+            ("local".to_string(), profile("qwen2.5-coder:14b")),
+            ("cloud".to_string(), profile("gpt-4o-mini")),
+        ]);
+        let pick = |hints: &[&str]| {
+            let hints: Vec<String> = hints.iter().map(|h| h.to_string()).collect();
+            choose_provider(&providers, &hints).map(|(name, _)| name)
+        };
+        assert_eq!(pick(&["qwen"]).as_deref(), Some("local"));
+        assert_eq!(pick(&["claude", "gpt-4o"]).as_deref(), Some("cloud"));
+        assert_eq!(pick(&["llama"]).as_deref(), Some("cloud"), "first by name");
+        assert!(choose_provider(&HashMap::new(), &[]).is_none());
+    }
+
+    #[test]
+    fn only_text_messages_are_accepted() {
+        let turns = sampling_turns(&json!({ "messages": [
+            { "role": "user", "content": { "type": "text", "text": "hi" } },
+            { "role": "assistant", "content": { "type": "text", "text": "hello" } },
+        ] }))
+        .unwrap();
+        assert_eq!(turns[0], Turn::User("hi".into()));
+        assert!(matches!(&turns[1], Turn::Assistant { text, .. } if text == "hello"));
+
+        let image = json!({ "messages": [
+            { "role": "user", "content": { "type": "image", "data": "", "mimeType": "image/png" } },
+        ] });
+        assert!(sampling_turns(&image).is_err());
+        assert!(sampling_turns(&json!({ "messages": [] })).is_err());
+    }
+}
@@ -0,0 +1,555 @@
+//! MCP server sessions — the protocol behind `clawd mcp serve --stdio` and
+//! the Streamable-HTTP endpoint `/api/v1/mcp` (`rest::mcp`).
+//!
+//! A session starts with `initialize` and then answers `ping`, `tools/list`,
+//! `tools/call` (through `McpDispatcher`), `resources/list`,
+//! `resources/templates/list`, `resources/read`, `resources/subscribe`,
+//! `resources/unsubscribe`, `prompts/list` and `prompts/get`.
+//!
+//! - A `tools/call` whose params carry `_meta.progressToken` gets a
+//!   `notifications/progress` when it starts and every few seconds while it
+//!   runs, so clients can keep long calls (`run_tests`) from timing out.
+//! - `notifications/cancelled` aborts the named in-flight call; the call then
+//!   gets no response, as the spec asks.
+//! - Upstream tools (`McpHub`) return the server's own `CallToolResult`;
+//!   other tool failures come back as `isError` results; unknown tools, bad
+//!   arguments and calls on unclaimed tasks are JSON-RPC errors
+//!   (`McpDispatcher::classify_error`).
+//! - Once a resource is subscribed, a watcher follows the `EventBroadcaster`
+//!   and sends `notifications/resources/updated` for it on the session's
+//!   standalone stream (`open_stream`), at most once per `UPDATE_COALESCE`.
+//!   A gap in the event sequence marks every subscription as updated.
+
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::dispatch::McpDispatcher;
+use super::prompts::{get_prompt, list_prompts};
+use super::resources::{is_subscribable, list_resources, read_resource, uris_for_event};
+use super::transport::{
+    handle_initialize, handle_initialized, handle_ping, McpCancelledParams, McpError, McpMessage,
+    McpProgressNotification, McpResponse, MCP_INTERNAL_ERROR, MCP_INVALID_PARAMS,
+    MCP_INVALID_REQUEST, MCP_METHOD_NOT_FOUND,
+};
+use crate::api_tokens::ApiToken;
+use crate::ipc::event::CursorItem;
+use crate::AppContext;
+
+/// How often a running `tools/call` reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Updates to subscribed resources are batched for this long, so a burst of
+/// events (a streaming reply, a task moving through phases) sends one
+/// notification per resource.
+const UPDATE_COALESCE: Duration = Duration::from_millis(500);
+
+/// Sessions unused for this long are dropped.
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+// ─── McpSession ───────────────────────────────────────────────────────────────
+
+/// One client's MCP session.
+pub struct McpSession {
+    id: String,
+    ctx: Arc<AppContext>,
+    dispatcher: Arc<McpDispatcher>,
+    /// Who tools act as — task claims and patches are checked against it.
+    /// Bound to the token or the session, never to `clientInfo`.
+    agent_id: String,
+    /// Scoped token the session was started with; `None` for the daemon
+    /// token.  Only the same token may use or end the session, and prompts
+    /// only read the repos it allows.
+    token: Option<Arc<ApiToken>>,
+    /// Running `tools/call`s by request id, for `notifications/cancelled`.
+    in_flight: Mutex<HashMap<String, AbortHandle>>,
+    /// URIs from `resources/subscribe`.
+    subscriptions: Arc<Mutex<HashSet<String>>>,
+    /// The standalone stream for server-initiated notifications, if open.
+    stream: Arc<Mutex<Option<mpsc::UnboundedSender<String>>>>,
+    /// Follows daemon events once something is subscribed.
+    watcher: Mutex<Option<AbortHandle>>,
+    last_active: Mutex<Instant>,
+}
+
+impl McpSession {
+    /// Start a session from an `initialize` request and answer it.
+    pub fn initialize(
+        ctx: Arc<AppContext>,
+        id: Value,
+        params: Option<Value>,
+        token: Option<Arc<ApiToken>>,
+    ) -> (Self, McpResponse) {
+        let client = params
+            .as_ref()
+            .and_then(|p| p.pointer("/clientInfo/name"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let session_id = Uuid::new_v4().simple().to_string();
+        // A client could name itself after another agent; the token (or,
+        // for the daemon token, the session) cannot be borrowed.
+        let agent_id = match &token {
+            Some(token) => format!("token:{}", token.id),
+            None => format!("mcp:{session_id}"),
+        };
+        let session = Self {
+            id: session_id,
+            dispatcher: Arc::new(McpDispatcher::new(ctx.clone())),
+            ctx,
+            agent_id,
+            token,
+            in_flight: Mutex::new(HashMap::new()),
+            subscriptions: Arc::new(Mutex::new(HashSet::new())),
+            stream: Arc::new(Mutex::new(None)),
+            watcher: Mutex::new(None),
+            last_active: Mutex::new(Instant::now()),
+        };
+        info!(session = %session.id, agent = %session.agent_id, client = ?client, "MCP session started");
+        (session, handle_initialize(id, params))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether `token` is the one the session was started with.
+    pub fn started_by(&self, token: Option<&ApiToken>) -> bool {
+        self.token.as_ref().map(|t| &t.id) == token.map(|t| &t.id)
+    }
+
+    fn touch(&self) {
+        *self.last_active.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+    }
+
+    /// Handle one client message.  Notifications produced while it runs are
+    /// sent on `notify` as JSON strings; the return value is the response,
+    /// or `None` for notifications and cancelled calls.
+    pub async fn handle(
+        &self,
+        msg: McpMessage,
+        notify: &mpsc::UnboundedSender<String>,
+    ) -> Option<McpResponse> {
+        self.touch();
+        let Some(id) = msg.id else {
+            self.handle_notification(&msg.method, msg.params);
+            return None;
+        };
+        let params = msg.params.unwrap_or(Value::Null);
+        let response = match msg.method.as_str() {
+            "initialize" => McpResponse::error(
+                id,
+                McpError::new(MCP_INVALID_REQUEST, "session is already initialized"),
+            ),
+            "ping" => handle_ping(id),
+            "tools/list" => {
+                let tools = self.dispatcher.list_tools().await;
+                McpResponse::ok(id, json!({ "tools": tools }))
+            }
+            "tools/call" => return self.call_tool(id, params, notify).await,
+            "resources/list" => {
+                let resources = list_resources(&self.ctx).await;
+                McpResponse::ok(id, json!({ "resources": resources }))
+            }
+            "resources/templates/list" => {
+                McpResponse::ok(id, json!({ "resourceTemplates": resource_templates() }))
+            }
+            "resources/read" => match params.get("uri").and_then(Value::as_str) {
+                Some(uri) => match read_resource(&self.ctx, uri).await {
+                    Ok(contents) => McpResponse::ok(id, contents),
+                    Err(msg) => McpResponse::error(id, McpError::new(MCP_INVALID_PARAMS, msg)),
+                },
+                None => McpResponse::error(
+                    id,
+                    McpError::new(MCP_INVALID_PARAMS, "missing required field 'uri'"),
+                ),
+            },
+            "resources/subscribe" | "resources/unsubscribe" => {
+                match params.get("uri").and_then(Value::as_str) {
+                    Some(uri) if msg.method == "resources/unsubscribe" => {
+                        self.lock_subscriptions().remove(uri);
+                        McpResponse::ok(id, json!({}))
+                    }
+                    Some(uri) if is_subscribable(uri) => {
+                        self.lock_subscriptions().insert(uri.to_string());
+                        self.watch();
+                        McpResponse::ok(id, json!({}))
+                    }
+                    Some(uri) => McpResponse::error(
+                        id,
+                        McpError::new(
+                            MCP_INVALID_PARAMS,
+                            format!("resource does not support subscriptions: {uri}"),
+                        ),
+                    ),
+                    None => McpResponse::error(
+                        id,
+                        McpError::new(MCP_INVALID_PARAMS, "missing required field 'uri'"),
+                    ),
+                }
+            }
+            "prompts/list" => {
+                McpResponse::ok(id, json!({ "prompts": list_prompts(&self.ctx).await }))
+            }
+            "prompts/get" => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Some(McpResponse::error(
+                        id,
+                        McpError::new(MCP_INVALID_PARAMS, "missing required field 'name'"),
+                    ));
+                };
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                match get_prompt(&self.ctx, name, &args, self.token.as_deref()).await {
+                    Ok(prompt) => McpResponse::ok(id, prompt),
+                    Err(e) => {
+                        debug!(session = %self.id, prompt = name, err = %format!("{e:#}"), "MCP prompt failed");
+                        McpResponse::error(id, McpDispatcher::classify_error(&e))
+                    }
+                }
+            }
+            other => McpResponse::error(
+                id,
+                McpError::new(MCP_METHOD_NOT_FOUND, format!("method not found: {other}")),
+            ),
+        };
+        Some(response)
+    }
+
+    fn handle_notification(&self, method: &str, params: Option<Value>) {
+        match method {
+            "notifications/initialized" => handle_initialized(),
+            "notifications/cancelled" => {
+                let Some(params) =
+                    params.and_then(|p| serde_json::from_value::<McpCancelledParams>(p).ok())
+                else {
+                    return;
+                };
+                let key = params.request_id.to_string();
+                let handle = self
+                    .in_flight
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&key);
+                if let Some(handle) = handle {
+                    handle.abort();
+                    info!(session = %self.id, request = %key, reason = %params.reason, "MCP call cancelled");
+                }
+            }
+            other => debug!(session = %self.id, method = other, "MCP notification ignored"),
+        }
+    }
+
+    /// Run a `tools/call` as its own task so it can be cancelled, reporting
+    /// progress while it runs when the client asked for it.
+    async fn call_tool(
+        &self,
+        id: Value,
+        params: Value,
+        notify: &mpsc::UnboundedSender<String>,
+    ) -> Option<McpResponse> {
+        let Some(name) = params
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            return Some(McpResponse::error(
+                id,
+                McpError::new(MCP_INVALID_PARAMS, "missing required field 'name'"),
+            ));
+        };
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let progress_token = params.pointer("/_meta/progressToken").cloned();
+
+        let passthrough = self.dispatcher.is_upstream_tool(&name).await;
+        let dispatcher = Arc::clone(&self.dispatcher);
+        let agent_id = Some(self.agent_id.clone());
+        let session_id = Some(self.id.clone());
+        let tool = name.clone();
+        let mut task = tokio::spawn(async move {
+            dispatcher
+                .dispatch(&tool, arguments, agent_id, session_id)
+                .await
+        });
+        let key = id.to_string();
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.clone(), task.abort_handle());
+
+        let started = Instant::now();
+        let report = |token: &Value| {
+            let note =
+                McpProgressNotification::new(token.clone(), started.elapsed().as_secs(), None)
+                    .with_message(format!("running {name}"));
+            let _ = notify.send(note.to_json());
+        };
+        if let Some(token) = &progress_token {
+            report(token);
+        }
+        let mut ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + PROGRESS_INTERVAL,
+            PROGRESS_INTERVAL,
+        );
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut task => break outcome,
+                _ = ticker.tick() => {
+                    if let Some(token) = &progress_token {
+                        report(token);
+                    }
+                }
+            }
+        };
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key);
+
+        let response = match outcome {
+            Err(e) if e.is_cancelled() => return None,
+            Err(_) => McpResponse::error(
+                id,
+                McpError::new(MCP_INTERNAL_ERROR, format!("tool {name} panicked")),
+            ),
+            Ok(Ok(result)) if passthrough => McpResponse::ok(id, result),
+            Ok(Ok(result)) => McpResponse::ok(id, tool_result(&result, false)),
+            Ok(Err(e)) => {
+                let msg = e.to_string();
+                if msg.starts_with("MCP_INVALID_PARAMS:")
+                    || msg.starts_with("MCP_PROVIDER_NOT_AVAILABLE:")
+                    || msg.starts_with("MCP_POLICY_DENIED:")
+                {
+                    McpResponse::error(id, McpDispatcher::classify_error(&e))
+                } else {
+                    McpResponse::ok(id, tool_result(&json!({ "error": format!("{e:#}") }), true))
+                }
+            }
+        };
+        Some(response)
+    }
+
+    /// Abort every in-flight call and the resource watcher, and close the
+    /// standalone stream — the session is ending.
+    pub fn cancel_all(&self) {
+        for (_, handle) in self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+        {
+            handle.abort();
+        }
+        if let Some(watcher) = self
+            .watcher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            watcher.abort();
+        }
+        self.stream.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    /// Open the session's standalone stream (`GET /api/v1/mcp`).  A newer
+    /// stream replaces an older one, which then ends.
+    pub fn open_stream(&self) -> mpsc::UnboundedReceiver<String> {
+        self.touch();
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.stream.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+        rx
+    }
+
+    fn lock_subscriptions(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.subscriptions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start following daemon events for subscribed resources, once.
+    fn watch(&self) {
+        let mut watcher = self.watcher.lock().unwrap_or_else(|e| e.into_inner());
+        if watcher.is_some() {
+            return;
+        }
+        let mut cursor = self.ctx.broadcaster.subscribe_from(None);
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let stream = Arc::clone(&self.stream);
+        let session = self.id.clone();
+        let task = tokio::spawn(async move {
+            let mut dirty = BTreeSet::new();
+            let mut flush_at: Option<tokio::time::Instant> = None;
+            loop {
+                let item = match flush_at {
+                    None => cursor.next().await,
+                    Some(at) => tokio::select! {
+                        item = cursor.next() => item,
+                        _ = tokio::time::sleep_until(at) => {
+                            flush_at = None;
+                            send_updates(&stream, std::mem::take(&mut dirty));
+                            continue;
+                        }
+                    },
+                };
+                let Some(item) = item else { break };
+                let subscribed = subscriptions.lock().unwrap_or_else(|e| e.into_inner());
+                match item {
+                    CursorItem::Event(event) => dirty.extend(
+                        uris_for_event(&event.method, &event.params)
+                            .into_iter()
+                            .filter(|uri| subscribed.contains(uri)),
+                    ),
+                    CursorItem::Gap { .. } => dirty.extend(subscribed.iter().cloned()),
+                }
+                drop(subscribed);
+                if !dirty.is_empty() && flush_at.is_none() {
+                    flush_at = Some(tokio::time::Instant::now() + UPDATE_COALESCE);
+                }
+            }
+            debug!(session = %session, "MCP resource watcher stopped");
+        });
+        *watcher = Some(task.abort_handle());
+    }
+}
+
+/// Send `notifications/resources/updated` for each URI on the standalone
+/// stream; dropped when none is open.
+fn send_updates(stream: &Mutex<Option<mpsc::UnboundedSender<String>>>, uris: BTreeSet<String>) {
+    let mut stream = stream.lock().unwrap_or_else(|e| e.into_inner());
+    for uri in uris {
+        let note = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": { "uri": uri },
+        });
+        if stream
+            .as_ref()
+            .is_some_and(|tx| tx.send(note.to_string()).is_err())
+        {
+            *stream = None;
+        }
+    }
+}
+
+/// A `tools/call` result: the tool's JSON as text, plus structured content.
+fn tool_result(value: &Value, is_error: bool) -> Value {
+    json!({
+        "content": [{
+            "type": "text",
+            "text": serde_json::to_string_pretty(value).unwrap_or_default(),
+        }],
+        "structuredContent": value,
+        "isError": is_error,
+    })
+}
+
+/// URI templates for the per-item resources `read_resource` understands.
+fn resource_templates() -> Value {
+    json!([
+        {
+            "uriTemplate": "clawd://session/{id}/messages",
+            "name": "Session messages",
+            "mimeType": "application/json",
+        },
+        {
+            "uriTemplate": "clawd://task/{id}",
+            "name": "Agent task",
+            "mimeType": "application/json",
+        },
+        {
+            "uriTemplate": "clawd://repo/{path}",
+            "name": "Repository file",
+            "description": "A file in the first registered repo",
+        },
+    ])
+}
+
+// ─── Session registry ─────────────────────────────────────────────────────────
+
+/// Live sessions by `Mcp-Session-Id`.
+#[derive(Default)]
+pub struct McpSessions {
+    sessions: Mutex<HashMap<String, Arc<McpSession>>>,
+}
+
+impl McpSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<McpSession>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register a session, dropping any that went idle.
+    pub fn insert(&self, session: McpSession) -> Arc<McpSession> {
+        let session = Arc::new(session);
+        let mut sessions = self.lock();
+        sessions.retain(|_, s| {
+            let keep = s.idle_for() < SESSION_IDLE_TIMEOUT;
+            if !keep {
+                s.cancel_all();
+            }
+            keep
+        });
+        sessions.insert(session.id.clone(), Arc::clone(&session));
+        session
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<McpSession>> {
+        self.lock()
+            .get(id)
+            .filter(|s| s.idle_for() < SESSION_IDLE_TIMEOUT)
+            .cloned()
+    }
+
+    /// End a session and abort its calls.  `false` if it did not exist.
+    pub fn remove(&self, id: &str) -> bool {
+        match self.lock().remove(id) {
+            Some(session) => {
+                session.cancel_all();
+                info!(session = %id, "MCP session ended");
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_results_carry_text_and_structure() {
+        let ok = tool_result(&json!({ "claimed": true }), false);
+        assert_eq!(ok["isError"], false);
+        assert_eq!(ok["structuredContent"]["claimed"], true);
+        let text = ok["content"][0]["text"].as_str().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(text).unwrap()["claimed"],
+            true
+        );
+    }
+
+    #[test]
+    fn templates_cover_every_item_resource() {
+        let templates = resource_templates();
+        let uris: Vec<&str> = templates
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|t| t["uriTemplate"].as_str())
+            .collect();
+        assert!(uris.contains(&"clawd://task/{id}"));
+        assert!(uris.contains(&"clawd://session/{id}/messages"));
+    }
+}
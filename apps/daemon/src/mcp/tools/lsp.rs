@@ -0,0 +1,201 @@
+/// MCP tool handlers for semantic code navigation via the LSP proxy.
+///
+/// Covers: lsp_hover, lsp_definition, lsp_references, lsp_rename,
+/// lsp_code_actions, lsp_document_symbols and lsp_workspace_symbols.
+///
+/// Each call starts the language server for `(language, repo)` on first use
+/// and reuses it afterwards.  The language is taken from the `language`
+/// argument or detected from the file extension.  Files must live inside
+/// `repo`, which must be a registered repository or a task worktree.
+/// Rename and code actions return edits; agents apply them with
+/// `apply_patch`.
+use crate::lsp::handlers::lsp_proxy;
+use crate::lsp::model::TextRange;
+use crate::AppContext;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+// ─── Helpers ──────────────────────────────────────────────────────────────────
+
+fn str_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("MCP_INVALID_PARAMS: missing required field '{}'", key))
+}
+
+fn u32_arg(args: &Value, key: &str) -> Result<u32> {
+    args.get(key)
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| anyhow::anyhow!("MCP_INVALID_PARAMS: missing required field '{}'", key))
+}
+
+/// Where a request runs: canonical repo root, file inside it, and language.
+struct Target {
+    repo: PathBuf,
+    file: PathBuf,
+    language: String,
+}
+
+/// The canonical `repo` argument.  Only registered repositories and task
+/// worktrees are accepted, so a call cannot start a language server (and
+/// whatever build scripts it runs) in an arbitrary directory.
+async fn repo_arg(ctx: &AppContext, args: &Value) -> Result<PathBuf> {
+    let repo = str_arg(args, "repo")?;
+    let path = std::fs::canonicalize(repo)
+        .with_context(|| format!("MCP_INVALID_PARAMS: repo '{}' not found", repo))?;
+    let registered = ctx
+        .repo_registry
+        .list_paths()
+        .await
+        .iter()
+        .any(|r| Path::new(r) == path);
+    let worktree = ctx
+        .worktree_manager
+        .list()
+        .await
+        .iter()
+        .any(|w| w.worktree_path.canonicalize().is_ok_and(|w| w == path));
+    if !registered && !worktree {
+        anyhow::bail!(
+            "MCP_INVALID_PARAMS: repo '{}' is not a registered repository",
+            repo
+        );
+    }
+    Ok(path)
+}
+
+/// Resolve `repo` + `file` (absolute, or relative to the repo) and the
+/// language, rejecting files outside the repo.
+async fn target(ctx: &AppContext, args: &Value) -> Result<Target> {
+    let repo = repo_arg(ctx, args).await?;
+    let file_arg = str_arg(args, "file")?;
+    let file = std::fs::canonicalize(repo.join(file_arg))
+        .with_context(|| format!("MCP_INVALID_PARAMS: file '{}' not found", file_arg))?;
+    if !file.starts_with(&repo) {
+        anyhow::bail!(
+            "MCP_INVALID_PARAMS: file '{}' is outside the repo",
+            file_arg
+        );
+    }
+    let language = match args.get("language").and_then(|v| v.as_str()) {
+        Some(l) => l.to_string(),
+        None => lsp_proxy().language_for(&file).ok_or_else(|| {
+            anyhow::anyhow!(
+                "MCP_INVALID_PARAMS: no language server for '{}' — pass 'language'",
+                file_arg
+            )
+        })?,
+    };
+    Ok(Target {
+        repo,
+        file,
+        language,
+    })
+}
+
+/// Start (or reuse) the server, then run `f` on a blocking thread.
+async fn run<T: Send + 'static>(
+    language: String,
+    repo: PathBuf,
+    f: impl FnOnce(&str, &Path) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(move || {
+        lsp_proxy()
+            .start_server(&language, &repo)
+            .map_err(|e| anyhow::anyhow!("MCP_PROVIDER_NOT_AVAILABLE: {:#}", e))?;
+        f(&language, &repo)
+    })
+    .await?
+}
+
+// ─── Tools ────────────────────────────────────────────────────────────────────
+
+/// MCP `lsp_hover` — `{"hover": {"contents", "range"} | null}`.
+pub async fn hover(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let (line, col) = (u32_arg(&args, "line")?, u32_arg(&args, "col")?);
+    let hover = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().hover(lang, repo, &t.file, line, col)
+    })
+    .await?;
+    Ok(json!({ "hover": hover }))
+}
+
+/// MCP `lsp_definition` — `{"locations": [...]}`.
+pub async fn definition(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let (line, col) = (u32_arg(&args, "line")?, u32_arg(&args, "col")?);
+    let locations = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().definition(lang, repo, &t.file, line, col)
+    })
+    .await?;
+    Ok(json!({ "locations": locations }))
+}
+
+/// MCP `lsp_references` — `{"locations": [...]}`.
+pub async fn references(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let (line, col) = (u32_arg(&args, "line")?, u32_arg(&args, "col")?);
+    let include_declaration = args
+        .get("include_declaration")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let locations = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().references(lang, repo, &t.file, line, col, include_declaration)
+    })
+    .await?;
+    Ok(json!({ "locations": locations }))
+}
+
+/// MCP `lsp_rename` — `{"edit": {"changes": [...]}}` (not applied).
+pub async fn rename(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let (line, col) = (u32_arg(&args, "line")?, u32_arg(&args, "col")?);
+    let new_name = str_arg(&args, "new_name")?.to_string();
+    let edit = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().rename(lang, repo, &t.file, line, col, &new_name)
+    })
+    .await?;
+    Ok(json!({ "edit": edit }))
+}
+
+/// MCP `lsp_code_actions` — `{"actions": [...]}` (not applied).
+pub async fn code_actions(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let (line, col) = (u32_arg(&args, "line")?, u32_arg(&args, "col")?);
+    let range = TextRange {
+        line,
+        col,
+        end_line: u32_arg(&args, "end_line").unwrap_or(line),
+        end_col: u32_arg(&args, "end_col").unwrap_or(col),
+    };
+    let actions = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().code_actions(lang, repo, &t.file, range)
+    })
+    .await?;
+    Ok(json!({ "actions": actions }))
+}
+
+/// MCP `lsp_document_symbols` — `{"symbols": [...]}`.
+pub async fn document_symbols(ctx: &AppContext, args: Value) -> Result<Value> {
+    let t = target(ctx, &args).await?;
+    let symbols = run(t.language, t.repo, move |lang, repo| {
+        lsp_proxy().document_symbols(lang, repo, &t.file)
+    })
+    .await?;
+    Ok(json!({ "symbols": symbols }))
+}
+
+/// MCP `lsp_workspace_symbols` — `{"symbols": [...]}`.
+pub async fn workspace_symbols(ctx: &AppContext, args: Value) -> Result<Value> {
+    let repo = repo_arg(ctx, &args).await?;
+    let language = str_arg(&args, "language")?.to_string();
+    let query = str_arg(&args, "query")?.to_string();
+    let symbols = run(language, repo, move |lang, repo| {
+        lsp_proxy().workspace_symbols(lang, repo, &query)
+    })
+    .await?;
+    Ok(json!({ "symbols": symbols }))
+}
@@ -7,6 +7,9 @@
 /// Tool implementation submodules:
 /// - `task` — create_task, claim_task, log_event, run_tests, request_approval, transition_task
 /// - `patch` — apply_patch (with idempotency key store)
+/// - `lsp` — lsp_hover, lsp_definition, lsp_references, lsp_rename,
+///   lsp_code_actions, lsp_document_symbols, lsp_workspace_symbols
+pub mod lsp;
 pub mod patch;
 pub mod task;
 
@@ -210,7 +213,7 @@ pub fn clawd_tools() -> Vec<McpToolDef> {
         // ── transition_task ───────────────────────────────────────────────────
         McpToolDef::new(
             "transition_task",
-            "Transition a task to a new state (e.g., 'in_progress' → 'done', 'blocked').",
+            "Transition a task to a new state (e.g., 'in_progress' → 'in_cr', 'done', 'blocked'). Moving to 'in_cr' (code review) can be refused while the task's patches have introduced LSP errors.",
             json!({
                 "type": "object",
                 "required": ["task_id", "new_state"],
@@ -221,7 +224,7 @@ pub fn clawd_tools() -> Vec<McpToolDef> {
                     },
                     "new_state": {
                         "type": "string",
-                        "enum": ["pending", "in_progress", "done", "blocked", "interrupted"],
+                        "enum": ["pending", "in_progress", "in_cr", "done", "blocked", "interrupted"],
                         "description": "Target state."
                     },
                     "reason": {
@@ -232,9 +235,141 @@ pub fn clawd_tools() -> Vec<McpToolDef> {
                 "additionalProperties": false
             }),
         ),
+
+        // ── lsp_hover ─────────────────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_hover",
+            "Show the type and documentation of the symbol at a position, from the language server.",
+            lsp_schema(&["line", "col"], json!({})),
+        ),
+
+        // ── lsp_definition ────────────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_definition",
+            "Go to the definition of the symbol at a position. Returns file/line/col locations.",
+            lsp_schema(&["line", "col"], json!({})),
+        ),
+
+        // ── lsp_references ────────────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_references",
+            "Find every reference to the symbol at a position, with semantic accuracy.",
+            lsp_schema(
+                &["line", "col"],
+                json!({
+                    "include_declaration": {
+                        "type": "boolean",
+                        "description": "Include the declaration itself. Defaults to true.",
+                        "default": true
+                    }
+                }),
+            ),
+        ),
+
+        // ── lsp_rename ────────────────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_rename",
+            "Compute the edits that rename the symbol at a position across the workspace. The edits are returned, not applied — apply them with apply_patch.",
+            lsp_schema(
+                &["line", "col", "new_name"],
+                json!({
+                    "new_name": {
+                        "type": "string",
+                        "description": "The new identifier."
+                    }
+                }),
+            ),
+        ),
+
+        // ── lsp_code_actions ──────────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_code_actions",
+            "List quick fixes and refactorings the language server offers for a range. Edits are returned, not applied.",
+            lsp_schema(
+                &["line", "col"],
+                json!({
+                    "end_line": {
+                        "type": "integer",
+                        "description": "0-based end line of the range. Defaults to 'line'."
+                    },
+                    "end_col": {
+                        "type": "integer",
+                        "description": "0-based end column of the range. Defaults to 'col'."
+                    }
+                }),
+            ),
+        ),
+
+        // ── lsp_document_symbols ──────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_document_symbols",
+            "Outline of a file: functions, types, methods and fields with their positions.",
+            lsp_schema(&[], json!({})),
+        ),
+
+        // ── lsp_workspace_symbols ─────────────────────────────────────────────
+        McpToolDef::new(
+            "lsp_workspace_symbols",
+            "Search symbols by name across the whole workspace.",
+            json!({
+                "type": "object",
+                "required": ["repo", "language", "query"],
+                "properties": {
+                    "repo": {
+                        "type": "string",
+                        "description": "Absolute path to the git repo (workspace root)."
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Language server to ask, e.g. 'rust', 'typescript'."
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Symbol name or fragment to search for."
+                    }
+                },
+                "additionalProperties": false
+            }),
+        ),
     ]
 }
 
+/// Input schema shared by the file-based `lsp_*` tools: `repo`, `file`,
+/// optional `language`, plus `extra` properties.  `line` / `col` are added
+/// when listed in `required`.
+fn lsp_schema(required: &[&str], extra: Value) -> Value {
+    let mut properties = json!({
+        "repo": {
+            "type": "string",
+            "description": "Absolute path to the git repo (workspace root)."
+        },
+        "file": {
+            "type": "string",
+            "description": "File path, absolute or relative to repo. Must be inside repo."
+        },
+        "language": {
+            "type": "string",
+            "description": "Language server to use. Detected from the file extension when omitted."
+        }
+    });
+    if required.contains(&"line") {
+        properties["line"] = json!({ "type": "integer", "description": "0-based line." });
+        properties["col"] =
+            json!({ "type": "integer", "description": "0-based column (UTF-16 code units)." });
+    }
+    if let (Some(props), Value::Object(extra)) = (properties.as_object_mut(), extra) {
+        props.extend(extra);
+    }
+    let mut all_required = vec!["repo", "file"];
+    all_required.extend_from_slice(required);
+    json!({
+        "type": "object",
+        "required": all_required,
+        "properties": properties,
+        "additionalProperties": false
+    })
+}
+
 // ─── tools/list handler ───────────────────────────────────────────────────────
 
 /// Handle a MCP `tools/list` request.
@@ -39,6 +39,37 @@ fn check_and_record_key(key: &str) -> bool {
     }
 }
 
+// ─── Patch parsing ───────────────────────────────────────────────────────────
+
+fn parse_patch(patch: &str) -> Result<git2::Diff<'static>> {
+    git2::Diff::from_buffer(patch.as_bytes())
+        .map_err(|e| anyhow::anyhow!("MCP_INVALID_PARAMS: invalid patch: {}", e))
+}
+
+fn diff_files(diff: &git2::Diff<'_>) -> Result<Vec<String>> {
+    let mut files: Vec<String> = Vec::new();
+    diff.foreach(
+        &mut |delta, _| {
+            if let Some(path) = delta.new_file().path() {
+                files.push(path.to_string_lossy().into_owned());
+            } else if let Some(path) = delta.old_file().path() {
+                files.push(path.to_string_lossy().into_owned());
+            }
+            true
+        },
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| anyhow::anyhow!("patch delta enumeration failed: {}", e))?;
+    Ok(files)
+}
+
+/// Worktree-relative paths touched by a unified diff.
+pub fn files_in_patch(patch: &str) -> Result<Vec<String>> {
+    diff_files(&parse_patch(patch)?)
+}
+
 // ─── apply_patch ─────────────────────────────────────────────────────────────
 
 /// MCP `apply_patch` handler.
@@ -95,27 +126,8 @@ pub async fn apply_patch(ctx: &AppContext, args: Value, agent_id: Option<&str>)
         }));
     }
 
-    // Validate the patch is well-formed using git2.
-    let patch_bytes = patch_str.as_bytes();
-    let diff = git2::Diff::from_buffer(patch_bytes)
-        .map_err(|e| anyhow::anyhow!("MCP_INVALID_PARAMS: invalid patch: {}", e))?;
-
-    // Extract the list of files touched by the patch.
-    let mut files_changed: Vec<String> = Vec::new();
-    diff.foreach(
-        &mut |delta, _| {
-            if let Some(path) = delta.new_file().path() {
-                files_changed.push(path.to_string_lossy().into_owned());
-            } else if let Some(path) = delta.old_file().path() {
-                files_changed.push(path.to_string_lossy().into_owned());
-            }
-            true
-        },
-        None,
-        None,
-        None,
-    )
-    .map_err(|e| anyhow::anyhow!("patch delta enumeration failed: {}", e))?;
+    // Validate the patch is well-formed and extract the files it touches.
+    let files_changed = files_in_patch(patch_str)?;
 
     // Open the task's isolated git worktree (NOT the project root).
     // Each code-modifying task is bound to a dedicated worktree so that
@@ -127,18 +139,24 @@ pub async fn apply_patch(ctx: &AppContext, args: Value, agent_id: Option<&str>)
         )
     })?;
     let repo_path = &worktree_info.worktree_path;
-    let repo = git2::Repository::open(repo_path).map_err(|e| {
-        anyhow::anyhow!(
-            "failed to open worktree at '{}': {}",
-            repo_path.display(),
-            e
-        )
-    })?;
 
-    let mut apply_opts = git2::ApplyOptions::new();
-    // Apply to workdir (index=false), so the changes appear as working-tree edits.
-    repo.apply(&diff, git2::ApplyLocation::WorkDir, Some(&mut apply_opts))
-        .map_err(|e| anyhow::anyhow!("git apply failed: {}", e))?;
+    // git2 handles are not `Send`, so they must not live across an await —
+    // the MCP server runs tool calls on spawned tasks.
+    {
+        let diff = parse_patch(patch_str)?;
+        let repo = git2::Repository::open(repo_path).map_err(|e| {
+            anyhow::anyhow!(
+                "failed to open worktree at '{}': {}",
+                repo_path.display(),
+                e
+            )
+        })?;
+
+        let mut apply_opts = git2::ApplyOptions::new();
+        // Apply to workdir (index=false), so the changes appear as working-tree edits.
+        repo.apply(&diff, git2::ApplyLocation::WorkDir, Some(&mut apply_opts))
+            .map_err(|e| anyhow::anyhow!("git apply failed: {}", e))?;
+    }
 
     // Log the operation.
     let detail = format!(
@@ -2,6 +2,8 @@
 ///
 /// Covers: create_task, claim_task, log_event, run_tests, request_approval,
 /// and transition_task.  `apply_patch` lives in `tools/patch.rs`.
+use crate::lsp::handlers::lsp_proxy;
+use crate::policy::lsp_gate::lsp_gate;
 use crate::AppContext;
 use anyhow::Result;
 use serde_json::{json, Value};
@@ -315,6 +317,18 @@ pub async fn request_approval(
     let aid = agent_id.unwrap_or("mcp-agent");
     let approval_id = Uuid::new_v4().to_string();
 
+    // Park the task until a human answers; approval.list reads it from here.
+    ctx.task_storage
+        .request_approval(
+            &approval_id,
+            task_id,
+            aid,
+            tool_name,
+            risk_level,
+            &tool_arguments.to_string(),
+        )
+        .await?;
+
     // Broadcast the approval request to connected clients (Flutter / web app).
     ctx.broadcaster.broadcast(
         "tool.approvalRequested",
@@ -328,13 +342,6 @@ pub async fn request_approval(
         }),
     );
 
-    // Transition task to needs_approval state so approval.list can find it.
-    // Store approval_id in notes for correlation by approval.respond.
-    let _ = ctx
-        .task_storage
-        .update_status(task_id, "needs_approval", Some(&approval_id), None)
-        .await;
-
     let _ = ctx
         .task_storage
         .log_activity(
@@ -393,9 +400,21 @@ pub async fn transition_task(
 
     let block_reason = if new_state == "blocked" { reason } else { None };
 
-    ctx.task_storage
+    // Hold back code review while the task's patches leave LSP errors behind.
+    if new_state == "in_cr" && ctx.config.diff_risk.block_review_on_lsp_errors {
+        lsp_gate()
+            .check_review_transition(task_id)
+            .map_err(|v| anyhow::anyhow!("MCP_POLICY_DENIED: {}", v))?;
+    }
+
+    let task = ctx
+        .task_storage
         .update_status(task_id, new_state, notes, block_reason)
         .await?;
+    if new_state == "done" {
+        let task_id = task_id.to_string();
+        let _ = tokio::task::spawn_blocking(move || lsp_gate().forget(lsp_proxy(), &task_id)).await;
+    }
 
     let _ = ctx
         .task_storage
@@ -416,6 +435,8 @@ pub async fn transition_task(
         json!({
             "task_id": task_id,
             "new_state": new_state,
+            "status": new_state,
+            "repo_path": task.repo_path,
             "agent_id": aid,
         }),
     );
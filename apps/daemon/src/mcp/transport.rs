@@ -1,18 +1,24 @@
 /// MCP JSON-RPC 2.0 transport types and lifecycle handlers.
 ///
-/// Supports the Model Context Protocol (MCP) specification version 2024-11-05.
-/// Transport variants: stdio (for subprocess MCP servers) and WebSocket (for
-/// network-attached MCP servers).
+/// Supports the Model Context Protocol (MCP) specification versions 2025-03-26
+/// and 2024-11-05.  Transport variants: stdio (for subprocess MCP servers) and
+/// WebSocket (for network-attached MCP servers).  `clawd`'s own server speaks
+/// stdio and Streamable HTTP (`mcp::server`, `rest::mcp`).
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 // ─── Transport enum ───────────────────────────────────────────────────────────
 
 /// Which transport a MCP connection uses.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum McpTransport {
     /// Standard I/O — used when spawning MCP servers as child processes.
     Stdio,
+    /// Streamable HTTP at the given endpoint (e.g., `https://mcp.internal/search`).
+    /// Servers that predate it are reached over `Sse` at the same URL.
+    Http(String),
+    /// The HTTP+SSE transport of MCP 2024-11-05 — the URL of the event stream.
+    Sse(String),
     /// WebSocket at the given address (e.g., `ws://127.0.0.1:9000`).
     WebSocket(String),
 }
@@ -120,6 +126,11 @@ pub const MCP_INVALID_PARAMS: i32 = -32602;
 pub const MCP_INTERNAL_ERROR: i32 = -32603;
 /// Maps to clawd providerNotAvailable — task not in Active+Claimed state.
 pub const MCP_PROVIDER_NOT_AVAILABLE: i32 = -32002;
+/// Maps to clawd toolSecurityBlocked — the call was refused by policy.
+pub const MCP_POLICY_DENIED: i32 = -32028;
+
+/// Protocol versions `clawd` speaks, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-03-26", "2024-11-05"];
 
 // ─── Lifecycle params ─────────────────────────────────────────────────────────
 
@@ -162,31 +173,42 @@ pub struct McpServerInfo {
 /// `notifications/progress` — sent server → client to report long-running progress.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpProgressNotification {
+    pub jsonrpc: String,
     pub method: String,
     pub params: McpProgressParams,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpProgressParams {
+    /// The `_meta.progressToken` of the request — a string or an integer.
     #[serde(rename = "progressToken")]
-    pub progress_token: String,
+    pub progress_token: Value,
     pub progress: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 impl McpProgressNotification {
-    pub fn new(token: impl Into<String>, progress: u64, total: Option<u64>) -> Self {
+    pub fn new(token: impl Into<Value>, progress: u64, total: Option<u64>) -> Self {
         Self {
+            jsonrpc: "2.0".into(),
             method: "notifications/progress".into(),
             params: McpProgressParams {
                 progress_token: token.into(),
                 progress,
                 total,
+                message: None,
             },
         }
     }
 
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.params.message = Some(message.into());
+        self
+    }
+
     /// Serialise to a JSON string for sending over the wire.
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
@@ -196,6 +218,7 @@ impl McpProgressNotification {
 /// `notifications/cancelled` — client cancels a pending request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpCancelledNotification {
+    pub jsonrpc: String,
     pub method: String,
     pub params: McpCancelledParams,
 }
@@ -204,12 +227,14 @@ pub struct McpCancelledNotification {
 pub struct McpCancelledParams {
     #[serde(rename = "requestId")]
     pub request_id: Value,
+    #[serde(default)]
     pub reason: String,
 }
 
 impl McpCancelledNotification {
     pub fn new(request_id: Value, reason: impl Into<String>) -> Self {
         Self {
+            jsonrpc: "2.0".into(),
             method: "notifications/cancelled".into(),
             params: McpCancelledParams {
                 request_id,
@@ -239,14 +264,20 @@ pub trait McpTransportHandler: Send + Sync {
 
 /// Handle an `initialize` request from an MCP client.
 ///
-/// Parses the client's capabilities (for future negotiation), returns the
-/// `initialize` response with our protocol version and server info.
-pub fn handle_initialize(id: Value, _params: Option<Value>) -> McpResponse {
+/// Answers with the client's protocol version when we speak it, otherwise
+/// our newest, and advertises the tools and resources `clawd` serves.
+pub fn handle_initialize(id: Value, params: Option<Value>) -> McpResponse {
+    let requested = params
+        .as_ref()
+        .and_then(|p| p.get("protocolVersion"))
+        .and_then(Value::as_str);
+    let protocol_version = requested
+        .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+    let capabilities = super::capabilities::ClawdCapabilities::default();
     let result = McpInitializeResult {
-        protocol_version: "2024-11-05".into(),
-        capabilities: serde_json::json!({
-            "tools": { "listChanged": false }
-        }),
+        protocol_version: protocol_version.into(),
+        capabilities: capabilities.to_mcp_value(),
         server_info: McpServerInfo {
             name: "clawd".into(),
             version: env!("CARGO_PKG_VERSION").to_string(),
@@ -264,10 +295,27 @@ pub fn handle_ping(id: Value) -> McpResponse {
     McpResponse::ok(id, serde_json::json!({}))
 }
 
-/// Handle the `initialized` notification — no response needed.
+/// Handle the `notifications/initialized` notification — no response needed.
 ///
 /// Called after the client receives the `initialize` response and is ready.
 /// We log the event; no reply is sent (returns `None` in the dispatch loop).
 pub fn handle_initialized() {
     tracing::debug!("MCP client sent 'initialized' notification — session is ready");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn initialize_answers_with_a_supported_version() {
+        let resp = handle_initialize(json!(1), Some(json!({ "protocolVersion": "2024-11-05" })));
+        assert_eq!(resp.result.unwrap()["protocolVersion"], "2024-11-05");
+        let resp = handle_initialize(json!(1), Some(json!({ "protocolVersion": "1999-01-01" })));
+        let result = resp.result.unwrap();
+        assert_eq!(result["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert!(result["capabilities"]["resources"].is_object());
+        assert!(result["capabilities"]["prompts"].is_object());
+    }
+}
@@ -71,6 +71,44 @@ impl MemoryStore {
         .execute(&self.pool)
         .await
         .context("Creating memory_entries table")?;
+
+        // Full-text index for session.search.  Backfilled only when empty so
+        // the existing entries are indexed once, then kept in sync by triggers.
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
+                key,
+                value,
+                entry_id   UNINDEXED,
+                created_at UNINDEXED,
+                tokenize   = 'porter unicode61'
+            );
+            INSERT INTO memory_fts(key, value, entry_id, created_at)
+            SELECT key, value, id, created_at FROM memory_entries
+            WHERE NOT EXISTS (SELECT 1 FROM memory_fts);
+            CREATE TRIGGER IF NOT EXISTS memory_entries_ai_fts
+            AFTER INSERT ON memory_entries
+            BEGIN
+                INSERT INTO memory_fts(key, value, entry_id, created_at)
+                VALUES (NEW.key, NEW.value, NEW.id, NEW.created_at);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memory_entries_au_fts
+            AFTER UPDATE OF key, value ON memory_entries
+            BEGIN
+                DELETE FROM memory_fts WHERE entry_id = OLD.id;
+                INSERT INTO memory_fts(key, value, entry_id, created_at)
+                VALUES (NEW.key, NEW.value, NEW.id, NEW.created_at);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memory_entries_ad_fts
+            AFTER DELETE ON memory_entries
+            BEGIN
+                DELETE FROM memory_fts WHERE entry_id = OLD.id;
+            END;
+            "#,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Creating memory_fts index")?;
         Ok(())
     }
 
@@ -170,6 +208,19 @@ impl MemoryStore {
         Ok(entry)
     }
 
+    /// Get a single entry by ID.
+    pub async fn get_by_id(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        let entry = sqlx::query_as::<_, MemoryEntry>(
+            "SELECT id, scope, key, value, weight, source, created_at, updated_at
+             FROM memory_entries WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Fetching memory entry by id")?;
+        Ok(entry)
+    }
+
     /// Scope string for a project path: "global" or sha256(path).
     pub fn project_scope(repo_path: &str) -> String {
         if repo_path.is_empty() {
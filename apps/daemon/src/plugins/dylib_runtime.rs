@@ -5,17 +5,194 @@
 //! Verifies Ed25519 signature on the binary before loading.
 //! (Sandbox via seccomp/sandbox profile is a deployment-time concern.)
 
+use std::ffi::{c_char, CStr, CString};
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
 use libloading::{Library, Symbol};
+use serde_json::Value;
 
 use clawd_plugin_abi::{
     ClawaContext, ClawaError, ClawaPlugin, CLAWD_PLUGIN_ABI_VERSION, CLAWD_PLUGIN_INIT_SYMBOL,
 };
 
+use super::hooks::{HookCall, HookReply, ToolRegistration};
 use super::signing::verify_plugin_signature;
 
+// ─── Host state ──────────────────────────────────────────────────────────────
+
+/// Per-call state behind `ClawaContext::_inner`.
+///
+/// Host functions only queue into this struct; the manager drains it after
+/// the callback returns, so nothing re-enters the daemon from plugin code.
+pub struct DylibHostState {
+    pub plugin_name: String,
+    pub queued_events: Vec<(String, Value)>,
+    pub tool_input: Option<String>,
+    pub reason: Option<String>,
+    pub result: Option<String>,
+    pub tools: Vec<ToolRegistration>,
+    pub methods: Vec<String>,
+    /// `register_*` is only accepted while `on_load` runs.
+    pub registering: bool,
+}
+
+impl DylibHostState {
+    pub fn new(plugin_name: impl Into<String>) -> Self {
+        Self {
+            plugin_name: plugin_name.into(),
+            queued_events: Vec::new(),
+            tool_input: None,
+            reason: None,
+            result: None,
+            tools: Vec::new(),
+            methods: Vec::new(),
+            registering: false,
+        }
+    }
+
+    /// Build a context whose host functions write into `self`.
+    /// The context must not outlive `self` or be used after `self` moves.
+    pub fn context(&mut self) -> ClawaContext {
+        ClawaContext {
+            _inner: self as *mut Self as *mut core::ffi::c_void,
+            send_event: host_send_event,
+            log: host_log,
+            set_tool_input: Some(host_set_tool_input),
+            set_reason: Some(host_set_reason),
+            register_tool: Some(host_register_tool),
+            register_method: Some(host_register_method),
+            set_result: Some(host_set_result),
+            _reserved: [std::ptr::null_mut(); 3],
+        }
+    }
+}
+
+/// SAFETY: `ctx` must be null or a context built by [`DylibHostState::context`]
+/// whose state is still alive.
+unsafe fn host_state<'a>(ctx: *mut ClawaContext) -> Option<&'a mut DylibHostState> {
+    if ctx.is_null() || (*ctx)._inner.is_null() {
+        return None;
+    }
+    Some(&mut *((*ctx)._inner as *mut DylibHostState))
+}
+
+/// SAFETY: `ptr` must be null or a valid null-terminated string.
+unsafe fn read_cstr(ptr: *const c_char) -> Option<String> {
+    (!ptr.is_null()).then(|| CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+unsafe extern "C" fn host_send_event(
+    ctx: *mut ClawaContext,
+    method: *const c_char,
+    params_json: *const c_char,
+) -> ClawaError {
+    let (Some(state), Some(method)) = (host_state(ctx), read_cstr(method)) else {
+        return ClawaError::DaemonError;
+    };
+    let params = read_cstr(params_json)
+        .and_then(|p| serde_json::from_str(&p).ok())
+        .unwrap_or(Value::Null);
+    state.queued_events.push((method, params));
+    ClawaError::None
+}
+
+unsafe extern "C" fn host_log(ctx: *mut ClawaContext, level: u8, msg: *const c_char) {
+    let plugin = host_state(ctx)
+        .map(|s| s.plugin_name.clone())
+        .unwrap_or_default();
+    let msg = read_cstr(msg).unwrap_or_default();
+    match level {
+        0 => tracing::trace!(plugin = %plugin, "{msg}"),
+        1 => tracing::debug!(plugin = %plugin, "{msg}"),
+        2 => tracing::info!(plugin = %plugin, "{msg}"),
+        3 => tracing::warn!(plugin = %plugin, "{msg}"),
+        _ => tracing::error!(plugin = %plugin, "{msg}"),
+    }
+}
+
+unsafe extern "C" fn host_set_tool_input(
+    ctx: *mut ClawaContext,
+    input_json: *const c_char,
+) -> ClawaError {
+    match host_state(ctx) {
+        Some(state) => {
+            state.tool_input = read_cstr(input_json);
+            ClawaError::None
+        }
+        None => ClawaError::DaemonError,
+    }
+}
+
+unsafe extern "C" fn host_set_reason(ctx: *mut ClawaContext, reason: *const c_char) -> ClawaError {
+    match host_state(ctx) {
+        Some(state) => {
+            state.reason = read_cstr(reason);
+            ClawaError::None
+        }
+        None => ClawaError::DaemonError,
+    }
+}
+
+unsafe extern "C" fn host_register_tool(
+    ctx: *mut ClawaContext,
+    name: *const c_char,
+    description: *const c_char,
+    input_schema_json: *const c_char,
+    risk: *const c_char,
+) -> ClawaError {
+    let Some(state) = host_state(ctx) else {
+        return ClawaError::DaemonError;
+    };
+    if !state.registering {
+        return ClawaError::CallbackError;
+    }
+    let Some(name) = read_cstr(name) else {
+        return ClawaError::CallbackError;
+    };
+    state.tools.push(ToolRegistration {
+        name,
+        description: read_cstr(description).unwrap_or_default(),
+        input_schema: read_cstr(input_schema_json).unwrap_or_else(|| "{}".to_string()),
+        risk: read_cstr(risk),
+    });
+    ClawaError::None
+}
+
+unsafe extern "C" fn host_register_method(
+    ctx: *mut ClawaContext,
+    method: *const c_char,
+) -> ClawaError {
+    let Some(state) = host_state(ctx) else {
+        return ClawaError::DaemonError;
+    };
+    if !state.registering {
+        return ClawaError::CallbackError;
+    }
+    match read_cstr(method) {
+        Some(method) => {
+            state.methods.push(method);
+            ClawaError::None
+        }
+        None => ClawaError::CallbackError,
+    }
+}
+
+unsafe extern "C" fn host_set_result(
+    ctx: *mut ClawaContext,
+    result_json: *const c_char,
+) -> ClawaError {
+    match host_state(ctx) {
+        Some(state) => {
+            state.result = read_cstr(result_json);
+            ClawaError::None
+        }
+        None => ClawaError::DaemonError,
+    }
+}
+
+// ─── Plugin ──────────────────────────────────────────────────────────────────
+
 /// A loaded dylib plugin instance.
 pub struct DylibPlugin {
     /// The underlying loaded library. Must outlive the plugin vtable pointer.
@@ -89,7 +266,7 @@ impl DylibPlugin {
                 .unwrap_or_else(|| "unknown".into())
         } else {
             unsafe {
-                std::ffi::CStr::from_ptr(plugin_ref.name)
+                CStr::from_ptr(plugin_ref.name)
                     .to_string_lossy()
                     .into_owned()
             }
@@ -118,7 +295,7 @@ impl DylibPlugin {
     pub fn call_on_session_start(
         &self,
         ctx: *mut ClawaContext,
-        session_id: *const core::ffi::c_char,
+        session_id: *const c_char,
     ) -> ClawaError {
         let plugin = unsafe { &*self.plugin };
         if let Some(f) = plugin.on_session_start {
@@ -128,6 +305,156 @@ impl DylibPlugin {
         }
     }
 
+    /// Call `on_session_end` with the given session ID.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn call_on_session_end(
+        &self,
+        ctx: *mut ClawaContext,
+        session_id: *const c_char,
+    ) -> ClawaError {
+        let plugin = unsafe { &*self.plugin };
+        if let Some(f) = plugin.on_session_end {
+            unsafe { f(ctx, session_id) }
+        } else {
+            ClawaError::None
+        }
+    }
+
+    /// Call `on_tool_call`; the plugin may set a new input or reason on `ctx`.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn call_on_tool_call(
+        &self,
+        ctx: *mut ClawaContext,
+        session_id: *const c_char,
+        tool_name: *const c_char,
+        input_json: *const c_char,
+    ) -> ClawaError {
+        let plugin = unsafe { &*self.plugin };
+        if let Some(f) = plugin.on_tool_call {
+            unsafe { f(ctx, session_id, tool_name, input_json) }
+        } else {
+            ClawaError::None
+        }
+    }
+
+    /// Call `on_message` with the message role and JSON-encoded content.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn call_on_message(
+        &self,
+        ctx: *mut ClawaContext,
+        session_id: *const c_char,
+        role: *const c_char,
+        content_json: *const c_char,
+    ) -> ClawaError {
+        let plugin = unsafe { &*self.plugin };
+        if let Some(f) = plugin.on_message {
+            unsafe { f(ctx, session_id, role, content_json) }
+        } else {
+            ClawaError::None
+        }
+    }
+
+    /// Deliver one hook call with a live host context and collect what the
+    /// plugin queued.  Blocking — the manager runs this off the async runtime.
+    pub fn invoke(&self, call: &HookCall) -> Result<HookReply> {
+        let mut state = DylibHostState::new(&self.name);
+        state.registering = matches!(call, HookCall::Load);
+        let mut ctx = state.context();
+        let ctx_ptr: *mut ClawaContext = &mut ctx;
+        let c = |s: &str| CString::new(s).context("argument contains a NUL byte");
+
+        let code = match call {
+            HookCall::Load => self.call_on_load(ctx_ptr),
+            HookCall::Unload => {
+                self.call_on_unload(ctx_ptr);
+                ClawaError::None
+            }
+            HookCall::SessionStart { session_id } => {
+                self.call_on_session_start(ctx_ptr, c(session_id)?.as_ptr())
+            }
+            HookCall::SessionEnd { session_id } => {
+                self.call_on_session_end(ctx_ptr, c(session_id)?.as_ptr())
+            }
+            HookCall::ToolCall {
+                session_id,
+                tool_name,
+                input_json,
+            } => self.call_on_tool_call(
+                ctx_ptr,
+                c(session_id)?.as_ptr(),
+                c(tool_name)?.as_ptr(),
+                c(input_json)?.as_ptr(),
+            ),
+            HookCall::Message {
+                session_id,
+                role,
+                content_json,
+            } => self.call_on_message(
+                ctx_ptr,
+                c(session_id)?.as_ptr(),
+                c(role)?.as_ptr(),
+                c(content_json)?.as_ptr(),
+            ),
+            HookCall::ToolInvoke {
+                session_id,
+                tool_name,
+                input_json,
+            } => self.call_on_tool_invoke(
+                ctx_ptr,
+                c(session_id)?.as_ptr(),
+                c(tool_name)?.as_ptr(),
+                c(input_json)?.as_ptr(),
+            ),
+            HookCall::Rpc {
+                method,
+                params_json,
+            } => self.call_on_rpc(ctx_ptr, c(method)?.as_ptr(), c(params_json)?.as_ptr()),
+        };
+
+        Ok(HookReply {
+            code,
+            tool_input: state.tool_input,
+            reason: state.reason,
+            events: state.queued_events,
+            result: state.result,
+            tools: state.tools,
+            methods: state.methods,
+        })
+    }
+
+    /// Call `on_tool_invoke` for a tool the plugin registered.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn call_on_tool_invoke(
+        &self,
+        ctx: *mut ClawaContext,
+        session_id: *const c_char,
+        tool_name: *const c_char,
+        input_json: *const c_char,
+    ) -> ClawaError {
+        let plugin = unsafe { &*self.plugin };
+        if let Some(f) = plugin.on_tool_invoke {
+            unsafe { f(ctx, session_id, tool_name, input_json) }
+        } else {
+            ClawaError::NotFound
+        }
+    }
+
+    /// Call `on_rpc` for a method the plugin registered.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn call_on_rpc(
+        &self,
+        ctx: *mut ClawaContext,
+        method: *const c_char,
+        params_json: *const c_char,
+    ) -> ClawaError {
+        let plugin = unsafe { &*self.plugin };
+        if let Some(f) = plugin.on_rpc {
+            unsafe { f(ctx, method, params_json) }
+        } else {
+            ClawaError::NotFound
+        }
+    }
+
     /// Call `on_unload` if the plugin provides it.
     #[allow(clippy::not_unsafe_ptr_arg_deref)]
     pub fn call_on_unload(&self, ctx: *mut ClawaContext) {
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MIT
+//! Plugin hook calls and their results, shared by both runtimes.
+//!
+//! The manager turns every ABI event into a [`HookCall`], runs it on a
+//! blocking thread under a per-call timeout, and gets back a [`HookReply`].
+//! For `on_tool_call` the replies of all plugins are folded into a single
+//! [`ToolCallDecision`] that `PolicyEngine::evaluate` consumes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use clawd_plugin_abi::ClawaError;
+
+/// Default upper bound on a single plugin callback.
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runs a daemon tool on behalf of a plugin: `(plugin, tool, input)`.
+///
+/// Called from the plugin's blocking thread, so implementations may block.
+pub type ToolRunner = Arc<dyn Fn(&str, &str, Value) -> Result<Value> + Send + Sync>;
+
+/// One ABI event delivered to one plugin.
+#[derive(Debug, Clone)]
+pub enum HookCall {
+    Load,
+    Unload,
+    SessionStart {
+        session_id: String,
+    },
+    SessionEnd {
+        session_id: String,
+    },
+    ToolCall {
+        session_id: String,
+        tool_name: String,
+        /// JSON object string.
+        input_json: String,
+    },
+    Message {
+        session_id: String,
+        role: String,
+        /// JSON-encoded string content.
+        content_json: String,
+    },
+    /// Run a tool the plugin registered (unprefixed name).
+    ToolInvoke {
+        session_id: String,
+        tool_name: String,
+        input_json: String,
+    },
+    /// Handle a JSON-RPC method the plugin registered (unprefixed name).
+    Rpc {
+        method: String,
+        params_json: String,
+    },
+}
+
+impl HookCall {
+    /// ABI callback name, used in logs and as the WASM export name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookCall::Load => "on_load",
+            HookCall::Unload => "on_unload",
+            HookCall::SessionStart { .. } => "on_session_start",
+            HookCall::SessionEnd { .. } => "on_session_end",
+            HookCall::ToolCall { .. } => "on_tool_call",
+            HookCall::Message { .. } => "on_message",
+            HookCall::ToolInvoke { .. } => "on_tool_invoke",
+            HookCall::Rpc { .. } => "on_rpc",
+        }
+    }
+}
+
+/// Everything a plugin produced during one callback.
+#[derive(Debug, Clone)]
+pub struct HookReply {
+    /// Callback return code.
+    pub code: ClawaError,
+    /// Replacement tool input set via `set_tool_input` (JSON string).
+    pub tool_input: Option<String>,
+    /// Reason set via `set_reason`.
+    pub reason: Option<String>,
+    /// Events queued via `send_event`, broadcast after the call returns.
+    pub events: Vec<(String, Value)>,
+    /// Result set via `set_result` (JSON string).
+    pub result: Option<String>,
+    /// Tools registered via `register_tool` (load only).
+    pub tools: Vec<ToolRegistration>,
+    /// Methods registered via `register_method` (load only).
+    pub methods: Vec<String>,
+}
+
+impl HookReply {
+    /// A callback that did nothing (plugin opted out of the event).
+    pub fn none() -> Self {
+        Self {
+            code: ClawaError::None,
+            tool_input: None,
+            reason: None,
+            events: Vec::new(),
+            result: None,
+            tools: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Interpret a reply to [`HookCall::ToolInvoke`] or [`HookCall::Rpc`]:
+    /// the parsed result, or the plugin's error.
+    pub fn into_result(self) -> Result<Value, String> {
+        if self.code != ClawaError::None {
+            return Err(self
+                .reason
+                .unwrap_or_else(|| format!("plugin returned {:?}", self.code)));
+        }
+        match self.result.as_deref() {
+            None => Ok(Value::Null),
+            Some(raw) => serde_json::from_str(raw)
+                .map_err(|e| format!("plugin result is not valid JSON: {e}")),
+        }
+    }
+}
+
+/// A tool a plugin asked to register during load.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolRegistration {
+    /// Unprefixed tool name.
+    pub name: String,
+    pub description: String,
+    /// JSON Schema text as supplied by the plugin.
+    pub input_schema: String,
+    /// Declared risk (`low` … `critical`), if any.
+    pub risk: Option<String>,
+}
+
+/// What one plugin's `on_tool_call` decided.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolVerdict {
+    Allow,
+    Deny { reason: Option<String> },
+    Modify { input: Value },
+}
+
+impl ToolVerdict {
+    /// Interpret a reply to [`HookCall::ToolCall`].
+    ///
+    /// `CapabilityDenied` denies; `None` allows, or modifies when a valid JSON
+    /// object was set as the new input.  Other codes and unparsable inputs are
+    /// treated as allow so a broken plugin cannot wedge tool execution — the
+    /// caller logs them.
+    pub fn from_reply(reply: &HookReply) -> Result<Self, String> {
+        match reply.code {
+            ClawaError::CapabilityDenied => Ok(ToolVerdict::Deny {
+                reason: reply.reason.clone(),
+            }),
+            ClawaError::None => match reply.tool_input.as_deref() {
+                None => Ok(ToolVerdict::Allow),
+                Some(raw) => match serde_json::from_str::<Value>(raw) {
+                    Ok(input) if input.is_object() => Ok(ToolVerdict::Modify { input }),
+                    Ok(_) => Err("set_tool_input must be a JSON object".to_string()),
+                    Err(e) => Err(format!("set_tool_input is not valid JSON: {e}")),
+                },
+            },
+            other => Err(format!("on_tool_call returned {other:?}")),
+        }
+    }
+}
+
+/// Combined decision of every loaded plugin for one tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCallDecision {
+    Allow,
+    /// The first plugin that denied, and why.
+    Deny {
+        plugin: String,
+        reason: String,
+    },
+    /// Final input after every modifying plugin ran, in name order.
+    Modify {
+        plugins: Vec<String>,
+        input: Value,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reply(code: ClawaError, input: Option<&str>) -> HookReply {
+        HookReply {
+            code,
+            tool_input: input.map(str::to_string),
+            reason: Some("no".to_string()),
+            ..HookReply::none()
+        }
+    }
+
+    #[test]
+    fn verdict_from_reply() {
+        assert_eq!(
+            ToolVerdict::from_reply(&reply(ClawaError::None, None)),
+            Ok(ToolVerdict::Allow)
+        );
+        assert_eq!(
+            ToolVerdict::from_reply(&reply(ClawaError::CapabilityDenied, None)),
+            Ok(ToolVerdict::Deny {
+                reason: Some("no".to_string())
+            })
+        );
+        assert_eq!(
+            ToolVerdict::from_reply(&reply(ClawaError::None, Some(r#"{"path":"a"}"#))),
+            Ok(ToolVerdict::Modify {
+                input: json!({"path": "a"})
+            })
+        );
+        assert!(ToolVerdict::from_reply(&reply(ClawaError::None, Some("[1]"))).is_err());
+        assert!(ToolVerdict::from_reply(&reply(ClawaError::CallbackError, None)).is_err());
+    }
+
+    #[test]
+    fn invoke_result_from_reply() {
+        let ok = HookReply {
+            result: Some(r#"{"n":1}"#.to_string()),
+            ..HookReply::none()
+        };
+        assert_eq!(ok.into_result(), Ok(json!({"n": 1})));
+        assert_eq!(HookReply::none().into_result(), Ok(Value::Null));
+        assert_eq!(
+            reply(ClawaError::CallbackError, None).into_result(),
+            Err("no".to_string())
+        );
+    }
+}
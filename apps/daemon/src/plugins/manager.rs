@@ -3,22 +3,45 @@
 //!
 //! Owns all loaded plugins. Responsible for:
 //! - Loading enabled plugins from `.claw/plugins/` on daemon startup.
-//! - Reloading on `plugin.enable`.
+//! - Reloading on `plugin.enable` and when a plugin's files change
+//!   (see [`super::watcher`]).
 //! - Unloading on `plugin.disable`.
 //! - Delivering events to all loaded plugins.
 //! - Isolating plugin crashes — a panicking plugin is disabled, daemon continues.
-
-use std::collections::HashMap;
+//!
+//! Every callback runs on a blocking thread and is bounded by `call_timeout`,
+//! so a slow plugin never stalls a session.  Calls into one plugin queue
+//! for their turn first; the time spent waiting is not charged to them.  A WASM callback bounds itself:
+//! the guest gets `call_timeout` of execution and its `clawd_run_tool` calls
+//! another `call_timeout` in total, so the manager waits for it to finish or
+//! trap.  A native callback cannot be interrupted: one that overruns is
+//! abandoned to its thread and the plugin is disabled like one that panicked.
+//!
+//! Each plugin has a gate: calls hold it shared, while reload and disable hold
+//! it exclusively.  A reload therefore waits for in-flight calls to drain,
+//! and calls that arrive meanwhile are delivered to whichever version is
+//! active once the swap has finished.
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
-use tokio::sync::Mutex;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, RwLock};
 
 use clawd_plugin_abi::manifest::{ManifestRuntime, PluginManifest};
+use clawd_plugin_abi::ClawaError;
 
 use super::dylib_runtime::DylibPlugin;
-use super::wasm_runtime::WasmPlugin;
+use super::hooks::{
+    HookCall, HookReply, ToolCallDecision, ToolRegistration, ToolRunner, ToolVerdict,
+    DEFAULT_CALL_TIMEOUT,
+};
+use super::signing::verify_plugin_bytes;
+use super::wasm_runtime::{WasmLimits, WasmPlugin};
+use crate::ipc::event::EventBroadcaster;
+use crate::tasks::schema::RiskLevel;
 
 /// Status of a loaded plugin.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
@@ -38,40 +61,184 @@ pub struct PluginInfo {
     pub status: PluginStatus,
     pub path: String,
     pub is_signed: bool,
+    /// Agent-facing names of the MCP tools this plugin contributes.
+    pub tools: Vec<String>,
+    /// JSON-RPC methods this plugin contributes (`plugin.<name>.<method>`).
+    pub methods: Vec<String>,
+}
+
+/// An MCP tool contributed by a plugin.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginTool {
+    /// Agent-facing name, `<plugin>__<tool>`.
+    pub name: String,
+    pub plugin: String,
+    /// Name the plugin registered and receives in `on_tool_invoke`.
+    pub tool: String,
+    pub description: String,
+    pub input_schema: Value,
+    /// Declared risk; `.claw/policies/tool-risk.json` takes precedence.
+    pub risk: RiskLevel,
+}
+
+/// Separator between plugin and tool in contributed tool names.
+pub const PLUGIN_TOOL_SEPARATOR: &str = "__";
+
+/// Session id a plugin's own `clawd_run_tool` calls are dispatched under.
+///
+/// [`PluginManager::on_tool_call`] recognises it and does not ask the calling
+/// plugin to review its own call.
+pub fn plugin_session_id(plugin: &str) -> String {
+    format!("{PLUGIN_SESSION_PREFIX}{plugin}")
 }
 
+const PLUGIN_SESSION_PREFIX: &str = "plugin:";
+
 /// Internal plugin entry.
 enum LoadedPlugin {
     Dylib(DylibPlugin),
     Wasm(WasmPlugin),
+    /// A native-like plugin backed by a closure, for tests.
+    #[cfg(test)]
+    Fake(FakeHook),
 }
 
+#[cfg(test)]
+type FakeHook = Box<dyn FnMut(&HookCall) -> Result<HookReply> + Send>;
+
 impl LoadedPlugin {
-    fn name(&self) -> &str {
+    fn invoke(&mut self, call: &HookCall, runner: Option<ToolRunner>) -> Result<HookReply> {
         match self {
-            LoadedPlugin::Dylib(p) => &p.name,
-            LoadedPlugin::Wasm(p) => &p.name,
+            LoadedPlugin::Dylib(p) => p.invoke(call),
+            LoadedPlugin::Wasm(p) => p.invoke(call, runner),
+            #[cfg(test)]
+            LoadedPlugin::Fake(f) => f(call),
         }
     }
 }
 
+/// A loaded plugin plus the lock that serialises calls into it.
+type PluginSlot = Arc<Slot>;
+
+struct Slot {
+    plugin: std::sync::Mutex<LoadedPlugin>,
+    /// Calls wait their turn here, before their timeout starts.  The guard
+    /// is held until the callback returns, even one that overran.
+    turn: Arc<Mutex<()>>,
+    /// Set once the plugin is marked failed, releasing queued calls.
+    failed: tokio::sync::watch::Sender<bool>,
+    /// Whether an overrunning call is stopped by the epoch deadline.
+    interruptible: bool,
+    /// Risk of each RPC method as declared in the manifest's `method_risk`.
+    method_risk: HashMap<String, RiskLevel>,
+}
+
+impl Slot {
+    fn new(plugin: LoadedPlugin, method_risk: HashMap<String, RiskLevel>) -> PluginSlot {
+        let interruptible = matches!(plugin, LoadedPlugin::Wasm(_));
+        Arc::new(Self {
+            plugin: std::sync::Mutex::new(plugin),
+            turn: Arc::new(Mutex::new(())),
+            failed: tokio::sync::watch::Sender::new(false),
+            interruptible,
+            method_risk,
+        })
+    }
+}
+
+/// Shared by calls, exclusive during reload / disable.
+type PluginGate = Arc<RwLock<()>>;
+
 /// Plugin manager — owns all loaded plugins.
 pub struct PluginManager {
     /// Loaded and enabled plugins, keyed by name.
-    plugins: Mutex<HashMap<String, LoadedPlugin>>,
+    plugins: Mutex<HashMap<String, PluginSlot>>,
     /// Plugin metadata (all known, enabled or not).
     registry: Mutex<HashMap<String, PluginInfo>>,
     /// Base directory where plugins are installed.
     plugins_dir: PathBuf,
+    /// Upper bound on a single plugin callback.
+    call_timeout: Duration,
+    /// Where events queued by plugins via `send_event` are broadcast.
+    events: Option<Arc<EventBroadcaster>>,
+    /// Fuel and memory caps for WASM plugins.
+    wasm_limits: WasmLimits,
+    /// Backs the WASM `clawd_run_tool` import once the daemon is up.
+    tool_runner: std::sync::RwLock<Option<ToolRunner>>,
+    /// Contributed MCP tools keyed by agent-facing name.
+    tools: Mutex<BTreeMap<String, PluginTool>>,
+    /// Contributed RPC methods: full method → (plugin, method, risk).
+    methods: Mutex<BTreeMap<String, (String, String, RiskLevel)>>,
+    /// Per-plugin call gates, kept across reloads.
+    gates: std::sync::Mutex<HashMap<String, PluginGate>>,
+    /// Serialises reloads so two file events cannot swap concurrently.
+    reload_lock: Mutex<()>,
+    /// Private directory dylibs are staged in before they are opened.
+    staging_dir: PathBuf,
+    /// Hex Ed25519 key plugin binaries must be signed with, if any.
+    signer_pubkey: Option<String>,
 }
 
 impl PluginManager {
-    pub fn new(plugins_dir: PathBuf) -> Arc<Self> {
-        Arc::new(Self {
+    pub fn new(plugins_dir: PathBuf) -> Self {
+        let staging_dir = plugins_dir.with_file_name("plugin-staging");
+        Self {
             plugins: Mutex::new(HashMap::new()),
             registry: Mutex::new(HashMap::new()),
             plugins_dir,
-        })
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+            events: None,
+            wasm_limits: WasmLimits::default(),
+            tool_runner: std::sync::RwLock::new(None),
+            tools: Mutex::new(BTreeMap::new()),
+            methods: Mutex::new(BTreeMap::new()),
+            gates: std::sync::Mutex::new(HashMap::new()),
+            reload_lock: Mutex::new(()),
+            staging_dir,
+            signer_pubkey: None,
+        }
+    }
+
+    /// Require every plugin binary to carry a valid signature for this key.
+    pub fn with_signer_pubkey(mut self, pubkey_hex: impl Into<String>) -> Self {
+        self.signer_pubkey = Some(pubkey_hex.into());
+        self
+    }
+
+    /// Directory plugins are installed in.
+    pub fn plugins_dir(&self) -> &Path {
+        &self.plugins_dir
+    }
+
+    /// Override the per-callback timeout (default 2s).
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    /// Upper bound on a single plugin callback.
+    pub fn call_timeout(&self) -> Duration {
+        self.call_timeout
+    }
+
+    /// Broadcast events that plugins send via `send_event`.
+    pub fn with_events(mut self, broadcaster: Arc<EventBroadcaster>) -> Self {
+        self.events = Some(broadcaster);
+        self
+    }
+
+    /// Override the WASM fuel and memory caps.  The deadline always follows
+    /// the call timeout.
+    pub fn with_wasm_limits(mut self, limits: WasmLimits) -> Self {
+        self.wasm_limits = limits;
+        self
+    }
+
+    /// Let WASM plugins with `daemon.rpc` run daemon tools.  Set after the
+    /// daemon context exists, so it may be called once plugins are loaded.
+    pub fn set_tool_runner(&self, runner: ToolRunner) {
+        *self.tool_runner.write().unwrap_or_else(|e| e.into_inner()) = Some(runner);
     }
 
     /// Load all enabled plugins from `{plugins_dir}/`.
@@ -107,47 +274,26 @@ impl PluginManager {
 
     /// Load a single plugin from its directory.
     pub async fn load_plugin(&self, plugin_dir: &Path) -> Result<()> {
-        let manifest_json = tokio::fs::read_to_string(plugin_dir.join("clawd-plugin.json"))
-            .await
-            .context("failed to read clawd-plugin.json")?;
-        let manifest = PluginManifest::from_json(&manifest_json)
-            .context("failed to parse clawd-plugin.json")?;
+        let (manifest, loaded) = self.instantiate(plugin_dir)?;
 
-        let binary_path = plugin_dir.join(&manifest.entry);
-        let sig = if manifest.signature.is_empty() {
-            None
-        } else {
-            Some(manifest.signature.as_str())
-        };
-
-        let loaded = match manifest.runtime {
-            ManifestRuntime::Dylib => {
-                let plugin = DylibPlugin::load(&binary_path, sig, None)
-                    .context("failed to load dylib plugin")?;
-                LoadedPlugin::Dylib(plugin)
-            }
-            ManifestRuntime::Wasm => {
-                let plugin =
-                    WasmPlugin::load(&binary_path, &manifest.name, manifest.capabilities.clone())
-                        .context("failed to load WASM plugin")?;
-                LoadedPlugin::Wasm(plugin)
-            }
-        };
-
-        let info = PluginInfo {
-            name: manifest.name.clone(),
-            version: manifest.version.clone(),
-            runtime: format!("{:?}", manifest.runtime).to_lowercase(),
-            status: PluginStatus::Enabled,
-            path: plugin_dir.to_string_lossy().into_owned(),
-            is_signed: manifest.is_signed(),
+        // `on_load` / `clawd_plugin_init` must succeed before the plugin
+        // receives any event.
+        let slot = Slot::new(loaded, method_risks(&manifest)?);
+        let reply = match self.call_slot(&manifest.name, &slot, HookCall::Load).await {
+            Some(reply) if reply.code == ClawaError::None => reply,
+            Some(reply) => bail!("on_load failed: {:?}", reply.code),
+            None => bail!("on_load did not complete"),
         };
+        let (tools, methods) = self
+            .register_contributions(&manifest.name, &slot, reply.tools, reply.methods)
+            .await;
 
+        let info = plugin_info(&manifest, plugin_dir, tools, methods);
         tracing::info!(plugin = %manifest.name, "plugin loaded");
         self.plugins
             .lock()
             .await
-            .insert(manifest.name.clone(), loaded);
+            .insert(manifest.name.clone(), slot);
         self.registry
             .lock()
             .await
@@ -155,13 +301,156 @@ impl PluginManager {
         Ok(())
     }
 
+    /// Replace a loaded plugin with the version currently on disk.
+    ///
+    /// The new binary is verified and instantiated first; then in-flight
+    /// calls drain, the old instance gets `on_unload` and the new one
+    /// `on_load`.  If the new `on_load` fails the old instance is loaded
+    /// again and stays active, and the error is returned.
+    pub async fn reload(&self, name: &str) -> Result<()> {
+        let _serial = self.reload_lock.lock().await;
+        let (plugin_dir, was_signed, previous_version) = {
+            let registry = self.registry.lock().await;
+            let info = registry
+                .get(name)
+                .with_context(|| format!("plugin '{name}' not found"))?;
+            (
+                PathBuf::from(&info.path),
+                info.is_signed,
+                info.version.clone(),
+            )
+        };
+
+        let result = self
+            .swap(name, &plugin_dir, was_signed, &previous_version)
+            .await;
+        if let Err(ref e) = result {
+            tracing::warn!(plugin = %name, error = %format!("{e:#}"), "plugin reload failed");
+            self.broadcast(
+                "plugin.reloadFailed",
+                json!({ "name": name, "version": previous_version, "error": format!("{e:#}") }),
+            );
+        }
+        result
+    }
+
+    async fn swap(
+        &self,
+        name: &str,
+        plugin_dir: &Path,
+        was_signed: bool,
+        previous_version: &str,
+    ) -> Result<()> {
+        let (manifest, loaded) = self.instantiate(plugin_dir)?;
+        if manifest.name != name {
+            bail!("manifest now names the plugin '{}'", manifest.name);
+        }
+        if was_signed && !manifest.is_signed() {
+            bail!("new version is unsigned but the loaded one was signed");
+        }
+        let new_slot = Slot::new(loaded, method_risks(&manifest)?);
+
+        // Drain: wait for in-flight calls and hold new ones until the swap.
+        let gate = self.gate(name);
+        let _drained = gate.write().await;
+        let old_slot = self
+            .plugins
+            .lock()
+            .await
+            .get(name)
+            .cloned()
+            .with_context(|| format!("plugin '{name}' is not loaded"))?;
+        self.call_slot(name, &old_slot, HookCall::Unload).await;
+
+        let failure = match self.call_slot(name, &new_slot, HookCall::Load).await {
+            Some(reply) if reply.code == ClawaError::None => {
+                let (tools, methods) = self
+                    .register_contributions(name, &new_slot, reply.tools, reply.methods)
+                    .await;
+                let info = plugin_info(&manifest, plugin_dir, tools, methods);
+                self.plugins.lock().await.insert(name.to_string(), new_slot);
+                self.registry.lock().await.insert(name.to_string(), info);
+                tracing::info!(
+                    plugin = %name,
+                    from = %previous_version,
+                    to = %manifest.version,
+                    "plugin reloaded"
+                );
+                self.broadcast(
+                    "plugin.reloaded",
+                    json!({
+                        "name": name,
+                        "version": manifest.version,
+                        "previousVersion": previous_version,
+                    }),
+                );
+                return Ok(());
+            }
+            Some(reply) => format!("on_load failed: {:?}", reply.code),
+            None => "on_load did not complete".to_string(),
+        };
+
+        // Roll back: the old instance is still in memory, bring it back up.
+        drop(new_slot);
+        match self.call_slot(name, &old_slot, HookCall::Load).await {
+            Some(reply) if reply.code == ClawaError::None => {
+                let (tools, methods) = self
+                    .register_contributions(name, &old_slot, reply.tools, reply.methods)
+                    .await;
+                if let Some(info) = self.registry.lock().await.get_mut(name) {
+                    info.tools = tools;
+                    info.methods = methods;
+                }
+                bail!("{failure}; kept version {previous_version}");
+            }
+            _ => {
+                self.plugins.lock().await.remove(name);
+                self.forget_contributions(name).await;
+                if let Some(info) = self.registry.lock().await.get_mut(name) {
+                    info.status = PluginStatus::Failed;
+                }
+                bail!(
+                    "{failure}; version {previous_version} failed to load again and was disabled"
+                );
+            }
+        }
+    }
+
+    /// React to a change under `{plugins_dir}/{dir}`: reload a loaded plugin,
+    /// load a new or failed one, leave disabled plugins alone.
+    pub async fn on_plugin_dir_changed(&self, plugin_dir: &Path) -> Result<()> {
+        if !plugin_dir.join("clawd-plugin.json").exists() {
+            return Ok(());
+        }
+        let path = plugin_dir.to_string_lossy();
+        let known = self
+            .registry
+            .lock()
+            .await
+            .values()
+            .find(|info| info.path == path)
+            .map(|info| (info.name.clone(), info.status.clone()));
+        match known {
+            Some((name, PluginStatus::Enabled)) => self.reload(&name).await,
+            Some((_, PluginStatus::Disabled)) => Ok(()),
+            Some((_, PluginStatus::Failed)) | None => {
+                let _serial = self.reload_lock.lock().await;
+                self.load_plugin(plugin_dir).await
+            }
+        }
+    }
+
     /// List all known plugins (enabled + disabled + failed).
     pub async fn list(&self) -> Vec<PluginInfo> {
         self.registry.lock().await.values().cloned().collect()
     }
 
-    /// Enable a plugin by name (re-loads if disabled/failed).
+    /// Enable a plugin by name (re-loads if disabled/failed, upgrades in
+    /// place if already loaded).
     pub async fn enable(&self, name: &str) -> Result<()> {
+        if self.plugins.lock().await.contains_key(name) {
+            return self.reload(name).await;
+        }
         let plugin_dir = self.plugins_dir.join(name);
         self.load_plugin(&plugin_dir).await?;
         if let Some(info) = self.registry.lock().await.get_mut(name) {
@@ -172,9 +461,13 @@ impl PluginManager {
 
     /// Disable a plugin by name (calls on_unload, removes from active set).
     pub async fn disable(&self, name: &str) -> Result<()> {
-        let mut plugins = self.plugins.lock().await;
-        if let Some(plugin) = plugins.remove(name) {
-            tracing::info!(plugin = %plugin.name(), "plugin disabled");
+        let gate = self.gate(name);
+        let _drained = gate.write().await;
+        let removed = self.plugins.lock().await.remove(name);
+        self.forget_contributions(name).await;
+        if let Some(slot) = removed {
+            self.call_slot(name, &slot, HookCall::Unload).await;
+            tracing::info!(plugin = %name, "plugin disabled");
         }
         if let Some(info) = self.registry.lock().await.get_mut(name) {
             info.status = PluginStatus::Disabled;
@@ -182,38 +475,703 @@ impl PluginManager {
         Ok(())
     }
 
+    // ─── Event delivery ──────────────────────────────────────────────────────
+
     /// Deliver a `session_start` event to all loaded plugins.
     pub async fn on_session_start(&self, session_id: &str) {
-        let plugins = self.plugins.lock().await;
-        let session_cstr = std::ffi::CString::new(session_id).unwrap_or_default();
-        for (name, plugin) in plugins.iter() {
-            match plugin {
-                LoadedPlugin::Dylib(p) => {
-                    // We don't have a real ClawaContext in this scaffold —
-                    // pass null; dylib plugins should handle null gracefully.
-                    let result =
-                        p.call_on_session_start(std::ptr::null_mut(), session_cstr.as_ptr());
-                    if result != clawd_plugin_abi::ClawaError::None {
-                        tracing::warn!(plugin = %name, "on_session_start returned error");
-                    }
+        self.notify_all(HookCall::SessionStart {
+            session_id: session_id.to_string(),
+        })
+        .await;
+    }
+
+    /// Deliver a `session_end` event to all loaded plugins.
+    pub async fn on_session_end(&self, session_id: &str) {
+        self.notify_all(HookCall::SessionEnd {
+            session_id: session_id.to_string(),
+        })
+        .await;
+    }
+
+    /// Deliver a `message` event; `content` is passed JSON-encoded.
+    pub async fn on_message(&self, session_id: &str, role: &str, content: &str) {
+        self.notify_all(HookCall::Message {
+            session_id: session_id.to_string(),
+            role: role.to_string(),
+            content_json: Value::String(content.to_string()).to_string(),
+        })
+        .await;
+    }
+
+    /// Ask every plugin, in name order, whether a tool call may proceed.
+    ///
+    /// The first deny wins.  A modification is passed on to the next plugin,
+    /// so later plugins review the rewritten input.  A plugin that fails,
+    /// panics or times out while reviewing denies the call: a policy plugin
+    /// fails closed.  One unloaded meanwhile is skipped.
+    ///
+    /// A plugin running a tool itself (session id [`plugin_session_id`]) is
+    /// not asked about its own call — it is still busy inside its callback.
+    pub async fn on_tool_call(
+        &self,
+        session_id: &str,
+        tool_name: &str,
+        input: &Value,
+    ) -> ToolCallDecision {
+        let mut input = input.clone();
+        let mut modified_by = Vec::new();
+        let caller = session_id.strip_prefix(PLUGIN_SESSION_PREFIX);
+        for name in self.snapshot().await {
+            if caller == Some(name.as_str()) {
+                continue;
+            }
+            let call = HookCall::ToolCall {
+                session_id: session_id.to_string(),
+                tool_name: tool_name.to_string(),
+                input_json: input.to_string(),
+            };
+            let gate = self.gate(&name);
+            let _in_flight = gate.read().await;
+            let Some(slot) = self.plugins.lock().await.get(&name).cloned() else {
+                continue;
+            };
+            let Some(reply) = self.call_slot(&name, &slot, call).await else {
+                tracing::warn!(plugin = %name, tool = %tool_name, "plugin failed to review tool call — denying");
+                return ToolCallDecision::Deny {
+                    reason: format!("plugin '{name}' failed to review the call"),
+                    plugin: name,
+                };
+            };
+            match ToolVerdict::from_reply(&reply) {
+                Ok(ToolVerdict::Allow) => {}
+                Ok(ToolVerdict::Deny { reason }) => {
+                    tracing::info!(plugin = %name, tool = %tool_name, "plugin denied tool call");
+                    return ToolCallDecision::Deny {
+                        reason: reason.unwrap_or_else(|| "denied by plugin".to_string()),
+                        plugin: name,
+                    };
                 }
-                LoadedPlugin::Wasm(p) => {
-                    if let Err(e) = p.call_on_session_start(session_id) {
-                        tracing::warn!(plugin = %name, error = %e, "WASM on_session_start error");
-                    }
+                Ok(ToolVerdict::Modify { input: new_input }) => {
+                    tracing::info!(plugin = %name, tool = %tool_name, "plugin modified tool input");
+                    input = new_input;
+                    modified_by.push(name);
+                }
+                Err(e) => {
+                    tracing::warn!(plugin = %name, error = %e, "ignoring on_tool_call result");
                 }
             }
         }
+        if modified_by.is_empty() {
+            ToolCallDecision::Allow
+        } else {
+            ToolCallDecision::Modify {
+                plugins: modified_by,
+                input,
+            }
+        }
+    }
+
+    /// Forward session lifecycle and message events from the broadcaster to
+    /// plugins.  Session start/end are delivered by the session handlers.
+    pub fn start_event_dispatcher(self: &Arc<Self>, broadcaster: &EventBroadcaster) {
+        let manager = Arc::clone(self);
+        let mut rx = broadcaster.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let raw = match rx.recv().await {
+                    Ok(raw) => raw,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!(skipped = n, "plugin event dispatcher lagged");
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                let Ok(event) = serde_json::from_str::<Value>(&raw) else {
+                    continue;
+                };
+                if let Some((session_id, role, content)) = message_event(&event) {
+                    if !manager.plugins.lock().await.is_empty() {
+                        manager.on_message(&session_id, &role, &content).await;
+                    }
+                }
+            }
+        });
+    }
+
+    // ─── Contributed tools and methods ───────────────────────────────────────
+
+    /// MCP tools contributed by loaded plugins, sorted by name.
+    pub async fn tools(&self) -> Vec<PluginTool> {
+        self.tools.lock().await.values().cloned().collect()
+    }
+
+    /// Look up a contributed tool by its agent-facing name.
+    pub async fn tool(&self, name: &str) -> Option<PluginTool> {
+        self.tools.lock().await.get(name).cloned()
+    }
+
+    /// Run a contributed tool.  Policy checks are the caller's job.
+    pub async fn invoke_tool(&self, session_id: &str, name: &str, input: &Value) -> Result<Value> {
+        let tool = self
+            .tool(name)
+            .await
+            .with_context(|| format!("unknown plugin tool: {name}"))?;
+        let call = HookCall::ToolInvoke {
+            session_id: session_id.to_string(),
+            tool_name: tool.tool.clone(),
+            input_json: input.to_string(),
+        };
+        self.request(&tool.plugin, call).await
+    }
+
+    /// Whether `method` is a registered `plugin.<name>.<method>` RPC.
+    pub async fn has_method(&self, method: &str) -> bool {
+        self.methods.lock().await.contains_key(method)
+    }
+
+    /// Risk of a registered `plugin.<name>.<method>` RPC: the manifest's
+    /// `method_risk` entry, or medium.
+    pub async fn method_risk(&self, method: &str) -> Option<RiskLevel> {
+        self.methods
+            .lock()
+            .await
+            .get(method)
+            .map(|(_, _, risk)| risk.clone())
+    }
+
+    /// Handle a contributed JSON-RPC method.
+    pub async fn call_method(&self, method: &str, params: Value) -> Result<Value> {
+        let Some((plugin, local, _)) = self.methods.lock().await.get(method).cloned() else {
+            bail!("METHOD_NOT_FOUND:{method}");
+        };
+        let call = HookCall::Rpc {
+            method: local,
+            params_json: params.to_string(),
+        };
+        self.request(&plugin, call).await
     }
 
     /// Unload all plugins cleanly (called on daemon shutdown).
     pub async fn shutdown(&self) {
-        let mut plugins = self.plugins.lock().await;
-        for (name, plugin) in plugins.drain() {
+        let drained: Vec<(String, PluginSlot)> = self.plugins.lock().await.drain().collect();
+        self.tools.lock().await.clear();
+        self.methods.lock().await.clear();
+        for (name, slot) in drained {
             tracing::debug!(plugin = %name, "unloading plugin");
-            if let LoadedPlugin::Dylib(p) = plugin {
-                p.call_on_unload(std::ptr::null_mut());
+            self.call_slot(&name, &slot, HookCall::Unload).await;
+        }
+    }
+
+    // ─── Internals ───────────────────────────────────────────────────────────
+
+    /// Loaded plugin names in order, so delivery order is deterministic.
+    async fn snapshot(&self) -> Vec<String> {
+        let mut plugins: Vec<_> = self.plugins.lock().await.keys().cloned().collect();
+        plugins.sort();
+        plugins
+    }
+
+    fn gate(&self, name: &str) -> PluginGate {
+        let mut gates = self.gates.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(gates.entry(name.to_string()).or_default())
+    }
+
+    /// Read the manifest, verify the binary and create (but not load) the
+    /// plugin.  The binary is read once and the checked bytes are what gets
+    /// loaded: dylibs are opened from a private staged copy, which also gives
+    /// reloads a path the dynamic loader has not mapped yet.
+    fn instantiate(&self, plugin_dir: &Path) -> Result<(PluginManifest, LoadedPlugin)> {
+        let manifest_json = std::fs::read_to_string(plugin_dir.join("clawd-plugin.json"))
+            .context("failed to read clawd-plugin.json")?;
+        let manifest = PluginManifest::from_json(&manifest_json)
+            .context("failed to parse clawd-plugin.json")?;
+        // The name prefixes the plugin's tools (`<name>__<tool>`) and methods
+        // (`plugin.<name>.<method>`), so it must not contain either separator.
+        if !is_identifier(&manifest.name, true) || manifest.name.contains("__") {
+            bail!(
+                "plugin name '{}' must match [A-Za-z0-9_-]+ without '__'",
+                manifest.name
+            );
+        }
+
+        let binary_path = plugin_dir.join(&manifest.entry);
+        let binary = std::fs::read(&binary_path)
+            .with_context(|| format!("cannot read plugin binary: {}", binary_path.display()))?;
+        if let Some(ref pubkey) = self.signer_pubkey {
+            verify_plugin_bytes(&binary, &manifest.signature, pubkey)
+                .context("plugin signature verification failed")?;
+        }
+
+        let loaded = match manifest.runtime {
+            ManifestRuntime::Dylib => {
+                let staged = self
+                    .stage(&manifest.name, &binary_path, &binary)
+                    .with_context(|| format!("failed to stage {}", binary_path.display()))?;
+                // The mapping outlives the file; `staged` removes it on drop.
+                let plugin = DylibPlugin::load(staged.path(), None, None)
+                    .context("failed to load dylib plugin")?;
+                LoadedPlugin::Dylib(plugin)
+            }
+            ManifestRuntime::Wasm => {
+                let limits = WasmLimits {
+                    deadline: self.call_timeout,
+                    ..self.wasm_limits
+                };
+                let plugin = WasmPlugin::from_bytes(
+                    &binary,
+                    plugin_dir.to_path_buf(),
+                    &manifest.name,
+                    manifest.capabilities.clone(),
+                    limits,
+                )
+                .context("failed to load WASM plugin")?;
+                LoadedPlugin::Wasm(plugin)
+            }
+        };
+        Ok((manifest, loaded))
+    }
+
+    /// Write `binary` to a fresh, randomly named file in the staging
+    /// directory, which only the daemon's user can enter.
+    fn stage(
+        &self,
+        name: &str,
+        binary_path: &Path,
+        binary: &[u8],
+    ) -> Result<tempfile::NamedTempFile> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&self.staging_dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.staging_dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+
+        let ext = binary_path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let mut staged = tempfile::Builder::new()
+            .prefix(&format!("{name}-"))
+            .suffix(&ext)
+            .tempfile_in(&self.staging_dir)?;
+        std::io::Write::write_all(&mut staged, binary)?;
+        staged.as_file().sync_all()?;
+        Ok(staged)
+    }
+
+    fn broadcast(&self, method: &str, params: Value) {
+        if let Some(ref broadcaster) = self.events {
+            broadcaster.broadcast(method, params);
+        }
+    }
+
+    /// Validate and record what a plugin registered during load.  Invalid
+    /// registrations are skipped with a warning rather than failing the load.
+    async fn register_contributions(
+        &self,
+        plugin: &str,
+        slot: &Slot,
+        tools: Vec<ToolRegistration>,
+        methods: Vec<String>,
+    ) -> (Vec<String>, Vec<String>) {
+        self.forget_contributions(plugin).await;
+        let mut tool_names = Vec::new();
+        let mut registry = self.tools.lock().await;
+        for reg in tools {
+            match plugin_tool(plugin, reg) {
+                Ok(tool) => {
+                    tool_names.push(tool.name.clone());
+                    registry.insert(tool.name.clone(), tool);
+                }
+                Err(e) => {
+                    tracing::warn!(plugin = %plugin, error = %e, "ignoring tool registration")
+                }
+            }
+        }
+        drop(registry);
+
+        let mut method_names = Vec::new();
+        let mut registry = self.methods.lock().await;
+        for method in methods {
+            if !is_identifier(&method, false) {
+                tracing::warn!(plugin = %plugin, method = %method, "ignoring method registration — use [A-Za-z0-9_]");
+                continue;
+            }
+            let full = format!("plugin.{plugin}.{method}");
+            let risk = slot
+                .method_risk
+                .get(&method)
+                .cloned()
+                .unwrap_or(RiskLevel::Medium);
+            method_names.push(full.clone());
+            registry.insert(full, (plugin.to_string(), method, risk));
+        }
+        if !tool_names.is_empty() || !method_names.is_empty() {
+            tracing::info!(plugin = %plugin, tools = ?tool_names, methods = ?method_names, "plugin contributions registered");
+        }
+        (tool_names, method_names)
+    }
+
+    async fn forget_contributions(&self, plugin: &str) {
+        self.tools.lock().await.retain(|_, t| t.plugin != plugin);
+        self.methods.lock().await.retain(|_, (p, _, _)| p != plugin);
+    }
+
+    /// Call a plugin and interpret the reply as a tool / RPC result.
+    async fn request(&self, plugin: &str, call: HookCall) -> Result<Value> {
+        let hook = call.name();
+        let reply = self
+            .call(plugin, call)
+            .await
+            .with_context(|| format!("plugin '{plugin}' failed or timed out in {hook}"))?;
+        reply
+            .into_result()
+            .map_err(|e| anyhow::anyhow!("plugin '{plugin}': {e}"))
+    }
+
+    /// Deliver a notification to every plugin concurrently; results other
+    /// than queued events are only logged.
+    async fn notify_all(&self, call: HookCall) {
+        let calls = self.snapshot().await.into_iter().map(|name| {
+            let call = call.clone();
+            async move {
+                if let Some(reply) = self.call(&name, call.clone()).await {
+                    if reply.code != ClawaError::None {
+                        tracing::warn!(plugin = %name, hook = call.name(), code = ?reply.code, "plugin hook returned error");
+                    }
+                }
+            }
+        });
+        futures_util::future::join_all(calls).await;
+    }
+
+    /// Call the active version of a loaded plugin, holding its gate shared.
+    ///
+    /// Returns `None` when the plugin is not loaded (or was disabled while
+    /// waiting on a reload) and in the cases described on [`Self::call_slot`].
+    async fn call(&self, name: &str, call: HookCall) -> Option<HookReply> {
+        let gate = self.gate(name);
+        let _in_flight = gate.read().await;
+        let slot = self.plugins.lock().await.get(name).cloned()?;
+        self.call_slot(name, &slot, call).await
+    }
+
+    /// Run one callback on a blocking thread once earlier calls into the
+    /// plugin have returned.  From then on a native callback is given
+    /// `call_timeout`; a WASM one is bounded by its own limits.
+    ///
+    /// Returns `None` when the call failed, panicked or timed out, or the
+    /// plugin was marked failed while the call waited.  A panic, or a native
+    /// callback overrunning the timeout, marks the plugin failed and removes
+    /// it from the active set.
+    async fn call_slot(&self, name: &str, slot: &PluginSlot, call: HookCall) -> Option<HookReply> {
+        let hook = call.name();
+        let mut failed = slot.failed.subscribe();
+        let turn = tokio::select! {
+            turn = Arc::clone(&slot.turn).lock_owned() => turn,
+            _ = failed.wait_for(|failed| *failed) => return None,
+        };
+        if *slot.failed.borrow() {
+            return None;
+        }
+        let runner = self
+            .tool_runner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let interruptible = slot.interruptible;
+        let task_slot = Arc::clone(slot);
+        let task = tokio::task::spawn_blocking(move || {
+            let _turn = turn;
+            let mut plugin = task_slot.plugin.lock().unwrap_or_else(|e| e.into_inner());
+            plugin.invoke(&call, runner)
+        });
+        let outcome = if interruptible {
+            Ok(task.await)
+        } else {
+            tokio::time::timeout(self.call_timeout, task).await
+        };
+        match outcome {
+            Err(_) => {
+                tracing::error!(
+                    plugin = %name,
+                    hook,
+                    timeout_ms = self.call_timeout.as_millis() as u64,
+                    "plugin hook timed out — disabling plugin"
+                );
+                slot.failed.send_replace(true);
+                self.mark_failed(name).await;
+                None
+            }
+            Ok(Err(e)) => {
+                tracing::error!(plugin = %name, hook, error = %e, "plugin hook panicked — disabling plugin");
+                slot.failed.send_replace(true);
+                self.mark_failed(name).await;
+                None
+            }
+            Ok(Ok(Err(e))) => {
+                tracing::warn!(plugin = %name, hook, error = %e, "plugin hook failed");
+                None
+            }
+            Ok(Ok(Ok(mut reply))) => {
+                self.flush_events(name, std::mem::take(&mut reply.events));
+                Some(reply)
             }
         }
     }
+
+    /// Remove a misbehaving plugin from the active set and mark it failed.
+    async fn mark_failed(&self, name: &str) {
+        self.plugins.lock().await.remove(name);
+        self.forget_contributions(name).await;
+        if let Some(info) = self.registry.lock().await.get_mut(name) {
+            info.status = PluginStatus::Failed;
+        }
+    }
+
+    /// Broadcast events a plugin queued.  Only `plugin.<name>.*` methods are
+    /// allowed so plugins cannot impersonate daemon events or each other.
+    fn flush_events(&self, plugin: &str, events: Vec<(String, Value)>) {
+        let prefix = format!("plugin.{plugin}.");
+        for (method, params) in events {
+            if !method.starts_with(&prefix) {
+                tracing::warn!(plugin = %plugin, method = %method, "dropping plugin event outside its plugin.<name>.* namespace");
+                continue;
+            }
+            self.broadcast(&method, params);
+        }
+    }
+}
+
+fn plugin_info(
+    manifest: &PluginManifest,
+    plugin_dir: &Path,
+    tools: Vec<String>,
+    methods: Vec<String>,
+) -> PluginInfo {
+    PluginInfo {
+        name: manifest.name.clone(),
+        version: manifest.version.clone(),
+        runtime: format!("{:?}", manifest.runtime).to_lowercase(),
+        status: PluginStatus::Enabled,
+        path: plugin_dir.to_string_lossy().into_owned(),
+        is_signed: manifest.is_signed(),
+        tools,
+        methods,
+    }
+}
+
+/// Build the agent-facing tool from a plugin's registration.
+fn plugin_tool(plugin: &str, reg: ToolRegistration) -> Result<PluginTool> {
+    if !is_identifier(&reg.name, true) {
+        bail!("tool name '{}' must match [A-Za-z0-9_-]+", reg.name);
+    }
+    let input_schema: Value = serde_json::from_str(&reg.input_schema)
+        .with_context(|| format!("tool '{}' has an invalid input schema", reg.name))?;
+    if !input_schema.is_object() {
+        bail!("tool '{}' input schema must be a JSON object", reg.name);
+    }
+    let risk = match reg.risk.as_deref() {
+        None => RiskLevel::Medium,
+        Some(r) => serde_json::from_value(Value::String(r.to_lowercase()))
+            .with_context(|| format!("tool '{}' has unknown risk '{r}'", reg.name))?,
+    };
+    Ok(PluginTool {
+        name: format!("{plugin}{PLUGIN_TOOL_SEPARATOR}{}", reg.name),
+        plugin: plugin.to_string(),
+        tool: reg.name,
+        description: reg.description,
+        input_schema,
+        risk,
+    })
+}
+
+/// Parse the manifest's `method_risk` table.
+fn method_risks(manifest: &PluginManifest) -> Result<HashMap<String, RiskLevel>> {
+    manifest
+        .method_risk
+        .iter()
+        .map(|(method, r)| {
+            let risk = serde_json::from_value(Value::String(r.to_lowercase()))
+                .with_context(|| format!("method '{method}' has unknown risk '{r}'"))?;
+            Ok((method.clone(), risk))
+        })
+        .collect()
+}
+
+fn is_identifier(s: &str, allow_dash: bool) -> bool {
+    !s.is_empty()
+        && s.len() <= 64
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || (allow_dash && c == '-'))
+}
+
+/// Extract `(session_id, role, content)` from a finished-message broadcast.
+///
+/// User messages are created complete; assistant messages stream and are
+/// complete once `session.messageUpdated` reports `status: done`.
+fn message_event(event: &Value) -> Option<(String, String, String)> {
+    let params = event.get("params")?;
+    let session_id = params.get("sessionId")?.as_str()?.to_string();
+    match event.get("method")?.as_str()? {
+        "session.messageCreated" => {
+            let msg = params.get("message")?;
+            let role = msg.get("role")?.as_str()?;
+            let content = msg.get("content")?.as_str()?;
+            let done = msg.get("status").and_then(Value::as_str) == Some("done");
+            (done && matches!(role, "user" | "assistant") && !content.is_empty())
+                .then(|| (session_id, role.to_string(), content.to_string()))
+        }
+        "session.messageUpdated" => {
+            let done = params.get("status").and_then(Value::as_str) == Some("done");
+            let content = params.get("content")?.as_str()?;
+            (done && !content.is_empty())
+                .then(|| (session_id, "assistant".to_string(), content.to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn plugin_tool_validates_registration() {
+        let reg = |name: &str, schema: &str, risk: Option<&str>| ToolRegistration {
+            name: name.into(),
+            description: "d".into(),
+            input_schema: schema.into(),
+            risk: risk.map(str::to_string),
+        };
+        let tool =
+            plugin_tool("lint", reg("run-lint", r#"{"type":"object"}"#, Some("Low"))).unwrap();
+        assert_eq!(tool.name, "lint__run-lint");
+        assert_eq!(tool.risk, RiskLevel::Low);
+        assert_eq!(
+            plugin_tool("lint", reg("x", "{}", None)).unwrap().risk,
+            RiskLevel::Medium
+        );
+        assert!(plugin_tool("lint", reg("a.b", "{}", None)).is_err());
+        assert!(plugin_tool("lint", reg("x", "[]", None)).is_err());
+        assert!(plugin_tool("lint", reg("x", "{}", Some("extreme"))).is_err());
+    }
+
+    /// A manager with one native-style plugin, `fake`, running `hook`.
+    async fn with_fake(
+        timeout: Duration,
+        hook: impl FnMut(&HookCall) -> Result<HookReply> + Send + 'static,
+    ) -> PluginManager {
+        let manager = PluginManager::new(PathBuf::from("plugins")).with_call_timeout(timeout);
+        let slot = Slot::new(LoadedPlugin::Fake(Box::new(hook)), HashMap::new());
+        manager.plugins.lock().await.insert("fake".into(), slot);
+        manager
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn queued_calls_are_not_charged_for_waiting() {
+        let manager = with_fake(Duration::from_millis(200), |_| {
+            std::thread::sleep(Duration::from_millis(80));
+            Ok(HookReply::none())
+        })
+        .await;
+        let input = json!({});
+        let calls = (0..4).map(|_| manager.on_tool_call("s1", "read_file", &input));
+        let decisions = futures_util::future::join_all(calls).await;
+        assert!(
+            decisions.iter().all(|d| *d == ToolCallDecision::Allow),
+            "{decisions:?}"
+        );
+        assert_eq!(manager.snapshot().await, ["fake"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_plugin_that_times_out_denies_the_call() {
+        let manager = with_fake(Duration::from_millis(50), |_| {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(HookReply::none())
+        })
+        .await;
+        let input = json!({});
+        // The call queued behind the hung one is released, and denied too.
+        let (first, queued) = tokio::join!(
+            manager.on_tool_call("s1", "read_file", &input),
+            manager.on_tool_call("s2", "read_file", &input),
+        );
+        for decision in [first, queued] {
+            assert!(
+                matches!(&decision, ToolCallDecision::Deny { plugin, .. } if plugin == "fake"),
+                "{decision:?}"
+            );
+        }
+        // The plugin was disabled, so later calls no longer consult it.
+        assert!(manager.snapshot().await.is_empty());
+        assert_eq!(
+            manager.on_tool_call("s3", "read_file", &input).await,
+            ToolCallDecision::Allow
+        );
+    }
+
+    #[test]
+    fn staged_binaries_live_in_a_private_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = PluginManager::new(dir.path().join("plugins"));
+        let staged = manager
+            .stage("lint", Path::new("liblint.so"), b"binary")
+            .unwrap();
+        let path = staged.path().to_path_buf();
+        assert_eq!(
+            path.parent(),
+            Some(dir.path().join("plugin-staging").as_path())
+        );
+        assert!(path.to_string_lossy().ends_with(".so"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"binary");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("plugin-staging"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        drop(staged);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn message_event_picks_finished_messages() {
+        let created = json!({"method": "session.messageCreated", "params": {
+            "sessionId": "s1",
+            "message": {"role": "user", "content": "hi", "status": "done"}
+        }});
+        assert_eq!(
+            message_event(&created),
+            Some(("s1".into(), "user".into(), "hi".into()))
+        );
+
+        let streaming = json!({"method": "session.messageUpdated", "params": {
+            "sessionId": "s1", "messageId": "m", "content": "par", "status": "streaming"
+        }});
+        assert_eq!(message_event(&streaming), None);
+
+        let done = json!({"method": "session.messageUpdated", "params": {
+            "sessionId": "s1", "messageId": "m", "content": "partial answer", "status": "done"
+        }});
+        assert_eq!(
+            message_event(&done),
+            Some(("s1".into(), "assistant".into(), "partial answer".into()))
+        );
+
+        let tool = json!({"method": "session.messageCreated", "params": {
+            "sessionId": "s1",
+            "message": {"role": "tool", "content": "x", "status": "done"}
+        }});
+        assert_eq!(message_event(&tool), None);
+    }
 }
@@ -5,9 +5,13 @@
 //! - `dylib_runtime` — native .dylib/.so loader
 //! - `wasm_runtime` — WebAssembly loader (wasmtime)
 //! - `manager` — plugin lifecycle and event dispatch
+//! - `hooks` — hook calls, replies and tool-call decisions
 //! - `signing` — Ed25519 signature helpers
+//! - `watcher` — hot reload when a plugin's files change
 
 pub mod dylib_runtime;
+pub mod hooks;
 pub mod manager;
 pub mod signing;
 pub mod wasm_runtime;
+pub mod watcher;
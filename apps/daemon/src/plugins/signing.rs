@@ -48,13 +48,19 @@ pub fn sign_plugin(binary_path: &Path, key_hex: &str) -> Result<String> {
 pub fn verify_plugin_signature(binary_path: &Path, sig_hex: &str, pubkey_hex: &str) -> Result<()> {
     let binary = std::fs::read(binary_path)
         .with_context(|| format!("cannot read plugin binary: {}", binary_path.display()))?;
+    verify_plugin_bytes(&binary, sig_hex, pubkey_hex)
+}
+
+/// Verify the Ed25519 signature of plugin bytes already in memory, so the
+/// caller can load exactly what was checked.
+pub fn verify_plugin_bytes(binary: &[u8], sig_hex: &str, pubkey_hex: &str) -> Result<()> {
     let _sig_bytes = hex::decode(sig_hex).context("invalid signature hex")?;
     let _pub_bytes = hex::decode(pubkey_hex).context("invalid public key hex")?;
 
     // Placeholder — in production wire ed25519-dalek:
     //   let verifying_key = VerifyingKey::from_bytes(&pub_bytes.try_into()?)?;
     //   let signature = ed25519_dalek::Signature::from_bytes(&sig_bytes.try_into()?);
-    //   verifying_key.verify(&binary, &signature).context("signature invalid")?;
+    //   verifying_key.verify(binary, &signature).context("signature invalid")?;
 
     // For now: accept any non-empty sig for testing purposes.
     // Remove this shortcut once ed25519-dalek is wired in Sprint NN.
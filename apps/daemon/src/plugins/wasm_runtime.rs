@@ -4,26 +4,144 @@
 //! Loads `.wasm` plugin modules. Exposes a set of host functions that WASM
 //! plugins can call (send_event, log, read_file, run_tool). Capability
 //! grants per plugin are enforced before each host call.
+//!
+//! ## Guest ABI
+//!
+//! Host functions are imported from module `env`. Strings are passed as
+//! `(ptr, len)` pairs of UTF-8 in the guest's exported `memory`:
+//!
+//! | Import | Capability | Returns |
+//! |--------|------------|---------|
+//! | `clawd_log(level, msg_ptr, msg_len)` | — | — |
+//! | `clawd_send_event(method_ptr, method_len, params_ptr, params_len)` | `network.relay` | `ClawaError` code |
+//! | `clawd_read_file(path_ptr, path_len, out_ptr, out_cap)` | `fs.read` | file length, or `-ClawaError` |
+//! | `clawd_run_tool(name_ptr, name_len, input_ptr, input_len, out_ptr, out_cap)` | `daemon.rpc` | result JSON length, or `-ClawaError` |
+//! | `clawd_set_tool_input(json_ptr, json_len)` | — | `ClawaError` code |
+//! | `clawd_set_reason(msg_ptr, msg_len)` | — | `ClawaError` code |
+//! | `clawd_set_result(json_ptr, json_len)` | — | `ClawaError` code |
+//! | `clawd_register_tool(name, description, schema_json, risk)` as 4 ptr/len pairs | — | `ClawaError` code |
+//! | `clawd_register_method(name_ptr, name_len)` | — | `ClawaError` code |
+//!
+//! Length-returning imports copy at most `out_cap` bytes; a result longer than
+//! the buffer can be fetched again with a bigger one. `clawd_register_*` is
+//! only accepted during `clawd_plugin_init`; an empty `risk` means medium.
+//!
+//! The module must export `clawd_plugin_init() -> i32`. Event hooks are
+//! optional exports named after the ABI callbacks (`on_session_start`,
+//! `on_session_end`, `on_tool_call`, `on_message`, `on_tool_invoke`, `on_rpc`,
+//! `on_unload`) taking one `(ptr, len)` pair per string argument and
+//! returning a `ClawaError` code.
+//! Hooks with string arguments require a `clawd_alloc(len) -> ptr` export;
+//! `clawd_free(ptr, len)` is called afterwards, newest allocation first, when
+//! present.
+//!
+//! ## Limits
+//!
+//! Every call gets a fresh fuel budget and an epoch deadline, so a runaway
+//! plugin traps instead of pinning a blocking thread. The deadline covers
+//! guest execution only: time spent inside `clawd_run_tool` is added back.
+//! Tool calls get their own budget of the same length; once a call has used
+//! it up, further `clawd_run_tool` calls fail with `DaemonError`. Linear
+//! memory growth is capped per instance.
 
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
 use serde_json::Value;
+use wasmtime::{
+    AsContextMut, Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, Trap, Val,
+};
 
 use clawd_plugin_abi::manifest::ManifestCapability;
+use clawd_plugin_abi::ClawaError;
+
+use super::hooks::{HookCall, HookReply, ToolRegistration, ToolRunner, DEFAULT_CALL_TIMEOUT};
+
+/// How often the shared engine's epoch advances.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+// ─── Limits ──────────────────────────────────────────────────────────────────
+
+/// Per-call CPU and per-instance memory caps.
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Fuel granted to each call (roughly one unit per WASM instruction).
+    pub fuel: u64,
+    /// Maximum linear memory per instance, in bytes.
+    pub memory_bytes: usize,
+    /// Wall-clock deadline per call, enforced via epoch interruption.  Time
+    /// spent in `clawd_run_tool` is not counted; it has a budget of its own
+    /// of the same length.
+    pub deadline: Duration,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 500_000_000,
+            memory_bytes: 64 * 1024 * 1024,
+            deadline: DEFAULT_CALL_TIMEOUT,
+        }
+    }
+}
+
+/// Engine shared by all WASM plugins, with fuel metering and epoch
+/// interruption enabled.  A background thread advances the epoch.
+fn shared_engine() -> Result<&'static Engine> {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    if let Some(engine) = ENGINE.get() {
+        return Ok(engine);
+    }
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    let engine = Engine::new(&config).context("failed to create wasmtime engine")?;
+    if ENGINE.set(engine.clone()).is_ok() {
+        std::thread::Builder::new()
+            .name("clawd-wasm-epoch".into())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                engine.increment_epoch();
+            })
+            .context("failed to start WASM epoch thread")?;
+    }
+    Ok(ENGINE.get().expect("engine initialised above"))
+}
+
+// ─── Host state ──────────────────────────────────────────────────────────────
 
 /// Host state threaded through the WASM store.
 pub struct WasmHostState {
-    /// Session-local broadcast channel sender (wrapped in Arc<Mutex>).
-    /// Events queued here are drained and dispatched after each plugin call.
+    /// Events queued via `clawd_send_event`, dispatched after each plugin call.
     pub queued_events: Vec<(String, Value)>,
-    /// Log messages queued for emission after the call returns.
-    pub queued_logs: Vec<(u8, String)>,
     /// Capability grants for this plugin instance.
     pub capabilities: HashSet<String>,
     /// Plugin name (for logging).
     pub plugin_name: String,
+    /// Replacement tool input set via `clawd_set_tool_input`.
+    pub tool_input: Option<String>,
+    /// Reason set via `clawd_set_reason`.
+    pub reason: Option<String>,
+    /// Directory `clawd_read_file` paths are resolved against.
+    pub fs_root: PathBuf,
+    /// Executes `clawd_run_tool`; `None` until the daemon wires one up.
+    pub tool_runner: Option<ToolRunner>,
+    /// Result set via `clawd_set_result`.
+    pub result: Option<String>,
+    /// Tools registered via `clawd_register_tool`.
+    pub tools: Vec<ToolRegistration>,
+    /// Methods registered via `clawd_register_method`.
+    pub methods: Vec<String>,
+    /// `clawd_register_*` is only accepted while `clawd_plugin_init` runs.
+    pub registering: bool,
+    limits: StoreLimits,
+    /// When the current call's epoch deadline expires.
+    deadline_at: Instant,
+    /// Time the current call has left for `clawd_run_tool`.
+    host_budget: Duration,
 }
 
 impl WasmHostState {
@@ -31,85 +149,604 @@ impl WasmHostState {
         let cap_set = capabilities.iter().map(|c| format!("{:?}", c)).collect();
         Self {
             queued_events: Vec::new(),
-            queued_logs: Vec::new(),
             capabilities: cap_set,
             plugin_name: plugin_name.into(),
+            tool_input: None,
+            reason: None,
+            fs_root: PathBuf::new(),
+            tool_runner: None,
+            result: None,
+            tools: Vec::new(),
+            methods: Vec::new(),
+            registering: false,
+            limits: StoreLimits::default(),
+            deadline_at: Instant::now(),
+            host_budget: Duration::ZERO,
         }
     }
 
     pub fn has_capability(&self, cap: &str) -> bool {
         self.capabilities.contains(cap)
     }
+
+    /// Check a grant, logging the denial.
+    fn allows(&self, cap: ManifestCapability, import: &str) -> bool {
+        let granted = self.has_capability(&format!("{:?}", cap));
+        if !granted {
+            tracing::warn!(
+                plugin = %self.plugin_name,
+                import,
+                capability = ?cap,
+                "WASM host call denied — capability not granted"
+            );
+        }
+        granted
+    }
+
+    /// Resolve a plugin-supplied relative path inside `fs_root`.
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        if Path::new(path).is_absolute() {
+            return None;
+        }
+        let root = self.fs_root.canonicalize().ok()?;
+        let full = root.join(path).canonicalize().ok()?;
+        full.starts_with(&root).then_some(full)
+    }
+}
+
+// ─── Host functions ──────────────────────────────────────────────────────────
+
+fn error_code(e: ClawaError) -> i32 {
+    e as i32
+}
+
+fn guest_bytes(caller: &mut Caller<'_, WasmHostState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = caller.get_export("memory")?.into_memory()?;
+    let start = usize::try_from(ptr).ok()?;
+    let end = start.checked_add(usize::try_from(len).ok()?)?;
+    memory.data(&caller).get(start..end).map(<[u8]>::to_vec)
+}
+
+fn guest_str(caller: &mut Caller<'_, WasmHostState>, ptr: i32, len: i32) -> Option<String> {
+    guest_bytes(caller, ptr, len).and_then(|b| String::from_utf8(b).ok())
+}
+
+/// Copy `bytes` into a guest buffer and return the full length, or a negated
+/// error code.
+fn write_out(
+    caller: &mut Caller<'_, WasmHostState>,
+    out_ptr: i32,
+    out_cap: i32,
+    bytes: &[u8],
+) -> i32 {
+    let Ok(total) = i32::try_from(bytes.len()) else {
+        return -error_code(ClawaError::DaemonError);
+    };
+    let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        return -error_code(ClawaError::DaemonError);
+    };
+    let n = bytes.len().min(usize::try_from(out_cap).unwrap_or(0));
+    let Ok(start) = usize::try_from(out_ptr) else {
+        return -error_code(ClawaError::DaemonError);
+    };
+    if memory.write(caller, start, &bytes[..n]).is_err() {
+        return -error_code(ClawaError::DaemonError);
+    }
+    total
 }
 
+fn link_host_functions(linker: &mut Linker<WasmHostState>) -> Result<()> {
+    linker.func_wrap(
+        "env",
+        "clawd_log",
+        |mut caller: Caller<'_, WasmHostState>, level: i32, ptr: i32, len: i32| {
+            let msg = guest_str(&mut caller, ptr, len).unwrap_or_default();
+            let plugin = caller.data().plugin_name.clone();
+            match level {
+                0 => tracing::trace!(plugin = %plugin, "{msg}"),
+                1 => tracing::debug!(plugin = %plugin, "{msg}"),
+                2 => tracing::info!(plugin = %plugin, "{msg}"),
+                3 => tracing::warn!(plugin = %plugin, "{msg}"),
+                _ => tracing::error!(plugin = %plugin, "{msg}"),
+            }
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_send_event",
+        |mut caller: Caller<'_, WasmHostState>, m_ptr: i32, m_len: i32, p_ptr: i32, p_len: i32| {
+            if !caller
+                .data()
+                .allows(ManifestCapability::NetworkRelay, "clawd_send_event")
+            {
+                return error_code(ClawaError::CapabilityDenied);
+            }
+            let Some(method) = guest_str(&mut caller, m_ptr, m_len) else {
+                return error_code(ClawaError::DaemonError);
+            };
+            let params = guest_str(&mut caller, p_ptr, p_len)
+                .and_then(|p| serde_json::from_str(&p).ok())
+                .unwrap_or(Value::Null);
+            caller.data_mut().queued_events.push((method, params));
+            error_code(ClawaError::None)
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_read_file",
+        |mut caller: Caller<'_, WasmHostState>,
+         path_ptr: i32,
+         path_len: i32,
+         out_ptr: i32,
+         out_cap: i32| {
+            if !caller
+                .data()
+                .allows(ManifestCapability::FsRead, "clawd_read_file")
+            {
+                return -error_code(ClawaError::CapabilityDenied);
+            }
+            let Some(path) = guest_str(&mut caller, path_ptr, path_len) else {
+                return -error_code(ClawaError::DaemonError);
+            };
+            let Some(full) = caller.data().resolve(&path) else {
+                return -error_code(ClawaError::NotFound);
+            };
+            match std::fs::read(&full) {
+                Ok(bytes) => write_out(&mut caller, out_ptr, out_cap, &bytes),
+                Err(_) => -error_code(ClawaError::NotFound),
+            }
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_run_tool",
+        |mut caller: Caller<'_, WasmHostState>,
+         name_ptr: i32,
+         name_len: i32,
+         input_ptr: i32,
+         input_len: i32,
+         out_ptr: i32,
+         out_cap: i32| {
+            if !caller.data().allows(ManifestCapability::DaemonRpc, "clawd_run_tool") {
+                return -error_code(ClawaError::CapabilityDenied);
+            }
+            let (Some(tool), Some(input)) = (
+                guest_str(&mut caller, name_ptr, name_len),
+                guest_str(&mut caller, input_ptr, input_len),
+            ) else {
+                return -error_code(ClawaError::DaemonError);
+            };
+            let Ok(input) = serde_json::from_str::<Value>(&input) else {
+                return -error_code(ClawaError::CallbackError);
+            };
+            let Some(runner) = caller.data().tool_runner.clone() else {
+                return -error_code(ClawaError::NotFound);
+            };
+            let plugin = caller.data().plugin_name.clone();
+            if caller.data().host_budget.is_zero() {
+                tracing::warn!(plugin = %plugin, tool = %tool, "plugin tool call refused — tool time budget used up");
+                return -error_code(ClawaError::DaemonError);
+            }
+            // The tool runs in the daemon, so its time is charged to the
+            // tool budget instead of the guest's deadline.
+            let started = Instant::now();
+            let left = caller.data().deadline_at.saturating_duration_since(started);
+            let outcome = runner(&plugin, &tool, input);
+            let state = caller.data_mut();
+            state.host_budget = state.host_budget.saturating_sub(started.elapsed());
+            set_deadline(&mut caller.as_context_mut(), left);
+            match outcome {
+                Ok(result) => write_out(&mut caller, out_ptr, out_cap, result.to_string().as_bytes()),
+                Err(e) => {
+                    tracing::warn!(plugin = %plugin, tool = %tool, error = %e, "plugin tool call failed");
+                    -error_code(ClawaError::DaemonError)
+                }
+            }
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_set_tool_input",
+        |mut caller: Caller<'_, WasmHostState>, ptr: i32, len: i32| match guest_str(
+            &mut caller,
+            ptr,
+            len,
+        ) {
+            Some(input) => {
+                caller.data_mut().tool_input = Some(input);
+                error_code(ClawaError::None)
+            }
+            None => error_code(ClawaError::DaemonError),
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_set_reason",
+        |mut caller: Caller<'_, WasmHostState>, ptr: i32, len: i32| match guest_str(
+            &mut caller,
+            ptr,
+            len,
+        ) {
+            Some(reason) => {
+                caller.data_mut().reason = Some(reason);
+                error_code(ClawaError::None)
+            }
+            None => error_code(ClawaError::DaemonError),
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_set_result",
+        |mut caller: Caller<'_, WasmHostState>, ptr: i32, len: i32| match guest_str(
+            &mut caller,
+            ptr,
+            len,
+        ) {
+            Some(result) => {
+                caller.data_mut().result = Some(result);
+                error_code(ClawaError::None)
+            }
+            None => error_code(ClawaError::DaemonError),
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_register_tool",
+        |mut caller: Caller<'_, WasmHostState>,
+         name_ptr: i32,
+         name_len: i32,
+         desc_ptr: i32,
+         desc_len: i32,
+         schema_ptr: i32,
+         schema_len: i32,
+         risk_ptr: i32,
+         risk_len: i32| {
+            if !caller.data().registering {
+                return error_code(ClawaError::CallbackError);
+            }
+            let Some(name) = guest_str(&mut caller, name_ptr, name_len) else {
+                return error_code(ClawaError::DaemonError);
+            };
+            let description = guest_str(&mut caller, desc_ptr, desc_len).unwrap_or_default();
+            let input_schema =
+                guest_str(&mut caller, schema_ptr, schema_len).unwrap_or_else(|| "{}".into());
+            let risk = guest_str(&mut caller, risk_ptr, risk_len).filter(|r| !r.is_empty());
+            caller.data_mut().tools.push(ToolRegistration {
+                name,
+                description,
+                input_schema,
+                risk,
+            });
+            error_code(ClawaError::None)
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "clawd_register_method",
+        |mut caller: Caller<'_, WasmHostState>, ptr: i32, len: i32| {
+            if !caller.data().registering {
+                return error_code(ClawaError::CallbackError);
+            }
+            match guest_str(&mut caller, ptr, len) {
+                Some(method) => {
+                    caller.data_mut().methods.push(method);
+                    error_code(ClawaError::None)
+                }
+                None => error_code(ClawaError::DaemonError),
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+// ─── Plugin ──────────────────────────────────────────────────────────────────
+
 /// A loaded WASM plugin instance.
 ///
 /// Uses wasmtime to instantiate the module and call its exported functions.
-/// The engine and store are per-plugin — WASM isolation is per-instance.
+/// Each plugin has its own store, so linear memory is isolated per instance
+/// and persists across calls.
 pub struct WasmPlugin {
     /// Display name from manifest.
     pub name: String,
-    /// Instantiated WASM module bytes (stored for re-instantiation if needed).
-    _wasm_bytes: Vec<u8>,
+    store: Store<WasmHostState>,
+    instance: Instance,
     /// Granted capabilities (used by host functions).
     capabilities: Vec<ManifestCapability>,
+    limits: WasmLimits,
 }
 
 impl WasmPlugin {
     /// Load a WASM plugin from a `.wasm` binary.
     ///
-    /// Steps:
-    /// 1. Read the WASM bytes.
-    /// 2. Validate via wasmtime (checks structure, not semantics).
-    /// 3. Check that `clawd_plugin_init` export is present.
+    /// `clawd_read_file` resolves paths relative to the binary's directory.
     pub fn load(
         binary_path: &Path,
         name: impl Into<String>,
         capabilities: Vec<ManifestCapability>,
+        limits: WasmLimits,
     ) -> Result<Self> {
         let wasm_bytes = std::fs::read(binary_path)
             .with_context(|| format!("failed to read WASM: {}", binary_path.display()))?;
+        let fs_root = binary_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self::from_bytes(&wasm_bytes, fs_root, name, capabilities, limits)
+    }
+
+    /// Load a WASM plugin from bytes already read (and verified) by the
+    /// caller.
+    ///
+    /// Steps:
+    /// 1. Compile via wasmtime (validates structure and types).
+    /// 2. Instantiate against the host imports; unknown imports fail here.
+    /// 3. Check that `clawd_plugin_init` export is present.
+    ///
+    /// `clawd_read_file` resolves paths relative to `fs_root`.
+    pub fn from_bytes(
+        wasm_bytes: &[u8],
+        fs_root: PathBuf,
+        name: impl Into<String>,
+        capabilities: Vec<ManifestCapability>,
+        limits: WasmLimits,
+    ) -> Result<Self> {
+        let name = name.into();
 
         // Lightweight magic-byte validation (WASM magic: \0asm).
         if !wasm_bytes.starts_with(b"\0asm") {
-            bail!(
-                "not a valid WASM module: {} (missing \\0asm magic)",
-                binary_path.display()
-            );
+            bail!("not a valid WASM module: {name} (missing \\0asm magic)");
         }
 
-        // TODO (Sprint FF+): use wasmtime Engine to fully validate and
-        // instantiate the module with host function imports. For now we
-        // validate the magic bytes and store the bytes for later instantiation.
-        // Full wasmtime integration requires wasmtime = "18" in Cargo.toml.
+        let engine = shared_engine()?;
+        let module = Module::new(engine, wasm_bytes)
+            .with_context(|| format!("invalid WASM module: {name}"))?;
+        let mut linker = Linker::new(engine);
+        link_host_functions(&mut linker)?;
+
+        let mut state = WasmHostState::new(name.clone(), &capabilities);
+        state.fs_root = fs_root;
+        state.limits = StoreLimitsBuilder::new()
+            .memory_size(limits.memory_bytes)
+            .build();
+        let mut store = Store::new(engine, state);
+        store.limiter(|s| &mut s.limits);
+        refuel(&mut store, &limits)?;
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| describe_trap(e, &name))
+            .context("failed to instantiate WASM plugin")?;
+        instance
+            .get_typed_func::<(), i32>(&mut store, "clawd_plugin_init")
+            .context("missing `clawd_plugin_init() -> i32` export")?;
 
         Ok(Self {
-            name: name.into(),
-            _wasm_bytes: wasm_bytes,
+            name,
+            store,
+            instance,
             capabilities,
+            limits,
         })
     }
 
-    /// Call the `clawd_plugin_init` export (placeholder — full wasmtime
-    /// instantiation wired in Sprint FF execution).
-    pub fn call_init(&self) -> Result<()> {
-        // Placeholder: returns Ok until wasmtime is wired.
-        tracing::debug!(plugin = %self.name, "WASM plugin init (stub)");
-        Ok(())
+    /// Call the `clawd_plugin_init` export.
+    pub fn call_init(&mut self) -> Result<ClawaError> {
+        self.call_export("clawd_plugin_init", &[])
+            .map(|code| code.unwrap_or(ClawaError::None))
     }
 
     /// Call `on_session_start` WASM export.
-    pub fn call_on_session_start(&self, session_id: &str) -> Result<()> {
-        tracing::debug!(
-            plugin = %self.name,
-            session_id = %session_id,
-            "WASM on_session_start (stub)"
-        );
-        Ok(())
+    pub fn call_on_session_start(&mut self, session_id: &str) -> Result<ClawaError> {
+        self.call_hook("on_session_start", &[session_id])
+    }
+
+    /// Call `on_session_end` WASM export.
+    pub fn call_on_session_end(&mut self, session_id: &str) -> Result<ClawaError> {
+        self.call_hook("on_session_end", &[session_id])
+    }
+
+    /// Call `on_tool_call` WASM export.
+    pub fn call_on_tool_call(
+        &mut self,
+        session_id: &str,
+        tool_name: &str,
+        input_json: &str,
+    ) -> Result<ClawaError> {
+        self.call_hook("on_tool_call", &[session_id, tool_name, input_json])
+    }
+
+    /// Call `on_message` WASM export.
+    pub fn call_on_message(
+        &mut self,
+        session_id: &str,
+        role: &str,
+        content_json: &str,
+    ) -> Result<ClawaError> {
+        self.call_hook("on_message", &[session_id, role, content_json])
+    }
+
+    /// Deliver one hook call.  Blocking — the manager runs this off the
+    /// async runtime.  `runner` backs `clawd_run_tool` for this call.
+    pub fn invoke(&mut self, call: &HookCall, runner: Option<ToolRunner>) -> Result<HookReply> {
+        {
+            let state = self.store.data_mut();
+            state.tool_runner = runner;
+            state.tool_input = None;
+            state.reason = None;
+            state.result = None;
+            state.queued_events.clear();
+            state.tools.clear();
+            state.methods.clear();
+            state.registering = matches!(call, HookCall::Load);
+        }
+        let code = match call {
+            HookCall::Load => self.call_init(),
+            HookCall::Unload => self.call_hook("on_unload", &[]),
+            HookCall::SessionStart { session_id } => self.call_on_session_start(session_id),
+            HookCall::SessionEnd { session_id } => self.call_on_session_end(session_id),
+            HookCall::ToolCall {
+                session_id,
+                tool_name,
+                input_json,
+            } => self.call_on_tool_call(session_id, tool_name, input_json),
+            HookCall::Message {
+                session_id,
+                role,
+                content_json,
+            } => self.call_on_message(session_id, role, content_json),
+            HookCall::ToolInvoke {
+                session_id,
+                tool_name,
+                input_json,
+            } => self
+                .call_export("on_tool_invoke", &[session_id, tool_name, input_json])
+                .map(|code| code.unwrap_or(ClawaError::NotFound)),
+            HookCall::Rpc {
+                method,
+                params_json,
+            } => self
+                .call_export("on_rpc", &[method, params_json])
+                .map(|code| code.unwrap_or(ClawaError::NotFound)),
+        };
+        let state = self.store.data_mut();
+        state.tool_runner = None;
+        state.registering = false;
+        let code = code?;
+        Ok(HookReply {
+            code,
+            tool_input: state.tool_input.take(),
+            reason: state.reason.take(),
+            events: std::mem::take(&mut state.queued_events),
+            result: state.result.take(),
+            tools: std::mem::take(&mut state.tools),
+            methods: std::mem::take(&mut state.methods),
+        })
     }
 
     /// Returns the capability grants for this plugin.
     pub fn capabilities(&self) -> &[ManifestCapability] {
         &self.capabilities
     }
+
+    /// Call an optional hook export; a missing export means the plugin
+    /// opted out of the event.
+    fn call_hook(&mut self, export: &str, args: &[&str]) -> Result<ClawaError> {
+        self.call_export(export, args)
+            .map(|code| code.unwrap_or(ClawaError::None))
+    }
+
+    /// Copy `args` into guest memory and call `export`.  Returns `None` when
+    /// the export does not exist.
+    fn call_export(&mut self, export: &str, args: &[&str]) -> Result<Option<ClawaError>> {
+        let Some(func) = self.instance.get_func(&mut self.store, export) else {
+            return Ok(None);
+        };
+        let ty = func.ty(&self.store);
+        if ty.params().len() != args.len() * 2 || ty.results().len() != 1 {
+            bail!(
+                "export `{export}` must take {} i32 params and return i32",
+                args.len() * 2
+            );
+        }
+        refuel(&mut self.store, &self.limits)?;
+
+        let mut params = Vec::with_capacity(args.len() * 2);
+        let mut allocated = Vec::with_capacity(args.len());
+        for arg in args {
+            let (ptr, len) = self.copy_in(arg)?;
+            params.push(Val::I32(ptr));
+            params.push(Val::I32(len));
+            allocated.push((ptr, len));
+        }
+
+        let mut results = [Val::I32(0)];
+        let outcome = func
+            .call(&mut self.store, &params, &mut results)
+            .map_err(|e| describe_trap(e, &self.name));
+
+        if let Some(free) = self.instance.get_func(&mut self.store, "clawd_free") {
+            if let Ok(free) = free.typed::<(i32, i32), ()>(&self.store) {
+                refuel(&mut self.store, &self.limits)?;
+                // Newest first, so bump allocators can unwind.
+                for (ptr, len) in allocated.into_iter().rev() {
+                    let _ = free.call(&mut self.store, (ptr, len));
+                }
+            }
+        }
+
+        outcome.with_context(|| format!("WASM `{export}` failed"))?;
+        let code = results[0]
+            .i32()
+            .with_context(|| format!("export `{export}` must return i32"))?;
+        Ok(Some(clawa_error_from(code)))
+    }
+
+    /// Allocate guest memory via `clawd_alloc` and copy `s` into it.
+    fn copy_in(&mut self, s: &str) -> Result<(i32, i32)> {
+        let len = i32::try_from(s.len()).context("argument too large for WASM")?;
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut self.store, "clawd_alloc")
+            .context("hooks with string arguments need a `clawd_alloc(len) -> ptr` export")?;
+        let ptr = alloc
+            .call(&mut self.store, len)
+            .map_err(|e| describe_trap(e, &self.name))?;
+        let memory = self
+            .instance
+            .get_memory(&mut self.store, "memory")
+            .context("WASM plugin does not export `memory`")?;
+        memory
+            .write(
+                &mut self.store,
+                usize::try_from(ptr).context("clawd_alloc returned a negative pointer")?,
+                s.as_bytes(),
+            )
+            .context("clawd_alloc returned an out-of-bounds pointer")?;
+        Ok((ptr, len))
+    }
+}
+
+/// Reset the per-call fuel budget and epoch deadline.
+fn refuel(store: &mut Store<WasmHostState>, limits: &WasmLimits) -> Result<()> {
+    store.set_fuel(limits.fuel)?;
+    store.data_mut().host_budget = limits.deadline;
+    set_deadline(&mut store.as_context_mut(), limits.deadline);
+    Ok(())
+}
+
+/// Let the guest run for `left` more, rounded down to whole epoch ticks.
+fn set_deadline(store: &mut wasmtime::StoreContextMut<'_, WasmHostState>, left: Duration) {
+    let ticks = (left.as_millis() / EPOCH_TICK.as_millis()).max(1);
+    store.set_epoch_deadline(u64::try_from(ticks).unwrap_or(u64::MAX));
+    store.data_mut().deadline_at = Instant::now() + left;
+}
+
+/// Turn resource-limit traps into readable errors.
+fn describe_trap(e: anyhow::Error, plugin: &str) -> anyhow::Error {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => anyhow::anyhow!("plugin '{plugin}' exceeded its fuel limit"),
+        Some(Trap::Interrupt) => anyhow::anyhow!("plugin '{plugin}' exceeded its time limit"),
+        _ => e,
+    }
+}
+
+fn clawa_error_from(code: i32) -> ClawaError {
+    match code {
+        0 => ClawaError::None,
+        1 => ClawaError::InitFailed,
+        3 => ClawaError::CapabilityDenied,
+        4 => ClawaError::NotFound,
+        5 => ClawaError::DaemonError,
+        _ => ClawaError::CallbackError,
+    }
 }
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT
+//! Plugin hot reload — watches `.claw/plugins/` and upgrades plugins in place.
+//!
+//! Changes are debounced and grouped by plugin directory, then handed to
+//! [`PluginManager::on_plugin_dir_changed`], which re-verifies, drains,
+//! swaps and rolls back as needed.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify_debouncer_full::{
+    new_debouncer,
+    notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
+    DebounceEventResult, Debouncer, FileIdMap,
+};
+use tracing::{info, warn};
+
+use super::manager::PluginManager;
+
+/// Keeps the plugins directory watch alive; dropping it stops hot reload.
+pub struct PluginWatcher {
+    _watcher: Debouncer<RecommendedWatcher, FileIdMap>,
+}
+
+impl PluginWatcher {
+    /// Start watching the manager's plugins directory.
+    ///
+    /// Returns `None` if the directory is missing or the watcher could not be
+    /// created (non-fatal; plugins then only change on restart or
+    /// `plugin.enable`).
+    pub fn start(manager: Arc<PluginManager>, debounce: Duration) -> Option<Self> {
+        let root = manager.plugins_dir().to_path_buf();
+        if !root.is_dir() {
+            return None;
+        }
+        let rt_handle = tokio::runtime::Handle::current();
+        let watch_root = root.clone();
+
+        let debouncer = new_debouncer(debounce, None, move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+            let dirs: BTreeSet<PathBuf> = events
+                .iter()
+                .filter(|e| {
+                    matches!(
+                        e.event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    )
+                })
+                .flat_map(|e| e.event.paths.iter())
+                .filter_map(|p| plugin_dir_of(&watch_root, p))
+                .collect();
+            for dir in dirs {
+                let manager = Arc::clone(&manager);
+                rt_handle.spawn(async move {
+                    if let Err(e) = manager.on_plugin_dir_changed(&dir).await {
+                        warn!(dir = %dir.display(), error = %format!("{e:#}"), "plugin hot reload failed");
+                    }
+                });
+            }
+        });
+
+        match debouncer {
+            Ok(mut debouncer) => {
+                if let Err(e) = debouncer.watcher().watch(&root, RecursiveMode::Recursive) {
+                    warn!("plugin watcher failed to start: {e} — hot reload disabled");
+                    return None;
+                }
+                info!(path = %root.display(), "plugin hot-reload watcher started");
+                Some(Self {
+                    _watcher: debouncer,
+                })
+            }
+            Err(e) => {
+                warn!("plugin watcher creation failed: {e} — hot reload disabled");
+                None
+            }
+        }
+    }
+}
+
+/// `{root}/{plugin}/...` → `{root}/{plugin}`.  Hidden entries and files
+/// directly under the root are ignored.
+fn plugin_dir_of(root: &Path, path: &Path) -> Option<PathBuf> {
+    let rel = path.strip_prefix(root).ok()?;
+    let mut parts = rel.components();
+    let first = parts.next()?.as_os_str();
+    parts.next()?;
+    if first.to_string_lossy().starts_with('.') {
+        return None;
+    }
+    Some(root.join(first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_dir_of_groups_by_first_component() {
+        let root = Path::new("/p");
+        assert_eq!(
+            plugin_dir_of(root, Path::new("/p/lint/lint.wasm")),
+            Some(PathBuf::from("/p/lint"))
+        );
+        assert_eq!(
+            plugin_dir_of(root, Path::new("/p/lint/sub/x")),
+            Some(PathBuf::from("/p/lint"))
+        );
+        assert_eq!(plugin_dir_of(root, Path::new("/p/README")), None);
+        assert_eq!(plugin_dir_of(root, Path::new("/p/.tmp/x")), None);
+        assert_eq!(plugin_dir_of(root, Path::new("/elsewhere/x")), None);
+    }
+}
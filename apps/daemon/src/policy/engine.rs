@@ -3,9 +3,11 @@
 //! `PolicyEngine::evaluate` is called from `McpDispatcher` (and other tool
 //! dispatch sites) *before* any tool executes. It runs three checks in order:
 //!
-//! 1. **Task state gate** — task must be in a state that permits the call.
-//! 2. **Risk classification** — look up the tool's risk level.
-//! 3. **Approval rules** — decide Allow / Deny / NeedsApproval.
+//! 1. **Plugin review** — loaded plugins may deny the call or rewrite its input.
+//! 2. **Task state gate** — task must be in a state that permits the call.
+//! 3. **Risk classification** — look up the tool's risk level (plugin tools
+//!    without a `tool-risk.json` rule use the risk they declared).
+//! 4. **Approval rules** — decide Allow / Deny / NeedsApproval.
 
 use std::path::Path;
 use std::sync::Arc;
@@ -13,6 +15,8 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::debug;
 
+use crate::plugins::hooks::ToolCallDecision;
+use crate::plugins::manager::PluginManager;
 use crate::tasks::reducer::TaskState;
 use crate::tasks::schema::RiskLevel;
 
@@ -38,14 +42,26 @@ pub enum PolicyDecision {
     },
 }
 
+impl PolicyDecision {
+    /// Status recorded in the audit log for this decision.
+    pub fn audit_status(&self) -> &'static str {
+        match self {
+            PolicyDecision::Allow => "allowed",
+            PolicyDecision::Deny { .. } => "denied",
+            PolicyDecision::NeedsApproval { .. } => "needs_approval",
+        }
+    }
+}
+
 // ─── PolicyEngine ─────────────────────────────────────────────────────────────
 
 /// Orchestrates risk classification, approval rules, and trust database checks.
 pub struct PolicyEngine {
     pub risk_db: Arc<RwLock<RiskDatabase>>,
     pub trust_db: Arc<RwLock<TrustDatabase>>,
-    pub supply_chain: Arc<SupplyChainPolicy>,
+    pub supply_chain: Arc<RwLock<SupplyChainPolicy>>,
     rules: ApprovalRules,
+    plugins: Option<Arc<PluginManager>>,
 }
 
 impl PolicyEngine {
@@ -53,13 +69,14 @@ impl PolicyEngine {
     pub fn new(
         risk_db: Arc<RwLock<RiskDatabase>>,
         trust_db: Arc<RwLock<TrustDatabase>>,
-        supply_chain: Arc<SupplyChainPolicy>,
+        supply_chain: Arc<RwLock<SupplyChainPolicy>>,
     ) -> Self {
         Self {
             risk_db,
             trust_db,
             supply_chain,
             rules: ApprovalRules::default(),
+            plugins: None,
         }
     }
 
@@ -78,31 +95,66 @@ impl PolicyEngine {
         Self {
             risk_db: Arc::new(RwLock::new(risk_db)),
             trust_db: Arc::new(RwLock::new(trust_db)),
-            supply_chain: Arc::new(supply_chain),
+            supply_chain: Arc::new(RwLock::new(supply_chain)),
             rules: ApprovalRules::default(),
+            plugins: None,
         }
     }
 
+    /// Let loaded plugins review tool calls via `on_tool_call`.
+    pub fn with_plugins(mut self, plugins: Arc<PluginManager>) -> Self {
+        self.plugins = Some(plugins);
+        self
+    }
+
     /// Evaluate a proposed tool invocation and return the policy decision.
     ///
+    /// Plugin modifications are discarded; use [`Self::evaluate_mut`] when the
+    /// caller executes the tool with the reviewed arguments.
+    ///
     /// # Arguments
     ///
     /// * `tool_name`  — Name of the MCP tool being invoked.
     /// * `args`       — Full JSON arguments for the invocation.
     /// * `task_state` — Current state of the associated task, if any.
-    /// * `agent_id`   — Identifier of the requesting agent.
+    /// * `session_id` — Session making the call; plugins see it in
+    ///   `on_tool_call`, and `plugin:<name>` marks a plugin's own calls.
     pub async fn evaluate(
         &self,
         tool_name: &str,
-        _args: &serde_json::Value,
+        args: &serde_json::Value,
         task_state: Option<&TaskState>,
-        _agent_id: &str,
+        session_id: &str,
     ) -> PolicyDecision {
-        // ── Step 1: look up risk level ────────────────────────────────────
-        let risk = {
-            let db = self.risk_db.read().await;
-            db.get_risk(tool_name)
-        };
+        let mut args = args.clone();
+        self.evaluate_mut(tool_name, &mut args, task_state, session_id)
+            .await
+    }
+
+    /// Like [`Self::evaluate`], but applies plugin input rewrites to `args`.
+    pub async fn evaluate_mut(
+        &self,
+        tool_name: &str,
+        args: &mut serde_json::Value,
+        task_state: Option<&TaskState>,
+        session_id: &str,
+    ) -> PolicyDecision {
+        // ── Step 1: plugin review ─────────────────────────────────────────
+        if let Some(denied) = self.review_with_plugins(tool_name, args, session_id).await {
+            return denied;
+        }
+        self.apply_rules(tool_name, task_state).await
+    }
+
+    /// Steps 2 and 3 of [`Self::evaluate`]: classify the tool's risk and
+    /// apply the approval rules, without plugin review.
+    pub async fn apply_rules(
+        &self,
+        tool_name: &str,
+        task_state: Option<&TaskState>,
+    ) -> PolicyDecision {
+        // ── Step 2: look up risk level ────────────────────────────────────
+        let risk = self.risk_of(tool_name).await;
 
         debug!(
             tool = tool_name,
@@ -111,9 +163,51 @@ impl PolicyEngine {
             "policy evaluate"
         );
 
-        // ── Step 2: apply approval rules ──────────────────────────────────
+        // ── Step 3: apply approval rules ──────────────────────────────────
         self.rules.should_approve(tool_name, risk, task_state)
     }
+
+    /// Effective risk of a tool: an explicit `tool-risk.json` rule wins, then
+    /// the risk a plugin declared for its tool or RPC method, then `Medium`.
+    pub async fn risk_of(&self, tool_name: &str) -> RiskLevel {
+        if let Some(risk) = self.risk_db.read().await.lookup(tool_name) {
+            return risk;
+        }
+        let Some(plugins) = &self.plugins else {
+            return RiskLevel::Medium;
+        };
+        if let Some(tool) = plugins.tool(tool_name).await {
+            return tool.risk;
+        }
+        plugins
+            .method_risk(tool_name)
+            .await
+            .unwrap_or(RiskLevel::Medium)
+    }
+
+    /// Run only the plugin review step.
+    ///
+    /// Returns `Some(Deny)` when a plugin vetoed the call; a plugin rewrite is
+    /// applied to `args` in place.  Without plugins this is a no-op.
+    pub async fn review_with_plugins(
+        &self,
+        tool_name: &str,
+        args: &mut serde_json::Value,
+        session_id: &str,
+    ) -> Option<PolicyDecision> {
+        let plugins = self.plugins.as_ref()?;
+        match plugins.on_tool_call(session_id, tool_name, args).await {
+            ToolCallDecision::Allow => None,
+            ToolCallDecision::Deny { plugin, reason } => Some(PolicyDecision::Deny {
+                reason: format!("plugin '{plugin}' denied {tool_name}: {reason}"),
+            }),
+            ToolCallDecision::Modify { plugins, input } => {
+                debug!(tool = tool_name, plugins = ?plugins, "tool input rewritten by plugins");
+                *args = input;
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -128,7 +222,7 @@ mod tests {
         PolicyEngine::new(
             Arc::new(RwLock::new(RiskDatabase::default_rules())),
             Arc::new(RwLock::new(TrustDatabase::default())),
-            Arc::new(SupplyChainPolicy::empty()),
+            Arc::new(RwLock::new(SupplyChainPolicy::empty())),
         )
     }
 
@@ -161,4 +255,17 @@ mod tests {
             .await;
         assert!(matches!(decision, PolicyDecision::NeedsApproval { .. }));
     }
+
+    #[tokio::test]
+    async fn no_loaded_plugins_leaves_args_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins = Arc::new(PluginManager::new(dir.path().to_path_buf()));
+        let e = engine().with_plugins(plugins);
+        let mut args = json!({"path": "src/lib.rs"});
+        let decision = e
+            .evaluate_mut("read_file", &mut args, Some(&TaskState::Active), "s1")
+            .await;
+        assert_eq!(decision, PolicyDecision::Allow);
+        assert_eq!(args, json!({"path": "src/lib.rs"}));
+    }
 }
@@ -0,0 +1,335 @@
+//! LSP post-edit gate — checks agent patches against the running language server.
+//!
+//! Before `apply_patch` touches a file, the MCP dispatcher records the file's
+//! current error-level diagnostics (its baseline for the task).  After the
+//! patch, when `PolicyHooks::post_tool` asks for `run_linter`, the changed
+//! files are synced to the server and every error not in the baseline is
+//! reported back to the agent with the tool result.
+//!
+//! Introduced errors are remembered per task, so a later patch that fixes
+//! them clears them.  With `diff_risk.block_review_on_lsp_errors` a
+//! `transition_task` to `in_cr` is refused while any remain.
+//!
+//! Files are checked against a server rooted at the task's worktree, started
+//! on first use (a server for the main checkout does not see the worktree as
+//! its workspace).  Servers the gate started are stopped when the task is
+//! forgotten.  Files in languages without a configured server are skipped.
+//! The files of one patch are checked concurrently.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+
+use crate::lsp::model::{DiagSeverity, DiagnosticItem};
+use crate::lsp::proxy::LspProxy;
+
+use super::sandbox::PolicyViolation;
+
+// ─── Report ───────────────────────────────────────────────────────────────────
+
+/// Result of checking one patch, attached to the `apply_patch` result as `lsp`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LspGateReport {
+    /// Files that a language server checked.
+    pub checked_files: Vec<String>,
+    /// Errors in those files that were not there before the task edited them.
+    pub new_errors: Vec<DiagnosticItem>,
+}
+
+// ─── Gate ─────────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct TaskFiles {
+    /// Errors each file had before the task first patched it.
+    baseline: HashMap<PathBuf, Vec<DiagnosticItem>>,
+    /// Errors the task's patches introduced, as of the last check.
+    introduced: BTreeMap<PathBuf, Vec<DiagnosticItem>>,
+    /// Servers (language, workspace root) the gate started for the task.
+    servers: HashSet<(String, PathBuf)>,
+}
+
+/// Per-task baselines and introduced errors.
+///
+/// Methods block on language server I/O — call them from a blocking thread.
+#[derive(Default)]
+pub struct LspGate {
+    tasks: Mutex<HashMap<String, TaskFiles>>,
+}
+
+static LSP_GATE: OnceLock<LspGate> = OnceLock::new();
+
+/// Daemon-global gate used by the MCP dispatcher.
+pub fn lsp_gate() -> &'static LspGate {
+    LSP_GATE.get_or_init(LspGate::default)
+}
+
+impl LspGate {
+    /// Record the pre-patch errors of `files` (relative to `worktree`) that
+    /// have a language server and no baseline for this task yet.
+    pub fn before_patch(&self, proxy: &LspProxy, task_id: &str, worktree: &Path, files: &[String]) {
+        let targets: Vec<(PathBuf, String)> = files
+            .iter()
+            .map(|f| worktree.join(f))
+            .filter(|file| {
+                !self
+                    .with_task(task_id, |t| t.baseline.contains_key(file))
+                    .unwrap_or(false)
+            })
+            .filter_map(|file| {
+                let language = self.server_for(proxy, task_id, worktree, &file)?;
+                Some((file, language))
+            })
+            .collect();
+        for (file, errors) in check_files(proxy, worktree, targets) {
+            self.with_task_mut(task_id, |t| {
+                t.baseline.insert(file, errors);
+            });
+        }
+    }
+
+    /// Sync the patched `files` to their language servers and report the
+    /// errors the task has introduced in them.
+    pub fn after_patch(
+        &self,
+        proxy: &LspProxy,
+        task_id: &str,
+        worktree: &Path,
+        files: &[String],
+    ) -> LspGateReport {
+        let targets: Vec<(PathBuf, String)> = files
+            .iter()
+            .map(|f| worktree.join(f))
+            .filter_map(|file| {
+                let language = self.server_for(proxy, task_id, worktree, &file)?;
+                Some((file, language))
+            })
+            .collect();
+        let mut report = LspGateReport::default();
+        for (file, current) in check_files(proxy, worktree, targets) {
+            let introduced = self.with_task_mut(task_id, |t| {
+                let baseline = t.baseline.get(&file).map(Vec::as_slice).unwrap_or(&[]);
+                let introduced = not_in(current, baseline);
+                if introduced.is_empty() {
+                    t.introduced.remove(&file);
+                } else {
+                    t.introduced.insert(file.clone(), introduced.clone());
+                }
+                introduced
+            });
+            report
+                .checked_files
+                .push(file.to_string_lossy().into_owned());
+            report.new_errors.extend(introduced);
+        }
+        report
+    }
+
+    /// Language of the server for `file` rooted at `worktree`, starting one
+    /// if none runs yet.  `None` when the language has no server.
+    fn server_for(
+        &self,
+        proxy: &LspProxy,
+        task_id: &str,
+        worktree: &Path,
+        file: &Path,
+    ) -> Option<String> {
+        let language = proxy.language_for(file)?;
+        if proxy
+            .running_server_for(file)
+            .is_some_and(|(_, root)| root == worktree)
+        {
+            return Some(language);
+        }
+        match proxy.start_server(&language, worktree) {
+            Ok(_) => {
+                self.with_task_mut(task_id, |t| {
+                    t.servers.insert((language.clone(), worktree.to_path_buf()));
+                });
+                Some(language)
+            }
+            Err(e) => {
+                tracing::debug!(%language, worktree = %worktree.display(), "lsp gate server not started: {e:#}");
+                None
+            }
+        }
+    }
+
+    /// Errors the task introduced that were still present at the last check.
+    pub fn introduced_errors(&self, task_id: &str) -> Vec<DiagnosticItem> {
+        self.with_task(task_id, |t| {
+            t.introduced.values().flatten().cloned().collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Refuse the move to code review while introduced errors remain.
+    pub fn check_review_transition(&self, task_id: &str) -> Result<(), PolicyViolation> {
+        let errors = self.introduced_errors(task_id);
+        match errors.first() {
+            None => Ok(()),
+            Some(first) => Err(PolicyViolation::LspErrorsIntroduced {
+                count: errors.len(),
+                first: format!("{}:{}: {}", first.file, first.line + 1, first.message),
+            }),
+        }
+    }
+
+    /// Drop everything recorded for `task_id` and stop the servers the gate
+    /// started for it.
+    pub fn forget(&self, proxy: &LspProxy, task_id: &str) {
+        let Some(task) = self.lock().remove(task_id) else {
+            return;
+        };
+        for (language, root) in task.servers {
+            if let Err(e) = proxy.stop_server(&language, &root) {
+                tracing::debug!(%language, root = %root.display(), "lsp gate server not stopped: {e:#}");
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TaskFiles>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_task<T>(&self, task_id: &str, f: impl FnOnce(&TaskFiles) -> T) -> Option<T> {
+        self.lock().get(task_id).map(f)
+    }
+
+    fn with_task_mut<T>(&self, task_id: &str, f: impl FnOnce(&mut TaskFiles) -> T) -> T {
+        f(self.lock().entry(task_id.to_string()).or_default())
+    }
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────────────
+
+/// Error-level diagnostics of each `(file, language)`, fetched in parallel
+/// from the servers rooted at `worktree` — each waits for the server to
+/// publish.  Files the server could not check are left out; a file that does
+/// not exist (created or deleted by the patch) has none.
+fn check_files(
+    proxy: &LspProxy,
+    worktree: &Path,
+    targets: Vec<(PathBuf, String)>,
+) -> Vec<(PathBuf, Vec<DiagnosticItem>)> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .into_iter()
+            .map(|(file, language)| {
+                scope.spawn(move || {
+                    if !file.exists() {
+                        return Some((file, Vec::new()));
+                    }
+                    match proxy.diagnostics_from_disk(&language, worktree, &file) {
+                        Ok(diags) => Some((file, errors_only(diags))),
+                        Err(e) => {
+                            tracing::debug!(file = %file.display(), "lsp gate check skipped: {e:#}");
+                            None
+                        }
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().ok().flatten())
+            .collect()
+    })
+}
+
+fn errors_only(diags: Vec<DiagnosticItem>) -> Vec<DiagnosticItem> {
+    diags
+        .into_iter()
+        .filter(|d| d.severity == DiagSeverity::Error)
+        .collect()
+}
+
+/// `current` minus `baseline`, matching on source and message (not position —
+/// edits above an old error move it).  Each baseline entry cancels at most
+/// one current entry.
+fn not_in(current: Vec<DiagnosticItem>, baseline: &[DiagnosticItem]) -> Vec<DiagnosticItem> {
+    let mut remaining: Vec<&DiagnosticItem> = baseline.iter().collect();
+    current
+        .into_iter()
+        .filter(|d| {
+            match remaining
+                .iter()
+                .position(|b| b.source == d.source && b.message == d.message)
+            {
+                Some(i) => {
+                    remaining.swap_remove(i);
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diag(line: u32, message: &str) -> DiagnosticItem {
+        DiagnosticItem {
+            file: "/w/a.rs".into(),
+            line,
+            col: 0,
+            severity: DiagSeverity::Error,
+            message: message.into(),
+            source: "rustc".into(),
+        }
+    }
+
+    #[test]
+    fn moved_baseline_errors_are_not_new() {
+        let baseline = vec![diag(3, "mismatched types")];
+        let current = vec![diag(7, "mismatched types"), diag(9, "unresolved import")];
+        let new = not_in(current, &baseline);
+        assert_eq!(new, vec![diag(9, "unresolved import")]);
+    }
+
+    #[test]
+    fn duplicate_messages_are_counted() {
+        let baseline = vec![diag(1, "expected `;`")];
+        let current = vec![diag(1, "expected `;`"), diag(5, "expected `;`")];
+        assert_eq!(not_in(current, &baseline).len(), 1);
+    }
+
+    #[test]
+    fn review_is_blocked_until_errors_clear() {
+        let gate = LspGate::default();
+        gate.with_task_mut("t1", |t| {
+            t.introduced.insert(
+                PathBuf::from("/w/a.rs"),
+                vec![diag(4, "cannot find value `x`")],
+            );
+        });
+        let err = gate.check_review_transition("t1").unwrap_err();
+        assert!(err.to_string().contains("/w/a.rs:5"), "{err}");
+
+        gate.with_task_mut("t1", |t| t.introduced.clear());
+        assert!(gate.check_review_transition("t1").is_ok());
+        assert!(gate.check_review_transition("unknown").is_ok());
+    }
+
+    #[test]
+    fn files_the_patch_removed_check_clean() {
+        let proxy = LspProxy::new();
+        let worktree = Path::new("/nonexistent/worktree");
+        let file = worktree.join("gone.rs");
+        let checked = check_files(&proxy, worktree, vec![(file.clone(), "rust".into())]);
+        assert_eq!(checked, vec![(file, Vec::new())]);
+    }
+
+    #[test]
+    fn files_without_a_language_server_are_skipped() {
+        let gate = LspGate::default();
+        let proxy = LspProxy::new();
+        let report = gate.after_patch(&proxy, "t1", Path::new("/w"), &["notes.unknownext".into()]);
+        assert!(report.checked_files.is_empty());
+        gate.forget(&proxy, "t1");
+        assert!(gate.with_task("t1", |_| ()).is_none());
+    }
+}
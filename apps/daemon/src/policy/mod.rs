@@ -17,6 +17,8 @@
 //!   dispatcher.
 //! - **DoD checker** — validates Definition-of-Done gates before a task
 //!   transitions to CodeReview.
+//! - **LSP gate** — reports language server errors introduced by agent
+//!   patches and can hold back the move to CodeReview.
 //! - **Scanners** — thin wrappers around placeholder and secrets scanners.
 //! - **RBAC** — role-based access control for agent tool dispatch.
 
@@ -24,6 +26,7 @@ pub mod approval;
 pub mod dod;
 pub mod engine;
 pub mod hooks;
+pub mod lsp_gate;
 pub mod mcp_trust;
 pub mod output_scan;
 pub mod rbac;
@@ -52,7 +52,21 @@ impl RiskDatabase {
         }
 
         // ── Medium risk — state-mutating but reversible ───────────────────
-        for tool in &["run_tests", "create_task", "claim_task", "transition_task"] {
+        // The LSP tools start a language server in the repo, which may run
+        // its build scripts.
+        for tool in &[
+            "run_tests",
+            "create_task",
+            "claim_task",
+            "transition_task",
+            "lsp_hover",
+            "lsp_definition",
+            "lsp_references",
+            "lsp_rename",
+            "lsp_code_actions",
+            "lsp_document_symbols",
+            "lsp_workspace_symbols",
+        ] {
             rules.insert((*tool).to_string(), RiskLevel::Medium);
         }
 
@@ -113,7 +127,12 @@ impl RiskDatabase {
     /// Defaults to `Medium` when the tool is not in the database, so unknown
     /// tools require an active task before they can execute.
     pub fn get_risk(&self, tool: &str) -> RiskLevel {
-        self.rules.get(tool).cloned().unwrap_or(RiskLevel::Medium)
+        self.lookup(tool).unwrap_or(RiskLevel::Medium)
+    }
+
+    /// Return the configured risk level, or `None` when the tool has no rule.
+    pub fn lookup(&self, tool: &str) -> Option<RiskLevel> {
+        self.rules.get(tool).cloned()
     }
 }
 
@@ -135,6 +154,13 @@ mod tests {
         assert_eq!(db.get_risk("create_task"), RiskLevel::Medium);
     }
 
+    #[test]
+    fn lsp_tools_are_medium_risk() {
+        let db = RiskDatabase::default_rules();
+        assert_eq!(db.get_risk("lsp_hover"), RiskLevel::Medium);
+        assert_eq!(db.get_risk("lsp_workspace_symbols"), RiskLevel::Medium);
+    }
+
     #[test]
     fn default_rules_high_risk() {
         let db = RiskDatabase::default_rules();
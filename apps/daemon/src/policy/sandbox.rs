@@ -58,6 +58,12 @@ pub enum PolicyViolation {
     /// Tests were run but are not passing.
     #[error("tests are failing — cannot transition to CR")]
     TestsFailing,
+
+    /// The task's patches introduced language server errors that remain.
+    #[error(
+        "{count} LSP error(s) introduced by this task (first: {first}) — cannot transition to CR"
+    )]
+    LspErrorsIntroduced { count: usize, first: String },
 }
 
 // ─── Sandbox policy ───────────────────────────────────────────────────────────
@@ -5,6 +5,10 @@
 //! recorded.  On subsequent connections the fingerprint must match — a
 //! mismatch indicates a potential supply-chain attack (e.g. the MCP server
 //! binary was replaced).
+//!
+//! The fingerprint also covers the server's environment variables and HTTP
+//! headers, so a changed `NODE_OPTIONS` or a redirected `Authorization` is a
+//! mismatch too.  Only their hash is stored — values may be secrets.
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -25,7 +29,8 @@ pub struct ServerFingerprint {
     pub command: String,
     /// Arguments passed to the command.
     pub args: Vec<String>,
-    /// SHA-256 of `command + args` (hex encoded).
+    /// SHA-256 of `command + args`, plus env and headers when set (hex
+    /// encoded).
     pub binary_hash: String,
     /// When this server was first seen.
     pub first_seen: DateTime<Utc>,
@@ -89,18 +94,20 @@ impl SupplyChainPolicy {
         }
     }
 
-    /// Verify that the given server's command matches what was previously
-    /// registered, or register it for the first time.
+    /// Verify that the given server's command and `settings` (env vars and
+    /// headers, as `(name, value)`) match what was previously registered, or
+    /// register them for the first time.
     ///
-    /// Returns `Ok(())` on success or `Err(PolicyViolation)` if the command
-    /// hash has changed since the server was last registered.
+    /// Returns `Ok(())` on success or `Err(PolicyViolation)` if the
+    /// fingerprint has changed since the server was last registered.
     pub fn verify_or_register(
         &mut self,
         server_name: &str,
         command: &str,
         args: &[&str],
+        settings: &[(&str, &str)],
     ) -> Result<(), PolicyViolation> {
-        let hash = Self::compute_command_hash(command, args);
+        let hash = Self::compute_fingerprint(command, args, settings);
         let now = Utc::now();
 
         if let Some(existing) = self.servers.get_mut(server_name) {
@@ -146,6 +153,30 @@ impl SupplyChainPolicy {
         format!("{:x}", hasher.finalize())
     }
 
+    /// `compute_command_hash` extended with `settings`, sorted so their
+    /// order does not matter.  Without settings it is the command hash, so
+    /// servers registered before settings were covered still verify.
+    pub fn compute_fingerprint(command: &str, args: &[&str], settings: &[(&str, &str)]) -> String {
+        if settings.is_empty() {
+            return Self::compute_command_hash(command, args);
+        }
+        let mut sorted = settings.to_vec();
+        sorted.sort_unstable();
+        let mut hasher = Sha256::new();
+        hasher.update(command.as_bytes());
+        for arg in args {
+            hasher.update(b"\x00");
+            hasher.update(arg.as_bytes());
+        }
+        for (name, value) in sorted {
+            hasher.update(b"\x01");
+            hasher.update(name.as_bytes());
+            hasher.update(b"=");
+            hasher.update(value.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
     /// Write the current allowlist back to disk.
     fn persist(&self) -> anyhow::Result<()> {
         if self.allowlist_path.as_os_str().is_empty() {
@@ -171,7 +202,7 @@ mod tests {
     fn first_registration_succeeds() {
         let mut policy = SupplyChainPolicy::empty();
         policy
-            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"])
+            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"], &[])
             .expect("first registration");
         assert!(policy.servers.contains_key("my-mcp"));
     }
@@ -180,10 +211,10 @@ mod tests {
     fn same_command_verifies_ok() {
         let mut policy = SupplyChainPolicy::empty();
         policy
-            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"])
+            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"], &[])
             .expect("first registration");
         policy
-            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"])
+            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"], &[])
             .expect("second verification");
     }
 
@@ -191,10 +222,11 @@ mod tests {
     fn changed_command_returns_violation() {
         let mut policy = SupplyChainPolicy::empty();
         policy
-            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"])
+            .verify_or_register("my-mcp", "/usr/bin/node", &["server.js"], &[])
             .expect("first registration");
 
-        let result = policy.verify_or_register("my-mcp", "/usr/local/bin/node", &["server.js"]);
+        let result =
+            policy.verify_or_register("my-mcp", "/usr/local/bin/node", &["server.js"], &[]);
         assert!(result.is_err());
         if let Err(PolicyViolation::SupplyChainMismatch { server, .. }) = result {
             assert_eq!(server, "my-mcp");
@@ -203,6 +235,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn changed_env_or_headers_return_violation() {
+        let mut policy = SupplyChainPolicy::empty();
+        let env = [("env:API_URL", "https://a.example")];
+        policy
+            .verify_or_register("my-mcp", "node", &["server.js"], &env)
+            .expect("first registration");
+        policy
+            .verify_or_register("my-mcp", "node", &["server.js"], &env)
+            .expect("same settings");
+        assert!(policy
+            .verify_or_register(
+                "my-mcp",
+                "node",
+                &["server.js"],
+                &[("env:API_URL", "https://b.example")]
+            )
+            .is_err());
+        assert!(policy
+            .verify_or_register("my-mcp", "node", &["server.js"], &[])
+            .is_err());
+        assert_eq!(
+            SupplyChainPolicy::compute_fingerprint("node", &["server.js"], &[]),
+            SupplyChainPolicy::compute_command_hash("node", &["server.js"])
+        );
+    }
+
     #[test]
     fn compute_hash_is_deterministic() {
         let h1 = SupplyChainPolicy::compute_command_hash("node", &["server.js"]);
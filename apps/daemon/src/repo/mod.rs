@@ -58,9 +58,9 @@ impl RepoRegistry {
 
         // Open git repo in background thread (git2 is sync)
         let canonical_clone = canonical.clone();
-        let status = tokio::task::spawn_blocking(move || {
+        let (status, head) = tokio::task::spawn_blocking(move || {
             let repo = Repository::open(&canonical_clone).context("not a git repository")?;
-            git::read_status(&repo)
+            anyhow::Ok((git::read_status(&repo)?, head_sha(&repo)))
         })
         .await??;
 
@@ -69,20 +69,58 @@ impl RepoRegistry {
         // use Handle::current() captured here (inside the async fn) to spawn tasks from it.
         let broadcaster = self.broadcaster.clone();
         let canonical_for_watcher = canonical.clone();
+        let last_head = Arc::new(std::sync::Mutex::new(head));
         let rt_handle = tokio::runtime::Handle::current();
-        let watcher = watcher::start_watcher(&canonical, move || {
+        let watcher = watcher::start_watcher(&canonical, move |paths| {
             let canonical_inner = canonical_for_watcher.clone();
             let broadcaster = broadcaster.clone();
+            let last_head = last_head.clone();
             rt_handle.spawn(async move {
+                // Keep language servers' open documents in step with disk.
+                let lsp_paths = paths.clone();
+                tokio::task::spawn_blocking(move || {
+                    crate::lsp::handlers::lsp_proxy().sync_changed_files(&lsp_paths)
+                });
+
+                let root = canonical_inner.clone();
                 let result = tokio::task::spawn_blocking(move || {
                     let repo = Repository::open(&canonical_inner)?;
-                    git::read_status(&repo)
+                    anyhow::Ok((git::read_status(&repo)?, head_sha(&repo)))
                 })
                 .await;
-                if let Ok(Ok(new_status)) = result {
+                let Ok(Ok((new_status, head))) = result else {
+                    return;
+                };
+                broadcaster.broadcast(
+                    "repo.statusChanged",
+                    serde_json::to_value(&new_status).unwrap_or_default(),
+                );
+
+                let repo_path = root.to_string_lossy().to_string();
+                let changed = working_tree_paths(&root, &paths);
+                if !changed.is_empty() {
                     broadcaster.broadcast(
-                        "repo.statusChanged",
-                        serde_json::to_value(&new_status).unwrap_or_default(),
+                        "repo.filesChanged",
+                        serde_json::json!({ "repoPath": repo_path, "paths": changed }),
+                    );
+                }
+
+                let previous = {
+                    let mut last = last_head.lock().unwrap_or_else(|e| e.into_inner());
+                    if *last == head {
+                        return;
+                    }
+                    std::mem::replace(&mut *last, head.clone())
+                };
+                if let Some(sha) = head {
+                    broadcaster.broadcast(
+                        "repo.committed",
+                        serde_json::json!({
+                            "repoPath": repo_path,
+                            "branch": new_status.branch,
+                            "sha": sha,
+                            "previousSha": previous,
+                        }),
                     );
                 }
             });
@@ -194,3 +232,21 @@ impl RepoRegistry {
         .await?
     }
 }
+
+/// The commit `HEAD` points at, or `None` for an unborn branch.
+fn head_sha(repo: &Repository) -> Option<String> {
+    repo.head()
+        .ok()
+        .and_then(|h| h.target())
+        .map(|oid| oid.to_string())
+}
+
+/// Repo-relative paths of changed working-tree files (`.git/` internals excluded).
+fn working_tree_paths(root: &std::path::Path, paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .filter_map(|p| p.strip_prefix(root).ok())
+        .filter(|rel| !rel.starts_with(".git") && !rel.as_os_str().is_empty())
+        .map(|rel| rel.to_string_lossy().to_string())
+        .collect()
+}
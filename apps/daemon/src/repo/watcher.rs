@@ -4,13 +4,17 @@ use notify_debouncer_full::{
     notify::{RecursiveMode, Watcher},
     DebounceEventResult,
 };
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tracing::warn;
 
 // ─── Basic watcher (legacy) ───────────────────────────────────────────────────
 
 /// Starts a debounced file watcher on `repo_path`.
-/// When changes are detected, triggers `on_change`.
+/// When changes are detected, triggers `on_change` with the affected paths.
 pub fn start_watcher<F>(
     repo_path: &Path,
     on_change: F,
@@ -21,13 +25,21 @@ pub fn start_watcher<F>(
     >,
 >
 where
-    F: Fn() + Send + 'static,
+    F: Fn(Vec<PathBuf>) + Send + 'static,
 {
     let mut debouncer = new_debouncer(
         Duration::from_millis(300),
         None,
         move |result: DebounceEventResult| match result {
-            Ok(_events) => on_change(),
+            Ok(events) => {
+                let mut paths: Vec<PathBuf> = events
+                    .iter()
+                    .flat_map(|e| e.paths.iter().cloned())
+                    .collect();
+                paths.sort();
+                paths.dedup();
+                on_change(paths)
+            }
             Err(errors) => {
                 for e in errors {
                     warn!(err = %e, "file watcher error");
@@ -3,43 +3,772 @@
 // Token is stored in `~/.claw/config.toml` under `[api] token = "..."`.
 // Generate with: `clawd api-token generate`
 // Header: Authorization: Bearer <token>
+// The SSE routes (EventSource cannot set headers) also take `?token=<token>`;
+// elsewhere a query token is ignored, so it does not end up in access logs.
+//
+// Accepted tokens and the scope they grant:
+//   api_token          → `api_scope` from config.toml (default: write)
+//   daemon auth_token  → admin (the same credential the WebSocket uses)
+//   scoped token       → its method globs and repos (`api_tokens`)
+//   none               → only when the daemon has no token at all (auth off,
+//                        as on the WebSocket); write, unauthenticated
+//
+// Each RPC method's scope is listed in `METHOD_SCOPES`; unlisted methods need
+// `admin`.  REST routes need `read` for GET and `write` otherwise; a scoped
+// token must also allow the RPC method the route stands for (`ROUTE_METHODS`).
+// `POST /api/v1/rpc` and `/api/v1/mcp` check each call instead.
 
 use axum::{
-    extract::{Request, State},
-    http::{header, StatusCode},
+    body::Body,
+    extract::{MatchedPath, Query, Request, State},
+    http::{header, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Json, Response},
 };
-use serde_json::json;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::api_tokens::{self, ApiToken};
+use crate::config::ApiScope;
+use crate::ipc::tokens_equal;
 use crate::AppContext;
 
+/// Largest request body buffered to check a repo-limited token.
+const MAX_CHECKED_BODY: usize = 4 * 1024 * 1024;
+
+/// What the caller's token allows — added to request extensions by
+/// `require_api_auth`.
+#[derive(Debug, Clone)]
+pub struct ApiGrant {
+    pub scope: ApiScope,
+    /// False when the request was let through without a token.
+    pub authenticated: bool,
+    /// The scoped token presented, whose limits apply on top of `scope`.
+    pub token: Option<Arc<ApiToken>>,
+}
+
+impl ApiGrant {
+    /// Whether this grant may call the JSON-RPC `method`, before any repo
+    /// limit is checked.
+    pub fn allows(&self, method: &str) -> bool {
+        method_scope(method) <= self.scope
+            && self.token.as_ref().is_none_or(|t| t.allows_method(method))
+    }
+
+    /// Check one call, including a scoped token's repo limit.  `Err`
+    /// carries the message for an `unauthorized` response.
+    pub async fn authorize(
+        &self,
+        method: &str,
+        params: &Value,
+        ctx: &AppContext,
+    ) -> Result<(), String> {
+        let needed = method_scope(method);
+        if needed > self.scope {
+            return Err(format!(
+                "Unauthorized — token lacks the '{}' scope for {method}",
+                scope_name(needed)
+            ));
+        }
+        match &self.token {
+            Some(token) => token.authorize(method, params, ctx.storage.pool()).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Scope each RPC method needs.  Every method `ipc::dispatch` routes is listed,
+/// as are the REST-only and MCP pseudo-methods; read covers methods that only
+/// look at state, write anything that changes it, starts work or calls out,
+/// and admin anything that manages the daemon, its accounts or its tokens.
+const METHOD_SCOPES: &[(&str, ApiScope)] = &[
+    ("account.list", ApiScope::Admin),
+    ("account.create", ApiScope::Admin),
+    ("account.delete", ApiScope::Admin),
+    ("account.setPriority", ApiScope::Admin),
+    ("account.history", ApiScope::Admin),
+    ("license.get", ApiScope::Read),
+    ("license.check", ApiScope::Write),
+    ("license.tier", ApiScope::Read),
+    ("daemon.ping", ApiScope::Read),
+    ("daemon.status", ApiScope::Read),
+    ("daemon.checkUpdate", ApiScope::Write),
+    ("daemon.applyUpdate", ApiScope::Admin),
+    ("daemon.updatePolicy", ApiScope::Admin),
+    ("daemon.setUpdatePolicy", ApiScope::Admin),
+    ("daemon.checkProvider", ApiScope::Write),
+    ("daemon.providers", ApiScope::Read),
+    ("daemon.changelog", ApiScope::Read),
+    ("daemon.setName", ApiScope::Admin),
+    ("daemon.pairPin", ApiScope::Admin),
+    ("repo.list", ApiScope::Read),
+    ("repo.open", ApiScope::Write),
+    ("repo.close", ApiScope::Write),
+    ("repo.status", ApiScope::Read),
+    ("repo.diff", ApiScope::Read),
+    ("repo.fileDiff", ApiScope::Read),
+    ("repo.tree", ApiScope::Read),
+    ("repo.readFile", ApiScope::Read),
+    ("repo.scan", ApiScope::Write),
+    ("repo.profile", ApiScope::Read),
+    ("repo.generateArtifacts", ApiScope::Write),
+    ("repo.syncArtifacts", ApiScope::Write),
+    ("repo.driftScore", ApiScope::Read),
+    ("repo.driftReport", ApiScope::Read),
+    ("message.pin", ApiScope::Write),
+    ("message.unpin", ApiScope::Write),
+    ("session.contextStatus", ApiScope::Read),
+    ("session.health", ApiScope::Read),
+    ("session.splitProposed", ApiScope::Write),
+    ("session.setModel", ApiScope::Write),
+    ("session.addRepoContext", ApiScope::Write),
+    ("session.listRepoContexts", ApiScope::Read),
+    ("session.removeRepoContext", ApiScope::Write),
+    ("session.create", ApiScope::Write),
+    ("session.list", ApiScope::Read),
+    ("session.get", ApiScope::Read),
+    ("session.delete", ApiScope::Write),
+    ("session.sendMessage", ApiScope::Write),
+    ("session.getMessages", ApiScope::Read),
+    ("session.search", ApiScope::Read),
+    ("session.pause", ApiScope::Write),
+    ("session.resume", ApiScope::Write),
+    ("session.cancel", ApiScope::Write),
+    ("session.setProvider", ApiScope::Write),
+    ("session.setMode", ApiScope::Write),
+    ("session.toolCallAudit", ApiScope::Read),
+    ("session.attentionMap", ApiScope::Read),
+    ("session.intentSummary", ApiScope::Read),
+    ("session.export", ApiScope::Read),
+    ("session.import", ApiScope::Write),
+    ("session.replay", ApiScope::Write),
+    ("session.share", ApiScope::Write),
+    ("session.revokeShare", ApiScope::Write),
+    ("session.shareList", ApiScope::Read),
+    ("session.trace", ApiScope::Read),
+    ("session.events", ApiScope::Read),
+    ("context.bridge", ApiScope::Write),
+    ("validators.list", ApiScope::Read),
+    ("validators.run", ApiScope::Write),
+    ("token.sessionUsage", ApiScope::Read),
+    ("token.totalUsage", ApiScope::Read),
+    ("token.budgetStatus", ApiScope::Read),
+    ("token.create", ApiScope::Admin),
+    ("token.list", ApiScope::Admin),
+    ("token.revoke", ApiScope::Admin),
+    ("events.subscribe", ApiScope::Read),
+    ("events.stream", ApiScope::Read),
+    ("tool.approve", ApiScope::Write),
+    ("tool.reject", ApiScope::Write),
+    ("tasks.list", ApiScope::Read),
+    ("tasks.get", ApiScope::Read),
+    ("tasks.claim", ApiScope::Write),
+    ("tasks.release", ApiScope::Write),
+    ("tasks.heartbeat", ApiScope::Write),
+    ("tasks.updateStatus", ApiScope::Write),
+    ("tasks.addTask", ApiScope::Write),
+    ("tasks.bulkAdd", ApiScope::Write),
+    ("tasks.logActivity", ApiScope::Write),
+    ("tasks.note", ApiScope::Write),
+    ("tasks.activity", ApiScope::Read),
+    ("tasks.fromPlanning", ApiScope::Write),
+    ("tasks.fromChecklist", ApiScope::Write),
+    ("tasks.summary", ApiScope::Read),
+    ("tasks.progressEstimate", ApiScope::Read),
+    ("tasks.export", ApiScope::Read),
+    ("tasks.validate", ApiScope::Read),
+    ("tasks.sync", ApiScope::Write),
+    ("tasks.createSpec", ApiScope::Write),
+    ("tasks.transition", ApiScope::Write),
+    ("tasks.listEvents", ApiScope::Read),
+    ("tasks.agents.register", ApiScope::Write),
+    ("tasks.agents.list", ApiScope::Read),
+    ("tasks.agents.heartbeat", ApiScope::Write),
+    ("tasks.agents.disconnect", ApiScope::Write),
+    ("agents.spawn", ApiScope::Write),
+    ("agents.list", ApiScope::Read),
+    ("agents.cancel", ApiScope::Write),
+    ("agents.heartbeat", ApiScope::Write),
+    ("afs.init", ApiScope::Write),
+    ("afs.status", ApiScope::Read),
+    ("afs.syncInstructions", ApiScope::Write),
+    ("afs.register", ApiScope::Write),
+    ("drift.scan", ApiScope::Write),
+    ("drift.list", ApiScope::Read),
+    ("standards.list", ApiScope::Read),
+    ("providers.detect", ApiScope::Write),
+    ("providers.list", ApiScope::Read),
+    ("providers.listCapabilities", ApiScope::Read),
+    ("doctor.scan", ApiScope::Admin),
+    ("doctor.fix", ApiScope::Admin),
+    ("doctor.approveRelease", ApiScope::Admin),
+    ("doctor.hookInstall", ApiScope::Admin),
+    ("traces.query", ApiScope::Read),
+    ("traces.summary", ApiScope::Read),
+    ("worktrees.create", ApiScope::Write),
+    ("worktrees.list", ApiScope::Read),
+    ("worktrees.diff", ApiScope::Read),
+    ("worktrees.commit", ApiScope::Write),
+    ("worktrees.accept", ApiScope::Write),
+    ("worktrees.reject", ApiScope::Write),
+    ("worktrees.delete", ApiScope::Write),
+    ("worktrees.merge", ApiScope::Write),
+    ("worktrees.cleanup", ApiScope::Write),
+    ("approval.list", ApiScope::Read),
+    ("approval.respond", ApiScope::Write),
+    ("plugin.list", ApiScope::Read),
+    ("plugin.enable", ApiScope::Admin),
+    ("plugin.disable", ApiScope::Admin),
+    ("plugin.info", ApiScope::Read),
+    ("mcp.status", ApiScope::Read),
+    ("mcp.listTools", ApiScope::Read),
+    ("mcp.listResources", ApiScope::Read),
+    ("mcp.getResource", ApiScope::Read),
+    ("mcp.listPrompts", ApiScope::Read),
+    ("mcp.getPrompt", ApiScope::Read),
+    ("mcp.ping", ApiScope::Read),
+    ("mcp.callTool", ApiScope::Write),
+    ("scheduler.status", ApiScope::Read),
+    ("scheduler.enqueue", ApiScope::Write),
+    ("scheduler.cancel", ApiScope::Write),
+    ("scheduler.reprioritize", ApiScope::Write),
+    ("scheduler.acquire", ApiScope::Write),
+    ("scheduler.release", ApiScope::Write),
+    ("threads.start", ApiScope::Write),
+    ("threads.resume", ApiScope::Write),
+    ("threads.fork", ApiScope::Write),
+    ("threads.list", ApiScope::Read),
+    ("system.resources", ApiScope::Read),
+    ("system.resourceHistory", ApiScope::Read),
+    ("te.phase.create", ApiScope::Write),
+    ("te.phase.list", ApiScope::Read),
+    ("te.task.create", ApiScope::Write),
+    ("te.task.get", ApiScope::Read),
+    ("te.task.list", ApiScope::Read),
+    ("te.task.transition", ApiScope::Write),
+    ("te.task.claim", ApiScope::Write),
+    ("te.agent.register", ApiScope::Write),
+    ("te.agent.heartbeat", ApiScope::Write),
+    ("te.agent.deregister", ApiScope::Write),
+    ("te.event.log", ApiScope::Write),
+    ("te.event.list", ApiScope::Read),
+    ("te.checkpoint.write", ApiScope::Write),
+    ("te.note.add", ApiScope::Write),
+    ("te.note.list", ApiScope::Read),
+    ("project.create", ApiScope::Write),
+    ("project.list", ApiScope::Read),
+    ("project.get", ApiScope::Read),
+    ("project.update", ApiScope::Write),
+    ("project.delete", ApiScope::Write),
+    ("project.addRepo", ApiScope::Write),
+    ("project.removeRepo", ApiScope::Write),
+    ("project.pulse", ApiScope::Read),
+    ("device.pair", ApiScope::Admin),
+    ("device.list", ApiScope::Admin),
+    ("device.revoke", ApiScope::Admin),
+    ("device.rename", ApiScope::Admin),
+    ("onboarding.checkAll", ApiScope::Write),
+    ("onboarding.checkProvider", ApiScope::Write),
+    ("onboarding.addApiKey", ApiScope::Admin),
+    ("onboarding.capabilities", ApiScope::Read),
+    ("onboarding.generateGci", ApiScope::Write),
+    ("onboarding.generateCodexMd", ApiScope::Write),
+    ("onboarding.generateCursorRules", ApiScope::Write),
+    ("onboarding.bootstrapAid", ApiScope::Write),
+    ("onboarding.checkAid", ApiScope::Write),
+    ("ae.plan.create", ApiScope::Write),
+    ("ae.plan.approve", ApiScope::Write),
+    ("ae.plan.get", ApiScope::Read),
+    ("ae.decision.record", ApiScope::Write),
+    ("ae.confidence.get", ApiScope::Read),
+    ("ae.recipe.list", ApiScope::Read),
+    ("ae.recipe.create", ApiScope::Write),
+    ("arena.create", ApiScope::Write),
+    ("arena.vote", ApiScope::Write),
+    ("arena.leaderboard", ApiScope::Read),
+    ("completion.suggest", ApiScope::Write),
+    ("packs.install", ApiScope::Admin),
+    ("packs.update", ApiScope::Admin),
+    ("packs.remove", ApiScope::Admin),
+    ("packs.search", ApiScope::Read),
+    ("packs.publish", ApiScope::Admin),
+    ("packs.list", ApiScope::Read),
+    ("mailbox.send", ApiScope::Write),
+    ("mailbox.list", ApiScope::Read),
+    ("mailbox.archive", ApiScope::Write),
+    ("topology.get", ApiScope::Read),
+    ("topology.validate", ApiScope::Read),
+    ("topology.addDependency", ApiScope::Write),
+    ("topology.removeDependency", ApiScope::Write),
+    ("topology.crossValidate", ApiScope::Read),
+    ("review.run", ApiScope::Write),
+    ("review.fix", ApiScope::Write),
+    ("review.learn", ApiScope::Write),
+    ("review.diffRisk", ApiScope::Read),
+    ("builder.create", ApiScope::Write),
+    ("builder.templates", ApiScope::Read),
+    ("builder.status", ApiScope::Read),
+    ("analytics.personal", ApiScope::Read),
+    ("analytics.providers", ApiScope::Read),
+    ("analytics.session", ApiScope::Read),
+    ("analytics.achievements", ApiScope::Read),
+    ("analytics.budget", ApiScope::Read),
+    ("dead_letter.list", ApiScope::Read),
+    ("dead_letter.retry", ApiScope::Write),
+    ("automation.list", ApiScope::Read),
+    ("automation.trigger", ApiScope::Write),
+    ("automation.disable", ApiScope::Write),
+    ("automation.validate", ApiScope::Read),
+    ("automation.update", ApiScope::Write),
+    ("eval.list", ApiScope::Read),
+    ("eval.run", ApiScope::Write),
+    ("task.spawn", ApiScope::Write),
+    ("task.lineage", ApiScope::Read),
+    ("task.heartbeat", ApiScope::Write),
+    ("task.expandOwnership", ApiScope::Write),
+    ("task.evidencePack", ApiScope::Read),
+    ("ghost_diff.check", ApiScope::Write),
+    ("workflow.create", ApiScope::Write),
+    ("workflow.list", ApiScope::Read),
+    ("workflow.run", ApiScope::Write),
+    ("workflow.getRun", ApiScope::Read),
+    ("workflow.delete", ApiScope::Write),
+    ("sovereignty.report", ApiScope::Read),
+    ("sovereignty.events", ApiScope::Read),
+    ("git.query", ApiScope::Read),
+    ("ci.run", ApiScope::Write),
+    ("ci.status", ApiScope::Read),
+    ("ci.cancel", ApiScope::Write),
+    ("digest.today", ApiScope::Read),
+    ("lsp.start", ApiScope::Write),
+    ("lsp.stop", ApiScope::Write),
+    ("lsp.diagnostics", ApiScope::Read),
+    ("lsp.completions", ApiScope::Read),
+    ("lsp.hover", ApiScope::Read),
+    ("lsp.definition", ApiScope::Read),
+    ("lsp.references", ApiScope::Read),
+    ("lsp.rename", ApiScope::Write),
+    ("lsp.codeActions", ApiScope::Read),
+    ("lsp.documentSymbols", ApiScope::Read),
+    ("lsp.workspaceSymbols", ApiScope::Read),
+    ("lsp.list", ApiScope::Read),
+    ("browser.screenshot", ApiScope::Write),
+    ("prompt.suggest", ApiScope::Write),
+    ("prompt.recordUsed", ApiScope::Write),
+    ("ide.extensionConnected", ApiScope::Write),
+    ("ide.editorContext", ApiScope::Write),
+    ("ide.syncSettings", ApiScope::Write),
+    ("ide.listConnections", ApiScope::Read),
+    ("ide.latestContext", ApiScope::Read),
+    ("connectivity.status", ApiScope::Read),
+    ("memory.list", ApiScope::Read),
+    ("memory.add", ApiScope::Write),
+    ("memory.remove", ApiScope::Write),
+    ("memory.update", ApiScope::Write),
+    ("metrics.list", ApiScope::Read),
+    ("metrics.summary", ApiScope::Read),
+    ("metrics.rollups", ApiScope::Read),
+    ("push.register", ApiScope::Admin),
+    ("push.unregister", ApiScope::Admin),
+    ("pack.rate", ApiScope::Write),
+    ("instructions.compile", ApiScope::Write),
+    ("instructions.explain", ApiScope::Read),
+    ("instructions.budgetReport", ApiScope::Read),
+    ("instructions.import", ApiScope::Write),
+    ("instructions.lint", ApiScope::Read),
+    ("instructions.propose", ApiScope::Write),
+    ("instructions.accept", ApiScope::Write),
+    ("instructions.dismiss", ApiScope::Write),
+    ("instructions.snapshot", ApiScope::Write),
+    ("instructions.snapshotCheck", ApiScope::Read),
+    ("instructions.doctor", ApiScope::Read),
+    ("security.analyzeContent", ApiScope::Admin),
+    ("security.testInjection", ApiScope::Admin),
+    ("policy.test", ApiScope::Admin),
+    ("policy.seedTests", ApiScope::Admin),
+    ("bench.run", ApiScope::Write),
+    ("bench.compare", ApiScope::Read),
+    ("bench.list", ApiScope::Read),
+    ("bench.seedTasks", ApiScope::Write),
+    ("artifacts.evidencePack", ApiScope::Read),
+];
+
+/// Routes that stream SSE and so may take the token as `?token=`.
+const SSE_ROUTES: &[&str] = &["/api/v1/events", "/api/v1/sessions/{id}/events"];
+
+/// The RPC method each REST route stands for, so scoped tokens' method globs
+/// apply to REST too: `(HTTP method, path below /api/v1, RPC method)`.
+const ROUTE_METHODS: &[(&str, &str, &str)] = &[
+    ("GET", "/sessions", "session.list"),
+    ("POST", "/sessions", "session.create"),
+    ("GET", "/sessions/{id}", "session.get"),
+    ("POST", "/sessions/{id}/tasks", "session.sendMessage"),
+    ("GET", "/sessions/{id}/events", "session.events"),
+    ("GET", "/tasks", "tasks.list"),
+    ("GET", "/tasks/{id}", "tasks.get"),
+    ("POST", "/tasks/{id}/claim", "tasks.claim"),
+    ("POST", "/tasks/{id}/transition", "tasks.updateStatus"),
+    ("GET", "/worktrees", "worktrees.list"),
+    ("GET", "/worktrees/{task_id}/diff", "worktrees.diff"),
+    ("POST", "/worktrees/{task_id}/merge", "worktrees.merge"),
+    ("GET", "/approvals", "approval.list"),
+    ("POST", "/approvals/{id}", "approval.respond"),
+    ("GET", "/memory", "memory.list"),
+    ("POST", "/memory", "memory.add"),
+    ("PATCH", "/memory/{id}", "memory.update"),
+    ("DELETE", "/memory/{id}", "memory.remove"),
+    ("GET", "/automations", "automation.list"),
+    ("PATCH", "/automations/{name}", "automation.update"),
+    ("POST", "/automations/{name}/trigger", "automation.trigger"),
+    ("GET", "/metrics", "metrics.summary"),
+    ("GET", "/events", "events.stream"),
+];
+
+/// RPC method for a routed REST request (`route` as matched, with the
+/// `/api/v1` prefix).
+pub fn route_method(method: &Method, route: &str) -> Option<&'static str> {
+    let path = route.strip_prefix("/api/v1")?;
+    ROUTE_METHODS
+        .iter()
+        .find(|(m, p, _)| *m == method.as_str() && *p == path)
+        .map(|(_, _, rpc)| *rpc)
+}
+
+/// Path parameters named after the RPC params that identify them, so a
+/// repo-limited token can resolve the repo a route touches.  Ids it cannot
+/// resolve (memory entries, automations) are left out; their routes are
+/// refused to repo-limited tokens.
+fn path_params(route: &str, path: &str, params: &mut Map<String, Value>) {
+    let session_route = route.starts_with("/api/v1/sessions/");
+    for (pattern, value) in route.split('/').zip(path.split('/')) {
+        let key = match pattern {
+            "{id}" if session_route => "session_id",
+            "{id}" if route.starts_with("/api/v1/tasks/") => "task_id",
+            "{id}" if route.starts_with("/api/v1/approvals/") => "approval_id",
+            "{task_id}" => "task_id",
+            _ => continue,
+        };
+        params.insert(key.to_string(), Value::String(value.to_string()));
+    }
+}
+
+/// Event namespaces that only admin-scoped clients may watch: they report on
+/// the daemon's accounts, devices, tokens and security checks.
+const ADMIN_EVENT_NAMESPACES: &[&str] = &["account", "device", "push", "security", "token"];
+
+/// Scope needed to receive a broadcast event.  Events name what happened,
+/// not an RPC method, so they have their own (namespace) table.
+pub fn event_scope(event: &str) -> ApiScope {
+    let namespace = event.split('.').next().unwrap_or(event);
+    if ADMIN_EVENT_NAMESPACES.contains(&namespace) {
+        ApiScope::Admin
+    } else {
+        ApiScope::Read
+    }
+}
+
+/// Whether the daemon routes `method`: it has a `METHOD_SCOPES` entry or is a
+/// plugin method (`plugin.<name>.<method>`).
+pub fn is_known_method(method: &str) -> bool {
+    METHOD_SCOPES.iter().any(|(m, _)| *m == method)
+        || (method.starts_with("plugin.") && method.matches('.').count() >= 2)
+}
+
+/// Scope a JSON-RPC method needs (`METHOD_SCOPES`).  Plugin methods need
+/// `write`; anything unknown needs `admin`, so a method added without an
+/// entry stays out of reach of scoped tokens.
+pub fn method_scope(method: &str) -> ApiScope {
+    if let Some((_, scope)) = METHOD_SCOPES.iter().find(|(m, _)| *m == method) {
+        return *scope;
+    }
+    if is_known_method(method) {
+        return ApiScope::Write;
+    }
+    ApiScope::Admin
+}
+
 pub async fn require_api_auth(
     State(ctx): State<Arc<AppContext>>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Response {
-    // Extract Bearer token from Authorization header
+    // Extract Bearer token from Authorization header, then `?token=` on SSE
+    // routes.
+    let sse_route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|p| SSE_ROUTES.contains(&p.as_str()));
     let token = req
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.strip_prefix("Bearer "));
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| {
+            req.uri()
+                .query()
+                .filter(|_| sse_route)?
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+        });
+
+    let api_token = ctx.config.api_token.as_deref().filter(|t| !t.is_empty());
+    let scoped = match token {
+        Some(t) => api_tokens::authenticate(ctx.storage.pool(), t).await,
+        None => None,
+    };
 
-    let expected = ctx.config.api_token.as_deref().unwrap_or("");
+    let grant = match token {
+        Some(t) if api_token.is_some_and(|expected| tokens_equal(t, expected)) => ApiGrant {
+            scope: ctx.config.api_scope,
+            authenticated: true,
+            token: None,
+        },
+        Some(t) if !ctx.auth_token.is_empty() && tokens_equal(t, &ctx.auth_token) => ApiGrant {
+            scope: ApiScope::Admin,
+            authenticated: true,
+            token: None,
+        },
+        // Method globs limit a scoped token further; it never gets admin.
+        _ if scoped.is_some() => ApiGrant {
+            scope: ApiScope::Write,
+            authenticated: true,
+            token: scoped.map(Arc::new),
+        },
+        // Auth disabled: the daemon runs without any token.
+        _ if api_token.is_none() && ctx.auth_token.is_empty() => ApiGrant {
+            scope: ApiScope::Write,
+            authenticated: false,
+            token: None,
+        },
+        _ => return unauthorized("Invalid or missing API token"),
+    };
 
-    if expected.is_empty() {
-        // Auth disabled — allow all (not recommended in production)
-        return next.run(req).await;
+    // The RPC bridge and the MCP endpoint check scopes per call.
+    if !matches!(req.uri().path(), "/api/v1/rpc" | "/api/v1/mcp") {
+        let needed = if req.method() == Method::GET || req.method() == Method::HEAD {
+            ApiScope::Read
+        } else {
+            ApiScope::Write
+        };
+        if needed > grant.scope {
+            return forbidden(needed);
+        }
+        if let Some(token) = grant.token.clone() {
+            req = match check_scoped_route(&token, req, &ctx).await {
+                Ok(req) => req,
+                Err(resp) => return resp,
+            };
+        }
     }
 
-    match token {
-        Some(t) if t == expected => next.run(req).await,
-        _ => (
-            StatusCode::UNAUTHORIZED,
-            Json(json!({ "error": "Invalid or missing API token" })),
-        )
-            .into_response(),
+    req.extensions_mut().insert(grant);
+    next.run(req).await
+}
+
+/// Check a REST request against a scoped token: the route's RPC method must
+/// match its globs and, for a repo-limited token, the query, path and JSON
+/// body must name an allowed repo, without naming a key twice differently.  Hands back the request (with its body
+/// restored if it was read).
+async fn check_scoped_route(
+    token: &ApiToken,
+    req: Request,
+    ctx: &AppContext,
+) -> Result<Request, Response> {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let Some(method) = route_method(req.method(), &route) else {
+        return Err(forbidden_message(&format!(
+            "Token '{}' may not call this endpoint",
+            token.name
+        )));
+    };
+    if token.repos.is_empty() {
+        return match token
+            .authorize(method, &Value::Null, ctx.storage.pool())
+            .await
+        {
+            Ok(()) => Ok(req),
+            Err(msg) => Err(forbidden_message(&msg)),
+        };
+    }
+
+    let mut params = Map::new();
+    if let Ok(Query(query)) = Query::<HashMap<String, String>>::try_from_uri(req.uri()) {
+        params.extend(query.into_iter().map(|(k, v)| (k, Value::String(v))));
+    }
+    path_params(&route, req.uri().path(), &mut params);
+
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_CHECKED_BODY)
+        .await
+        .map_err(|_| forbidden_message("Request body too large to check against the token"))?;
+    // The handler reads each field from one place; a key given twice with
+    // different values could pass the check with one and act on the other.
+    if let Ok(Value::Object(fields)) = serde_json::from_slice::<Value>(&bytes) {
+        for (k, v) in fields {
+            match params.get(&k) {
+                Some(seen) if *seen != v => {
+                    return Err(forbidden_message(&format!(
+                        "Conflicting values for '{k}' in the request"
+                    )))
+                }
+                Some(_) => {}
+                None => {
+                    params.insert(k, v);
+                }
+            }
+        }
+    }
+
+    match token
+        .authorize(method, &Value::Object(params), ctx.storage.pool())
+        .await
+    {
+        Ok(()) => Ok(Request::from_parts(parts, Body::from(bytes))),
+        Err(msg) => Err(forbidden_message(&msg)),
+    }
+}
+
+fn unauthorized(message: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({ "error": message }))).into_response()
+}
+
+fn forbidden(needed: ApiScope) -> Response {
+    forbidden_message(&format!(
+        "API token lacks the '{}' scope",
+        scope_name(needed)
+    ))
+}
+
+fn forbidden_message(message: &str) -> Response {
+    (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response()
+}
+
+pub fn scope_name(scope: ApiScope) -> &'static str {
+    match scope {
+        ApiScope::Read => "read",
+        ApiScope::Write => "write",
+        ApiScope::Admin => "admin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods_map_to_scopes() {
+        assert_eq!(method_scope("tasks.list"), ApiScope::Read);
+        assert_eq!(method_scope("session.getMessages"), ApiScope::Read);
+        assert_eq!(method_scope("lsp.workspaceSymbols"), ApiScope::Read);
+        assert_eq!(method_scope("tasks.claim"), ApiScope::Write);
+        assert_eq!(method_scope("session.sendMessage"), ApiScope::Write);
+        assert_eq!(method_scope("account.list"), ApiScope::Admin);
+        assert_eq!(method_scope("daemon.applyUpdate"), ApiScope::Admin);
+        assert_eq!(method_scope("daemon.status"), ApiScope::Read);
+        for m in [
+            "license.check",
+            "daemon.checkUpdate",
+            "onboarding.checkAll",
+            "onboarding.checkProvider",
+            "onboarding.checkAid",
+            "ghost_diff.check",
+        ] {
+            assert_eq!(method_scope(m), ApiScope::Write, "{m}");
+        }
+        for m in [
+            "policy.test",
+            "policy.seedTests",
+            "security.analyzeContent",
+            "security.testInjection",
+            "doctor.scan",
+            "daemon.updatePolicy",
+        ] {
+            assert_eq!(method_scope(m), ApiScope::Admin, "{m}");
+        }
+        assert_eq!(method_scope("plugin.hello.greet"), ApiScope::Write);
+        assert_eq!(method_scope("daemon.somethingNew"), ApiScope::Admin);
+        assert_eq!(event_scope("task.updated"), ApiScope::Read);
+        assert_eq!(event_scope("account.limited"), ApiScope::Admin);
+    }
+
+    #[test]
+    fn every_dispatched_method_has_a_scope() {
+        let source = include_str!("../ipc/mod.rs");
+        let dispatch = &source[source.find("async fn dispatch(").unwrap()..];
+        let mut seen = 0;
+        for line in dispatch.lines() {
+            let Some(arm) = line.trim().strip_prefix('"') else {
+                continue;
+            };
+            let Some((method, _)) = arm.split_once("\" =>") else {
+                continue;
+            };
+            seen += 1;
+            let entries = METHOD_SCOPES.iter().filter(|(m, _)| *m == method).count();
+            assert_eq!(entries, 1, "{method} needs exactly one METHOD_SCOPES entry");
+        }
+        assert!(seen > 200, "only found {seen} dispatch arms");
+    }
+
+    #[test]
+    fn grants_include_lower_scopes() {
+        let write = ApiGrant {
+            scope: ApiScope::Write,
+            authenticated: true,
+            token: None,
+        };
+        assert!(write.allows("memory.list"));
+        assert!(write.allows("memory.add"));
+        assert!(!write.allows("device.revoke"));
+        assert!(!write.allows("token.list"));
+    }
+
+    #[test]
+    fn every_protected_route_maps_to_an_rpc_method() {
+        for op in crate::rest::openapi::operations() {
+            if op.public || op.path == "/rpc" || op.path == "/mcp" {
+                continue;
+            }
+            let method = Method::from_bytes(op.method.to_uppercase().as_bytes()).unwrap();
+            let route = format!("/api/v1{}", op.path);
+            assert!(
+                route_method(&method, &route).is_some(),
+                "{} {} has no entry in ROUTE_METHODS",
+                op.method,
+                op.path
+            );
+        }
+    }
+
+    #[test]
+    fn path_params_name_sessions_tasks_and_approvals() {
+        let mut params = Map::new();
+        path_params(
+            "/api/v1/sessions/{id}/tasks",
+            "/api/v1/sessions/s1/tasks",
+            &mut params,
+        );
+        path_params(
+            "/api/v1/worktrees/{task_id}/diff",
+            "/api/v1/worktrees/t1/diff",
+            &mut params,
+        );
+        path_params(
+            "/api/v1/approvals/{id}",
+            "/api/v1/approvals/a1",
+            &mut params,
+        );
+        assert_eq!(params["session_id"], "s1");
+        assert_eq!(params["task_id"], "t1");
+        assert_eq!(params["approval_id"], "a1");
+
+        let mut params = Map::new();
+        path_params("/api/v1/memory/{id}", "/api/v1/memory/m1", &mut params);
+        assert!(params.is_empty());
     }
 }
@@ -0,0 +1,151 @@
+// rest/error.rs — HTTP error mapping for REST routes.
+//
+// REST routes mostly bridge to the JSON-RPC handlers, which report failures
+// as `anyhow` errors carrying the same markers `ipc::classify_error` reads.
+// `ApiError` turns those markers into HTTP status codes so REST clients get
+// 404/409/422 instead of a blanket 500.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+
+use crate::tasks::storage::{
+    MISSING_COMPLETION_NOTES, TASK_ALREADY_CLAIMED, TASK_NOT_FOUND, TASK_NOT_RESUMABLE,
+};
+
+pub type ApiResult<T = Json<serde_json::Value>> = Result<T, ApiError>;
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        let msg = e.to_string();
+        let task_code = |code: i32| msg.contains(&format!("TASK_CODE:{code}"));
+
+        // Task system markers carry a numeric code, not a readable message.
+        if task_code(TASK_NOT_FOUND) {
+            return Self::not_found("Task not found");
+        }
+        if task_code(TASK_ALREADY_CLAIMED) {
+            return Self::new(
+                StatusCode::CONFLICT,
+                "Task already claimed by another agent",
+            );
+        }
+        if task_code(TASK_NOT_RESUMABLE) {
+            return Self::new(
+                StatusCode::CONFLICT,
+                "Task cannot be resumed — not in interrupted or pending state",
+            );
+        }
+        if task_code(MISSING_COMPLETION_NOTES) {
+            return Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Completion notes are required when marking a task done",
+            );
+        }
+
+        let status = status_for(&msg);
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::error!(err = %e, "REST handler failed");
+            return Self::new(status, "Internal error");
+        }
+        Self::new(status, msg)
+    }
+}
+
+/// Status for an error message from a JSON-RPC handler.
+fn status_for(msg: &str) -> StatusCode {
+    if msg.contains("taskCompletionBlocked") {
+        return StatusCode::UNPROCESSABLE_ENTITY;
+    }
+    if msg.contains("worktreeNotMerged")
+        || msg.contains("has conflicts")
+        || msg.contains("PROVIDER_NOT_AVAILABLE")
+        || msg.contains("SESSION_BUSY")
+    {
+        return StatusCode::CONFLICT;
+    }
+    if msg.contains("SESSION_NOT_FOUND")
+        || msg.contains("REPO_NOT_FOUND")
+        || msg.contains("not found")
+    {
+        return StatusCode::NOT_FOUND;
+    }
+    if msg.contains("INVALID_PARAMS")
+        || msg.starts_with("missing")
+        || msg.contains("is required")
+        || msg.contains("requires")
+        || msg.starts_with("invalid")
+    {
+        return StatusCode::BAD_REQUEST;
+    }
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(e: anyhow::Error) -> StatusCode {
+        ApiError::from(e).status
+    }
+
+    #[test]
+    fn rpc_markers_map_to_http_statuses() {
+        let not_found = anyhow::anyhow!("TASK_CODE:{}", TASK_NOT_FOUND);
+        assert_eq!(status(not_found), StatusCode::NOT_FOUND);
+        let claimed = anyhow::anyhow!("TASK_CODE:{}", TASK_ALREADY_CLAIMED);
+        assert_eq!(status(claimed), StatusCode::CONFLICT);
+        let notes = anyhow::anyhow!("TASK_CODE:{}", MISSING_COMPLETION_NOTES);
+        assert_eq!(status(notes), StatusCode::UNPROCESSABLE_ENTITY);
+        let approval = anyhow::anyhow!("approval 'a1' not found or already resolved");
+        assert_eq!(status(approval), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(anyhow::anyhow!("missing field: task_id")),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(anyhow::anyhow!(
+                "merge of b has conflicts — resolve manually"
+            )),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn unknown_errors_hide_their_message() {
+        let e = ApiError::from(anyhow::anyhow!("disk I/O error at /home/me/db"));
+        assert_eq!(e.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(e.message, "Internal error");
+    }
+}
@@ -0,0 +1,311 @@
+// rest/mcp.rs — MCP over Streamable HTTP (POST/GET/DELETE /api/v1/mcp).
+//
+// POST carries one JSON-RPC message or a batch:
+//   - a lone `initialize` starts a session; the response's `Mcp-Session-Id`
+//     header must be sent on every later request (400 without it, 404 once
+//     the session is gone — the client should initialize again);
+//   - notifications and client responses only → 202;
+//   - requests → `text/event-stream` when the client accepts it, carrying
+//     progress notifications and then each response, otherwise JSON.
+// GET opens the session's standalone SSE stream, which carries
+// `notifications/resources/updated` for subscribed resources; a newer GET
+// replaces the older stream.
+// DELETE ends the session and aborts its running calls.
+//
+// A session belongs to the token that initialized it: requests carrying its
+// id with any other token get 403, and its tools act as that token's agent.
+//
+// Each request is checked against the caller's `ApiGrant` as an RPC method
+// (`mcp_rpc_method`), so scoped tokens need `mcp.*`-style globs and
+// `tools/call` needs `write`.  Like `/rpc`, the endpoint always needs a token
+// when the daemon has one.  The session itself lives in `mcp::server`.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    Extension,
+};
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+
+use super::auth::ApiGrant;
+use super::rpc::MAX_BATCH;
+use crate::ipc::{error_value, INVALID_REQUEST, PARSE_ERROR, UNAUTHORIZED};
+use crate::mcp::{McpMessage, McpSession, McpSessions};
+use crate::AppContext;
+
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+const FOREIGN_SESSION: &str = "MCP session was started with a different token";
+
+/// The RPC method an MCP request counts as for token scopes and globs.
+pub fn mcp_rpc_method(method: &str) -> &'static str {
+    match method {
+        "tools/call" => "mcp.callTool",
+        "tools/list" => "mcp.listTools",
+        "resources/list" | "resources/templates/list" => "mcp.listResources",
+        "resources/read" | "resources/subscribe" | "resources/unsubscribe" => "mcp.getResource",
+        "prompts/list" => "mcp.listPrompts",
+        "prompts/get" => "mcp.getPrompt",
+        _ => "mcp.ping",
+    }
+}
+
+pub async fn mcp_post(
+    State(ctx): State<Arc<AppContext>>,
+    Extension(grant): Extension<ApiGrant>,
+    Extension(sessions): Extension<Arc<McpSessions>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_value(Value::Null, PARSE_ERROR, "Parse error")),
+            )
+                .into_response()
+        }
+    };
+    let messages = match payload {
+        Value::Array(items) if items.is_empty() || items.len() > MAX_BATCH => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_value(Value::Null, INVALID_REQUEST, "Invalid Request")),
+            )
+                .into_response()
+        }
+        Value::Array(items) => items,
+        single => vec![single],
+    };
+
+    if let [msg] = messages.as_slice() {
+        if msg.get("method").and_then(Value::as_str) == Some("initialize") {
+            return initialize(ctx, &grant, &sessions, msg.clone()).await;
+        }
+    }
+
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Missing Mcp-Session-Id header — send initialize first",
+        );
+    };
+    let Some(session) = sessions.get(session_id) else {
+        return error_response(StatusCode::NOT_FOUND, "Unknown or expired MCP session");
+    };
+    if !session.started_by(grant.token.as_deref()) {
+        return error_response(StatusCode::FORBIDDEN, FOREIGN_SESSION);
+    }
+
+    // Notifications run now; client responses need nothing from us.
+    let mut requests = Vec::new();
+    let (notify, rx) = mpsc::unbounded_channel::<String>();
+    for raw in messages {
+        let Ok(msg) = serde_json::from_value::<McpMessage>(raw.clone()) else {
+            if raw.get("result").is_none() && raw.get("error").is_none() {
+                let id = raw.get("id").cloned().unwrap_or(Value::Null);
+                requests.push(Err(error_value(id, INVALID_REQUEST, "Invalid Request")));
+            }
+            continue;
+        };
+        if msg.id.is_none() {
+            session.handle(msg, &notify).await;
+            continue;
+        }
+        let method = mcp_rpc_method(&msg.method);
+        let params = msg
+            .params
+            .as_ref()
+            .and_then(|p| p.get("arguments"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        match grant.authorize(method, &params, &ctx).await {
+            Ok(()) => requests.push(Ok(msg)),
+            Err(denied) => requests.push(Err(error_value(
+                msg.id.unwrap_or(Value::Null),
+                UNAUTHORIZED,
+                &denied,
+            ))),
+        }
+    }
+    if requests.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let wants_stream = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    let run = |req: Result<McpMessage, Value>, notify: mpsc::UnboundedSender<String>| {
+        let session = Arc::clone(&session);
+        async move {
+            match req {
+                Ok(msg) => session
+                    .handle(msg, &notify)
+                    .await
+                    .and_then(|resp| serde_json::to_value(resp).ok()),
+                Err(denied) => Some(denied),
+            }
+        }
+    };
+
+    if wants_stream {
+        // Progress notifications and responses share one channel; the
+        // stream ends once every call has answered and dropped its sender.
+        for req in requests {
+            let call = run(req, notify.clone());
+            let out = notify.clone();
+            tokio::spawn(async move {
+                if let Some(resp) = call.await {
+                    let _ = out.send(resp.to_string());
+                }
+            });
+        }
+        drop(notify);
+        let stream = UnboundedReceiverStream::new(rx)
+            .map(|data| Ok::<_, Infallible>(Event::default().event("message").data(data)));
+        return with_session(Sse::new(stream).into_response(), session.id());
+    }
+
+    drop(rx);
+    let batch = requests.len() > 1;
+    let mut responses: Vec<Value> = join_all(requests.into_iter().map(|r| run(r, notify.clone())))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    let resp = match responses.len() {
+        0 => StatusCode::ACCEPTED.into_response(),
+        1 if !batch => Json(responses.remove(0)).into_response(),
+        _ => Json(Value::Array(responses)).into_response(),
+    };
+    with_session(resp, session.id())
+}
+
+/// Start a session and answer its `initialize`.
+async fn initialize(
+    ctx: Arc<AppContext>,
+    grant: &ApiGrant,
+    sessions: &McpSessions,
+    raw: Value,
+) -> Response {
+    let msg = match serde_json::from_value::<McpMessage>(raw) {
+        Ok(msg) if msg.id.is_some() => msg,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_value(Value::Null, INVALID_REQUEST, "Invalid Request")),
+            )
+                .into_response()
+        }
+    };
+    let id = msg.id.unwrap_or(Value::Null);
+    if let Err(denied) = grant
+        .authorize(mcp_rpc_method("initialize"), &Value::Null, &ctx)
+        .await
+    {
+        return Json(error_value(id, UNAUTHORIZED, &denied)).into_response();
+    }
+    let (session, response) = McpSession::initialize(ctx, id, msg.params, grant.token.clone());
+    let session = sessions.insert(session);
+    with_session(Json(response).into_response(), session.id())
+}
+
+pub async fn mcp_get(
+    State(ctx): State<Arc<AppContext>>,
+    Extension(grant): Extension<ApiGrant>,
+    Extension(sessions): Extension<Arc<McpSessions>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header");
+    };
+    let Some(session) = sessions.get(session_id) else {
+        return error_response(StatusCode::NOT_FOUND, "Unknown or expired MCP session");
+    };
+    if !session.started_by(grant.token.as_deref()) {
+        return error_response(StatusCode::FORBIDDEN, FOREIGN_SESSION);
+    }
+    if let Err(denied) = grant
+        .authorize(mcp_rpc_method("resources/subscribe"), &Value::Null, &ctx)
+        .await
+    {
+        return error_response(StatusCode::FORBIDDEN, &denied);
+    }
+    let stream = UnboundedReceiverStream::new(session.open_stream())
+        .map(|data| Ok::<_, Infallible>(Event::default().event("message").data(data)));
+    let sse = Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("ping"),
+    );
+    with_session(sse.into_response(), session.id())
+}
+
+pub async fn mcp_delete(
+    Extension(grant): Extension<ApiGrant>,
+    Extension(sessions): Extension<Arc<McpSessions>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header");
+    };
+    if let Some(session) = sessions.get(session_id) {
+        if !session.started_by(grant.token.as_deref()) {
+            return error_response(StatusCode::FORBIDDEN, FOREIGN_SESSION);
+        }
+    }
+    if sessions.remove(session_id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        error_response(StatusCode::NOT_FOUND, "Unknown or expired MCP session")
+    }
+}
+
+fn with_session(mut resp: Response, session_id: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(session_id) {
+        resp.headers_mut().insert(SESSION_HEADER, value);
+    }
+    resp
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ApiScope;
+    use crate::rest::auth::method_scope;
+
+    #[test]
+    fn only_tool_calls_need_write() {
+        assert_eq!(method_scope(mcp_rpc_method("tools/call")), ApiScope::Write);
+        for m in [
+            "initialize",
+            "ping",
+            "tools/list",
+            "resources/list",
+            "resources/read",
+            "resources/subscribe",
+            "prompts/list",
+            "prompts/get",
+        ] {
+            assert_eq!(method_scope(mcp_rpc_method(m)), ApiScope::Read, "{m}");
+        }
+    }
+}
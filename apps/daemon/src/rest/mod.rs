@@ -3,31 +3,53 @@
 // Axum HTTP server on port 4301 (local only unless relay is enabled).
 // Bridges REST calls to the internal JSON-RPC handlers.
 //
-// Endpoints:
-//   GET  /api/v1/sessions
-//   POST /api/v1/sessions
-//   GET  /api/v1/sessions/{id}
-//   POST /api/v1/sessions/{id}/tasks
-//   GET  /api/v1/sessions/{id}/events   (SSE)
-//   GET  /api/v1/metrics
-//   GET  /api/v1/memory
-//   GET  /api/v1/openapi.json
-//   GET  /api/v1/health
+// Endpoints (all under /api/v1; see `openapi::operations` for the full list):
+//   GET    /health                         (no auth)
+//   GET    /openapi.json                   (no auth)
+//   GET    /sessions                POST /sessions
+//   GET    /sessions/{id}           POST /sessions/{id}/tasks
+//   GET    /sessions/{id}/events   (SSE)
+//   GET    /tasks                   GET  /tasks/{id}
+//   POST   /tasks/{id}/claim        POST /tasks/{id}/transition
+//   GET    /worktrees               GET  /worktrees/{task_id}/diff
+//   POST   /worktrees/{task_id}/merge
+//   GET    /approvals               POST /approvals/{id}
+//   GET    /memory                  POST /memory
+//   PATCH  /memory/{id}             DELETE /memory/{id}
+//   GET    /automations             PATCH /automations/{name}
+//   POST   /automations/{name}/trigger
+//   GET    /metrics
+//   GET    /events                 (SSE, filterable, resumable)
+//   POST   /rpc                    JSON-RPC 2.0 bridge (single or batch)
+//   POST   /mcp    GET /mcp        DELETE /mcp   MCP over Streamable HTTP
+//
+// List endpoints take `?limit=&offset=` (see `pagination`).  Errors are
+// `{ "error": "..." }` with a status from `error::ApiError`.  Scopes and
+// tokens are checked in `auth`; `/rpc` and `/mcp` check them per call (see
+// `rpc`, `mcp`).
 
 pub mod auth;
+pub mod error;
+pub mod mcp;
 pub mod openapi;
+pub mod pagination;
 pub mod routes;
+pub mod rpc;
 pub mod sse;
 
 use anyhow::Result;
 use axum::{
-    routing::{get, post},
-    Router,
+    http::{StatusCode, Uri},
+    middleware,
+    routing::{get, patch, post},
+    Extension, Json, Router,
 };
+use serde_json::{json, Value};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing::info;
 
+use crate::mcp::McpSessions;
 use crate::AppContext;
 
 pub const REST_PORT: u16 = 4301;
@@ -45,25 +67,98 @@ pub async fn start_rest_server(ctx: Arc<AppContext>) -> Result<()> {
 }
 
 pub fn build_router(ctx: Arc<AppContext>) -> Router {
-    Router::new()
+    let public = Router::new()
         // Health (no auth)
         .route("/api/v1/health", get(routes::health::health))
         // OpenAPI spec (no auth)
-        .route("/api/v1/openapi.json", get(openapi::openapi_spec))
+        .route("/api/v1/openapi.json", get(openapi::openapi_spec));
+
+    let protected = Router::new()
         // Sessions
         .route(
             "/api/v1/sessions",
             get(routes::sessions::list_sessions).post(routes::sessions::create_session),
         )
-        .route("/api/v1/sessions/:id", get(routes::sessions::get_session))
+        .route("/api/v1/sessions/{id}", get(routes::sessions::get_session))
         .route(
-            "/api/v1/sessions/:id/tasks",
+            "/api/v1/sessions/{id}/tasks",
             post(routes::sessions::submit_task),
         )
-        .route("/api/v1/sessions/:id/events", get(sse::session_events_sse))
+        .route("/api/v1/sessions/{id}/events", get(sse::session_events_sse))
+        // Daemon-wide event stream (SSE)
+        .route("/api/v1/events", get(sse::events_sse))
+        // Tasks
+        .route("/api/v1/tasks", get(routes::tasks::list_tasks))
+        .route("/api/v1/tasks/{id}", get(routes::tasks::get_task))
+        .route("/api/v1/tasks/{id}/claim", post(routes::tasks::claim_task))
+        .route(
+            "/api/v1/tasks/{id}/transition",
+            post(routes::tasks::transition_task),
+        )
+        // Worktrees
+        .route("/api/v1/worktrees", get(routes::worktrees::list_worktrees))
+        .route(
+            "/api/v1/worktrees/{task_id}/diff",
+            get(routes::worktrees::worktree_diff),
+        )
+        .route(
+            "/api/v1/worktrees/{task_id}/merge",
+            post(routes::worktrees::merge_worktree),
+        )
+        // Approvals
+        .route("/api/v1/approvals", get(routes::approvals::list_approvals))
+        .route(
+            "/api/v1/approvals/{id}",
+            post(routes::approvals::respond_approval),
+        )
         // Metrics
         .route("/api/v1/metrics", get(routes::metrics::get_metrics))
+        // JSON-RPC bridge
+        .route("/api/v1/rpc", post(rpc::rpc_bridge))
+        // MCP server (Streamable HTTP)
+        .route(
+            "/api/v1/mcp",
+            post(mcp::mcp_post)
+                .get(mcp::mcp_get)
+                .delete(mcp::mcp_delete),
+        )
         // Memory
-        .route("/api/v1/memory", get(routes::memory::list_memory))
+        .route(
+            "/api/v1/memory",
+            get(routes::memory::list_memory).post(routes::memory::create_memory),
+        )
+        .route(
+            "/api/v1/memory/{id}",
+            patch(routes::memory::update_memory).delete(routes::memory::delete_memory),
+        )
+        // Automations
+        .route(
+            "/api/v1/automations",
+            get(routes::automations::list_automations),
+        )
+        .route(
+            "/api/v1/automations/{name}",
+            patch(routes::automations::update_automation),
+        )
+        .route(
+            "/api/v1/automations/{name}/trigger",
+            post(routes::automations::trigger_automation),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            ctx.clone(),
+            auth::require_api_auth,
+        ));
+
+    public
+        .merge(protected)
+        .fallback(no_such_endpoint)
+        .layer(Extension(Arc::new(McpSessions::new())))
         .with_state(ctx)
 }
+
+async fn no_such_endpoint(uri: Uri) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("No such endpoint: {}", uri.path()) })),
+    )
+}
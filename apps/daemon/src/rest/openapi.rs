@@ -2,16 +2,327 @@
 //
 // Returns the ClawDE REST API spec as JSON at GET /api/v1/openapi.json.
 // Used by the @clawde/rest SDK codegen and API docs.
+//
+// The `paths` section is generated from `operations()` — one entry per
+// route in `build_router`.  Path parameters come from the `{name}` segments,
+// list operations get `limit`/`offset` and a `pagination` object, and every
+// authenticated operation documents 401.  `tests/rest_api_test.rs` checks
+// that each operation listed here is actually routed.
 
 use axum::{extract::State, Json};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::sync::Arc;
 
 use super::REST_PORT;
 use crate::AppContext;
 
+// ─── Operations ───────────────────────────────────────────────────────────────
+
+/// One documented REST operation.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    /// Lower-case HTTP method (`get`, `post`, `patch`, `delete`).
+    pub method: &'static str,
+    /// Path below `/api/v1`, with `{param}` segments as routed.
+    pub path: &'static str,
+    pub operation_id: &'static str,
+    pub summary: &'static str,
+    /// Served without a bearer token.
+    pub public: bool,
+    /// Query parameters other than pagination (all strings).
+    pub query: &'static [&'static str],
+    /// Component schema name of the JSON request body.
+    pub body: Option<&'static str>,
+    pub body_required: bool,
+    /// `(items key, item schema)` for paginated list responses.
+    pub list: Option<(&'static str, &'static str)>,
+    /// Success status and description.
+    pub success: (u16, &'static str),
+    /// `(key, schema)` of the object wrapped in a success response; an empty
+    /// key means the schema is the whole response.
+    pub returns: Option<(&'static str, &'static str)>,
+    pub errors: &'static [u16],
+}
+
+impl Operation {
+    fn new(
+        method: &'static str,
+        path: &'static str,
+        operation_id: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            operation_id,
+            summary,
+            public: false,
+            query: &[],
+            body: None,
+            body_required: false,
+            list: None,
+            success: (200, "OK"),
+            returns: None,
+            errors: &[],
+        }
+    }
+
+    fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    fn with_query(mut self, params: &'static [&'static str]) -> Self {
+        self.query = params;
+        self
+    }
+
+    fn with_body(mut self, schema: &'static str) -> Self {
+        self.body = Some(schema);
+        self.body_required = true;
+        self
+    }
+
+    fn with_optional_body(mut self, schema: &'static str) -> Self {
+        self.body = Some(schema);
+        self.body_required = false;
+        self
+    }
+
+    fn with_list(mut self, key: &'static str, schema: &'static str) -> Self {
+        self.list = Some((key, schema));
+        self
+    }
+
+    fn with_success(mut self, status: u16, description: &'static str) -> Self {
+        self.success = (status, description);
+        self
+    }
+
+    fn with_returns(mut self, key: &'static str, schema: &'static str) -> Self {
+        self.returns = Some((key, schema));
+        self
+    }
+
+    fn with_errors(mut self, errors: &'static [u16]) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Names of the `{param}` path segments.
+    pub fn path_params(&self) -> impl Iterator<Item = &'static str> {
+        self.path
+            .split('/')
+            .filter_map(|seg| seg.strip_prefix('{')?.strip_suffix('}'))
+    }
+}
+
+/// Every route served under `/api/v1`.
+pub fn operations() -> Vec<Operation> {
+    use Operation as Op;
+    vec![
+        Op::new("get", "/health", "getHealth", "Daemon health check").public(),
+        Op::new("get", "/openapi.json", "getOpenApi", "This specification").public(),
+        // Sessions
+        Op::new("get", "/sessions", "listSessions", "List active sessions"),
+        Op::new("post", "/sessions", "createSession", "Create a new session")
+            .with_body("CreateSessionRequest")
+            .with_errors(&[400]),
+        Op::new("get", "/sessions/{id}", "getSession", "Get session status")
+            .with_returns("", "Session")
+            .with_errors(&[404]),
+        Op::new(
+            "post",
+            "/sessions/{id}/tasks",
+            "submitTask",
+            "Submit a task to a session",
+        )
+        .with_body("SubmitTaskRequest")
+        .with_errors(&[404, 409]),
+        Op::new(
+            "get",
+            "/sessions/{id}/events",
+            "sessionEvents",
+            "SSE stream of session events",
+        ),
+        // Tasks
+        Op::new("get", "/tasks", "listTasks", "List tasks")
+            .with_query(&[
+                "repo_path",
+                "status",
+                "agent",
+                "severity",
+                "phase",
+                "tag",
+                "search",
+            ])
+            .with_list("tasks", "Task"),
+        Op::new("get", "/tasks/{id}", "getTask", "Get a task")
+            .with_returns("task", "Task")
+            .with_errors(&[404]),
+        Op::new(
+            "post",
+            "/tasks/{id}/claim",
+            "claimTask",
+            "Claim a task for an agent",
+        )
+        .with_body("ClaimTaskRequest")
+        .with_returns("task", "Task")
+        .with_errors(&[404, 409]),
+        Op::new(
+            "post",
+            "/tasks/{id}/transition",
+            "transitionTask",
+            "Move a task to a new status",
+        )
+        .with_body("TransitionTaskRequest")
+        .with_returns("task", "Task")
+        .with_errors(&[400, 404, 409, 422]),
+        // Worktrees
+        Op::new("get", "/worktrees", "listWorktrees", "List task worktrees")
+            .with_list("worktrees", "Worktree"),
+        Op::new(
+            "get",
+            "/worktrees/{task_id}/diff",
+            "getWorktreeDiff",
+            "Uncommitted diff of a task worktree",
+        )
+        .with_errors(&[404]),
+        Op::new(
+            "post",
+            "/worktrees/{task_id}/merge",
+            "mergeWorktree",
+            "Merge a task worktree into its base branch",
+        )
+        .with_errors(&[404, 409]),
+        // Approvals
+        Op::new(
+            "get",
+            "/approvals",
+            "listApprovals",
+            "List pending approval requests",
+        )
+        .with_query(&["repo_path"])
+        .with_list("approvals", "Approval"),
+        Op::new(
+            "post",
+            "/approvals/{id}",
+            "respondApproval",
+            "Grant or deny an approval",
+        )
+        .with_body("RespondApprovalRequest")
+        .with_errors(&[400, 404]),
+        // Memory
+        Op::new("get", "/memory", "listMemory", "List memory entries")
+            .with_query(&["scope", "repo_path"])
+            .with_list("entries", "MemoryEntry"),
+        Op::new(
+            "post",
+            "/memory",
+            "createMemory",
+            "Add or replace a memory entry",
+        )
+        .with_body("CreateMemoryRequest")
+        .with_success(201, "Created")
+        .with_returns("entry", "MemoryEntry")
+        .with_errors(&[400]),
+        Op::new(
+            "patch",
+            "/memory/{id}",
+            "updateMemory",
+            "Update a memory entry's value or weight",
+        )
+        .with_body("UpdateMemoryRequest")
+        .with_returns("entry", "MemoryEntry")
+        .with_errors(&[404]),
+        Op::new(
+            "delete",
+            "/memory/{id}",
+            "deleteMemory",
+            "Delete a memory entry",
+        )
+        .with_success(204, "Deleted")
+        .with_errors(&[404]),
+        // Automations
+        Op::new("get", "/automations", "listAutomations", "List automations")
+            .with_list("automations", "Automation"),
+        Op::new(
+            "patch",
+            "/automations/{name}",
+            "updateAutomation",
+            "Enable or disable an automation",
+        )
+        .with_body("UpdateAutomationRequest")
+        .with_errors(&[404]),
+        Op::new(
+            "post",
+            "/automations/{name}/trigger",
+            "triggerAutomation",
+            "Fire an automation now",
+        )
+        .with_optional_body("TriggerAutomationRequest")
+        .with_success(202, "Triggered")
+        .with_errors(&[404]),
+        // Metrics
+        Op::new("get", "/metrics", "getMetrics", "24h metrics summary")
+            .with_returns("", "MetricsSummary"),
+        // Events
+        Op::new(
+            "get",
+            "/events",
+            "streamEvents",
+            "SSE stream of daemon events, resumable with Last-Event-ID",
+        )
+        .with_query(&["types", "session_ids", "repo_path", "last_event_id"]),
+        // JSON-RPC bridge
+        Op::new(
+            "post",
+            "/rpc",
+            "callRpc",
+            "Call JSON-RPC methods (single request or batch)",
+        )
+        .with_body("RpcPayload")
+        .with_returns("", "RpcPayloadResponse"),
+        // MCP server
+        Op::new(
+            "post",
+            "/mcp",
+            "postMcp",
+            "Send MCP messages over Streamable HTTP (Mcp-Session-Id after initialize)",
+        )
+        .with_body("RpcPayload")
+        .with_returns("", "RpcPayloadResponse")
+        .with_errors(&[400, 404]),
+        Op::new(
+            "get",
+            "/mcp",
+            "streamMcpSession",
+            "SSE stream of server notifications for an MCP session (resource updates)",
+        )
+        .with_errors(&[400, 404]),
+        Op::new("delete", "/mcp", "deleteMcpSession", "End an MCP session")
+            .with_success(204, "Session ended")
+            .with_errors(&[400, 404]),
+    ]
+}
+
+// ─── Spec ─────────────────────────────────────────────────────────────────────
+
 pub async fn openapi_spec(State(_ctx): State<Arc<AppContext>>) -> Json<Value> {
-    Json(json!({
+    Json(spec())
+}
+
+/// The full OpenAPI document.
+pub fn spec() -> Value {
+    let mut paths = Map::new();
+    for op in operations() {
+        let entry = paths
+            .entry(op.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        entry[op.method] = operation_json(&op);
+    }
+
+    json!({
         "openapi": "3.1.0",
         "info": {
             "title": "ClawDE REST API",
@@ -32,130 +343,310 @@ pub async fn openapi_spec(State(_ctx): State<Arc<AppContext>>) -> Json<Value> {
                     "description": "API token from `~/.claw/config.toml` [api] section."
                 }
             },
-            "schemas": {
-                "Session": {
-                    "type": "object",
-                    "properties": {
-                        "id": { "type": "string" },
-                        "status": { "type": "string", "enum": ["idle", "running", "paused", "completed", "error"] },
-                        "provider": { "type": "string" },
-                        "repo_path": { "type": "string" },
-                        "created_at": { "type": "integer", "description": "Unix timestamp" }
-                    }
-                },
-                "MetricsSummary": {
-                    "type": "object",
-                    "properties": {
-                        "total_tokens_in": { "type": "integer" },
-                        "total_tokens_out": { "type": "integer" },
-                        "total_tool_calls": { "type": "integer" },
-                        "total_cost_usd": { "type": "number" },
-                        "session_count": { "type": "integer" }
-                    }
-                },
-                "MemoryEntry": {
-                    "type": "object",
-                    "properties": {
-                        "id": { "type": "string" },
-                        "scope": { "type": "string" },
-                        "key": { "type": "string" },
-                        "value": { "type": "string" },
-                        "weight": { "type": "integer", "minimum": 1, "maximum": 10 },
-                        "source": { "type": "string" }
-                    }
-                }
+            "schemas": schemas()
+        },
+        "paths": paths
+    })
+}
+
+fn operation_json(op: &Operation) -> Value {
+    let tag = op.path.split('/').nth(1).unwrap_or("daemon");
+    let mut parameters: Vec<Value> = op
+        .path_params()
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    parameters.extend(
+        op.query
+            .iter()
+            .map(|name| json!({ "name": name, "in": "query", "schema": { "type": "string" } })),
+    );
+    if op.list.is_some() {
+        parameters.push(json!({
+            "name": "limit", "in": "query",
+            "schema": { "type": "integer", "minimum": 1, "maximum": super::pagination::MAX_LIMIT, "default": super::pagination::DEFAULT_LIMIT }
+        }));
+        parameters.push(json!({
+            "name": "offset", "in": "query",
+            "schema": { "type": "integer", "minimum": 0, "default": 0 }
+        }));
+    }
+
+    let (status, description) = op.success;
+    let mut success = json!({ "description": description });
+    let body_schema = match (op.list, op.returns) {
+        (Some((key, item)), _) => Some(json!({
+            "type": "object",
+            "properties": {
+                key: { "type": "array", "items": schema_ref(item) },
+                "pagination": schema_ref("Pagination")
             }
+        })),
+        (None, Some(("", schema))) => Some(schema_ref(schema)),
+        (None, Some((key, schema))) => Some(json!({
+            "type": "object",
+            "properties": { key: schema_ref(schema) }
+        })),
+        (None, None)
+            if op.path.ends_with("/events") || (op.path == "/mcp" && op.method == "get") =>
+        {
+            success["content"] = json!({ "text/event-stream": { "schema": { "type": "string" } } });
+            None
+        }
+        (None, None) if status != 204 => Some(json!({ "type": "object" })),
+        (None, None) => None,
+    };
+    if let Some(schema) = body_schema {
+        success["content"] = json!({ "application/json": { "schema": schema } });
+    }
+
+    let mut responses = Map::new();
+    responses.insert(status.to_string(), success);
+    let auth_error: &[u16] = if op.public { &[] } else { &[401, 403] };
+    for code in op.errors.iter().chain(auth_error) {
+        responses.insert(
+            code.to_string(),
+            json!({
+                "description": error_description(*code),
+                "content": { "application/json": { "schema": schema_ref("Error") } }
+            }),
+        );
+    }
+
+    let mut out = json!({
+        "operationId": op.operation_id,
+        "summary": op.summary,
+        "tags": [tag],
+        "responses": responses,
+    });
+    if !parameters.is_empty() {
+        out["parameters"] = Value::Array(parameters);
+    }
+    if let Some(body) = op.body {
+        out["requestBody"] = json!({
+            "required": op.body_required,
+            "content": { "application/json": { "schema": schema_ref(body) } }
+        });
+    }
+    if op.public {
+        out["security"] = json!([]);
+    }
+    out
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn error_description(code: u16) -> &'static str {
+    match code {
+        400 => "Invalid request",
+        401 => "Missing or invalid API token",
+        403 => "API token lacks the scope this operation needs",
+        404 => "Not found",
+        409 => "Conflicts with the current state",
+        422 => "Rejected by a completion check",
+        _ => "Error",
+    }
+}
+
+fn schemas() -> Value {
+    json!({
+        "Error": {
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": { "type": "string" } }
         },
-        "paths": {
-            "/health": {
-                "get": {
-                    "operationId": "getHealth",
-                    "summary": "Daemon health check",
-                    "security": [],
-                    "responses": { "200": { "description": "Daemon is healthy" } }
-                }
-            },
-            "/sessions": {
-                "get": {
-                    "operationId": "listSessions",
-                    "summary": "List active sessions",
-                    "responses": { "200": { "description": "Session list" } }
+        "Pagination": {
+            "type": "object",
+            "properties": {
+                "limit": { "type": "integer" },
+                "offset": { "type": "integer" },
+                "next_offset": { "type": ["integer", "null"], "description": "Offset of the next page; null on the last page" },
+                "total": { "type": "integer", "description": "Present when the full list size is known" }
+            }
+        },
+        "Session": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "status": { "type": "string", "enum": ["idle", "running", "paused", "completed", "error"] },
+                "provider": { "type": "string" },
+                "repo_path": { "type": "string" },
+                "created_at": { "type": "integer", "description": "Unix timestamp" }
+            }
+        },
+        "CreateSessionRequest": {
+            "type": "object",
+            "properties": {
+                "provider": { "type": "string" },
+                "repo_path": { "type": "string" }
+            }
+        },
+        "SubmitTaskRequest": {
+            "type": "object",
+            "required": ["content"],
+            "properties": { "content": { "type": "string" } }
+        },
+        "MetricsSummary": {
+            "type": "object",
+            "properties": {
+                "total_tokens_in": { "type": "integer" },
+                "total_tokens_out": { "type": "integer" },
+                "total_tool_calls": { "type": "integer" },
+                "total_cost_usd": { "type": "number" },
+                "session_count": { "type": "integer" }
+            }
+        },
+        "Task": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "title": { "type": "string" },
+                "status": {
+                    "type": "string",
+                    "enum": ["pending", "in_progress", "in_cr", "in_qa", "blocked", "interrupted", "done", "deferred"]
                 },
-                "post": {
-                    "operationId": "createSession",
-                    "summary": "Create a new session",
-                    "requestBody": {
-                        "required": true,
-                        "content": {
-                            "application/json": {
-                                "schema": {
-                                    "type": "object",
-                                    "properties": {
-                                        "provider": { "type": "string" },
-                                        "repo_path": { "type": "string" }
-                                    }
-                                }
-                            }
-                        }
-                    },
-                    "responses": { "200": { "description": "Created session" } }
-                }
-            },
-            "/sessions/{id}": {
-                "get": {
-                    "operationId": "getSession",
-                    "summary": "Get session status",
-                    "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
-                    "responses": { "200": { "description": "Session details" } }
-                }
-            },
-            "/sessions/{id}/tasks": {
-                "post": {
-                    "operationId": "submitTask",
-                    "summary": "Submit a task to a session",
-                    "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
-                    "requestBody": {
-                        "required": true,
-                        "content": {
-                            "application/json": {
-                                "schema": {
-                                    "type": "object",
-                                    "required": ["content"],
-                                    "properties": { "content": { "type": "string" } }
-                                }
-                            }
-                        }
-                    },
-                    "responses": { "200": { "description": "Task submitted" } }
-                }
-            },
-            "/sessions/{id}/events": {
-                "get": {
-                    "operationId": "sessionEvents",
-                    "summary": "SSE stream of session events",
-                    "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
-                    "responses": {
-                        "200": {
-                            "description": "SSE event stream",
-                            "content": { "text/event-stream": { "schema": { "type": "string" } } }
-                        }
-                    }
-                }
-            },
-            "/metrics": {
-                "get": {
-                    "operationId": "getMetrics",
-                    "summary": "24h metrics summary",
-                    "responses": { "200": { "description": "Metrics summary" } }
-                }
-            },
-            "/memory": {
-                "get": {
-                    "operationId": "listMemory",
-                    "summary": "List memory entries",
-                    "responses": { "200": { "description": "Memory entries" } }
+                "severity": { "type": ["string", "null"] },
+                "phase": { "type": ["string", "null"] },
+                "claimed_by": { "type": ["string", "null"] },
+                "repo_path": { "type": "string" },
+                "notes": { "type": ["string", "null"] },
+                "updated_at": { "type": "integer", "description": "Unix timestamp" }
+            }
+        },
+        "ClaimTaskRequest": {
+            "type": "object",
+            "required": ["agent_id"],
+            "properties": { "agent_id": { "type": "string" } }
+        },
+        "TransitionTaskRequest": {
+            "type": "object",
+            "required": ["status"],
+            "properties": {
+                "status": { "type": "string" },
+                "notes": { "type": "string", "description": "Required when status is done" },
+                "block_reason": { "type": "string" },
+                "agent_id": { "type": "string" },
+                "modified_files": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "Worktree": {
+            "type": "object",
+            "properties": {
+                "task_id": { "type": "string" },
+                "worktree_path": { "type": "string" },
+                "branch": { "type": "string" },
+                "repo_path": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "status": { "type": "string" }
+            }
+        },
+        "Approval": {
+            "type": "object",
+            "properties": {
+                "approval_id": { "type": "string" },
+                "task_id": { "type": "string" },
+                "title": { "type": "string" },
+                "agent_id": { "type": "string" },
+                "tool_name": { "type": "string" },
+                "arguments": {},
+                "risk_level": { "type": "string" },
+                "repo_path": { "type": "string" },
+                "requested_at": { "type": "integer", "description": "Unix timestamp" }
+            }
+        },
+        "RespondApprovalRequest": {
+            "type": "object",
+            "required": ["decision"],
+            "properties": {
+                "decision": { "type": "string", "enum": ["grant", "deny"] },
+                "reason": { "type": "string" }
+            }
+        },
+        "MemoryEntry": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "scope": { "type": "string" },
+                "key": { "type": "string" },
+                "value": { "type": "string" },
+                "weight": { "type": "integer", "minimum": 1, "maximum": 10 },
+                "source": { "type": "string" }
+            }
+        },
+        "CreateMemoryRequest": {
+            "type": "object",
+            "required": ["key", "value"],
+            "properties": {
+                "scope": { "type": "string", "description": "\"global\" (default) or a project scope" },
+                "repo_path": { "type": "string", "description": "Use this repo's project scope" },
+                "key": { "type": "string" },
+                "value": { "type": "string" },
+                "weight": { "type": "integer", "minimum": 1, "maximum": 10 },
+                "source": { "type": "string" }
+            }
+        },
+        "UpdateMemoryRequest": {
+            "type": "object",
+            "properties": {
+                "value": { "type": "string" },
+                "weight": { "type": "integer", "minimum": 1, "maximum": 10 }
+            }
+        },
+        "Automation": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "enabled": { "type": "boolean" },
+                "trigger": { "type": "string" },
+                "schedule": { "type": ["string", "null"] },
+                "action": { "type": "string" },
+                "builtin": { "type": "boolean" }
+            }
+        },
+        "UpdateAutomationRequest": {
+            "type": "object",
+            "required": ["enabled"],
+            "properties": { "enabled": { "type": "boolean" } }
+        },
+        "RpcRequest": {
+            "type": "object",
+            "required": ["jsonrpc", "method"],
+            "properties": {
+                "jsonrpc": { "const": "2.0" },
+                "id": { "type": ["string", "integer", "null"], "description": "Omit for a notification" },
+                "method": { "type": "string" },
+                "params": {}
+            }
+        },
+        "RpcResponse": {
+            "type": "object",
+            "properties": {
+                "jsonrpc": { "const": "2.0" },
+                "id": { "type": ["string", "integer", "null"] },
+                "result": {},
+                "error": {
+                    "type": "object",
+                    "properties": { "code": { "type": "integer" }, "message": { "type": "string" } }
                 }
             }
+        },
+        "RpcPayload": {
+            "oneOf": [
+                schema_ref("RpcRequest"),
+                { "type": "array", "items": schema_ref("RpcRequest"), "minItems": 1, "maxItems": super::rpc::MAX_BATCH }
+            ]
+        },
+        "RpcPayloadResponse": {
+            "oneOf": [
+                schema_ref("RpcResponse"),
+                { "type": "array", "items": schema_ref("RpcResponse") }
+            ]
+        },
+        "TriggerAutomationRequest": {
+            "type": "object",
+            "properties": { "session_id": { "type": "string" } }
         }
-    }))
+    })
 }
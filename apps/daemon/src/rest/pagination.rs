@@ -0,0 +1,105 @@
+// rest/pagination.rs — `?limit=&offset=` paging for REST list endpoints.
+//
+// Every list response carries a `pagination` object:
+//   { limit, offset, next_offset, total? }
+// `next_offset` is null on the last page.  `total` is present when the whole
+// list was in memory anyway.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 200;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+
+    /// Page of a list that was loaded in full.
+    pub fn slice<T>(&self, items: Vec<T>) -> (Vec<T>, Value) {
+        let total = items.len();
+        let (limit, offset) = (self.limit(), self.offset());
+        let page: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+        let next = (offset + page.len() < total).then_some(offset + page.len());
+        let meta = json!({
+            "limit": limit,
+            "offset": offset,
+            "next_offset": next,
+            "total": total,
+        });
+        (page, meta)
+    }
+
+    /// Page of rows fetched with `limit() + 1` — the extra row only tells
+    /// whether another page exists.
+    pub fn from_probe<T>(&self, mut rows: Vec<T>) -> (Vec<T>, Value) {
+        let (limit, offset) = (self.limit(), self.offset());
+        let more = rows.len() > limit;
+        rows.truncate(limit);
+        let meta = json!({
+            "limit": limit,
+            "offset": offset,
+            "next_offset": more.then_some(offset + limit),
+        });
+        (rows, meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slice_reports_next_offset_until_the_end() {
+        let q = PageQuery {
+            limit: Some(2),
+            offset: Some(1),
+        };
+        let (page, meta) = q.slice(vec![1, 2, 3, 4]);
+        assert_eq!(page, vec![2, 3]);
+        assert_eq!(meta["next_offset"], 3);
+        assert_eq!(meta["total"], 4);
+
+        let q = PageQuery {
+            limit: Some(2),
+            offset: Some(3),
+        };
+        let (page, meta) = q.slice(vec![1, 2, 3, 4]);
+        assert_eq!(page, vec![4]);
+        assert!(meta["next_offset"].is_null());
+    }
+
+    #[test]
+    fn probe_row_is_dropped() {
+        let q = PageQuery {
+            limit: Some(2),
+            offset: None,
+        };
+        let (page, meta) = q.from_probe(vec!['a', 'b', 'c']);
+        assert_eq!(page, vec!['a', 'b']);
+        assert_eq!(meta["next_offset"], 2);
+        let (_, meta) = q.from_probe(vec!['a']);
+        assert!(meta["next_offset"].is_null());
+    }
+
+    #[test]
+    fn limit_is_clamped() {
+        let q = PageQuery {
+            limit: Some(10_000),
+            offset: None,
+        };
+        assert_eq!(q.limit(), MAX_LIMIT);
+        assert_eq!(PageQuery::default().limit(), DEFAULT_LIMIT);
+    }
+}
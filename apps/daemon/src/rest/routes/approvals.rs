@@ -0,0 +1,64 @@
+// rest/routes/approvals.rs — Human-approval REST routes.
+//
+//   GET  /api/v1/approvals         approval.list (paginated)
+//   POST /api/v1/approvals/{id}    approval.respond
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::ipc::handlers::approval;
+use crate::rest::error::ApiResult;
+use crate::rest::pagination::PageQuery;
+use crate::AppContext;
+
+#[derive(Deserialize)]
+pub struct ApprovalQuery {
+    /// Only this repo's approvals.
+    pub repo_path: Option<String>,
+}
+
+pub async fn list_approvals(
+    State(ctx): State<Arc<AppContext>>,
+    Query(q): Query<ApprovalQuery>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult {
+    let params = json!({
+        "repo_path": q.repo_path,
+        "limit": page.limit() + 1,
+        "offset": page.offset(),
+    });
+    let mut result = approval::list(params, &ctx).await?;
+    let rows = match result["approvals"].take() {
+        Value::Array(items) => items,
+        _ => Vec::new(),
+    };
+    let (items, pagination) = page.from_probe(rows);
+    Ok(Json(
+        json!({ "approvals": items, "pagination": pagination }),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct RespondRequest {
+    pub decision: String,
+    pub reason: Option<String>,
+}
+
+pub async fn respond_approval(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<String>,
+    Json(body): Json<RespondRequest>,
+) -> ApiResult {
+    let params = json!({
+        "approval_id": id,
+        "decision": body.decision,
+        "reason": body.reason,
+    });
+    let result = approval::respond(params, &ctx).await?;
+    Ok(Json(result))
+}
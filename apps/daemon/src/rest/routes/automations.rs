@@ -0,0 +1,65 @@
+// rest/routes/automations.rs — Automation REST routes.
+//
+//   GET   /api/v1/automations                  automation.list (paginated)
+//   PATCH /api/v1/automations/{name}           enable / disable
+//   POST  /api/v1/automations/{name}/trigger   automation.trigger → 202
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::ipc::handlers::automations;
+use crate::rest::error::ApiResult;
+use crate::rest::pagination::PageQuery;
+use crate::AppContext;
+
+pub async fn list_automations(
+    State(ctx): State<Arc<AppContext>>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult {
+    let mut result = automations::list(json!({}), &ctx).await?;
+    let all = match result["automations"].take() {
+        Value::Array(items) => items,
+        _ => Vec::new(),
+    };
+    let (items, pagination) = page.slice(all);
+    Ok(Json(
+        json!({ "automations": items, "pagination": pagination }),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAutomationRequest {
+    pub enabled: bool,
+}
+
+pub async fn update_automation(
+    State(ctx): State<Arc<AppContext>>,
+    Path(name): Path<String>,
+    Json(body): Json<UpdateAutomationRequest>,
+) -> ApiResult {
+    let params = json!({ "name": name, "enabled": body.enabled });
+    let result = automations::disable(params, &ctx).await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize, Default)]
+pub struct TriggerRequest {
+    pub session_id: Option<String>,
+}
+
+pub async fn trigger_automation(
+    State(ctx): State<Arc<AppContext>>,
+    Path(name): Path<String>,
+    body: Option<Json<TriggerRequest>>,
+) -> ApiResult<(StatusCode, Json<Value>)> {
+    let Json(body) = body.unwrap_or_default();
+    let params = json!({ "name": name, "sessionId": body.session_id });
+    let result = automations::trigger(params, &ctx).await?;
+    Ok((StatusCode::ACCEPTED, Json(result)))
+}
@@ -1,42 +1,135 @@
-// rest/routes/memory.rs — GET /api/v1/memory (Sprint QQ RA.5).
+// rest/routes/memory.rs — Memory REST routes (Sprint QQ RA.5).
+//
+//   GET    /api/v1/memory          list (paginated)
+//   POST   /api/v1/memory          memory.add → 201
+//   PATCH  /api/v1/memory/{id}     update value / weight
+//   DELETE /api/v1/memory/{id}     memory.remove → 204
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::memory::store::{AddMemoryRequest, MemoryEntry};
+use crate::memory::MemoryStore;
+use crate::rest::error::{ApiError, ApiResult};
+use crate::rest::pagination::PageQuery;
 use crate::AppContext;
 
 #[derive(Deserialize)]
 pub struct MemoryQuery {
     pub scope: Option<String>,
+    /// Project scope for this repo (plus global entries).
+    pub repo_path: Option<String>,
 }
 
 pub async fn list_memory(
     State(ctx): State<Arc<AppContext>>,
     Query(q): Query<MemoryQuery>,
-) -> Json<Value> {
-    let scope = q.scope.as_deref().unwrap_or("global");
-    match ctx.memory_store.list(scope).await {
-        Ok(entries) => {
-            let list: Vec<Value> = entries
-                .iter()
-                .map(|e| {
-                    json!({
-                        "id": e.id,
-                        "scope": e.scope,
-                        "key": e.key,
-                        "value": e.value,
-                        "weight": e.weight,
-                        "source": e.source,
-                    })
-                })
-                .collect();
-            Json(json!({ "entries": list }))
+    Query(page): Query<PageQuery>,
+) -> ApiResult {
+    let entries = match q.repo_path.as_deref() {
+        Some(repo) => {
+            ctx.memory_store
+                .list_all(&MemoryStore::project_scope(repo))
+                .await?
         }
-        Err(e) => Json(json!({ "error": e.to_string() })),
+        None => {
+            ctx.memory_store
+                .list(q.scope.as_deref().unwrap_or("global"))
+                .await?
+        }
+    };
+    let (entries, pagination) = page.slice(entries);
+    let list: Vec<Value> = entries.iter().map(entry_json).collect();
+    Ok(Json(json!({ "entries": list, "pagination": pagination })))
+}
+
+#[derive(Deserialize)]
+pub struct CreateMemoryRequest {
+    pub scope: Option<String>,
+    pub repo_path: Option<String>,
+    pub key: String,
+    pub value: String,
+    pub weight: Option<i64>,
+    pub source: Option<String>,
+}
+
+pub async fn create_memory(
+    State(ctx): State<Arc<AppContext>>,
+    Json(body): Json<CreateMemoryRequest>,
+) -> ApiResult<(StatusCode, Json<Value>)> {
+    let scope = match body.repo_path.as_deref() {
+        Some(repo) => MemoryStore::project_scope(repo),
+        None => body.scope.unwrap_or_else(|| "global".to_string()),
+    };
+    let entry = ctx
+        .memory_store
+        .upsert(AddMemoryRequest {
+            scope,
+            key: body.key,
+            value: body.value,
+            weight: body.weight,
+            source: body.source,
+        })
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "entry": entry_json(&entry) })),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMemoryRequest {
+    pub value: Option<String>,
+    pub weight: Option<i64>,
+}
+
+pub async fn update_memory(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateMemoryRequest>,
+) -> ApiResult {
+    let existing = ctx
+        .memory_store
+        .get_by_id(&id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Memory entry not found"))?;
+    let entry = ctx
+        .memory_store
+        .upsert(AddMemoryRequest {
+            scope: existing.scope,
+            key: existing.key,
+            value: body.value.unwrap_or(existing.value),
+            weight: Some(body.weight.unwrap_or(existing.weight)),
+            source: Some(existing.source),
+        })
+        .await?;
+    Ok(Json(json!({ "entry": entry_json(&entry) })))
+}
+
+pub async fn delete_memory(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<String>,
+) -> ApiResult<StatusCode> {
+    if ctx.memory_store.remove(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("Memory entry not found"))
     }
 }
+
+fn entry_json(e: &MemoryEntry) -> Value {
+    json!({
+        "id": e.id,
+        "scope": e.scope,
+        "key": e.key,
+        "value": e.value,
+        "weight": e.weight,
+        "source": e.source,
+    })
+}
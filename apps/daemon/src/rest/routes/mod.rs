@@ -1,4 +1,8 @@
+pub mod approvals;
+pub mod automations;
 pub mod health;
 pub mod memory;
 pub mod metrics;
 pub mod sessions;
+pub mod tasks;
+pub mod worktrees;
@@ -2,13 +2,13 @@
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::rest::error::{ApiError, ApiResult};
 use crate::AppContext;
 
 pub async fn list_sessions(State(ctx): State<Arc<AppContext>>) -> Json<Value> {
@@ -28,10 +28,7 @@ pub async fn list_sessions(State(ctx): State<Arc<AppContext>>) -> Json<Value> {
     Json(json!({ "sessions": list }))
 }
 
-pub async fn get_session(
-    State(ctx): State<Arc<AppContext>>,
-    Path(id): Path<String>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+pub async fn get_session(State(ctx): State<Arc<AppContext>>, Path(id): Path<String>) -> ApiResult {
     match ctx.session_manager.get(&id).await {
         Ok(s) => Ok(Json(json!({
             "id": s.id,
@@ -41,10 +38,7 @@ pub async fn get_session(
             "created_at": s.created_at,
             "model_override": s.model_override,
         }))),
-        Err(_) => Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Session not found" })),
-        )),
+        Err(_) => Err(ApiError::not_found("Session not found")),
     }
 }
 
@@ -57,19 +51,14 @@ pub struct CreateSessionRequest {
 pub async fn create_session(
     State(ctx): State<Arc<AppContext>>,
     Json(body): Json<CreateSessionRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> ApiResult {
     let params = json!({
         "provider": body.provider.unwrap_or_else(|| "claude".to_string()),
         "repo_path": body.repo_path.unwrap_or_default(),
     });
 
-    match crate::ipc::handlers::session::create(params, &ctx).await {
-        Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": e.to_string() })),
-        )),
-    }
+    let result = crate::ipc::handlers::session::create(params, &ctx).await?;
+    Ok(Json(result))
 }
 
 #[derive(Deserialize)]
@@ -81,18 +70,13 @@ pub async fn submit_task(
     State(ctx): State<Arc<AppContext>>,
     Path(session_id): Path<String>,
     Json(body): Json<SubmitTaskRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> ApiResult {
     let params = json!({
         "session_id": session_id,
         "content": body.content,
         "role": "user",
     });
 
-    match crate::ipc::handlers::session::send_message(params, &ctx).await {
-        Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": e.to_string() })),
-        )),
-    }
+    let result = crate::ipc::handlers::session::send_message(params, &ctx).await?;
+    Ok(Json(result))
 }
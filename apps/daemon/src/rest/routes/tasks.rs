@@ -0,0 +1,101 @@
+// rest/routes/tasks.rs — Task REST routes.
+//
+//   GET  /api/v1/tasks                    list (filters + pagination)
+//   GET  /api/v1/tasks/{id}               get
+//   POST /api/v1/tasks/{id}/claim         tasks.claim
+//   POST /api/v1/tasks/{id}/transition    tasks.updateStatus
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::rest::error::ApiResult;
+use crate::rest::pagination::PageQuery;
+use crate::tasks::storage::TaskListParams;
+use crate::AppContext;
+
+#[derive(Deserialize)]
+pub struct TaskFilter {
+    pub repo_path: Option<String>,
+    pub status: Option<String>,
+    pub agent: Option<String>,
+    pub severity: Option<String>,
+    pub phase: Option<String>,
+    pub tag: Option<String>,
+    pub search: Option<String>,
+}
+
+pub async fn list_tasks(
+    State(ctx): State<Arc<AppContext>>,
+    Query(filter): Query<TaskFilter>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult {
+    let rows = ctx
+        .task_storage
+        .list_tasks(&TaskListParams {
+            repo_path: filter.repo_path,
+            status: filter.status,
+            agent: filter.agent,
+            severity: filter.severity,
+            phase: filter.phase,
+            tag: filter.tag,
+            search: filter.search,
+            limit: Some(page.limit() as i64 + 1),
+            offset: Some(page.offset() as i64),
+        })
+        .await?;
+    let (tasks, pagination) = page.from_probe(rows);
+    Ok(Json(json!({ "tasks": tasks, "pagination": pagination })))
+}
+
+pub async fn get_task(State(ctx): State<Arc<AppContext>>, Path(id): Path<String>) -> ApiResult {
+    let result = crate::ipc::handlers::tasks::get(json!({ "task_id": id }), &ctx).await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct ClaimRequest {
+    pub agent_id: String,
+}
+
+pub async fn claim_task(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<String>,
+    Json(body): Json<ClaimRequest>,
+) -> ApiResult {
+    let params = json!({ "task_id": id, "agent_id": body.agent_id });
+    let result = crate::ipc::handlers::tasks::claim(params, &ctx).await?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct TransitionRequest {
+    pub status: String,
+    pub notes: Option<String>,
+    pub block_reason: Option<String>,
+    pub agent_id: Option<String>,
+    pub modified_files: Option<Vec<String>>,
+}
+
+pub async fn transition_task(
+    State(ctx): State<Arc<AppContext>>,
+    Path(id): Path<String>,
+    Json(body): Json<TransitionRequest>,
+) -> ApiResult {
+    let mut params = json!({
+        "task_id": id,
+        "status": body.status,
+        "notes": body.notes,
+        "block_reason": body.block_reason,
+        "agent_id": body.agent_id.unwrap_or_else(|| "rest-api".to_string()),
+    });
+    if let Some(files) = body.modified_files {
+        params["modified_files"] = Value::from(files);
+    }
+    let result = crate::ipc::handlers::tasks::update_status(params, &ctx).await?;
+    Ok(Json(result))
+}
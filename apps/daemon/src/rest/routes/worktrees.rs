@@ -0,0 +1,48 @@
+// rest/routes/worktrees.rs — Task worktree REST routes.
+//
+//   GET  /api/v1/worktrees                    worktrees.list (paginated)
+//   GET  /api/v1/worktrees/{task_id}/diff     worktrees.diff
+//   POST /api/v1/worktrees/{task_id}/merge    worktrees.merge
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::ipc::handlers::worktrees;
+use crate::rest::error::ApiResult;
+use crate::rest::pagination::PageQuery;
+use crate::AppContext;
+
+pub async fn list_worktrees(
+    State(ctx): State<Arc<AppContext>>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult {
+    let mut result = worktrees::list(json!({}), &ctx).await?;
+    let all = match result["worktrees"].take() {
+        Value::Array(items) => items,
+        _ => Vec::new(),
+    };
+    let (items, pagination) = page.slice(all);
+    Ok(Json(
+        json!({ "worktrees": items, "pagination": pagination }),
+    ))
+}
+
+pub async fn worktree_diff(
+    State(ctx): State<Arc<AppContext>>,
+    Path(task_id): Path<String>,
+) -> ApiResult {
+    let result = worktrees::diff(json!({ "task_id": task_id }), &ctx).await?;
+    Ok(Json(result))
+}
+
+pub async fn merge_worktree(
+    State(ctx): State<Arc<AppContext>>,
+    Path(task_id): Path<String>,
+) -> ApiResult {
+    let result = worktrees::merge(json!({ "task_id": task_id }), &ctx).await?;
+    Ok(Json(result))
+}
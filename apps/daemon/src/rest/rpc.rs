@@ -0,0 +1,125 @@
+// rest/rpc.rs — JSON-RPC 2.0 over HTTP (POST /api/v1/rpc).
+//
+// Accepts a single request object or a batch array and runs each call
+// through the same handler table as the WebSocket (`ipc::dispatch_call`).
+//
+// - Every call is checked against the caller's `ApiGrant`; a call above the
+//   token's scope, or outside a scoped token's methods or repos, gets an
+//   `unauthorized` (-32004) error, not an HTTP 403, so the rest of the batch
+//   still runs.
+// - Runs of consecutive read-scope calls in a batch execute concurrently;
+//   a write or admin call waits for everything before it and runs alone, so
+//   a batch like [add, list] sees its own write.
+// - Responses come back in request order; notifications (no `id`) get none.
+//   A batch of only notifications returns 204.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use super::auth::{is_known_method, method_scope, ApiGrant};
+use crate::config::ApiScope;
+use crate::ipc::{
+    dispatch_call, error_value, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR, UNAUTHORIZED,
+};
+use crate::AppContext;
+
+/// Largest batch accepted in one request.
+pub const MAX_BATCH: usize = 100;
+
+pub async fn rpc_bridge(
+    State(ctx): State<Arc<AppContext>>,
+    Extension(grant): Extension<ApiGrant>,
+    body: Bytes,
+) -> Response {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(_) => {
+            return Json(error_value(Value::Null, PARSE_ERROR, "Parse error")).into_response()
+        }
+    };
+
+    match payload {
+        Value::Array(calls) => {
+            if calls.is_empty() {
+                return Json(error_value(Value::Null, INVALID_REQUEST, "Invalid Request"))
+                    .into_response();
+            }
+            if calls.len() > MAX_BATCH {
+                let msg = format!("Invalid Request — batch exceeds {MAX_BATCH} calls");
+                return Json(error_value(Value::Null, INVALID_REQUEST, &msg)).into_response();
+            }
+            let responses: Vec<Value> = run_batch(calls, &ctx, &grant)
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(Value::Array(responses)).into_response()
+            }
+        }
+        call => match run_call(call, &ctx, &grant).await {
+            Some(resp) => Json(resp).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Run a batch and return one slot per call, in request order.
+async fn run_batch(calls: Vec<Value>, ctx: &AppContext, grant: &ApiGrant) -> Vec<Option<Value>> {
+    let mut out = Vec::with_capacity(calls.len());
+    let mut reads = Vec::new();
+    for call in calls {
+        if is_read_call(&call) {
+            reads.push(call);
+            continue;
+        }
+        out.extend(join_all(reads.drain(..).map(|c| run_call(c, ctx, grant))).await);
+        out.push(run_call(call, ctx, grant).await);
+    }
+    out.extend(join_all(reads.into_iter().map(|c| run_call(c, ctx, grant))).await);
+    out
+}
+
+fn is_read_call(call: &Value) -> bool {
+    call.get("method")
+        .and_then(Value::as_str)
+        .is_some_and(|m| method_scope(m) == ApiScope::Read)
+}
+
+/// Validate, authorize and run one call.  `None` for notifications.
+async fn run_call(call: Value, ctx: &AppContext, grant: &ApiGrant) -> Option<Value> {
+    let Value::Object(mut obj) = call else {
+        return Some(error_value(Value::Null, INVALID_REQUEST, "Invalid Request"));
+    };
+    let is_notification = !obj.contains_key("id");
+    let id = obj.remove("id").unwrap_or(Value::Null);
+    let valid_id = matches!(id, Value::Null | Value::String(_) | Value::Number(_));
+    let method = match obj.get("method").and_then(Value::as_str) {
+        Some(m) if obj.get("jsonrpc") == Some(&json!("2.0")) && valid_id => m.to_string(),
+        _ => {
+            let id = if valid_id { id } else { Value::Null };
+            return Some(error_value(id, INVALID_REQUEST, "Invalid Request"));
+        }
+    };
+    let params = obj.remove("params").unwrap_or(Value::Null);
+
+    // Unknown methods would need `admin`; report them as missing instead.
+    if !is_known_method(&method) {
+        return (!is_notification).then(|| error_value(id, METHOD_NOT_FOUND, "Method not found"));
+    }
+    let resp = match grant.authorize(&method, &params, ctx).await {
+        Ok(()) => dispatch_call(&method, id, params, ctx).await,
+        Err(msg) => error_value(id, UNAUTHORIZED, &msg),
+    };
+    (!is_notification).then_some(resp)
+}
@@ -1,70 +1,121 @@
 // rest/sse.rs — SSE push event bridge (Sprint QQ RA.4).
 //
-// GET /api/v1/sessions/{id}/events
+// GET /api/v1/events                  every broadcaster event, filtered by
+//                                     ?types=&session_ids=&repo_path=
+// GET /api/v1/sessions/{id}/events    one session's events plus global ones
 //
-// Streams session push events as Server-Sent Events. The client subscribes
-// to the daemon's broadcast channel and forwards matching events.
+// Each SSE event carries the broadcaster's sequence id (`id:`), the RPC
+// method as its type (`event:`) and `{ method, params }` as data.  A client
+// that reconnects with `Last-Event-ID` (or `?last_event_id=`) first gets the
+// buffered events it missed; if they were already evicted it gets a `gap`
+// event and should reload its state.  A scoped token only receives the
+// events its method globs and repos allow.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::HeaderMap,
     response::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse,
     },
+    Extension,
 };
-use futures_util::stream;
+use futures_util::{stream, Stream};
+use serde::Deserialize;
 use serde_json::json;
+use std::convert::Infallible;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::ipc::event::CursorItem;
+use crate::ipc::event_filter::EventFilter;
+use crate::rest::auth::ApiGrant;
 use crate::AppContext;
 
+#[derive(Deserialize)]
+pub struct EventsQuery {
+    /// Comma-separated method globs.
+    pub types: Option<String>,
+    /// Comma-separated session ids.
+    pub session_ids: Option<String>,
+    pub repo_path: Option<String>,
+    /// For clients that cannot send `Last-Event-ID`.
+    pub last_event_id: Option<u64>,
+}
+
+pub async fn events_sse(
+    State(ctx): State<Arc<AppContext>>,
+    Extension(grant): Extension<ApiGrant>,
+    headers: HeaderMap,
+    Query(q): Query<EventsQuery>,
+) -> impl IntoResponse {
+    let mut filter = EventFilter::from_query(
+        q.types.as_deref(),
+        q.session_ids.as_deref(),
+        q.repo_path.as_deref(),
+    );
+    filter.token = grant.token;
+    let after = last_event_id(&headers).or(q.last_event_id);
+    sse_response(event_stream(ctx, filter, after))
+}
+
 pub async fn session_events_sse(
     State(ctx): State<Arc<AppContext>>,
+    Extension(grant): Extension<ApiGrant>,
+    headers: HeaderMap,
     Path(session_id): Path<String>,
 ) -> impl IntoResponse {
-    let rx = ctx.broadcaster.subscribe();
-
-    let s = stream::unfold((rx, session_id.clone()), move |(mut rx, sid)| async move {
-        loop {
-            match rx.recv().await {
-                Ok(event_str) => {
-                    // Parse the JSON string emitted by EventBroadcaster
-                    let event: serde_json::Value = match serde_json::from_str(&event_str) {
-                        Ok(v) => v,
-                        Err(_) => continue,
-                    };
-
-                    // Forward events that belong to this session or are global
-                    let event_session = event
-                        .get("params")
-                        .and_then(|p: &serde_json::Value| p.get("session_id"))
-                        .and_then(|v: &serde_json::Value| v.as_str())
-                        .unwrap_or("");
+    let mut filter = EventFilter::for_session(session_id);
+    filter.token = grant.token;
+    sse_response(event_stream(ctx, filter, last_event_id(&headers)))
+}
 
-                    if event_session == sid || event_session.is_empty() {
-                        let method = event
-                            .get("method")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("event")
-                            .to_string();
-                        let data = json!({
-                            "method": event.get("method"),
-                            "params": event.get("params"),
-                        });
-                        let sse_event = Event::default().data(data.to_string()).event(method);
-                        return Some((Ok::<Event, std::convert::Infallible>(sse_event), (rx, sid)));
-                    }
-                    // Not our session — continue loop
-                }
-                Err(_) => return None,
-            }
-        }
-    });
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
 
+fn sse_response(
+    s: impl Stream<Item = Result<Event, Infallible>> + Send + 'static,
+) -> impl IntoResponse {
     Sse::new(s).keep_alive(
         KeepAlive::new()
             .interval(Duration::from_secs(15))
             .text("ping"),
     )
 }
+
+/// Broadcaster events after `after` that pass `filter`, as SSE events.
+fn event_stream(
+    ctx: Arc<AppContext>,
+    filter: EventFilter,
+    after: Option<u64>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let cursor = ctx.broadcaster.subscribe_from(after);
+    stream::unfold(
+        (cursor, filter, ctx),
+        |(mut cursor, mut filter, ctx)| async move {
+            loop {
+                let event = match cursor.next().await? {
+                    CursorItem::Event(event) => event,
+                    CursorItem::Gap { after } => {
+                        let data = json!({ "after": after });
+                        let gap = Event::default().event("gap").data(data.to_string());
+                        return Some((Ok(gap), (cursor, filter, ctx)));
+                    }
+                };
+                if !filter.matches(&event, ctx.storage.pool()).await {
+                    continue;
+                }
+                let data = json!({ "method": event.method, "params": event.params });
+                let sse_event = Event::default()
+                    .id(event.id.to_string())
+                    .event(event.method.as_str())
+                    .data(data.to_string());
+                return Some((Ok(sse_event), (cursor, filter, ctx)));
+            }
+        },
+    )
+}
@@ -104,6 +104,15 @@ impl AccountPool {
         }
     }
 
+    /// Whether any account (in any state) is registered for `provider`.
+    pub async fn has_provider(&self, provider: &str) -> bool {
+        self.accounts
+            .read()
+            .await
+            .values()
+            .any(|a| a.provider == provider)
+    }
+
     /// List all accounts (any state).
     pub async fn list(&self) -> Vec<AccountEntry> {
         self.accounts.read().await.values().cloned().collect()
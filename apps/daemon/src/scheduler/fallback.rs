@@ -3,14 +3,25 @@
 //! When the primary provider is rate-limited or unavailable, falls back to
 //! alternative providers in order. Integrates with `AccountPool` and
 //! `RateLimitTracker` for real-time availability decisions.
+//!
+//! Chains come from `[fallback]` in config.toml (see
+//! [`FallbackConfig::resolve`]).  Every skipped provider is broadcast as a
+//! `provider.fallback` event so it is clear why work ran where it did.
 
 use std::sync::Arc;
 
 use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::json;
 use tracing::{debug, info, warn};
 
 use super::accounts::{AccountEntry, AccountPool};
 use super::rate_limits::RateLimitTracker;
+use crate::config::FallbackChainsConfig;
+use crate::ipc::event::EventBroadcaster;
+
+/// `source` of the chain used when nothing is configured.
+pub const BUILTIN_CHAIN: &str = "builtin";
 
 // ── Config ───────────────────────────────────────────────────────────────────
 
@@ -21,6 +32,9 @@ pub struct FallbackConfig {
     pub primary: String,
     /// Ordered list of fallback providers (e.g. `["codex"]`).
     pub alternatives: Vec<String>,
+    /// Where the chain came from, e.g. `"roles.reviewer"` or
+    /// `"projects./repo.default"`; [`BUILTIN_CHAIN`] when not configured.
+    pub source: String,
 }
 
 impl FallbackConfig {
@@ -28,6 +42,7 @@ impl FallbackConfig {
         Self {
             primary: "claude".to_string(),
             alternatives: vec!["codex".to_string()],
+            source: BUILTIN_CHAIN.to_string(),
         }
     }
 
@@ -35,7 +50,94 @@ impl FallbackConfig {
         Self {
             primary: "codex".to_string(),
             alternatives: vec!["claude".to_string()],
+            source: BUILTIN_CHAIN.to_string(),
+        }
+    }
+
+    /// Build a config from an ordered chain.  Blank and repeated entries are
+    /// dropped; returns `None` when nothing is left.
+    pub fn from_chain(chain: &[String], source: impl Into<String>) -> Option<Self> {
+        let mut providers: Vec<String> = Vec::new();
+        for p in chain {
+            let p = p.trim().to_lowercase();
+            if !p.is_empty() && !providers.contains(&p) {
+                providers.push(p);
+            }
+        }
+        let mut iter = providers.into_iter();
+        Some(Self {
+            primary: iter.next()?,
+            alternatives: iter.collect(),
+            source: source.into(),
+        })
+    }
+
+    /// Pick the most specific configured chain for `role` in `repo_path`:
+    /// project + role, project default, role, global default, then built-in.
+    pub fn resolve(
+        chains: &FallbackChainsConfig,
+        role: Option<&str>,
+        repo_path: Option<&str>,
+    ) -> Self {
+        let project = repo_path.and_then(|repo| {
+            let repo = repo.trim_end_matches('/');
+            chains
+                .projects
+                .iter()
+                .find(|(key, _)| key.trim_end_matches('/') == repo)
+        });
+
+        let mut candidates: Vec<(&[String], String)> = Vec::new();
+        if let Some((key, project)) = project {
+            if let Some(chain) = role.and_then(|r| project.roles.get(r)) {
+                candidates.push((
+                    chain,
+                    format!("projects.{key}.roles.{}", role.unwrap_or_default()),
+                ));
+            }
+            candidates.push((&project.default, format!("projects.{key}.default")));
+        }
+        if let Some(chain) = role.and_then(|r| chains.roles.get(r)) {
+            candidates.push((chain, format!("roles.{}", role.unwrap_or_default())));
+        }
+        candidates.push((&chains.default, "default".to_string()));
+
+        candidates
+            .into_iter()
+            .find_map(|(chain, source)| Self::from_chain(chain, source))
+            .unwrap_or_else(Self::claude_first)
+    }
+
+    /// Primary followed by alternatives, in order.
+    pub fn providers(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.primary.as_str()).chain(self.alternatives.iter().map(String::as_str))
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        self.source == BUILTIN_CHAIN
+    }
+}
+
+/// One step down a fallback chain: `from` was skipped for `reason`, and
+/// `to` (if any) is tried next.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FallbackHop {
+    pub from: String,
+    pub to: Option<String>,
+    pub reason: String,
+    /// The `source` of the chain being walked.
+    pub chain: String,
+}
+
+impl FallbackHop {
+    /// Broadcast this hop as `provider.fallback`, merged with `context`
+    /// (task / agent identifiers) so audits can tie it to a unit of work.
+    pub fn emit(&self, broadcaster: &EventBroadcaster, context: serde_json::Value) {
+        let mut params = json!(self);
+        if let (Some(obj), Some(ctx)) = (params.as_object_mut(), context.as_object()) {
+            obj.extend(ctx.clone());
         }
+        broadcaster.broadcast("provider.fallback", params);
     }
 }
 
@@ -44,11 +146,23 @@ impl FallbackConfig {
 pub struct FallbackEngine {
     pub pool: Arc<AccountPool>,
     pub rate_limits: Arc<RateLimitTracker>,
+    /// Receives a `provider.fallback` event per hop when set.
+    events: Option<Arc<EventBroadcaster>>,
 }
 
 impl FallbackEngine {
     pub fn new(pool: Arc<AccountPool>, rate_limits: Arc<RateLimitTracker>) -> Self {
-        Self { pool, rate_limits }
+        Self {
+            pool,
+            rate_limits,
+            events: None,
+        }
+    }
+
+    /// Broadcast each fallback hop on `broadcaster`.
+    pub fn with_events(mut self, broadcaster: Arc<EventBroadcaster>) -> Self {
+        self.events = Some(broadcaster);
+        self
     }
 
     /// Get the best available account for `config`.
@@ -56,17 +170,27 @@ impl FallbackEngine {
     /// Tries the primary provider first, then each alternative in order.
     /// Returns an error only if no account is available from any provider.
     pub async fn get_account(&self, config: &FallbackConfig) -> Result<AccountEntry> {
-        // Try primary.
-        if let Some(entry) = self.try_get(&config.primary).await {
-            return Ok(entry);
-        }
-        warn!(primary = %config.primary, "primary provider unavailable — trying alternatives");
-
-        // Try alternatives in order.
-        for alt in &config.alternatives {
-            if let Some(entry) = self.try_get(alt).await {
-                info!(provider = %alt, "using fallback provider");
-                return Ok(entry);
+        let chain: Vec<&str> = config.providers().collect();
+        for (i, provider) in chain.iter().enumerate() {
+            match self.try_get(provider).await {
+                Ok(entry) => {
+                    if i > 0 {
+                        info!(provider = %provider, chain = %config.source, "using fallback provider");
+                    }
+                    return Ok(entry);
+                }
+                Err(reason) => {
+                    let hop = FallbackHop {
+                        from: provider.to_string(),
+                        to: chain.get(i + 1).map(|p| p.to_string()),
+                        reason,
+                        chain: config.source.clone(),
+                    };
+                    warn!(from = %hop.from, to = ?hop.to, reason = %hop.reason, "provider fallback");
+                    if let Some(ref events) = self.events {
+                        hop.emit(events, json!({}));
+                    }
+                }
             }
         }
 
@@ -77,18 +201,57 @@ impl FallbackEngine {
         )
     }
 
-    /// Attempt to get an available account for `provider`, honouring rate limits.
-    async fn try_get(&self, provider: &str) -> Option<AccountEntry> {
-        let entry = self.pool.get_available(provider).await?;
+    /// Narrow `config` to the providers that can take work right now.
+    ///
+    /// Providers with no registered accounts are not managed by the pool and
+    /// are kept as-is; managed providers without an available account are
+    /// dropped, each with a hop explaining why.  Returns `None` when nothing
+    /// is left.
+    pub async fn available_chain(
+        &self,
+        config: &FallbackConfig,
+    ) -> (Option<FallbackConfig>, Vec<FallbackHop>) {
+        let chain: Vec<&str> = config.providers().collect();
+        let mut kept = Vec::new();
+        let mut hops = Vec::new();
+        for (i, provider) in chain.iter().enumerate() {
+            if !self.pool.has_provider(provider).await {
+                kept.push(provider.to_string());
+                continue;
+            }
+            match self.try_get(provider).await {
+                Ok(_) => kept.push(provider.to_string()),
+                Err(reason) => hops.push(FallbackHop {
+                    from: provider.to_string(),
+                    to: chain.get(i + 1).map(|p| p.to_string()),
+                    reason,
+                    chain: config.source.clone(),
+                }),
+            }
+        }
+        (
+            FallbackConfig::from_chain(&kept, config.source.clone()),
+            hops,
+        )
+    }
+
+    /// Attempt to get an available account for `provider`, honouring rate
+    /// limits.  The error explains why the provider was skipped.
+    async fn try_get(&self, provider: &str) -> Result<AccountEntry, String> {
+        let entry = self
+            .pool
+            .get_available(provider)
+            .await
+            .ok_or_else(|| format!("no available {provider} account"))?;
         if self.rate_limits.is_limited(&entry.account_id).await {
             debug!(
                 account_id = %entry.account_id,
                 provider,
                 "account is rate-limited — skipping"
             );
-            return None;
+            return Err(format!("account {} is rate-limited", entry.account_id));
         }
-        Some(entry)
+        Ok(entry)
     }
 
     /// Record that a request completed successfully.
@@ -107,3 +270,98 @@ impl FallbackEngine {
 
 /// Thread-safe wrapper for use in `AppContext`.
 pub type SharedFallbackEngine = Arc<FallbackEngine>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProjectFallbackConfig;
+
+    fn chain(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn chains() -> FallbackChainsConfig {
+        let mut cfg = FallbackChainsConfig {
+            default: chain(&["claude", "gemini"]),
+            ..Default::default()
+        };
+        cfg.roles
+            .insert("reviewer".into(), chain(&["codex", "claude"]));
+        cfg.projects.insert(
+            "/work/app/".into(),
+            ProjectFallbackConfig {
+                default: chain(&["cursor"]),
+                roles: [(
+                    "implementer".to_string(),
+                    chain(&["gemini", "gemini", "claude"]),
+                )]
+                .into(),
+            },
+        );
+        cfg
+    }
+
+    #[test]
+    fn resolve_prefers_the_most_specific_chain() {
+        let cfg = chains();
+        let r = FallbackConfig::resolve(&cfg, Some("implementer"), Some("/work/app"));
+        assert_eq!(r.providers().collect::<Vec<_>>(), ["gemini", "claude"]);
+        assert_eq!(r.source, "projects./work/app/.roles.implementer");
+
+        let r = FallbackConfig::resolve(&cfg, Some("reviewer"), Some("/work/app"));
+        assert_eq!(r.primary, "cursor");
+
+        let r = FallbackConfig::resolve(&cfg, Some("reviewer"), Some("/elsewhere"));
+        assert_eq!(r.source, "roles.reviewer");
+
+        let r = FallbackConfig::resolve(&cfg, Some("planner"), None);
+        assert_eq!(r.source, "default");
+
+        let r = FallbackConfig::resolve(&FallbackChainsConfig::default(), None, None);
+        assert!(r.is_builtin());
+        assert_eq!(r.primary, "claude");
+    }
+
+    fn account(id: &str, provider: &str) -> AccountEntry {
+        AccountEntry {
+            account_id: id.into(),
+            provider: provider.into(),
+            vault_ref: String::new(),
+            is_available: true,
+            blocked_until: None,
+            rpm_used: 0,
+            tpm_used: 0,
+            total_requests: 0,
+            last_used: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn get_account_emits_one_event_per_hop() {
+        let pool = Arc::new(AccountPool::new());
+        pool.register(account("c1", "claude")).await;
+        pool.register(account("g1", "gemini")).await;
+        pool.mark_rate_limited("c1", 60).await;
+
+        let events = Arc::new(EventBroadcaster::new());
+        let mut rx = events.subscribe();
+        let engine = FallbackEngine::new(pool, Arc::new(RateLimitTracker::new()))
+            .with_events(events.clone());
+
+        let config =
+            FallbackConfig::from_chain(&chain(&["claude", "codex", "gemini"]), "default").unwrap();
+        let entry = engine.get_account(&config).await.unwrap();
+        assert_eq!(entry.account_id, "g1");
+
+        let hops: Vec<serde_json::Value> = (0..2)
+            .map(|_| serde_json::from_str::<serde_json::Value>(&rx.try_recv().unwrap()).unwrap())
+            .collect();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(hops[0]["method"], "provider.fallback");
+        assert_eq!(hops[0]["params"]["from"], "claude");
+        assert_eq!(hops[0]["params"]["to"], "codex");
+        assert_eq!(hops[1]["params"]["from"], "codex");
+        assert_eq!(hops[1]["params"]["to"], "gemini");
+        assert_eq!(hops[1]["params"]["chain"], "default");
+    }
+}
@@ -1,15 +1,31 @@
 //! Priority-ordered scheduling queue for provider requests.
 //!
-//! Requests are dequeued in descending priority order (higher `priority` value
-//! = more urgent). Ties are broken by enqueue time (FIFO).
+//! Requests are dequeued in descending *effective* priority: the requested
+//! `priority` (higher value = more urgent) plus an aging bonus that grows
+//! while the request waits, so low-priority work cannot starve.  Ties are
+//! broken by enqueue time (FIFO).
+//!
+//! A queue opened with [`SchedulerQueue::load`] writes every change through
+//! to the `scheduler_queue` table, so queued requests survive a restart.
+//! Requests that were running when the daemon stopped are re-queued.
+//!
+//! [`SchedulerQueue::acquire`] only hands out a request when an account for
+//! its provider has a free concurrency slot; [`SchedulerQueue::release`]
+//! returns the slot.  Workers reach both through `scheduler.acquire` and
+//! `scheduler.release`.
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use super::accounts::AccountEntry;
+use crate::config::SchedulerConfig;
 
 // ── Request ──────────────────────────────────────────────────────────────────
 
@@ -28,23 +44,6 @@ pub struct SchedulerRequest {
     pub enqueued_at: DateTime<Utc>,
 }
 
-impl Ord for SchedulerRequest {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // BinaryHeap is a max-heap: cmp returning Greater means self pops first.
-        // Higher priority value → pops first.
-        self.priority
-            .cmp(&other.priority)
-            // FIFO within the same priority tier: earlier enqueued → pops first.
-            .then(other.enqueued_at.cmp(&self.enqueued_at))
-    }
-}
-
-impl PartialOrd for SchedulerRequest {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 impl PartialEq for SchedulerRequest {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -53,42 +52,358 @@ impl PartialEq for SchedulerRequest {
 
 impl Eq for SchedulerRequest {}
 
+/// A request holding an account slot.
+#[derive(Debug, Clone, Serialize)]
+pub struct Lease {
+    #[serde(flatten)]
+    pub request: SchedulerRequest,
+    pub account_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// A queued request as reported by `scheduler.status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    #[serde(flatten)]
+    pub request: SchedulerRequest,
+    /// `priority` plus the aging bonus earned so far.
+    pub effective_priority: u32,
+    /// 1-based position in dispatch order.
+    pub position: usize,
+    pub waiting_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub queued: Vec<QueueEntry>,
+    pub running: Vec<Lease>,
+    /// Slots in use per account.
+    pub slots_in_use: BTreeMap<String, usize>,
+    pub slots_per_account: usize,
+}
+
 // ── Queue ────────────────────────────────────────────────────────────────────
 
+#[derive(Default)]
+struct QueueState {
+    queued: Vec<SchedulerRequest>,
+    /// request id → lease
+    running: HashMap<String, Lease>,
+}
+
 pub struct SchedulerQueue {
-    queue: Mutex<BinaryHeap<SchedulerRequest>>,
+    state: Mutex<QueueState>,
+    /// Write-through persistence; `None` for a purely in-memory queue.
+    pool: Option<SqlitePool>,
+    config: SchedulerConfig,
 }
 
 impl SchedulerQueue {
+    /// In-memory queue with default aging and slot settings.
     pub fn new() -> Self {
+        Self::with_config(SchedulerConfig::default())
+    }
+
+    /// In-memory queue with explicit settings.
+    pub fn with_config(config: SchedulerConfig) -> Self {
         Self {
-            queue: Mutex::new(BinaryHeap::new()),
+            state: Mutex::new(QueueState::default()),
+            pool: None,
+            config,
+        }
+    }
+
+    /// Open the durable queue, re-queueing anything that was running when
+    /// the daemon last stopped.
+    pub async fn load(pool: SqlitePool, config: SchedulerConfig) -> Result<Self> {
+        let requeued = sqlx::query(
+            "UPDATE scheduler_queue SET status = 'queued', account_id = NULL, started_at = NULL
+             WHERE status = 'running'",
+        )
+        .execute(&pool)
+        .await?
+        .rows_affected();
+        if requeued > 0 {
+            info!(count = requeued, "re-queued interrupted scheduler requests");
         }
+
+        let rows: Vec<(String, String, String, String, String, i64, String)> = sqlx::query_as(
+            "SELECT id, task_id, agent_id, role, provider, priority, enqueued_at
+             FROM scheduler_queue WHERE status = 'queued'",
+        )
+        .fetch_all(&pool)
+        .await?;
+        let queued = rows
+            .into_iter()
+            .map(|(id, task_id, agent_id, role, provider, priority, at)| {
+                // A bad timestamp only costs the request its aging bonus.
+                let enqueued_at = match DateTime::parse_from_rfc3339(&at) {
+                    Ok(t) => t.with_timezone(&Utc),
+                    Err(e) => {
+                        warn!(id = %id, enqueued_at = %at, err = %e, "unparseable enqueue time — aging restarts now");
+                        Utc::now()
+                    }
+                };
+                SchedulerRequest {
+                    id,
+                    task_id,
+                    agent_id,
+                    role,
+                    provider,
+                    priority: priority.clamp(0, u8::MAX as i64) as u8,
+                    enqueued_at,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            state: Mutex::new(QueueState {
+                queued,
+                running: HashMap::new(),
+            }),
+            pool: Some(pool),
+            config,
+        })
+    }
+
+    /// `priority` plus one `aging_step` per full `aging_interval_secs` waited.
+    pub fn effective_priority(&self, req: &SchedulerRequest, now: DateTime<Utc>) -> u32 {
+        let interval = self.config.aging_interval_secs as i64;
+        let bonus = if interval > 0 {
+            let waited = (now - req.enqueued_at).num_seconds().max(0);
+            (waited / interval).min(u32::MAX as i64) as u32
+        } else {
+            0
+        };
+        (req.priority as u32).saturating_add(bonus.saturating_mul(self.config.aging_step))
+    }
+
+    /// Queued requests in dispatch order, with their effective priority.
+    fn ordered<'a>(
+        &self,
+        queued: &'a [SchedulerRequest],
+        now: DateTime<Utc>,
+    ) -> Vec<(&'a SchedulerRequest, u32)> {
+        let mut out: Vec<_> = queued
+            .iter()
+            .map(|r| (r, self.effective_priority(r, now)))
+            .collect();
+        out.sort_by(|(a, pa), (b, pb)| pb.cmp(pa).then(a.enqueued_at.cmp(&b.enqueued_at)));
+        out
     }
 
     /// Enqueue a request. Higher priority requests will be dequeued first.
-    pub async fn enqueue(&self, req: SchedulerRequest) {
-        self.queue.lock().await.push(req);
+    /// Fails if a request with the same id is already queued or running.
+    pub async fn enqueue(&self, req: SchedulerRequest) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if state.running.contains_key(&req.id) {
+            bail!("request {} is already running", req.id);
+        }
+        if state.queued.iter().any(|r| r.id == req.id) {
+            bail!("request {} is already queued", req.id);
+        }
+        if let Some(ref pool) = self.pool {
+            sqlx::query(
+                "INSERT INTO scheduler_queue
+                 (id, task_id, agent_id, role, provider, priority, enqueued_at, status)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 'queued')",
+            )
+            .bind(&req.id)
+            .bind(&req.task_id)
+            .bind(&req.agent_id)
+            .bind(&req.role)
+            .bind(&req.provider)
+            .bind(req.priority as i64)
+            .bind(req.enqueued_at.to_rfc3339())
+            .execute(pool)
+            .await?;
+        }
+        state.queued.push(req);
+        Ok(())
+    }
+
+    /// Dequeue the highest-priority request (or oldest request at equal
+    /// priority) without reserving an account slot.
+    pub async fn dequeue(&self) -> Result<Option<SchedulerRequest>> {
+        let mut state = self.state.lock().await;
+        let Some(id) = self
+            .ordered(&state.queued, Utc::now())
+            .first()
+            .map(|(r, _)| r.id.clone())
+        else {
+            return Ok(None);
+        };
+        self.delete_row(&id).await?;
+        let idx = state.queued.iter().position(|r| r.id == id);
+        Ok(idx.map(|i| state.queued.remove(i)))
+    }
+
+    /// Take the best request whose provider has an account with a free slot.
+    ///
+    /// Requests for saturated providers stay queued without blocking requests
+    /// for other providers.  The least-loaded eligible account is chosen.
+    pub async fn acquire(&self, accounts: &[AccountEntry]) -> Result<Option<Lease>> {
+        let now = Utc::now();
+        let mut state = self.state.lock().await;
+        let mut in_use: HashMap<&str, usize> = HashMap::new();
+        for lease in state.running.values() {
+            *in_use.entry(lease.account_id.as_str()).or_default() += 1;
+        }
+
+        let Some((req_id, account_id)) =
+            self.ordered(&state.queued, now)
+                .into_iter()
+                .find_map(|(req, _)| {
+                    accounts
+                        .iter()
+                        .filter(|a| {
+                            a.provider == req.provider
+                                && a.is_available
+                                && a.blocked_until.is_none_or(|t| now >= t)
+                        })
+                        .map(|a| (a, in_use.get(a.account_id.as_str()).copied().unwrap_or(0)))
+                        .filter(|(_, used)| *used < self.config.slots_per_account)
+                        .min_by_key(|(_, used)| *used)
+                        .map(|(a, _)| (req.id.clone(), a.account_id.clone()))
+                })
+        else {
+            return Ok(None);
+        };
+
+        if let Some(ref pool) = self.pool {
+            sqlx::query(
+                "UPDATE scheduler_queue SET status = 'running', account_id = ?, started_at = ?
+                 WHERE id = ?",
+            )
+            .bind(&account_id)
+            .bind(now.to_rfc3339())
+            .bind(&req_id)
+            .execute(pool)
+            .await?;
+        }
+        let Some(idx) = state.queued.iter().position(|r| r.id == req_id) else {
+            return Ok(None);
+        };
+        let lease = Lease {
+            request: state.queued.remove(idx),
+            account_id,
+            started_at: now,
+        };
+        state.running.insert(req_id, lease.clone());
+        Ok(Some(lease))
+    }
+
+    /// Finish a running request and free its account slot.  Returns the
+    /// lease, or `None` if the request was not running.
+    pub async fn release(&self, request_id: &str) -> Result<Option<Lease>> {
+        let mut state = self.state.lock().await;
+        if !state.running.contains_key(request_id) {
+            return Ok(None);
+        }
+        self.delete_row(request_id).await?;
+        Ok(state.running.remove(request_id))
     }
 
-    /// Dequeue the highest-priority request (or oldest request at equal priority).
-    pub async fn dequeue(&self) -> Option<SchedulerRequest> {
-        self.queue.lock().await.pop()
+    /// Remove a queued request.  Returns `false` if no such request is queued;
+    /// running requests cannot be cancelled here.
+    pub async fn cancel(&self, request_id: &str) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        if state.running.contains_key(request_id) {
+            bail!("request {request_id} is already running");
+        }
+        let Some(idx) = state.queued.iter().position(|r| r.id == request_id) else {
+            return Ok(false);
+        };
+        self.delete_row(request_id).await?;
+        state.queued.remove(idx);
+        Ok(true)
+    }
+
+    /// Change the base priority of a queued request.  Aging earned so far is
+    /// kept because it derives from the unchanged enqueue time.
+    pub async fn reprioritize(&self, request_id: &str, priority: u8) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        if state.running.contains_key(request_id) {
+            bail!("request {request_id} is already running");
+        }
+        let Some(req) = state.queued.iter_mut().find(|r| r.id == request_id) else {
+            return Ok(false);
+        };
+        if let Some(ref pool) = self.pool {
+            sqlx::query("UPDATE scheduler_queue SET priority = ? WHERE id = ?")
+                .bind(priority as i64)
+                .bind(request_id)
+                .execute(pool)
+                .await?;
+        }
+        req.priority = priority;
+        Ok(true)
+    }
+
+    /// 1-based dispatch position of a queued request.
+    pub async fn position(&self, request_id: &str) -> Option<usize> {
+        let state = self.state.lock().await;
+        self.ordered(&state.queued, Utc::now())
+            .iter()
+            .position(|(r, _)| r.id == request_id)
+            .map(|i| i + 1)
     }
 
-    /// Current queue depth.
+    /// Queued requests in dispatch order plus current leases.
+    pub async fn snapshot(&self) -> QueueSnapshot {
+        let now = Utc::now();
+        let state = self.state.lock().await;
+        let queued = self
+            .ordered(&state.queued, now)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (r, effective))| QueueEntry {
+                request: r.clone(),
+                effective_priority: effective,
+                position: i + 1,
+                waiting_secs: (now - r.enqueued_at).num_seconds().max(0),
+            })
+            .collect();
+        let mut running: Vec<Lease> = state.running.values().cloned().collect();
+        running.sort_by_key(|l| l.started_at);
+        let mut slots_in_use = BTreeMap::new();
+        for lease in &running {
+            *slots_in_use.entry(lease.account_id.clone()).or_default() += 1;
+        }
+        QueueSnapshot {
+            queued,
+            running,
+            slots_in_use,
+            slots_per_account: self.config.slots_per_account,
+        }
+    }
+
+    /// Current queue depth (queued, not running).
     pub async fn len(&self) -> usize {
-        self.queue.lock().await.len()
+        self.state.lock().await.queued.len()
     }
 
-    /// Priority of the next request to be dequeued, or `None` if empty.
+    /// Base priority of the next request to be dequeued, or `None` if empty.
     pub async fn peek_priority(&self) -> Option<u8> {
-        self.queue.lock().await.peek().map(|r| r.priority)
+        let state = self.state.lock().await;
+        self.ordered(&state.queued, Utc::now())
+            .first()
+            .map(|(r, _)| r.priority)
     }
 
     /// Returns `true` if the queue is empty.
     pub async fn is_empty(&self) -> bool {
-        self.queue.lock().await.is_empty()
+        self.state.lock().await.queued.is_empty()
+    }
+
+    async fn delete_row(&self, request_id: &str) -> Result<()> {
+        if let Some(ref pool) = self.pool {
+            sqlx::query("DELETE FROM scheduler_queue WHERE id = ?")
+                .bind(request_id)
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
     }
 }
 
@@ -621,7 +621,7 @@ impl Runner for ClaudeCodeRunner {
 /// - `"high"`:   destructive or shell-execution tools (bash, delete, computer)
 /// - `"medium"`: file-write or content-modifying tools
 /// - `"low"`:    read-only or informational tools
-fn classify_tool_risk(tool_name: &str) -> &'static str {
+pub(crate) fn classify_tool_risk(tool_name: &str) -> &'static str {
     let n = tool_name.to_lowercase();
     if n.contains("bash")
         || n.contains("execute")
@@ -0,0 +1,1008 @@
+//! Direct HTTP provider runner.
+//!
+//! Talks to an OpenAI-compatible `/chat/completions` endpoint (OpenAI, vLLM,
+//! Ollama, llama.cpp) or the Anthropic Messages API over streaming SSE,
+//! instead of spawning a vendor CLI.  A provider profile becomes an HTTP
+//! provider by naming its wire protocol:
+//!
+//! ```toml
+//! [provider.local]
+//! api = "openai"
+//! base_url = "http://localhost:11434/v1"
+//! model = "qwen2.5-coder:14b"
+//! ```
+//!
+//! Sessions created with `provider = "local"` then use this runner.  The model
+//! gets the tools in [`super::http_tools`]: read-only tools run immediately,
+//! everything else is surfaced as a `pending` tool call and waits for
+//! `tool.approve` / `tool.reject`.  Token usage reported by the stream is
+//! recorded through [`TokenTracker`].
+
+use super::claude::{classify_tool_risk, validate_tool_args, ArgValidation};
+use super::http_tools::{self, ToolSpec};
+use super::runner::{Runner, ToolDecision};
+use crate::{
+    config::{HttpApi, ProviderProfile},
+    intelligence::token_tracker::TokenTracker,
+    ipc::event::EventBroadcaster,
+    storage::Storage,
+};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{oneshot, Mutex};
+use tracing::{debug, warn};
+
+/// Upper bound on model ↔ tool round trips within one turn.
+const MAX_TOOL_ROUNDS: usize = 25;
+/// `max_tokens` when the profile does not set one (required by Anthropic).
+const DEFAULT_MAX_TOKENS: u64 = 4096;
+/// Request timeout when the profile does not set one.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Minimum gap between DB writes of a streaming message; broadcasts are not throttled.
+const PERSIST_INTERVAL: Duration = Duration::from_millis(500);
+
+// ─── Endpoint ─────────────────────────────────────────────────────────────────
+
+/// Resolved connection settings for one HTTP provider.
+#[derive(Debug, Clone)]
+pub struct HttpEndpoint {
+    pub api: HttpApi,
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub max_tokens: u64,
+    pub system_prompt_prefix: Option<String>,
+    pub timeout: Duration,
+}
+
+impl HttpEndpoint {
+    /// Build an endpoint from `[provider.<name>]`.  The API key is read from
+    /// the environment now so a missing model or key fails at session creation
+    /// rather than mid-turn.
+    pub fn from_profile(name: &str, profile: &ProviderProfile) -> Result<Self> {
+        let api = profile
+            .api
+            .with_context(|| format!("PROVIDER_NOT_AVAILABLE: provider '{name}' has no api"))?;
+        let model = profile.model.clone().with_context(|| {
+            format!("PROVIDER_NOT_AVAILABLE: provider '{name}' must set `model`")
+        })?;
+        let key_env = profile
+            .api_key_env
+            .as_deref()
+            .unwrap_or(api.default_api_key_env());
+        Ok(Self {
+            api,
+            base_url: profile
+                .base_url
+                .as_deref()
+                .unwrap_or(api.default_base_url())
+                .trim_end_matches('/')
+                .to_string(),
+            model,
+            api_key: std::env::var(key_env).ok().filter(|k| !k.is_empty()),
+            max_tokens: profile.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            system_prompt_prefix: profile.system_prompt_prefix.clone(),
+            timeout: Duration::from_secs(profile.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        })
+    }
+
+    fn url(&self) -> String {
+        match self.api {
+            HttpApi::OpenAi => format!("{}/chat/completions", self.base_url),
+            HttpApi::Anthropic => format!("{}/messages", self.base_url),
+        }
+    }
+
+    /// POST `body` to the endpoint with its auth headers and timeout.
+    pub fn post(&self, client: &reqwest::Client, body: &Value) -> reqwest::RequestBuilder {
+        let req = client.post(self.url()).timeout(self.timeout).json(body);
+        match (self.api, &self.api_key) {
+            (HttpApi::OpenAi, Some(key)) => req.bearer_auth(key),
+            (HttpApi::OpenAi, None) => req,
+            (HttpApi::Anthropic, key) => {
+                let req = req.header("anthropic-version", ANTHROPIC_VERSION);
+                match key {
+                    Some(key) => req.header("x-api-key", key),
+                    None => req,
+                }
+            }
+        }
+    }
+}
+
+// ─── Conversation model ───────────────────────────────────────────────────────
+
+/// Provider-neutral conversation history entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Turn {
+    User(String),
+    Assistant {
+        text: String,
+        tool_calls: Vec<ToolCallRequest>,
+    },
+    ToolResult {
+        call_id: String,
+        content: String,
+        is_error: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCallRequest {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// Everything a single streamed response produced.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    pub tool_calls: Vec<ToolCallRequest>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+/// The streaming request body in the endpoint's dialect.
+pub fn request_body(
+    endpoint: &HttpEndpoint,
+    system: &str,
+    history: &[Turn],
+    tools: &[ToolSpec],
+) -> Value {
+    match endpoint.api {
+        HttpApi::OpenAi => openai_request(endpoint, system, history, tools),
+        HttpApi::Anthropic => anthropic_request(endpoint, system, history, tools),
+    }
+}
+
+/// Build the `/chat/completions` request body.  `tools` is omitted when
+/// empty.
+pub fn openai_request(
+    endpoint: &HttpEndpoint,
+    system: &str,
+    history: &[Turn],
+    tools: &[ToolSpec],
+) -> Value {
+    let mut messages = vec![json!({ "role": "system", "content": system })];
+    for turn in history {
+        messages.push(match turn {
+            Turn::User(text) => json!({ "role": "user", "content": text }),
+            Turn::Assistant { text, tool_calls } => {
+                let mut msg = json!({
+                    "role": "assistant",
+                    "content": if text.is_empty() { Value::Null } else { json!(text) },
+                });
+                if !tool_calls.is_empty() {
+                    msg["tool_calls"] = tool_calls
+                        .iter()
+                        .map(|c| {
+                            json!({
+                                "id": c.id,
+                                "type": "function",
+                                "function": { "name": c.name, "arguments": c.arguments.to_string() }
+                            })
+                        })
+                        .collect();
+                }
+                msg
+            }
+            Turn::ToolResult {
+                call_id, content, ..
+            } => json!({ "role": "tool", "tool_call_id": call_id, "content": content }),
+        });
+    }
+    let mut body = json!({
+        "model": endpoint.model,
+        "messages": messages,
+        "max_tokens": endpoint.max_tokens,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+    });
+    if !tools.is_empty() {
+        body["tools"] = tools.iter().map(|t| json!({
+            "type": "function",
+            "function": { "name": t.name, "description": t.description, "parameters": t.parameters }
+        })).collect();
+    }
+    body
+}
+
+/// Build the Anthropic `/messages` request body.  Consecutive tool results
+/// are folded into a single user message, as the API requires; `tools` is
+/// omitted when empty.
+pub fn anthropic_request(
+    endpoint: &HttpEndpoint,
+    system: &str,
+    history: &[Turn],
+    tools: &[ToolSpec],
+) -> Value {
+    let mut messages: Vec<Value> = Vec::new();
+    for turn in history {
+        match turn {
+            Turn::User(text) => messages.push(json!({ "role": "user", "content": text })),
+            Turn::Assistant { text, tool_calls } => {
+                let mut blocks = Vec::new();
+                if !text.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": text }));
+                }
+                for c in tool_calls {
+                    blocks.push(json!({
+                        "type": "tool_use", "id": c.id, "name": c.name, "input": c.arguments
+                    }));
+                }
+                if !blocks.is_empty() {
+                    messages.push(json!({ "role": "assistant", "content": blocks }));
+                }
+            }
+            Turn::ToolResult {
+                call_id,
+                content,
+                is_error,
+            } => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": content,
+                    "is_error": is_error,
+                });
+                let extends_previous = messages.last().is_some_and(|m| {
+                    m["role"] == "user"
+                        && m["content"]
+                            .as_array()
+                            .is_some_and(|b| b.iter().all(|b| b["type"] == "tool_result"))
+                });
+                match messages.last_mut() {
+                    Some(last) if extends_previous => {
+                        if let Some(blocks) = last["content"].as_array_mut() {
+                            blocks.push(block);
+                        }
+                    }
+                    _ => messages.push(json!({ "role": "user", "content": [block] })),
+                }
+            }
+        }
+    }
+    let mut body = json!({
+        "model": endpoint.model,
+        "system": system,
+        "messages": messages,
+        "max_tokens": endpoint.max_tokens,
+        "stream": true,
+    });
+    if !tools.is_empty() {
+        body["tools"] = tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.name, "description": t.description, "input_schema": t.parameters
+                })
+            })
+            .collect();
+    }
+    body
+}
+
+// ─── SSE decoding ─────────────────────────────────────────────────────────────
+
+/// Incremental `text/event-stream` splitter.  Feed raw body chunks; get back
+/// the `data:` payload of every event completed so far.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buf: Vec<u8>,
+}
+
+/// One decoded SSE event.  `event` is `"message"` when the stream names none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+impl SseDecoder {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.push_events(chunk)
+            .into_iter()
+            .map(|e| e.data)
+            .collect()
+    }
+
+    /// Like `push`, keeping each event's `event:` name — MCP streams mix
+    /// `endpoint` and `message` events.
+    pub fn push_events(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buf
+            .extend(chunk.iter().copied().filter(|b| *b != b'\r'));
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.windows(2).position(|w| w == b"\n\n") {
+            let raw: Vec<u8> = self.buf.drain(..pos + 2).collect();
+            let text = String::from_utf8_lossy(&raw[..pos]);
+            let mut event = None;
+            let mut data = Vec::new();
+            for line in text.lines() {
+                if let Some(name) = line.strip_prefix("event:") {
+                    event = Some(name.trim().to_string());
+                } else if let Some(d) = line.strip_prefix("data:") {
+                    data.push(d.strip_prefix(' ').unwrap_or(d));
+                }
+            }
+            if !data.is_empty() {
+                events.push(SseEvent {
+                    event: event.unwrap_or_else(|| "message".into()),
+                    data: data.join("\n"),
+                });
+            }
+        }
+        events
+    }
+}
+
+/// Folds streamed SSE payloads into a [`Completion`] for either dialect.
+#[derive(Debug)]
+pub struct StreamDecoder {
+    api: HttpApi,
+    text: String,
+    /// Tool calls keyed by stream index: (id, name, accumulated JSON arguments).
+    calls: BTreeMap<u64, (String, String, String)>,
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl StreamDecoder {
+    pub fn new(api: HttpApi) -> Self {
+        Self {
+            api,
+            text: String::new(),
+            calls: BTreeMap::new(),
+            input_tokens: 0,
+            output_tokens: 0,
+        }
+    }
+
+    /// Apply one event payload.  Returns any new assistant text.
+    pub fn apply(&mut self, data: &str) -> Result<Option<String>> {
+        if data == "[DONE]" {
+            return Ok(None);
+        }
+        let v: Value = serde_json::from_str(data)
+            .with_context(|| format!("malformed stream event: {data}"))?;
+        if let Some(err) = v.get("error") {
+            let msg = err["message"].as_str().unwrap_or("unknown error");
+            bail!("provider stream error: {msg}");
+        }
+        let delta = match self.api {
+            HttpApi::OpenAi => self.apply_openai(&v),
+            HttpApi::Anthropic => self.apply_anthropic(&v),
+        };
+        if let Some(ref d) = delta {
+            self.text.push_str(d);
+        }
+        Ok(delta)
+    }
+
+    fn apply_openai(&mut self, v: &Value) -> Option<String> {
+        if let Some(usage) = v.get("usage").filter(|u| u.is_object()) {
+            self.input_tokens = token_count(&usage["prompt_tokens"]);
+            self.output_tokens = token_count(&usage["completion_tokens"]);
+        }
+        let delta = &v["choices"][0]["delta"];
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or(0);
+            let entry = self.calls.entry(index).or_default();
+            if let Some(id) = call["id"].as_str() {
+                entry.0 = id.to_string();
+            }
+            if let Some(name) = call["function"]["name"].as_str() {
+                entry.1.push_str(name);
+            }
+            if let Some(args) = call["function"]["arguments"].as_str() {
+                entry.2.push_str(args);
+            }
+        }
+        delta["content"]
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    }
+
+    fn apply_anthropic(&mut self, v: &Value) -> Option<String> {
+        match v["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &v["message"]["usage"];
+                self.input_tokens = token_count(&usage["input_tokens"]);
+                self.output_tokens = token_count(&usage["output_tokens"]);
+                None
+            }
+            "content_block_start" if v["content_block"]["type"] == "tool_use" => {
+                let index = v["index"].as_u64().unwrap_or(0);
+                let block = &v["content_block"];
+                self.calls.insert(
+                    index,
+                    (
+                        block["id"].as_str().unwrap_or_default().to_string(),
+                        block["name"].as_str().unwrap_or_default().to_string(),
+                        String::new(),
+                    ),
+                );
+                None
+            }
+            "content_block_delta" => {
+                let delta = &v["delta"];
+                match delta["type"].as_str().unwrap_or_default() {
+                    "text_delta" => delta["text"].as_str().map(str::to_string),
+                    "input_json_delta" => {
+                        let index = v["index"].as_u64().unwrap_or(0);
+                        if let (Some(entry), Some(part)) =
+                            (self.calls.get_mut(&index), delta["partial_json"].as_str())
+                        {
+                            entry.2.push_str(part);
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            "message_delta" => {
+                if let Some(out) = v["usage"]["output_tokens"].as_u64() {
+                    self.output_tokens = out as u32;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn finish(self) -> Completion {
+        let tool_calls = self
+            .calls
+            .into_iter()
+            .map(|(index, (id, name, args))| ToolCallRequest {
+                id: if id.is_empty() {
+                    format!("call_{index}")
+                } else {
+                    id
+                },
+                name,
+                arguments: if args.trim().is_empty() {
+                    json!({})
+                } else {
+                    // Keep unparseable arguments visible so the tool reports a
+                    // useful error instead of silently receiving nothing.
+                    serde_json::from_str(&args).unwrap_or_else(|_| json!({ "_raw": args }))
+                },
+            })
+            .collect();
+        Completion {
+            text: self.text,
+            tool_calls,
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+        }
+    }
+}
+
+fn token_count(v: &Value) -> u32 {
+    v.as_u64().unwrap_or(0).min(u32::MAX as u64) as u32
+}
+
+// ─── Runner ───────────────────────────────────────────────────────────────────
+
+pub struct HttpRunner {
+    session_id: String,
+    repo_path: PathBuf,
+    storage: Arc<Storage>,
+    broadcaster: Arc<EventBroadcaster>,
+    tokens: TokenTracker,
+    endpoint: HttpEndpoint,
+    client: reqwest::Client,
+    /// Conversation sent with each request; seeded from the DB on first use.
+    history: Mutex<Option<Vec<Turn>>>,
+    /// Tool calls waiting on `tool.approve` / `tool.reject`.
+    pending: std::sync::Mutex<HashMap<String, oneshot::Sender<ToolDecision>>>,
+    cancelled: AtomicBool,
+}
+
+/// Assistant message being streamed to clients.
+#[derive(Default)]
+struct StreamingMessage {
+    id: Option<String>,
+    content: String,
+    persisted_at: Option<Instant>,
+}
+
+impl HttpRunner {
+    pub fn new(
+        session_id: String,
+        repo_path: String,
+        storage: Arc<Storage>,
+        broadcaster: Arc<EventBroadcaster>,
+        endpoint: HttpEndpoint,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            session_id,
+            repo_path: PathBuf::from(repo_path),
+            tokens: TokenTracker::new(storage.clone()),
+            storage,
+            broadcaster,
+            endpoint,
+            client: reqwest::Client::new(),
+            history: Mutex::new(None),
+            pending: std::sync::Mutex::new(HashMap::new()),
+            cancelled: AtomicBool::new(false),
+        })
+    }
+
+    fn system_prompt(&self) -> String {
+        let base = format!(
+            "You are a coding agent working in the repository at {}. \
+             Use the provided tools to inspect and change files; paths are relative to the repository root.",
+            self.repo_path.display()
+        );
+        match &self.endpoint.system_prompt_prefix {
+            Some(prefix) => format!("{prefix}\n\n{base}"),
+            None => base,
+        }
+    }
+
+    async fn set_status(&self, status: &str) -> Result<()> {
+        self.storage
+            .update_session_status(&self.session_id, status)
+            .await?;
+        self.broadcaster.broadcast(
+            "session.statusChanged",
+            json!({ "sessionId": self.session_id, "status": status }),
+        );
+        Ok(())
+    }
+
+    /// Rebuild text history from stored messages.  Tool exchanges from earlier
+    /// daemon runs are not replayed; their effect is already in the repo.
+    async fn seed_history(&self) -> Result<Vec<Turn>> {
+        let rows = self
+            .storage
+            .list_messages(&self.session_id, 1000, None)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|m| match m.role.as_str() {
+                "user" => Some(Turn::User(m.content)),
+                "assistant" if !m.content.is_empty() => Some(Turn::Assistant {
+                    text: m.content,
+                    tool_calls: Vec::new(),
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn drive(&self, history: &mut Vec<Turn>) -> Result<()> {
+        for _ in 0..MAX_TOOL_ROUNDS {
+            if self.cancelled.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            let completion = self.complete(history).await?;
+            let calls = completion.tool_calls.clone();
+            history.push(Turn::Assistant {
+                text: completion.text,
+                tool_calls: completion.tool_calls,
+            });
+            if calls.is_empty() {
+                return Ok(());
+            }
+            // Every tool call needs a result, even when the turn is cancelled,
+            // or the next request would be rejected by the provider.
+            for call in &calls {
+                let (content, is_error) = if self.cancelled.load(Ordering::Acquire) {
+                    ("Turn cancelled before this tool ran.".to_string(), true)
+                } else {
+                    self.handle_tool_call(call).await?
+                };
+                history.push(Turn::ToolResult {
+                    call_id: call.id.clone(),
+                    content,
+                    is_error,
+                });
+            }
+        }
+        warn!(session = %self.session_id, "HTTP provider hit {MAX_TOOL_ROUNDS} tool rounds; ending turn");
+        Ok(())
+    }
+
+    /// Stream one model response, mirroring text into an assistant message.
+    async fn complete(&self, history: &[Turn]) -> Result<Completion> {
+        let tools = http_tools::definitions();
+        let system = self.system_prompt();
+        let ep = &self.endpoint;
+        let resp = ep
+            .post(&self.client, &request_body(ep, &system, history, &tools))
+            .send()
+            .await
+            .with_context(|| format!("request to {} failed", ep.url()))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            bail!(
+                "provider returned HTTP {status}: {}",
+                body.chars().take(500).collect::<String>()
+            );
+        }
+
+        let mut stream = resp.bytes_stream();
+        let mut sse = SseDecoder::default();
+        let mut decoder = StreamDecoder::new(ep.api);
+        let mut msg = StreamingMessage::default();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("provider stream interrupted")?;
+            for data in sse.push(&chunk) {
+                if let Some(delta) = decoder.apply(&data)? {
+                    self.append_text(&mut msg, &delta).await?;
+                }
+            }
+            if self.cancelled.load(Ordering::Acquire) {
+                debug!(session = %self.session_id, "HTTP stream cancelled");
+                break;
+            }
+        }
+        let completion = decoder.finish();
+
+        if let Some(ref id) = msg.id {
+            self.storage
+                .update_message_content(id, &msg.content, "done")
+                .await?;
+            self.broadcaster.broadcast(
+                "session.messageUpdated",
+                json!({
+                    "sessionId": self.session_id,
+                    "messageId": id,
+                    "content": msg.content,
+                    "status": "done"
+                }),
+            );
+        }
+        if completion.input_tokens > 0 || completion.output_tokens > 0 {
+            self.tokens
+                .record(
+                    &self.session_id,
+                    msg.id.as_deref(),
+                    &ep.model,
+                    completion.input_tokens,
+                    completion.output_tokens,
+                )
+                .await?;
+        }
+        Ok(completion)
+    }
+
+    async fn append_text(&self, msg: &mut StreamingMessage, delta: &str) -> Result<()> {
+        msg.content.push_str(delta);
+        let Some(ref id) = msg.id else {
+            let row = self
+                .storage
+                .create_message(&self.session_id, "assistant", &msg.content, "streaming")
+                .await?;
+            self.storage
+                .increment_message_count(&self.session_id)
+                .await?;
+            self.broadcaster.broadcast(
+                "session.messageCreated",
+                json!({
+                    "sessionId": self.session_id,
+                    "message": {
+                        "id": row.id,
+                        "sessionId": self.session_id,
+                        "role": "assistant",
+                        "content": msg.content,
+                        "status": "streaming",
+                        "createdAt": row.created_at
+                    }
+                }),
+            );
+            msg.id = Some(row.id);
+            msg.persisted_at = Some(Instant::now());
+            return Ok(());
+        };
+        if msg
+            .persisted_at
+            .is_none_or(|t| t.elapsed() >= PERSIST_INTERVAL)
+        {
+            self.storage
+                .update_message_content(id, &msg.content, "streaming")
+                .await?;
+            msg.persisted_at = Some(Instant::now());
+        }
+        self.broadcaster.broadcast(
+            "session.messageUpdated",
+            json!({
+                "sessionId": self.session_id,
+                "messageId": id,
+                "content": msg.content,
+                "status": "streaming"
+            }),
+        );
+        Ok(())
+    }
+
+    /// Record, gate and execute one tool call.  Returns the result text for
+    /// the model and whether it is an error.
+    async fn handle_tool_call(&self, call: &ToolCallRequest) -> Result<(String, bool)> {
+        let tool_msg = self
+            .storage
+            .create_message(&self.session_id, "tool", "", "done")
+            .await?;
+        self.storage
+            .increment_message_count(&self.session_id)
+            .await?;
+        let input_str = call.arguments.to_string();
+        let tool_call = self
+            .storage
+            .create_tool_call(&self.session_id, &tool_msg.id, &call.name, &input_str)
+            .await?;
+
+        let scope = super::tool_name_to_scope(&call.name);
+        let auto_approve = scope == "file_read";
+        let blocked = match validate_tool_args(&call.name, &call.arguments) {
+            ArgValidation::Blocked(reason) => Some(reason),
+            ArgValidation::Ok if !self.scope_permitted(scope).await? => {
+                Some(format!("session permissions do not include '{scope}'"))
+            }
+            ArgValidation::Ok => None,
+        };
+
+        // Register the waiter before announcing the call so an immediate
+        // approval cannot race ahead of us.
+        let decision = if auto_approve || blocked.is_some() {
+            None
+        } else {
+            let (tx, rx) = oneshot::channel();
+            self.pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(tool_call.id.clone(), tx);
+            Some(rx)
+        };
+
+        self.broadcaster.broadcast(
+            "session.toolCallCreated",
+            json!({
+                "sessionId": self.session_id,
+                "toolCall": {
+                    "id": tool_call.id,
+                    "messageId": tool_msg.id,
+                    "name": call.name,
+                    "input": call.arguments,
+                    "status": if decision.is_some() { "pending" } else { "running" },
+                    "riskLevel": classify_tool_risk(&call.name),
+                    "createdAt": tool_call.created_at
+                }
+            }),
+        );
+
+        if let Some(reason) = blocked {
+            let output = format!("Tool call blocked: {reason}");
+            self.finish_tool_call(&tool_call.id, &output, "error")
+                .await?;
+            return Ok((output, true));
+        }
+
+        match decision {
+            None => {
+                let _ = self
+                    .storage
+                    .create_tool_call_event(
+                        &self.session_id,
+                        &call.name,
+                        Some(&input_str),
+                        "auto",
+                        None,
+                    )
+                    .await;
+            }
+            Some(rx) => match rx.await {
+                Ok(ToolDecision::Approved) => {}
+                // `reject_tool` has already recorded and broadcast the rejection.
+                Ok(ToolDecision::Rejected) => {
+                    return Ok(("The user rejected this tool call.".to_string(), true));
+                }
+                Err(_) => {
+                    let output = "Turn cancelled before this tool ran.".to_string();
+                    self.finish_tool_call(&tool_call.id, &output, "rejected")
+                        .await?;
+                    return Ok((output, true));
+                }
+            },
+        }
+
+        let (output, status) =
+            match http_tools::execute(&call.name, &call.arguments, &self.repo_path).await {
+                Ok(out) => (out, "done"),
+                Err(e) => (format!("{e:#}"), "error"),
+            };
+        self.finish_tool_call(&tool_call.id, &output, status)
+            .await?;
+        Ok((output, status == "error"))
+    }
+
+    async fn finish_tool_call(&self, id: &str, output: &str, status: &str) -> Result<()> {
+        self.storage
+            .complete_tool_call(id, Some(output), status)
+            .await?;
+        self.broadcaster.broadcast(
+            "session.toolCallUpdated",
+            json!({
+                "sessionId": self.session_id,
+                "toolCallId": id,
+                "status": status,
+                "output": output
+            }),
+        );
+        Ok(())
+    }
+
+    /// Auto-approved tools bypass `tool.approve`, so check session scopes here.
+    async fn scope_permitted(&self, scope: &str) -> Result<bool> {
+        let Some(session) = self.storage.get_session(&self.session_id).await? else {
+            return Ok(true);
+        };
+        let perms: Vec<String> = session
+            .permissions
+            .as_deref()
+            .and_then(|p| serde_json::from_str(p).ok())
+            .unwrap_or_default();
+        Ok(perms.is_empty() || perms.iter().any(|p| p == scope))
+    }
+}
+
+#[async_trait]
+impl Runner for HttpRunner {
+    async fn run_turn(&self, content: &str) -> Result<()> {
+        self.cancelled.store(false, Ordering::Release);
+        self.set_status("running").await?;
+
+        let mut guard = self.history.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.seed_history().await?);
+        }
+        let history = guard.as_mut().expect("history seeded above");
+        // A freshly seeded history already ends with this turn's message.
+        if history.last() != Some(&Turn::User(content.to_string())) {
+            history.push(Turn::User(content.to_string()));
+        }
+        let checkpoint = history.len();
+
+        if let Err(e) = self.drive(history).await {
+            // Drop the partial exchange so a retry starts from a valid history.
+            history.truncate(checkpoint);
+            return Err(e);
+        }
+        drop(guard);
+        self.set_status("idle").await
+    }
+
+    async fn send(&self, _content: &str) -> Result<()> {
+        bail!(
+            "PROVIDER_NOT_AVAILABLE: HTTP providers do not support mid-session message injection — start a new turn instead"
+        )
+    }
+
+    async fn pause(&self) -> Result<()> {
+        // No subprocess to suspend; the session manager's paused status
+        // already blocks the next turn.
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.cancelled.store(true, Ordering::Release);
+        // Dropping the senders wakes any tool call waiting for approval.
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        Ok(())
+    }
+
+    async fn decide_tool(&self, tool_call_id: &str, decision: ToolDecision) -> Result<bool> {
+        let tx = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tool_call_id);
+        Ok(tx.is_some_and(|tx| tx.send(decision).is_ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_decoder_handles_split_chunks() {
+        let mut sse = SseDecoder::default();
+        assert!(sse.push(b"data: {\"a\"").is_empty());
+        assert_eq!(
+            sse.push(b":1}\r\n\r\nevent: x\ndata: [DONE]\n\n"),
+            vec!["{\"a\":1}".to_string(), "[DONE]".to_string()]
+        );
+    }
+
+    #[test]
+    fn sse_decoder_keeps_event_names() {
+        let mut sse = SseDecoder::default();
+        let events =
+            sse.push_events(b"event: endpoint\ndata: /messages?s=1\n\n: ping\n\ndata: {}\n\n");
+        assert_eq!(events[0].event, "endpoint");
+        assert_eq!(events[0].data, "/messages?s=1");
+        assert_eq!(events[1].event, "message");
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn openai_stream_accumulates_tool_call_arguments() {
+        let mut d = StreamDecoder::new(HttpApi::OpenAi);
+        d.apply(r#"{"choices":[{"delta":{"content":"Hi"}}]}"#)
+            .unwrap();
+        d.apply(r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"c1","function":{"name":"read_file","arguments":"{\"pa"}}]}}]}"#).unwrap();
+        d.apply(r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"th\":\"a.rs\"}"}}]}}]}"#).unwrap();
+        d.apply(r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":5}}"#)
+            .unwrap();
+        let c = d.finish();
+        assert_eq!(c.text, "Hi");
+        assert_eq!(c.tool_calls[0].id, "c1");
+        assert_eq!(c.tool_calls[0].arguments, json!({ "path": "a.rs" }));
+        assert_eq!((c.input_tokens, c.output_tokens), (12, 5));
+    }
+
+    #[test]
+    fn anthropic_tool_results_share_one_user_message() {
+        let ep = HttpEndpoint {
+            api: HttpApi::Anthropic,
+            base_url: String::new(),
+            model: "m".into(),
+            api_key: None,
+            max_tokens: 10,
+            system_prompt_prefix: None,
+            timeout: Duration::from_secs(1),
+        };
+        let call = |id: &str| ToolCallRequest {
+            id: id.into(),
+            name: "read_file".into(),
+            arguments: json!({}),
+        };
+        let history = vec![
+            Turn::User("go".into()),
+            Turn::Assistant {
+                text: String::new(),
+                tool_calls: vec![call("a"), call("b")],
+            },
+            Turn::ToolResult {
+                call_id: "a".into(),
+                content: "1".into(),
+                is_error: false,
+            },
+            Turn::ToolResult {
+                call_id: "b".into(),
+                content: "2".into(),
+                is_error: true,
+            },
+        ];
+        let body = anthropic_request(&ep, "sys", &history, &[]);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["content"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[2]["content"][1]["is_error"], true);
+    }
+}
@@ -0,0 +1,313 @@
+//! Tools exposed to direct HTTP providers.
+//!
+//! CLI providers bring their own tool implementations; a raw chat-completions
+//! endpoint does not, so [`super::http::HttpRunner`] offers this small set.
+//! Every path is confined to the session's repo (or worktree) and output is
+//! capped so a single call cannot flood the context window.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Largest file `read_file` returns before truncating.
+const MAX_READ_BYTES: usize = 256 * 1024;
+/// Largest combined stdout/stderr `run_command` returns.
+const MAX_COMMAND_OUTPUT: usize = 64 * 1024;
+/// Hard limit on a single `run_command` invocation.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+/// Maximum number of paths `glob_files` lists.
+const MAX_GLOB_RESULTS: usize = 500;
+/// Directories `glob_files` never descends into.
+const SKIP_DIRS: &[&str] = &[".git", "node_modules", "target", ".dart_tool", "build"];
+
+/// Provider-neutral tool description; the runner renders it into the
+/// OpenAI `tools` or Anthropic `tools` shape.
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: Value,
+}
+
+pub fn definitions() -> Vec<ToolSpec> {
+    vec![
+        ToolSpec {
+            name: "read_file",
+            description: "Read a UTF-8 text file from the repository.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the repository root" }
+                },
+                "required": ["path"]
+            }),
+        },
+        ToolSpec {
+            name: "glob_files",
+            description: "List repository files matching a glob such as `src/**/*.rs`.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string" }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolSpec {
+            name: "write_file",
+            description: "Create or overwrite a file in the repository. Requires user approval.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the repository root" },
+                    "content": { "type": "string" }
+                },
+                "required": ["path", "content"]
+            }),
+        },
+        ToolSpec {
+            name: "run_command",
+            description: "Run a shell command in the repository root. Requires user approval.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string" }
+                },
+                "required": ["command"]
+            }),
+        },
+    ]
+}
+
+/// Execute a tool call. Errors are returned to the model as tool results,
+/// so messages should say what went wrong in terms it can act on.
+pub async fn execute(name: &str, args: &Value, repo: &Path) -> Result<String> {
+    match name {
+        "read_file" => read_file(repo, str_arg(args, "path")?).await,
+        "glob_files" => glob_files(repo, str_arg(args, "pattern")?).await,
+        "write_file" => write_file(repo, str_arg(args, "path")?, str_arg(args, "content")?).await,
+        "run_command" => run_command(repo, str_arg(args, "command")?).await,
+        other => bail!("unknown tool '{other}'"),
+    }
+}
+
+fn str_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .with_context(|| format!("missing string argument '{key}'"))
+}
+
+/// Resolve `rel` against `repo`, rejecting anything that would escape it.
+/// `..` is resolved lexically, then the longest existing prefix is
+/// canonicalized so a symlink cannot point outside the repo; the part that
+/// does not exist yet (a file about to be written) is appended as is.
+pub fn resolve_in_repo(repo: &Path, rel: &str) -> Result<PathBuf> {
+    let rel_path = Path::new(rel);
+    let candidate = if rel_path.is_absolute() {
+        rel_path.to_path_buf()
+    } else {
+        repo.join(rel_path)
+    };
+    let mut out = PathBuf::new();
+    for comp in candidate.components() {
+        match comp {
+            Component::ParentDir => {
+                if !out.pop() {
+                    bail!("path '{rel}' escapes the repository");
+                }
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    let Ok(inside) = out.strip_prefix(repo) else {
+        bail!("path '{rel}' escapes the repository");
+    };
+
+    let root = repo
+        .canonicalize()
+        .with_context(|| format!("cannot open repository {}", repo.display()))?;
+    let mut existing = root.join(inside);
+    let mut missing = Vec::new();
+    // `symlink_metadata` so a dangling link counts as existing and fails to
+    // canonicalize below instead of being written through.
+    while std::fs::symlink_metadata(&existing).is_err() {
+        let Some(name) = existing.file_name() else {
+            break;
+        };
+        missing.push(name.to_os_string());
+        existing.pop();
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("cannot resolve '{rel}'"))?;
+    if !resolved.starts_with(&root) {
+        bail!("path '{rel}' escapes the repository");
+    }
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
+}
+
+async fn read_file(repo: &Path, rel: &str) -> Result<String> {
+    let path = resolve_in_repo(repo, rel)?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("cannot read '{rel}'"))?;
+    let truncated = bytes.len() > MAX_READ_BYTES;
+    let mut text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_READ_BYTES)]).into_owned();
+    if truncated {
+        text.push_str(&format!(
+            "\n[truncated: showing {MAX_READ_BYTES} of {} bytes]",
+            bytes.len()
+        ));
+    }
+    Ok(text)
+}
+
+async fn write_file(repo: &Path, rel: &str, content: &str) -> Result<String> {
+    let path = resolve_in_repo(repo, rel)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, content)
+        .await
+        .with_context(|| format!("cannot write '{rel}'"))?;
+    Ok(format!("wrote {} bytes to {rel}", content.len()))
+}
+
+async fn glob_files(repo: &Path, pattern: &str) -> Result<String> {
+    let re = regex::Regex::new(&crate::automations::condition::glob_to_regex(pattern))
+        .with_context(|| format!("invalid glob '{pattern}'"))?;
+    let root = repo.to_path_buf();
+    let matches = tokio::task::spawn_blocking(move || {
+        let mut found = Vec::new();
+        let mut stack = vec![root.clone()];
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(ft) = entry.file_type() else { continue };
+                if ft.is_dir() {
+                    let name = entry.file_name();
+                    if !SKIP_DIRS.iter().any(|s| name == *s) {
+                        stack.push(path);
+                    }
+                } else if let Ok(rel) = path.strip_prefix(&root) {
+                    let rel = rel.to_string_lossy().replace('\\', "/");
+                    if re.is_match(&rel) {
+                        found.push(rel);
+                    }
+                }
+            }
+        }
+        found.sort();
+        found
+    })
+    .await?;
+
+    if matches.is_empty() {
+        return Ok("no files matched".to_string());
+    }
+    let total = matches.len();
+    let mut out = matches
+        .into_iter()
+        .take(MAX_GLOB_RESULTS)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if total > MAX_GLOB_RESULTS {
+        out.push_str(&format!("\n[{} more not shown]", total - MAX_GLOB_RESULTS));
+    }
+    Ok(out)
+}
+
+async fn run_command(repo: &Path, command: &str) -> Result<String> {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(repo)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, child)
+        .await
+        .with_context(|| format!("command timed out after {}s", COMMAND_TIMEOUT.as_secs()))?
+        .context("failed to spawn shell")?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&stderr);
+    }
+    if text.len() > MAX_COMMAND_OUTPUT {
+        let mut cut = MAX_COMMAND_OUTPUT;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        text.push_str("\n[output truncated]");
+    }
+    let code = output.status.code().unwrap_or(-1);
+    if !output.status.success() {
+        bail!("exit code {code}\n{text}");
+    }
+    Ok(format!("exit code {code}\n{text}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_rejects_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        let root = repo.canonicalize().unwrap();
+        assert_eq!(
+            resolve_in_repo(&repo, "src/../lib.rs").unwrap(),
+            root.join("lib.rs")
+        );
+        assert_eq!(
+            resolve_in_repo(&repo, "src/new/mod.rs").unwrap(),
+            root.join("src/new/mod.rs")
+        );
+        assert!(resolve_in_repo(&repo, "../other/secret").is_err());
+        assert!(resolve_in_repo(&repo, "/etc/passwd").is_err());
+        let absolute = repo.join("ok.txt");
+        assert!(resolve_in_repo(&repo, absolute.to_str().unwrap()).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_rejects_symlinks_out_of_the_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret"), "x").unwrap();
+        std::os::unix::fs::symlink(&outside, repo.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("gone"), repo.join("dangling")).unwrap();
+
+        assert!(resolve_in_repo(&repo, "link/secret").is_err());
+        assert!(resolve_in_repo(&repo, "link/new.txt").is_err());
+        assert!(resolve_in_repo(&repo, "dangling").is_err());
+    }
+
+    #[tokio::test]
+    async fn glob_lists_matching_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(dir.path().join("src/a.rs"), "").unwrap();
+        std::fs::write(dir.path().join("src/nested/b.rs"), "").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        let out = glob_files(dir.path(), "src/**/*.rs").await.unwrap();
+        assert_eq!(out, "src/a.rs\nsrc/nested/b.rs");
+    }
+}
@@ -3,24 +3,32 @@ pub mod codex;
 pub mod completion;
 pub mod cursor;
 pub mod events;
+pub mod http;
+pub mod http_tools;
 pub mod router;
 pub mod runner;
 pub mod system_prompt;
 pub mod telemetry;
 pub mod worktree;
 
-use crate::{ipc::event::EventBroadcaster, storage::Storage, AppContext};
+use crate::{
+    config::{ProviderProfile, RoutingConfig},
+    ipc::event::EventBroadcaster,
+    storage::Storage,
+    AppContext,
+};
 use anyhow::{Context, Result};
 use serde::Serialize;
 use serde_json::json;
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 use tokio::sync::RwLock;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use claude::ClaudeCodeRunner;
 use codex::CodexRunner;
 use cursor::CursorRunner;
-use runner::Runner;
+use http::{HttpEndpoint, HttpRunner};
+use runner::{Runner, ToolDecision};
 
 // ─── View types (matching @clawde/proto) ─────────────────────────────────────
 
@@ -42,6 +50,10 @@ pub struct SessionView {
     /// `None` when the provider was explicitly specified.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub routed_provider: Option<String>,
+    /// Why auto-routing chose `routed_provider`: method, per-candidate scores
+    /// and evidence, and human-readable `reasons`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_explain: Option<serde_json::Value>,
     /// GCI mode: NORMAL | LEARN | STORM | FORGE | CRUNCH
     pub mode: String,
     /// Resource tier: active | warm | cold
@@ -76,6 +88,10 @@ pub struct SessionManager {
     data_dir: PathBuf,
     /// In-memory runners for active sessions
     handles: RwLock<HashMap<String, Arc<SessionHandle>>>,
+    /// Direct HTTP providers from `[provider.<name>]` profiles with an `api`.
+    http_providers: HashMap<String, ProviderProfile>,
+    /// Learned auto-routing thresholds for `provider = "auto"`.
+    routing: RoutingConfig,
 }
 
 impl SessionManager {
@@ -89,9 +105,28 @@ impl SessionManager {
             broadcaster,
             data_dir,
             handles: RwLock::new(HashMap::new()),
+            http_providers: HashMap::new(),
+            routing: RoutingConfig::default(),
         }
     }
 
+    /// Register direct HTTP providers so sessions can select them by name.
+    pub fn with_http_providers(mut self, providers: HashMap<String, ProviderProfile>) -> Self {
+        self.http_providers = providers;
+        self
+    }
+
+    /// Configure learned auto-routing.
+    pub fn with_routing(mut self, routing: RoutingConfig) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    fn is_known_provider(&self, provider: &str) -> bool {
+        matches!(provider, "claude" | "codex" | "cursor")
+            || self.http_providers.contains_key(provider)
+    }
+
     pub async fn active_count(&self) -> usize {
         self.handles.read().await.len()
     }
@@ -109,23 +144,29 @@ impl SessionManager {
     ) -> Result<SessionView> {
         // Resolve "auto" provider via intent classification.
         // The resolved provider is stored separately as `routed_provider`.
+        let mut routing_explain = None;
         let (effective_provider, routed_provider) = if provider == "auto" {
-            let chosen = router::classify_intent(initial_message, &[]);
-            (
-                chosen.as_str().to_string(),
-                Some(chosen.as_str().to_string()),
-            )
+            let decision = self.route_auto(repo_path, initial_message).await;
+            routing_explain = serde_json::to_value(&decision.explain).ok();
+            let chosen = decision.provider.as_str().to_string();
+            (chosen.clone(), Some(chosen))
         } else {
-            // Validate explicit provider — must match ProviderType.name values from Dart
-            match provider {
-                "claude" | "codex" | "cursor" => {}
-                _ => anyhow::bail!("PROVIDER_NOT_AVAILABLE: unknown provider: {}", provider),
+            // Validate explicit provider — must match ProviderType.name values
+            // from Dart, or a configured HTTP provider.
+            if !self.is_known_provider(provider) {
+                anyhow::bail!("PROVIDER_NOT_AVAILABLE: unknown provider: {}", provider);
             }
             (provider.to_string(), None)
         };
 
-        // Check that the provider CLI is installed and authenticated.
-        check_provider_ready(&effective_provider).await?;
+        // Check that the provider CLI is installed and authenticated, or that
+        // the HTTP provider profile is complete.
+        match self.http_providers.get(&effective_provider) {
+            Some(profile) => {
+                HttpEndpoint::from_profile(&effective_provider, profile)?;
+            }
+            None => check_provider_ready(&effective_provider).await?,
+        }
 
         // Enforce session limit.  Checked here (inside the manager) rather than
         // in the handler so the check and creation are logically coupled; SQLite
@@ -154,8 +195,9 @@ impl SessionManager {
 
         // If provider was auto-routed, persist the routing decision.
         if let Some(ref rp) = routed_provider {
+            let explain = routing_explain.as_ref().map(|v| v.to_string());
             self.storage
-                .update_session_routed_provider(&row.id, rp)
+                .update_session_routed_provider(&row.id, rp, explain.as_deref())
                 .await?;
         }
 
@@ -177,6 +219,7 @@ impl SessionManager {
         // before update_session_routed_provider ran.
         if routed_provider.is_some() {
             view.routed_provider = routed_provider;
+            view.routing_explain = routing_explain;
         }
         self.broadcaster.broadcast(
             "session.statusChanged",
@@ -185,6 +228,66 @@ impl SessionManager {
         Ok(view)
     }
 
+    /// Resolve `provider = "auto"` from the repo's languages and recorded
+    /// history.  History errors degrade to the keyword heuristic.
+    async fn route_auto(
+        &self,
+        repo_path: &str,
+        initial_message: Option<&str>,
+    ) -> router::RoutingDecision {
+        let languages = self.repo_languages(repo_path).await;
+        let history = if self.routing.learned {
+            router::RoutingHistory::load(self.storage.pool())
+                .await
+                .unwrap_or_else(|e| {
+                    warn!(err = %e, "failed to load routing history; using heuristic");
+                    router::RoutingHistory::default()
+                })
+        } else {
+            router::RoutingHistory::default()
+        };
+        router::route(initial_message, &languages, &history, &self.routing)
+    }
+
+    /// Languages of `repo_path`, primary first: the stored repo profile when
+    /// one exists, otherwise a quick manifest/extension scan.
+    async fn repo_languages(&self, repo_path: &str) -> Vec<String> {
+        let profile: Option<(String, String)> = sqlx::query_as(
+            "SELECT primary_lang, secondary_langs FROM repo_profiles WHERE repo_path = ?",
+        )
+        .bind(repo_path)
+        .fetch_optional(self.storage.pool())
+        .await
+        .ok()
+        .flatten();
+        let mut langs = match profile {
+            Some((primary, secondary)) => {
+                let mut langs = vec![primary];
+                // Stored as serde camelCase ("typeScript"); match `as_str` keys.
+                langs.extend(
+                    serde_json::from_str::<Vec<String>>(&secondary)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|l| l.to_lowercase()),
+                );
+                langs
+            }
+            None => {
+                let path = PathBuf::from(repo_path);
+                tokio::task::spawn_blocking(move || {
+                    crate::repo_intelligence::scanner::detect_primary_language(&path)
+                        .as_str()
+                        .to_string()
+                })
+                .await
+                .map(|l| vec![l])
+                .unwrap_or_default()
+            }
+        };
+        langs.retain(|l| l != "unknown");
+        langs
+    }
+
     pub async fn list(&self) -> Result<Vec<SessionView>> {
         let rows = self.storage.list_sessions().await?;
         Ok(rows.into_iter().map(row_to_view).collect())
@@ -316,9 +419,8 @@ impl SessionManager {
     /// `session.sendMessage` uses the new provider.
     pub async fn set_provider(&self, session_id: &str, new_provider: &str) -> Result<()> {
         // Validate the target provider (must be explicit — not "auto").
-        match new_provider {
-            "claude" | "codex" | "cursor" => {}
-            _ => anyhow::bail!("PROVIDER_NOT_AVAILABLE: unknown provider: {}", new_provider),
+        if !self.is_known_provider(new_provider) {
+            anyhow::bail!("PROVIDER_NOT_AVAILABLE: unknown provider: {}", new_provider);
         }
 
         let session = self
@@ -340,7 +442,7 @@ impl SessionManager {
         // Persist the provider choice as routed_provider (the sessions.provider
         // column always holds the originally-requested provider).
         self.storage
-            .update_session_routed_provider(session_id, new_provider)
+            .update_session_routed_provider(session_id, new_provider, None)
             .await?;
 
         self.broadcaster.broadcast(
@@ -407,19 +509,31 @@ impl SessionManager {
             .as_deref()
             .unwrap_or(session_row.provider.as_str());
 
+        let http_endpoint = match self.http_providers.get(effective_provider) {
+            Some(profile) => Some(HttpEndpoint::from_profile(effective_provider, profile)?),
+            None => None,
+        };
+
         let runner: Arc<dyn Runner> = {
             let mut handles = self.handles.write().await;
             if let Some(h) = handles.get(session_id) {
                 h.runner.clone()
             } else {
-                let r: Arc<dyn Runner> = match effective_provider {
-                    "codex" => CodexRunner::new(
+                let r: Arc<dyn Runner> = match (effective_provider, http_endpoint) {
+                    (_, Some(endpoint)) => HttpRunner::new(
                         session_id.to_string(),
                         effective_path,
                         self.storage.clone(),
                         self.broadcaster.clone(),
+                        endpoint,
                     ),
-                    "cursor" => CursorRunner::new(
+                    ("codex", None) => CodexRunner::new(
+                        session_id.to_string(),
+                        effective_path,
+                        self.storage.clone(),
+                        self.broadcaster.clone(),
+                    ),
+                    ("cursor", None) => CursorRunner::new(
                         session_id.to_string(),
                         effective_path,
                         self.storage.clone(),
@@ -561,6 +675,26 @@ impl SessionManager {
                 "status": "approved",
             }),
         );
+        // HTTP runners hold the tool until the user decides; wake them.
+        self.deliver_decision(session_id, tool_call_id, ToolDecision::Approved)
+            .await
+    }
+
+    async fn deliver_decision(
+        &self,
+        session_id: &str,
+        tool_call_id: &str,
+        decision: ToolDecision,
+    ) -> Result<()> {
+        let runner = self
+            .handles
+            .read()
+            .await
+            .get(session_id)
+            .map(|h| h.runner.clone());
+        if let Some(runner) = runner {
+            runner.decide_tool(tool_call_id, decision).await?;
+        }
         Ok(())
     }
 
@@ -577,7 +711,8 @@ impl SessionManager {
                 "status": "rejected",
             }),
         );
-        Ok(())
+        self.deliver_decision(session_id, tool_call_id, ToolDecision::Rejected)
+            .await
     }
 }
 
@@ -640,6 +775,10 @@ fn row_to_view(row: crate::storage::SessionRow) -> SessionView {
         message_count: row.message_count,
         permissions,
         routed_provider: row.routed_provider,
+        routing_explain: row
+            .routing_explain
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok()),
         mode: row.mode,
         tier: row.tier,
         model_override: row.model_override,
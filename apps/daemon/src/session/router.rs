@@ -1,11 +1,20 @@
 // SPDX-License-Identifier: MIT
-//! Provider intent router — pure heuristic, no subprocess calls, no ML.
+//! Provider intent router for `provider: "auto"`.
 //!
-//! Classifies an incoming message to determine whether Claude Code or Codex
-//! is the better provider for the task.  Used by `session.create` when the
-//! caller passes `provider: "auto"`.
+//! Two layers:
 //!
-//! Signal scoring:
+//! 1. **Learned routing** ([`route`]) — scores each candidate from recorded
+//!    history: arena head-to-head votes (per task type and repo language) and
+//!    session outcomes (per language), minus a cost penalty from recorded
+//!    token spend.  A history bucket is only trusted once it has
+//!    `min_samples` observations; more specific buckets win over broader ones.
+//! 2. **Keyword heuristic** ([`classify_intent`]) — used when no candidate has
+//!    enough history, and to break exact ties.
+//!
+//! Every decision carries a [`RoutingExplain`] so the client can show why a
+//! provider was picked.
+//!
+//! Heuristic signal scoring:
 //!   Codex signals:  "debug", "explain", "review", "why", "error", "bug",
 //!                   "what does", "what is"
 //!   Claude signals: "generate", "refactor", "implement", "build", "create",
@@ -14,6 +23,16 @@
 //!   If codex_score > claude_score → Codex
 //!   Otherwise (including tie) → Claude (safe default)
 
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::arena::model::ProviderRecord;
+use crate::arena::storage::ArenaStorage;
+use crate::config::RoutingConfig;
+
 /// The provider selected by the auto-router.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Provider {
@@ -22,6 +41,9 @@ pub enum Provider {
 }
 
 impl Provider {
+    /// Providers the auto-router chooses between.
+    pub const CANDIDATES: [Provider; 2] = [Provider::Claude, Provider::Codex];
+
     /// Return the provider name as used in the daemon protocol (matches Dart ProviderType.name).
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -33,8 +55,8 @@ impl Provider {
 
 /// Classify an optional initial message to choose the best provider.
 ///
-/// `_repo_languages` is accepted for future use (language-based routing)
-/// but is not yet used in the scoring logic.
+/// Pure keyword heuristic; language-aware routing lives in [`route`], so
+/// `_repo_languages` is unused here.
 ///
 /// The routing decision is **logged at `debug` level only** to avoid
 /// leaking user message content into info-level logs.
@@ -78,6 +100,415 @@ pub fn classify_intent(initial_message: Option<&str>, _repo_languages: &[String]
     }
 }
 
+// ─── Task types ───────────────────────────────────────────────────────────────
+
+/// Map a message onto the arena task categories
+/// (`general | debug | refactor | explain | generate`).
+pub fn classify_task_type(initial_message: Option<&str>) -> &'static str {
+    let msg = initial_message.unwrap_or("").to_lowercase();
+    let categories: &[(&str, &[&str])] = &[
+        (
+            "debug",
+            &["debug", "error", "bug", "crash", "failing", "broken", "fix"],
+        ),
+        (
+            "refactor",
+            &[
+                "refactor",
+                "clean up",
+                "cleanup",
+                "rename",
+                "restructure",
+                "simplify",
+            ],
+        ),
+        (
+            "explain",
+            &[
+                "explain",
+                "why",
+                "what does",
+                "what is",
+                "how does",
+                "review",
+            ],
+        ),
+        (
+            "generate",
+            &["generate", "implement", "build", "create", "write", "add"],
+        ),
+    ];
+    let mut best = ("general", 0);
+    for (name, signals) in categories {
+        let hits = signals.iter().filter(|&&s| msg.contains(s)).count();
+        if hits > best.1 {
+            best = (name, hits);
+        }
+    }
+    best.0
+}
+
+// ─── History ──────────────────────────────────────────────────────────────────
+
+/// Recorded evidence the learned router scores against.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingHistory {
+    /// Arena head-to-head records per provider / task type / language.
+    pub arena: Vec<ProviderRecord>,
+    /// Session outcomes per provider / language over finished sessions:
+    /// `wins` counts those whose last run completed (`idle`), `trials` adds
+    /// those that ended in `error`.  Running and paused sessions are not
+    /// counted yet.  `task_type` is always "all".
+    pub outcomes: Vec<ProviderRecord>,
+    /// Provider → (mean cost per session in USD, sessions with usage).
+    pub costs: HashMap<String, (f64, u64)>,
+}
+
+impl RoutingHistory {
+    /// Load arena votes, session outcomes and token costs from SQLite.
+    pub async fn load(pool: &SqlitePool) -> Result<Self> {
+        let arena = ArenaStorage::new(pool.clone())
+            .get_provider_records()
+            .await?;
+
+        let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+            "SELECT
+                 COALESCE(s.routed_provider, s.provider) AS prov,
+                 COALESCE(rp.primary_lang, 'unknown') AS language,
+                 SUM(CASE WHEN s.status = 'idle' THEN 1 ELSE 0 END) AS ok,
+                 COUNT(*) AS total
+             FROM sessions s
+             LEFT JOIN repo_profiles rp ON rp.repo_path = s.repo_path
+             WHERE s.message_count > 0 AND s.status IN ('idle', 'error')
+             GROUP BY prov, language",
+        )
+        .fetch_all(pool)
+        .await?;
+        let outcomes = rows
+            .into_iter()
+            .map(|(provider, language, ok, total)| ProviderRecord {
+                provider,
+                task_type: "all".to_string(),
+                language,
+                wins: ok.max(0) as u64,
+                trials: total.max(0) as u64,
+            })
+            .collect();
+
+        let rows: Vec<(String, f64, i64)> = sqlx::query_as(
+            "SELECT prov, AVG(cost), COUNT(*) FROM (
+                 SELECT COALESCE(s.routed_provider, s.provider) AS prov,
+                        SUM(t.estimated_cost_usd) AS cost
+                 FROM token_usage t
+                 JOIN sessions s ON s.id = t.session_id
+                 GROUP BY s.id
+             )
+             GROUP BY prov",
+        )
+        .fetch_all(pool)
+        .await?;
+        let costs = rows
+            .into_iter()
+            .map(|(provider, avg, n)| (provider, (avg, n.max(0) as u64)))
+            .collect();
+
+        Ok(Self {
+            arena,
+            outcomes,
+            costs,
+        })
+    }
+}
+
+// ─── Learned routing ──────────────────────────────────────────────────────────
+
+/// One history bucket that contributed to a provider's score.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evidence {
+    /// `arena` or `outcomes`.
+    pub source: &'static str,
+    /// Task type the bucket covers ("all" when not segmented).
+    pub task_type: String,
+    /// Language the bucket covers ("all" when not segmented).
+    pub language: String,
+    pub wins: u64,
+    pub trials: u64,
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateScore {
+    pub provider: String,
+    /// Final score: quality minus cost penalty.
+    pub score: f64,
+    /// Sample-weighted success rate, or the 0.5 prior without evidence.
+    pub quality: f64,
+    pub evidence: Vec<Evidence>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_cost_usd: Option<f64>,
+    pub cost_penalty: f64,
+}
+
+/// Why the auto-router picked a provider.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingExplain {
+    /// `learned` when history decided, `heuristic` when keywords did.
+    pub method: &'static str,
+    pub task_type: String,
+    pub languages: Vec<String>,
+    pub min_samples: u64,
+    pub candidates: Vec<CandidateScore>,
+    /// Human-readable reasons, most important first.
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoutingDecision {
+    pub provider: Provider,
+    pub explain: RoutingExplain,
+}
+
+/// Quality assumed for a candidate without trusted history.
+const PRIOR: f64 = 0.5;
+/// Scores closer than this are treated as a tie.
+const TIE_EPSILON: f64 = 1e-6;
+
+/// Pick a provider for `provider: "auto"` using recorded history, falling
+/// back to [`classify_intent`] when no candidate has enough samples.
+pub fn route(
+    initial_message: Option<&str>,
+    repo_languages: &[String],
+    history: &RoutingHistory,
+    config: &RoutingConfig,
+) -> RoutingDecision {
+    let heuristic = classify_intent(initial_message, repo_languages);
+    let task_type = classify_task_type(initial_message);
+    let min = config.min_samples.max(1);
+
+    let mut candidates: Vec<CandidateScore> = Provider::CANDIDATES
+        .iter()
+        .map(|p| {
+            let name = p.as_str();
+            let mut evidence = Vec::new();
+            if let Some(e) = best_bucket(
+                "arena",
+                &history.arena,
+                name,
+                task_type,
+                repo_languages,
+                min,
+            ) {
+                evidence.push(e);
+            }
+            if let Some(e) = best_bucket(
+                "outcomes",
+                &history.outcomes,
+                name,
+                "all",
+                repo_languages,
+                min,
+            ) {
+                evidence.push(e);
+            }
+            let trials: u64 = evidence.iter().map(|e| e.trials).sum();
+            let quality = if trials == 0 {
+                PRIOR
+            } else {
+                evidence
+                    .iter()
+                    .map(|e| e.rate * e.trials as f64)
+                    .sum::<f64>()
+                    / trials as f64
+            };
+            let avg_cost_usd = history
+                .costs
+                .get(name)
+                .filter(|(_, n)| *n >= min)
+                .map(|(avg, _)| *avg);
+            CandidateScore {
+                provider: name.to_string(),
+                score: quality,
+                quality,
+                evidence,
+                avg_cost_usd,
+                cost_penalty: 0.0,
+            }
+        })
+        .collect();
+
+    let max_cost = candidates
+        .iter()
+        .filter_map(|c| c.avg_cost_usd)
+        .fold(0.0_f64, f64::max);
+    if max_cost > 0.0 && config.cost_weight > 0.0 {
+        // A provider without cost data is charged the highest measured cost,
+        // so missing data never makes it look cheaper than a measured one.
+        for c in &mut candidates {
+            let cost = c.avg_cost_usd.unwrap_or(max_cost);
+            c.cost_penalty = config.cost_weight * cost / max_cost;
+            c.score = c.quality - c.cost_penalty;
+        }
+    }
+
+    let has_history = candidates.iter().any(|c| !c.evidence.is_empty());
+    let mut reasons = Vec::new();
+    let provider = if config.learned && has_history {
+        let best = candidates
+            .iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .map(|c| c.score)
+            .unwrap_or(PRIOR);
+        let leaders: Vec<&CandidateScore> = candidates
+            .iter()
+            .filter(|c| best - c.score < TIE_EPSILON)
+            .collect();
+        let chosen = if leaders.len() == 1 {
+            provider_named(&leaders[0].provider).unwrap_or(heuristic.clone())
+        } else {
+            reasons.push("scores tied; keyword heuristic broke the tie".to_string());
+            heuristic.clone()
+        };
+        reasons.insert(
+            0,
+            format!(
+                "{} scored highest ({:.2}) for {task_type} tasks",
+                chosen.as_str(),
+                best
+            ),
+        );
+        for c in &candidates {
+            reasons.extend(describe(c, min));
+        }
+        chosen
+    } else {
+        reasons.push(if config.learned {
+            format!("no provider has {min} or more recorded samples yet; used keyword heuristic")
+        } else {
+            "learned routing disabled; used keyword heuristic".to_string()
+        });
+        heuristic.clone()
+    };
+
+    let method = if config.learned && has_history {
+        "learned"
+    } else {
+        "heuristic"
+    };
+    tracing::debug!(
+        provider = provider.as_str(),
+        method,
+        task_type,
+        "provider auto-routing decision"
+    );
+
+    RoutingDecision {
+        provider,
+        explain: RoutingExplain {
+            method,
+            task_type: task_type.to_string(),
+            languages: repo_languages.to_vec(),
+            min_samples: min,
+            candidates,
+            reasons,
+        },
+    }
+}
+
+fn provider_named(name: &str) -> Option<Provider> {
+    Provider::CANDIDATES
+        .iter()
+        .find(|p| p.as_str() == name)
+        .cloned()
+}
+
+/// Most specific bucket with at least `min` trials:
+/// task type + language, then task type, then language, then everything.
+fn best_bucket(
+    source: &'static str,
+    records: &[ProviderRecord],
+    provider: &str,
+    task_type: &str,
+    languages: &[String],
+    min: u64,
+) -> Option<Evidence> {
+    let segmented = task_type != "all";
+    let mut scopes: Vec<(Option<&str>, Option<&str>)> = Vec::new();
+    if segmented {
+        scopes.extend(
+            languages
+                .iter()
+                .map(|l| (Some(task_type), Some(l.as_str()))),
+        );
+        scopes.push((Some(task_type), None));
+    }
+    scopes.extend(languages.iter().map(|l| (None, Some(l.as_str()))));
+    scopes.push((None, None));
+
+    scopes.into_iter().find_map(|(tt, lang)| {
+        let (wins, trials) = records
+            .iter()
+            .filter(|r| {
+                r.provider == provider
+                    && tt.is_none_or(|t| r.task_type == t)
+                    && lang.is_none_or(|l| r.language == l)
+            })
+            .fold((0, 0), |(w, t), r| (w + r.wins, t + r.trials));
+        (trials >= min).then(|| Evidence {
+            source,
+            task_type: tt.unwrap_or("all").to_string(),
+            language: lang.unwrap_or("all").to_string(),
+            wins,
+            trials,
+            rate: wins as f64 / trials as f64,
+        })
+    })
+}
+
+fn describe(c: &CandidateScore, min: u64) -> Vec<String> {
+    let mut out = Vec::new();
+    if c.evidence.is_empty() {
+        out.push(format!(
+            "{}: fewer than {min} samples in every bucket; assumed {PRIOR:.2}",
+            c.provider
+        ));
+    }
+    for e in &c.evidence {
+        let scope = match (e.task_type.as_str(), e.language.as_str()) {
+            ("all", "all") => "overall".to_string(),
+            ("all", lang) => format!("in {lang} repos"),
+            (tt, "all") => format!("on {tt} tasks"),
+            (tt, lang) => format!("on {tt} tasks in {lang} repos"),
+        };
+        let what = if e.source == "arena" {
+            "arena votes won"
+        } else {
+            "sessions completed without error"
+        };
+        out.push(format!(
+            "{}: {} of {} {what} {scope} ({:.0}%)",
+            c.provider,
+            e.wins,
+            e.trials,
+            e.rate * 100.0
+        ));
+    }
+    if let Some(cost) = c.avg_cost_usd {
+        out.push(format!(
+            "{}: averages ${cost:.3} per session (penalty {:.2})",
+            c.provider, c.cost_penalty
+        ));
+    } else if c.cost_penalty > 0.0 {
+        out.push(format!(
+            "{}: no cost data; assumed the highest measured cost (penalty {:.2})",
+            c.provider, c.cost_penalty
+        ));
+    }
+    out
+}
+
 // ─── Tests ────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -143,4 +574,146 @@ mod tests {
         assert_eq!(Provider::Claude.as_str(), "claude");
         assert_eq!(Provider::Codex.as_str(), "codex");
     }
+
+    fn record(
+        provider: &str,
+        task_type: &str,
+        language: &str,
+        wins: u64,
+        trials: u64,
+    ) -> ProviderRecord {
+        ProviderRecord {
+            provider: provider.to_string(),
+            task_type: task_type.to_string(),
+            language: language.to_string(),
+            wins,
+            trials,
+        }
+    }
+
+    fn rust() -> Vec<String> {
+        vec!["rust".to_string()]
+    }
+
+    #[test]
+    fn test_task_type_classification() {
+        assert_eq!(classify_task_type(Some("debug this crash")), "debug");
+        assert_eq!(classify_task_type(Some("refactor the parser")), "refactor");
+        assert_eq!(
+            classify_task_type(Some("explain how does this work")),
+            "explain"
+        );
+        assert_eq!(classify_task_type(Some("implement a cache")), "generate");
+        assert_eq!(classify_task_type(None), "general");
+    }
+
+    #[test]
+    fn test_learned_route_without_history_uses_heuristic() {
+        let d = route(
+            Some("debug this error"),
+            &rust(),
+            &RoutingHistory::default(),
+            &RoutingConfig::default(),
+        );
+        assert_eq!(d.provider, Provider::Codex);
+        assert_eq!(d.explain.method, "heuristic");
+        assert!(d.explain.reasons[0].contains("heuristic"));
+    }
+
+    #[test]
+    fn test_learned_route_prefers_language_specific_wins() {
+        // Overall codex wins debug tasks, but claude wins them in Rust repos.
+        let history = RoutingHistory {
+            arena: vec![
+                record("claude", "debug", "rust", 7, 9),
+                record("codex", "debug", "rust", 2, 9),
+                record("claude", "debug", "python", 1, 20),
+                record("codex", "debug", "python", 19, 20),
+            ],
+            ..Default::default()
+        };
+        let d = route(
+            Some("debug this error"),
+            &rust(),
+            &history,
+            &RoutingConfig::default(),
+        );
+        assert_eq!(d.provider, Provider::Claude);
+        assert_eq!(d.explain.method, "learned");
+        let claude = &d.explain.candidates[0];
+        assert_eq!(claude.evidence[0].language, "rust");
+        assert_eq!((claude.evidence[0].wins, claude.evidence[0].trials), (7, 9));
+    }
+
+    #[test]
+    fn test_learned_route_respects_min_samples() {
+        let history = RoutingHistory {
+            arena: vec![record("codex", "generate", "rust", 3, 3)],
+            ..Default::default()
+        };
+        let d = route(
+            Some("implement a parser"),
+            &rust(),
+            &history,
+            &RoutingConfig::default(),
+        );
+        assert_eq!(d.explain.method, "heuristic");
+        assert_eq!(d.provider, Provider::Claude);
+    }
+
+    #[test]
+    fn test_learned_route_applies_cost_penalty() {
+        // Equal quality; codex is a third of the price.
+        let history = RoutingHistory {
+            outcomes: vec![
+                record("claude", "all", "rust", 9, 10),
+                record("codex", "all", "rust", 9, 10),
+            ],
+            costs: HashMap::from([
+                ("claude".to_string(), (0.30, 10)),
+                ("codex".to_string(), (0.10, 10)),
+            ]),
+            ..Default::default()
+        };
+        let d = route(
+            Some("implement a parser"),
+            &rust(),
+            &history,
+            &RoutingConfig::default(),
+        );
+        assert_eq!(d.provider, Provider::Codex);
+        let claude = &d.explain.candidates[0];
+        assert!((claude.cost_penalty - 0.15).abs() < 1e-9);
+        assert!(d.explain.reasons.iter().any(|r| r.contains("per session")));
+
+        let ignore_cost = RoutingConfig {
+            cost_weight: 0.0,
+            ..Default::default()
+        };
+        let d = route(Some("implement a parser"), &rust(), &history, &ignore_cost);
+        assert_eq!(d.provider, Provider::Claude, "tie falls back to heuristic");
+    }
+
+    #[test]
+    fn test_unmeasured_cost_is_not_free() {
+        // Only claude has cost data.  Codex has no history at all and would
+        // win on its 0.50 prior if missing cost data were free.
+        let history = RoutingHistory {
+            outcomes: vec![record("claude", "all", "rust", 6, 10)],
+            costs: HashMap::from([("claude".to_string(), (0.30, 10))]),
+            ..Default::default()
+        };
+        let d = route(
+            Some("debug this error"),
+            &rust(),
+            &history,
+            &RoutingConfig::default(),
+        );
+        assert_eq!(d.provider, Provider::Claude);
+        let codex = &d.explain.candidates[1];
+        assert_eq!(codex.avg_cost_usd, None);
+        assert!((codex.cost_penalty - 0.15).abs() < 1e-9);
+        assert!((codex.score - 0.35).abs() < 1e-9);
+        assert!(d.explain.reasons.iter().any(|r| r.contains("no cost data")));
+    }
 }
@@ -29,4 +29,11 @@ pub trait Runner: Send + Sync {
 
     /// Shut down the runner cleanly.
     async fn stop(&self) -> Result<()>;
+
+    /// Deliver a user's decision on a pending tool call.  Returns `false`
+    /// when this runner was not waiting on that call — CLI runners execute
+    /// tools themselves, so for them approval is only recorded.
+    async fn decide_tool(&self, _tool_call_id: &str, _decision: ToolDecision) -> Result<bool> {
+        Ok(false)
+    }
 }
@@ -131,7 +131,11 @@ impl AuditLog {
             *guard = Some(f);
         }
 
-        guard.as_mut().unwrap().write_all(bytes).await?;
+        let file = guard.as_mut().unwrap();
+        file.write_all(bytes).await?;
+        // A tokio file finishes a write in the background; flush so the
+        // entry is on disk before the call it records returns.
+        file.flush().await?;
         Ok(())
     }
 }
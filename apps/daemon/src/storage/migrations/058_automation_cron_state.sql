@@ -0,0 +1,6 @@
+-- Migration 058: Cron automation state (last fire time per automation)
+-- Lets the cron scheduler survive restarts without double-firing or skipping.
+CREATE TABLE IF NOT EXISTS automation_cron_state (
+    name          TEXT PRIMARY KEY,
+    last_fired_at TEXT NOT NULL   -- RFC 3339, UTC
+);
@@ -0,0 +1,20 @@
+-- Migration 059: Per-step workflow run state (DAG recipes + resume after crash)
+
+-- Snapshot of what the run was started with, so it can be resumed even if
+-- the recipe is edited or deleted afterwards.
+ALTER TABLE workflow_runs ADD COLUMN recipe_yaml TEXT;
+ALTER TABLE workflow_runs ADD COLUMN inputs_json TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE workflow_runs ADD COLUMN repo_path TEXT NOT NULL DEFAULT '.';
+
+CREATE TABLE IF NOT EXISTS workflow_run_steps (
+    run_id      TEXT NOT NULL REFERENCES workflow_runs(id) ON DELETE CASCADE,
+    step_id     TEXT NOT NULL,
+    step_index  INTEGER NOT NULL,
+    status      TEXT NOT NULL DEFAULT 'pending',  -- pending|running|succeeded|failed|skipped
+    session_id  TEXT,
+    output      TEXT,
+    error       TEXT,
+    started_at  TEXT,
+    finished_at TEXT,
+    PRIMARY KEY (run_id, step_id)
+);
@@ -0,0 +1,7 @@
+-- Migration 060: Record which event launched a workflow run.
+-- Both columns are NULL for runs started manually via `workflow.run`.
+
+ALTER TABLE workflow_runs ADD COLUMN trigger_kind TEXT;   -- on_commit|on_task_done|on_file_change|on_session_complete
+ALTER TABLE workflow_runs ADD COLUMN trigger_event TEXT;  -- JSON: source, subject, detail, coalesced, firedAt
+
+CREATE INDEX IF NOT EXISTS idx_workflow_runs_recipe_status ON workflow_runs(recipe_id, status);
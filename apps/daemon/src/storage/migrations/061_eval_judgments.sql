@@ -0,0 +1,11 @@
+-- Migration 061: Cache of LLM-as-judge verdicts.
+-- Keyed by SHA-256 of (judge id, rubric, output) so re-scoring identical
+-- output with the same rubric and judge never calls the model again.
+
+CREATE TABLE IF NOT EXISTS eval_judgments (
+    cache_key   TEXT PRIMARY KEY,
+    judge       TEXT NOT NULL,
+    score       REAL NOT NULL,   -- 0.0–10.0
+    reasoning   TEXT NOT NULL DEFAULT '',
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,20 @@
+-- Migration 062: Durable scheduler queue.
+-- Rows exist only while a request is queued or running; finished, dequeued
+-- and cancelled requests are deleted.  Rows left 'running' by a crash are
+-- re-queued when the daemon starts.
+
+CREATE TABLE IF NOT EXISTS scheduler_queue (
+    id           TEXT PRIMARY KEY,
+    task_id      TEXT NOT NULL,
+    agent_id     TEXT NOT NULL,
+    role         TEXT NOT NULL,
+    provider     TEXT NOT NULL,
+    priority     INTEGER NOT NULL,            -- 0–255, higher = more urgent
+    enqueued_at  TEXT NOT NULL,               -- RFC 3339; drives priority aging
+    status       TEXT NOT NULL DEFAULT 'queued'
+                 CHECK (status IN ('queued', 'running')),
+    account_id   TEXT,                        -- slot holder while running
+    started_at   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduler_queue_status ON scheduler_queue(status);
@@ -0,0 +1,5 @@
+-- Migration 063: Why auto-routing picked a provider.
+-- JSON payload written when a session is created with provider = 'auto';
+-- cleared when the provider is changed explicitly.
+
+ALTER TABLE sessions ADD COLUMN routing_explain TEXT;
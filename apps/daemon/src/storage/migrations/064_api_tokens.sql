@@ -0,0 +1,19 @@
+-- Migration 064: Scoped API tokens.
+-- Only the SHA-256 of each secret is stored; the secret is shown once at
+-- creation.  `methods` and `repos` are JSON arrays — `repos` is NULL for a
+-- token that may touch any repo.
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id           TEXT PRIMARY KEY,
+    name         TEXT NOT NULL,
+    token_hash   TEXT NOT NULL UNIQUE,
+    prefix       TEXT NOT NULL,
+    methods      TEXT NOT NULL,
+    repos        TEXT,
+    created_at   INTEGER NOT NULL,
+    expires_at   INTEGER,
+    last_used_at INTEGER,
+    revoked_at   INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_created ON api_tokens(created_at DESC);
@@ -0,0 +1,92 @@
+-- Cross-entity full-text search (session.search).
+--
+-- Adds FTS5 indexes for agent tasks and the tool-call audit log alongside
+-- `session_fts` (028), and keeps `session_fts` current when a message's
+-- content changes.  Memory entries are indexed by `MemoryStore::migrate`,
+-- which owns the `memory_entries` table.
+--
+-- Each index stores the source row's id UNINDEXED rather than mapping
+-- rowids: the daily VACUUM may renumber rowids of tables keyed by TEXT ids.
+
+-- ─── Messages ────────────────────────────────────────────────────────────────
+-- Streaming turns rewrite `content` on every chunk; re-index only once the
+-- message has settled.
+CREATE TRIGGER IF NOT EXISTS messages_au_fts
+AFTER UPDATE OF content ON messages
+WHEN NEW.status != 'streaming'
+BEGIN
+    DELETE FROM session_fts WHERE message_id = OLD.id;
+    INSERT INTO session_fts(content, session_id, message_id, role, created_at)
+    VALUES (NEW.content, NEW.session_id, NEW.id, NEW.role, NEW.created_at);
+END;
+
+-- Re-index messages that were still empty placeholders when 028 ran.
+DELETE FROM session_fts
+WHERE message_id IN (SELECT id FROM messages WHERE status != 'streaming');
+INSERT INTO session_fts(content, session_id, message_id, role, created_at)
+SELECT content, session_id, id, role, created_at
+FROM messages
+WHERE status != 'streaming' AND content IS NOT NULL AND content != '';
+
+-- ─── Agent tasks ─────────────────────────────────────────────────────────────
+CREATE VIRTUAL TABLE IF NOT EXISTS task_fts USING fts5(
+    title,                            -- task title (indexed)
+    notes,                            -- free-text notes (indexed)
+    task_id      UNINDEXED,
+    created_at   UNINDEXED,          -- unix seconds
+    tokenize     = 'porter unicode61'
+);
+
+INSERT INTO task_fts(title, notes, task_id, created_at)
+SELECT title, COALESCE(notes, ''), id, created_at FROM agent_tasks;
+
+CREATE TRIGGER IF NOT EXISTS agent_tasks_ai_fts
+AFTER INSERT ON agent_tasks
+BEGIN
+    INSERT INTO task_fts(title, notes, task_id, created_at)
+    VALUES (NEW.title, COALESCE(NEW.notes, ''), NEW.id, NEW.created_at);
+END;
+
+CREATE TRIGGER IF NOT EXISTS agent_tasks_au_fts
+AFTER UPDATE OF title, notes ON agent_tasks
+BEGIN
+    DELETE FROM task_fts WHERE task_id = OLD.id;
+    INSERT INTO task_fts(title, notes, task_id, created_at)
+    VALUES (NEW.title, COALESCE(NEW.notes, ''), NEW.id, NEW.created_at);
+END;
+
+CREATE TRIGGER IF NOT EXISTS agent_tasks_ad_fts
+AFTER DELETE ON agent_tasks
+BEGIN
+    DELETE FROM task_fts WHERE task_id = OLD.id;
+END;
+
+-- ─── Tool-call audit log ─────────────────────────────────────────────────────
+-- The audit log is append-only; rows only leave through retention pruning.
+CREATE VIRTUAL TABLE IF NOT EXISTS tool_call_fts USING fts5(
+    tool_name,                        -- tool name (indexed)
+    sanitized_input,                  -- redacted arguments (indexed)
+    rejection_reason,                 -- why it was refused, if it was (indexed)
+    event_id     UNINDEXED,
+    session_id   UNINDEXED,
+    created_at   UNINDEXED,          -- RFC 3339 timestamp
+    tokenize     = 'porter unicode61'
+);
+
+INSERT INTO tool_call_fts(tool_name, sanitized_input, rejection_reason, event_id, session_id, created_at)
+SELECT tool_name, COALESCE(sanitized_input, ''), COALESCE(rejection_reason, ''), id, session_id, created_at
+FROM tool_call_events;
+
+CREATE TRIGGER IF NOT EXISTS tool_call_events_ai_fts
+AFTER INSERT ON tool_call_events
+BEGIN
+    INSERT INTO tool_call_fts(tool_name, sanitized_input, rejection_reason, event_id, session_id, created_at)
+    VALUES (NEW.tool_name, COALESCE(NEW.sanitized_input, ''), COALESCE(NEW.rejection_reason, ''),
+            NEW.id, NEW.session_id, NEW.created_at);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tool_call_events_ad_fts
+AFTER DELETE ON tool_call_events
+BEGIN
+    DELETE FROM tool_call_fts WHERE event_id = OLD.id;
+END;
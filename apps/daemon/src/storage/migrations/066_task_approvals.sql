@@ -0,0 +1,21 @@
+-- Migration 066: Pending human approvals from MCP `request_approval`.
+-- agent_tasks.status has no approval state (its CHECK predates approvals),
+-- so the task is parked as `blocked` and the request is kept here until
+-- `approval.respond` grants or denies it.
+
+CREATE TABLE IF NOT EXISTS task_approvals (
+    approval_id  TEXT PRIMARY KEY,
+    task_id      TEXT NOT NULL REFERENCES agent_tasks(id) ON DELETE CASCADE,
+    repo_path    TEXT NOT NULL,
+    agent_id     TEXT NOT NULL,
+    tool_name    TEXT NOT NULL,
+    risk_level   TEXT NOT NULL,
+    arguments    TEXT NOT NULL DEFAULT '{}',   -- JSON
+    status       TEXT NOT NULL DEFAULT 'pending'
+        CHECK(status IN ('pending','granted','denied')),
+    requested_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    resolved_at  INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_approvals_pending
+    ON task_approvals(status, repo_path, requested_at);
@@ -47,6 +47,8 @@ pub struct SessionRow {
     /// Explicit model override set by the user via session.setModel.
     /// NULL = auto-route; non-NULL bypasses the classifier.
     pub model_override: Option<String>,
+    /// JSON explanation of the auto-routing decision, if any.
+    pub routing_explain: Option<String>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -327,18 +329,26 @@ impl Storage {
     }
 
     /// Set the `routed_provider` for a session (used when `provider = "auto"`).
+    ///
+    /// `routing_explain` is the JSON reason for an auto-routing decision;
+    /// pass `None` for explicit provider changes so a stale reason is cleared.
     pub async fn update_session_routed_provider(
         &self,
         id: &str,
         routed_provider: &str,
+        routing_explain: Option<&str>,
     ) -> Result<()> {
         let now = Utc::now().to_rfc3339();
-        sqlx::query("UPDATE sessions SET routed_provider = ?, updated_at = ? WHERE id = ?")
-            .bind(routed_provider)
-            .bind(&now)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+        sqlx::query(
+            "UPDATE sessions SET routed_provider = ?, routing_explain = ?, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(routed_provider)
+        .bind(routing_explain)
+        .bind(&now)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
         Ok(())
     }
 
@@ -713,6 +723,19 @@ impl Storage {
         Ok(())
     }
 
+    /// Bump and return the event-id epoch, one per daemon start.  Event ids
+    /// carry it in their high bits, so ids keep rising across restarts.
+    pub async fn next_event_epoch(&self) -> Result<u64> {
+        let (epoch,): (i64,) = sqlx::query_as(
+            "INSERT INTO settings (key, value) VALUES ('event_epoch', '1')
+             ON CONFLICT(key) DO UPDATE SET value = CAST(value AS INTEGER) + 1
+             RETURNING CAST(value AS INTEGER)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(epoch as u64)
+    }
+
     // ─── License cache ───────────────────────────────────────────────────────
 
     pub async fn get_license_cache(&self) -> Result<Option<LicenseCacheRow>> {
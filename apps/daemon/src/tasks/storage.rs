@@ -96,6 +96,23 @@ pub struct WorkSessionRow {
     pub repo_path: String,
 }
 
+/// A human approval requested for a task (`task_approvals`), with the
+/// task's title.
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ApprovalRow {
+    pub approval_id: String,
+    pub task_id: String,
+    pub title: String,
+    pub repo_path: String,
+    pub agent_id: String,
+    pub tool_name: String,
+    pub risk_level: String,
+    pub arguments: String,
+    pub status: String,
+    pub requested_at: i64,
+    pub resolved_at: Option<i64>,
+}
+
 // ─── Query params ─────────────────────────────────────────────────────────────
 
 #[derive(Debug, Default, Deserialize)]
@@ -840,6 +857,103 @@ impl TaskStorage {
         Ok(())
     }
 
+    // ─── Approvals ────────────────────────────────────────────────────────────
+
+    /// Record a pending approval for `task_id` and park the task as
+    /// `blocked` until it is answered.
+    pub async fn request_approval(
+        &self,
+        approval_id: &str,
+        task_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        risk_level: &str,
+        arguments: &str,
+    ) -> Result<()> {
+        let task = self
+            .get_task(task_id)
+            .await?
+            .ok_or_else(|| anyhow!("TASK_CODE:{}", TASK_NOT_FOUND))?;
+        sqlx::query(
+            "INSERT INTO task_approvals
+                 (approval_id, task_id, repo_path, agent_id, tool_name, risk_level, arguments, requested_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(approval_id)
+        .bind(task_id)
+        .bind(&task.repo_path)
+        .bind(agent_id)
+        .bind(tool_name)
+        .bind(risk_level)
+        .bind(arguments)
+        .bind(now_ts())
+        .execute(&self.pool)
+        .await?;
+        self.update_status(
+            task_id,
+            "blocked",
+            None,
+            Some(&format!("awaiting approval for {tool_name}")),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Pending approvals, oldest first.
+    pub async fn list_pending_approvals(
+        &self,
+        repo_path: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ApprovalRow>> {
+        Ok(sqlx::query_as(
+            "SELECT a.*, t.title FROM task_approvals a
+             JOIN agent_tasks t ON t.id = a.task_id
+             WHERE a.status = 'pending' AND (? IS NULL OR a.repo_path = ?)
+             ORDER BY a.requested_at, a.approval_id
+             LIMIT ? OFFSET ?",
+        )
+        .bind(repo_path)
+        .bind(repo_path)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn get_approval(&self, approval_id: &str) -> Result<Option<ApprovalRow>> {
+        Ok(sqlx::query_as(
+            "SELECT a.*, t.title FROM task_approvals a
+             JOIN agent_tasks t ON t.id = a.task_id
+             WHERE a.approval_id = ?",
+        )
+        .bind(approval_id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Mark a pending approval `granted` or `denied`.  `None` when it does
+    /// not exist or was already answered.
+    pub async fn resolve_approval(
+        &self,
+        approval_id: &str,
+        granted: bool,
+    ) -> Result<Option<ApprovalRow>> {
+        let updated = sqlx::query(
+            "UPDATE task_approvals SET status = ?, resolved_at = ?
+             WHERE approval_id = ? AND status = 'pending'",
+        )
+        .bind(if granted { "granted" } else { "denied" })
+        .bind(now_ts())
+        .bind(approval_id)
+        .execute(&self.pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_approval(approval_id).await
+    }
+
     // ─── Work sessions ────────────────────────────────────────────────────────
 
     pub async fn open_work_session(&self, repo_path: &str) -> Result<WorkSessionRow> {
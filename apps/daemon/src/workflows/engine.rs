@@ -1,25 +1,45 @@
 //! Sprint DD WR.2/WR.3 — Workflow recipe engine.
 //!
 //! Executes multi-step AI workflow recipes. Each step creates a session and
-//! runs a prompt, optionally inheriting context from the previous step.
+//! runs a prompt, optionally inheriting context from an earlier step.
+//! Steps form a DAG — see `workflows::plan` for ordering rules and
+//! `workflows::runner` for execution.
 //!
 //! ## Recipe YAML format
 //!
 //! ```yaml
 //! name: code-review
 //! description: Review a diff and create follow-up tasks
+//! inputs:
+//!   - name: diff
+//!     from: git_diff          # computed when not passed to workflow.run
+//! max_parallel: 2
 //! steps:
-//!   - prompt: "Review the changes in this diff: {diff}"
+//!   - id: review
+//!     prompt: "Review the changes in this diff: {diff}"
 //!     provider: claude
-//!   - prompt: "Create follow-up tasks for the issues found"
-//!     inherit_from: previous
+//!   - id: tests
+//!     prompt: "List missing test cases for: {diff}"
+//!     depends_on: []          # runs in parallel with `review`
+//!   - id: tasks
+//!     prompt: "Create tasks for: {steps.review.output}\n{steps.tests.output}"
+//!     inherit_from: review
+//!   - id: retry
+//!     prompt: "The review step failed; summarise the diff instead: {diff}"
+//!     when: review.failed
 //! triggers:
 //!   - on_commit
+//! debounce_secs: 30           # coalesce bursts of trigger events
+//! max_concurrent_runs: 1      # triggered runs beyond this are skipped
 //! ```
+//!
+//! Triggers are dispatched by `workflows::triggers`.
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use super::plan::WorkflowPlan;
+
 /// A workflow recipe definition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -41,24 +61,46 @@ pub struct WorkflowRecipe {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowStep {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub provider: Option<String>,
-    /// If `"previous"`, this step inherits context from the prior step's session.
+    /// `"previous"` or a step id: this step continues that step's session.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inherit_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+    /// `<step>.succeeded`, `<step>.failed` or `<step>.skipped`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
 }
 
 /// Events that automatically trigger a workflow.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkflowTrigger {
+    /// `HEAD` moved in a watched repository.
     OnCommit,
+    /// A task reached `done`.
     OnTaskDone,
+    /// Files changed in a watched repository.
     OnFileChange,
+    /// A session finished a turn (ran to `idle` or `error`).
     OnSessionComplete,
 }
 
+impl WorkflowTrigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OnCommit => "on_commit",
+            Self::OnTaskDone => "on_task_done",
+            Self::OnFileChange => "on_file_change",
+            Self::OnSessionComplete => "on_session_complete",
+        }
+    }
+}
+
 /// A running workflow instance.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -70,6 +112,9 @@ pub struct WorkflowRun {
     pub total_steps: i64,
     pub started_at: String,
     pub finished_at: Option<String>,
+    /// The event that launched this run; `None` for manual runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<serde_json::Value>,
 }
 
 /// Parse a workflow recipe from YAML.
@@ -78,6 +123,13 @@ pub fn parse_recipe_yaml(yaml: &str) -> Result<WorkflowRecipeYaml> {
     Ok(recipe)
 }
 
+/// Parse a recipe and validate its step graph.
+pub fn parse_and_plan(yaml: &str) -> Result<(WorkflowRecipeYaml, WorkflowPlan)> {
+    let recipe = parse_recipe_yaml(yaml)?;
+    let plan = WorkflowPlan::build(&recipe)?;
+    Ok((recipe, plan))
+}
+
 /// Raw YAML deserialization form (before DB storage).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowRecipeYaml {
@@ -89,15 +141,58 @@ pub struct WorkflowRecipeYaml {
     pub tags: Vec<String>,
     #[serde(default)]
     pub triggers: Vec<WorkflowTrigger>,
+    /// Values the caller supplies to `workflow.run`, referenced as `{name}`.
+    #[serde(default)]
+    pub inputs: Vec<WorkflowInput>,
+    /// Cap on concurrently running steps (default 4).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
+    /// Quiet period before a triggered run starts; events arriving within
+    /// it are coalesced into one run (default 10).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debounce_secs: Option<u64>,
+    /// Cap on triggered runs of this recipe in flight at once (default 1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_runs: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowStepYaml {
+    /// Step name used by `depends_on`, `when` and `{steps.<id>.output}`.
+    /// Defaults to `step-<n>` (1-based).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub provider: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inherit_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+/// A named recipe input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Used when the caller does not supply a value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Computed by the daemon when the caller does not supply a value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<InputSource>,
+}
+
+/// Daemon-computed input values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputSource {
+    /// Unified diff of the working tree (staged + unstaged) against `HEAD`.
+    GitDiff,
 }
 
 /// 5 built-in workflow recipes shipped with clawd.
@@ -116,11 +211,17 @@ name: code-review
 description: Review a diff and create follow-up tasks for issues found
 tags: [review, quality]
 triggers: [on_commit]
+inputs:
+  - name: diff
+    description: Unified diff to review (defaults to uncommitted changes)
+    from: git_diff
 steps:
-  - prompt: "Review the recent git diff. List all issues, bugs, and improvements as a numbered list."
+  - id: review
+    prompt: "Review the following diff. List all issues, bugs, and improvements as a numbered list.\n\n{diff}"
     provider: codex
-  - prompt: "For each issue you found, create a task with `task.create`. Use the issue description as the title."
-    inherit_from: previous
+  - id: tasks
+    prompt: "For each issue you found, create a task with `task.create`. Use the issue description as the title."
+    inherit_from: review
 "#;
 
 const RELEASE_PREP_YAML: &str = r#"
@@ -197,6 +298,16 @@ steps:
         assert_eq!(recipe.steps.len(), 1);
     }
 
+    #[test]
+    fn test_builtin_recipes_plan() {
+        for recipe in builtin_recipes() {
+            WorkflowPlan::build(&recipe).unwrap_or_else(|e| panic!("{}: {e}", recipe.name));
+        }
+        let review = parse_recipe_yaml(CODE_REVIEW_YAML).unwrap();
+        assert!(review.steps[0].prompt.contains("{diff}"));
+        assert_eq!(review.inputs[0].from, Some(InputSource::GitDiff));
+    }
+
     #[test]
     fn test_trigger_deserialization() {
         let yaml = r#"
@@ -1,3 +1,6 @@
 //! Sprint DD — Workflow Recipe system.
 
 pub mod engine;
+pub mod plan;
+pub mod runner;
+pub mod triggers;
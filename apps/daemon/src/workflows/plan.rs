@@ -0,0 +1,613 @@
+//! Workflow DAG planning — validation, step readiness and prompt templating.
+//!
+//! A [`WorkflowPlan`] is built from a parsed [`WorkflowRecipeYaml`] and is
+//! pure data: the runner (`workflows::runner`) asks it which steps are ready
+//! given the current step states, and renders prompts from recipe inputs and
+//! earlier step outputs.
+//!
+//! ## Ordering rules
+//!
+//! - If no step declares `depends_on`, the recipe is a straight line: each
+//!   step depends on the one before it (the pre-DAG behaviour).
+//! - Otherwise `depends_on` is authoritative and steps without it (or with
+//!   `depends_on: []`) start immediately, in parallel.
+//! - Referencing another step — `{steps.<id>.output}` in the prompt, a
+//!   `when:` condition or `inherit_from` — implicitly depends on it.
+//!
+//! A step runs once all its dependencies have finished. Without `when:` it
+//! runs only if every dependency succeeded and is skipped otherwise; with
+//! `when: <step>.<succeeded|failed|skipped>` it runs exactly when that holds.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{InputSource, WorkflowInput, WorkflowRecipeYaml};
+
+/// Default cap on concurrently running steps within one run.
+pub const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// `{name}`, `{inputs.name}` or `{steps.<id>.output|status}`.
+static PLACEHOLDER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_\-]*(?:\.[A-Za-z0-9_\-]+)*)\}").unwrap());
+
+// ─── Step status ───────────────────────────────────────────────────────────
+
+/// Persisted state of one step within a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    /// True once the step will not change again in this run.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            StepStatus::Succeeded | StepStatus::Failed | StepStatus::Skipped
+        )
+    }
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for StepStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "pending" => StepStatus::Pending,
+            "running" => StepStatus::Running,
+            "succeeded" => StepStatus::Succeeded,
+            "failed" => StepStatus::Failed,
+            "skipped" => StepStatus::Skipped,
+            other => bail!("unknown step status: {other}"),
+        })
+    }
+}
+
+// ─── Plan ──────────────────────────────────────────────────────────────────
+
+/// `when: <step>.<outcome>` on a planned step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepCondition {
+    pub step: String,
+    pub outcome: StepStatus,
+}
+
+/// A validated step with resolved id and dependencies.
+#[derive(Debug, Clone)]
+pub struct PlannedStep {
+    pub id: String,
+    /// Position in the recipe's `steps` list (0-based).
+    pub index: usize,
+    pub prompt: String,
+    pub provider: Option<String>,
+    /// Step whose session this step continues, if any.
+    pub inherit_from: Option<String>,
+    pub depends_on: Vec<String>,
+    pub when: Option<StepCondition>,
+}
+
+/// What the runner should do with a pending step right now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepDecision {
+    Run(String),
+    Skip(String),
+}
+
+/// A validated, acyclic workflow.
+#[derive(Debug, Clone)]
+pub struct WorkflowPlan {
+    pub steps: Vec<PlannedStep>,
+    pub inputs: Vec<WorkflowInput>,
+    pub max_parallel: usize,
+}
+
+/// Input values known up front, plus inputs the caller must compute.
+pub type ResolvedInputs = (HashMap<String, String>, Vec<(String, InputSource)>);
+
+impl WorkflowPlan {
+    /// Validate a recipe and resolve step ids, dependencies and conditions.
+    pub fn build(recipe: &WorkflowRecipeYaml) -> Result<Self> {
+        if recipe.steps.is_empty() {
+            bail!("recipe '{}' has no steps", recipe.name);
+        }
+
+        let ids: Vec<String> = recipe
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s.id.clone().unwrap_or_else(|| format!("step-{}", i + 1)))
+            .collect();
+        let mut seen = HashSet::new();
+        for id in &ids {
+            if id.is_empty() || id.contains('.') {
+                bail!("invalid step id '{id}': must be non-empty and contain no '.'");
+            }
+            if !seen.insert(id.as_str()) {
+                bail!("duplicate step id '{id}'");
+            }
+        }
+
+        let mut input_names = HashSet::new();
+        for input in &recipe.inputs {
+            if !input_names.insert(input.name.as_str()) {
+                bail!("duplicate input '{}'", input.name);
+            }
+        }
+
+        let linear = recipe.steps.iter().all(|s| s.depends_on.is_none());
+        let mut inherited_by: HashMap<String, String> = HashMap::new();
+        let mut steps = Vec::with_capacity(recipe.steps.len());
+
+        for (i, raw) in recipe.steps.iter().enumerate() {
+            let id = ids[i].clone();
+            let known = |dep: &str, what: &str| -> Result<()> {
+                if dep == id {
+                    bail!("step '{id}' cannot {what} itself");
+                }
+                if !ids.iter().any(|s| s == dep) {
+                    bail!("step '{id}' {what} unknown step '{dep}'");
+                }
+                Ok(())
+            };
+
+            let mut deps: Vec<String> = Vec::new();
+            let mut add_dep = |dep: &str| {
+                if !deps.iter().any(|d| d == dep) {
+                    deps.push(dep.to_string());
+                }
+            };
+
+            if linear && i > 0 {
+                add_dep(&ids[i - 1]);
+            }
+            for dep in raw.depends_on.iter().flatten() {
+                known(dep, "depends on")?;
+                add_dep(dep);
+            }
+
+            let inherit_from = match raw.inherit_from.as_deref() {
+                None => None,
+                Some("previous") => {
+                    if i == 0 {
+                        bail!("step '{id}' inherits from 'previous' but is the first step");
+                    }
+                    Some(ids[i - 1].clone())
+                }
+                Some(other) => {
+                    known(other, "inherits from")?;
+                    Some(other.to_string())
+                }
+            };
+            if let Some(parent) = &inherit_from {
+                // One session can only carry one conversation forward.
+                if let Some(other) = inherited_by.insert(parent.clone(), id.clone()) {
+                    bail!("steps '{other}' and '{id}' both inherit the session of '{parent}'");
+                }
+                add_dep(parent);
+            }
+
+            let when = match raw.when.as_deref() {
+                None => None,
+                Some(expr) => {
+                    let cond = parse_when(expr)
+                        .ok_or_else(|| anyhow::anyhow!(
+                            "step '{id}': invalid when '{expr}' (expected '<step>.succeeded', '<step>.failed' or '<step>.skipped')"
+                        ))?;
+                    known(&cond.step, "has a condition on")?;
+                    add_dep(&cond.step);
+                    Some(cond)
+                }
+            };
+
+            for r in template_refs(&raw.prompt) {
+                match r {
+                    TemplateRef::Step { step, .. } => {
+                        known(&step, "references")?;
+                        add_dep(&step);
+                    }
+                    TemplateRef::Input(name) if name.starts_with("inputs.") => {
+                        let bare = &name["inputs.".len()..];
+                        if !input_names.contains(bare) {
+                            bail!("step '{id}' references undeclared input '{bare}'");
+                        }
+                    }
+                    // Bare `{word}` may be literal text (e.g. code); only
+                    // declared inputs are substituted.
+                    TemplateRef::Input(_) => {}
+                }
+            }
+
+            steps.push(PlannedStep {
+                id,
+                index: i,
+                prompt: raw.prompt.clone(),
+                provider: raw.provider.clone(),
+                inherit_from,
+                depends_on: deps,
+                when,
+            });
+        }
+
+        let plan = Self {
+            steps,
+            inputs: recipe.inputs.clone(),
+            max_parallel: recipe.max_parallel.unwrap_or(DEFAULT_MAX_PARALLEL).max(1),
+        };
+        plan.check_acyclic()?;
+        Ok(plan)
+    }
+
+    pub fn step(&self, id: &str) -> Option<&PlannedStep> {
+        self.steps.iter().find(|s| s.id == id)
+    }
+
+    /// Decide what to do with every pending step whose dependencies are all
+    /// terminal. Steps that are still waiting are not returned.
+    pub fn decide(&self, states: &HashMap<String, StepStatus>) -> Vec<StepDecision> {
+        let status = |id: &str| states.get(id).copied().unwrap_or(StepStatus::Pending);
+        self.steps
+            .iter()
+            .filter(|s| status(&s.id) == StepStatus::Pending)
+            .filter(|s| s.depends_on.iter().all(|d| status(d).is_terminal()))
+            .map(|s| {
+                let run = match &s.when {
+                    Some(cond) => status(&cond.step) == cond.outcome,
+                    None => s
+                        .depends_on
+                        .iter()
+                        .all(|d| status(d) == StepStatus::Succeeded),
+                };
+                // An inherited session only exists if the parent actually ran.
+                let parent_ran = s
+                    .inherit_from
+                    .as_deref()
+                    .map(|p| matches!(status(p), StepStatus::Succeeded | StepStatus::Failed))
+                    .unwrap_or(true);
+                if run && parent_ran {
+                    StepDecision::Run(s.id.clone())
+                } else {
+                    StepDecision::Skip(s.id.clone())
+                }
+            })
+            .collect()
+    }
+
+    /// Resolve recipe inputs: explicit values win, then defaults. Inputs with
+    /// a `from:` source and no explicit value are returned in the second
+    /// element for the caller to compute. Missing required inputs are errors.
+    pub fn resolve_inputs(&self, provided: &HashMap<String, String>) -> Result<ResolvedInputs> {
+        let mut values = provided.clone();
+        let mut computed = Vec::new();
+        for input in &self.inputs {
+            if values.contains_key(&input.name) {
+                continue;
+            }
+            if let Some(source) = input.from {
+                computed.push((input.name.clone(), source));
+            } else if let Some(default) = &input.default {
+                values.insert(input.name.clone(), default.clone());
+            } else {
+                bail!("missing required input '{}'", input.name);
+            }
+        }
+        Ok((values, computed))
+    }
+
+    fn check_acyclic(&self) -> Result<()> {
+        // Kahn's algorithm over the resolved dependency lists.
+        let mut indegree: HashMap<&str, usize> = self
+            .steps
+            .iter()
+            .map(|s| (s.id.as_str(), s.depends_on.len()))
+            .collect();
+        let mut ready: Vec<&str> = indegree
+            .iter()
+            .filter(|(_, &n)| n == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut visited = 0;
+        while let Some(id) = ready.pop() {
+            visited += 1;
+            for s in self
+                .steps
+                .iter()
+                .filter(|s| s.depends_on.iter().any(|d| d == id))
+            {
+                let n = indegree.get_mut(s.id.as_str()).expect("known step");
+                *n -= 1;
+                if *n == 0 {
+                    ready.push(s.id.as_str());
+                }
+            }
+        }
+        if visited != self.steps.len() {
+            let mut stuck: Vec<&str> = indegree
+                .into_iter()
+                .filter(|(_, n)| *n > 0)
+                .map(|(id, _)| id)
+                .collect();
+            stuck.sort_unstable();
+            bail!("dependency cycle between steps: {}", stuck.join(", "));
+        }
+        Ok(())
+    }
+}
+
+fn parse_when(expr: &str) -> Option<StepCondition> {
+    let (step, outcome) = expr.trim().rsplit_once('.')?;
+    let outcome = match outcome {
+        "succeeded" => StepStatus::Succeeded,
+        "failed" => StepStatus::Failed,
+        "skipped" => StepStatus::Skipped,
+        _ => return None,
+    };
+    Some(StepCondition {
+        step: step.to_string(),
+        outcome,
+    })
+}
+
+// ─── Templating ────────────────────────────────────────────────────────────
+
+/// A placeholder found in a prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateRef {
+    /// `{name}` or `{inputs.name}` (the raw text inside the braces).
+    Input(String),
+    /// `{steps.<id>.output}` or `{steps.<id>.status}`.
+    Step { step: String, field: String },
+}
+
+/// All placeholders in `template`, in order of appearance.
+pub fn template_refs(template: &str) -> Vec<TemplateRef> {
+    PLACEHOLDER_RE
+        .captures_iter(template)
+        .map(|c| {
+            let inner = &c[1];
+            match inner
+                .strip_prefix("steps.")
+                .and_then(|r| r.rsplit_once('.'))
+            {
+                Some((step, field)) if field == "output" || field == "status" => {
+                    TemplateRef::Step {
+                        step: step.to_string(),
+                        field: field.to_string(),
+                    }
+                }
+                _ => TemplateRef::Input(inner.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Output and status of a finished step, for template rendering.
+#[derive(Debug, Clone, Default)]
+pub struct StepOutput {
+    pub status: Option<StepStatus>,
+    pub output: String,
+}
+
+/// Substitute placeholders. Unknown bare `{word}` placeholders are left as-is.
+pub fn render_template(
+    template: &str,
+    inputs: &HashMap<String, String>,
+    steps: &HashMap<String, StepOutput>,
+) -> String {
+    PLACEHOLDER_RE
+        .replace_all(template, |c: &regex::Captures<'_>| {
+            let inner = &c[1];
+            if let Some(rest) = inner.strip_prefix("steps.") {
+                if let Some((step, field)) = rest.rsplit_once('.') {
+                    if let Some(out) = steps.get(step) {
+                        match field {
+                            "output" => return out.output.clone(),
+                            "status" => {
+                                return out
+                                    .status
+                                    .map(|s| s.as_str().to_string())
+                                    .unwrap_or_default()
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            let key = inner.strip_prefix("inputs.").unwrap_or(inner);
+            inputs.get(key).cloned().unwrap_or_else(|| c[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflows::engine::parse_recipe_yaml;
+
+    fn plan(yaml: &str) -> Result<WorkflowPlan> {
+        WorkflowPlan::build(&parse_recipe_yaml(yaml)?)
+    }
+
+    fn states(pairs: &[(&str, StepStatus)]) -> HashMap<String, StepStatus> {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn legacy_recipes_are_linear() {
+        let p = plan(
+            r#"
+name: linear
+steps:
+  - prompt: "one"
+  - prompt: "two"
+    inherit_from: previous
+  - prompt: "three"
+"#,
+        )
+        .unwrap();
+        assert_eq!(p.steps[0].id, "step-1");
+        assert!(p.steps[0].depends_on.is_empty());
+        assert_eq!(p.steps[1].depends_on, vec!["step-1"]);
+        assert_eq!(p.steps[1].inherit_from.as_deref(), Some("step-1"));
+        assert_eq!(p.steps[2].depends_on, vec!["step-2"]);
+        assert_eq!(
+            p.decide(&HashMap::new()),
+            vec![StepDecision::Run("step-1".into())]
+        );
+    }
+
+    #[test]
+    fn independent_steps_fan_out_and_join() {
+        let p = plan(
+            r#"
+name: fan
+steps:
+  - id: lint
+    prompt: "lint"
+    depends_on: []
+  - id: test
+    prompt: "test"
+  - id: report
+    prompt: "Lint: {steps.lint.output} Tests: {steps.test.output}"
+"#,
+        )
+        .unwrap();
+        // `depends_on: []` opts out of linear ordering; `report` has no
+        // depends_on but references both outputs.
+        assert!(p.step("test").unwrap().depends_on.is_empty());
+        assert_eq!(p.step("report").unwrap().depends_on, vec!["lint", "test"]);
+
+        let p = plan(
+            r#"
+name: fan
+steps:
+  - id: lint
+    prompt: "lint"
+  - id: test
+    prompt: "test"
+  - id: report
+    prompt: "{steps.lint.output}"
+    depends_on: [test]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            p.decide(&HashMap::new()),
+            vec![
+                StepDecision::Run("lint".into()),
+                StepDecision::Run("test".into())
+            ]
+        );
+        let s = states(&[
+            ("lint", StepStatus::Succeeded),
+            ("test", StepStatus::Running),
+        ]);
+        assert!(p.decide(&s).is_empty());
+    }
+
+    #[test]
+    fn when_conditions_and_failure_propagation() {
+        let p = plan(
+            r#"
+name: branching
+steps:
+  - id: build
+    prompt: "build"
+  - id: fix
+    prompt: "fix it"
+    when: build.failed
+  - id: ship
+    prompt: "ship"
+    depends_on: [build]
+"#,
+        )
+        .unwrap();
+        let s = states(&[("build", StepStatus::Failed)]);
+        assert_eq!(
+            p.decide(&s),
+            vec![
+                StepDecision::Run("fix".into()),
+                StepDecision::Skip("ship".into())
+            ]
+        );
+        let s = states(&[("build", StepStatus::Succeeded)]);
+        assert_eq!(
+            p.decide(&s),
+            vec![
+                StepDecision::Skip("fix".into()),
+                StepDecision::Run("ship".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_recipes_are_rejected() {
+        let cases = [
+            // unknown dependency
+            "name: x\nsteps:\n  - id: a\n    prompt: p\n    depends_on: [b]\n",
+            // cycle
+            "name: x\nsteps:\n  - id: a\n    prompt: p\n    depends_on: [b]\n  - id: b\n    prompt: p\n    depends_on: [a]\n",
+            // duplicate id
+            "name: x\nsteps:\n  - id: a\n    prompt: p\n  - id: a\n    prompt: p\n",
+            // bad when
+            "name: x\nsteps:\n  - id: a\n    prompt: p\n  - id: b\n    prompt: p\n    when: a.done\n",
+            // unknown step in template
+            "name: x\nsteps:\n  - id: a\n    prompt: \"{steps.zzz.output}\"\n",
+            // undeclared input
+            "name: x\nsteps:\n  - id: a\n    prompt: \"{inputs.diff}\"\n",
+            // two steps continuing one session
+            "name: x\nsteps:\n  - id: a\n    prompt: p\n  - id: b\n    prompt: p\n    inherit_from: a\n  - id: c\n    prompt: p\n    inherit_from: a\n",
+        ];
+        for yaml in cases {
+            assert!(plan(yaml).is_err(), "should reject:\n{yaml}");
+        }
+    }
+
+    #[test]
+    fn render_fills_inputs_and_outputs() {
+        let inputs: HashMap<String, String> = [("diff".to_string(), "+added".to_string())].into();
+        let steps: HashMap<String, StepOutput> = [(
+            "review".to_string(),
+            StepOutput {
+                status: Some(StepStatus::Succeeded),
+                output: "1. bug".into(),
+            },
+        )]
+        .into();
+        let out = render_template(
+            "Diff: {diff} / {inputs.diff}. Review ({steps.review.status}): {steps.review.output}. Keep {this} and { spaced }",
+            &inputs,
+            &steps,
+        );
+        assert_eq!(
+            out,
+            "Diff: +added / +added. Review (succeeded): 1. bug. Keep {this} and { spaced }"
+        );
+    }
+}
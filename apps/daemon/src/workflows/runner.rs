@@ -0,0 +1,700 @@
+//! Workflow run execution.
+//!
+//! A run walks the recipe's [`WorkflowPlan`], launching every ready step (up
+//! to `max_parallel` at once) through a [`StepExecutor`]. Each step's status,
+//! session, output and error are persisted in `workflow_run_steps` as they
+//! change, so a run interrupted by a daemon crash can be resumed: succeeded
+//! steps keep their outputs and everything else is re-planned.
+//!
+//! Push events: `workflow.stepStarted`, `workflow.stepCompleted`, then
+//! `workflow.ran` (all steps finished without failure) or `workflow.failed`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use sqlx::{Row as _, SqlitePool};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::engine::{parse_and_plan, InputSource};
+use super::plan::{render_template, StepDecision, StepOutput, StepStatus, WorkflowPlan};
+use super::triggers::WorkflowEvent;
+use crate::ipc::event::EventBroadcaster;
+use crate::AppContext;
+
+/// How long a single step may run before it is cancelled and marked failed.
+const STEP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Interval between session status checks while a step runs.
+const STEP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// ─── Step execution ────────────────────────────────────────────────────────
+
+/// Everything an executor needs to run one step.
+#[derive(Debug, Clone)]
+pub struct StepRequest {
+    pub run_id: String,
+    pub step_id: String,
+    /// Session title, e.g. `"code-review — review"`.
+    pub title: String,
+    /// Prompt with all placeholders rendered.
+    pub prompt: String,
+    pub provider: String,
+    pub repo_path: String,
+    /// Session to continue instead of creating a new one.
+    pub inherit_session: Option<String>,
+}
+
+/// Outcome of one step.
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    /// `Succeeded` or `Failed`.
+    pub status: StepStatus,
+    pub session_id: Option<String>,
+    /// The step's final assistant message (empty on failure).
+    pub output: String,
+    pub error: Option<String>,
+}
+
+impl StepResult {
+    pub fn failed(session_id: Option<String>, error: impl Into<String>) -> Self {
+        Self {
+            status: StepStatus::Failed,
+            session_id,
+            output: String::new(),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Runs a single workflow step to completion.
+#[async_trait]
+pub trait StepExecutor: Send + Sync {
+    async fn run_step(&self, req: StepRequest) -> StepResult;
+}
+
+/// Production executor: one AI session per step (or a continued session for
+/// `inherit_from`), waiting for the turn to finish.
+pub struct SessionStepExecutor {
+    ctx: AppContext,
+}
+
+impl SessionStepExecutor {
+    pub fn new(ctx: AppContext) -> Self {
+        Self { ctx }
+    }
+}
+
+#[async_trait]
+impl StepExecutor for SessionStepExecutor {
+    async fn run_step(&self, req: StepRequest) -> StepResult {
+        let sm = &self.ctx.session_manager;
+        let session_id = match req.inherit_session {
+            Some(id) => id,
+            None => match sm
+                .create(
+                    &req.provider,
+                    &req.repo_path,
+                    &req.title,
+                    0,
+                    None,
+                    Some(&req.prompt),
+                )
+                .await
+            {
+                Ok(session) => session.id,
+                Err(e) => return StepResult::failed(None, format!("{e:#}")),
+            },
+        };
+        // Record the session before its turn starts, so its completion is
+        // known to belong to this run (`triggers::resolve`).
+        if let Err(e) = record_step_session(
+            self.ctx.storage.pool(),
+            &req.run_id,
+            &req.step_id,
+            &session_id,
+        )
+        .await
+        {
+            return StepResult::failed(Some(session_id), format!("{e:#}"));
+        }
+
+        if let Err(e) = sm.send_message(&session_id, &req.prompt, &self.ctx).await {
+            return StepResult::failed(Some(session_id), format!("{e:#}"));
+        }
+
+        let deadline = tokio::time::Instant::now() + STEP_TIMEOUT;
+        loop {
+            tokio::time::sleep(STEP_POLL_INTERVAL).await;
+            let status = match self.ctx.storage.get_session(&session_id).await {
+                Ok(Some(row)) => row.status,
+                Ok(None) => return StepResult::failed(Some(session_id), "session was deleted"),
+                Err(e) => return StepResult::failed(Some(session_id), format!("{e:#}")),
+            };
+            match status.as_str() {
+                "idle" => break,
+                "error" => {
+                    return StepResult::failed(Some(session_id), "session ended with an error")
+                }
+                // running / paused
+                _ if tokio::time::Instant::now() >= deadline => {
+                    let _ = sm.cancel(&session_id).await;
+                    return StepResult::failed(Some(session_id), "step timed out");
+                }
+                _ => {}
+            }
+        }
+
+        let output = match self.ctx.storage.list_messages(&session_id, 50, None).await {
+            Ok(msgs) => msgs
+                .into_iter()
+                .rev()
+                .find(|m| m.role == "assistant")
+                .map(|m| m.content)
+                .unwrap_or_default(),
+            Err(e) => return StepResult::failed(Some(session_id), format!("{e:#}")),
+        };
+
+        StepResult {
+            status: StepStatus::Succeeded,
+            session_id: Some(session_id),
+            output,
+            error: None,
+        }
+    }
+}
+
+// ─── Starting and resuming runs ────────────────────────────────────────────
+
+/// A run that has been persisted and is ready to execute.
+#[derive(Debug, Clone)]
+pub struct PreparedRun {
+    pub run_id: String,
+    pub recipe_id: String,
+    pub recipe_name: String,
+    pub plan: WorkflowPlan,
+    pub inputs: HashMap<String, String>,
+    pub repo_path: String,
+}
+
+/// Load a recipe, resolve its inputs and persist a new run with all steps
+/// pending. `trigger` records the event that launched an automatic run.
+/// Does not execute anything.
+pub async fn create_run(
+    pool: &SqlitePool,
+    recipe_id: &str,
+    provided_inputs: &HashMap<String, String>,
+    repo_path: &str,
+    trigger: Option<&WorkflowEvent>,
+) -> Result<PreparedRun> {
+    let row = sqlx::query("SELECT name, template_yaml FROM workflow_recipes WHERE id = ?")
+        .bind(recipe_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("workflow recipe not found: {}", recipe_id))?;
+    let recipe_name: String = row.get("name");
+    let recipe_yaml: String = row.get("template_yaml");
+    let (_, plan) = parse_and_plan(&recipe_yaml)?;
+
+    let (mut inputs, computed) = plan.resolve_inputs(provided_inputs)?;
+    for (name, source) in computed {
+        let value = compute_input(source, repo_path)
+            .await
+            .with_context(|| format!("computing input '{name}'"))?;
+        inputs.insert(name, value);
+    }
+
+    let trigger_event = trigger
+        .map(|event| {
+            let mut record = serde_json::to_value(event)?;
+            record["firedAt"] = json!(chrono::Utc::now().to_rfc3339());
+            serde_json::to_string(&record)
+        })
+        .transpose()?;
+
+    let run_id = Uuid::new_v4().to_string();
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO workflow_runs
+            (id, recipe_id, status, total_steps, recipe_yaml, inputs_json, repo_path,
+             trigger_kind, trigger_event)
+         VALUES (?, ?, 'running', ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run_id)
+    .bind(recipe_id)
+    .bind(plan.steps.len() as i64)
+    .bind(&recipe_yaml)
+    .bind(serde_json::to_string(&inputs)?)
+    .bind(repo_path)
+    .bind(trigger.map(|e| e.trigger.as_str()))
+    .bind(trigger_event)
+    .execute(&mut *tx)
+    .await?;
+    for step in &plan.steps {
+        sqlx::query(
+            "INSERT INTO workflow_run_steps (run_id, step_id, step_index) VALUES (?, ?, ?)",
+        )
+        .bind(&run_id)
+        .bind(&step.id)
+        .bind(step.index as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("UPDATE workflow_recipes SET run_count = run_count + 1 WHERE id = ?")
+        .bind(recipe_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(PreparedRun {
+        run_id,
+        recipe_id: recipe_id.to_string(),
+        recipe_name,
+        plan,
+        inputs,
+        repo_path: repo_path.to_string(),
+    })
+}
+
+/// Re-open an unfinished run. Succeeded steps keep their outputs; running,
+/// failed and skipped steps go back to pending and are re-planned. A step
+/// that was cut off mid-turn keeps its session and continues it.
+pub async fn prepare_resume(pool: &SqlitePool, run_id: &str) -> Result<PreparedRun> {
+    let row = sqlx::query(
+        "SELECT r.recipe_id, r.status, r.recipe_yaml, r.inputs_json, r.repo_path,
+                COALESCE(w.name, r.recipe_id) AS recipe_name
+         FROM workflow_runs r LEFT JOIN workflow_recipes w ON w.id = r.recipe_id
+         WHERE r.id = ?",
+    )
+    .bind(run_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("workflow run not found: {}", run_id))?;
+
+    let status: String = row.get("status");
+    if !matches!(status.as_str(), "interrupted" | "failed") {
+        anyhow::bail!(
+            "workflow run {run_id} is {status}; only interrupted or failed runs can be resumed"
+        );
+    }
+    let recipe_yaml: Option<String> = row.get("recipe_yaml");
+    let recipe_yaml = recipe_yaml
+        .ok_or_else(|| anyhow::anyhow!("workflow run {run_id} predates resumable runs"))?;
+    let (_, plan) = parse_and_plan(&recipe_yaml)?;
+    let inputs: HashMap<String, String> =
+        serde_json::from_str(row.get::<String, _>("inputs_json").as_str()).unwrap_or_default();
+
+    sqlx::query(
+        "UPDATE workflow_run_steps
+         SET status = 'pending', error = NULL, started_at = NULL, finished_at = NULL,
+             session_id = CASE WHEN status = 'running' THEN session_id END
+         WHERE run_id = ? AND status != 'succeeded'",
+    )
+    .bind(run_id)
+    .execute(pool)
+    .await?;
+    sqlx::query("UPDATE workflow_runs SET status = 'running', finished_at = NULL WHERE id = ?")
+        .bind(run_id)
+        .execute(pool)
+        .await?;
+
+    Ok(PreparedRun {
+        run_id: run_id.to_string(),
+        recipe_id: row.get("recipe_id"),
+        recipe_name: row.get("recipe_name"),
+        plan,
+        inputs,
+        repo_path: row.get("repo_path"),
+    })
+}
+
+/// Mark runs left `running` by a previous daemon process as `interrupted` so
+/// clients can offer to resume them. Called once at startup.
+pub async fn mark_interrupted(pool: &SqlitePool) -> Result<u64> {
+    let res =
+        sqlx::query("UPDATE workflow_runs SET status = 'interrupted' WHERE status = 'running'")
+            .execute(pool)
+            .await?;
+    Ok(res.rows_affected())
+}
+
+/// Per-step state of a run, for `workflow.getRun`.
+pub async fn run_status(pool: &SqlitePool, run_id: &str) -> Result<Value> {
+    let run = sqlx::query(
+        "SELECT id, recipe_id, status, current_step, total_steps, started_at, finished_at,
+                trigger_event
+         FROM workflow_runs WHERE id = ?",
+    )
+    .bind(run_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("workflow run not found: {}", run_id))?;
+
+    let steps = sqlx::query(
+        "SELECT step_id, step_index, status, session_id, output, error, started_at, finished_at
+         FROM workflow_run_steps WHERE run_id = ? ORDER BY step_index",
+    )
+    .bind(run_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(json!({
+        "runId": run.get::<String, _>("id"),
+        "recipeId": run.get::<String, _>("recipe_id"),
+        "status": run.get::<String, _>("status"),
+        "currentStep": run.get::<i64, _>("current_step"),
+        "totalSteps": run.get::<i64, _>("total_steps"),
+        "startedAt": run.get::<String, _>("started_at"),
+        "finishedAt": run.get::<Option<String>, _>("finished_at"),
+        "trigger": run
+            .get::<Option<String>, _>("trigger_event")
+            .and_then(|t| serde_json::from_str::<Value>(&t).ok()),
+        "steps": steps.iter().map(|s| json!({
+            "stepId": s.get::<String, _>("step_id"),
+            "stepIndex": s.get::<i64, _>("step_index"),
+            "status": s.get::<String, _>("status"),
+            "sessionId": s.get::<Option<String>, _>("session_id"),
+            "output": s.get::<Option<String>, _>("output"),
+            "error": s.get::<Option<String>, _>("error"),
+            "startedAt": s.get::<Option<String>, _>("started_at"),
+            "finishedAt": s.get::<Option<String>, _>("finished_at"),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+/// Compute a daemon-sourced input value.
+pub async fn compute_input(source: InputSource, repo_path: &str) -> Result<String> {
+    match source {
+        InputSource::GitDiff => {
+            let path = repo_path.to_string();
+            tokio::task::spawn_blocking(move || working_tree_diff(std::path::Path::new(&path)))
+                .await
+                .context("git diff task panicked")?
+        }
+    }
+}
+
+/// Unified diff of the working tree (staged + unstaged) against `HEAD`.
+fn working_tree_diff(repo_path: &std::path::Path) -> Result<String> {
+    let repo = git2::Repository::discover(repo_path)
+        .with_context(|| format!("not a git repository: {}", repo_path.display()))?;
+    let head_tree = match repo.head() {
+        Ok(h) => Some(h.peel_to_tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), None)?;
+    let mut text = String::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(text)
+}
+
+// ─── Execution ─────────────────────────────────────────────────────────────
+
+/// Final state of an executed run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// `"done"` or `"failed"`.
+    pub status: String,
+    pub steps: HashMap<String, StepStatus>,
+}
+
+/// Execute a prepared run to completion, persisting step state as it goes.
+pub async fn execute_run(
+    pool: SqlitePool,
+    broadcaster: Arc<EventBroadcaster>,
+    executor: Arc<dyn StepExecutor>,
+    run: PreparedRun,
+) -> Result<RunOutcome> {
+    let PreparedRun {
+        run_id,
+        recipe_id,
+        recipe_name,
+        plan,
+        inputs,
+        repo_path,
+    } = run;
+
+    // Load persisted state (non-empty when resuming).
+    let mut states: HashMap<String, StepStatus> = HashMap::new();
+    let mut outputs: HashMap<String, StepOutput> = HashMap::new();
+    let mut sessions: HashMap<String, String> = HashMap::new();
+    for row in sqlx::query(
+        "SELECT step_id, status, session_id, output FROM workflow_run_steps WHERE run_id = ?",
+    )
+    .bind(&run_id)
+    .fetch_all(&pool)
+    .await?
+    {
+        let id: String = row.get("step_id");
+        let status: StepStatus = row.get::<String, _>("status").parse()?;
+        if let Some(session) = row.get::<Option<String>, _>("session_id") {
+            sessions.insert(id.clone(), session);
+        }
+        if status.is_terminal() {
+            outputs.insert(
+                id.clone(),
+                StepOutput {
+                    status: Some(status),
+                    output: row.get::<Option<String>, _>("output").unwrap_or_default(),
+                },
+            );
+        }
+        states.insert(id, status);
+    }
+
+    let mut running: JoinSet<(String, StepResult)> = JoinSet::new();
+    loop {
+        // Apply decisions until nothing changes — a skip can unblock more steps.
+        loop {
+            let mut progressed = false;
+            for decision in plan.decide(&states) {
+                match decision {
+                    StepDecision::Skip(id) => {
+                        states.insert(id.clone(), StepStatus::Skipped);
+                        outputs.insert(
+                            id.clone(),
+                            StepOutput {
+                                status: Some(StepStatus::Skipped),
+                                output: String::new(),
+                            },
+                        );
+                        finish_step(&pool, &run_id, &id, StepStatus::Skipped, None, "", None)
+                            .await?;
+                        broadcaster.broadcast(
+                            "workflow.stepCompleted",
+                            step_event(&run_id, &recipe_id, &plan, &id, StepStatus::Skipped, None),
+                        );
+                        progressed = true;
+                    }
+                    StepDecision::Run(id) if running.len() < plan.max_parallel => {
+                        let step = plan.step(&id).expect("decided step exists");
+                        let req = StepRequest {
+                            run_id: run_id.clone(),
+                            step_id: id.clone(),
+                            title: format!("{recipe_name} — {id}"),
+                            prompt: render_template(&step.prompt, &inputs, &outputs),
+                            provider: step.provider.clone().unwrap_or_else(|| "claude".into()),
+                            repo_path: repo_path.clone(),
+                            // A pending step only has a session if a previous
+                            // daemon was stopped while it ran; pick that up.
+                            inherit_session: sessions.get(&id).cloned().or_else(|| {
+                                step.inherit_from
+                                    .as_ref()
+                                    .and_then(|p| sessions.get(p).cloned())
+                            }),
+                        };
+                        states.insert(id.clone(), StepStatus::Running);
+                        sqlx::query(
+                            "UPDATE workflow_run_steps SET status = 'running', started_at = datetime('now')
+                             WHERE run_id = ? AND step_id = ?",
+                        )
+                        .bind(&run_id)
+                        .bind(&id)
+                        .execute(&pool)
+                        .await?;
+                        broadcaster.broadcast(
+                            "workflow.stepStarted",
+                            json!({ "runId": run_id, "recipeId": recipe_id, "stepId": id, "stepIndex": step.index }),
+                        );
+                        let exec = Arc::clone(&executor);
+                        running.spawn(async move { (id, exec.run_step(req).await) });
+                        progressed = true;
+                    }
+                    StepDecision::Run(_) => {} // no free slot yet
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        let (id, result) = match joined {
+            Ok(r) => r,
+            Err(e) => {
+                // A panicking executor loses its step id; fail the run loudly
+                // rather than leaving a step stuck in `running`.
+                warn!(run = %run_id, "workflow step task failed: {e}");
+                anyhow::bail!("workflow step task failed: {e}");
+            }
+        };
+
+        states.insert(id.clone(), result.status);
+        outputs.insert(
+            id.clone(),
+            StepOutput {
+                status: Some(result.status),
+                output: result.output.clone(),
+            },
+        );
+        if let Some(session) = &result.session_id {
+            sessions.insert(id.clone(), session.clone());
+        }
+        finish_step(
+            &pool,
+            &run_id,
+            &id,
+            result.status,
+            result.session_id.as_deref(),
+            &result.output,
+            result.error.as_deref(),
+        )
+        .await?;
+        broadcaster.broadcast(
+            "workflow.stepCompleted",
+            step_event(
+                &run_id,
+                &recipe_id,
+                &plan,
+                &id,
+                result.status,
+                result.session_id.as_deref(),
+            ),
+        );
+    }
+
+    let failed: Vec<&String> = plan
+        .steps
+        .iter()
+        .map(|s| &s.id)
+        .filter(|id| states.get(*id) == Some(&StepStatus::Failed))
+        .collect();
+    let status = if failed.is_empty() { "done" } else { "failed" };
+    sqlx::query("UPDATE workflow_runs SET status = ?, finished_at = datetime('now') WHERE id = ?")
+        .bind(status)
+        .bind(&run_id)
+        .execute(&pool)
+        .await?;
+
+    if failed.is_empty() {
+        info!(run = %run_id, recipe = %recipe_name, "workflow run completed");
+        broadcaster.broadcast(
+            "workflow.ran",
+            json!({
+                "runId": run_id,
+                "recipeId": recipe_id,
+                "stepsCompleted": plan.steps.len(),
+            }),
+        );
+    } else {
+        broadcaster.broadcast(
+            "workflow.failed",
+            json!({
+                "runId": run_id,
+                "recipeId": recipe_id,
+                "failedSteps": failed,
+                "error": format!("{} step(s) failed", failed.len()),
+            }),
+        );
+    }
+
+    Ok(RunOutcome {
+        status: status.to_string(),
+        steps: states,
+    })
+}
+
+/// Persist the session a running step works in.
+async fn record_step_session(
+    pool: &SqlitePool,
+    run_id: &str,
+    step_id: &str,
+    session_id: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE workflow_run_steps SET session_id = ? WHERE run_id = ? AND step_id = ?")
+        .bind(session_id)
+        .bind(run_id)
+        .bind(step_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn finish_step(
+    pool: &SqlitePool,
+    run_id: &str,
+    step_id: &str,
+    status: StepStatus,
+    session_id: Option<&str>,
+    output: &str,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE workflow_run_steps
+         SET status = ?, session_id = COALESCE(?, session_id), output = ?, error = ?,
+             finished_at = datetime('now')
+         WHERE run_id = ? AND step_id = ?",
+    )
+    .bind(status.as_str())
+    .bind(session_id)
+    .bind(output)
+    .bind(error)
+    .bind(run_id)
+    .bind(step_id)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "UPDATE workflow_runs SET current_step = (
+             SELECT COUNT(*) FROM workflow_run_steps
+             WHERE run_id = ? AND status IN ('succeeded', 'failed', 'skipped'))
+         WHERE id = ?",
+    )
+    .bind(run_id)
+    .bind(run_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn step_event(
+    run_id: &str,
+    recipe_id: &str,
+    plan: &WorkflowPlan,
+    step_id: &str,
+    status: StepStatus,
+    session_id: Option<&str>,
+) -> Value {
+    json!({
+        "runId": run_id,
+        "recipeId": recipe_id,
+        "stepId": step_id,
+        "stepIndex": plan.step(step_id).map(|s| s.index),
+        "status": status,
+        "sessionId": session_id,
+    })
+}
+
+/// Spawn [`execute_run`] in the background with the session-backed executor.
+pub fn spawn_run(ctx: &AppContext, run: PreparedRun) {
+    let pool = ctx.storage.clone_pool();
+    let broadcaster = ctx.broadcaster.clone();
+    let executor: Arc<dyn StepExecutor> = Arc::new(SessionStepExecutor::new(ctx.clone()));
+    tokio::spawn(async move {
+        let run_id = run.run_id.clone();
+        if let Err(e) = execute_run(pool.clone(), broadcaster.clone(), executor, run).await {
+            warn!(run = %run_id, "workflow run aborted: {e:#}");
+            let _ = sqlx::query(
+                "UPDATE workflow_runs SET status = 'failed', finished_at = datetime('now') WHERE id = ?",
+            )
+            .bind(&run_id)
+            .execute(&pool)
+            .await;
+            broadcaster.broadcast(
+                "workflow.failed",
+                json!({ "runId": run_id, "error": format!("{e:#}") }),
+            );
+        }
+    });
+}
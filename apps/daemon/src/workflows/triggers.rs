@@ -0,0 +1,584 @@
+//! Automatic workflow launches from [`WorkflowTrigger`]s.
+//!
+//! The dispatcher listens on the daemon's event bus and turns notifications
+//! into trigger events:
+//!
+//! | Trigger               | Notification                                        |
+//! |-----------------------|-----------------------------------------------------|
+//! | `on_commit`           | `repo.committed` (HEAD moved in a watched repo)     |
+//! | `on_file_change`      | `repo.filesChanged`                                 |
+//! | `on_task_done`        | `task.statusChanged` → done, `task.stateChanged` → done |
+//! | `on_session_complete` | `session.statusChanged` running → idle / error      |
+//!
+//! Events are debounced per recipe and repository: a run starts once no
+//! further matching event has arrived for `debounce_secs`, and carries the
+//! most recent event plus a count of how many were coalesced. A recipe never
+//! has more than `max_concurrent_runs` runs in flight; triggers beyond that
+//! are dropped and reported as `workflow.triggerSkipped`.
+//!
+//! The launching event is stored on the run (`trigger_kind`,
+//! `trigger_event`) and exposed by `workflow.getRun`. Triggered runs also
+//! receive the inputs `trigger` and `trigger_subject` (commit sha, task id,
+//! session id or first changed file).
+//!
+//! A recipe cannot re-trigger itself: sessions created by workflow steps never
+//! fire `on_session_complete`, and `on_commit` / `on_file_change` events for a
+//! repository are ignored by a recipe while it has a run there, and for
+//! [`OWN_RUN_GRACE_SECS`] after that run finishes (the watcher reports edits
+//! late). Edits made by others during that window do not trigger it either.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{Row as _, SqlitePool};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use super::engine::{parse_recipe_yaml, WorkflowTrigger};
+use super::runner::{self, PreparedRun};
+use crate::AppContext;
+
+/// Quiet period before a triggered run starts, unless the recipe overrides it.
+pub const DEFAULT_DEBOUNCE_SECS: u64 = 10;
+/// Triggered runs of one recipe allowed in flight, unless the recipe overrides it.
+pub const DEFAULT_MAX_CONCURRENT_RUNS: usize = 1;
+/// How long after a run finishes its repository's file and commit events
+/// are still attributed to it.
+pub const OWN_RUN_GRACE_SECS: u64 = 5;
+/// Longest the dispatcher waits when nothing is pending.
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+// ─── Events ────────────────────────────────────────────────────────────────
+
+/// Something that happened which may launch workflow runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowEvent {
+    pub trigger: WorkflowTrigger,
+    /// Notification the event was derived from, e.g. `repo.committed`.
+    pub source: String,
+    pub repo_path: Option<String>,
+    /// Commit sha, task id, session id or first changed file.
+    pub subject: Option<String>,
+    /// The notification's params.
+    pub detail: Value,
+    /// Number of events folded into this one by debouncing.
+    pub coalesced: u32,
+}
+
+impl WorkflowEvent {
+    fn new(trigger: WorkflowTrigger, source: &str, params: &Value) -> Self {
+        Self {
+            trigger,
+            source: source.to_string(),
+            repo_path: None,
+            subject: None,
+            detail: params.clone(),
+            coalesced: 1,
+        }
+    }
+
+    fn repo(mut self, path: Option<&str>) -> Self {
+        self.repo_path = path.filter(|p| !p.is_empty()).map(String::from);
+        self
+    }
+
+    fn subject(mut self, subject: Option<&str>) -> Self {
+        self.subject = subject.map(String::from);
+        self
+    }
+}
+
+/// Maps event-bus notifications to [`WorkflowEvent`]s.
+///
+/// Stateful because session completion is a transition: a session going
+/// `idle` only counts if it was previously seen `running`.
+#[derive(Debug, Default)]
+pub struct EventClassifier {
+    running_sessions: std::collections::HashSet<String>,
+}
+
+impl EventClassifier {
+    pub fn classify(&mut self, method: &str, params: &Value) -> Option<WorkflowEvent> {
+        let str_field = |key: &str| params.get(key).and_then(|v| v.as_str());
+        match method {
+            "repo.committed" => Some(
+                WorkflowEvent::new(WorkflowTrigger::OnCommit, method, params)
+                    .repo(str_field("repoPath"))
+                    .subject(str_field("sha")),
+            ),
+            "repo.filesChanged" => {
+                let first = params
+                    .get("paths")
+                    .and_then(|v| v.as_array())
+                    .and_then(|a| a.first())
+                    .and_then(|v| v.as_str());
+                first.map(|file| {
+                    WorkflowEvent::new(WorkflowTrigger::OnFileChange, method, params)
+                        .repo(str_field("repoPath"))
+                        .subject(Some(file))
+                })
+            }
+            // `tasks.updateStatus` reports `status`; the MCP `transition_task`
+            // tool reports `new_state`. The repo is looked up when absent.
+            "task.statusChanged"
+                if matches!(
+                    str_field("status").or(str_field("new_state")),
+                    Some("done" | "completed")
+                ) =>
+            {
+                Some(
+                    WorkflowEvent::new(WorkflowTrigger::OnTaskDone, method, params)
+                        .repo(str_field("repo_path"))
+                        .subject(str_field("task_id")),
+                )
+            }
+            "task.stateChanged" if str_field("state") == Some("done") => Some(
+                WorkflowEvent::new(WorkflowTrigger::OnTaskDone, method, params)
+                    .subject(str_field("task_id")),
+            ),
+            "session.statusChanged" => {
+                let session_id = str_field("sessionId")?;
+                match str_field("status")? {
+                    "running" => {
+                        self.running_sessions.insert(session_id.to_string());
+                        None
+                    }
+                    status => {
+                        let was_running = self.running_sessions.remove(session_id);
+                        (was_running && matches!(status, "idle" | "error")).then(|| {
+                            WorkflowEvent::new(WorkflowTrigger::OnSessionComplete, method, params)
+                                .subject(Some(session_id))
+                        })
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// ─── Debouncing ────────────────────────────────────────────────────────────
+
+/// Trailing-edge debouncer keyed by recipe and repository.
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    pending: HashMap<(String, String), (WorkflowEvent, Instant)>,
+}
+
+impl Debouncer {
+    /// Queue `event` for `recipe_id`, replacing (and counting) any event
+    /// already waiting for the same recipe and repository.
+    pub fn push(
+        &mut self,
+        recipe_id: &str,
+        mut event: WorkflowEvent,
+        window: Duration,
+        now: Instant,
+    ) {
+        let key = (
+            recipe_id.to_string(),
+            event.repo_path.clone().unwrap_or_default(),
+        );
+        if let Some((previous, _)) = self.pending.remove(&key) {
+            event.coalesced += previous.coalesced;
+        }
+        self.pending.insert(key, (event, now + window));
+    }
+
+    /// Remove and return every event whose quiet period has elapsed.
+    pub fn take_due(&mut self, now: Instant) -> Vec<(String, WorkflowEvent)> {
+        let due: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, at))| *at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut out: Vec<_> = due
+            .into_iter()
+            .filter_map(|key| {
+                let (event, _) = self.pending.remove(&key)?;
+                Some((key.0, event))
+            })
+            .collect();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    /// When the next pending event becomes due.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.values().map(|(_, at)| *at).min()
+    }
+}
+
+// ─── Launching ─────────────────────────────────────────────────────────────
+
+/// Result of trying to start a triggered run.
+#[derive(Debug)]
+pub enum Launch {
+    Started(Box<PreparedRun>),
+    /// The recipe already has `limit` runs in flight.
+    AtLimit {
+        limit: usize,
+    },
+}
+
+/// Create a run of `recipe_id` for `event`, honouring the recipe's
+/// `max_concurrent_runs`. Does not execute it.
+pub async fn launch_triggered(
+    pool: &SqlitePool,
+    recipe_id: &str,
+    event: &WorkflowEvent,
+) -> Result<Launch> {
+    let yaml: String =
+        sqlx::query_scalar("SELECT template_yaml FROM workflow_recipes WHERE id = ?")
+            .bind(recipe_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("workflow recipe not found: {}", recipe_id))?;
+    let limit = parse_recipe_yaml(&yaml)?
+        .max_concurrent_runs
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_RUNS);
+
+    let running: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM workflow_runs WHERE recipe_id = ? AND status = 'running'",
+    )
+    .bind(recipe_id)
+    .fetch_one(pool)
+    .await?;
+    if running as usize >= limit {
+        return Ok(Launch::AtLimit { limit });
+    }
+
+    let mut inputs = HashMap::new();
+    inputs.insert("trigger".to_string(), event.trigger.as_str().to_string());
+    if let Some(subject) = &event.subject {
+        inputs.insert("trigger_subject".to_string(), subject.clone());
+    }
+    let repo_path = event.repo_path.as_deref().unwrap_or(".");
+    let run = runner::create_run(pool, recipe_id, &inputs, repo_path, Some(event)).await?;
+    Ok(Launch::Started(Box::new(run)))
+}
+
+/// Whether `event` may have been caused by a run of `recipe_id` itself: a
+/// commit or file change in a repository where the recipe has a run in
+/// flight, or one that finished within [`OWN_RUN_GRACE_SECS`].
+pub async fn caused_by_own_run(
+    pool: &SqlitePool,
+    recipe_id: &str,
+    event: &WorkflowEvent,
+) -> Result<bool> {
+    if !matches!(
+        event.trigger,
+        WorkflowTrigger::OnCommit | WorkflowTrigger::OnFileChange
+    ) {
+        return Ok(false);
+    }
+    let Some(repo_path) = &event.repo_path else {
+        return Ok(false);
+    };
+    let own: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM workflow_runs
+         WHERE recipe_id = ? AND repo_path = ?
+           AND (status = 'running' OR finished_at >= datetime('now', ?))
+         LIMIT 1",
+    )
+    .bind(recipe_id)
+    .bind(repo_path)
+    .bind(format!("-{OWN_RUN_GRACE_SECS} seconds"))
+    .fetch_optional(pool)
+    .await?;
+    Ok(own.is_some())
+}
+
+// ─── Dispatcher ────────────────────────────────────────────────────────────
+
+/// Start the trigger dispatcher. Must be called once at daemon startup.
+pub fn start_dispatcher(ctx: AppContext) {
+    let mut rx = ctx.broadcaster.subscribe();
+    tokio::spawn(async move {
+        let mut classifier = EventClassifier::default();
+        let mut debouncer = Debouncer::default();
+        loop {
+            let wait = debouncer
+                .next_due()
+                .map(|at| at.saturating_duration_since(Instant::now()))
+                .unwrap_or(IDLE_WAIT);
+            tokio::select! {
+                msg = rx.recv() => match msg {
+                    Ok(text) => {
+                        let Ok(notification) = serde_json::from_str::<Value>(&text) else {
+                            continue;
+                        };
+                        let method = notification["method"].as_str().unwrap_or_default();
+                        let Some(event) = classifier.classify(method, &notification["params"]) else {
+                            continue;
+                        };
+                        match resolve(&ctx, event).await {
+                            Ok(Some(event)) => {
+                                if let Err(e) = enqueue(&ctx, &mut debouncer, event).await {
+                                    warn!("workflow trigger: {e:#}");
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("workflow trigger: {e:#}"),
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("workflow triggers: dropped {n} events (too slow)");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = tokio::time::sleep(wait) => {
+                    for (recipe_id, event) in debouncer.take_due(Instant::now()) {
+                        launch(&ctx, &recipe_id, event).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Fill in the repository for task and session events. Returns `None` for
+/// events that must not launch anything.
+async fn resolve(ctx: &AppContext, mut event: WorkflowEvent) -> Result<Option<WorkflowEvent>> {
+    match event.trigger {
+        WorkflowTrigger::OnTaskDone if event.repo_path.is_none() => {
+            if let Some(task_id) = &event.subject {
+                let repo = ctx
+                    .task_storage
+                    .get_task(task_id)
+                    .await?
+                    .map(|t| t.repo_path);
+                event = event.repo(repo.as_deref());
+            }
+        }
+        WorkflowTrigger::OnSessionComplete => {
+            let Some(session_id) = event.subject.clone() else {
+                return Ok(None);
+            };
+            let owned_by_workflow: Option<i64> =
+                sqlx::query_scalar("SELECT 1 FROM workflow_run_steps WHERE session_id = ? LIMIT 1")
+                    .bind(&session_id)
+                    .fetch_optional(ctx.storage.pool())
+                    .await?;
+            if owned_by_workflow.is_some() {
+                return Ok(None);
+            }
+            let repo = ctx
+                .storage
+                .get_session(&session_id)
+                .await?
+                .map(|s| s.repo_path);
+            event = event.repo(repo.as_deref());
+        }
+        _ => {}
+    }
+    if event.repo_path.is_none() {
+        debug!(
+            trigger = event.trigger.as_str(),
+            "workflow trigger without a repository ignored"
+        );
+        return Ok(None);
+    }
+    Ok(Some(event))
+}
+
+/// Queue `event` for every recipe that lists its trigger.
+async fn enqueue(ctx: &AppContext, debouncer: &mut Debouncer, event: WorkflowEvent) -> Result<()> {
+    let rows = sqlx::query("SELECT id, name, template_yaml FROM workflow_recipes")
+        .fetch_all(ctx.storage.pool())
+        .await?;
+    let now = Instant::now();
+    for row in rows {
+        let id: String = row.get("id");
+        let recipe = match parse_recipe_yaml(row.get::<String, _>("template_yaml").as_str()) {
+            Ok(r) => r,
+            Err(e) => {
+                debug!(recipe = %row.get::<String, _>("name"), "unparseable recipe skipped: {e}");
+                continue;
+            }
+        };
+        if recipe.triggers.contains(&event.trigger) {
+            if caused_by_own_run(ctx.storage.pool(), &id, &event).await? {
+                debug!(recipe = %id, trigger = event.trigger.as_str(), "ignored the recipe's own change");
+                continue;
+            }
+            let window = Duration::from_secs(recipe.debounce_secs.unwrap_or(DEFAULT_DEBOUNCE_SECS));
+            debouncer.push(&id, event.clone(), window, now);
+        }
+    }
+    Ok(())
+}
+
+async fn launch(ctx: &AppContext, recipe_id: &str, event: WorkflowEvent) {
+    match launch_triggered(ctx.storage.pool(), recipe_id, &event).await {
+        Ok(Launch::Started(run)) => {
+            info!(
+                recipe = %run.recipe_name,
+                run = %run.run_id,
+                trigger = event.trigger.as_str(),
+                "workflow triggered"
+            );
+            ctx.broadcaster.broadcast(
+                "workflow.triggered",
+                json!({
+                    "runId": run.run_id,
+                    "recipeId": recipe_id,
+                    "recipeName": run.recipe_name,
+                    "trigger": event,
+                }),
+            );
+            runner::spawn_run(ctx, *run);
+        }
+        Ok(Launch::AtLimit { limit }) => {
+            debug!(recipe = %recipe_id, limit, "workflow trigger skipped: concurrency limit");
+            ctx.broadcaster.broadcast(
+                "workflow.triggerSkipped",
+                json!({
+                    "recipeId": recipe_id,
+                    "trigger": event,
+                    "reason": format!("{limit} run(s) already in flight"),
+                }),
+            );
+        }
+        Err(e) => warn!(recipe = %recipe_id, "triggered workflow failed to start: {e:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_completes_only_after_running() {
+        let mut c = EventClassifier::default();
+        let idle = json!({ "sessionId": "s1", "status": "idle" });
+        assert!(c.classify("session.statusChanged", &idle).is_none());
+
+        let running = json!({ "sessionId": "s1", "status": "running" });
+        assert!(c.classify("session.statusChanged", &running).is_none());
+        let event = c.classify("session.statusChanged", &idle).unwrap();
+        assert_eq!(event.trigger, WorkflowTrigger::OnSessionComplete);
+        assert_eq!(event.subject.as_deref(), Some("s1"));
+
+        // A second idle (e.g. a defensive resume) is not another completion.
+        assert!(c.classify("session.statusChanged", &idle).is_none());
+    }
+
+    #[test]
+    fn classifies_repo_and_task_notifications() {
+        let mut c = EventClassifier::default();
+        let commit = c
+            .classify(
+                "repo.committed",
+                &json!({ "repoPath": "/r", "sha": "abc", "previousSha": "def" }),
+            )
+            .unwrap();
+        assert_eq!(commit.trigger, WorkflowTrigger::OnCommit);
+        assert_eq!(commit.repo_path.as_deref(), Some("/r"));
+        assert_eq!(commit.subject.as_deref(), Some("abc"));
+
+        let files = c
+            .classify(
+                "repo.filesChanged",
+                &json!({ "repoPath": "/r", "paths": ["src/a.rs", "src/b.rs"] }),
+            )
+            .unwrap();
+        assert_eq!(files.trigger, WorkflowTrigger::OnFileChange);
+        assert_eq!(files.subject.as_deref(), Some("src/a.rs"));
+        assert!(c
+            .classify(
+                "repo.filesChanged",
+                &json!({ "repoPath": "/r", "paths": [] })
+            )
+            .is_none());
+
+        let done = c
+            .classify(
+                "task.statusChanged",
+                &json!({ "task_id": "t1", "status": "done", "repo_path": "/r" }),
+            )
+            .unwrap();
+        assert_eq!(done.trigger, WorkflowTrigger::OnTaskDone);
+        assert_eq!(done.repo_path.as_deref(), Some("/r"));
+        assert!(c
+            .classify(
+                "task.statusChanged",
+                &json!({ "task_id": "t1", "status": "in_progress" })
+            )
+            .is_none());
+        assert!(c
+            .classify(
+                "task.stateChanged",
+                &json!({ "task_id": "t1", "state": "done" })
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn mcp_task_transitions_fire_on_task_done() {
+        let mut c = EventClassifier::default();
+        let done = c
+            .classify(
+                "task.statusChanged",
+                &json!({ "task_id": "t1", "new_state": "done", "agent_id": "a1" }),
+            )
+            .unwrap();
+        assert_eq!(done.trigger, WorkflowTrigger::OnTaskDone);
+        assert_eq!(done.subject.as_deref(), Some("t1"));
+        // Resolved from the task when the dispatcher handles the event.
+        assert_eq!(done.repo_path, None);
+        assert!(c
+            .classify(
+                "task.statusChanged",
+                &json!({ "task_id": "t1", "new_state": "active", "agent_id": "a1" })
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn debouncer_coalesces_until_quiet() {
+        let mut d = Debouncer::default();
+        let mut c = EventClassifier::default();
+        let event = |sha: &str, c: &mut EventClassifier| {
+            c.classify("repo.committed", &json!({ "repoPath": "/r", "sha": sha }))
+                .unwrap()
+        };
+        let window = Duration::from_secs(10);
+        let t0 = Instant::now();
+
+        d.push("recipe", event("a", &mut c), window, t0);
+        d.push(
+            "recipe",
+            event("b", &mut c),
+            window,
+            t0 + Duration::from_secs(5),
+        );
+        assert!(d.take_due(t0 + Duration::from_secs(12)).is_empty());
+        assert_eq!(d.next_due(), Some(t0 + Duration::from_secs(15)));
+
+        let due = d.take_due(t0 + Duration::from_secs(15));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].1.subject.as_deref(), Some("b"));
+        assert_eq!(due[0].1.coalesced, 2);
+        assert!(d.next_due().is_none());
+    }
+
+    #[test]
+    fn debouncer_keys_by_repository() {
+        let mut d = Debouncer::default();
+        let mut c = EventClassifier::default();
+        let t0 = Instant::now();
+        for repo in ["/a", "/b"] {
+            let e = c
+                .classify("repo.committed", &json!({ "repoPath": repo, "sha": "x" }))
+                .unwrap();
+            d.push("recipe", e, Duration::ZERO, t0);
+        }
+        assert_eq!(d.take_due(t0).len(), 2);
+    }
+}
@@ -2,6 +2,7 @@
 
 use clawd::automations::{
     builtins,
+    condition::Condition,
     engine::{AutomationEngine, TriggerEvent, TriggerType},
 };
 
@@ -133,3 +134,200 @@ fn fire_does_not_panic_without_listeners() {
         session_duration_secs: Some(10),
     });
 }
+
+// ─── Cron scheduling ──────────────────────────────────────────────────────────
+
+mod cron {
+    use chrono::{DateTime, Utc};
+    use clawd::automations::{
+        config,
+        cron::CronSchedule,
+        engine::{plan_cron_runs, CatchUpPolicy, TriggerType},
+    };
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn on_time_run_fires_for_every_policy() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        let last = at("2026-03-01T09:00:00Z");
+        let now = at("2026-03-01T10:00:01Z");
+        for policy in [CatchUpPolicy::Skip, CatchUpPolicy::Once, CatchUpPolicy::All] {
+            let plan = plan_cron_runs(&hourly, policy, &last, &now);
+            assert_eq!(plan.run, vec![at("2026-03-01T10:00:00Z")], "{policy:?}");
+            assert_eq!(plan.advance_to, Some(at("2026-03-01T10:00:00Z")));
+        }
+    }
+
+    #[test]
+    fn nothing_due_leaves_state_untouched() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        let plan = plan_cron_runs(
+            &hourly,
+            CatchUpPolicy::Once,
+            &at("2026-03-01T10:00:00Z"),
+            &at("2026-03-01T10:59:00Z"),
+        );
+        assert!(plan.run.is_empty());
+        assert_eq!(plan.advance_to, None);
+    }
+
+    #[test]
+    fn missed_runs_follow_catch_up_policy() {
+        let hourly = CronSchedule::parse("@hourly").unwrap();
+        // Daemon was down from 09:00 until 12:30 — 10:00, 11:00, 12:00 missed.
+        let last = at("2026-03-01T09:00:00Z");
+        let now = at("2026-03-01T12:30:00Z");
+
+        let skip = plan_cron_runs(&hourly, CatchUpPolicy::Skip, &last, &now);
+        assert!(skip.run.is_empty());
+        assert_eq!(skip.advance_to, Some(at("2026-03-01T12:00:00Z")));
+
+        let once = plan_cron_runs(&hourly, CatchUpPolicy::Once, &last, &now);
+        assert_eq!(once.run, vec![at("2026-03-01T12:00:00Z")]);
+
+        let all = plan_cron_runs(&hourly, CatchUpPolicy::All, &last, &now);
+        assert_eq!(all.run.len(), 3);
+        assert_eq!(all.advance_to, Some(at("2026-03-01T12:00:00Z")));
+    }
+
+    #[test]
+    fn replaying_after_advance_does_not_double_fire() {
+        let every5 = CronSchedule::parse("*/5 * * * *").unwrap();
+        let now = at("2026-03-01T10:05:10Z");
+        let first = plan_cron_runs(
+            &every5,
+            CatchUpPolicy::All,
+            &at("2026-03-01T10:00:00Z"),
+            &now,
+        );
+        let persisted = first.advance_to.unwrap();
+        // Simulated restart a few seconds later with the persisted state.
+        let second = plan_cron_runs(&every5, CatchUpPolicy::All, &persisted, &now);
+        assert!(second.run.is_empty());
+    }
+
+    #[test]
+    fn config_loads_cron_automation() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".claw")).unwrap();
+        std::fs::write(
+            dir.path().join(".claw/config.toml"),
+            r#"
+[[automations]]
+name     = "nightly-tests"
+trigger  = "cron"
+schedule = "0 3 * * *"
+catch_up = "skip"
+action   = "run_tests"
+"#,
+        )
+        .unwrap();
+
+        let autos = config::load_from_repo(dir.path());
+        assert_eq!(autos.len(), 1);
+        assert_eq!(autos[0].trigger, TriggerType::Cron);
+        assert_eq!(autos[0].schedule.as_deref(), Some("0 3 * * *"));
+        assert_eq!(autos[0].catch_up, CatchUpPolicy::Skip);
+        assert_eq!(
+            autos[0].next_run_after(&at("2026-03-01T12:00:00Z")),
+            Some(at("2026-03-02T03:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn config_rejects_cron_without_valid_schedule() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".claw")).unwrap();
+        for body in [
+            "[[automations]]\nname = \"a\"\ntrigger = \"cron\"\naction = \"run_tests\"\n",
+            "[[automations]]\nname = \"a\"\ntrigger = \"cron\"\nschedule = \"61 * * * *\"\naction = \"run_tests\"\n",
+        ] {
+            std::fs::write(dir.path().join(".claw/config.toml"), body).unwrap();
+            assert!(config::load_from_repo(dir.path()).is_empty());
+        }
+    }
+}
+
+// ─── Condition expressions ────────────────────────────────────────────────────
+
+#[test]
+fn conditions_are_checked_against_the_parsed_expression() {
+    let engine = make_test_engine();
+    let mut auto = engine
+        .automations
+        .blocking_read()
+        .iter()
+        .find(|a| a.name == "todo-extractor")
+        .unwrap()
+        .clone();
+    // Typo in the field name: previously treated as "always true", now it
+    // cannot be loaded at all.
+    assert!(Condition::parse("sesion_output =~ 'TODO'").is_err());
+
+    let event = TriggerEvent {
+        kind: TriggerType::SessionComplete,
+        session_id: Some("s1".into()),
+        task_id: None,
+        file_path: None,
+        session_output: Some("TODO: something".into()),
+        session_duration_secs: None,
+    };
+    auto.condition = Some(Condition::parse("session_output =~ 'TODO' and task_id").unwrap());
+    assert!(!auto.matches(&event));
+
+    auto.condition = Some(Condition::parse("session_output =~ 'TODO' and not task_id").unwrap());
+    assert!(auto.matches(&event));
+}
+
+#[test]
+fn config_rejects_invalid_condition() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".claw")).unwrap();
+    std::fs::write(
+        dir.path().join(".claw/config.toml"),
+        "[[automations]]\nname = \"rs-saved\"\ntrigger = \"file_saved\"\n\
+         condition = \"file_path glob 'src/**' and\"\naction = \"run_tests\"\n",
+    )
+    .unwrap();
+    assert!(clawd::automations::config::load_from_repo(dir.path()).is_empty());
+}
+
+// ─── Repo automations ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn opening_a_repo_loads_its_automations_and_closing_drops_them() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".claw")).unwrap();
+    std::fs::write(
+        dir.path().join(".claw/config.toml"),
+        "[[automations]]\nname = \"nightly-tests\"\ntrigger = \"cron\"\n\
+         schedule = \"0 3 * * *\"\naction = \"run_tests\"\n\n\
+         [[automations]]\nname = \"todo-extractor\"\ntrigger = \"cron\"\n\
+         schedule = \"0 4 * * *\"\naction = \"run_tests\"\n",
+    )
+    .unwrap();
+    let repo = dir.path().canonicalize().unwrap();
+    let repo = repo.to_str().unwrap();
+    let engine = make_test_engine();
+    let builtins = engine.automations.read().await.len();
+
+    // Loading twice (a re-open) does not duplicate; a built-in name is kept.
+    engine.load_repo(repo).await;
+    engine.load_repo(repo).await;
+    let autos = engine.automations.read().await.clone();
+    assert_eq!(autos.len(), builtins + 1);
+    let nightly = autos.iter().find(|a| a.name == "nightly-tests").unwrap();
+    assert_eq!(nightly.trigger, TriggerType::Cron);
+    assert_eq!(nightly.repo_path.as_deref(), Some(repo));
+    assert_eq!(nightly.cron_key(), format!("{repo}#nightly-tests"));
+    let todo = autos.iter().find(|a| a.name == "todo-extractor").unwrap();
+    assert!(todo.builtin);
+
+    engine.unload_repo(repo).await;
+    let autos = engine.automations.read().await;
+    assert_eq!(autos.len(), builtins);
+    assert!(autos.iter().all(|a| a.repo_path.is_none()));
+}
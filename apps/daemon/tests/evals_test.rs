@@ -237,3 +237,127 @@ async fn test_forbidden_tool_unknown_tool_allowed() {
         "Unknown/read-only tools should be allowed by default"
     );
 }
+
+// ─── LLM judge ────────────────────────────────────────────────────────────────
+
+mod judge {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use clawd::evals::judge::{CliJudge, HttpJudge, Judge, JudgeBackend};
+    use clawd::storage::Storage;
+
+    /// A fake provider CLI that prints `reply`, logs each invocation's argv
+    /// and saves the prompt it read from stdin to `prompt.txt`.
+    #[cfg(unix)]
+    fn fake_provider(dir: &std::path::Path, reply: &str) -> (String, std::path::PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+        let log = dir.join("calls.log");
+        let script = dir.join("fake-claude");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"call $*\" >> '{}'\ncat > '{}'\ncat <<'REPLY'\n{reply}\nREPLY\n",
+                log.display(),
+                dir.join("prompt.txt").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (script.to_string_lossy().to_string(), log)
+    }
+
+    /// Serve one canned chat-completions reply on a local port.
+    async fn fake_endpoint(content: &'static str) -> String {
+        use axum::{routing::post, Json, Router};
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<serde_json::Value>| async move {
+                assert_eq!(body["model"], "judge-model");
+                Json(serde_json::json!({
+                    "choices": [{ "message": { "role": "assistant", "content": content } }]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/v1/chat/completions")
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cli_judge_verdicts_are_cached_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path()).await.unwrap();
+        let (program, log) = fake_provider(
+            dir.path(),
+            r#"Sure. {"score": 8, "reasoning": "Covers the edge cases."}"#,
+        );
+        let judge = Judge::new(vec![Arc::new(
+            CliJudge::new("claude").unwrap().with_program(program),
+        )])
+        .with_cache(storage.clone_pool());
+
+        let first = judge.score("fn add() {}", "Is it correct?").await.unwrap();
+        assert_eq!(first.score, 8.0);
+        assert_eq!(first.reasoning, "Covers the edge cases.");
+        assert!(!first.judgments[0].cached);
+
+        let second = judge.score("fn add() {}", "Is it correct?").await.unwrap();
+        assert!(second.judgments[0].cached);
+        assert_eq!(second.score, 8.0);
+
+        // Different output → cache miss → the provider runs again.
+        judge.score("fn sub() {}", "Is it correct?").await.unwrap();
+        let calls = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(calls.lines().count(), 2);
+        // The prompt travels over stdin, never argv.
+        assert!(!calls.contains("fn sub"));
+        let prompt = std::fs::read_to_string(dir.path().join("prompt.txt")).unwrap();
+        assert!(prompt.contains("fn sub() {}"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn multiple_judges_are_averaged_and_failures_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let (program, _) = fake_provider(dir.path(), r#"{"score": 6, "reasoning": "cli"}"#);
+        let endpoint = fake_endpoint(r#"{"score": 9, "reasoning": "http"}"#).await;
+
+        let backends: Vec<Arc<dyn JudgeBackend>> = vec![
+            Arc::new(CliJudge::new("claude").unwrap().with_program(program)),
+            Arc::new(
+                HttpJudge::new(&endpoint, "judge-model", None, Duration::from_secs(5)).unwrap(),
+            ),
+            Arc::new(
+                CliJudge::new("codex")
+                    .unwrap()
+                    .with_program(dir.path().join("missing").to_string_lossy()),
+            ),
+        ];
+        let verdict = Judge::new(backends)
+            .score("output", "rubric")
+            .await
+            .unwrap();
+
+        assert_eq!(verdict.judgments.len(), 2);
+        assert_eq!(verdict.score, 7.5);
+        assert!(verdict.reasoning.contains("cli") && verdict.reasoning.contains("http"));
+        assert_eq!(verdict.errors.len(), 1);
+        assert!(verdict.errors[0].starts_with("codex"));
+    }
+
+    #[tokio::test]
+    async fn all_judges_failing_is_an_error() {
+        let endpoint = fake_endpoint("I refuse to grade this.").await;
+        let judge = Judge::new(vec![Arc::new(
+            HttpJudge::new(&endpoint, "judge-model", None, Duration::from_secs(5)).unwrap(),
+        )]);
+        let err = judge.score("output", "rubric").await.unwrap_err();
+        assert!(
+            err.to_string().contains("no judge produced a verdict"),
+            "{err}"
+        );
+    }
+}
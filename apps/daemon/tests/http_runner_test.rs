@@ -0,0 +1,275 @@
+//! Direct HTTP provider runner against a local mock SSE server.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use clawd::config::HttpApi;
+use clawd::intelligence::token_tracker::TokenTracker;
+use clawd::ipc::event::EventBroadcaster;
+use clawd::session::http::{HttpEndpoint, HttpRunner};
+use clawd::session::runner::{Runner, ToolDecision};
+use clawd::storage::Storage;
+use serde_json::{json, Value};
+
+/// Serves the scripted SSE bodies in order, one per request, and keeps every
+/// request body for inspection.
+struct MockProvider {
+    base_url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+async fn mock_provider(path: &'static str, responses: Vec<String>) -> MockProvider {
+    use axum::{http::header, routing::post, Json, Router};
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let app = Router::new().route(
+        path,
+        post(move |Json(body): Json<Value>| {
+            let seen = seen.clone();
+            let responses = responses.clone();
+            async move {
+                let n = {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(body);
+                    seen.len() - 1
+                };
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    responses[n.min(responses.len() - 1)].clone(),
+                )
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    MockProvider {
+        base_url: format!("http://{addr}/v1"),
+        requests,
+    }
+}
+
+fn sse(events: &[Value]) -> String {
+    events.iter().map(|e| format!("data: {e}\n\n")).collect()
+}
+
+fn endpoint(api: HttpApi, base_url: &str) -> HttpEndpoint {
+    HttpEndpoint {
+        api,
+        base_url: base_url.to_string(),
+        model: "mock-model".to_string(),
+        api_key: Some("test-key".to_string()),
+        max_tokens: 1024,
+        system_prompt_prefix: None,
+        timeout: Duration::from_secs(10),
+    }
+}
+
+struct Fixture {
+    storage: Arc<Storage>,
+    broadcaster: Arc<EventBroadcaster>,
+    session_id: String,
+    repo: tempfile::TempDir,
+    _data: tempfile::TempDir,
+}
+
+async fn fixture(prompt: &str) -> Fixture {
+    let data = tempfile::tempdir().unwrap();
+    let repo = tempfile::tempdir().unwrap();
+    let storage = Arc::new(Storage::new(data.path()).await.unwrap());
+    let session = storage
+        .create_session("local", &repo.path().to_string_lossy(), "t", None)
+        .await
+        .unwrap();
+    storage
+        .create_message_and_increment_count(&session.id, "user", prompt, "done")
+        .await
+        .unwrap();
+    Fixture {
+        storage,
+        broadcaster: Arc::new(EventBroadcaster::new()),
+        session_id: session.id,
+        repo,
+        _data: data,
+    }
+}
+
+impl Fixture {
+    fn runner(&self, endpoint: HttpEndpoint) -> Arc<HttpRunner> {
+        HttpRunner::new(
+            self.session_id.clone(),
+            self.repo.path().to_string_lossy().to_string(),
+            self.storage.clone(),
+            self.broadcaster.clone(),
+            endpoint,
+        )
+    }
+}
+
+#[tokio::test]
+async fn openai_stream_runs_read_tool_and_records_usage() {
+    let fx = fixture("what is in hello.txt?").await;
+    std::fs::write(fx.repo.path().join("hello.txt"), "hi there").unwrap();
+
+    let mock = mock_provider(
+        "/v1/chat/completions",
+        vec![
+            sse(&[
+                json!({"choices":[{"delta":{"content":"Let me look."}}]}),
+                json!({"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"read_file","arguments":"{\"path\":"}}]}}]}),
+                json!({"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"hello.txt\"}"}}]}}]}),
+                json!({"choices":[],"usage":{"prompt_tokens":100,"completion_tokens":20}}),
+            ]) + "data: [DONE]\n\n",
+            sse(&[
+                json!({"choices":[{"delta":{"content":"It says "}}]}),
+                json!({"choices":[{"delta":{"content":"hi there."}}]}),
+                json!({"choices":[],"usage":{"prompt_tokens":150,"completion_tokens":5}}),
+            ]) + "data: [DONE]\n\n",
+        ],
+    )
+    .await;
+
+    let runner = fx.runner(endpoint(HttpApi::OpenAi, &mock.base_url));
+    runner.run_turn("what is in hello.txt?").await.unwrap();
+
+    let requests = mock.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["stream"], true);
+    assert_eq!(requests[0]["tools"][0]["function"]["name"], "read_file");
+    let last = requests[1]["messages"].as_array().unwrap().last().unwrap();
+    assert_eq!(last["role"], "tool");
+    assert_eq!(last["tool_call_id"], "call_1");
+    assert_eq!(last["content"], "hi there");
+
+    let messages = fx
+        .storage
+        .list_messages(&fx.session_id, 50, None)
+        .await
+        .unwrap();
+    let assistant: Vec<_> = messages
+        .iter()
+        .filter(|m| m.role == "assistant")
+        .map(|m| (m.content.as_str(), m.status.as_str()))
+        .collect();
+    assert_eq!(
+        assistant,
+        vec![("Let me look.", "done"), ("It says hi there.", "done")]
+    );
+
+    let calls = fx
+        .storage
+        .list_tool_calls_for_session(&fx.session_id)
+        .await
+        .unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].status, "done");
+
+    let usage = TokenTracker::new(fx.storage.clone())
+        .get_session_usage(&fx.session_id)
+        .await
+        .unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (250, 25));
+
+    let session = fx
+        .storage
+        .get_session(&fx.session_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(session.status, "idle");
+}
+
+fn anthropic_write_call() -> String {
+    sse(&[
+        json!({"type":"message_start","message":{"usage":{"input_tokens":40,"output_tokens":1}}}),
+        json!({"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"write_file","input":{}}}),
+        json!({"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"path\":\"out.txt\","}}),
+        json!({"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"content\":\"written\"}"}}),
+        json!({"type":"content_block_stop","index":0}),
+        json!({"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":12}}),
+        json!({"type":"message_stop"}),
+    ])
+}
+
+fn anthropic_text(text: &str) -> String {
+    sse(&[
+        json!({"type":"message_start","message":{"usage":{"input_tokens":60,"output_tokens":1}}}),
+        json!({"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}),
+        json!({"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":text}}),
+        json!({"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":3}}),
+        json!({"type":"message_stop"}),
+    ])
+}
+
+/// Run a turn that asks for `write_file`, deliver `decision` once the call is
+/// announced as pending, and return the runner's requests.
+async fn run_with_decision(fx: &Fixture, decision: ToolDecision) -> Vec<Value> {
+    let mock = mock_provider(
+        "/v1/messages",
+        vec![anthropic_write_call(), anthropic_text("Finished.")],
+    )
+    .await;
+    let runner = fx.runner(endpoint(HttpApi::Anthropic, &mock.base_url));
+    let mut events = fx.broadcaster.subscribe();
+
+    let turn = tokio::spawn({
+        let runner = runner.clone();
+        async move { runner.run_turn("write the file").await }
+    });
+
+    let tool_call_id = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let raw = events.recv().await.unwrap();
+            let v: Value = serde_json::from_str(&raw).unwrap();
+            if v["method"] == "session.toolCallCreated" {
+                assert_eq!(v["params"]["toolCall"]["status"], "pending");
+                return v["params"]["toolCall"]["id"].as_str().unwrap().to_string();
+            }
+        }
+    })
+    .await
+    .expect("tool call was never announced");
+
+    // Nothing runs before the user decides.
+    assert!(!fx.repo.path().join("out.txt").exists());
+    assert!(runner.decide_tool(&tool_call_id, decision).await.unwrap());
+    turn.await.unwrap().unwrap();
+
+    let requests = mock.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    requests
+}
+
+#[tokio::test]
+async fn anthropic_write_waits_for_approval() {
+    let fx = fixture("write the file").await;
+    let requests = run_with_decision(&fx, ToolDecision::Approved).await;
+
+    assert_eq!(
+        std::fs::read_to_string(fx.repo.path().join("out.txt")).unwrap(),
+        "written"
+    );
+    assert_eq!(requests[0]["tools"][2]["name"], "write_file");
+    let result = &requests[1]["messages"][2];
+    assert_eq!(result["role"], "user");
+    assert_eq!(result["content"][0]["type"], "tool_result");
+    assert_eq!(result["content"][0]["tool_use_id"], "toolu_1");
+    assert_eq!(result["content"][0]["is_error"], false);
+
+    let usage = TokenTracker::new(fx.storage.clone())
+        .get_session_usage(&fx.session_id)
+        .await
+        .unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (100, 15));
+}
+
+#[tokio::test]
+async fn rejected_tool_is_reported_to_the_model() {
+    let fx = fixture("write the file").await;
+    let requests = run_with_decision(&fx, ToolDecision::Rejected).await;
+
+    assert!(!fx.repo.path().join("out.txt").exists());
+    let result = &requests[1]["messages"][2]["content"][0];
+    assert_eq!(result["is_error"], true);
+    assert!(result["content"].as_str().unwrap().contains("rejected"));
+}
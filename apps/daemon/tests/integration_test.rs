@@ -45,8 +45,15 @@ async fn start_test_daemon() -> (String, Arc<AppContext>) {
     let token_tracker = TokenTracker::new(storage.clone());
     let memory_store = clawd::memory::MemoryStore::new(storage.clone_pool());
     let metrics_store = clawd::metrics::MetricsStore::new(storage.clone_pool());
+    let plugin_manager = Arc::new(clawd::plugins::manager::PluginManager::new(
+        data_dir.join(".claw").join("plugins"),
+    ));
     let quality = clawd::connectivity::new_shared_quality();
     let peer_registry = clawd::connectivity::direct::new_registry();
+    let policy_engine = Arc::new(
+        clawd::policy::PolicyEngine::load(&data_dir.join(".claw"))
+            .with_plugins(plugin_manager.clone()),
+    );
     let ctx = Arc::new(AppContext {
         config,
         storage: storage.clone(),
@@ -86,6 +93,10 @@ async fn start_test_daemon() -> (String, Arc<AppContext>) {
         peer_registry,
         memory_store,
         metrics_store,
+        plugin_manager,
+        mcp_hub: Arc::new(clawd::mcp::McpHub::new(&policy_engine)),
+        audit_log: Arc::new(clawd::storage::event_log::AuditLog::new(&data_dir)),
+        policy_engine,
     });
 
     let ctx_server = ctx.clone();
@@ -0,0 +1,432 @@
+//! LSP proxy tests against a fake stdio language server.
+//!
+//! The fake server is a POSIX shell script that speaks the LSP stdio framing,
+//! logs every message it receives, and answers each request with a canned
+//! result that echoes the document URI back.  It advertises incremental
+//! sync, publishes an informational diagnostic per document version (plus an
+//! error when the text it was sent contains `ERR`), and asks the client for
+//! `workspace/configuration` once initialized.
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clawd::ipc::event::EventBroadcaster;
+use clawd::lsp::{LspConfig, LspProxy, TextRange};
+use clawd::policy::lsp_gate::LspGate;
+use serde_json::Value;
+use tokio::sync::broadcast::Receiver;
+
+/// Canned LSP server.  `$1` is the log file; each received body is appended
+/// as one line.  `textDocument/hover` is preceded by a `window/logMessage`
+/// notification so the proxy has to skip it.
+const FAKE_SERVER: &str = r#"#!/bin/sh
+LC_ALL=C
+export LC_ALL
+LOG="$1"
+CR=$(printf '\r')
+R0='{"start":{"line":0,"character":3},"end":{"line":0,"character":8}}'
+R4='{"start":{"line":4,"character":4},"end":{"line":4,"character":9}}'
+
+send() {
+  printf 'Content-Length: %s\r\n\r\n%s' "${#1}" "$1"
+}
+
+while :; do
+  len=
+  while IFS= read -r line; do
+    line=${line%"$CR"}
+    [ -z "$line" ] && break
+    case "$line" in Content-Length:*) len=${line#Content-Length: } ;; esac
+  done
+  [ -z "$len" ] && exit 0
+  body=$(dd bs=1 count="$len" 2>/dev/null)
+  printf '%s\n' "$body" >> "$LOG"
+
+  method=$(printf '%s' "$body" | sed -n 's/.*"method":"\([^"]*\)".*/\1/p')
+  id=$(printf '%s' "$body" | sed -n 's/^{"jsonrpc":"2.0","id":\([0-9]*\),.*/\1/p')
+  uri=$(printf '%s' "$body" | grep -o '"uri":"[^"]*"' | head -n 1 | cut -d'"' -f4)
+
+  case "$method" in
+    exit) exit 0 ;;
+    initialize) result='{"capabilities":{"textDocumentSync":2}}' ;;
+    initialized)
+      send '{"jsonrpc":"2.0","id":900,"method":"workspace/configuration","params":{"items":[{"section":"fake"}]}}' ;;
+    textDocument/didOpen|textDocument/didChange)
+      ver=$(printf '%s' "$body" | sed -n 's/.*"version":\([0-9]*\).*/\1/p')
+      extra=
+      case "$body" in *ERR*) extra=",{\"range\":$R4,\"severity\":1,\"message\":\"unexpected ERR\"}" ;; esac
+      send "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{\"uri\":\"$uri\",\"diagnostics\":[{\"range\":$R0,\"severity\":3,\"message\":\"v$ver\"}$extra]}}" ;;
+    textDocument/hover)
+      send '{"jsonrpc":"2.0","method":"window/logMessage","params":{"type":3,"message":"indexing"}}'
+      result="{\"contents\":{\"kind\":\"plaintext\",\"value\":\"fn greet(name: &str)\"},\"range\":$R0}" ;;
+    textDocument/definition)
+      result="[{\"uri\":\"$uri\",\"range\":$R0}]" ;;
+    textDocument/references)
+      result="[{\"uri\":\"$uri\",\"range\":$R0},{\"uri\":\"$uri\",\"range\":$R4}]" ;;
+    textDocument/rename)
+      name=$(printf '%s' "$body" | sed -n 's/.*"newName":"\([^"]*\)".*/\1/p')
+      result="{\"changes\":{\"$uri\":[{\"range\":$R0,\"newText\":\"$name\"},{\"range\":$R4,\"newText\":\"$name\"}]}}" ;;
+    textDocument/codeAction)
+      result="[{\"title\":\"Inline variable\",\"kind\":\"refactor.inline\",\"isPreferred\":true,\"edit\":{\"changes\":{\"$uri\":[{\"range\":$R4,\"newText\":\"x\"}]}}},{\"title\":\"Run cargo fix\",\"command\":\"cargo.fix\"}]" ;;
+    textDocument/documentSymbol)
+      result="[{\"name\":\"greet\",\"kind\":12,\"detail\":\"fn(&str)\",\"range\":$R0,\"selectionRange\":$R0,\"children\":[{\"name\":\"name\",\"kind\":13,\"range\":$R4,\"selectionRange\":$R4}]}]" ;;
+    workspace/symbol)
+      query=$(printf '%s' "$body" | sed -n 's/.*"query":"\([^"]*\)".*/\1/p')
+      result="[{\"name\":\"$query\",\"kind\":23,\"containerName\":\"crate\",\"location\":{\"uri\":\"file:///ws/src/a%20b.rs\",\"range\":$R4}}]" ;;
+    *) result=null ;;
+  esac
+  [ -n "$id" ] && [ -n "$method" ] && send "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":$result}"
+done
+"#;
+
+struct Harness {
+    _dir: tempfile::TempDir,
+    proxy: LspProxy,
+    events: Receiver<String>,
+    root: PathBuf,
+    file: PathBuf,
+    log: PathBuf,
+}
+
+impl Harness {
+    fn start() -> Self {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ws");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let file = root.join("src/lib.rs");
+        std::fs::write(&file, "fn greet(name: &str) {}\n").unwrap();
+
+        let script = dir.path().join("fake-lsp");
+        std::fs::write(&script, FAKE_SERVER).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let log = dir.path().join("lsp.log");
+
+        let broadcaster = Arc::new(EventBroadcaster::new());
+        let events = broadcaster.subscribe();
+        let proxy = LspProxy::with_configs(vec![LspConfig {
+            language: "fake".into(),
+            server_command: vec![script.to_string_lossy().into_owned()],
+            server_args: vec![log.to_string_lossy().into_owned()],
+            file_extensions: vec![".rs".into()],
+        }])
+        .with_events(broadcaster);
+        proxy.start_server("fake", &root).unwrap();
+        Self {
+            _dir: dir,
+            proxy,
+            events,
+            root,
+            file,
+            log,
+        }
+    }
+
+    fn received(&self, method: &str) -> Vec<String> {
+        let needle = format!("\"method\":\"{method}\"");
+        std::fs::read_to_string(&self.log)
+            .unwrap_or_default()
+            .lines()
+            .filter(|l| l.contains(&needle))
+            .map(String::from)
+            .collect()
+    }
+
+    /// `params` of every `lsp.diagnosticsChanged` broadcast so far.
+    fn diagnostics_events(&mut self) -> Vec<Value> {
+        let mut out = Vec::new();
+        while let Ok(raw) = self.events.try_recv() {
+            let event: Value = serde_json::from_str(&raw).unwrap();
+            if event["method"] == "lsp.diagnosticsChanged" {
+                out.push(event["params"].clone());
+            }
+        }
+        out
+    }
+
+    /// Make a request so every earlier notification has been processed.
+    fn round_trip(&self) {
+        self.proxy
+            .workspace_symbols("fake", &self.root, "x")
+            .unwrap();
+    }
+
+    fn file_str(&self) -> String {
+        self.file.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.proxy.stop_server("fake", &self.root);
+    }
+}
+
+#[test]
+fn hover_skips_notifications_and_opens_file_once() {
+    let h = Harness::start();
+    let hover = h
+        .proxy
+        .hover("fake", &h.root, &h.file, 0, 4)
+        .unwrap()
+        .expect("hover");
+    assert_eq!(hover.contents, "fn greet(name: &str)");
+    assert_eq!(hover.range.unwrap().end_col, 8);
+
+    h.proxy.hover("fake", &h.root, &h.file, 0, 4).unwrap();
+    let opened = h.received("textDocument/didOpen");
+    assert_eq!(opened.len(), 1, "file should be opened once");
+    assert!(opened[0].contains("fn greet"), "didOpen carries file text");
+}
+
+#[test]
+fn definition_and_references_return_locations() {
+    let h = Harness::start();
+    let defs = h.proxy.definition("fake", &h.root, &h.file, 4, 6).unwrap();
+    assert_eq!(defs.len(), 1);
+    assert_eq!(defs[0].file, h.file_str());
+    assert_eq!((defs[0].range.line, defs[0].range.col), (0, 3));
+
+    let refs = h
+        .proxy
+        .references("fake", &h.root, &h.file, 0, 4, false)
+        .unwrap();
+    assert_eq!(refs.len(), 2);
+    assert_eq!(refs[1].range.line, 4);
+    assert!(h.received("textDocument/references")[0].contains("\"includeDeclaration\":false"));
+}
+
+#[test]
+fn rename_returns_edits_without_touching_disk() {
+    let h = Harness::start();
+    let edit = h
+        .proxy
+        .rename("fake", &h.root, &h.file, 0, 4, "welcome")
+        .unwrap();
+    assert_eq!(edit.changes.len(), 1);
+    assert_eq!(edit.changes[0].file, h.file_str());
+    assert_eq!(edit.changes[0].edits.len(), 2);
+    assert!(edit.changes[0]
+        .edits
+        .iter()
+        .all(|e| e.new_text == "welcome"));
+    assert_eq!(
+        std::fs::read_to_string(&h.file).unwrap(),
+        "fn greet(name: &str) {}\n"
+    );
+}
+
+#[test]
+fn code_actions_cover_edits_and_commands() {
+    let h = Harness::start();
+    let range = TextRange {
+        line: 4,
+        col: 4,
+        end_line: 4,
+        end_col: 9,
+    };
+    let actions = h
+        .proxy
+        .code_actions("fake", &h.root, &h.file, range)
+        .unwrap();
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].kind.as_deref(), Some("refactor.inline"));
+    assert!(actions[0].is_preferred);
+    assert_eq!(
+        actions[0].edit.as_ref().unwrap().changes[0].edits[0].new_text,
+        "x"
+    );
+    assert_eq!(actions[1].command.as_deref(), Some("cargo.fix"));
+    assert!(actions[1].edit.is_none());
+}
+
+#[test]
+fn document_and_workspace_symbols() {
+    let h = Harness::start();
+    let outline = h.proxy.document_symbols("fake", &h.root, &h.file).unwrap();
+    let names: Vec<_> = outline.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["greet", "name"]);
+    assert_eq!(outline[0].kind, "function");
+    assert_eq!(outline[1].container.as_deref(), Some("greet"));
+    assert_eq!(outline[1].location.file, h.file_str());
+
+    let found = h
+        .proxy
+        .workspace_symbols("fake", &h.root, "Greeter")
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].name, "Greeter");
+    assert_eq!(found[0].kind, "struct");
+    assert_eq!(found[0].location.file, "/ws/src/a b.rs");
+}
+
+#[test]
+fn diagnostics_are_cached_and_pushed() {
+    let mut h = Harness::start();
+    let content = std::fs::read_to_string(&h.file).unwrap();
+
+    let diags = h
+        .proxy
+        .get_diagnostics("fake", &h.root, &h.file, &content)
+        .unwrap();
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].message, "v1");
+    assert_eq!(diags[0].file, h.file_str());
+
+    let pushed = h.diagnostics_events();
+    assert_eq!(pushed.len(), 1);
+    assert_eq!(pushed[0]["file"], h.file_str());
+    assert_eq!(pushed[0]["language"], "fake");
+    assert_eq!(pushed[0]["diagnostics"][0]["message"], "v1");
+
+    // Unchanged content: served from the cache, nothing sent.
+    let again = h
+        .proxy
+        .get_diagnostics("fake", &h.root, &h.file, &content)
+        .unwrap();
+    assert_eq!(again, diags);
+    assert!(h.received("textDocument/didChange").is_empty());
+    assert!(h.diagnostics_events().is_empty());
+}
+
+#[test]
+fn changes_are_sent_as_incremental_ranges() {
+    let mut h = Harness::start();
+    h.proxy
+        .get_diagnostics("fake", &h.root, &h.file, "fn greet(name: &str) {}\n")
+        .unwrap();
+    let diags = h
+        .proxy
+        .get_diagnostics("fake", &h.root, &h.file, "fn greet(who: &str) {}\n")
+        .unwrap();
+    assert_eq!(diags[0].message, "v2");
+
+    let changes = h.received("textDocument/didChange");
+    assert_eq!(changes.len(), 1);
+    let change: Value = serde_json::from_str(&changes[0]).unwrap();
+    assert_eq!(change["params"]["textDocument"]["version"], 2);
+    assert_eq!(
+        change["params"]["contentChanges"][0],
+        serde_json::json!({
+            "range": {
+                "start": { "line": 0, "character": 9 },
+                "end": { "line": 0, "character": 13 }
+            },
+            "text": "who"
+        })
+    );
+    assert_eq!(h.diagnostics_events().len(), 2);
+}
+
+#[test]
+fn disk_changes_reach_open_documents() {
+    let mut h = Harness::start();
+    h.proxy.hover("fake", &h.root, &h.file, 0, 0).unwrap();
+
+    std::fs::write(&h.file, "fn greet() {}\n").unwrap();
+    let unrelated = h.root.join("src/other.rs");
+    std::fs::write(&unrelated, "fn other() {}\n").unwrap();
+    h.proxy
+        .sync_changed_files(&[h.file.clone(), unrelated.clone()]);
+    h.round_trip();
+    let changes = h.received("textDocument/didChange");
+    assert_eq!(changes.len(), 1, "only open documents are synced");
+    assert!(changes[0].contains("\"version\":2"));
+
+    h.diagnostics_events();
+    std::fs::remove_file(&h.file).unwrap();
+    h.proxy.sync_changed_files(std::slice::from_ref(&h.file));
+    h.round_trip();
+    assert_eq!(h.received("textDocument/didClose").len(), 1);
+    let cleared = h.diagnostics_events();
+    assert_eq!(cleared.len(), 1);
+    assert_eq!(cleared[0]["diagnostics"], serde_json::json!([]));
+}
+
+#[test]
+fn server_requests_are_answered() {
+    let h = Harness::start();
+    // The reply is sent by the reader thread, independently of our requests.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let reply = loop {
+        let log = std::fs::read_to_string(&h.log).unwrap_or_default();
+        if let Some(line) = log.lines().find(|l| l.contains("\"id\":900")) {
+            break serde_json::from_str::<Value>(line).unwrap();
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "no workspace/configuration reply"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    assert_eq!(reply["result"], serde_json::json!([null]));
+}
+
+#[test]
+fn concurrent_requests_are_matched_by_id() {
+    let h = Harness::start();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| h.proxy.definition("fake", &h.root, &h.file, 0, 4)))
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().unwrap().len(), 1);
+        }
+    });
+}
+
+#[test]
+fn gate_reports_errors_a_patch_introduces_until_fixed() {
+    let h = Harness::start();
+    let gate = LspGate::default();
+    let files = ["src/lib.rs".to_string()];
+
+    gate.before_patch(&h.proxy, "t1", &h.root, &files);
+    std::fs::write(&h.file, "fn greet(name: &str) { ERR }\n").unwrap();
+    let report = gate.after_patch(&h.proxy, "t1", &h.root, &files);
+    assert_eq!(report.checked_files, vec![h.file_str()]);
+    assert_eq!(report.new_errors.len(), 1, "info diagnostics are ignored");
+    assert_eq!(report.new_errors[0].message, "unexpected ERR");
+    let blocked = gate.check_review_transition("t1").unwrap_err();
+    assert!(blocked.to_string().contains("1 LSP error"), "{blocked}");
+
+    // A later patch that fixes the error clears it.
+    std::fs::write(&h.file, "fn greet(name: &str) {}\n").unwrap();
+    let report = gate.after_patch(&h.proxy, "t1", &h.root, &files);
+    assert!(report.new_errors.is_empty());
+    assert!(gate.check_review_transition("t1").is_ok());
+}
+
+#[test]
+fn gate_ignores_errors_that_predate_the_task() {
+    let h = Harness::start();
+    std::fs::write(&h.file, "// ERR\nfn a() {}\n").unwrap();
+    let gate = LspGate::default();
+    let files = ["src/lib.rs".to_string(), "README.md".to_string()];
+
+    gate.before_patch(&h.proxy, "t2", &h.root, &files);
+    std::fs::write(&h.file, "// ERR\nfn a() {}\nfn b() { ERR }\n").unwrap();
+    let report = gate.after_patch(&h.proxy, "t2", &h.root, &files);
+    assert_eq!(
+        report.checked_files,
+        vec![h.file_str()],
+        "files without a server are skipped"
+    );
+    assert!(report.new_errors.is_empty(), "{:?}", report.new_errors);
+}
+
+#[test]
+fn requests_need_a_running_server() {
+    let proxy = LspProxy::with_configs(vec![]);
+    let err = proxy
+        .hover(
+            "fake",
+            Path::new("/nowhere"),
+            Path::new("/nowhere/a.rs"),
+            0,
+            0,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("not running"), "{err}");
+}
@@ -206,3 +206,139 @@ async fn test_handoff_chain() {
     let qa = registry.get(&chain[3]).expect("qa record");
     assert_eq!(qa.role, AgentRole::QaExecutor);
 }
+
+// ─── Configured fallback chains ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_spawn_follows_configured_fallback_chain() {
+    use clawd::config::{FallbackChainsConfig, ProjectFallbackConfig};
+
+    let mut chains = FallbackChainsConfig::default();
+    chains.roles.insert(
+        "reviewer".into(),
+        vec!["claude".into(), "gemini".into(), "codex".into()],
+    );
+    chains.projects.insert(
+        "/repos/app".into(),
+        ProjectFallbackConfig {
+            default: vec!["cursor".into(), "claude".into()],
+            ..Default::default()
+        },
+    );
+    let orch = Orchestrator::new().with_fallback_chains(chains);
+
+    // Project default applies to every role in that repo.
+    let planner = orch
+        .spawn_in_project(
+            AgentRole::Planner,
+            "t1",
+            "high",
+            None,
+            None,
+            Some("/repos/app"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(planner.provider, Provider::Unknown("cursor".into()));
+    assert!(planner.hops.is_empty());
+
+    // The reviewer chain starts with claude, but the implementer already used
+    // it, so routing hops to the next entry and says why.
+    let reviewer = orch
+        .spawn_in_project(
+            AgentRole::Reviewer,
+            "t1",
+            "medium",
+            None,
+            Some(Provider::Claude),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(reviewer.provider, Provider::Unknown("gemini".into()));
+    assert_eq!(reviewer.hops.len(), 1);
+    assert_eq!(reviewer.hops[0].from, "claude");
+    assert_eq!(reviewer.hops[0].to.as_deref(), Some("gemini"));
+    assert_eq!(reviewer.hops[0].chain, "roles.reviewer");
+
+    let registry = orch.registry.read().await;
+    assert_eq!(registry.get(&reviewer.agent_id).unwrap().model, "default");
+}
+
+#[tokio::test]
+async fn test_spawn_skips_providers_without_an_available_account() {
+    use clawd::config::FallbackChainsConfig;
+    use clawd::scheduler::accounts::{AccountEntry, AccountPool};
+    use clawd::scheduler::fallback::FallbackEngine;
+    use clawd::scheduler::rate_limits::RateLimitTracker;
+    use std::sync::Arc;
+
+    let account = |id: &str, provider: &str| AccountEntry {
+        account_id: id.into(),
+        provider: provider.into(),
+        vault_ref: String::new(),
+        is_available: true,
+        blocked_until: None,
+        rpm_used: 0,
+        tpm_used: 0,
+        total_requests: 0,
+        last_used: None,
+    };
+    let pool = Arc::new(AccountPool::new());
+    pool.register(account("c1", "claude")).await;
+    pool.register(account("g1", "gemini")).await;
+    pool.mark_rate_limited("c1", 60).await;
+    let engine = Arc::new(FallbackEngine::new(
+        Arc::clone(&pool),
+        Arc::new(RateLimitTracker::new()),
+    ));
+
+    let chains = FallbackChainsConfig {
+        default: vec!["claude".into(), "gemini".into(), "codex".into()],
+        ..Default::default()
+    };
+    let orch = Orchestrator::new()
+        .with_fallback_chains(chains)
+        .with_fallback_engine(engine);
+
+    // Claude's only account is rate-limited, so the chain moves on to gemini.
+    let planner = orch
+        .spawn_in_project(AgentRole::Planner, "t1", "high", None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(planner.provider, Provider::Unknown("gemini".into()));
+    assert_eq!(planner.hops.len(), 1);
+    assert_eq!(planner.hops[0].from, "claude");
+    assert!(planner.hops[0]
+        .reason
+        .contains("no available claude account"));
+
+    // With gemini exhausted too, codex (not managed by the pool) is used.
+    pool.mark_rate_limited("g1", 60).await;
+    let implementer = orch
+        .spawn_in_project(AgentRole::Implementer, "t1", "high", None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(implementer.provider, Provider::Codex);
+    assert_eq!(implementer.hops.len(), 2);
+
+    // A chain whose every provider is exhausted refuses to spawn.
+    let only_claude = FallbackChainsConfig {
+        default: vec!["claude".into()],
+        ..Default::default()
+    };
+    let orch = Orchestrator::new()
+        .with_fallback_chains(only_claude)
+        .with_fallback_engine(Arc::new(FallbackEngine::new(
+            pool,
+            Arc::new(RateLimitTracker::new()),
+        )));
+    let err = orch
+        .spawn_in_project(AgentRole::Planner, "t3", "high", None, None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        clawd::agents::orchestrator::OrchestratorError::NoAvailableProvider { .. }
+    ));
+}
@@ -0,0 +1,159 @@
+/// Plugin hot reload — version swap, rollback and the directory watcher.
+///
+/// Each test installs a small WASM plugin whose `version` RPC method reports
+/// which build is active, then replaces it on disk.
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clawd::ipc::event::EventBroadcaster;
+use clawd::plugins::manager::PluginManager;
+use clawd::plugins::watcher::PluginWatcher;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::Receiver;
+
+/// A plugin build: `plugin.bump.version` returns `{"v":<build>}`, `on_unload`
+/// sends `plugin.bump.unloaded {"v":<build>}`, and init returns `init_code`.
+fn build(build: u32, init_code: i32) -> String {
+    format!(
+        r#"(module
+          (import "env" "clawd_register_method" (func $method (param i32 i32) (result i32)))
+          (import "env" "clawd_set_result" (func $result (param i32 i32) (result i32)))
+          (import "env" "clawd_send_event" (func $send (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (global $heap (mut i32) (i32.const 1024))
+          (func (export "clawd_alloc") (param $len i32) (result i32)
+            (global.get $heap)
+            (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+          (data (i32.const 0) "version")
+          (data (i32.const 16) "{{\"v\":{build}}}")
+          (data (i32.const 32) "plugin.bump.unloaded")
+          (func (export "clawd_plugin_init") (result i32)
+            (drop (call $method (i32.const 0) (i32.const 7)))
+            (i32.const {init_code}))
+          (func (export "on_rpc") (param i32 i32 i32 i32) (result i32)
+            (call $result (i32.const 16) (i32.const {len})))
+          (func (export "on_unload") (result i32)
+            (call $send (i32.const 32) (i32.const 20) (i32.const 16) (i32.const {len}))))"#,
+        len = format!("{{\"v\":{build}}}").len(),
+    )
+}
+
+fn install(plugin_dir: &Path, version: &str, wat: &str) {
+    std::fs::create_dir_all(plugin_dir).unwrap();
+    std::fs::write(
+        plugin_dir.join("clawd-plugin.json"),
+        json!({
+            "name": "bump",
+            "version": version,
+            "runtime": "wasm",
+            "entry": "bump.wasm",
+            "capabilities": ["network.relay"],
+            "signature": ""
+        })
+        .to_string(),
+    )
+    .unwrap();
+    std::fs::write(plugin_dir.join("bump.wasm"), wat::parse_str(wat).unwrap()).unwrap();
+}
+
+async fn active_build(manager: &PluginManager) -> Value {
+    manager
+        .call_method("plugin.bump.version", json!({}))
+        .await
+        .unwrap()
+}
+
+async fn next_event(events: &mut Receiver<String>, method: &str) -> Value {
+    loop {
+        let raw = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .unwrap_or_else(|_| panic!("no {method} event"))
+            .unwrap();
+        let event: Value = serde_json::from_str(&raw).unwrap();
+        if event["method"] == method {
+            return event["params"].clone();
+        }
+    }
+}
+
+async fn setup() -> (tempfile::TempDir, Arc<PluginManager>, Receiver<String>) {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    install(&plugins_dir.path().join("bump"), "0.1.0", &build(1, 0));
+    let broadcaster = Arc::new(EventBroadcaster::new());
+    let events = broadcaster.subscribe();
+    let manager =
+        Arc::new(PluginManager::new(plugins_dir.path().to_path_buf()).with_events(broadcaster));
+    manager.load_all().await.unwrap();
+    assert_eq!(active_build(&manager).await, json!({"v": 1}));
+    (plugins_dir, manager, events)
+}
+
+#[tokio::test]
+async fn reload_unloads_old_version_and_swaps_in_new() {
+    let (dir, manager, mut events) = setup().await;
+    install(&dir.path().join("bump"), "0.2.0", &build(2, 0));
+
+    manager.reload("bump").await.unwrap();
+
+    assert_eq!(
+        next_event(&mut events, "plugin.bump.unloaded").await,
+        json!({"v": 1})
+    );
+    assert_eq!(
+        next_event(&mut events, "plugin.reloaded").await,
+        json!({"name": "bump", "version": "0.2.0", "previousVersion": "0.1.0"})
+    );
+    assert_eq!(active_build(&manager).await, json!({"v": 2}));
+    let info = &manager.list().await[0];
+    assert_eq!(info.version, "0.2.0");
+    assert_eq!(info.methods, vec!["plugin.bump.version".to_string()]);
+}
+
+#[tokio::test]
+async fn failed_on_load_rolls_back_to_previous_version() {
+    let (dir, manager, mut events) = setup().await;
+    install(&dir.path().join("bump"), "0.3.0", &build(3, 1));
+
+    let err = manager.reload("bump").await.unwrap_err();
+    assert!(format!("{err:#}").contains("kept version 0.1.0"), "{err:#}");
+
+    let failed = next_event(&mut events, "plugin.reloadFailed").await;
+    assert_eq!(failed["version"], "0.1.0");
+    assert_eq!(active_build(&manager).await, json!({"v": 1}));
+    assert_eq!(manager.list().await[0].version, "0.1.0");
+}
+
+#[tokio::test]
+async fn broken_binary_leaves_running_version_untouched() {
+    let (dir, manager, mut events) = setup().await;
+    std::fs::write(dir.path().join("bump/bump.wasm"), b"not wasm").unwrap();
+
+    assert!(manager.reload("bump").await.is_err());
+
+    next_event(&mut events, "plugin.reloadFailed").await;
+    assert!(
+        events.try_recv().is_err(),
+        "old version must not be unloaded"
+    );
+    assert_eq!(active_build(&manager).await, json!({"v": 1}));
+}
+
+#[tokio::test]
+async fn watcher_reloads_when_plugin_files_change() {
+    let (dir, manager, _events) = setup().await;
+    let _watcher = PluginWatcher::start(manager.clone(), Duration::from_millis(100))
+        .expect("watcher should start");
+
+    install(&dir.path().join("bump"), "0.2.0", &build(2, 0));
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    while manager.list().await[0].version != "0.2.0" {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "plugin was not reloaded"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(active_build(&manager).await, json!({"v": 2}));
+}
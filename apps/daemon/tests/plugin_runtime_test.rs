@@ -82,4 +82,43 @@ mod plugin_runtime_tests {
         let not_wasm = b"ELF binary here";
         assert!(!not_wasm.starts_with(b"\0asm"));
     }
+
+    #[tokio::test]
+    async fn manager_delivers_hooks_and_unloads() {
+        use clawd::plugins::hooks::ToolCallDecision;
+        use clawd::plugins::manager::{PluginManager, PluginStatus};
+        use serde_json::json;
+
+        let dir = tempfile::tempdir().unwrap();
+        let plugin_dir = dir.path().join("noop");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join("clawd-plugin.json"),
+            r#"{"name": "noop", "version": "0.1.0", "runtime": "wasm", "entry": "noop.wasm"}"#,
+        )
+        .unwrap();
+        let wasm = wat::parse_str(
+            r#"(module (func (export "clawd_plugin_init") (result i32) i32.const 0))"#,
+        )
+        .unwrap();
+        std::fs::write(plugin_dir.join("noop.wasm"), wasm).unwrap();
+
+        let manager = PluginManager::new(dir.path().to_path_buf());
+        manager.load_all().await.unwrap();
+        assert_eq!(manager.list().await.len(), 1);
+
+        manager.on_session_start("s1").await;
+        manager.on_message("s1", "user", "hello").await;
+        let decision = manager
+            .on_tool_call("s1", "apply_patch", &json!({"patch": ""}))
+            .await;
+        assert_eq!(decision, ToolCallDecision::Allow);
+        manager.on_session_end("s1").await;
+
+        manager.disable("noop").await.unwrap();
+        assert!(matches!(
+            manager.list().await[0].status,
+            PluginStatus::Disabled
+        ));
+    }
 }
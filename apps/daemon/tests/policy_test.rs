@@ -33,7 +33,7 @@ fn make_engine() -> PolicyEngine {
     PolicyEngine::new(
         Arc::new(RwLock::new(RiskDatabase::default_rules())),
         Arc::new(RwLock::new(TrustDatabase::default())),
-        Arc::new(SupplyChainPolicy::empty()),
+        Arc::new(RwLock::new(SupplyChainPolicy::empty())),
     )
 }
 
@@ -0,0 +1,1903 @@
+//! Integration tests for the REST API router.
+//! Serves `rest::build_router` on a random port and drives it over HTTP.
+
+use clawd::{
+    account::AccountRegistry,
+    agents::orchestrator::Orchestrator,
+    config::{ApiScope, DaemonConfig},
+    intelligence::token_tracker::TokenTracker,
+    ipc::event::EventBroadcaster,
+    license::LicenseInfo,
+    repo::RepoRegistry,
+    scheduler::{
+        accounts::AccountPool, fallback::FallbackEngine, queue::SchedulerQueue,
+        rate_limits::RateLimitTracker,
+    },
+    session::SessionManager,
+    storage::Storage,
+    tasks::TaskStorage,
+    telemetry, update,
+    worktree::WorktreeManager,
+    AppContext,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Credentials the test daemon is configured with.
+#[derive(Default)]
+struct Auth {
+    api_token: Option<&'static str>,
+    api_scope: ApiScope,
+    daemon_token: &'static str,
+}
+
+/// Build a minimal AppContext for testing.
+async fn make_test_ctx(dir: &TempDir, auth: Auth) -> Arc<AppContext> {
+    let data_dir = dir.path().to_path_buf();
+    // A free WebSocket port, for tests that also run `ipc::run`.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut config = DaemonConfig::new(
+        Some(port),
+        Some(data_dir.clone()),
+        Some("error".to_string()),
+        None,
+        None,
+    );
+    config.api_token = auth.api_token.map(String::from);
+    config.api_scope = auth.api_scope;
+    let config = Arc::new(config);
+    let storage = Arc::new(Storage::new(&data_dir).await.unwrap());
+    let broadcaster = Arc::new(EventBroadcaster::new());
+    let repo_registry = Arc::new(RepoRegistry::new(broadcaster.clone()));
+    let session_manager = Arc::new(SessionManager::new(
+        storage.clone(),
+        broadcaster.clone(),
+        data_dir.clone(),
+    ));
+    let account_registry = Arc::new(AccountRegistry::new(storage.clone(), broadcaster.clone()));
+    let updater = Arc::new(update::spawn(config.clone(), broadcaster.clone()));
+    let account_pool = Arc::new(AccountPool::new());
+    let rate_limit_tracker = Arc::new(RateLimitTracker::new());
+    let fallback_engine = Arc::new(FallbackEngine::new(
+        Arc::clone(&account_pool),
+        Arc::clone(&rate_limit_tracker),
+    ));
+
+    let token_tracker = TokenTracker::new(storage.clone());
+    let memory_store = clawd::memory::MemoryStore::new(storage.clone_pool());
+    memory_store.migrate().await.unwrap();
+    let metrics_store = clawd::metrics::MetricsStore::new(storage.clone_pool());
+    let plugin_manager = Arc::new(clawd::plugins::manager::PluginManager::new(
+        data_dir.join(".claw").join("plugins"),
+    ));
+    let quality = clawd::connectivity::new_shared_quality();
+    let peer_registry = clawd::connectivity::direct::new_registry();
+    let policy_engine = Arc::new(
+        clawd::policy::PolicyEngine::load(&data_dir.join(".claw"))
+            .with_plugins(plugin_manager.clone()),
+    );
+
+    Arc::new(AppContext {
+        config: config.clone(),
+        storage: storage.clone(),
+        broadcaster,
+        repo_registry,
+        session_manager,
+        daemon_id: "test-daemon-id".to_string(),
+        license: Arc::new(tokio::sync::RwLock::new(LicenseInfo::free())),
+        telemetry: Arc::new(telemetry::spawn(
+            config,
+            "test-daemon-id".to_string(),
+            "free".to_string(),
+        )),
+        account_registry,
+        updater,
+        started_at: std::time::Instant::now(),
+        auth_token: auth.daemon_token.to_string(),
+        task_storage: Arc::new(TaskStorage::new(storage.clone_pool())),
+        worktree_manager: Arc::new(WorktreeManager::new(&data_dir)),
+        account_pool,
+        rate_limit_tracker,
+        fallback_engine,
+        scheduler_queue: Arc::new(SchedulerQueue::new()),
+        orchestrator: Arc::new(Orchestrator::new()),
+        token_tracker,
+        metrics: Arc::new(clawd::metrics::DaemonMetrics::new()),
+        version_watcher: Arc::new(clawd::doctor::version_watcher::VersionWatcher::new(
+            Arc::new(clawd::ipc::event::EventBroadcaster::new()),
+        )),
+        ide_bridge: clawd::ide::new_shared_bridge(),
+        provider_sessions: clawd::agents::provider_session::new_shared_registry(),
+        recovery_mode: false,
+        automation_engine: clawd::automations::engine::AutomationEngine::new(
+            clawd::automations::builtins::all(),
+        ),
+        quality,
+        peer_registry,
+        memory_store,
+        metrics_store,
+        plugin_manager,
+        mcp_hub: Arc::new(clawd::mcp::McpHub::new(&policy_engine)),
+        audit_log: Arc::new(clawd::storage::event_log::AuditLog::new(&data_dir)),
+        policy_engine,
+    })
+}
+
+struct Api {
+    _dir: TempDir,
+    ctx: Arc<AppContext>,
+    base: String,
+    http: reqwest::Client,
+    /// Daemon token `call` sends, if the daemon has one.
+    token: &'static str,
+}
+
+impl Api {
+    async fn start(auth: Auth) -> Self {
+        let dir = TempDir::new().unwrap();
+        let token = auth.daemon_token;
+        let ctx = make_test_ctx(&dir, auth).await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = clawd::rest::build_router(ctx.clone());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+        Self {
+            _dir: dir,
+            ctx,
+            base: format!("http://{addr}/api/v1"),
+            http: reqwest::Client::new(),
+            token,
+        }
+    }
+
+    async fn call(&self, method: &str, path: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+        let mut req = self.http.request(method, format!("{}{path}", self.base));
+        if !self.token.is_empty() {
+            req = req.bearer_auth(self.token);
+        }
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let resp = req.send().await.unwrap();
+        let status = resp.status();
+        let body = resp.json().await.unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    async fn add_task(&self, id: &str) {
+        self.add_task_in(id, "/nonexistent/repo").await;
+    }
+
+    async fn add_task_in(&self, id: &str, repo: &str) {
+        self.ctx
+            .task_storage
+            .add_task(
+                id, id, None, None, None, None, None, None, None, None, None, None, repo,
+            )
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_routed() {
+    let api = Api::start(Auth::default()).await;
+    for op in clawd::rest::openapi::operations() {
+        let mut path = op.path.to_string();
+        for param in op.path_params() {
+            path = path.replace(&format!("{{{param}}}"), "missing");
+        }
+        // No body: JSON extractors reject the request before the handler runs.
+        let url = format!("{}{path}", api.base);
+        let method = reqwest::Method::from_bytes(op.method.to_uppercase().as_bytes()).unwrap();
+        let resp = api.http.request(method, &url).send().await.unwrap();
+        let status = resp.status();
+        assert_ne!(
+            status,
+            StatusCode::METHOD_NOT_ALLOWED,
+            "{} {path}",
+            op.method
+        );
+        if status == StatusCode::NOT_FOUND {
+            let body: Value = resp.json().await.unwrap();
+            let error = body["error"].as_str().unwrap_or_default();
+            assert!(
+                !error.starts_with("No such endpoint"),
+                "{} {path} is not routed",
+                op.method
+            );
+        }
+    }
+
+    let (status, body) = api.call("get", "/nope", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "No such endpoint: /api/v1/nope");
+
+    let (_, spec) = api.call("get", "/openapi.json", None).await;
+    assert!(spec["paths"]["/tasks/{id}/claim"]["post"]["responses"]["409"].is_object());
+    assert_eq!(
+        spec["paths"]["/memory/{id}"]["delete"]["parameters"][0]["name"],
+        "id"
+    );
+}
+
+#[tokio::test]
+async fn tasks_are_paginated_claimed_and_transitioned() {
+    let api = Api::start(Auth::default()).await;
+    for id in ["t1", "t2", "t3"] {
+        api.add_task(id).await;
+    }
+
+    let (status, page) = api.call("get", "/tasks?limit=2", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["tasks"].as_array().unwrap().len(), 2);
+    assert_eq!(page["pagination"]["next_offset"], 2);
+    let (_, page) = api.call("get", "/tasks?limit=2&offset=2", None).await;
+    assert_eq!(page["tasks"].as_array().unwrap().len(), 1);
+    assert!(page["pagination"]["next_offset"].is_null());
+
+    let claim = |agent: &str| Some(json!({ "agent_id": agent }));
+    let (status, body) = api.call("post", "/tasks/t1/claim", claim("a1")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["task"]["status"], "in_progress");
+    let (status, _) = api.call("post", "/tasks/t1/claim", claim("a2")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = api.call("get", "/tasks/nope", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let done = |notes: Option<&str>| Some(json!({ "status": "done", "notes": notes }));
+    let (status, body) = api.call("post", "/tasks/t1/transition", done(None)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+    let (status, body) = api
+        .call("post", "/tasks/t1/transition", done(Some("shipped")))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["task"]["status"], "done");
+
+    let (_, page) = api.call("get", "/tasks?status=done", None).await;
+    assert_eq!(page["tasks"][0]["id"], "t1");
+}
+
+#[tokio::test]
+async fn memory_entries_can_be_written_and_deleted() {
+    let api = Api::start(Auth::default()).await;
+
+    let (status, body) = api
+        .call(
+            "post",
+            "/memory",
+            Some(json!({ "key": "style", "value": "tabs", "weight": 3 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    let id = body["entry"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = api
+        .call(
+            "patch",
+            &format!("/memory/{id}"),
+            Some(json!({ "weight": 9 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["entry"]["weight"], 9);
+    assert_eq!(body["entry"]["value"], "tabs");
+
+    let (_, list) = api.call("get", "/memory", None).await;
+    assert_eq!(list["pagination"]["total"], 1);
+
+    let (status, _) = api.call("delete", &format!("/memory/{id}"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = api.call("delete", &format!("/memory/{id}"), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = api
+        .call(
+            "patch",
+            &format!("/memory/{id}"),
+            Some(json!({ "value": "x" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn search_spans_messages_tasks_memory_and_tool_calls() {
+    let api = Api::start(Auth::default()).await;
+    let storage = &api.ctx.storage;
+    let session = storage
+        .create_session("claude", "/repo", "Release", None)
+        .await
+        .unwrap();
+    let sid = session.id.as_str();
+    let asked = storage
+        .create_message(sid, "user", "please deploy the staging cluster", "done")
+        .await
+        .unwrap();
+    // Streaming chunks are indexed only once the message settles.
+    let reply = storage
+        .create_message(sid, "assistant", "", "streaming")
+        .await
+        .unwrap();
+    storage
+        .update_message_content(&reply.id, "rolling out", "streaming")
+        .await
+        .unwrap();
+    storage
+        .update_message_content(&reply.id, "deploy finished 🚀", "done")
+        .await
+        .unwrap();
+    storage
+        .create_tool_call_event(sid, "shell", Some("kubectl deploy"), "auto", None)
+        .await
+        .unwrap();
+    api.add_task("deploy-pipeline").await;
+    api.ctx
+        .memory_store
+        .upsert(clawd::memory::AddMemoryRequest {
+            scope: "global".into(),
+            key: "deploy.target".into(),
+            value: "staging".into(),
+            weight: None,
+            source: None,
+        })
+        .await
+        .unwrap();
+
+    let api = &api;
+    let search = |params: Value| async move {
+        let (_, resp) = rpc(
+            api,
+            None,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "session.search", "params": params }),
+        )
+        .await;
+        resp
+    };
+
+    // Two hits per page, walked with the cursor.
+    let mut seen = Vec::new();
+    let mut cursor = Value::Null;
+    loop {
+        let resp = search(json!({ "query": "deploy", "limit": 2, "cursor": cursor })).await;
+        let result = &resp["result"];
+        assert_eq!(result["totalHits"], 5, "{resp}");
+        for hit in result["results"].as_array().unwrap() {
+            let snippet: Vec<u16> = hit["snippet"].as_str().unwrap().encode_utf16().collect();
+            let [start, end] = [0, 1].map(|i| hit["highlights"][0][i].as_u64().unwrap() as usize);
+            let matched = String::from_utf16(&snippet[start..end]).unwrap();
+            assert_eq!(matched.to_lowercase(), "deploy", "{hit}");
+            seen.push((hit["kind"].as_str().unwrap().to_string(), hit["id"].clone()));
+        }
+        cursor = result["nextCursor"].clone();
+        if cursor.is_null() {
+            break;
+        }
+    }
+    seen.sort_by(|a, b| a.0.cmp(&b.0));
+    let kinds: Vec<&str> = seen.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(kinds, ["memory", "message", "message", "task", "toolCall"]);
+    assert!(seen.contains(&("message".into(), json!(reply.id))));
+
+    let total = |resp: Value| resp["result"]["totalHits"].clone();
+    assert_eq!(total(search(json!({ "query": "rolling" })).await), 0);
+    let in_session = search(json!({ "query": "deploy", "filterBy": { "sessionId": sid } })).await;
+    assert_eq!(in_session["result"]["totalHits"], 3);
+    assert_eq!(in_session["result"]["results"][0]["sessionId"], sid);
+    // Filters are bound parameters, not spliced into the SQL.
+    let injected = json!({ "query": "deploy", "filterBy": { "sessionId": "x' OR '1'='1" } });
+    assert_eq!(total(search(injected).await), 0);
+    let by_user = search(json!({ "query": "deploy", "filterBy": { "role": "user" } })).await;
+    assert_eq!(
+        by_user["result"]["results"][0]["messageId"],
+        json!(asked.id)
+    );
+    assert_eq!(by_user["result"]["totalHits"], 1);
+    let kinds = json!({ "query": "deploy", "kinds": ["task", "memory"] });
+    assert_eq!(total(search(kinds).await), 2);
+    let later = json!({ "query": "deploy", "filterBy": { "dateFrom": "2999-01-01T00:00:00Z" } });
+    assert_eq!(total(search(later).await), 0);
+    let resp = search(json!({ "query": "deploy", "cursor": "bogus" })).await;
+    assert!(resp["error"].is_object(), "{resp}");
+
+    storage
+        .update_message_content(&asked.id, "never mind", "done")
+        .await
+        .unwrap();
+    assert_eq!(total(search(json!({ "query": "deploy" })).await), 4);
+}
+
+#[tokio::test]
+async fn search_is_refused_to_repo_limited_tokens() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let storage = &api.ctx.storage;
+    let other = storage
+        .create_session("claude", "/work/other", "Other", None)
+        .await
+        .unwrap();
+    storage
+        .create_message(&other.id, "user", "deploy the secret cluster", "done")
+        .await
+        .unwrap();
+    let search = json!({ "jsonrpc": "2.0", "id": 1, "method": "session.search",
+                         "params": { "query": "deploy", "repo_path": "/work/app" } });
+
+    let limited = create_token(
+        &api,
+        json!({ "name": "app", "methods": ["session.*"], "repos": ["/work/app"] }),
+    )
+    .await;
+    let (_, resp) = rpc(&api, Some(&limited), search.clone()).await;
+    assert_eq!(resp["error"]["code"], -32004, "{resp}");
+
+    let open = create_token(&api, json!({ "name": "all", "methods": ["session.*"] })).await;
+    let (_, resp) = rpc(&api, Some(&open), search).await;
+    assert_eq!(resp["result"]["totalHits"], 1, "{resp}");
+}
+
+#[tokio::test]
+async fn approvals_and_automations() {
+    let api = Api::start(Auth::default()).await;
+
+    let (status, _) = api
+        .call(
+            "post",
+            "/approvals/a1",
+            Some(json!({ "decision": "grant" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = api
+        .call(
+            "post",
+            "/approvals/a1",
+            Some(json!({ "decision": "maybe" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, list) = api.call("get", "/automations?limit=1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let name = list["automations"][0]["name"].as_str().unwrap().to_string();
+    let (status, body) = api
+        .call(
+            "patch",
+            &format!("/automations/{name}"),
+            Some(json!({ "enabled": false })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], false);
+    let (status, _) = api
+        .call("post", &format!("/automations/{name}/trigger"), None)
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let (status, _) = api
+        .call(
+            "patch",
+            "/automations/nope",
+            Some(json!({ "enabled": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn requested_approvals_are_listed_and_granted() {
+    let api = Api::start(Auth::default()).await;
+    for (task, repo) in [
+        ("t1", "/work/app"),
+        ("t2", "/work/app"),
+        ("t3", "/work/other"),
+    ] {
+        api.add_task_in(task, repo).await;
+        let pending = clawd::mcp::tools::task::request_approval(
+            &api.ctx,
+            json!({ "task_id": task, "tool_name": "apply_patch",
+                    "arguments": { "path": "a.rs" }, "risk_level": "high" }),
+            Some("agent-1"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending["pending"], true);
+    }
+    let task = api.ctx.task_storage.get_task("t1").await.unwrap().unwrap();
+    assert_eq!(task.status, "blocked");
+
+    let (status, body) = api
+        .call("get", "/approvals?repo_path=/work/app&limit=1", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let first = &body["approvals"][0];
+    assert_eq!(first["tool_name"], "apply_patch");
+    assert_eq!(first["arguments"]["path"], "a.rs");
+    assert_eq!(body["pagination"]["next_offset"], 1);
+    let (_, body) = api
+        .call("get", "/approvals?repo_path=/work/app&offset=1", None)
+        .await;
+    assert_eq!(body["approvals"].as_array().unwrap().len(), 1);
+    assert!(body["pagination"]["next_offset"].is_null());
+
+    let id = first["approval_id"].as_str().unwrap();
+    let task_id = first["task_id"].as_str().unwrap();
+    let (status, body) = api
+        .call(
+            "post",
+            &format!("/approvals/{id}"),
+            Some(json!({ "decision": "grant" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let task = api
+        .ctx
+        .task_storage
+        .get_task(task_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, "in_progress");
+
+    // Answered approvals leave the list and cannot be answered again.
+    let (_, body) = api.call("get", "/approvals", None).await;
+    assert_eq!(body["approvals"].as_array().unwrap().len(), 2);
+    let (status, _) = api
+        .call(
+            "post",
+            &format!("/approvals/{id}"),
+            Some(json!({ "decision": "deny" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn api_token_is_required_when_configured() {
+    let api = Api::start(Auth {
+        api_token: Some("s3cret"),
+        ..Auth::default()
+    })
+    .await;
+
+    let (status, _) = api.call("get", "/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = api.call("get", "/tasks", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let resp = api
+        .http
+        .get(format!("{}/tasks", api.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    // A query token is only read on the SSE routes.
+    let (status, _) = api.call("get", "/tasks?token=s3cret", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let resp = api
+        .http
+        .get(format!("{}/events?token=s3cret", api.base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let (status, _) = api.call("get", "/events?token=wrong", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn daemon_token_is_required_without_an_api_token() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+
+    let (status, _) = api.call("get", "/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let resp = api
+        .http
+        .post(format!("{}/memory", api.base))
+        .json(&json!({ "key": "k", "value": "v" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let (status, _) = api.call("get", "/tasks", None).await;
+    assert_eq!(status, StatusCode::OK, "call sends the daemon token");
+}
+
+async fn rpc(api: &Api, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let mut req = api.http.post(format!("{}/rpc", api.base)).json(&body);
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+    let resp = req.send().await.unwrap();
+    let status = resp.status();
+    (status, resp.json().await.unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn rpc_bridge_runs_single_calls_and_ordered_batches() {
+    let api = Api::start(Auth::default()).await;
+
+    let (status, resp) = rpc(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": "a", "method": "tasks.list", "params": {} }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resp["id"], "a");
+    assert!(resp["result"]["tasks"].is_array(), "{resp}");
+
+    let (status, resp) = rpc(
+        &api,
+        None,
+        json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "memory.add", "params": { "key": "k", "value": "v" } },
+            { "jsonrpc": "2.0", "id": 2, "method": "memory.list", "params": {} },
+            { "jsonrpc": "2.0", "method": "daemon.ping" },
+            42,
+            { "jsonrpc": "2.0", "id": 5, "method": "no.such" },
+            { "jsonrpc": "2.0", "id": 6, "method": "memory.list", "params": {} }
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let resp = resp.as_array().unwrap();
+    let ids: Vec<&Value> = resp.iter().map(|r| &r["id"]).collect();
+    assert_eq!(
+        ids,
+        [&json!(1), &json!(2), &Value::Null, &json!(5), &json!(6)]
+    );
+    assert_eq!(
+        resp[1]["result"]["count"], 1,
+        "the read sees the earlier write"
+    );
+    assert_eq!(resp[2]["error"]["code"], -32600);
+    assert_eq!(resp[3]["error"]["code"], -32601);
+
+    let (status, _) = rpc(
+        &api,
+        None,
+        json!([{ "jsonrpc": "2.0", "method": "daemon.ping" }]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, resp) = rpc(&api, None, json!([])).await;
+    assert_eq!(resp["error"]["code"], -32600);
+
+    let resp = api
+        .http
+        .post(format!("{}/rpc", api.base))
+        .body("{not json")
+        .send()
+        .await
+        .unwrap();
+    let resp: Value = resp.json().await.unwrap();
+    assert_eq!(resp["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn rpc_bridge_enforces_token_scopes() {
+    let api = Api::start(Auth {
+        api_token: Some("reader"),
+        api_scope: ApiScope::Read,
+        daemon_token: "daemon",
+    })
+    .await;
+
+    let (status, _) = rpc(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "tasks.list" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, resp) = rpc(
+        &api,
+        Some("reader"),
+        json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "memory.list" },
+            { "jsonrpc": "2.0", "id": 2, "method": "memory.add", "params": { "key": "k", "value": "v" } },
+            { "jsonrpc": "2.0", "id": 3, "method": "account.list" }
+        ]),
+    )
+    .await;
+    assert!(resp[0]["result"].is_object(), "{resp}");
+    assert_eq!(resp[1]["error"]["code"], -32004);
+    assert!(resp[1]["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("'write'"));
+    assert_eq!(resp[2]["error"]["code"], -32004);
+
+    // REST routes follow the same scope: read-only tokens cannot write.
+    let resp = api
+        .http
+        .post(format!("{}/memory", api.base))
+        .bearer_auth("reader")
+        .json(&json!({ "key": "k", "value": "v" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // The daemon token grants admin.
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "account.list" }),
+    )
+    .await;
+    assert!(resp.get("error").is_none(), "{resp}");
+}
+
+/// Create a scoped token with the daemon token and return its secret.
+async fn create_token(api: &Api, params: Value) -> String {
+    let (_, resp) = rpc(
+        api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "token.create", "params": params }),
+    )
+    .await;
+    resp["result"]["secret"]
+        .as_str()
+        .unwrap_or_else(|| panic!("token.create failed: {resp}"))
+        .to_string()
+}
+
+#[tokio::test]
+async fn repo_limited_tokens_cannot_reach_other_repos_by_id() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    for (task, repo) in [("t-app", "/work/app"), ("t-other", "/work/other")] {
+        api.add_task_in(task, repo).await;
+        api.ctx
+            .task_storage
+            .request_approval(
+                &format!("ap-{task}"),
+                task,
+                "agent",
+                "apply_patch",
+                "high",
+                "{}",
+            )
+            .await
+            .unwrap();
+    }
+    let secret = create_token(
+        &api,
+        json!({ "name": "app", "methods": ["approval.*", "memory.*", "automation.*"], "repos": ["/work/app"] }),
+    )
+    .await;
+    let send = |method: reqwest::Method, path: &str| {
+        api.http
+            .request(method, format!("{}{path}", api.base))
+            .bearer_auth(&secret)
+    };
+
+    let resp = send(reqwest::Method::GET, "/approvals?repo_path=/work/app")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let tasks: Vec<&str> = body["approvals"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|a| a["task_id"].as_str())
+        .collect();
+    assert_eq!(tasks, ["t-app"]);
+
+    // An allowed repo_path does not vouch for another repo's approval, nor
+    // for an id that does not resolve.
+    for id in ["ap-t-other", "ap-elsewhere"] {
+        let resp = send(
+            reqwest::Method::POST,
+            &format!("/approvals/{id}?repo_path=/work/app"),
+        )
+        .json(&json!({ "decision": "grant" }))
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN, "{id}");
+    }
+    let resp = send(
+        reqwest::Method::POST,
+        "/approvals/ap-t-app?repo_path=/work/app",
+    )
+    .json(&json!({ "decision": "grant" }))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Memory entries and automations are not tied to a checked repo.
+    let resp = send(reqwest::Method::DELETE, "/memory/m1?repo_path=/work/app")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let (_, resp) = rpc(
+        &api,
+        Some(&secret),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "automation.list",
+                "params": { "repo_path": "/work/app" } }),
+    )
+    .await;
+    assert_eq!(resp["error"]["code"], -32004, "{resp}");
+}
+
+#[tokio::test]
+async fn scoped_tokens_limit_methods_and_repos() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    api.add_task_in("t-app", "/work/app").await;
+    api.add_task_in("t-other", "/work/other").await;
+    let secret = create_token(
+        &api,
+        json!({ "name": "ci", "methods": ["tasks.*"], "repos": ["/work/app/"] }),
+    )
+    .await;
+    assert!(secret.starts_with("clawd_"));
+
+    let (_, resp) = rpc(
+        &api,
+        Some(&secret),
+        json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "tasks.list", "params": { "repo_path": "/work/app" } },
+            { "jsonrpc": "2.0", "id": 2, "method": "tasks.get", "params": { "task_id": "t-app" } },
+            { "jsonrpc": "2.0", "id": 3, "method": "tasks.list", "params": {} },
+            { "jsonrpc": "2.0", "id": 4, "method": "tasks.list", "params": { "repo_path": "/work/other" } },
+            { "jsonrpc": "2.0", "id": 5, "method": "tasks.get", "params": { "task_id": "t-other" } },
+            { "jsonrpc": "2.0", "id": 6, "method": "memory.list", "params": { "repo_path": "/work/app" } },
+            { "jsonrpc": "2.0", "id": 7, "method": "token.list" }
+        ]),
+    )
+    .await;
+    assert!(resp[0]["result"]["tasks"].is_array(), "{resp}");
+    assert_eq!(resp[1]["result"]["task"]["id"], "t-app", "{resp}");
+    for denied in &resp.as_array().unwrap()[2..] {
+        assert_eq!(denied["error"]["code"], -32004, "{denied}");
+    }
+    assert!(resp[2]["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("pass repo_path"));
+
+    // REST routes map to the same methods and repos.
+    let get = |path: &str| {
+        api.http
+            .get(format!("{}{path}", api.base))
+            .bearer_auth(&secret)
+            .send()
+    };
+    assert_eq!(
+        get("/tasks?repo_path=/work/app").await.unwrap().status(),
+        StatusCode::OK
+    );
+    assert_eq!(get("/tasks/t-app").await.unwrap().status(), StatusCode::OK);
+    assert_eq!(
+        get("/tasks/t-other").await.unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(get("/tasks").await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        get("/memory?repo_path=/work/app").await.unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        get("/tasks?repo_path=/work/app/../other")
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::FORBIDDEN
+    );
+    // The query cannot vouch for a different repo in the body.
+    let resp = api
+        .http
+        .post(format!(
+            "{}/tasks/t-app/claim?repo_path=/work/app",
+            api.base
+        ))
+        .bearer_auth(&secret)
+        .json(&json!({ "agent_id": "a1", "repo_path": "/work/other" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Listing shows use and never the secret.
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "token.list" }),
+    )
+    .await;
+    let listed = &resp["result"]["tokens"][0];
+    assert_eq!(listed["status"], "active");
+    assert_eq!(listed["repos"], json!(["/work/app"]));
+    assert!(listed["last_used_at"].is_number(), "{listed}");
+    assert!(!resp.to_string().contains(&secret));
+}
+
+#[tokio::test]
+async fn revoked_and_expired_tokens_are_rejected() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "daemon.ping" });
+
+    let revoked = create_token(&api, json!({ "name": "a", "methods": ["daemon.*"] })).await;
+    let (status, _) = rpc(&api, Some(&revoked), ping.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "token.list" }),
+    )
+    .await;
+    let id = resp["result"]["tokens"][0]["id"].clone();
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "token.revoke", "params": { "id": id } }),
+    )
+    .await;
+    assert_eq!(resp["result"]["revoked"], true, "{resp}");
+    let (status, _) = rpc(&api, Some(&revoked), ping.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let expired = create_token(
+        &api,
+        json!({ "name": "b", "methods": ["*"], "expires_in_secs": 3600 }),
+    )
+    .await;
+    sqlx::query("UPDATE api_tokens SET expires_at = 1 WHERE name = 'b'")
+        .execute(api.ctx.storage.pool())
+        .await
+        .unwrap();
+    let (status, _) = rpc(&api, Some(&expired), ping).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "token.create",
+                "params": { "name": "c", "methods": ["tasks list"] } }),
+    )
+    .await;
+    assert_eq!(resp["error"]["code"], -32602, "{resp}");
+}
+
+#[tokio::test]
+async fn scoped_tokens_are_enforced_on_the_websocket() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let secret = create_token(
+        &api,
+        json!({ "name": "ws", "methods": ["daemon.ping", "account.*", "task.*"] }),
+    )
+    .await;
+    let ctx = api.ctx.clone();
+    tokio::spawn(async move { clawd::ipc::run(ctx).await.ok() });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let (mut ws, _) = connect_async(format!("ws://127.0.0.1:{}", api.ctx.config.port))
+        .await
+        .unwrap();
+    let mut call = async |id: u64, method: &str, params: Value| -> Value {
+        let req = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        ws.send(Message::Text(req.to_string())).await.unwrap();
+        loop {
+            if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                let v: Value = serde_json::from_str(&text).unwrap();
+                if v["id"] == id {
+                    return v;
+                }
+            }
+        }
+    };
+
+    let resp = call(1, "daemon.auth", json!({ "token": secret })).await;
+    assert_eq!(resp["result"]["authenticated"], true, "{resp}");
+    let resp = call(2, "daemon.ping", json!({})).await;
+    assert!(resp.get("error").is_none(), "{resp}");
+    let resp = call(3, "tasks.list", json!({})).await;
+    assert_eq!(resp["error"]["code"], -32004);
+    // Admin methods stay out of reach whatever the globs say.
+    let resp = call(4, "account.list", json!({})).await;
+    assert_eq!(resp["error"]["code"], -32004, "{resp}");
+    let (_, resp) = rpc(
+        &api,
+        Some(&secret),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "account.list" }),
+    )
+    .await;
+    assert!(resp.get("result").is_none(), "{resp}");
+
+    // Broadcasts are held to the same globs, even without a subscription.
+    let events = &api.ctx.broadcaster;
+    events.broadcast("other.tick", json!({}));
+    events.broadcast("account.updated", json!({}));
+    events.broadcast("task.updated", json!({ "task_id": "t1" }));
+    let event = loop {
+        if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+            let v: Value = serde_json::from_str(&text).unwrap();
+            if v.get("id").is_none() {
+                break v;
+            }
+        }
+    };
+    assert_eq!(event["method"], "task.updated", "{event}");
+
+    // Revocation applies to the open connection on its next call.
+    sqlx::query("UPDATE api_tokens SET revoked_at = 1")
+        .execute(api.ctx.storage.pool())
+        .await
+        .unwrap();
+    let req = json!({ "jsonrpc": "2.0", "id": 5, "method": "daemon.ping" });
+    ws.send(Message::Text(req.to_string())).await.unwrap();
+    let resp = loop {
+        if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+            let v: Value = serde_json::from_str(&text).unwrap();
+            if v["id"] == 5 {
+                break v;
+            }
+        }
+    };
+    assert_eq!(resp["error"]["code"], -32004);
+}
+
+/// Read `n` SSE events as `(id, event, data)`, skipping keep-alive comments.
+async fn read_sse(resp: &mut reqwest::Response, n: usize) -> Vec<(String, String, Value)> {
+    let mut buf = String::new();
+    let mut events = Vec::new();
+    while events.len() < n {
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), resp.chunk())
+            .await
+            .expect("timed out waiting for SSE events")
+            .unwrap()
+            .expect("SSE stream ended");
+        buf.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buf.find("\n\n") {
+            let block: String = buf.drain(..end + 2).collect();
+            let (mut id, mut event, mut data) = (String::new(), String::new(), Value::Null);
+            for line in block.lines() {
+                if let Some(v) = line.strip_prefix("id: ") {
+                    id = v.to_string();
+                } else if let Some(v) = line.strip_prefix("event: ") {
+                    event = v.to_string();
+                } else if let Some(v) = line.strip_prefix("data: ") {
+                    data = serde_json::from_str(v).unwrap_or(Value::Null);
+                }
+            }
+            if !event.is_empty() {
+                events.push((id, event, data));
+            }
+        }
+    }
+    events
+}
+
+#[tokio::test]
+async fn event_stream_filters_and_resumes_from_last_event_id() {
+    let api = Api::start(Auth::default()).await;
+    let events = &api.ctx.broadcaster;
+    // Ids carry a per-run epoch; `base` is this run's id before any event.
+    let base = events.last_event_id();
+    let id = |n: u64| (base + n).to_string();
+    events.broadcast("session.statusChanged", json!({ "sessionId": "s1" }));
+    events.broadcast(
+        "task.updated",
+        json!({ "task_id": "t1", "repo_path": "/work/app" }),
+    );
+    events.broadcast("session.statusChanged", json!({ "sessionId": "s2" }));
+
+    let open = |query: &str, last_id: Option<&str>| {
+        let mut req = api.http.get(format!("{}/events{query}", api.base));
+        if let Some(id) = last_id {
+            req = req.header("Last-Event-ID", id);
+        }
+        req.send()
+    };
+
+    // Replay from the start, filtered by type and session.
+    let mut resp = open("?types=session.*&session_ids=s2&last_event_id=0", None)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let got = read_sse(&mut resp, 1).await;
+    assert_eq!(got[0].0, id(3));
+    assert_eq!(got[0].1, "session.statusChanged");
+    assert_eq!(got[0].2["params"]["sessionId"], "s2");
+
+    // Last-Event-ID resumes after the given id; the repo filter applies.
+    let mut resp = open("?repo_path=/work/app", Some(&id(1))).await.unwrap();
+    events.broadcast("task.updated", json!({ "repo_path": "/work/other" }));
+    events.broadcast("task.updated", json!({ "repo_path": "/work/app/sub" }));
+    let got = read_sse(&mut resp, 2).await;
+    assert_eq!(got[0].0, id(2));
+    assert_eq!(got[1].0, id(5));
+
+    // An id the buffer cannot account for (here, one from an earlier daemon
+    // run) yields a gap, then live events.
+    let mut resp = open("?types=live.*", Some("999")).await.unwrap();
+    events.broadcast("live.one", json!({}));
+    let got = read_sse(&mut resp, 2).await;
+    assert_eq!(got[0].1, "gap");
+    assert_eq!(got[1].1, "live.one");
+    assert_eq!(got[1].0, id(6));
+}
+
+#[tokio::test]
+async fn websocket_clients_can_subscribe_and_resume() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+    let api = Api::start(Auth::default()).await;
+    let ctx = api.ctx.clone();
+    tokio::spawn(async move { clawd::ipc::run(ctx).await.ok() });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    // The daemon may already have broadcast events of its own.
+    let base = api.ctx.broadcaster.last_event_id();
+    for n in 1..=3 {
+        api.ctx
+            .broadcaster
+            .broadcast("test.tick", json!({ "n": n }));
+    }
+    api.ctx.broadcaster.broadcast("other.tick", json!({}));
+
+    let (mut ws, _) = connect_async(format!("ws://127.0.0.1:{}", api.ctx.config.port))
+        .await
+        .unwrap();
+    let req = json!({ "jsonrpc": "2.0", "id": 1, "method": "events.subscribe",
+                      "params": { "types": ["test.*"], "last_event_id": base + 1 } });
+    ws.send(Message::Text(req.to_string())).await.unwrap();
+
+    let mut next = async || -> Value {
+        loop {
+            if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    };
+    let resp = next().await;
+    assert_eq!(resp["result"]["subscribed"], true, "{resp}");
+    let last = resp["result"]["last_event_id"].as_u64().unwrap();
+    assert!(last >= base + 4, "{resp}");
+
+    api.ctx
+        .broadcaster
+        .broadcast("test.tick", json!({ "n": 4 }));
+    let mut got = Vec::new();
+    for _ in 0..3 {
+        let ev = next().await;
+        assert_eq!(ev["method"], "test.tick", "{ev}");
+        got.push((ev["event_id"].as_u64().unwrap(), ev["params"]["n"].clone()));
+    }
+    assert_eq!(got[..2], [(base + 2, json!(2)), (base + 3, json!(3))]);
+    assert!(got[2].0 > last);
+    assert_eq!(got[2].1, 4);
+}
+
+/// POST one MCP message, returning status, session header and body.
+async fn mcp_post(
+    api: &Api,
+    session: Option<&str>,
+    msg: Value,
+) -> (StatusCode, Option<String>, Value) {
+    let mut req = api
+        .http
+        .post(format!("{}/mcp", api.base))
+        .bearer_auth("daemon")
+        .json(&msg);
+    if let Some(session) = session {
+        req = req.header("Mcp-Session-Id", session);
+    }
+    let resp = req.send().await.unwrap();
+    let status = resp.status();
+    let header = resp
+        .headers()
+        .get("mcp-session-id")
+        .map(|v| v.to_str().unwrap().to_string());
+    (status, header, resp.json().await.unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn mcp_sessions_serve_tools_and_resources() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+
+    let repo = api._dir.path().to_str().unwrap().to_string();
+    let (status, session, init) = mcp_post(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" } } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{init}");
+    let session = session.expect("initialize sets Mcp-Session-Id");
+    assert_eq!(init["result"]["protocolVersion"], "2025-03-26");
+    assert!(init["result"]["capabilities"]["tools"].is_object());
+    assert!(init["result"]["capabilities"]["resources"].is_object());
+
+    // Every later request needs the session.
+    let list = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" });
+    let (status, _, _) = mcp_post(&api, None, list.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _, _) = mcp_post(&api, Some("nope"), list.clone()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    let (status, _, _) = mcp_post(&api, Some(&session), initialized).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (_, _, tools) = mcp_post(&api, Some(&session), list.clone()).await;
+    let names: Vec<&str> = tools["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|t| t["name"].as_str())
+        .collect();
+    assert!(names.contains(&"create_task") && names.contains(&"apply_patch"));
+
+    let (_, _, created) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+            "name": "create_task", "arguments": { "title": "From MCP", "repo": repo } } }),
+    )
+    .await;
+    assert_eq!(created["result"]["isError"], false, "{created}");
+    let task_id = created["result"]["structuredContent"]["task_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (_, _, unknown) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                "params": { "name": "no_such_tool", "arguments": {} } }),
+    )
+    .await;
+    assert_eq!(unknown["error"]["code"], -32602);
+
+    let (_, _, read) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 5, "method": "resources/read",
+                "params": { "uri": format!("clawd://task/{task_id}") } }),
+    )
+    .await;
+    let text = read["result"]["contents"][0]["text"].as_str().unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(text).unwrap()["title"],
+        "From MCP"
+    );
+
+    let end = api
+        .http
+        .delete(format!("{}/mcp", api.base))
+        .bearer_auth("daemon")
+        .header("Mcp-Session-Id", &session)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(end.status(), StatusCode::NO_CONTENT);
+    let (status, _, _) = mcp_post(&api, Some(&session), list).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn mcp_built_in_tools_follow_raised_risk_rules() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let risk_file = api._dir.path().join("tool-risk.json");
+    std::fs::write(&risk_file, r#"{ "high": ["create_task"] }"#).unwrap();
+    *api.ctx.policy_engine.risk_db.write().await =
+        clawd::policy::risk::RiskDatabase::load_from_json(&risk_file);
+
+    let (_, session, _) = mcp_post(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" } } }),
+    )
+    .await;
+    let session = session.unwrap();
+    let repo = api._dir.path().to_str().unwrap().to_string();
+    let (_, _, refused) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+            "name": "create_task", "arguments": { "title": "Gated", "repo": repo } } }),
+    )
+    .await;
+    assert_eq!(refused["error"]["code"], -32028, "{refused}");
+}
+
+/// Registers RPC method `ping`, which answers `{"pong":true}`.
+const PINGER: &str = r#"(module
+  (import "env" "clawd_register_method" (func $method (param i32 i32) (result i32)))
+  (import "env" "clawd_set_result" (func $result (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (func (export "clawd_alloc") (param $len i32) (result i32)
+    (global.get $heap)
+    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+  (data (i32.const 0) "ping")
+  (data (i32.const 16) "{\"pong\":true}")
+  (func (export "clawd_plugin_init") (result i32)
+    (call $method (i32.const 0) (i32.const 4)))
+  (func (export "on_rpc") (param i32 i32 i32 i32) (result i32)
+    (call $result (i32.const 16) (i32.const 13))))"#;
+
+/// Install and load [`PINGER`] as plugin `pinger` with the given manifest.
+async fn load_pinger(api: &Api, manifest: Value) {
+    let plugin_dir = api._dir.path().join("pinger");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::write(plugin_dir.join("clawd-plugin.json"), manifest.to_string()).unwrap();
+    std::fs::write(
+        plugin_dir.join("plugin.wasm"),
+        wat::parse_str(PINGER).unwrap(),
+    )
+    .unwrap();
+    api.ctx
+        .plugin_manager
+        .load_plugin(&plugin_dir)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn plugin_methods_go_through_the_policy_engine() {
+    let api = Api::start(Auth::default()).await;
+    load_pinger(
+        &api,
+        json!({ "name": "pinger", "version": "0.1.0", "runtime": "wasm",
+                "entry": "plugin.wasm", "signature": "" }),
+    )
+    .await;
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "plugin.pinger.ping", "params": {} });
+
+    // Unrated methods are medium risk and need an in-progress task.
+    let (_, denied) = rpc(&api, None, ping.clone()).await;
+    assert_eq!(denied["error"]["code"], -32028, "{denied}");
+
+    let risk_file = api._dir.path().join("tool-risk.json");
+    std::fs::write(&risk_file, r#"{ "low": ["plugin.pinger.ping"] }"#).unwrap();
+    *api.ctx.policy_engine.risk_db.write().await =
+        clawd::policy::risk::RiskDatabase::load_from_json(&risk_file);
+    let (_, pong) = rpc(&api, None, ping).await;
+    assert_eq!(pong["result"], json!({ "pong": true }), "{pong}");
+
+    let audit = std::fs::read_to_string(api._dir.path().join("audit.log")).unwrap();
+    let statuses: Vec<String> = audit
+        .lines()
+        .map(|l| serde_json::from_str::<Value>(l).unwrap())
+        .filter(|e| e["toolName"] == "plugin.pinger.ping")
+        .map(|e| e["approvalStatus"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(statuses, ["denied", "allowed"]);
+}
+
+#[tokio::test]
+async fn plugin_methods_declared_low_risk_need_no_task() {
+    let api = Api::start(Auth::default()).await;
+    load_pinger(
+        &api,
+        json!({ "name": "pinger", "version": "0.1.0", "runtime": "wasm",
+                "entry": "plugin.wasm", "signature": "", "method_risk": { "ping": "low" } }),
+    )
+    .await;
+
+    let (_, pong) = rpc(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "plugin.pinger.ping", "params": {} }),
+    )
+    .await;
+    assert_eq!(pong["result"], json!({ "pong": true }), "{pong}");
+}
+
+#[tokio::test]
+async fn lsp_requests_stay_inside_registered_workspaces() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let root = api._dir.path().join("work");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+
+    // RPC: `file` must resolve inside `workspaceRoot`.
+    let (_, resp) = rpc(
+        &api,
+        Some("daemon"),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "lsp.hover", "params": {
+            "language": "rust", "workspaceRoot": root,
+            "file": root.join("../outside.rs"), "line": 0, "col": 0 } }),
+    )
+    .await;
+    assert_eq!(resp["error"]["code"], -32602, "{resp}");
+
+    // MCP: the repo must be registered.
+    let (_, session, _) = mcp_post(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" } } }),
+    )
+    .await;
+    let (_, _, refused) = mcp_post(
+        &api,
+        Some(&session.unwrap()),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+            "name": "lsp_hover", "arguments": {
+                "repo": root, "file": "main.rs", "line": 0, "col": 0 } } }),
+    )
+    .await;
+    assert_eq!(refused["error"]["code"], -32602, "{refused}");
+    assert!(refused["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("not a registered repository"));
+}
+
+#[tokio::test]
+async fn mcp_subscriptions_and_prompts() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let (_, session, init) = mcp_post(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" } } }),
+    )
+    .await;
+    let session = session.unwrap();
+    assert_eq!(
+        init["result"]["capabilities"]["resources"]["subscribe"],
+        true
+    );
+    assert!(init["result"]["capabilities"]["prompts"].is_object());
+
+    // Updates arrive on the session's GET stream.
+    let mut stream = api
+        .http
+        .get(format!("{}/mcp", api.base))
+        .bearer_auth("daemon")
+        .header("Mcp-Session-Id", &session)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+
+    let (_, _, sub) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/subscribe",
+                "params": { "uri": "clawd://task/t1" } }),
+    )
+    .await;
+    assert!(sub["result"].is_object(), "{sub}");
+    let (_, _, repo_sub) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/subscribe",
+                "params": { "uri": "clawd://repo/README.md" } }),
+    )
+    .await;
+    assert_eq!(repo_sub["error"]["code"], -32602);
+
+    // A burst of changes to one task is one notification; other tasks are
+    // not reported.
+    for id in ["t2", "t1", "t1"] {
+        api.ctx
+            .broadcaster
+            .broadcast("task.statusChanged", json!({ "task_id": id }));
+    }
+    let updates = read_sse(&mut stream, 1).await;
+    assert_eq!(updates[0].2["method"], "notifications/resources/updated");
+    assert_eq!(updates[0].2["params"]["uri"], "clawd://task/t1");
+
+    let (_, _, prompts) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "prompts/list" }),
+    )
+    .await;
+    let names: Vec<&str> = prompts["result"]["prompts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|p| p["name"].as_str())
+        .collect();
+    assert!(names.contains(&"instructions") && names.contains(&"workflow/code-review"));
+
+    let (_, _, review) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 5, "method": "prompts/get", "params": {
+            "name": "workflow/code-review", "arguments": { "diff": "+fn added() {}" } } }),
+    )
+    .await;
+    let messages = review["result"]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["role"], "user");
+    assert!(messages[0]["content"]["text"]
+        .as_str()
+        .unwrap()
+        .ends_with("+fn added() {}"));
+
+    // The instructions prompt serves the golden snapshot when there is one,
+    // for registered repos only.
+    let project = api._dir.path().join("project");
+    git2::Repository::init(&project).unwrap();
+    clawd::instructions::snapshot::write_snapshot(
+        "# Rules\nUse pnpm.",
+        &project.join(".instruction-snapshot.md"),
+    )
+    .await
+    .unwrap();
+    let get_instructions = json!({ "jsonrpc": "2.0", "id": 6, "method": "prompts/get", "params": {
+        "name": "instructions", "arguments": { "path": project } } });
+    let (_, _, unregistered) = mcp_post(&api, Some(&session), get_instructions.clone()).await;
+    assert_eq!(unregistered["error"]["code"], -32602, "{unregistered}");
+    api.ctx
+        .repo_registry
+        .open(project.to_str().unwrap())
+        .await
+        .unwrap();
+    let (_, _, instructions) = mcp_post(&api, Some(&session), get_instructions).await;
+    assert_eq!(
+        instructions["result"]["messages"][0]["content"]["text"],
+        "# Rules\nUse pnpm."
+    );
+
+    let (_, _, missing) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 7, "method": "prompts/get",
+                "params": { "name": "workflow/nope" } }),
+    )
+    .await;
+    assert_eq!(missing["error"]["code"], -32602);
+}
+
+/// A WebSocket MCP server with one tool, `search`.
+async fn upstream_mcp_server() -> String {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(Message::Text(text))) = ws.next().await {
+                    let msg: Value = serde_json::from_str(&text).unwrap();
+                    let result = match msg["method"].as_str() {
+                        _ if msg.get("id").is_none() => continue,
+                        Some("initialize") => json!({
+                            "protocolVersion": msg["params"]["protocolVersion"],
+                            "capabilities": { "tools": {} },
+                            "serverInfo": { "name": "upstream", "version": "0" } }),
+                        Some("tools/list") => json!({ "tools": [{
+                            "name": "search", "description": "Search the index",
+                            "inputSchema": { "type": "object" } }] }),
+                        Some("tools/call") => json!({ "content": [{ "type": "text",
+                            "text": format!("found {}", msg["params"]["arguments"]["q"]) }] }),
+                        _ => json!({}),
+                    };
+                    let reply = json!({ "jsonrpc": "2.0", "id": msg["id"], "result": result });
+                    ws.send(Message::Text(reply.to_string())).await.unwrap();
+                }
+            });
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn upstream_mcp_tools_are_served_and_reported() {
+    use clawd::policy::mcp_trust::{McpTrustEntry, TrustLevel};
+
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    api.ctx
+        .policy_engine
+        .trust_db
+        .write()
+        .await
+        .upsert(McpTrustEntry {
+            server_name: "upstream".into(),
+            trust: TrustLevel::Trusted,
+            command_hash: None,
+            allowed_tools: Vec::new(),
+        });
+    let servers = json!({ "servers": [
+        { "name": "upstream", "url": upstream_mcp_server().await },
+        { "name": "stranger", "url": "ws://127.0.0.1:9" },
+    ] });
+    api.ctx.mcp_hub.start(
+        clawd::mcp::McpServersConfig::parse(&servers.to_string()).unwrap(),
+        None,
+    );
+
+    let status = |resp: &Value, name: &str| {
+        resp["result"]["servers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["name"] == name)
+            .cloned()
+            .unwrap()
+    };
+    let mut resp = Value::Null;
+    for _ in 0..100 {
+        (_, resp) = rpc(
+            &api,
+            Some("daemon"),
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "mcp.status", "params": {} }),
+        )
+        .await;
+        if status(&resp, "upstream")["state"] == "running" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let upstream = status(&resp, "upstream");
+    assert_eq!(upstream["state"], "running", "{resp}");
+    assert_eq!(upstream["tools"], json!(["upstream__search"]));
+    assert_eq!(upstream["transport"], "websocket");
+    let stranger = status(&resp, "stranger");
+    assert_eq!(stranger["state"], "blocked");
+    assert!(stranger["lastError"]
+        .as_str()
+        .unwrap()
+        .contains("mcp-trust.json"));
+
+    let (_, session, _) = mcp_post(
+        &api,
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" } } }),
+    )
+    .await;
+    let session = session.unwrap();
+    let (_, _, tools) = mcp_post(
+        &api,
+        Some(&session),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    )
+    .await;
+    let tool = tools["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["name"] == "upstream__search")
+        .cloned()
+        .expect("upstream tool is listed");
+    assert_eq!(tool["description"], "Search the index");
+
+    // Upstream tools go through the policy engine like plugin tools: a
+    // medium-risk call needs an in-progress task.
+    let call = |id: u64, arguments: Value| {
+        json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": "upstream__search", "arguments": arguments } })
+    };
+    let (_, _, denied) = mcp_post(&api, Some(&session), call(3, json!({ "q": "x" }))).await;
+    assert_eq!(denied["error"]["code"], -32028, "{denied}");
+
+    api.add_task("t1").await;
+    // A daemon-token session acts as `mcp:<session id>`.
+    let (status, _) = api
+        .call(
+            "post",
+            "/tasks/t1/claim",
+            Some(json!({ "agent_id": format!("mcp:{session}") })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, _, found) = mcp_post(
+        &api,
+        Some(&session),
+        call(4, json!({ "task_id": "t1", "q": "x" })),
+    )
+    .await;
+    // The server's own result is passed through.
+    assert_eq!(
+        found["result"]["content"][0]["text"], "found \"x\"",
+        "{found}"
+    );
+    assert!(found["result"].get("structuredContent").is_none());
+}
+
+#[tokio::test]
+async fn mcp_tool_calls_follow_token_scopes() {
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let secret = create_token(
+        &api,
+        json!({ "name": "mcp-reader", "methods": ["mcp.list*", "mcp.ping"] }),
+    )
+    .await;
+    let post = |session: Option<String>, msg: Value| {
+        let mut req = api
+            .http
+            .post(format!("{}/mcp", api.base))
+            .bearer_auth(&secret)
+            .json(&msg);
+        if let Some(session) = session {
+            req = req.header("Mcp-Session-Id", session);
+        }
+        req.send()
+    };
+
+    let resp = post(
+        None,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2024-11-05", "capabilities": {},
+            "clientInfo": { "name": "reader", "version": "1" } } }),
+    )
+    .await
+    .unwrap();
+    let session = resp.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let init: Value = resp.json().await.unwrap();
+    assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 2, "method": "tools/list" },
+        { "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+          "params": { "name": "create_task", "arguments": { "title": "x", "repo": "/r" } } },
+    ]);
+    let resp: Value = post(Some(session.clone()), batch)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(resp[0]["result"]["tools"].is_array(), "{resp}");
+    assert_eq!(resp[1]["error"]["code"], -32004, "{resp}");
+
+    // Only the token that started the session may end it.
+    let end = |token: &str| {
+        api.http
+            .delete(format!("{}/mcp", api.base))
+            .bearer_auth(token)
+            .header("Mcp-Session-Id", &session)
+            .send()
+    };
+    assert_eq!(end("daemon").await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(end(&secret).await.unwrap().status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn mcp_stdio_bridge_relays_progress_and_responses() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let api = Api::start(Auth {
+        daemon_token: "daemon",
+        ..Auth::default()
+    })
+    .await;
+    let endpoint = format!("{}/mcp", api.base);
+    let repo = api._dir.path().to_str().unwrap().to_string();
+    let (mut stdin, bridge_in) = tokio::io::duplex(64 * 1024);
+    let (bridge_out, stdout) = tokio::io::duplex(64 * 1024);
+    let bridge = tokio::spawn(async move {
+        clawd::cli::mcp::bridge(&endpoint, "daemon", BufReader::new(bridge_in), bridge_out).await
+    });
+    let mut stdout = BufReader::new(stdout).lines();
+    let mut next = async || -> Value {
+        let line = tokio::time::timeout(std::time::Duration::from_secs(5), stdout.next_line())
+            .await
+            .expect("no output from the bridge")
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(&line).unwrap()
+    };
+
+    for msg in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {},
+            "clientInfo": { "name": "stdio-client", "version": "1" } } }),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    ] {
+        stdin
+            .write_all(format!("{msg}\n").as_bytes())
+            .await
+            .unwrap();
+    }
+    let init = next().await;
+    assert_eq!(init["id"], 1);
+    assert_eq!(init["result"]["serverInfo"]["name"], "clawd");
+
+    let call = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+        "name": "create_task", "arguments": { "title": "Over stdio", "repo": repo },
+        "_meta": { "progressToken": "p1" } } });
+    stdin
+        .write_all(format!("{call}\nnot json\n").as_bytes())
+        .await
+        .unwrap();
+    let mut got = Vec::new();
+    for _ in 0..3 {
+        got.push(next().await);
+    }
+    let progress = got
+        .iter()
+        .find(|m| m["method"] == "notifications/progress")
+        .expect("progress notification");
+    assert_eq!(progress["params"]["progressToken"], "p1");
+    let parse_error = got.iter().find(|m| m["error"]["code"] == -32700);
+    assert!(parse_error.is_some(), "{got:?}");
+    let result = got
+        .iter()
+        .find(|m| m["id"] == 2)
+        .expect("tools/call response");
+    assert_eq!(result["result"]["isError"], false);
+    // Progress for a call comes before its response.
+    let pos = |pred: &dyn Fn(&Value) -> bool| got.iter().position(pred).unwrap();
+    assert!(pos(&|m| m["method"] == "notifications/progress") < pos(&|m| m["id"] == 2));
+
+    drop(stdin);
+    bridge.await.unwrap().unwrap();
+}
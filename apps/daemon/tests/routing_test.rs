@@ -0,0 +1,86 @@
+//! Learned auto-routing against history recorded in SQLite.
+
+use clawd::arena::storage::ArenaStorage;
+use clawd::config::RoutingConfig;
+use clawd::session::router::{route, Provider, RoutingHistory};
+use clawd::storage::Storage;
+
+#[tokio::test]
+async fn history_from_arena_votes_and_repo_profiles_drives_routing() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    let pool = storage.clone_pool();
+    sqlx::query("INSERT INTO repo_profiles (repo_path, primary_lang) VALUES ('/work/rs', 'rust')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Codex wins 5 of 6 debug arenas in the Rust repo.
+    let arena = ArenaStorage::new(pool.clone());
+    for i in 0..6 {
+        let a = storage
+            .create_session("claude", "/work/rs", "a", None)
+            .await
+            .unwrap();
+        let b = storage
+            .create_session("codex", "/work/rs", "b", None)
+            .await
+            .unwrap();
+        let session = arena
+            .create_session(&a.id, &b.id, "claude", "codex", "debug it")
+            .await
+            .unwrap();
+        let winner = if i == 0 { "claude" } else { "codex" };
+        arena
+            .record_vote(&session.id, winner, "debug")
+            .await
+            .unwrap();
+    }
+
+    let history = RoutingHistory::load(&pool).await.unwrap();
+    let records = arena.get_provider_records().await.unwrap();
+    let codex = records.iter().find(|r| r.provider == "codex").unwrap();
+    assert_eq!(
+        (codex.language.as_str(), codex.wins, codex.trials),
+        ("rust", 5, 6)
+    );
+
+    // Keywords alone would pick Claude for "fix"; history overrides them.
+    let decision = route(
+        Some("fix the failing parser"),
+        &["rust".to_string()],
+        &history,
+        &RoutingConfig::default(),
+    );
+    assert_eq!(decision.provider, Provider::Codex);
+    assert_eq!(decision.explain.method, "learned");
+    assert_eq!(decision.explain.task_type, "debug");
+    assert!(decision
+        .explain
+        .reasons
+        .iter()
+        .any(|r| r == "codex: 5 of 6 arena votes won on debug tasks in rust repos (83%)"));
+}
+
+#[tokio::test]
+async fn session_outcomes_count_only_finished_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    let pool = storage.clone_pool();
+    for status in ["idle", "idle", "error", "running", "paused"] {
+        let s = storage
+            .create_session("codex", "/work/rs", "s", None)
+            .await
+            .unwrap();
+        storage.increment_message_count(&s.id).await.unwrap();
+        storage.update_session_status(&s.id, status).await.unwrap();
+    }
+
+    let history = RoutingHistory::load(&pool).await.unwrap();
+    let codex = history
+        .outcomes
+        .iter()
+        .find(|r| r.provider == "codex")
+        .unwrap();
+    assert_eq!((codex.wins, codex.trials), (2, 3));
+}
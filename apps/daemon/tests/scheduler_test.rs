@@ -1,12 +1,14 @@
 //! Integration tests for the account scheduler.
 
 use chrono::Utc;
+use clawd::config::SchedulerConfig;
 use clawd::scheduler::{
     accounts::{AccountEntry, AccountPool},
     backoff::{next_backoff, BackoffConfig},
     queue::{SchedulerQueue, SchedulerRequest},
     rate_limits::SlidingWindow,
 };
+use clawd::storage::Storage;
 
 // ── Sliding window tests ─────────────────────────────────────────────────────
 
@@ -124,21 +126,21 @@ async fn test_queue_priority_ordering() {
     };
 
     // Enqueue in arbitrary order.
-    queue.enqueue(make_req("low", 10, 0)).await;
-    queue.enqueue(make_req("high", 200, 1)).await;
-    queue.enqueue(make_req("medium", 100, 2)).await;
+    queue.enqueue(make_req("low", 10, 0)).await.unwrap();
+    queue.enqueue(make_req("high", 200, 1)).await.unwrap();
+    queue.enqueue(make_req("medium", 100, 2)).await.unwrap();
 
     assert_eq!(queue.len().await, 3);
     assert_eq!(queue.peek_priority().await, Some(200));
 
     // Dequeue should come out in descending priority order.
-    let first = queue.dequeue().await.unwrap();
+    let first = queue.dequeue().await.unwrap().unwrap();
     assert_eq!(first.id, "high", "highest priority should dequeue first");
 
-    let second = queue.dequeue().await.unwrap();
+    let second = queue.dequeue().await.unwrap().unwrap();
     assert_eq!(second.id, "medium", "medium priority should dequeue second");
 
-    let third = queue.dequeue().await.unwrap();
+    let third = queue.dequeue().await.unwrap().unwrap();
     assert_eq!(third.id, "low", "lowest priority should dequeue last");
 
     assert!(queue.is_empty().await);
@@ -160,13 +162,224 @@ async fn test_queue_fifo_within_same_priority() {
     };
 
     // All same priority — should come out FIFO.
-    queue.enqueue(make_req("first", 0)).await;
-    queue.enqueue(make_req("second", 1)).await;
-    queue.enqueue(make_req("third", 2)).await;
+    queue.enqueue(make_req("first", 0)).await.unwrap();
+    queue.enqueue(make_req("second", 1)).await.unwrap();
+    queue.enqueue(make_req("third", 2)).await.unwrap();
 
-    assert_eq!(queue.dequeue().await.unwrap().id, "first");
-    assert_eq!(queue.dequeue().await.unwrap().id, "second");
-    assert_eq!(queue.dequeue().await.unwrap().id, "third");
+    assert_eq!(queue.dequeue().await.unwrap().unwrap().id, "first");
+    assert_eq!(queue.dequeue().await.unwrap().unwrap().id, "second");
+    assert_eq!(queue.dequeue().await.unwrap().unwrap().id, "third");
+}
+
+fn request(id: &str, provider: &str, priority: u8, age_secs: i64) -> SchedulerRequest {
+    SchedulerRequest {
+        id: id.to_string(),
+        task_id: "task-1".to_string(),
+        agent_id: "agent-1".to_string(),
+        role: "implementer".to_string(),
+        provider: provider.to_string(),
+        priority,
+        enqueued_at: Utc::now() - chrono::Duration::seconds(age_secs),
+    }
+}
+
+fn account(id: &str, provider: &str) -> AccountEntry {
+    AccountEntry {
+        account_id: id.to_string(),
+        provider: provider.to_string(),
+        vault_ref: format!("{}_KEY", id.to_uppercase()),
+        is_available: true,
+        blocked_until: None,
+        rpm_used: 0,
+        tpm_used: 0,
+        total_requests: 0,
+        last_used: None,
+    }
+}
+
+#[tokio::test]
+async fn test_queue_aging_prevents_starvation() {
+    let queue = SchedulerQueue::with_config(SchedulerConfig {
+        aging_interval_secs: 60,
+        aging_step: 10,
+        slots_per_account: 1,
+    });
+
+    // Waited 20 minutes: 10 + 20 * 10 = 210 beats a fresh 200.
+    queue
+        .enqueue(request("old-low", "claude", 10, 1200))
+        .await
+        .unwrap();
+    queue
+        .enqueue(request("new-high", "claude", 200, 0))
+        .await
+        .unwrap();
+
+    let snapshot = queue.snapshot().await;
+    assert_eq!(snapshot.queued[0].request.id, "old-low");
+    assert_eq!(snapshot.queued[0].effective_priority, 210);
+    assert_eq!(queue.position("new-high").await, Some(2));
+    assert_eq!(queue.dequeue().await.unwrap().unwrap().id, "old-low");
+}
+
+#[tokio::test]
+async fn test_queue_per_account_slots() {
+    let queue = SchedulerQueue::new(); // one slot per account
+    let accounts = vec![account("claude-1", "claude"), account("codex-1", "codex")];
+
+    queue
+        .enqueue(request("c1", "claude", 100, 2))
+        .await
+        .unwrap();
+    queue
+        .enqueue(request("c2", "claude", 100, 1))
+        .await
+        .unwrap();
+    queue.enqueue(request("x1", "codex", 10, 0)).await.unwrap();
+
+    let first = queue.acquire(&accounts).await.unwrap().unwrap();
+    assert_eq!(
+        (first.request.id.as_str(), first.account_id.as_str()),
+        ("c1", "claude-1")
+    );
+
+    // claude-1 is saturated, so the codex request jumps ahead of c2.
+    let second = queue.acquire(&accounts).await.unwrap().unwrap();
+    assert_eq!(second.request.id, "x1");
+    assert!(queue.acquire(&accounts).await.unwrap().is_none());
+    assert_eq!(
+        queue.snapshot().await.slots_in_use.get("claude-1"),
+        Some(&1)
+    );
+
+    assert!(queue.release("c1").await.unwrap().is_some());
+    let third = queue.acquire(&accounts).await.unwrap().unwrap();
+    assert_eq!(third.request.id, "c2");
+    assert!(queue.is_empty().await);
+}
+
+#[tokio::test]
+async fn test_queue_cancel_and_reprioritize() {
+    let queue = SchedulerQueue::new();
+    queue.enqueue(request("a", "claude", 50, 2)).await.unwrap();
+    queue.enqueue(request("b", "claude", 50, 1)).await.unwrap();
+    queue.enqueue(request("c", "claude", 50, 0)).await.unwrap();
+
+    assert_eq!(queue.position("c").await, Some(3));
+    assert!(queue.reprioritize("c", 90).await.unwrap());
+    assert_eq!(queue.position("c").await, Some(1));
+
+    assert!(queue.cancel("a").await.unwrap());
+    assert!(!queue.cancel("a").await.unwrap());
+    assert_eq!(queue.position("b").await, Some(2));
+
+    let lease = queue
+        .acquire(&[account("claude-1", "claude")])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lease.request.id, "c");
+    assert!(
+        queue.cancel("c").await.is_err(),
+        "running requests cannot be cancelled"
+    );
+}
+
+#[tokio::test]
+async fn test_duplicate_ids_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    let queue = SchedulerQueue::load(storage.clone_pool(), SchedulerConfig::default())
+        .await
+        .unwrap();
+    let accounts = [account("claude-1", "claude")];
+
+    queue.enqueue(request("a", "claude", 10, 0)).await.unwrap();
+    assert!(queue.enqueue(request("a", "claude", 90, 0)).await.is_err());
+    assert_eq!(queue.peek_priority().await, Some(10));
+
+    let lease = queue.acquire(&accounts).await.unwrap().unwrap();
+    assert!(
+        queue.enqueue(request("a", "claude", 10, 0)).await.is_err(),
+        "a running request must not be reset to queued"
+    );
+    assert!(queue.is_empty().await);
+    assert_eq!(
+        queue.snapshot().await.running[0].started_at,
+        lease.started_at
+    );
+    assert!(queue.release("a").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_queue_survives_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    let accounts = [account("claude-1", "claude")];
+
+    {
+        let queue = SchedulerQueue::load(storage.clone_pool(), SchedulerConfig::default())
+            .await
+            .unwrap();
+        queue
+            .enqueue(request("queued", "claude", 10, 0))
+            .await
+            .unwrap();
+        queue
+            .enqueue(request("running", "claude", 90, 0))
+            .await
+            .unwrap();
+        queue
+            .enqueue(request("cancelled", "claude", 50, 0))
+            .await
+            .unwrap();
+        queue
+            .enqueue(request("done", "claude", 200, 0))
+            .await
+            .unwrap();
+        assert!(queue.cancel("cancelled").await.unwrap());
+        let done = queue.acquire(&accounts).await.unwrap().unwrap();
+        assert_eq!(done.request.id, "done");
+        assert!(queue.release("done").await.unwrap().is_some());
+        assert_eq!(
+            queue.acquire(&accounts).await.unwrap().unwrap().request.id,
+            "running"
+        );
+        assert!(queue.reprioritize("queued", 20).await.unwrap());
+    }
+
+    // The interrupted request is re-queued; finished and cancelled ones are gone.
+    let queue = SchedulerQueue::load(storage.clone_pool(), SchedulerConfig::default())
+        .await
+        .unwrap();
+    let snapshot = queue.snapshot().await;
+    let ids: Vec<_> = snapshot
+        .queued
+        .iter()
+        .map(|e| e.request.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["running", "queued"]);
+    assert_eq!(snapshot.queued[1].request.priority, 20);
+    assert!(snapshot.running.is_empty());
+}
+
+#[tokio::test]
+async fn test_queue_reload_keeps_rows_with_bad_timestamps() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    sqlx::query(
+        "INSERT INTO scheduler_queue (id, task_id, agent_id, role, provider, priority, enqueued_at, status)
+         VALUES ('mangled', 't', 'a', 'implementer', 'claude', 5, 'yesterday', 'queued')",
+    )
+    .execute(&storage.clone_pool())
+    .await
+    .unwrap();
+
+    let queue = SchedulerQueue::load(storage.clone_pool(), SchedulerConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(queue.position("mangled").await, Some(1));
+    assert_eq!(queue.dequeue().await.unwrap().unwrap().priority, 5);
 }
 
 // ── Account pool tests ───────────────────────────────────────────────────────
@@ -0,0 +1,580 @@
+/// WASM plugin runtime tests — real wasmtime execution.
+///
+/// The end-to-end test compiles `examples/plugins/tool-guard/tool_guard.wat`
+/// and loads it through `PluginManager`. The rest use small inline modules to
+/// exercise capability checks, resource limits and contributed tools/methods.
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clawd::ipc::event::EventBroadcaster;
+use clawd::plugins::hooks::{HookCall, ToolCallDecision};
+use clawd::plugins::manager::PluginManager;
+use clawd::plugins::wasm_runtime::{WasmLimits, WasmPlugin};
+use clawd::tasks::schema::RiskLevel;
+use clawd_plugin_abi::manifest::ManifestCapability;
+use clawd_plugin_abi::ClawaError;
+use serde_json::{json, Value};
+
+/// Bump allocator + memory shared by the inline test modules.
+const ALLOC: &str = r#"
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (func (export "clawd_alloc") (param $len i32) (result i32)
+    (global.get $heap)
+    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+  (func (export "clawd_plugin_init") (result i32) (i32.const 0))
+"#;
+
+fn write_module(dir: &Path, wat: &str) -> std::path::PathBuf {
+    let path = dir.join("plugin.wasm");
+    std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+    path
+}
+
+fn message(content: &str) -> HookCall {
+    HookCall::Message {
+        session_id: "s1".into(),
+        role: "user".into(),
+        content_json: content.into(),
+    }
+}
+
+#[tokio::test]
+async fn example_tool_guard_denies_modifies_and_sends_events() {
+    let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/plugins/tool-guard");
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("tool-guard");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::copy(
+        example.join("clawd-plugin.json"),
+        plugin_dir.join("clawd-plugin.json"),
+    )
+    .unwrap();
+    let wasm = wat::parse_file(example.join("tool_guard.wat")).unwrap();
+    std::fs::write(plugin_dir.join("tool_guard.wasm"), wasm).unwrap();
+
+    let broadcaster = Arc::new(EventBroadcaster::new());
+    let mut events = broadcaster.subscribe();
+    let manager =
+        PluginManager::new(plugins_dir.path().to_path_buf()).with_events(broadcaster.clone());
+    manager.load_all().await.unwrap();
+    assert_eq!(manager.list().await.len(), 1, "tool-guard should load");
+
+    let denied = manager
+        .on_tool_call("s1", "apply_patch", &json!({"patch": "..."}))
+        .await;
+    assert_eq!(
+        denied,
+        ToolCallDecision::Deny {
+            plugin: "tool-guard".into(),
+            reason: "tool-guard: patches need a human review".into(),
+        }
+    );
+
+    let modified = manager
+        .on_tool_call("s1", "run_tests", &json!({"command": "cargo test -p one"}))
+        .await;
+    assert_eq!(
+        modified,
+        ToolCallDecision::Modify {
+            plugins: vec!["tool-guard".into()],
+            input: json!({"command": "cargo test --workspace"}),
+        }
+    );
+
+    let allowed = manager.on_tool_call("s1", "read_file", &json!({})).await;
+    assert_eq!(allowed, ToolCallDecision::Allow);
+
+    manager.on_session_start("s1").await;
+    let raw = tokio::time::timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("plugin event")
+        .unwrap();
+    let event: Value = serde_json::from_str(&raw).unwrap();
+    assert_eq!(event["method"], "plugin.tool-guard.sessionSeen");
+    assert_eq!(event["params"]["plugin"], "tool-guard");
+}
+
+/// Registers tool `echo` (low risk) and method `ping` during init; `echo`
+/// returns its input, `ping` returns `{"pong":true}`, and `on_message` tries to
+/// register another method after load.
+const CONTRIBUTOR: &str = r#"(module
+  (import "env" "clawd_register_tool"
+    (func $tool (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "env" "clawd_register_method" (func $method (param i32 i32) (result i32)))
+  (import "env" "clawd_set_result" (func $result (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (func (export "clawd_alloc") (param $len i32) (result i32)
+    (global.get $heap)
+    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+  (data (i32.const 0) "echo")
+  (data (i32.const 16) "Echo the input back")
+  (data (i32.const 48) "{\"type\":\"object\"}")
+  (data (i32.const 80) "low")
+  (data (i32.const 96) "ping")
+  (data (i32.const 112) "{\"pong\":true}")
+  (func (export "clawd_plugin_init") (result i32)
+    (i32.or
+      (call $tool (i32.const 0) (i32.const 4) (i32.const 16) (i32.const 19)
+                  (i32.const 48) (i32.const 17) (i32.const 80) (i32.const 3))
+      (call $method (i32.const 96) (i32.const 4))))
+  (func (export "on_tool_invoke") (param i32 i32 i32 i32 i32 i32) (result i32)
+    (call $result (local.get 4) (local.get 5)))
+  (func (export "on_rpc") (param i32 i32 i32 i32) (result i32)
+    (call $result (i32.const 112) (i32.const 13)))
+  (func (export "on_message") (param i32 i32 i32 i32 i32 i32) (result i32)
+    (call $method (i32.const 96) (i32.const 4))))"#;
+
+#[tokio::test]
+async fn plugin_contributes_tools_and_methods() {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("contrib");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::write(
+        plugin_dir.join("clawd-plugin.json"),
+        r#"{"name":"contrib","version":"0.1.0","runtime":"wasm","entry":"plugin.wasm","signature":""}"#,
+    )
+    .unwrap();
+    write_module(&plugin_dir, CONTRIBUTOR);
+
+    let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+    manager.load_all().await.unwrap();
+
+    let tools = manager.tools().await;
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "contrib__echo");
+    assert_eq!(tools[0].risk, RiskLevel::Low);
+    assert_eq!(tools[0].input_schema, json!({"type": "object"}));
+    let info = &manager.list().await[0];
+    assert_eq!(info.tools, vec!["contrib__echo".to_string()]);
+    assert_eq!(info.methods, vec!["plugin.contrib.ping".to_string()]);
+
+    let input = json!({"text": "hi"});
+    let echoed = manager
+        .invoke_tool("s1", "contrib__echo", &input)
+        .await
+        .unwrap();
+    assert_eq!(echoed, input);
+
+    let pong = manager
+        .call_method("plugin.contrib.ping", json!({}))
+        .await
+        .unwrap();
+    assert_eq!(pong, json!({"pong": true}));
+    let missing = manager
+        .call_method("plugin.contrib.nope", json!({}))
+        .await
+        .unwrap_err();
+    assert!(missing.to_string().starts_with("METHOD_NOT_FOUND:"));
+
+    manager.disable("contrib").await.unwrap();
+    assert!(manager.tools().await.is_empty());
+    assert!(!manager.has_method("plugin.contrib.ping").await);
+}
+
+#[tokio::test]
+async fn plugin_names_cannot_contain_separators() {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+    for name in ["a.b", "a__b"] {
+        let plugin_dir = plugins_dir.path().join(name);
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join("clawd-plugin.json"),
+            format!(r#"{{"name":"{name}","version":"0.1.0","runtime":"wasm","entry":"plugin.wasm","signature":""}}"#),
+        )
+        .unwrap();
+        write_module(&plugin_dir, CONTRIBUTOR);
+        let err = manager.load_plugin(&plugin_dir).await.unwrap_err();
+        assert!(err.to_string().contains("plugin name"), "{err:#}");
+    }
+    assert!(manager.tools().await.is_empty());
+}
+
+#[tokio::test]
+async fn events_outside_the_plugin_namespace_are_dropped() {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("spoof");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::write(
+        plugin_dir.join("clawd-plugin.json"),
+        r#"{"name":"spoof","version":"0.1.0","runtime":"wasm","entry":"plugin.wasm","capabilities":["network.relay"],"signature":""}"#,
+    )
+    .unwrap();
+    // on_session_start sends `plugin.tool-guard.sessionSeen`, then `plugin.spoof.ok`.
+    let wat = format!(
+        r#"(module
+          (import "env" "clawd_send_event" (func $send (param i32 i32 i32 i32) (result i32)))
+          {ALLOC}
+          (data (i32.const 0) "plugin.tool-guard.sessionSeen")
+          (data (i32.const 32) "plugin.spoof.ok")
+          (data (i32.const 64) "{{}}")
+          (func (export "on_session_start") (param i32 i32) (result i32)
+            (drop (call $send (i32.const 0) (i32.const 29) (i32.const 64) (i32.const 2)))
+            (drop (call $send (i32.const 32) (i32.const 15) (i32.const 64) (i32.const 2)))
+            (i32.const 0)))"#
+    );
+    write_module(&plugin_dir, &wat);
+
+    let broadcaster = Arc::new(EventBroadcaster::new());
+    let mut events = broadcaster.subscribe();
+    let manager = PluginManager::new(plugins_dir.path().to_path_buf()).with_events(broadcaster);
+    manager.load_all().await.unwrap();
+    manager.on_session_start("s1").await;
+
+    let raw = tokio::time::timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("plugin event")
+        .unwrap();
+    let event: Value = serde_json::from_str(&raw).unwrap();
+    assert_eq!(event["method"], "plugin.spoof.ok");
+}
+
+#[test]
+fn registration_is_rejected_after_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_module(dir.path(), CONTRIBUTOR);
+    let mut plugin = WasmPlugin::load(&path, "contrib", vec![], WasmLimits::default()).unwrap();
+    let reply = plugin.invoke(&HookCall::Load, None).unwrap();
+    assert_eq!(reply.code, ClawaError::None);
+    assert_eq!(reply.tools.len(), 1);
+    assert_eq!(reply.methods, vec!["ping".to_string()]);
+
+    let late = plugin.invoke(&message("{}"), None).unwrap();
+    assert_eq!(late.code, ClawaError::CallbackError);
+    assert!(late.methods.is_empty());
+}
+
+#[test]
+fn read_file_requires_capability_and_stays_in_root() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("data.txt"), "hello").unwrap();
+    // on_message(content = path): returns 0 when the read succeeds, else the
+    // negated error code from clawd_read_file.
+    let wat = format!(
+        r#"(module
+          (import "env" "clawd_read_file" (func $read (param i32 i32 i32 i32) (result i32)))
+          {ALLOC}
+          (func (export "on_message") (param i32 i32 i32 i32 i32 i32) (result i32)
+            (local $n i32)
+            (local.set $n (call $read (local.get 4) (local.get 5) (i32.const 0) (i32.const 64)))
+            (if (result i32) (i32.lt_s (local.get $n) (i32.const 0))
+              (then (i32.sub (i32.const 0) (local.get $n)))
+              (else (i32.const 0)))))"#
+    );
+    let path = write_module(dir.path(), &wat);
+
+    let mut denied = WasmPlugin::load(&path, "reader", vec![], WasmLimits::default()).unwrap();
+    let reply = denied.invoke(&message("data.txt"), None).unwrap();
+    assert_eq!(reply.code, ClawaError::CapabilityDenied);
+
+    let mut granted = WasmPlugin::load(
+        &path,
+        "reader",
+        vec![ManifestCapability::FsRead],
+        WasmLimits::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        granted.invoke(&message("data.txt"), None).unwrap().code,
+        ClawaError::None
+    );
+    assert_eq!(
+        granted
+            .invoke(&message("../outside.txt"), None)
+            .unwrap()
+            .code,
+        ClawaError::NotFound
+    );
+    assert_eq!(
+        granted
+            .invoke(&message("/etc/hostname"), None)
+            .unwrap()
+            .code,
+        ClawaError::NotFound
+    );
+}
+
+#[test]
+fn run_tool_goes_through_runner() {
+    let dir = tempfile::tempdir().unwrap();
+    // on_message: run tool `echo` with `{}` and hand the result back via
+    // clawd_set_tool_input so the test can inspect it.
+    let wat = format!(
+        r#"(module
+          (import "env" "clawd_run_tool" (func $run (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (import "env" "clawd_set_tool_input" (func $set (param i32 i32) (result i32)))
+          {ALLOC}
+          (data (i32.const 0) "echo")
+          (data (i32.const 8) "{{}}")
+          (func (export "on_message") (param i32 i32 i32 i32 i32 i32) (result i32)
+            (local $n i32)
+            (local.set $n (call $run (i32.const 0) (i32.const 4) (i32.const 8) (i32.const 2)
+                                     (i32.const 512) (i32.const 256)))
+            (if (result i32) (i32.lt_s (local.get $n) (i32.const 0))
+              (then (i32.sub (i32.const 0) (local.get $n)))
+              (else (call $set (i32.const 512) (local.get $n))))))"#
+    );
+    let path = write_module(dir.path(), &wat);
+    let mut plugin = WasmPlugin::load(
+        &path,
+        "runner",
+        vec![ManifestCapability::DaemonRpc],
+        WasmLimits::default(),
+    )
+    .unwrap();
+
+    // No runner wired yet.
+    assert_eq!(
+        plugin.invoke(&message(""), None).unwrap().code,
+        ClawaError::NotFound
+    );
+
+    let runner: clawd::plugins::hooks::ToolRunner =
+        Arc::new(|plugin, tool, input| Ok(json!({"plugin": plugin, "tool": tool, "input": input})));
+    let reply = plugin.invoke(&message(""), Some(runner)).unwrap();
+    assert_eq!(reply.code, ClawaError::None);
+    let result: Value = serde_json::from_str(reply.tool_input.as_deref().unwrap()).unwrap();
+    assert_eq!(
+        result,
+        json!({"plugin": "runner", "tool": "echo", "input": {}})
+    );
+}
+
+#[test]
+fn tool_time_is_not_charged_to_the_guest_deadline() {
+    let dir = tempfile::tempdir().unwrap();
+    // on_message: run tool `echo` twice; return the first failure code, or 0.
+    let wat = format!(
+        r#"(module
+          (import "env" "clawd_run_tool" (func $run (param i32 i32 i32 i32 i32 i32) (result i32)))
+          {ALLOC}
+          (data (i32.const 0) "echo")
+          (data (i32.const 8) "{{}}")
+          (func $echo (result i32)
+            (call $run (i32.const 0) (i32.const 4) (i32.const 8) (i32.const 2)
+                       (i32.const 512) (i32.const 256)))
+          (func (export "on_message") (param i32 i32 i32 i32 i32 i32) (result i32)
+            (local $n i32)
+            (local.set $n (call $echo))
+            (if (i32.lt_s (local.get $n) (i32.const 0))
+              (then (return (i32.sub (i32.const 0) (local.get $n)))))
+            (local.set $n (call $echo))
+            (if (result i32) (i32.lt_s (local.get $n) (i32.const 0))
+              (then (i32.sub (i32.const 0) (local.get $n)))
+              (else (i32.const 0)))))"#
+    );
+    let path = write_module(dir.path(), &wat);
+    let limits = WasmLimits {
+        deadline: Duration::from_millis(50),
+        ..WasmLimits::default()
+    };
+    let mut plugin =
+        WasmPlugin::load(&path, "slow", vec![ManifestCapability::DaemonRpc], limits).unwrap();
+    let runner: clawd::plugins::hooks::ToolRunner = Arc::new(|_, _, _| {
+        std::thread::sleep(Duration::from_millis(100));
+        Ok(json!({}))
+    });
+
+    // The first call outlasts the deadline without trapping the guest; it
+    // uses up the tool budget, so the second is refused.
+    let reply = plugin.invoke(&message(""), Some(runner)).unwrap();
+    assert_eq!(reply.code, ClawaError::DaemonError);
+}
+
+#[test]
+fn runaway_plugin_hits_fuel_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let wat = format!(
+        r#"(module {ALLOC}
+          (func (export "on_unload") (result i32) (loop $spin (br $spin)) (i32.const 0)))"#
+    );
+    let path = write_module(dir.path(), &wat);
+    let limits = WasmLimits {
+        fuel: 100_000,
+        ..WasmLimits::default()
+    };
+    let mut plugin = WasmPlugin::load(&path, "spinner", vec![], limits).unwrap();
+    let err = plugin.invoke(&HookCall::Unload, None).unwrap_err();
+    assert!(format!("{err:#}").contains("fuel limit"), "{err:#}");
+
+    // A fresh budget is granted on the next call.
+    assert_eq!(
+        plugin.invoke(&HookCall::Load, None).unwrap().code,
+        ClawaError::None
+    );
+}
+
+#[test]
+fn runaway_plugin_hits_deadline() {
+    let dir = tempfile::tempdir().unwrap();
+    let wat = format!(
+        r#"(module {ALLOC}
+          (func (export "on_unload") (result i32) (loop $spin (br $spin)) (i32.const 0)))"#
+    );
+    let path = write_module(dir.path(), &wat);
+    let limits = WasmLimits {
+        fuel: u64::MAX,
+        deadline: Duration::from_millis(50),
+        ..WasmLimits::default()
+    };
+    let mut plugin = WasmPlugin::load(&path, "spinner", vec![], limits).unwrap();
+    let started = std::time::Instant::now();
+    let err = plugin.invoke(&HookCall::Unload, None).unwrap_err();
+    assert!(format!("{err:#}").contains("time limit"), "{err:#}");
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn timed_out_call_is_interrupted_before_the_next_one() {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("contrib");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::write(
+        plugin_dir.join("clawd-plugin.json"),
+        r#"{"name":"contrib","version":"0.1.0","runtime":"wasm","entry":"plugin.wasm","signature":""}"#,
+    )
+    .unwrap();
+    let spinning = CONTRIBUTOR.replace(
+        "(call $result (i32.const 112) (i32.const 13)))",
+        "(loop $spin (br $spin)) (i32.const 0))",
+    );
+    write_module(&plugin_dir, &spinning);
+
+    let manager = PluginManager::new(plugins_dir.path().to_path_buf())
+        .with_call_timeout(Duration::from_millis(100))
+        .with_wasm_limits(WasmLimits {
+            fuel: u64::MAX,
+            ..WasmLimits::default()
+        });
+    manager.load_all().await.unwrap();
+
+    assert!(manager
+        .call_method("plugin.contrib.ping", json!({}))
+        .await
+        .is_err());
+    // The guest was stopped, so the plugin stays loaded and answers at once.
+    let input = json!({"text": "hi"});
+    let started = std::time::Instant::now();
+    let echoed = manager
+        .invoke_tool("s1", "contrib__echo", &input)
+        .await
+        .unwrap();
+    assert_eq!(echoed, input);
+    assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plugin_running_a_tool_is_not_asked_about_its_own_call() {
+    let plugins_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = plugins_dir.path().join("runner");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    std::fs::write(
+        plugin_dir.join("clawd-plugin.json"),
+        r#"{"name":"runner","version":"0.1.0","runtime":"wasm","entry":"plugin.wasm","capabilities":["daemon.rpc"],"signature":""}"#,
+    )
+    .unwrap();
+    // Contributes `go`, which runs the daemon tool `echo` and returns its
+    // result; `on_tool_call` denies every tool call.
+    let wat = r#"(module
+      (import "env" "clawd_register_tool"
+        (func $tool (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+      (import "env" "clawd_run_tool" (func $run (param i32 i32 i32 i32 i32 i32) (result i32)))
+      (import "env" "clawd_set_result" (func $result (param i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (global $heap (mut i32) (i32.const 1024))
+      (func (export "clawd_alloc") (param $len i32) (result i32)
+        (global.get $heap)
+        (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+      (data (i32.const 0) "echo")
+      (data (i32.const 8) "{}")
+      (data (i32.const 16) "go")
+      (data (i32.const 48) "{\"type\":\"object\"}")
+      (data (i32.const 80) "low")
+      (func (export "clawd_plugin_init") (result i32)
+        (call $tool (i32.const 16) (i32.const 2) (i32.const 16) (i32.const 2)
+                    (i32.const 48) (i32.const 17) (i32.const 80) (i32.const 3)))
+      (func (export "on_tool_invoke") (param i32 i32 i32 i32 i32 i32) (result i32)
+        (local $n i32)
+        (local.set $n (call $run (i32.const 0) (i32.const 4) (i32.const 8) (i32.const 2)
+                                 (i32.const 512) (i32.const 256)))
+        (if (result i32) (i32.lt_s (local.get $n) (i32.const 0))
+          (then (i32.sub (i32.const 0) (local.get $n)))
+          (else (call $result (i32.const 512) (local.get $n)))))
+      (func (export "on_tool_call") (param i32 i32 i32 i32 i32 i32) (result i32)
+        (i32.const 3)))"#;
+    write_module(&plugin_dir, wat);
+
+    let manager = Arc::new(PluginManager::new(plugins_dir.path().to_path_buf()));
+    manager.load_all().await.unwrap();
+
+    // Like the daemon's runner: plugins review the call under the plugin's
+    // session id before the tool runs.
+    let weak = Arc::downgrade(&manager);
+    let handle = tokio::runtime::Handle::current();
+    manager.set_tool_runner(Arc::new(move |plugin: &str, tool: &str, input| {
+        let manager = weak.upgrade().unwrap();
+        let session_id = clawd::plugins::manager::plugin_session_id(plugin);
+        match handle.block_on(manager.on_tool_call(&session_id, tool, &input)) {
+            ToolCallDecision::Deny { reason, .. } => anyhow::bail!("denied: {reason}"),
+            _ => Ok(json!({"ran": tool})),
+        }
+    }));
+
+    // Other sessions are still reviewed by the hook.
+    let decision = manager.on_tool_call("s1", "echo", &json!({})).await;
+    assert!(
+        matches!(decision, ToolCallDecision::Deny { .. }),
+        "{decision:?}"
+    );
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        manager.invoke_tool("s1", "runner__go", &json!({})),
+    )
+    .await
+    .expect("host tool call must not wait on the calling plugin")
+    .unwrap();
+    assert_eq!(result, json!({"ran": "echo"}));
+}
+
+#[test]
+fn memory_growth_is_capped() {
+    let dir = tempfile::tempdir().unwrap();
+    // Returns CallbackError (2) when memory.grow is refused.
+    let wat = format!(
+        r#"(module {ALLOC}
+          (func (export "on_unload") (result i32)
+            (if (result i32) (i32.eq (memory.grow (i32.const 32)) (i32.const -1))
+              (then (i32.const 2))
+              (else (i32.const 0)))))"#
+    );
+    let path = write_module(dir.path(), &wat);
+    let limits = WasmLimits {
+        memory_bytes: 1024 * 1024,
+        ..WasmLimits::default()
+    };
+    let mut plugin = WasmPlugin::load(&path, "grower", vec![], limits).unwrap();
+    assert_eq!(
+        plugin.invoke(&HookCall::Unload, None).unwrap().code,
+        ClawaError::CallbackError
+    );
+
+    let mut roomy = WasmPlugin::load(&path, "grower", vec![], WasmLimits::default()).unwrap();
+    assert_eq!(
+        roomy.invoke(&HookCall::Unload, None).unwrap().code,
+        ClawaError::None
+    );
+}
+
+#[test]
+fn missing_init_export_fails_to_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_module(dir.path(), "(module)");
+    let err = WasmPlugin::load(&path, "empty", vec![], WasmLimits::default())
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("clawd_plugin_init"), "{err:#}");
+}
@@ -0,0 +1,381 @@
+//! Sprint DD — Workflow DAG execution tests (fake step executor, real SQLite).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use clawd::ipc::event::EventBroadcaster;
+use clawd::storage::Storage;
+use clawd::workflows::plan::StepStatus;
+use clawd::workflows::runner::{
+    create_run, execute_run, mark_interrupted, prepare_resume, StepExecutor, StepRequest,
+    StepResult,
+};
+
+/// Records every request and fails the steps listed in `fail`.
+#[derive(Default)]
+struct FakeExecutor {
+    fail: Vec<&'static str>,
+    calls: Mutex<Vec<StepRequest>>,
+}
+
+#[async_trait]
+impl StepExecutor for FakeExecutor {
+    async fn run_step(&self, req: StepRequest) -> StepResult {
+        self.calls.lock().unwrap().push(req.clone());
+        if self.fail.contains(&req.step_id.as_str()) {
+            return StepResult::failed(None, "boom");
+        }
+        StepResult {
+            status: StepStatus::Succeeded,
+            session_id: Some(format!("sess-{}", req.step_id)),
+            output: format!("output of {}", req.step_id),
+            error: None,
+        }
+    }
+}
+
+impl FakeExecutor {
+    fn prompt_for(&self, step: &str) -> Option<String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.step_id == step)
+            .map(|r| r.prompt.clone())
+    }
+
+    fn session_for(&self, step: &str) -> Option<String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.step_id == step)
+            .and_then(|r| r.inherit_session.clone())
+    }
+
+    fn ran(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.step_id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+}
+
+async fn storage_with_recipe(yaml: &str) -> (Storage, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path()).await.unwrap();
+    sqlx::query(
+        "INSERT INTO workflow_recipes (id, name, template_yaml) VALUES ('r1', 'test-recipe', ?)",
+    )
+    .bind(yaml)
+    .execute(storage.pool())
+    .await
+    .unwrap();
+    (storage, dir)
+}
+
+const DAG_YAML: &str = r#"
+name: dag
+inputs:
+  - name: target
+  - name: tone
+    default: terse
+steps:
+  - id: lint
+    prompt: "Lint {target}"
+    depends_on: []
+  - id: test
+    prompt: "Test {target}"
+    depends_on: []
+  - id: report
+    prompt: "({tone}) lint={steps.lint.output}; test={steps.test.output}"
+    inherit_from: lint
+  - id: recover
+    prompt: "Tests failed: {steps.test.status}"
+    when: test.failed
+"#;
+
+#[tokio::test]
+async fn dag_run_fans_out_and_fills_templates() {
+    let (storage, _dir) = storage_with_recipe(DAG_YAML).await;
+    let inputs: HashMap<String, String> = [("target".to_string(), "src/".to_string())].into();
+    let run = create_run(storage.pool(), "r1", &inputs, ".", None)
+        .await
+        .unwrap();
+    let run_id = run.run_id.clone();
+
+    let exec = Arc::new(FakeExecutor::default());
+    let outcome = execute_run(
+        storage.clone_pool(),
+        Arc::new(EventBroadcaster::new()),
+        exec.clone(),
+        run,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(outcome.status, "done");
+    assert_eq!(exec.ran(), vec!["lint", "report", "test"]);
+    assert_eq!(exec.prompt_for("lint").unwrap(), "Lint src/");
+    assert_eq!(
+        exec.prompt_for("report").unwrap(),
+        "(terse) lint=output of lint; test=output of test"
+    );
+    let report = exec
+        .calls
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|r| r.step_id == "report")
+        .cloned()
+        .unwrap();
+    assert_eq!(report.inherit_session.as_deref(), Some("sess-lint"));
+    assert_eq!(outcome.steps["recover"], StepStatus::Skipped);
+
+    let (status, current): (String, i64) =
+        sqlx::query_as("SELECT status, current_step FROM workflow_runs WHERE id = ?")
+            .bind(&run_id)
+            .fetch_one(storage.pool())
+            .await
+            .unwrap();
+    assert_eq!(status, "done");
+    assert_eq!(current, 4);
+}
+
+#[tokio::test]
+async fn failed_step_takes_when_branch_and_skips_dependents() {
+    let (storage, _dir) = storage_with_recipe(DAG_YAML).await;
+    let inputs: HashMap<String, String> = [("target".to_string(), "src/".to_string())].into();
+    let run = create_run(storage.pool(), "r1", &inputs, ".", None)
+        .await
+        .unwrap();
+
+    let exec = Arc::new(FakeExecutor {
+        fail: vec!["test"],
+        ..Default::default()
+    });
+    let outcome = execute_run(
+        storage.clone_pool(),
+        Arc::new(EventBroadcaster::new()),
+        exec.clone(),
+        run,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(outcome.status, "failed");
+    assert_eq!(outcome.steps["report"], StepStatus::Skipped);
+    assert_eq!(outcome.steps["recover"], StepStatus::Succeeded);
+    assert_eq!(exec.prompt_for("recover").unwrap(), "Tests failed: failed");
+}
+
+#[tokio::test]
+async fn missing_required_input_is_rejected() {
+    let (storage, _dir) = storage_with_recipe(DAG_YAML).await;
+    let err = create_run(storage.pool(), "r1", &HashMap::new(), ".", None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("target"), "{err}");
+}
+
+#[tokio::test]
+async fn interrupted_run_resumes_without_rerunning_finished_steps() {
+    let (storage, _dir) = storage_with_recipe(DAG_YAML).await;
+    let inputs: HashMap<String, String> = [("target".to_string(), "src/".to_string())].into();
+    let run = create_run(storage.pool(), "r1", &inputs, ".", None)
+        .await
+        .unwrap();
+    let run_id = run.run_id.clone();
+
+    // Simulate a crash after `lint` finished and while `test` was running.
+    sqlx::query(
+        "UPDATE workflow_run_steps SET status = 'succeeded', session_id = 'sess-old', output = 'old lint'
+         WHERE run_id = ? AND step_id = 'lint'",
+    )
+    .bind(&run_id)
+    .execute(storage.pool())
+    .await
+    .unwrap();
+    sqlx::query(
+        "UPDATE workflow_run_steps SET status = 'running', session_id = 'sess-live'
+         WHERE run_id = ? AND step_id = 'test'",
+    )
+    .bind(&run_id)
+    .execute(storage.pool())
+    .await
+    .unwrap();
+    assert_eq!(mark_interrupted(storage.pool()).await.unwrap(), 1);
+
+    let resumed = prepare_resume(storage.pool(), &run_id).await.unwrap();
+    let exec = Arc::new(FakeExecutor::default());
+    let outcome = execute_run(
+        storage.clone_pool(),
+        Arc::new(EventBroadcaster::new()),
+        exec.clone(),
+        resumed,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(outcome.status, "done");
+    assert_eq!(exec.ran(), vec!["report", "test"]);
+    assert_eq!(
+        exec.session_for("test").as_deref(),
+        Some("sess-live"),
+        "the interrupted step continues its session"
+    );
+    assert_eq!(
+        exec.prompt_for("report").unwrap(),
+        "(terse) lint=old lint; test=output of test"
+    );
+
+    // A completed run cannot be resumed again.
+    assert!(prepare_resume(storage.pool(), &run_id).await.is_err());
+}
+
+const TRIGGERED_YAML: &str = r#"
+name: on-commit
+triggers: [on_commit]
+max_concurrent_runs: 1
+steps:
+  - id: check
+    prompt: "Check commit {trigger_subject} ({trigger})"
+"#;
+
+#[tokio::test]
+async fn triggered_run_records_event_and_respects_concurrency_limit() {
+    use clawd::workflows::runner::run_status;
+    use clawd::workflows::triggers::{launch_triggered, EventClassifier, Launch};
+
+    let (storage, _dir) = storage_with_recipe(TRIGGERED_YAML).await;
+    let event = EventClassifier::default()
+        .classify(
+            "repo.committed",
+            &serde_json::json!({ "repoPath": ".", "sha": "abc123", "previousSha": "def456" }),
+        )
+        .unwrap();
+
+    let Launch::Started(run) = launch_triggered(storage.pool(), "r1", &event)
+        .await
+        .unwrap()
+    else {
+        panic!("first trigger should start a run");
+    };
+    // The first run is still in flight, so a second trigger is dropped.
+    assert!(matches!(
+        launch_triggered(storage.pool(), "r1", &event)
+            .await
+            .unwrap(),
+        Launch::AtLimit { limit: 1 }
+    ));
+
+    let status = run_status(storage.pool(), &run.run_id).await.unwrap();
+    assert_eq!(status["trigger"]["trigger"], "on_commit");
+    assert_eq!(status["trigger"]["subject"], "abc123");
+    assert_eq!(status["trigger"]["detail"]["previousSha"], "def456");
+
+    let exec = Arc::new(FakeExecutor::default());
+    let outcome = execute_run(
+        storage.clone_pool(),
+        Arc::new(EventBroadcaster::new()),
+        exec.clone(),
+        *run,
+    )
+    .await
+    .unwrap();
+    assert_eq!(outcome.status, "done");
+    assert_eq!(
+        exec.prompt_for("check").unwrap(),
+        "Check commit abc123 (on_commit)"
+    );
+
+    // Once the run has finished the recipe can be triggered again.
+    assert!(matches!(
+        launch_triggered(storage.pool(), "r1", &event)
+            .await
+            .unwrap(),
+        Launch::Started(_)
+    ));
+}
+
+const FILE_CHANGE_YAML: &str = r#"
+name: format-on-save
+triggers: [on_file_change, on_commit]
+steps:
+  - id: fmt
+    prompt: "Format {trigger_subject}"
+"#;
+
+#[tokio::test]
+async fn a_recipe_ignores_changes_made_by_its_own_run() {
+    use clawd::workflows::triggers::{
+        caused_by_own_run, launch_triggered, EventClassifier, Launch,
+    };
+
+    let (storage, _dir) = storage_with_recipe(FILE_CHANGE_YAML).await;
+    let mut classifier = EventClassifier::default();
+    let mut changed = |repo: &str| {
+        classifier
+            .classify(
+                "repo.filesChanged",
+                &serde_json::json!({ "repoPath": repo, "paths": ["src/lib.rs"] }),
+            )
+            .unwrap()
+    };
+    let edit = changed("/r");
+    assert!(!caused_by_own_run(storage.pool(), "r1", &edit)
+        .await
+        .unwrap());
+
+    let Launch::Started(run) = launch_triggered(storage.pool(), "r1", &edit).await.unwrap() else {
+        panic!("the edit should start a run");
+    };
+    let run_id = run.run_id.clone();
+
+    // The run's own edits and commits in /r do not start it again; other
+    // repositories still do.
+    assert!(caused_by_own_run(storage.pool(), "r1", &changed("/r"))
+        .await
+        .unwrap());
+    let commit = EventClassifier::default()
+        .classify(
+            "repo.committed",
+            &serde_json::json!({ "repoPath": "/r", "sha": "abc" }),
+        )
+        .unwrap();
+    assert!(caused_by_own_run(storage.pool(), "r1", &commit)
+        .await
+        .unwrap());
+    assert!(!caused_by_own_run(storage.pool(), "r1", &changed("/other"))
+        .await
+        .unwrap());
+
+    // Late watcher events right after the run finishes are still its own.
+    execute_run(
+        storage.clone_pool(),
+        Arc::new(EventBroadcaster::new()),
+        Arc::new(FakeExecutor::default()),
+        *run,
+    )
+    .await
+    .unwrap();
+    assert!(caused_by_own_run(storage.pool(), "r1", &changed("/r"))
+        .await
+        .unwrap());
+
+    // Once the grace period has passed, edits trigger the recipe again.
+    sqlx::query("UPDATE workflow_runs SET finished_at = datetime('now', '-1 minute') WHERE id = ?")
+        .bind(&run_id)
+        .execute(storage.pool())
+        .await
+        .unwrap();
+    assert!(!caused_by_own_run(storage.pool(), "r1", &changed("/r"))
+        .await
+        .unwrap());
+}
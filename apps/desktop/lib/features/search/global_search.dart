@@ -63,7 +63,8 @@ class _GlobalSearchDialogState extends ConsumerState<GlobalSearchDialog> {
       final client = ref.read(daemonProvider.notifier).client;
       final result = await client.call<Map<String, dynamic>>(
         'session.search',
-        SearchQuery(query: query, limit: 30).toJson(),
+        // Only message hits open a session.
+        SearchQuery(query: query, limit: 30, kinds: const ['message']).toJson(),
       );
       if (!mounted) return;
       final resp = SearchResponse.fromJson(result);
@@ -203,8 +204,21 @@ class _ResultTile extends StatelessWidget {
 
   @override
   Widget build(BuildContext context) {
-    // Strip HTML bold tags from snippet for plain rendering.
-    final snippet = result.snippet.replaceAll('<b>', '').replaceAll('</b>', '').replaceAll('…', '…');
+    // Embolden the matched terms.
+    final snippet = result.snippet;
+    final spans = <TextSpan>[];
+    var pos = 0;
+    for (final (start, end) in result.highlights) {
+      if (start < pos || end > snippet.length) continue;
+      spans
+        ..add(TextSpan(text: snippet.substring(pos, start)))
+        ..add(TextSpan(
+          text: snippet.substring(start, end),
+          style: const TextStyle(fontWeight: FontWeight.w600),
+        ));
+      pos = end;
+    }
+    spans.add(TextSpan(text: snippet.substring(pos)));
 
     return InkWell(
       onTap: onTap,
@@ -223,8 +237,8 @@ class _ResultTile extends StatelessWidget {
               child: Column(
                 crossAxisAlignment: CrossAxisAlignment.start,
                 children: [
-                  Text(
-                    snippet,
+                  Text.rich(
+                    TextSpan(children: spans),
                     style: const TextStyle(fontSize: 13, color: Colors.white),
                     maxLines: 2,
                     overflow: TextOverflow.ellipsis,
@@ -100,8 +100,76 @@ pub struct ClawaContext {
         msg: *const core::ffi::c_char,
     ),
 
+    /// Replace the input of the tool call under review.
+    ///
+    /// Only meaningful inside `on_tool_call`: if the callback then returns
+    /// `ClawaError::None`, the daemon runs the tool with this input instead.
+    /// `input_json` — null-terminated UTF-8 JSON object string.
+    ///
+    /// Occupies a former reserved slot; null on daemons that predate it.
+    pub set_tool_input: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            input_json: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
+    /// Attach a human-readable reason to the current callback's result,
+    /// e.g. why a tool call was denied. `reason` — null-terminated UTF-8.
+    ///
+    /// Occupies a former reserved slot; null on daemons that predate it.
+    pub set_reason: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            reason: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
+    /// Register an MCP tool this plugin implements. Only honoured during
+    /// `on_load`; calls are delivered to `ClawaPlugin::on_tool_invoke`.
+    ///
+    /// `name` — `[A-Za-z0-9_-]+`; agents see it as `<plugin>__<name>`.
+    /// `description` — shown in `tools/list`.
+    /// `input_schema_json` — JSON Schema object for the arguments.
+    /// `risk` — `"low"`, `"medium"`, `"high"` or `"critical"`; null means
+    /// medium. `.claw/policies/tool-risk.json` overrides it.
+    ///
+    /// Occupies a former reserved slot; null on daemons that predate it.
+    pub register_tool: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            name: *const core::ffi::c_char,
+            description: *const core::ffi::c_char,
+            input_schema_json: *const core::ffi::c_char,
+            risk: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
+    /// Register a JSON-RPC method. Only honoured during `on_load`; clients
+    /// call it as `plugin.<plugin>.<method>` and requests are delivered to
+    /// `ClawaPlugin::on_rpc`. `method` — `[A-Za-z0-9_]+`.
+    ///
+    /// Occupies a former reserved slot; null on daemons that predate it.
+    pub register_method: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            method: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
+    /// Set the JSON result of the current `on_tool_invoke` / `on_rpc` call.
+    /// `result_json` — null-terminated UTF-8 JSON value.
+    ///
+    /// Occupies a former reserved slot; null on daemons that predate it.
+    pub set_result: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            result_json: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
     /// Reserved for future host functions. Must be set to null.
-    pub _reserved: [*mut core::ffi::c_void; 8],
+    pub _reserved: [*mut core::ffi::c_void; 3],
 }
 
 // ─── Plugin vtable ───────────────────────────────────────────────────────────
@@ -148,9 +216,13 @@ pub struct ClawaPlugin {
         ) -> ClawaError,
     >,
 
-    /// Called when the AI invokes a tool.
+    /// Called before the daemon runs a tool on the AI's behalf.
     /// `tool_name` — null-terminated tool name.
     /// `input_json` — null-terminated JSON input object.
+    ///
+    /// Return `ClawaError::None` to allow the call (with the input set via
+    /// `ClawaContext::set_tool_input`, if any) or `ClawaError::CapabilityDenied`
+    /// to deny it. Any other error is logged and treated as allow.
     pub on_tool_call: Option<
         unsafe extern "C" fn(
             ctx: *mut ClawaContext,
@@ -172,8 +244,37 @@ pub struct ClawaPlugin {
         ) -> ClawaError,
     >,
 
+    /// Called when an agent invokes a tool registered via
+    /// `ClawaContext::register_tool`. `tool_name` is the unprefixed name.
+    /// Set the result with `ClawaContext::set_result` and return
+    /// `ClawaError::None`; any other code fails the call (with the reason
+    /// from `set_reason`, if any).
+    ///
+    /// Occupies a former reserved slot; null on plugins that predate it.
+    pub on_tool_invoke: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            session_id: *const core::ffi::c_char,
+            tool_name: *const core::ffi::c_char,
+            input_json: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
+    /// Called for a JSON-RPC method registered via
+    /// `ClawaContext::register_method`. `method` is the unprefixed name.
+    /// Result and errors work as in `on_tool_invoke`.
+    ///
+    /// Occupies a former reserved slot; null on plugins that predate it.
+    pub on_rpc: Option<
+        unsafe extern "C" fn(
+            ctx: *mut ClawaContext,
+            method: *const core::ffi::c_char,
+            params_json: *const core::ffi::c_char,
+        ) -> ClawaError,
+    >,
+
     /// Reserved for future callbacks. Must be set to null.
-    pub _reserved: [Option<unsafe extern "C" fn()>; 8],
+    pub _reserved: [Option<unsafe extern "C" fn()>; 6],
 }
 
 // Safety: ClawaPlugin contains raw pointers but we document that the daemon
@@ -242,6 +343,24 @@ mod tests {
         assert_eq!(ClawaError::CapabilityDenied as u32, 3);
     }
 
+    #[test]
+    fn context_layout_unchanged_by_new_host_functions() {
+        // _inner + send_event + log + 8 slots since v1.0.0.
+        assert_eq!(
+            core::mem::size_of::<ClawaContext>(),
+            11 * core::mem::size_of::<*mut core::ffi::c_void>()
+        );
+    }
+
+    #[test]
+    fn vtable_layout_unchanged_by_new_callbacks() {
+        // abi_version (padded) + name + version + 6 callbacks + 8 slots since v1.0.0.
+        assert_eq!(
+            core::mem::size_of::<ClawaPlugin>(),
+            17 * core::mem::size_of::<*mut core::ffi::c_void>()
+        );
+    }
+
     #[test]
     fn event_type_variants() {
         assert_eq!(ClawaEventType::SessionStart as u32, 0);
@@ -4,6 +4,8 @@
 //! Every plugin pack must include a `clawd-plugin.json` manifest at the
 //! pack root. The daemon reads this before loading the plugin binary.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Runtime type string as it appears in `clawd-plugin.json`.
@@ -75,6 +77,12 @@ pub struct PluginManifest {
     #[serde(default)]
     pub capabilities: Vec<ManifestCapability>,
 
+    /// Risk level (`low`, `medium`, `high` or `critical`) of each RPC method
+    /// the plugin registers, keyed by the method's local name. Unlisted
+    /// methods are medium.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub method_risk: BTreeMap<String, String>,
+
     /// Base64-encoded Ed25519 signature over the plugin binary.
     /// Required for plugins distributed through the official registry.
     /// Self-signed plugins may omit this field (user is prompted).
@@ -146,11 +154,13 @@ mod tests {
             runtime: ManifestRuntime::Dylib,
             entry: "libtest.dylib".into(),
             capabilities: vec![ManifestCapability::FsRead],
+            method_risk: BTreeMap::from([("status".into(), "low".into())]),
             signature: String::new(),
         };
         let json = m.to_json().unwrap();
         let m2 = PluginManifest::from_json(&json).unwrap();
         assert_eq!(m.name, m2.name);
         assert_eq!(m.capabilities.len(), m2.capabilities.len());
+        assert_eq!(m.method_risk, m2.method_risk);
     }
 }
@@ -10,13 +10,13 @@ class SearchFilters {
     this.role,
   });
 
-  /// Restrict to a specific session.
+  /// Restrict to a specific session (messages and tool calls only).
   final String? sessionId;
 
-  /// ISO-8601 lower bound for message creation time.
+  /// RFC 3339 lower bound for creation time.
   final String? dateFrom;
 
-  /// ISO-8601 upper bound for message creation time.
+  /// RFC 3339 upper bound for creation time.
   final String? dateTo;
 
   /// Restrict to "user" or "assistant" messages.
@@ -36,36 +36,64 @@ class SearchQuery {
     required this.query,
     this.limit = 20,
     this.filterBy = const SearchFilters(),
+    this.kinds,
+    this.cursor,
   });
 
   final String query;
   final int limit;
   final SearchFilters filterBy;
 
+  /// Entity kinds to search: "message", "task", "memory", "toolCall".
+  /// Null searches all of them.
+  final List<String>? kinds;
+
+  /// `nextCursor` of the previous page.
+  final String? cursor;
+
   Map<String, dynamic> toJson() => {
         'query': query,
         'limit': limit,
         'filterBy': filterBy.toJson(),
+        if (kinds != null) 'kinds': kinds,
+        if (cursor != null) 'cursor': cursor,
       };
 }
 
 /// A single search result entry returned by `session.search`.
 class SearchResult {
   const SearchResult({
+    required this.kind,
+    required this.id,
     required this.sessionId,
     required this.messageId,
+    required this.title,
     required this.snippet,
+    required this.highlights,
     required this.role,
     required this.createdAt,
     required this.rank,
   });
 
+  /// "message", "task", "memory" or "toolCall".
+  final String kind;
+
+  /// ID of the message, task, memory entry or tool-call event.
+  final String id;
+
+  /// Session of a message or tool call; empty otherwise.
   final String sessionId;
   final String messageId;
 
-  /// Short snippet of matching text (may contain `<b>…</b>` HTML highlights).
+  /// Session title, task title, memory key or tool name.
+  final String title;
+
+  /// Plain-text excerpt around the match.
   final String snippet;
 
+  /// `(start, end)` ranges of matched terms in [snippet], as string indices.
+  final List<(int, int)> highlights;
+
   final String role;
   final String createdAt;
 
@@ -73,18 +101,32 @@ class SearchResult {
   final double rank;
 
   factory SearchResult.fromJson(Map<String, dynamic> json) => SearchResult(
+        kind: json['kind'] as String? ?? 'message',
+        id: json['id'] as String? ?? json['messageId'] as String? ?? '',
         sessionId: json['sessionId'] as String? ?? '',
         messageId: json['messageId'] as String? ?? '',
+        title: json['title'] as String? ?? '',
         snippet: json['snippet'] as String? ?? '',
+        highlights: (json['highlights'] as List<dynamic>?)
+                ?.map((e) => e as List<dynamic>)
+                .map((r) => (r[0] as int, r[1] as int))
+                .toList() ??
+            const [],
         role: json['role'] as String? ?? '',
         createdAt: json['createdAt'] as String? ?? '',
         rank: (json['rank'] as num?)?.toDouble() ?? 0.0,
       );
 
   Map<String, dynamic> toJson() => {
+        'kind': kind,
+        'id': id,
         'sessionId': sessionId,
         'messageId': messageId,
+        'title': title,
         'snippet': snippet,
+        'highlights': [
+          for (final (start, end) in highlights) [start, end]
+        ],
         'role': role,
         'createdAt': createdAt,
         'rank': rank,
@@ -93,16 +135,24 @@ class SearchResult {
 
 /// Response from `session.search`.
 class SearchResponse {
-  const SearchResponse({required this.results, required this.totalHits});
+  const SearchResponse({
+    required this.results,
+    required this.totalHits,
+    this.nextCursor,
+  });
 
   final List<SearchResult> results;
   final int totalHits;
 
+  /// Pass as [SearchQuery.cursor] for the next page; null on the last one.
+  final String? nextCursor;
+
   factory SearchResponse.fromJson(Map<String, dynamic> json) => SearchResponse(
         results: (json['results'] as List<dynamic>?)
                 ?.map((e) => SearchResult.fromJson(e as Map<String, dynamic>))
                 .toList() ??
             [],
         totalHits: json['totalHits'] as int? ?? 0,
+        nextCursor: json['nextCursor'] as String?,
       );
 }